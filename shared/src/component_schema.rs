@@ -0,0 +1,166 @@
+//! Vector component catalog
+//!
+//! Describes the source, transform and sink types the pipeline builder
+//! offers, with each type's fields. The catalog is loaded from the bundled
+//! `data/component_schema.json`, a curated subset of Vector's component
+//! reference, so the UI and the control plane share one definition.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+pub use crate::messages::ComponentKind;
+
+/// Bundled catalog data
+const CATALOG_JSON: &str = include_str!("../data/component_schema.json");
+
+/// Value type of a component field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Array,
+    Object,
+}
+
+/// A single configuration field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    /// Field name; nested options use dotted paths (e.g. `encoding.codec`)
+    pub name: String,
+    
+    /// Value type
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    
+    /// Whether Vector requires the field
+    #[serde(default)]
+    pub required: bool,
+    
+    /// Human-readable description
+    pub description: String,
+    
+    /// Alternative names that satisfy the field (e.g. deprecated spellings)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+impl FieldSchema {
+    /// The field name followed by its aliases
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+/// Schema for one component type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentSchema {
+    /// Human-readable description
+    pub description: String,
+    
+    /// Configuration fields
+    pub fields: Vec<FieldSchema>,
+}
+
+impl ComponentSchema {
+    /// Look up a field by name or alias
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields.iter().find(|f| f.names().any(|n| n == name))
+    }
+    
+    /// Fields Vector requires
+    pub fn required_fields(&self) -> impl Iterator<Item = &FieldSchema> {
+        self.fields.iter().filter(|f| f.required)
+    }
+}
+
+/// All known component types, keyed by type name within each kind
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComponentCatalog {
+    #[serde(default)]
+    pub sources: BTreeMap<String, ComponentSchema>,
+    
+    #[serde(default)]
+    pub transforms: BTreeMap<String, ComponentSchema>,
+    
+    #[serde(default)]
+    pub sinks: BTreeMap<String, ComponentSchema>,
+}
+
+impl ComponentCatalog {
+    /// Parse a catalog from JSON
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+    
+    /// Component types of one kind
+    pub fn components(&self, kind: ComponentKind) -> &BTreeMap<String, ComponentSchema> {
+        match kind {
+            ComponentKind::Source => &self.sources,
+            ComponentKind::Transform => &self.transforms,
+            ComponentKind::Sink => &self.sinks,
+        }
+    }
+    
+    /// Look up a component type's schema
+    pub fn get(&self, kind: ComponentKind, component_type: &str) -> Option<&ComponentSchema> {
+        self.components(kind).get(component_type)
+    }
+}
+
+/// The bundled catalog
+pub fn catalog() -> &'static ComponentCatalog {
+    static CATALOG: OnceLock<ComponentCatalog> = OnceLock::new();
+    CATALOG.get_or_init(|| {
+        ComponentCatalog::from_json(CATALOG_JSON).expect("bundled component_schema.json is valid")
+    })
+}
+
+/// Look up a component type's schema in the bundled catalog
+pub fn component_schema(kind: ComponentKind, component_type: &str) -> Option<&'static ComponentSchema> {
+    catalog().get(kind, component_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_bundled_catalog_parses() {
+        let catalog = catalog();
+        assert!(!catalog.sources.is_empty());
+        assert!(!catalog.transforms.is_empty());
+        assert!(!catalog.sinks.is_empty());
+    
+        // Every transform and sink takes inputs
+        for kind in [ComponentKind::Transform, ComponentKind::Sink] {
+            for (name, schema) in catalog.components(kind) {
+                assert!(schema.field("inputs").is_some_and(|f| f.required), "{} has no inputs field", name);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_component_lookup() {
+        let http = component_schema(ComponentKind::Sink, "http").unwrap();
+        let uri = http.field("uri").unwrap();
+        assert!(uri.required);
+        assert_eq!(uri.field_type, FieldType::String);
+    
+        let es = component_schema(ComponentKind::Sink, "elasticsearch").unwrap();
+        assert_eq!(es.field("endpoint").unwrap().name, "endpoints");
+    
+        assert!(component_schema(ComponentKind::Source, "http").is_none());
+        assert!(component_schema(ComponentKind::Sink, "not_a_sink").is_none());
+    }
+    
+    #[test]
+    fn test_component_kind_from_str() {
+        assert_eq!("sink".parse::<ComponentKind>().unwrap(), ComponentKind::Sink);
+        assert_eq!("transforms".parse::<ComponentKind>().unwrap(), ComponentKind::Transform);
+        assert!("pipe".parse::<ComponentKind>().is_err());
+    }
+}
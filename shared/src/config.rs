@@ -4,9 +4,11 @@
 //! serialized to TOML/JSON for deployment to Vector instances.
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use uuid::Uuid;
 
+use crate::messages::ComponentKind;
+
 /// A complete Vector pipeline configuration
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PipelineConfig {
@@ -397,6 +399,285 @@ impl Pipeline {
     }
 }
 
+// =============================================================================
+// Pipeline Graph
+// =============================================================================
+
+/// Errors converting between a [`PipelineGraph`] and TOML
+#[derive(Debug, thiserror::Error)]
+pub enum GraphError {
+    #[error("invalid TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+    
+    #[error("failed to serialize TOML: {0}")]
+    Serialize(String),
+    
+    #[error("component '{0}' is not a table")]
+    NotATable(String),
+    
+    #[error("component '{0}' has no type")]
+    MissingType(String),
+    
+    #[error("component '{id}' has invalid inputs: {reason}")]
+    InvalidInputs { id: String, reason: String },
+    
+    #[error("component '{node}' references unknown input '{input}'")]
+    UnknownInput { node: String, input: String },
+    
+    #[error("component id '{0}' is used more than once")]
+    DuplicateId(String),
+}
+
+/// A component in a [`PipelineGraph`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// Component ID (the key under `[sources]`, `[transforms]` or `[sinks]`)
+    pub id: String,
+    
+    /// Source, transform or sink
+    pub kind: ComponentKind,
+    
+    /// Component type (e.g., "file", "remap", "console")
+    #[serde(rename = "type")]
+    pub component_type: String,
+    
+    /// Component options, excluding `type` and `inputs`
+    #[serde(default)]
+    pub config: BTreeMap<String, serde_json::Value>,
+}
+
+impl GraphNode {
+    pub fn new(id: impl Into<String>, kind: ComponentKind, component_type: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            kind,
+            component_type: component_type.into(),
+            config: BTreeMap::new(),
+        }
+    }
+    
+    pub fn with_config(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.config.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// A data flow between components, derived from the target's `inputs`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct GraphEdge {
+    /// Target component ID
+    pub to: String,
+    
+    /// Upstream component ID
+    pub from: String,
+    
+    /// Named output of the upstream component (e.g. a `route` branch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_port: Option<String>,
+}
+
+impl GraphEdge {
+    /// The input reference as written in the target's `inputs`
+    pub fn input(&self) -> String {
+        match &self.from_port {
+            Some(port) => format!("{}.{}", self.from, port),
+            None => self.from.clone(),
+        }
+    }
+}
+
+/// A Vector pipeline as nodes and edges
+///
+/// The shared representation between the UI pipeline builder and the
+/// server. Round-trips through Vector's TOML format: edges are written as
+/// each component's `inputs` (sorted, since Vector ignores their order) and
+/// other top-level tables such as `[api]` are kept in `global`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineGraph {
+    /// Components keyed by ID
+    #[serde(default)]
+    pub nodes: BTreeMap<String, GraphNode>,
+    
+    /// Connections between components
+    #[serde(default)]
+    pub edges: BTreeSet<GraphEdge>,
+    
+    /// Top-level settings outside the component tables
+    #[serde(default)]
+    pub global: BTreeMap<String, serde_json::Value>,
+}
+
+impl PipelineGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add or replace a node
+    pub fn add_node(&mut self, node: GraphNode) {
+        self.nodes.insert(node.id.clone(), node);
+    }
+    
+    /// Connect `from` to `to`
+    pub fn connect(&mut self, from: impl Into<String>, to: impl Into<String>) {
+        self.edges.insert(GraphEdge { to: to.into(), from: from.into(), from_port: None });
+    }
+    
+    /// Connect a named output of `from` (e.g. a route branch) to `to`
+    pub fn connect_port(&mut self, from: impl Into<String>, port: impl Into<String>, to: impl Into<String>) {
+        self.edges.insert(GraphEdge { to: to.into(), from: from.into(), from_port: Some(port.into()) });
+    }
+    
+    /// Input references of a node, in `inputs` order
+    pub fn inputs_of(&self, node_id: &str) -> Vec<String> {
+        self.edges.iter().filter(|e| e.to == node_id).map(GraphEdge::input).collect()
+    }
+    
+    /// Render as a Vector TOML config
+    pub fn to_toml(&self) -> Result<String, GraphError> {
+        let mut root = toml::Table::new();
+        
+        for (key, value) in &self.global {
+            root.insert(key.clone(), json_to_toml(value).map_err(GraphError::Serialize)?);
+        }
+        
+        for node in self.nodes.values() {
+            let mut component = toml::Table::new();
+            component.insert("type".to_string(), toml::Value::String(node.component_type.clone()));
+            
+            if node.kind != ComponentKind::Source {
+                let inputs = self.inputs_of(&node.id).into_iter().map(toml::Value::String).collect();
+                component.insert("inputs".to_string(), toml::Value::Array(inputs));
+            }
+            
+            for (key, value) in &node.config {
+                component.insert(key.clone(), json_to_toml(value).map_err(GraphError::Serialize)?);
+            }
+            
+            let section = root
+                .entry(node.kind.table_name())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if let toml::Value::Table(section) = section {
+                section.insert(node.id.clone(), toml::Value::Table(component));
+            }
+        }
+        
+        toml::to_string_pretty(&root).map_err(|e| GraphError::Serialize(e.to_string()))
+    }
+    
+    /// Parse a Vector TOML config
+    ///
+    /// Every input must name a component, or `component.output` for a named
+    /// output; wildcard inputs are not supported.
+    pub fn from_toml(s: &str) -> Result<Self, GraphError> {
+        let root: toml::Table = toml::from_str(s)?;
+        let mut graph = Self::new();
+        let mut pending_inputs: Vec<(String, Vec<String>)> = Vec::new();
+        
+        for (key, value) in root {
+            let kind = match key.parse::<ComponentKind>() {
+                Ok(kind) if key == kind.table_name() => kind,
+                _ => {
+                    graph.global.insert(key, toml_to_json(value));
+                    continue;
+                }
+            };
+            
+            let toml::Value::Table(components) = value else {
+                return Err(GraphError::NotATable(key));
+            };
+            
+            for (id, component) in components {
+                let toml::Value::Table(mut component) = component else {
+                    return Err(GraphError::NotATable(id));
+                };
+                
+                let component_type = match component.remove("type") {
+                    Some(toml::Value::String(t)) => t,
+                    _ => return Err(GraphError::MissingType(id)),
+                };
+                
+                if kind != ComponentKind::Source {
+                    let inputs = match component.remove("inputs") {
+                        Some(toml::Value::Array(inputs)) => inputs
+                            .into_iter()
+                            .map(|input| match input {
+                                toml::Value::String(input) => Ok(input),
+                                other => Err(GraphError::InvalidInputs {
+                                    id: id.clone(),
+                                    reason: format!("expected a string, got {}", other.type_str()),
+                                }),
+                            })
+                            .collect::<Result<Vec<_>, _>>()?,
+                        Some(other) => {
+                            return Err(GraphError::InvalidInputs {
+                                id,
+                                reason: format!("expected an array, got {}", other.type_str()),
+                            });
+                        }
+                        None => Vec::new(),
+                    };
+                    pending_inputs.push((id.clone(), inputs));
+                }
+                
+                if graph.nodes.contains_key(&id) {
+                    return Err(GraphError::DuplicateId(id));
+                }
+                
+                let config = component.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect();
+                graph.add_node(GraphNode { id, kind, component_type, config });
+            }
+        }
+        
+        for (node, inputs) in pending_inputs {
+            for input in inputs {
+                if graph.nodes.contains_key(&input) {
+                    graph.connect(input, node.clone());
+                } else if let Some((from, port)) = input.split_once('.').filter(|(from, _)| graph.nodes.contains_key(*from)) {
+                    graph.connect_port(from, port, node.clone());
+                } else {
+                    return Err(GraphError::UnknownInput { node, input });
+                }
+            }
+        }
+        
+        Ok(graph)
+    }
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => serde_json::Value::from(i),
+        toml::Value::Float(f) => serde_json::Value::from(f),
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(d) => serde_json::Value::String(d.to_string()),
+        toml::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect(),
+        ),
+    }
+}
+
+fn json_to_toml(value: &serde_json::Value) -> Result<toml::Value, String> {
+    Ok(match value {
+        serde_json::Value::Null => return Err("null values cannot be written to TOML".to_string()),
+        serde_json::Value::Bool(b) => toml::Value::Boolean(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64().ok_or_else(|| format!("unsupported number {}", n))?),
+        },
+        serde_json::Value::String(s) => toml::Value::String(s.clone()),
+        serde_json::Value::Array(items) => toml::Value::Array(
+            items.iter().map(json_to_toml).collect::<Result<_, _>>()?,
+        ),
+        serde_json::Value::Object(map) => toml::Value::Table(
+            map.iter()
+                .map(|(k, v)| json_to_toml(v).map(|v| (k.clone(), v)))
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -422,4 +703,102 @@ mod tests {
         assert!(toml.contains("[transforms.parse]"));
         assert!(toml.contains("[sinks.console]"));
     }
+
+    
+    fn sample_graphs() -> Vec<PipelineGraph> {
+        // Linear: source -> transform -> sink
+        let mut linear = PipelineGraph::new();
+        linear.add_node(GraphNode::new("logs", ComponentKind::Source, "demo_logs").with_config("format", "json"));
+        linear.add_node(GraphNode::new("parse", ComponentKind::Transform, "remap").with_config("source", ". = parse_json!(.message)"));
+        linear.add_node(GraphNode::new("out", ComponentKind::Sink, "console")
+            .with_config("encoding", serde_json::json!({"codec": "json"})));
+        linear.connect("logs", "parse");
+        linear.connect("parse", "out");
+        
+        // Fan-in: two sources into one transform
+        let mut fan_in = PipelineGraph::new();
+        fan_in.add_node(GraphNode::new("app", ComponentKind::Source, "file").with_config("include", serde_json::json!(["/var/log/app/*.log"])));
+        fan_in.add_node(GraphNode::new("sys", ComponentKind::Source, "journald"));
+        fan_in.add_node(GraphNode::new("merge", ComponentKind::Transform, "remap").with_config("source", ".host = get_hostname!()"));
+        fan_in.add_node(GraphNode::new("drop", ComponentKind::Sink, "blackhole"));
+        fan_in.connect("app", "merge");
+        fan_in.connect("sys", "merge");
+        fan_in.connect("merge", "drop");
+        
+        // Fan-out through route outputs, plus global settings and mixed value types
+        let mut fan_out = PipelineGraph::new();
+        fan_out.global.insert("data_dir".to_string(), serde_json::json!("/var/lib/vector"));
+        fan_out.global.insert("api".to_string(), serde_json::json!({"enabled": true, "address": "0.0.0.0:8686"}));
+        fan_out.add_node(GraphNode::new("in", ComponentKind::Source, "http_server")
+            .with_config("address", "0.0.0.0:8080"));
+        fan_out.add_node(GraphNode::new("router", ComponentKind::Transform, "route")
+            .with_config("route", serde_json::json!({"errors": ".level == \"error\"", "rest": "true"})));
+        fan_out.add_node(GraphNode::new("sampled", ComponentKind::Transform, "sample").with_config("rate", 10));
+        fan_out.add_node(GraphNode::new("alerts", ComponentKind::Sink, "http")
+            .with_config("uri", "https://alerts.example.com")
+            .with_config("batch", serde_json::json!({"timeout_secs": 1.5, "max_events": 100})));
+        fan_out.add_node(GraphNode::new("archive", ComponentKind::Sink, "aws_s3").with_config("bucket", "logs"));
+        fan_out.connect("in", "router");
+        fan_out.connect_port("router", "errors", "alerts");
+        fan_out.connect_port("router", "rest", "sampled");
+        fan_out.connect("in", "archive");
+        fan_out.connect("sampled", "archive");
+        
+        vec![PipelineGraph::new(), linear, fan_in, fan_out]
+    }
+    
+    #[test]
+    fn test_graph_toml_round_trip() {
+        for graph in sample_graphs() {
+            let toml = graph.to_toml().unwrap();
+            let parsed = PipelineGraph::from_toml(&toml).unwrap();
+            assert_eq!(parsed, graph, "round trip changed graph:\n{}", toml);
+        }
+    }
+    
+    #[test]
+    fn test_graph_from_toml_edges() {
+        let graph = PipelineGraph::from_toml(r#"
+[sources.in]
+type = "stdin"
+
+[transforms.router]
+type = "route"
+inputs = ["in"]
+route.errors = ".level == 'error'"
+
+[sinks.errors]
+type = "console"
+inputs = ["router.errors", "in"]
+"#).unwrap();
+        
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.nodes["router"].kind, ComponentKind::Transform);
+        assert_eq!(graph.inputs_of("errors"), vec!["in", "router.errors"]);
+        assert!(graph.nodes["router"].config.contains_key("route"));
+        assert!(!graph.nodes["errors"].config.contains_key("inputs"));
+    }
+    
+    #[test]
+    fn test_graph_from_toml_errors() {
+        let unknown = PipelineGraph::from_toml(r#"
+[sinks.out]
+type = "console"
+inputs = ["missing"]
+"#);
+        assert!(matches!(unknown, Err(GraphError::UnknownInput { .. })));
+        
+        let untyped = PipelineGraph::from_toml("[sources.in]\nformat = \"json\"\n");
+        assert!(matches!(untyped, Err(GraphError::MissingType(id)) if id == "in"));
+        
+        let duplicate = PipelineGraph::from_toml(r#"
+[sources.x]
+type = "stdin"
+
+[sinks.x]
+type = "blackhole"
+inputs = []
+"#);
+        assert!(matches!(duplicate, Err(GraphError::DuplicateId(_))));
+    }
 }
@@ -3,10 +3,13 @@
 //! This crate contains common types used across the Vectorize platform:
 //! - Pipeline configuration types
 //! - API message types
-//! - Vector component definitions
+//! - Vector component definitions and the component catalog
+//! - Redaction rules for sampled events
 
+pub mod component_schema;
 pub mod config;
 pub mod messages;
+pub mod redaction;
 
 pub use config::*;
 pub use messages::*;
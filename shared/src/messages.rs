@@ -9,6 +9,95 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
+/// Version of the message schema shared by the UI and the control plane
+///
+/// Bump this whenever a change to these types would make an older peer
+/// mis-parse a message (renamed/removed fields, changed enum tags, etc.).
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest client protocol version the control plane still understands
+pub const MIN_SUPPORTED_PROTOCOL_VERSION: u32 = 1;
+
+/// Header a client may send on every request to declare its protocol version
+pub const PROTOCOL_VERSION_HEADER: &str = "x-vectorize-protocol";
+
+/// Handshake sent by a client before talking to the control plane
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Handshake {
+    /// Protocol version the client was built against
+    pub protocol_version: u32,
+    
+    /// Client build version, for logs
+    #[serde(default)]
+    pub client_version: Option<String>,
+}
+
+impl Handshake {
+    /// Handshake for this build
+    pub fn current(client_version: impl Into<String>) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            client_version: Some(client_version.into()),
+        }
+    }
+}
+
+/// Control plane reply to a compatible handshake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandshakeResponse {
+    /// Protocol version the server speaks
+    pub protocol_version: u32,
+    
+    /// Oldest client protocol version the server accepts
+    pub min_protocol_version: u32,
+    
+    /// Server build version
+    pub server_version: String,
+}
+
+/// Result of comparing a client's protocol version with the server's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolCompatibility {
+    /// Client and server understand each other
+    Compatible,
+    /// Client is older than the oldest version the server supports
+    ClientTooOld,
+    /// Client is newer than the server
+    ClientTooNew,
+}
+
+impl ProtocolCompatibility {
+    /// Compare `client_version` against this build's supported range
+    pub fn check(client_version: u32) -> Self {
+        Self::check_range(client_version, MIN_SUPPORTED_PROTOCOL_VERSION, PROTOCOL_VERSION)
+    }
+    
+    /// Compare `client_version` against `min..=current`
+    pub fn check_range(client_version: u32, min: u32, current: u32) -> Self {
+        if client_version < min {
+            Self::ClientTooOld
+        } else if client_version > current {
+            Self::ClientTooNew
+        } else {
+            Self::Compatible
+        }
+    }
+    
+    pub fn is_compatible(&self) -> bool {
+        *self == Self::Compatible
+    }
+    
+    /// What the user should do about a mismatch
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::Compatible => "Client and server protocol versions match",
+            Self::ClientTooOld => "Client is too old for this server; reload or update the UI",
+            Self::ClientTooNew => "Client is too new for this server; upgrade the Vectorize server",
+        }
+    }
+}
+
 /// Connection mode for the UI
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -99,6 +188,30 @@ pub enum ComponentKind {
     Sink,
 }
 
+impl ComponentKind {
+    /// Config table name (`sources`, `transforms`, `sinks`)
+    pub fn table_name(&self) -> &'static str {
+        match self {
+            ComponentKind::Source => "sources",
+            ComponentKind::Transform => "transforms",
+            ComponentKind::Sink => "sinks",
+        }
+    }
+}
+
+impl std::str::FromStr for ComponentKind {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "source" | "sources" => Ok(ComponentKind::Source),
+            "transform" | "transforms" => Ok(ComponentKind::Transform),
+            "sink" | "sinks" => Ok(ComponentKind::Sink),
+            _ => Err(format!("Unknown component kind: {}", s)),
+        }
+    }
+}
+
 /// Component metrics from Vector's GraphQL API
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ComponentMetrics {
@@ -300,4 +413,28 @@ mod tests {
             _ => panic!("Wrong message type"),
         }
     }
+    
+    #[test]
+    fn test_protocol_compatibility() {
+        assert_eq!(ProtocolCompatibility::check(PROTOCOL_VERSION), ProtocolCompatibility::Compatible);
+        assert_eq!(ProtocolCompatibility::check(PROTOCOL_VERSION + 1), ProtocolCompatibility::ClientTooNew);
+        
+        assert_eq!(ProtocolCompatibility::check_range(1, 2, 3), ProtocolCompatibility::ClientTooOld);
+        assert_eq!(ProtocolCompatibility::check_range(2, 2, 3), ProtocolCompatibility::Compatible);
+        assert_eq!(ProtocolCompatibility::check_range(3, 2, 3), ProtocolCompatibility::Compatible);
+        assert_eq!(ProtocolCompatibility::check_range(4, 2, 3), ProtocolCompatibility::ClientTooNew);
+        assert!(!ProtocolCompatibility::ClientTooOld.is_compatible());
+    }
+    
+    #[test]
+    fn test_handshake_serialization() {
+        let json = serde_json::to_value(Handshake::current("0.1.0")).unwrap();
+        assert_eq!(json["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(json["client_version"], "0.1.0");
+        
+        // client_version is optional
+        let parsed: Handshake = serde_json::from_str(r#"{"protocol_version": 7}"#).unwrap();
+        assert_eq!(parsed.protocol_version, 7);
+        assert!(parsed.client_version.is_none());
+    }
 }
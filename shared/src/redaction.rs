@@ -0,0 +1,172 @@
+//! Sampled event redaction
+//!
+//! Field rules for masking sensitive values in tapped events before they
+//! are shown, downloaded or copied. The control plane serves the rules and
+//! the tap viewer applies them, so both use this one implementation.
+
+use serde::{Deserialize, Serialize};
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "***";
+
+/// Fields redacted when nothing else is configured
+pub const DEFAULT_REDACTED_FIELDS: &[&str] = &[
+    "authorization",
+    "password",
+    "secret",
+    "api_key",
+    "*.token",
+];
+
+/// Field paths whose values are replaced with `***`
+///
+/// A path is dot-separated keys from the root of the event; `*` matches any
+/// single key. A path without dots (e.g. `password`) matches that key at any
+/// depth. Keys compare case-insensitively, and arrays are searched element
+/// by element without adding to the path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedactionRules {
+    pub fields: Vec<String>,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        Self::new(DEFAULT_REDACTED_FIELDS.iter().copied())
+    }
+}
+
+impl RedactionRules {
+    /// Rules for the given field paths; blank entries are ignored
+    pub fn new<I, S>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            fields: fields.into_iter()
+                .map(|f| f.as_ref().trim().to_string())
+                .filter(|f| !f.is_empty())
+                .collect(),
+        }
+    }
+    
+    /// Rules from a comma-separated list, e.g. `password, *.token`
+    pub fn parse(list: &str) -> Self {
+        Self::new(list.split(','))
+    }
+    
+    /// Whether no field is redacted
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+    
+    /// Redact matching fields in place
+    pub fn redact(&self, event: &mut serde_json::Value) {
+        if self.is_empty() {
+            return;
+        }
+        let patterns: Vec<Vec<&str>> = self.fields.iter()
+            .map(|f| f.split('.').collect())
+            .collect();
+        redact_value(event, &patterns, &mut Vec::new());
+    }
+    
+    /// A redacted copy of an event
+    pub fn redacted(&self, event: &serde_json::Value) -> serde_json::Value {
+        let mut event = event.clone();
+        self.redact(&mut event);
+        event
+    }
+}
+
+fn redact_value(value: &mut serde_json::Value, patterns: &[Vec<&str>], path: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                path.push(key.clone());
+                if patterns.iter().any(|p| matches(p, path)) {
+                    *field = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_value(field, patterns, path);
+                }
+                path.pop();
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact_value(item, patterns, path);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn matches(pattern: &[&str], path: &[String]) -> bool {
+    if pattern.len() == 1 {
+        return path.last().is_some_and(|key| segment_matches(pattern[0], key));
+    }
+    pattern.len() == path.len()
+        && pattern.iter().zip(path).all(|(p, key)| segment_matches(p, key))
+}
+
+fn segment_matches(pattern: &str, key: &str) -> bool {
+    pattern == "*" || pattern.eq_ignore_ascii_case(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn test_nested_path_redaction() {
+        let rules = RedactionRules::parse("user.email, headers.authorization");
+        let event = rules.redacted(&json!({
+            "message": "login",
+            "email": "top@example.com",
+            "user": { "email": "a@example.com", "name": "a" },
+            "headers": { "Authorization": "Bearer abc", "accept": "*/*" },
+        }));
+    
+        assert_eq!(event["user"]["email"], REDACTED);
+        assert_eq!(event["user"]["name"], "a");
+        assert_eq!(event["headers"]["Authorization"], REDACTED);
+        assert_eq!(event["headers"]["accept"], "*/*");
+        // Dotted paths are anchored at the root
+        assert_eq!(event["email"], "top@example.com");
+        assert_eq!(event["message"], "login");
+    }
+    
+    #[test]
+    fn test_wildcard_and_bare_field_redaction() {
+        let rules = RedactionRules::parse("*.token, password");
+        let event = rules.redacted(&json!({
+            "token": "top-level",
+            "session": { "token": "t1", "id": 7 },
+            "oauth": { "token": { "value": "t2" } },
+            "deep": { "nested": { "token": "kept", "password": "p1" } },
+            "users": [{ "password": "p2" }, { "name": "b" }],
+        }));
+    
+        assert_eq!(event["token"], "top-level");
+        assert_eq!(event["session"]["token"], REDACTED);
+        assert_eq!(event["session"]["id"], 7);
+        assert_eq!(event["oauth"]["token"], REDACTED);
+        // `*` matches exactly one key
+        assert_eq!(event["deep"]["nested"]["token"], "kept");
+        // Bare names match at any depth, including inside arrays
+        assert_eq!(event["deep"]["nested"]["password"], REDACTED);
+        assert_eq!(event["users"][0]["password"], REDACTED);
+        assert_eq!(event["users"][1]["name"], "b");
+    }
+    
+    #[test]
+    fn test_rule_parsing() {
+        assert_eq!(RedactionRules::parse(" a , ,b.c ").fields, vec!["a", "b.c"]);
+        assert!(RedactionRules::parse("").is_empty());
+        assert!(RedactionRules::default().fields.contains(&"*.token".to_string()));
+    
+        let event = json!({ "password": "x" });
+        assert_eq!(RedactionRules::parse("").redacted(&event), event);
+    }
+}
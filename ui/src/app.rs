@@ -45,12 +45,21 @@ pub fn App() -> impl IntoView {
                     .await
                 {
                     Ok(response) if response.ok() => {
+                        let origin = web_sys::window()
+                            .and_then(|w| w.location().origin().ok())
+                            .unwrap_or_else(|| "http://localhost:8080".to_string());
+                        
+                        // Make sure we can parse what this server sends
+                        if let Err(message) = check_protocol_version(&origin).await {
+                            web_sys::console::error_1(&message.clone().into());
+                            app_state.update_required.set(Some(message));
+                        }
+                        
+                        // Push agent status and deployment updates instead of polling
+                        app_state.start_live_updates(&origin);
+                        
                         // We're inside Vectorize - auto-connect via proxy
-                        let proxy_url = format!("{}/api", 
-                            web_sys::window()
-                                .and_then(|w| w.location().origin().ok())
-                                .unwrap_or_else(|| "http://localhost:8080".to_string())
-                        );
+                        let proxy_url = format!("{}/api", origin);
                         
                         if let Err(e) = app_state.connect_direct(&proxy_url).await {
                             web_sys::console::error_1(&format!("Auto-connect failed: {}", e).into());
@@ -133,6 +142,29 @@ pub fn App() -> impl IntoView {
     }
 }
 
+/// Handshake with the control plane, returning the update message on a version mismatch
+///
+/// Network failures and servers without the handshake endpoint are not
+/// treated as mismatches.
+async fn check_protocol_version(origin: &str) -> Result<(), String> {
+    let handshake = vectorize_shared::Handshake::current(env!("CARGO_PKG_VERSION"));
+    let Ok(request) = gloo_net::http::Request::post(&format!("{}/api/v1/handshake", origin)).json(&handshake) else {
+        return Ok(());
+    };
+    let Ok(response) = request.send().await else {
+        return Ok(());
+    };
+    
+    if response.status() != 426 {
+        return Ok(());
+    }
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    Err(body["error"]["message"]
+        .as_str()
+        .unwrap_or("This UI is incompatible with the Vectorize server")
+        .to_string())
+}
+
 /// Main layout wrapper - no sidebar, no bottom panel (Dashboard, Fleet, Settings)
 #[component]
 fn MainLayout(children: Children) -> impl IntoView {
@@ -0,0 +1,197 @@
+//! Control Plane Client
+//!
+//! Client for the Vectorize control plane's live-update WebSocket
+//! (`/api/v1/ws`), which pushes agent status changes and deployment progress
+//! instead of the dashboard polling for them.
+
+use serde::Deserialize;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use gloo_timers::future::TimeoutFuture;
+use wasm_bindgen::prelude::*;
+use web_sys::{CloseEvent, MessageEvent, WebSocket};
+
+use super::ReconnectPolicy;
+
+/// An update pushed by the control plane
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveUpdate {
+    /// An agent's health status changed
+    AgentStatusChanged {
+        agent_id: String,
+        agent_name: String,
+        previous_status: String,
+        status: String,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    /// Progress for a deployment (the event is the deployment SSE payload)
+    Deployment {
+        deployment_id: String,
+        event: serde_json::Value,
+    },
+}
+
+/// Callback type for receiving live updates
+pub type LiveUpdateCallback = Rc<dyn Fn(LiveUpdate)>;
+
+/// Client for a Vectorize control plane
+#[derive(Clone)]
+pub struct ControlPlaneClient {
+    ws_url: String,
+    policy: ReconnectPolicy,
+}
+
+/// Shared state of a live-update connection across reconnects
+struct LiveConnection {
+    ws_url: String,
+    policy: ReconnectPolicy,
+    on_update: LiveUpdateCallback,
+    ws: RefCell<Option<WebSocket>>,
+    /// Set once the handle is cancelled; stops reconnecting
+    closed: Cell<bool>,
+    /// Failed attempts since the last successful open
+    attempt: Cell<u32>,
+}
+
+/// Handle to control an active live-update subscription
+pub struct LiveUpdatesHandle {
+    connection: Rc<LiveConnection>,
+}
+
+impl LiveUpdatesHandle {
+    /// Close the WebSocket and stop reconnecting
+    pub fn cancel(&self) {
+        self.connection.closed.set(true);
+        if let Some(ws) = self.connection.ws.borrow_mut().take() {
+            let _ = ws.close();
+        }
+    }
+}
+
+impl Drop for LiveUpdatesHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl ControlPlaneClient {
+    /// Create a new control plane client
+    ///
+    /// # Arguments
+    /// * `base_url` - HTTP URL of the control plane (e.g., "http://localhost:8080")
+    pub fn new(base_url: &str) -> Self {
+        let ws_url = base_url
+            .trim_end_matches('/')
+            .replace("http://", "ws://")
+            .replace("https://", "wss://");
+    
+        Self {
+            ws_url: format!("{}/api/v1/ws", ws_url),
+            policy: ReconnectPolicy::default(),
+        }
+    }
+    
+    /// Use a custom reconnection backoff
+    #[allow(dead_code)]
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+    
+    /// Subscribe to live updates
+    ///
+    /// The connection is reopened with exponential backoff whenever it drops,
+    /// until the returned handle is cancelled or dropped. Updates that happen
+    /// while disconnected are not replayed.
+    pub fn subscribe_live_updates(
+        &self,
+        on_update: LiveUpdateCallback,
+    ) -> LiveUpdatesHandle {
+        let connection = Rc::new(LiveConnection {
+            ws_url: self.ws_url.clone(),
+            policy: self.policy,
+            on_update,
+            ws: RefCell::new(None),
+            closed: Cell::new(false),
+            attempt: Cell::new(0),
+        });
+    
+        open(connection.clone());
+    
+        LiveUpdatesHandle { connection }
+    }
+}
+
+/// Open the WebSocket, scheduling a reconnect when it closes
+fn open(connection: Rc<LiveConnection>) {
+    if connection.closed.get() {
+        return;
+    }
+    
+    let ws = match WebSocket::new(&connection.ws_url) {
+        Ok(ws) => ws,
+        Err(e) => {
+            web_sys::console::error_1(&format!("Failed to create live-update WebSocket: {:?}", e).into());
+            schedule_reconnect(connection);
+            return;
+        }
+    };
+    
+    let conn_for_open = connection.clone();
+    let onopen_callback = Closure::<dyn FnMut()>::new(move || {
+        if conn_for_open.attempt.replace(0) > 0 {
+            web_sys::console::log_1(&"Live updates reconnected".into());
+        }
+    });
+    ws.set_onopen(Some(onopen_callback.as_ref().unchecked_ref()));
+    onopen_callback.forget();
+    
+    let on_update = connection.on_update.clone();
+    let onmessage_callback = Closure::<dyn FnMut(_)>::new(move |e: MessageEvent| {
+        if let Ok(txt) = e.data().dyn_into::<js_sys::JsString>() {
+            let text: String = txt.into();
+            match serde_json::from_str::<LiveUpdate>(&text) {
+                Ok(update) => on_update(update),
+                Err(e) => {
+                    web_sys::console::warn_1(&format!("Ignoring unknown live update: {}", e).into());
+                }
+            }
+        }
+    });
+    ws.set_onmessage(Some(onmessage_callback.as_ref().unchecked_ref()));
+    onmessage_callback.forget();
+    
+    // A failed connection attempt also fires close, so reconnecting only here
+    // covers both drops and refused connections
+    let conn_for_close = connection.clone();
+    let onclose_callback = Closure::<dyn FnMut(_)>::new(move |e: CloseEvent| {
+        web_sys::console::log_1(&format!("Live-update WebSocket closed: code={}", e.code()).into());
+        conn_for_close.ws.borrow_mut().take();
+        schedule_reconnect(conn_for_close.clone());
+    });
+    ws.set_onclose(Some(onclose_callback.as_ref().unchecked_ref()));
+    onclose_callback.forget();
+    
+    *connection.ws.borrow_mut() = Some(ws);
+}
+
+/// Reopen the connection after the policy's backoff delay
+fn schedule_reconnect(connection: Rc<LiveConnection>) {
+    if connection.closed.get() {
+        return;
+    }
+    
+    let attempt = connection.attempt.get() + 1;
+    if !connection.policy.allows(attempt - 1) {
+        web_sys::console::error_1(&"Giving up on live updates after repeated failures".into());
+        return;
+    }
+    connection.attempt.set(attempt);
+    
+    wasm_bindgen_futures::spawn_local(async move {
+        TimeoutFuture::new(connection.policy.delay_ms(attempt)).await;
+        open(connection);
+    });
+}
@@ -2,9 +2,18 @@
 //!
 //! This client connects directly to a single Vector instance's GraphQL API.
 //! Used for single-node (laptop) deployments.
+//!
+//! The client tracks its connection state. When a request fails to reach
+//! Vector (network error, or a gateway error from the Vectorize proxy while
+//! Vector restarts) it starts probing the health endpoint with exponential
+//! backoff until Vector is back, notifying `on_state_change` listeners along
+//! the way.
 
-use gloo_net::http::Request;
+use gloo_net::http::{Request, Response};
+use gloo_timers::future::TimeoutFuture;
 use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 use vectorize_shared::{
     Component, ComponentKind, ComponentMetrics, DeployResult, NodeDeployResult,
     NodeHealthStatus, Pipeline, PipelineConfig, PipelineNode, Position, Topology, VectorNode,
@@ -12,13 +21,39 @@ use vectorize_shared::{
 };
 use std::collections::HashMap;
 
-use super::{VectorClient, VectorClientError};
+use super::{ConnectionState, ReconnectPolicy, VectorClient, VectorClientError};
+
+/// Callback type for connection state changes
+pub type StateCallback = Rc<dyn Fn(ConnectionState)>;
+
+/// Connection state shared by all clones of a client
+#[derive(Default)]
+struct Connection {
+    state: Cell<ConnectionState>,
+    /// A reconnect loop is running
+    reconnecting: Cell<bool>,
+    /// The client was closed; stop retrying and watching
+    closed: Cell<bool>,
+    listeners: RefCell<Vec<StateCallback>>,
+}
 
 /// Direct client for connecting to a single Vector instance
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct DirectClient {
     /// Vector API base URL
     base_url: String,
+    /// Backoff used when the connection drops
+    policy: ReconnectPolicy,
+    connection: Rc<Connection>,
+}
+
+impl std::fmt::Debug for DirectClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirectClient")
+            .field("base_url", &self.base_url)
+            .field("state", &self.state())
+            .finish()
+    }
 }
 
 impl DirectClient {
@@ -26,7 +61,144 @@ impl DirectClient {
     pub fn new(url: &str) -> Self {
         // Normalize URL (remove trailing slash)
         let base_url = url.trim_end_matches('/').to_string();
-        Self { base_url }
+        Self {
+            base_url,
+            policy: ReconnectPolicy::default(),
+            connection: Rc::new(Connection::default()),
+        }
+    }
+    
+    /// Use a custom reconnection backoff
+    #[allow(dead_code)]
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+    
+    /// Current connection state
+    pub fn state(&self) -> ConnectionState {
+        self.connection.state.get()
+    }
+    
+    /// Register a callback invoked whenever the connection state changes
+    pub fn on_state_change(&self, callback: impl Fn(ConnectionState) + 'static) {
+        self.connection.listeners.borrow_mut().push(Rc::new(callback));
+    }
+    
+    /// Check Vector's health every `interval_ms` so a dropped connection is
+    /// noticed even when nothing else is making requests
+    pub fn watch(&self, interval_ms: u32) {
+        let client = self.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            loop {
+                TimeoutFuture::new(interval_ms).await;
+                if client.connection.closed.get() {
+                    break;
+                }
+                if client.state() == ConnectionState::Connected {
+                    // Failures start the reconnect loop
+                    let _ = client.health().await;
+                }
+            }
+        });
+    }
+    
+    /// Retry immediately after the client gave up reconnecting
+    pub fn reconnect_now(&self) {
+        self.connection_lost();
+    }
+    
+    /// Stop retrying and mark the client disconnected
+    pub fn close(&self) {
+        self.connection.closed.set(true);
+        self.set_state(ConnectionState::Disconnected);
+    }
+    
+    fn set_state(&self, state: ConnectionState) {
+        if self.connection.closed.get() && state != ConnectionState::Disconnected {
+            return;
+        }
+        if self.connection.state.replace(state) == state {
+            return;
+        }
+        
+        // Clone the list so callbacks can register further listeners
+        let listeners = self.connection.listeners.borrow().clone();
+        for listener in listeners {
+            listener(state);
+        }
+    }
+    
+    /// Start the reconnect loop unless one is already running
+    fn connection_lost(&self) {
+        if self.connection.closed.get() || self.connection.reconnecting.replace(true) {
+            return;
+        }
+        
+        let client = self.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            client.reconnect().await;
+            client.connection.reconnecting.set(false);
+        });
+    }
+    
+    /// Probe the health endpoint with exponential backoff
+    ///
+    /// The attempt counter starts over on every connection loss, so the
+    /// backoff resets after a successful reconnect.
+    async fn reconnect(&self) {
+        let mut attempt = 1;
+        loop {
+            self.set_state(ConnectionState::Reconnecting { attempt });
+            TimeoutFuture::new(self.policy.delay_ms(attempt)).await;
+            
+            if self.connection.closed.get() {
+                return;
+            }
+            
+            if self.probe().await {
+                web_sys::console::log_1(&format!("Reconnected to Vector after {} attempt(s)", attempt).into());
+                self.set_state(ConnectionState::Connected);
+                return;
+            }
+            
+            if !self.policy.allows(attempt) {
+                web_sys::console::warn_1(&format!("Giving up reconnecting to Vector after {} attempts", attempt).into());
+                self.set_state(ConnectionState::Disconnected);
+                return;
+            }
+            attempt += 1;
+        }
+    }
+    
+    /// Health check that doesn't touch connection state
+    async fn probe(&self) -> bool {
+        matches!(Request::get(&self.health_url()).send().await, Ok(response) if response.ok())
+    }
+    
+    /// Send a request, tracking connection state
+    ///
+    /// Network errors and gateway errors (502-504, returned by the Vectorize
+    /// proxy while Vector is down) count as a lost connection.
+    async fn send(&self, request: Request) -> Result<Response, VectorClientError> {
+        match request.send().await {
+            Ok(response) if (502..=504).contains(&response.status()) => {
+                self.connection_lost();
+                Err(VectorClientError::ConnectionFailed(format!(
+                    "HTTP {}: {}",
+                    response.status(),
+                    response.status_text()
+                )))
+            }
+            Ok(response) => {
+                self.set_state(ConnectionState::Connected);
+                Ok(response)
+            }
+            Err(e) => {
+                self.connection_lost();
+                Err(VectorClientError::ConnectionFailed(e.to_string()))
+            }
+        }
     }
     
     /// Get the GraphQL endpoint URL
@@ -54,13 +226,11 @@ impl DirectClient {
             variables: None,
         };
         
-        let response = Request::post(&self.graphql_url())
+        let request = Request::post(&self.graphql_url())
             .header("Content-Type", "application/json")
             .json(&request)
-            .map_err(|e| VectorClientError::RequestFailed(e.to_string()))?
-            .send()
-            .await
-            .map_err(|e| VectorClientError::ConnectionFailed(e.to_string()))?;
+            .map_err(|e| VectorClientError::RequestFailed(e.to_string()))?;
+        let response = self.send(request).await?;
         
         if !response.ok() {
             return Err(VectorClientError::RequestFailed(format!(
@@ -243,11 +413,11 @@ impl DirectClient {
     async fn fetch_config(&self) -> Result<serde_json::Value, VectorClientError> {
         let url = format!("{}/config", self.base_url);
         
-        let response = Request::get(&url)
+        let request = Request::get(&url)
             .header("Accept", "application/json")
-            .send()
-            .await
-            .map_err(|e| VectorClientError::ConnectionFailed(e.to_string()))?;
+            .build()
+            .map_err(|e| VectorClientError::RequestFailed(e.to_string()))?;
+        let response = self.send(request).await?;
         
         if !response.ok() {
             // Config endpoint may not be available in standalone mode
@@ -375,10 +545,10 @@ impl DirectClient {
 #[async_trait::async_trait(?Send)]
 impl VectorClient for DirectClient {
     async fn health(&self) -> Result<(), VectorClientError> {
-        let response = Request::get(&self.health_url())
-            .send()
-            .await
-            .map_err(|e| VectorClientError::ConnectionFailed(e.to_string()))?;
+        let request = Request::get(&self.health_url())
+            .build()
+            .map_err(|e| VectorClientError::RequestFailed(e.to_string()))?;
+        let response = self.send(request).await?;
         
         if response.ok() {
             Ok(())
@@ -479,13 +649,11 @@ impl VectorClient for DirectClient {
     }
     
     async fn deploy_config(&self, config: &PipelineConfig) -> Result<DeployResult, VectorClientError> {
-        let response = Request::post(&self.config_url())
+        let request = Request::post(&self.config_url())
             .header("Content-Type", "application/json")
             .json(config)
-            .map_err(|e| VectorClientError::RequestFailed(e.to_string()))?
-            .send()
-            .await
-            .map_err(|e| VectorClientError::ConnectionFailed(e.to_string()))?;
+            .map_err(|e| VectorClientError::RequestFailed(e.to_string()))?;
+        let response = self.send(request).await?;
         
         let success = response.ok();
         let error = if !success {
@@ -6,15 +6,18 @@
 //! # Connection Modes
 //!
 //! - **DirectClient**: Connects directly to Vector's GraphQL API
-//! - **ControlPlaneClient**: Connects to the Vectorize control plane
+//! - **ControlPlaneClient**: Connects to the Vectorize control plane and
+//!   receives live agent and deployment updates over WebSocket
 //!
 //! Both implement the same `VectorClient` trait, allowing the UI to
 //! work identically in both modes.
 
+mod control_plane;
 mod direct;
 mod subscription;
 mod types;
 
+pub use control_plane::{ControlPlaneClient, LiveUpdate, LiveUpdatesHandle};
 pub use direct::DirectClient;
 pub use subscription::{SubscriptionClient, SubscriptionHandle};
 // Re-export types for external use
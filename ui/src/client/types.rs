@@ -1,7 +1,7 @@
 //! Client type definitions
 //!
 //! Additional types used by the Vector clients.
-//! These types are prepared for future filter/subscription enhancements.
+//! Filter and subscription types are prepared for future enhancements.
 
 use serde::{Deserialize, Serialize};
 
@@ -69,3 +69,56 @@ impl SubscriptionOptions {
         }
     }
 }
+
+/// State of a client's connection to Vector
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Requests are succeeding
+    Connected,
+    /// The connection dropped and the client is retrying
+    Reconnecting {
+        /// Retry attempt, starting at 1
+        attempt: u32,
+    },
+    /// Not connected, and not retrying
+    #[default]
+    Disconnected,
+}
+
+/// Exponential backoff for reconnection attempts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first retry
+    pub initial_delay_ms: u32,
+    
+    /// Upper bound on the delay between retries
+    pub max_delay_ms: u32,
+    
+    /// Give up after this many attempts (None = retry forever)
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before retry `attempt` (starting at 1), doubling each time up to the cap
+    pub fn delay_ms(&self, attempt: u32) -> u32 {
+        let exponent = attempt.saturating_sub(1).min(16);
+        self.initial_delay_ms
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay_ms)
+    }
+    
+    /// Whether another attempt is allowed after `attempt` failed ones
+    pub fn allows(&self, attempt: u32) -> bool {
+        self.max_attempts.map_or(true, |max| attempt < max)
+    }
+}
@@ -3,18 +3,26 @@
 use leptos::*;
 
 use super::FleetHealth;
+use crate::state::AppState;
 
 /// Fleet health overview card
 #[component]
 pub fn HealthCard() -> impl IntoView {
+    let app_state = expect_context::<AppState>();
     let (health, set_health) = create_signal(Option::<FleetHealth>::None);
     let (loading, set_loading) = create_signal(true);
     let (error, set_error) = create_signal(Option::<String>::None);
     
-    // Fetch health data on mount
-    create_effect(move |_| {
+    // Fetch health data on mount, and again whenever the control plane
+    // pushes an update
+    create_effect(move |prev_run: Option<()>| {
+        let _ = app_state.live_revision.get();
+        let first_load = prev_run.is_none();
         spawn_local(async move {
-            set_loading.set(true);
+            // Refresh in place after the first load so the card doesn't flash
+            if first_load {
+                set_loading.set(true);
+            }
             
             match fetch_fleet_health().await {
                 Ok(data) => {
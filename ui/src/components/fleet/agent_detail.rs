@@ -6,6 +6,7 @@
 use leptos::*;
 
 use super::{AgentInfo, AgentStatus, fetch_agents};
+use super::agent_top::AgentTop;
 
 /// Agent detail view
 #[component]
@@ -139,6 +140,9 @@ pub fn AgentDetail(
                                     </div>
                                 })}
                                 
+                                // Live per-component throughput
+                                <AgentTop agent_id=a.id.clone() />
+                                
                                 // Health history placeholder
                                 <div class="bg-theme-surface rounded-xl border border-theme-border p-6">
                                     <h3 class="text-lg font-medium text-theme mb-4">"Health History"</h3>
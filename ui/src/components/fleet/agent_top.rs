@@ -0,0 +1,139 @@
+//! Agent Throughput Component
+//!
+//! Live per-component throughput for one agent, like `vector top`, streamed
+//! from the control plane's `/api/v1/metrics/:id/top` WebSocket.
+
+use leptos::*;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use web_sys::{MessageEvent, WebSocket};
+
+/// One row of the throughput table
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+struct TopRow {
+    component_id: String,
+    kind: String,
+    component_type: Option<String>,
+    sent_events_total: Option<u64>,
+    events_per_sec: Option<f64>,
+    bytes_per_sec: Option<f64>,
+}
+
+/// A throughput snapshot pushed by the control plane
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+struct TopSnapshot {
+    components: Vec<TopRow>,
+}
+
+/// Format a per-second rate compactly (e.g. `1.2k/s`)
+fn format_rate(rate: Option<f64>, unit: &str) -> String {
+    match rate {
+        None => "—".to_string(),
+        Some(r) if r >= 1_000_000.0 => format!("{:.1}M{}/s", r / 1_000_000.0, unit),
+        Some(r) if r >= 1_000.0 => format!("{:.1}k{}/s", r / 1_000.0, unit),
+        Some(r) => format!("{:.0}{}/s", r, unit),
+    }
+}
+
+/// WebSocket URL of an agent's throughput stream
+fn top_url(agent_id: &str) -> String {
+    let origin = web_sys::window()
+        .and_then(|w| w.location().origin().ok())
+        .unwrap_or_else(|| "http://localhost:8080".to_string())
+        .replace("http://", "ws://")
+        .replace("https://", "wss://");
+    format!("{}/api/v1/metrics/{}/top", origin, agent_id)
+}
+
+/// Live-updating throughput table for an agent
+#[component]
+pub fn AgentTop(#[prop(into)] agent_id: String) -> impl IntoView {
+    let (snapshot, set_snapshot) = create_signal(Option::<TopSnapshot>::None);
+    let (connected, set_connected) = create_signal(false);
+    let socket: Rc<RefCell<Option<WebSocket>>> = Rc::new(RefCell::new(None));
+
+    match WebSocket::new(&top_url(&agent_id)) {
+        Ok(ws) => {
+            let onopen = Closure::<dyn FnMut()>::new(move || set_connected.set(true));
+            ws.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+            onopen.forget();
+
+            let onmessage = Closure::<dyn FnMut(_)>::new(move |e: MessageEvent| {
+                if let Ok(txt) = e.data().dyn_into::<js_sys::JsString>() {
+                    let text: String = txt.into();
+                    if let Ok(update) = serde_json::from_str::<TopSnapshot>(&text) {
+                        set_snapshot.set(Some(update));
+                    }
+                }
+            });
+            ws.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+            onmessage.forget();
+
+            let onclose = Closure::<dyn FnMut()>::new(move || set_connected.set(false));
+            ws.set_onclose(Some(onclose.as_ref().unchecked_ref()));
+            onclose.forget();
+
+            *socket.borrow_mut() = Some(ws);
+        }
+        Err(e) => {
+            web_sys::console::error_1(&format!("Failed to open throughput stream: {:?}", e).into());
+        }
+    }
+
+    // Stop streaming when the view goes away
+    let socket_for_cleanup = socket.clone();
+    on_cleanup(move || {
+        if let Some(ws) = socket_for_cleanup.borrow_mut().take() {
+            let _ = ws.close();
+        }
+    });
+
+    view! {
+        <div class="bg-theme-surface rounded-xl border border-theme-border p-6">
+            <div class="flex items-center justify-between mb-4">
+                <h3 class="text-lg font-medium text-theme">"Throughput"</h3>
+                <span class=move || if connected.get() { "text-xs text-green-400" } else { "text-xs text-theme-muted" }>
+                    {move || if connected.get() { "Live" } else { "Disconnected" }}
+                </span>
+            </div>
+            {move || match snapshot.get() {
+                None => view! {
+                    <div class="text-theme-muted text-center py-4">"Waiting for metrics..."</div>
+                }.into_view(),
+                Some(snapshot) if snapshot.components.is_empty() => view! {
+                    <div class="text-theme-muted text-center py-4">"No components reported"</div>
+                }.into_view(),
+                Some(snapshot) => view! {
+                    <table class="w-full text-sm">
+                        <thead>
+                            <tr class="text-left text-theme-muted border-b border-theme-border">
+                                <th class="py-2 font-medium">"Component"</th>
+                                <th class="py-2 font-medium">"Kind"</th>
+                                <th class="py-2 font-medium">"Type"</th>
+                                <th class="py-2 font-medium text-right">"Events"</th>
+                                <th class="py-2 font-medium text-right">"Bytes"</th>
+                                <th class="py-2 font-medium text-right">"Total"</th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            {snapshot.components.into_iter().map(|row| view! {
+                                <tr class="border-b border-theme-border last:border-0">
+                                    <td class="py-2 font-mono text-theme">{row.component_id}</td>
+                                    <td class="py-2 text-theme-secondary">{row.kind}</td>
+                                    <td class="py-2 text-theme-secondary">{row.component_type.unwrap_or_default()}</td>
+                                    <td class="py-2 text-right font-mono text-theme">{format_rate(row.events_per_sec, "")}</td>
+                                    <td class="py-2 text-right font-mono text-theme">{format_rate(row.bytes_per_sec, "B")}</td>
+                                    <td class="py-2 text-right font-mono text-theme-muted">
+                                        {row.sent_events_total.map(|t| t.to_string()).unwrap_or_else(|| "—".to_string())}
+                                    </td>
+                                </tr>
+                            }).collect_view()}
+                        </tbody>
+                    </table>
+                }.into_view(),
+            }}
+        </div>
+    }
+}
@@ -7,6 +7,7 @@ use leptos::*;
 use leptos_router::*;
 
 use super::{AgentInfo, AgentStatus, fetch_agents, delete_agent};
+use crate::state::AppState;
 
 /// Agents list with table view
 #[component]
@@ -32,6 +33,24 @@ pub fn AgentsList() -> impl IntoView {
         });
     });
     
+    // Apply agent status changes pushed by the control plane
+    {
+        let app_state = expect_context::<AppState>();
+        create_effect(move |_| {
+            let statuses = app_state.agent_statuses.get();
+            if statuses.is_empty() {
+                return;
+            }
+            set_agents.update(|agents| {
+                for agent in agents.iter_mut() {
+                    if let Some(status) = statuses.get(&agent.id) {
+                        agent.status = AgentStatus::from_status(status);
+                    }
+                }
+            });
+        });
+    }
+    
     // Filtered agents
     let filtered_agents = move || {
         let agents_list = agents.get();
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 
 mod agents_list;
 mod agent_detail;
+mod agent_top;
 mod topology;
 
 pub use agents_list::AgentsList;
@@ -35,6 +36,15 @@ pub enum AgentStatus {
 }
 
 impl AgentStatus {
+    /// Parse a status string as sent by the control plane
+    pub fn from_status(status: &str) -> Self {
+        match status {
+            "healthy" => AgentStatus::Healthy,
+            "unhealthy" => AgentStatus::Unhealthy,
+            _ => AgentStatus::Unknown,
+        }
+    }
+    
     /// CSS class for status dot
     pub fn dot_class(&self) -> &'static str {
         match self {
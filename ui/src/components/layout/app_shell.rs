@@ -9,7 +9,7 @@
 
 use leptos::*;
 
-use super::{MainTabs, PipelineSidebar, BottomPanel, DataPreviewPanel, StatusBar};
+use super::{MainTabs, PipelineSidebar, BottomPanel, DataPreviewPanel, StatusBar, ConnectionBanner, UpdateRequiredBanner};
 use crate::state::{AppState, Theme, BottomPanelTab};
 
 /// Main application shell layout
@@ -55,6 +55,12 @@ pub fn AppShell(
             // Top navigation tabs
             <MainTabs />
             
+            // Shown when this UI is too old or too new for the server
+            <UpdateRequiredBanner />
+            
+            // Shown while the connection to Vector is down
+            <ConnectionBanner />
+            
             // Main content area (with optional sidebar)
             <div class="flex-1 flex min-h-0 overflow-hidden">
                 // Pipeline sidebar (optional)
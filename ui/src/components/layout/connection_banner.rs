@@ -0,0 +1,44 @@
+//! Connection Banner Component
+//!
+//! Banner shown above the page content while the connection to Vector is
+//! lost, so stale data isn't mistaken for live data.
+
+use leptos::*;
+
+use crate::client::ConnectionState;
+use crate::state::AppState;
+
+/// Banner for a dropped connection (hidden while connected)
+#[component]
+pub fn ConnectionBanner() -> impl IntoView {
+    let app_state = expect_context::<AppState>();
+    // Copy handle for the retry button's click handler
+    let retry_state = store_value(app_state.clone());
+    
+    let lost = move || {
+        app_state.connected.get() && app_state.connection_state.get() != ConnectionState::Connected
+    };
+    
+    view! {
+        <Show when=lost>
+            <div class="flex items-center justify-between gap-3 px-4 py-2 bg-warning/10 border-b border-warning/30 text-sm text-warning">
+                <span>
+                    {move || match app_state.connection_state.get() {
+                        ConnectionState::Reconnecting { attempt } => {
+                            format!("Connection to Vector lost. Reconnecting (attempt {})...", attempt)
+                        }
+                        _ => "Disconnected from Vector. Data shown may be out of date.".to_string(),
+                    }}
+                </span>
+                <Show when=move || app_state.connection_state.get() == ConnectionState::Disconnected>
+                    <button
+                        class="px-2 py-0.5 rounded border border-warning/50 hover:bg-warning/20"
+                        on:click=move |_| retry_state.with_value(|state| state.reconnect_now())
+                    >
+                        "Retry"
+                    </button>
+                </Show>
+            </div>
+        </Show>
+    }
+}
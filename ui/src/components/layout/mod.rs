@@ -8,6 +8,8 @@
 //! - `BottomPanel` - Resizable panel with tabs for data preview, logs, etc.
 //! - `DataPreviewPanel` - Data preview tab content with toolbar
 //! - `StatusBar` - Connection status and quick stats
+//! - `ConnectionBanner` - Warning shown while the connection to Vector is down
+//! - `UpdateRequiredBanner` - Error shown when the UI and server protocol versions differ
 
 mod app_shell;
 mod main_tabs;
@@ -15,9 +17,13 @@ mod sidebar;
 mod top_bar;
 mod bottom_panel;
 mod status_bar;
+mod connection_banner;
+mod update_banner;
 
 pub use app_shell::AppShell;
 pub use main_tabs::MainTabs;
 pub use sidebar::PipelineSidebar;
 pub use bottom_panel::{BottomPanel, DataPreviewPanel};
 pub use status_bar::StatusBar;
+pub use connection_banner::ConnectionBanner;
+pub use update_banner::UpdateRequiredBanner;
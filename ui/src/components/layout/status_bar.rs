@@ -4,6 +4,7 @@
 
 use leptos::*;
 
+use crate::client::ConnectionState;
 use crate::state::AppState;
 
 /// Status bar at the bottom of the screen
@@ -35,14 +36,21 @@ pub fn StatusBar() -> impl IntoView {
                     <div class=move || {
                         let base = "w-2 h-2 rounded-full";
                         if app_state.connected.get() {
-                            format!("{} bg-success", base)
+                            match app_state.connection_state.get() {
+                                ConnectionState::Connected => format!("{} bg-success", base),
+                                _ => format!("{} bg-warning", base),
+                            }
                         } else {
                             format!("{} bg-theme-muted", base)
                         }
                     } />
                     <span>
                         {move || if app_state.connected.get() {
-                            "Connected"
+                            match app_state.connection_state.get() {
+                                ConnectionState::Connected => "Connected",
+                                ConnectionState::Reconnecting { .. } => "Reconnecting",
+                                ConnectionState::Disconnected => "Connection lost",
+                            }
                         } else {
                             "Disconnected"
                         }}
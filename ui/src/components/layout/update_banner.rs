@@ -0,0 +1,35 @@
+//! Update Required Banner Component
+//!
+//! Banner shown when the control plane rejects this UI's protocol version,
+//! since responses may be mis-parsed until the UI or server is updated.
+
+use leptos::*;
+
+use crate::state::AppState;
+
+/// Banner for a UI/server protocol mismatch (hidden when compatible)
+#[component]
+pub fn UpdateRequiredBanner() -> impl IntoView {
+    let app_state = expect_context::<AppState>();
+    
+    view! {
+        <Show when=move || app_state.update_required.get().is_some()>
+            <div class="flex items-center justify-between gap-3 px-4 py-2 bg-error/10 border-b border-error/30 text-sm text-error">
+                <span>
+                    "Update required: "
+                    {move || app_state.update_required.get().unwrap_or_default()}
+                </span>
+                <button
+                    class="px-2 py-0.5 rounded border border-error/50 hover:bg-error/20"
+                    on:click=move |_| {
+                        if let Some(window) = web_sys::window() {
+                            let _ = window.location().reload();
+                        }
+                    }
+                >
+                    "Reload"
+                </button>
+            </div>
+        </Show>
+    }
+}
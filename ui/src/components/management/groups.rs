@@ -1342,32 +1342,60 @@ async fn deploy_to_group(group_id: &str) -> Result<(), String> {
     }
 }
 
+/// Someone else saved the config after the editor loaded it
+#[derive(Clone)]
+struct ConfigConflict {
+    current_version: String,
+    /// Their changes, as a diff from the version the editor loaded
+    diff: String,
+}
+
+/// Why a config save failed
+enum SaveConfigError {
+    Conflict(ConfigConflict),
+    Failed(String),
+}
+
 /// Update group config via API
-async fn update_group_config(group_id: &str, config: &str) -> Result<String, String> {
-    let window = web_sys::window().ok_or("No window")?;
-    let origin = window.location().origin().map_err(|_| "No origin")?;
+///
+/// With a `base_version`, the server refuses the write if the config has
+/// changed since that version.
+async fn update_group_config(group_id: &str, config: &str, base_version: Option<&str>) -> Result<String, SaveConfigError> {
+    let window = web_sys::window().ok_or(SaveConfigError::Failed("No window".to_string()))?;
+    let origin = window.location().origin().map_err(|_| SaveConfigError::Failed("No origin".to_string()))?;
     
-    let body = serde_json::json!({ "config": config });
+    let body = serde_json::json!({ "config": config, "base_version": base_version });
     
     let response = gloo_net::http::Request::put(&format!("{}/api/v1/groups/{}/config", origin, group_id))
         .header("Content-Type", "application/json")
         .body(body.to_string())
-        .map_err(|e| format!("Body error: {}", e))?
+        .map_err(|e| SaveConfigError::Failed(format!("Body error: {}", e)))?
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| SaveConfigError::Failed(format!("Request failed: {}", e)))?;
     
-    if response.ok() {
-        let result: serde_json::Value = response.json().await.map_err(|e| format!("Parse: {}", e))?;
+    let status = response.status();
+    let result: serde_json::Value = response.json().await
+        .map_err(|e| SaveConfigError::Failed(format!("Parse: {}", e)))?;
+    if (200..300).contains(&status) {
         Ok(result.get("version").and_then(|v| v.as_str()).unwrap_or("").to_string())
+    } else if let (409, Some(current_version)) = (status, result["error"]["details"]["current_version"].as_str()) {
+        Err(SaveConfigError::Conflict(ConfigConflict {
+            current_version: current_version.to_string(),
+            diff: result["error"]["details"]["diff"].as_str().unwrap_or("").to_string(),
+        }))
     } else {
-        let result: serde_json::Value = response.json().await.map_err(|e| format!("Parse: {}", e))?;
-        Err(result.get("message").and_then(|v| v.as_str()).unwrap_or("Update failed").to_string())
+        Err(SaveConfigError::Failed(result["error"]["message"].as_str().unwrap_or("Update failed").to_string()))
     }
 }
 
 /// Fetch current group config
 async fn fetch_group_config(group_id: &str) -> Result<String, String> {
+    fetch_group_config_versioned(group_id).await.map(|(config, _)| config)
+}
+
+/// Fetch current group config and the version it was read at
+async fn fetch_group_config_versioned(group_id: &str) -> Result<(String, Option<String>), String> {
     let window = web_sys::window().ok_or("No window")?;
     let origin = window.location().origin().map_err(|_| "No origin")?;
     
@@ -1378,7 +1406,10 @@ async fn fetch_group_config(group_id: &str) -> Result<String, String> {
     
     if response.ok() {
         let result: serde_json::Value = response.json().await.map_err(|e| format!("Parse: {}", e))?;
-        Ok(result.get("config").and_then(|v| v.as_str()).unwrap_or("").to_string())
+        Ok((
+            result.get("config").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+            result.get("version").and_then(|v| v.as_str()).map(String::from),
+        ))
     } else {
         Err("Failed to fetch config".to_string())
     }
@@ -1396,21 +1427,34 @@ pub fn ConfigEditor(
     let (loading, set_loading) = create_signal(true);
     let (saving, set_saving) = create_signal(false);
     let (error, set_error) = create_signal(Option::<String>::None);
+    // Version the editor loaded; saves are refused if the config moved on since
+    let (base_version, set_base_version) = create_signal(Option::<String>::None);
+    let (conflict, set_conflict) = create_signal(Option::<ConfigConflict>::None);
     
-    let group_id_clone = group_id.clone();
+    let load_config = {
+        let group_id = group_id.clone();
+        move || {
+            let gid = group_id.clone();
+            spawn_local(async move {
+                set_loading.set(true);
+                match fetch_group_config_versioned(&gid).await {
+                    Ok((cfg, version)) => {
+                        set_config.set(cfg);
+                        set_base_version.set(version);
+                        set_conflict.set(None);
+                    }
+                    Err(e) => set_error.set(Some(e)),
+                }
+                set_loading.set(false);
+            });
+        }
+    };
     
     // Load config on mount
-    create_effect(move |_| {
-        let gid = group_id_clone.clone();
-        spawn_local(async move {
-            set_loading.set(true);
-            match fetch_group_config(&gid).await {
-                Ok(cfg) => set_config.set(cfg),
-                Err(e) => set_error.set(Some(e)),
-            }
-            set_loading.set(false);
-        });
-    });
+    {
+        let load_config = load_config.clone();
+        create_effect(move |_| load_config());
+    }
     
     // Validate on config change (debounced)
     let validate_config_fn = {
@@ -1446,6 +1490,7 @@ pub fn ConfigEditor(
                         if loading.get() {
                             view! { <div class="text-theme-secondary">"Loading..."</div> }.into_view()
                         } else {
+                            let load_config = load_config.clone();
                             view! {
                                 <div class="flex-1 flex flex-col min-h-0">
                                     // Editor
@@ -1519,6 +1564,39 @@ pub fn ConfigEditor(
                                         }
                                     })}
                                     
+                                    // Someone else saved first: show their changes
+                                    {move || conflict.get().map(|c| {
+                                        let load_config = load_config.clone();
+                                        let current_version = c.current_version.clone();
+                                        view! {
+                                            <div class="mt-4 p-3 bg-amber-500/10 border border-amber-500/30 rounded-lg text-amber-400 text-sm space-y-2">
+                                                <div class="font-medium flex items-center gap-2">
+                                                    <span class="status-dot degraded"></span>
+                                                    "This configuration was changed by someone else since you opened it. Their changes:"
+                                                </div>
+                                                <pre class="max-h-40 overflow-auto p-2 bg-theme-bg rounded font-mono text-xs text-theme">{c.diff.clone()}</pre>
+                                                <div class="flex gap-2">
+                                                    <button
+                                                        class="btn-secondary"
+                                                        on:click=move |_| load_config()
+                                                    >
+                                                        "Discard mine and load theirs"
+                                                    </button>
+                                                    <button
+                                                        class="btn-secondary"
+                                                        on:click=move |_| {
+                                                            // Save again on top of their version
+                                                            set_base_version.set(Some(current_version.clone()));
+                                                            set_conflict.set(None);
+                                                        }
+                                                    >
+                                                        "Keep mine"
+                                                    </button>
+                                                </div>
+                                            </div>
+                                        }
+                                    })}
+                                    
                                     // Error message
                                     {move || error.get().map(|e| view! {
                                         <div class="mt-4 p-3 bg-red-500/10 border border-red-500/30 rounded-lg text-red-400 text-sm flex items-center gap-2">
@@ -1543,22 +1621,26 @@ pub fn ConfigEditor(
                     <button
                         class="btn-primary disabled:opacity-50 disabled:cursor-not-allowed"
                         disabled=move || {
-                            saving.get() || validation.get().map(|v| !v.valid).unwrap_or(true)
+                            saving.get() || conflict.get().is_some() || validation.get().map(|v| !v.valid).unwrap_or(true)
                         }
                         on:click={
                             let gid = group_id_for_save.clone();
                             move |_| {
                                 let gid = gid.clone();
                                 let cfg = config.get();
+                                let base = base_version.get();
                                 set_saving.set(true);
                                 set_error.set(None);
                                 
                                 spawn_local(async move {
-                                    match update_group_config(&gid, &cfg).await {
+                                    match update_group_config(&gid, &cfg, base.as_deref()).await {
                                         Ok(version) => {
                                             on_save.call(version);
                                         }
-                                        Err(e) => {
+                                        Err(SaveConfigError::Conflict(c)) => {
+                                            set_conflict.set(Some(c));
+                                        }
+                                        Err(SaveConfigError::Failed(e)) => {
                                             set_error.set(Some(e));
                                         }
                                     }
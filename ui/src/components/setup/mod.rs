@@ -9,6 +9,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SetupStatus {
     pub is_setup: bool,
+    #[serde(default)]
+    pub requires_bootstrap_token: bool,
     pub version: String,
 }
 
@@ -18,6 +20,8 @@ pub struct SetupInitRequest {
     pub username: String,
     pub email: String,
     pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bootstrap_token: Option<String>,
 }
 
 /// Setup init response
@@ -56,7 +60,13 @@ async fn submit_setup(request: SetupInitRequest) -> Result<SetupInitResponse, St
         .await
         .map_err(|e| e.to_string())?;
     
-    response.json().await.map_err(|e| e.to_string())
+    if response.ok() {
+        response.json().await.map_err(|e| e.to_string())
+    } else {
+        // Errors come back as { "error": { "code", "message", "details" } }
+        let body: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
+        Err(body["error"]["message"].as_str().unwrap_or("Unknown error").to_string())
+    }
 }
 
 /// Setup wizard component
@@ -66,6 +76,7 @@ pub fn SetupWizard() -> impl IntoView {
     let (email, set_email) = create_signal(String::new());
     let (password, set_password) = create_signal(String::new());
     let (confirm_password, set_confirm_password) = create_signal(String::new());
+    let (bootstrap_token, set_bootstrap_token) = create_signal(String::new());
     let (error, set_error) = create_signal(Option::<String>::None);
     let (loading, set_loading) = create_signal(false);
     let (setup_complete, set_setup_complete) = create_signal(false);
@@ -82,6 +93,7 @@ pub fn SetupWizard() -> impl IntoView {
         let email_val = email.get();
         let password_val = password.get();
         let confirm_val = confirm_password.get();
+        let token_val = bootstrap_token.get();
         
         // Validate
         if username_val.len() < 3 {
@@ -112,6 +124,7 @@ pub fn SetupWizard() -> impl IntoView {
                 username: username_val,
                 email: email_val,
                 password: password_val,
+                bootstrap_token: (!token_val.trim().is_empty()).then_some(token_val),
             };
             
             match submit_setup(request).await {
@@ -174,8 +187,9 @@ pub fn SetupWizard() -> impl IntoView {
                                     </div>
                                 }.into_view()
                             }
-                            Some(Ok(_)) => {
+                            Some(Ok(s)) => {
                                 // Show setup form
+                                let requires_token = s.requires_bootstrap_token;
                                 view! {
                                     <div class="bg-gray-800 rounded-lg p-6 shadow-lg">
                                         <h2 class="text-xl font-semibold text-white mb-6">"Create Admin Account"</h2>
@@ -235,6 +249,20 @@ pub fn SetupWizard() -> impl IntoView {
                                                 />
                                             </div>
                                             
+                                            {requires_token.then(|| view! {
+                                                <div>
+                                                    <label class="block text-gray-300 text-sm mb-1">"Bootstrap Token"</label>
+                                                    <input
+                                                        type="password"
+                                                        class="w-full bg-gray-700 text-white px-4 py-2 rounded border border-gray-600 focus:border-blue-500 focus:outline-none"
+                                                        placeholder="VECTORIZE_BOOTSTRAP_TOKEN"
+                                                        prop:value=bootstrap_token
+                                                        on:input=move |ev| set_bootstrap_token.set(event_target_value(&ev))
+                                                        required
+                                                    />
+                                                </div>
+                                            })}
+                                            
                                             <button
                                                 type="submit"
                                                 class="w-full bg-blue-600 hover:bg-blue-700 disabled:bg-gray-600 text-white py-2 rounded transition"
@@ -2,9 +2,19 @@
 //!
 //! Provides UI components for viewing live data samples from Vector agents.
 //! Supports both REST-based sampling and WebSocket streaming.
+//!
+//! Sensitive fields are redacted with the control plane's rules plus any
+//! added for this browser session, before events are shown, downloaded or
+//! copied.
 
+use gloo_storage::{SessionStorage, Storage};
 use leptos::*;
 use serde::{Deserialize, Serialize};
+use vectorize_shared::redaction::RedactionRules;
+use web_sys::wasm_bindgen::JsCast;
+
+/// Session storage key for extra redaction fields
+const SESSION_REDACT_KEY: &str = "vectorize.tap.redact_fields";
 
 /// Sampled event from Vector
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -106,6 +116,24 @@ async fn check_rate_limit(agent_id: &str) -> Result<RateLimitStatus, String> {
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
     
+    // 429 still carries the status, with can_sample = false
+    if response.ok() || response.status() == 429 {
+        response.json().await.map_err(|e| format!("Parse failed: {}", e))
+    } else {
+        Err(format!("API error: {}", response.status()))
+    }
+}
+
+/// Fetch the control plane's redaction rules
+async fn fetch_redaction_rules() -> Result<RedactionRules, String> {
+    let window = web_sys::window().ok_or("No window")?;
+    let origin = window.location().origin().map_err(|_| "No origin")?;
+    
+    let response = gloo_net::http::Request::get(&format!("{}/api/v1/tap/redaction", origin))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    
     if response.ok() {
         response.json().await.map_err(|e| format!("Parse failed: {}", e))
     } else {
@@ -113,6 +141,60 @@ async fn check_rate_limit(agent_id: &str) -> Result<RateLimitStatus, String> {
     }
 }
 
+/// Server rules plus the session's extra comma-separated fields
+fn combined_rules(server: &RedactionRules, session_fields: &str) -> RedactionRules {
+    RedactionRules::new(
+        server.fields.iter()
+            .map(String::as_str)
+            .chain(session_fields.split(','))
+    )
+}
+
+/// Apply redaction rules to sampled events
+fn redact_events(events: &[SampledEvent], rules: &RedactionRules) -> Vec<SampledEvent> {
+    events.iter()
+        .map(|e| SampledEvent { event: rules.redacted(&e.event), ..e.clone() })
+        .collect()
+}
+
+/// Copy text to clipboard
+fn copy_to_clipboard(text: &str) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.navigator().clipboard().write_text(text);
+    }
+}
+
+/// Trigger a download of JSON text
+fn download_json(json: &str, filename: &str) {
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+        return;
+    };
+    
+    let blob_parts = js_sys::Array::new();
+    blob_parts.push(&wasm_bindgen::JsValue::from_str(json));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/json");
+    
+    if let Ok(blob) = web_sys::Blob::new_with_str_sequence_and_options(&blob_parts, &options) {
+        if let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) {
+            if let Ok(a) = document.create_element("a") {
+                let _ = a.set_attribute("href", &url);
+                let _ = a.set_attribute("download", filename);
+                
+                if let Some(body) = document.body() {
+                    let _ = body.append_child(&a);
+                    if let Some(html_a) = a.dyn_ref::<web_sys::HtmlElement>() {
+                        html_a.click();
+                    }
+                    let _ = body.remove_child(&a);
+                }
+                
+                let _ = web_sys::Url::revoke_object_url(&url);
+            }
+        }
+    }
+}
+
 /// Sample events from an agent via REST API
 async fn sample_events(agent_id: &str, patterns: &str, limit: u32) -> Result<SampleResponse, String> {
     let window = web_sys::window().ok_or("No window")?;
@@ -151,13 +233,26 @@ pub fn TapViewer() -> impl IntoView {
     // Reserved for WebSocket streaming mode
     let (_sampling_active, _set_sampling_active) = create_signal(false);
     
-    // Fetch agents on mount
+    // Redaction: default rules until the server's arrive, plus session fields
+    let (server_rules, set_server_rules) = create_signal(RedactionRules::default());
+    let (session_fields, set_session_fields) = create_signal(
+        SessionStorage::get::<String>(SESSION_REDACT_KEY).unwrap_or_default()
+    );
+    let redaction = create_memo(move |_| combined_rules(&server_rules.get(), &session_fields.get()));
+    // Everything shown, downloaded or copied goes through this
+    let visible_events = create_memo(move |_| redact_events(&events.get(), &redaction.get()));
+    
+    // Fetch agents and redaction rules on mount
     create_effect(move |_| {
         spawn_local(async move {
             match fetch_agents().await {
                 Ok(a) => set_agents.set(a),
                 Err(e) => set_error.set(Some(e)),
             }
+            match fetch_redaction_rules().await {
+                Ok(rules) => set_server_rules.set(rules),
+                Err(e) => web_sys::console::warn_1(&format!("Using default redaction rules: {}", e).into()),
+            }
         });
     });
     
@@ -258,8 +353,10 @@ pub fn TapViewer() -> impl IntoView {
                                     spawn_local(async move {
                                         match sample_events(&agent_id, &pattern, limit).await {
                                             Ok(response) => {
+                                                // Raw values are never kept
+                                                let sampled = redact_events(&response.events, &redaction.get_untracked());
                                                 set_events.update(|e| {
-                                                    e.extend(response.events);
+                                                    e.extend(sampled);
                                                     // Keep only last 100 events
                                                     if e.len() > 100 {
                                                         e.drain(0..e.len()-100);
@@ -293,6 +390,28 @@ pub fn TapViewer() -> impl IntoView {
                     </div>
                 })}
                 
+                // Session redaction fields
+                <div class="mt-3">
+                    <label class="block text-xs font-medium text-slate-400 mb-1">
+                        "Also redact (this session)"
+                    </label>
+                    <input
+                        type="text"
+                        class="w-full px-3 py-2 bg-slate-900 border border-slate-600 rounded text-sm focus:outline-none focus:border-blue-500"
+                        placeholder="user.email, *.session_id"
+                        prop:value=move || session_fields.get()
+                        on:input=move |ev| {
+                            let value = event_target_value(&ev);
+                            let _ = SessionStorage::set(SESSION_REDACT_KEY, &value);
+                            set_session_fields.set(value);
+                        }
+                    />
+                    <p class="mt-1 text-xs text-slate-500">
+                        "Always redacted: "
+                        {move || server_rules.get().fields.join(", ")}
+                    </p>
+                </div>
+                
                 // WebSocket info
                 {move || ws_info.get().map(|info| view! {
                     <div class="mt-2 p-2 bg-slate-900 rounded text-xs">
@@ -323,16 +442,40 @@ pub fn TapViewer() -> impl IntoView {
                             "("{move || events.get().len()}")"
                         </span>
                     </h3>
-                    <button
-                        class="px-3 py-1 text-xs bg-slate-700 hover:bg-slate-600 rounded"
-                        on:click=move |_| set_events.set(Vec::new())
-                    >
-                        "Clear"
-                    </button>
+                    <div class="flex items-center space-x-2">
+                        <button
+                            class="px-3 py-1 text-xs bg-slate-700 hover:bg-slate-600 disabled:opacity-50 rounded"
+                            disabled=move || events.get().is_empty()
+                            on:click=move |_| {
+                                if let Ok(json) = serde_json::to_string_pretty(&visible_events.get()) {
+                                    copy_to_clipboard(&json);
+                                }
+                            }
+                        >
+                            "Copy JSON"
+                        </button>
+                        <button
+                            class="px-3 py-1 text-xs bg-slate-700 hover:bg-slate-600 disabled:opacity-50 rounded"
+                            disabled=move || events.get().is_empty()
+                            on:click=move |_| {
+                                if let Ok(json) = serde_json::to_string_pretty(&visible_events.get()) {
+                                    download_json(&json, "sampled_events.json");
+                                }
+                            }
+                        >
+                            "Download JSON"
+                        </button>
+                        <button
+                            class="px-3 py-1 text-xs bg-slate-700 hover:bg-slate-600 rounded"
+                            on:click=move |_| set_events.set(Vec::new())
+                        >
+                            "Clear"
+                        </button>
+                    </div>
                 </div>
                 
                 {move || {
-                    let event_list = events.get();
+                    let event_list = visible_events.get();
                     if event_list.is_empty() {
                         view! {
                             <div class="text-center py-12 text-slate-500">
@@ -5,13 +5,17 @@
 //! - Pipeline state (loaded from Vector API, not localStorage)
 //! - Event buffers
 //! - Node execution status
+//! - Live agent status and deployment updates from the control plane
 //! - UI state (theme, sidebar, bottom panel)
 
 use leptos::*;
 use std::collections::HashMap;
 use vectorize_shared::{ConnectionMode, NodeEvent, NodeStatus, Pipeline, Topology};
 
-use crate::client::{DirectClient, VectorClient, VectorClientError};
+use crate::client::{
+    ConnectionState, ControlPlaneClient, DirectClient, LiveUpdate, LiveUpdatesHandle, VectorClient,
+    VectorClientError,
+};
 
 /// Maximum events to cache per node
 #[allow(dead_code)]
@@ -20,6 +24,9 @@ const MAX_EVENTS_PER_NODE: usize = 100;
 /// Default height for bottom panel
 const DEFAULT_BOTTOM_PANEL_HEIGHT: f64 = 256.0;
 
+/// How often the direct client checks that Vector is still reachable
+const HEALTH_WATCH_INTERVAL_MS: u32 = 10_000;
+
 /// Theme options
 #[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
 pub enum Theme {
@@ -51,12 +58,20 @@ pub struct AppState {
     /// Whether connected to a Vector instance or control plane
     pub connected: RwSignal<bool>,
     
+    /// Live state of the connection; `Reconnecting` while Vector is
+    /// unreachable (e.g. restarting), `connected` stays true meanwhile
+    pub connection_state: RwSignal<ConnectionState>,
+    
     /// Connection URL
     pub url: RwSignal<String>,
     
     /// Connection error message
     pub error: RwSignal<Option<String>>,
     
+    /// Set when the control plane speaks an incompatible protocol version;
+    /// holds the message telling the user which side to update
+    pub update_required: RwSignal<Option<String>>,
+    
     /// The active client (stored as a resource that can be updated)
     client: RwSignal<Option<DirectClient>>,
     
@@ -83,6 +98,23 @@ pub struct AppState {
     #[allow(dead_code)]
     pub config_panel_tab: RwSignal<ConfigPanelTab>,
     
+    // ========================================================================
+    // Control Plane Live Updates
+    // ========================================================================
+    
+    /// Latest pushed status per agent ID, overriding the last fetched list
+    pub agent_statuses: RwSignal<HashMap<String, String>>,
+    
+    /// Most recent deployment update pushed by the control plane
+    pub last_deployment_update: RwSignal<Option<LiveUpdate>>,
+    
+    /// Bumped on every live update; views showing fleet-wide aggregates
+    /// track it to refetch instead of polling
+    pub live_revision: RwSignal<u64>,
+    
+    /// The live-update subscription, kept open for the app's lifetime
+    live_updates: StoredValue<Option<LiveUpdatesHandle>>,
+    
     // ========================================================================
     // UI State
     // ========================================================================
@@ -144,8 +176,10 @@ impl AppState {
             // Connection state
             connection_mode: create_rw_signal(ConnectionMode::Direct),
             connected: create_rw_signal(false),
+            connection_state: create_rw_signal(ConnectionState::Disconnected),
             url: create_rw_signal(String::new()),
             error: create_rw_signal(None),
+            update_required: create_rw_signal(None),
             client: create_rw_signal(None),
             
             // Pipeline state
@@ -156,6 +190,12 @@ impl AppState {
             node_events: create_rw_signal(HashMap::new()),
             config_panel_tab: create_rw_signal(ConfigPanelTab::Settings),
             
+            // Control plane live updates
+            agent_statuses: create_rw_signal(HashMap::new()),
+            last_deployment_update: create_rw_signal(None),
+            live_revision: create_rw_signal(0),
+            live_updates: store_value(None),
+            
             // UI state
             theme: create_rw_signal(initial_theme),
             sidebar_collapsed: create_rw_signal(initial_sidebar_collapsed),
@@ -251,14 +291,14 @@ impl AppState {
     pub async fn connect_direct(&self, url: &str) -> Result<(), VectorClientError> {
         let client = DirectClient::new(url);
         
-        // Test connection by fetching health
-        client.health().await?;
-        
-        // Fetch initial topology
-        let topology = client.get_topology().await?;
-        
-        // Load the current pipeline configuration from Vector
-        let pipeline = client.fetch_pipeline().await?;
+        let (topology, pipeline) = match Self::load_initial(&client).await {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                // Don't keep retrying a connection that never came up
+                client.close();
+                return Err(e);
+            }
+        };
         web_sys::console::log_1(&format!(
             "Loaded pipeline from Vector: {} sources, {} transforms, {} sinks",
             pipeline.nodes.values().filter(|n| matches!(n.node_type, vectorize_shared::NodeType::Source(_))).count(),
@@ -266,17 +306,49 @@ impl AppState {
             pipeline.nodes.values().filter(|n| matches!(n.node_type, vectorize_shared::NodeType::Sink(_))).count(),
         ).into());
         
+        // Replace any previous client
+        if let Some(previous) = self.client.get_untracked() {
+            previous.close();
+        }
+        
+        // Mirror the client's connection state for the UI
+        let connection_state = self.connection_state;
+        client.on_state_change(move |state| connection_state.set(state));
+        client.watch(HEALTH_WATCH_INTERVAL_MS);
+        
         // Update state
         self.url.set(url.to_string());
         self.topology.set(Some(topology));
         self.pipeline.set(pipeline);  // Set the loaded pipeline
         self.connected.set(true);
+        self.connection_state.set(client.state());
         self.error.set(None);
         self.client.set(Some(client));
         
         Ok(())
     }
     
+    /// Check health, then fetch the topology and pipeline
+    async fn load_initial(client: &DirectClient) -> Result<(Topology, Pipeline), VectorClientError> {
+        // Test connection by fetching health
+        client.health().await?;
+        
+        // Fetch initial topology
+        let topology = client.get_topology().await?;
+        
+        // Load the current pipeline configuration from Vector
+        let pipeline = client.fetch_pipeline().await?;
+        
+        Ok((topology, pipeline))
+    }
+    
+    /// Retry the connection now instead of waiting for the next backoff step
+    pub fn reconnect_now(&self) {
+        if let Some(client) = self.client.get_untracked() {
+            client.reconnect_now();
+        }
+    }
+    
     /// Reload the pipeline from Vector (useful after external changes) (reserved for future)
     #[allow(dead_code)]
     pub async fn reload_pipeline(&self) -> Result<(), VectorClientError> {
@@ -288,9 +360,36 @@ impl AppState {
         Ok(())
     }
     
+    /// Subscribe to the control plane's live agent and deployment updates
+    ///
+    /// Replaces any previous subscription. The connection reconnects with
+    /// backoff on its own.
+    pub fn start_live_updates(&self, base_url: &str) {
+        let state = self.clone();
+        let handle = ControlPlaneClient::new(base_url)
+            .subscribe_live_updates(std::rc::Rc::new(move |update| state.apply_live_update(update)));
+        self.live_updates.set_value(Some(handle));
+    }
+    
+    /// Apply a pushed update to the global signals
+    pub fn apply_live_update(&self, update: LiveUpdate) {
+        if let LiveUpdate::AgentStatusChanged { agent_id, status, .. } = &update {
+            self.agent_statuses.update(|statuses| {
+                statuses.insert(agent_id.clone(), status.clone());
+            });
+        } else {
+            self.last_deployment_update.set(Some(update));
+        }
+        self.live_revision.update(|revision| *revision += 1);
+    }
+    
     /// Disconnect from the current connection
     pub fn disconnect(&self) {
+        if let Some(client) = self.client.get_untracked() {
+            client.close();
+        }
         self.connected.set(false);
+        self.connection_state.set(ConnectionState::Disconnected);
         self.client.set(None);
         self.topology.set(None);
         self.url.set(String::new());
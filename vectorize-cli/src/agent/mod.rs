@@ -3,13 +3,14 @@
 //! A lightweight agent that runs alongside Vector instances to:
 //! - Register with the Vectorize control plane
 //! - Pull configuration updates
-//! - Report health status
+//! - Send heartbeats with local Vector health
 //! - Enable remote management
 
 use std::path::PathBuf;
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
+use vectorize::request_id::{self, REQUEST_ID_HEADER};
 
 /// Agent configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +23,8 @@ pub struct AgentConfig {
     pub api_key: Option<String>,
     /// Worker group to join (optional - can be assigned by control plane)
     pub group: Option<String>,
+    /// Tags to register with (key/value pairs)
+    pub tags: Vec<(String, String)>,
     /// Vector API URL (local Vector instance)
     pub vector_url: String,
     /// Path to Vector config file (for writing updates)
@@ -39,6 +42,7 @@ impl Default for AgentConfig {
             name: None,
             api_key: None,
             group: None,
+            tags: Vec::new(),
             vector_url: "http://localhost:8686".to_string(),
             vector_config_path: PathBuf::from("/etc/vector/vector.toml"),
             health_interval: 30,
@@ -88,10 +92,15 @@ impl Agent {
         
         let url = format!("{}/api/v1/agents", self.config.control_plane_url);
         
+        let tags: serde_json::Map<String, serde_json::Value> = self.config.tags.iter()
+            .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+            .collect();
+        
         let body = serde_json::json!({
             "name": name,
             "url": self.config.vector_url,
             "group_id": self.config.group,
+            "tags": tags,
         });
         
         let mut request = self.client.post(&url).json(&body);
@@ -166,15 +175,19 @@ impl Agent {
     }
     
     /// Check for config updates and apply if needed
+    ///
+    /// Every call of one check shares a request ID, so the control plane's
+    /// logs for it can be matched with ours.
     pub async fn check_config_update(&mut self) -> Result<bool, AgentError> {
         let agent_id = self.agent_id.as_ref().ok_or_else(|| {
             AgentError::NotRegistered
         })?;
+        let request_id = request_id::generate();
         
         // Get agent info to find group
         let url = format!("{}/api/v1/agents/{}", self.config.control_plane_url, agent_id);
         
-        let mut request = self.client.get(&url);
+        let mut request = self.client.get(&url).header(REQUEST_ID_HEADER, &request_id);
         if let Some(ref api_key) = self.config.api_key {
             request = request.header("Authorization", format!("Bearer {}", api_key));
         }
@@ -202,7 +215,7 @@ impl Agent {
         // Fetch group config
         let url = format!("{}/api/v1/groups/{}/config", self.config.control_plane_url, group_id);
         
-        let mut request = self.client.get(&url);
+        let mut request = self.client.get(&url).header(REQUEST_ID_HEADER, &request_id);
         if let Some(ref api_key) = self.config.api_key {
             request = request.header("Authorization", format!("Bearer {}", api_key));
         }
@@ -212,9 +225,16 @@ impl Agent {
         })?;
         
         if !response.status().is_success() {
-            return Err(AgentError::Network("Failed to fetch group config".to_string()));
+            return Err(AgentError::Network(format!("Failed to fetch group config (request {})", request_id)));
         }
         
+        // The control plane echoes the ID it logged the request under
+        let request_id = response.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .unwrap_or(request_id);
+        
         let config_response: serde_json::Value = response.json().await.map_err(|e| {
             AgentError::Parse(format!("Failed to parse config: {}", e))
         })?;
@@ -228,13 +248,20 @@ impl Agent {
         }
         
         if let Some(config) = new_config {
-            info!("New configuration available (version: {:?})", new_version);
+            info!("New configuration available (version: {:?}, request {})", new_version, request_id);
             
             // Write new config to file
-            std::fs::write(&self.config.vector_config_path, config).map_err(|e| {
-                AgentError::ConfigWrite(format!("Failed to write config: {}", e))
-            })?;
+            if let Err(e) = std::fs::write(&self.config.vector_config_path, config) {
+                let error = format!("Failed to write config: {}", e);
+                if let Some(version) = new_version {
+                    self.ack_config(version, Some(&error), &request_id).await;
+                }
+                return Err(AgentError::ConfigWrite(error));
+            }
             
+            if let Some(version) = new_version {
+                self.ack_config(version, None, &request_id).await;
+            }
             self.current_config_version = new_version.map(|s| s.to_string());
             
             info!("Configuration updated successfully. Vector will auto-reload via --watch-config");
@@ -244,15 +271,57 @@ impl Agent {
         }
     }
     
-    /// Report health status to control plane
+    /// Tell the control plane whether a pulled config version was applied
+    ///
+    /// Settles deployments that recorded this agent as "Pending sync". A
+    /// failed ack is only logged; the config itself is already in place.
+    async fn ack_config(&self, version: &str, error: Option<&str>, request_id: &str) {
+        let Some(agent_id) = self.agent_id.as_ref() else {
+            return;
+        };
+        let url = format!("{}/api/v1/agents/{}/config/ack", self.config.control_plane_url, agent_id);
+        
+        let mut request = self.client.post(&url).json(&serde_json::json!({
+            "version": version,
+            "success": error.is_none(),
+            "error": error,
+        })).header(REQUEST_ID_HEADER, request_id);
+        if let Some(ref api_key) = self.config.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => warn!("Config acknowledgement rejected: {}", response.status()),
+            Err(e) => warn!("Failed to acknowledge config {} (request {}): {}", version, request_id, e),
+        }
+    }
+    
+    /// Send a heartbeat with local Vector health to the control plane
+    ///
+    /// Keeps the agent from being marked unreachable when the control plane
+    /// can't poll it directly.
     pub async fn report_health(&self) -> Result<(), AgentError> {
+        let agent_id = self.agent_id.as_ref().ok_or(AgentError::NotRegistered)?;
+        
         // Check Vector health
         let vector_health = self.check_vector_health().await;
+        debug!("Vector health: {:?}", vector_health);
         
-        info!("Vector health: {:?}", vector_health);
+        let url = format!("{}/api/v1/agents/{}/heartbeat", self.config.control_plane_url, agent_id);
+        let mut request = self.client.post(&url).json(&serde_json::json!({
+            "vector_health": vector_health,
+        }));
+        if let Some(ref api_key) = self.config.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
         
-        // In a full implementation, we'd POST this to the control plane
-        // For now, the control plane polls agents directly
+        let response = request.send().await.map_err(|e| {
+            AgentError::Network(format!("Failed to send heartbeat: {}", e))
+        })?;
+        if !response.status().is_success() {
+            return Err(AgentError::Network(format!("Heartbeat rejected: {}", response.status())));
+        }
         
         Ok(())
     }
@@ -307,7 +376,8 @@ impl Agent {
 }
 
 /// Vector health status
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum VectorHealth {
     Healthy,
     Unhealthy,
@@ -0,0 +1,419 @@
+//! HTTP clients for agents' Vector APIs
+//!
+//! Agents whose API sits behind mutual TLS carry their own CA certificate,
+//! client certificate and key. The key is stored sealed with
+//! [`SecretCipher`]. Each agent with TLS settings gets a dedicated
+//! `reqwest::Client`, built once and rebuilt when its settings change;
+//! agents without TLS settings share the default client.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::crypto::{SecretCipher, ENCRYPTION_KEY_ENV};
+use crate::db::models::Agent;
+use crate::db::repository::AgentRepository;
+use crate::db::DbPool;
+
+/// TLS settings for connecting to an agent's Vector API
+#[derive(Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+pub struct AgentTls {
+    /// CA certificate (PEM) that signed the agent's server certificate
+    pub ca_cert: Option<String>,
+    /// Client certificate (PEM) presented to the agent
+    pub client_cert: Option<String>,
+    /// Client private key (PKCS#8 PEM) for `client_cert`
+    pub client_key: Option<String>,
+    /// Accept any server certificate (development only)
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+impl AgentTls {
+    /// The TLS settings stored on an agent, if any are set, with the client
+    /// key decrypted
+    ///
+    /// Fails if the key is sealed and `cipher` is None or can't open it. A key
+    /// stored before keys were sealed is returned as is.
+    pub fn from_agent(agent: &Agent, cipher: Option<&SecretCipher>) -> Result<Option<Self>, String> {
+        let mut tls = Self::stored(agent);
+        if tls.is_empty() {
+            return Ok(None);
+        }
+        if let Some(sealed) = tls.client_key.take().filter(|key| SecretCipher::is_sealed(key)) {
+            let cipher = cipher.ok_or_else(|| format!(
+                "agent {} has an encrypted TLS client key but {} is not set",
+                agent.id, ENCRYPTION_KEY_ENV
+            ))?;
+            tls.client_key = Some(cipher.decrypt(&sealed)
+                .map_err(|e| format!("agent {} TLS client key: {}", agent.id, e))?);
+        } else {
+            tls.client_key = agent.tls_client_key.clone();
+        }
+        Ok(Some(tls))
+    }
+    
+    /// Whether an agent has any TLS settings stored
+    pub fn is_configured(agent: &Agent) -> bool {
+        !Self::stored(agent).is_empty()
+    }
+    
+    /// The settings exactly as stored on the agent
+    fn stored(agent: &Agent) -> Self {
+        Self {
+            ca_cert: agent.tls_ca_cert.clone(),
+            client_cert: agent.tls_client_cert.clone(),
+            client_key: agent.tls_client_key.clone(),
+            insecure_skip_verify: agent.tls_insecure_skip_verify,
+        }
+    }
+    
+    /// These settings with the client key encrypted for storage
+    ///
+    /// Refuses to store a client key when no cipher is configured, so keys
+    /// never reach the database in plaintext.
+    pub fn seal(&self, cipher: Option<&SecretCipher>) -> Result<Self, String> {
+        let client_key = match &self.client_key {
+            Some(key) => {
+                let cipher = cipher.ok_or_else(|| {
+                    format!("{} must be set to store agent TLS client keys", ENCRYPTION_KEY_ENV)
+                })?;
+                Some(cipher.encrypt(key).map_err(|e| e.to_string())?)
+            }
+            None => None,
+        };
+        Ok(Self { client_key, ..self.clone() })
+    }
+    
+    /// Hash of the settings, to tell when an agent's client must be rebuilt
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+    
+    /// Whether no setting differs from a plain HTTPS connection
+    pub fn is_empty(&self) -> bool {
+        self.ca_cert.is_none()
+            && self.client_cert.is_none()
+            && self.client_key.is_none()
+            && !self.insecure_skip_verify
+    }
+    
+    /// Build a client using these settings
+    ///
+    /// Fails if the PEM material doesn't parse, or if only one of the client
+    /// certificate and key is set.
+    pub fn build_client(&self, timeout: Option<Duration>) -> Result<reqwest::Client, String> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+    
+        if let Some(ca_cert) = &self.ca_cert {
+            let ca_cert = reqwest::Certificate::from_pem(ca_cert.as_bytes())
+                .map_err(|e| format!("Invalid CA certificate: {}", e))?;
+            builder = builder.add_root_certificate(ca_cert);
+        }
+    
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                let identity = reqwest::Identity::from_pkcs8_pem(cert.as_bytes(), key.as_bytes())
+                    .map_err(|e| format!("Invalid client certificate or key: {}", e))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => return Err("Client certificate and key must be set together".to_string()),
+        }
+    
+        if self.insecure_skip_verify {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+    
+        builder.build().map_err(|e| format!("Failed to build TLS client: {}", e))
+    }
+}
+
+impl std::fmt::Debug for AgentTls {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentTls")
+            .field("ca_cert", &self.ca_cert.is_some())
+            .field("client_cert", &self.client_cert.is_some())
+            .field("client_key", &self.client_key.as_ref().map(|_| "<redacted>"))
+            .field("insecure_skip_verify", &self.insecure_skip_verify)
+            .finish()
+    }
+}
+
+/// Seal TLS client keys stored in plaintext before keys were encrypted
+///
+/// Run at startup. Fails if a sealed key exists but no cipher is given, so a
+/// missing `VECTORIZE_ENCRYPTION_KEY` stops startup rather than breaking
+/// connections to those agents. Without a cipher, plaintext keys are left
+/// as they are with a warning. Returns the number of keys sealed.
+pub async fn seal_stored_keys(pool: &DbPool, cipher: Option<&SecretCipher>) -> Result<usize, String> {
+    let agents = AgentRepository::list_with_tls_client_key(pool).await
+        .map_err(|e| format!("Failed to load agent TLS settings: {}", e))?;
+    
+    let mut sealed = 0;
+    for agent in agents {
+        let Some(key) = agent.tls_client_key.as_deref() else { continue };
+        match (SecretCipher::is_sealed(key), cipher) {
+            (true, Some(_)) => {}
+            (true, None) => return Err(format!(
+                "Agent {} has an encrypted TLS client key but {} is not set",
+                agent.id, ENCRYPTION_KEY_ENV
+            )),
+            (false, Some(cipher)) => {
+                let key = cipher.encrypt(key).map_err(|e| e.to_string())?;
+                AgentRepository::set_tls_client_key(pool, &agent.id, &key).await
+                    .map_err(|e| format!("Failed to seal TLS client key for agent {}: {}", agent.id, e))?;
+                sealed += 1;
+            }
+            (false, None) => warn!(
+                "Agent {} has a plaintext TLS client key; set {} to encrypt it",
+                agent.id, ENCRYPTION_KEY_ENV
+            ),
+        }
+    }
+    if sealed > 0 {
+        info!("Encrypted {} stored agent TLS client key(s)", sealed);
+    }
+    Ok(sealed)
+}
+
+/// Hands out the right HTTP client for each agent
+pub struct AgentClients {
+    default: reqwest::Client,
+    timeout: Option<Duration>,
+    /// Opens sealed client keys
+    cipher: Option<SecretCipher>,
+    /// Clients for agents with TLS settings, keyed by agent ID, with the
+    /// fingerprint of the settings each was built from
+    cache: Mutex<HashMap<String, (u64, reqwest::Client)>>,
+}
+
+impl AgentClients {
+    /// Use `default` for agents without TLS settings
+    pub fn new(default: reqwest::Client) -> Self {
+        Self {
+            default,
+            timeout: None,
+            cipher: None,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+    
+    /// Decrypt agents' TLS client keys with `cipher`
+    pub fn with_cipher(mut self, cipher: Option<SecretCipher>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+    
+    /// Apply a request timeout to the per-agent TLS clients
+    ///
+    /// The default client keeps whatever timeout it was built with.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    
+    /// The client to use for an agent
+    ///
+    /// Agents with TLS settings that fail to load fall back to the default
+    /// client, so their requests fail the way they did before the settings
+    /// were added. A client built from an agent's earlier settings is
+    /// replaced.
+    pub fn for_agent(&self, agent: &Agent) -> reqwest::Client {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let tls = match AgentTls::from_agent(agent, self.cipher.as_ref()) {
+            Ok(Some(tls)) => tls,
+            Ok(None) => {
+                cache.remove(&agent.id);
+                return self.default.clone();
+            }
+            Err(e) => {
+                cache.remove(&agent.id);
+                warn!("Ignoring TLS settings for agent {}: {}", agent.id, e);
+                return self.default.clone();
+            }
+        };
+    
+        let fingerprint = tls.fingerprint();
+        if let Some((cached, client)) = cache.get(&agent.id) {
+            if *cached == fingerprint {
+                return client.clone();
+            }
+        }
+    
+        match tls.build_client(self.timeout) {
+            Ok(client) => {
+                cache.insert(agent.id.clone(), (fingerprint, client.clone()));
+                client
+            }
+            Err(e) => {
+                cache.remove(&agent.id);
+                warn!("Ignoring TLS settings for agent {}: {}", agent.id, e);
+                self.default.clone()
+            }
+        }
+    }
+    
+    /// Number of cached TLS clients
+    pub fn cached(&self) -> usize {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl Default for AgentClients {
+    fn default() -> Self {
+        Self::new(reqwest::Client::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const CA_CERT: &str = include_str!("../tests/fixtures/agent_tls_ca.pem");
+    const CLIENT_CERT: &str = include_str!("../tests/fixtures/agent_tls_client.pem");
+    const CLIENT_KEY: &str = include_str!("../tests/fixtures/agent_tls_client_key.pem");
+    
+    fn mtls() -> AgentTls {
+        AgentTls {
+            ca_cert: Some(CA_CERT.to_string()),
+            client_cert: Some(CLIENT_CERT.to_string()),
+            client_key: Some(CLIENT_KEY.to_string()),
+            insecure_skip_verify: false,
+        }
+    }
+    
+    fn cipher() -> SecretCipher {
+        SecretCipher::from_base64(&SecretCipher::generate_key()).unwrap()
+    }
+    
+    fn agent(tls: &AgentTls) -> Agent {
+        agent_with_id("agent-1", tls)
+    }
+    
+    fn agent_with_id(id: &str, tls: &AgentTls) -> Agent {
+        Agent {
+            id: id.to_string(),
+            name: id.to_string(),
+            url: format!("https://{}:8686", id),
+            group_id: None,
+            status: "unknown".to_string(),
+            vector_version: None,
+            last_seen: None,
+            registered_at: "2024-01-01 00:00:00".to_string(),
+            metadata: None,
+            deleted_at: None,
+            tls_ca_cert: tls.ca_cert.clone(),
+            tls_client_cert: tls.client_cert.clone(),
+            tls_client_key: tls.client_key.clone(),
+            tls_insecure_skip_verify: tls.insecure_skip_verify,
+        }
+    }
+    
+    #[test]
+    fn test_build_client_from_pem() {
+        assert!(mtls().build_client(Some(Duration::from_secs(5))).is_ok());
+    
+        let ca_only = AgentTls { ca_cert: Some(CA_CERT.to_string()), ..Default::default() };
+        assert!(ca_only.build_client(None).is_ok());
+    
+        let cert_without_key = AgentTls { client_key: None, ..mtls() };
+        assert!(cert_without_key.build_client(None).is_err());
+    
+        let bad_ca = AgentTls { ca_cert: Some("not a certificate".to_string()), ..Default::default() };
+        assert!(bad_ca.build_client(None).is_err());
+    }
+    
+    #[test]
+    fn test_clients_are_cached_per_agent() {
+        let clients = AgentClients::default();
+    
+        clients.for_agent(&agent(&AgentTls::default()));
+        assert_eq!(clients.cached(), 0);
+    
+        clients.for_agent(&agent(&mtls()));
+        clients.for_agent(&agent(&mtls()));
+        assert_eq!(clients.cached(), 1);
+    
+        // Changed settings replace the agent's client rather than adding one
+        clients.for_agent(&agent(&AgentTls { insecure_skip_verify: true, ..mtls() }));
+        assert_eq!(clients.cached(), 1);
+    
+        clients.for_agent(&agent_with_id("agent-2", &mtls()));
+        assert_eq!(clients.cached(), 2);
+    
+        // Clearing the settings drops the client
+        clients.for_agent(&agent(&AgentTls::default()));
+        assert_eq!(clients.cached(), 1);
+    }
+    
+    #[test]
+    fn test_client_key_sealed_for_storage() {
+        let (cipher, other) = (cipher(), cipher());
+    
+        assert!(mtls().seal(None).unwrap_err().contains(ENCRYPTION_KEY_ENV));
+        let ca_only = AgentTls { ca_cert: Some(CA_CERT.to_string()), ..Default::default() };
+        assert_eq!(ca_only.seal(None).unwrap(), ca_only);
+    
+        let sealed = mtls().seal(Some(&cipher)).unwrap();
+        assert!(!sealed.client_key.as_deref().unwrap().contains("PRIVATE KEY"));
+        assert_eq!(sealed.client_cert, mtls().client_cert);
+    
+        let stored = agent(&sealed);
+        assert_eq!(AgentTls::from_agent(&stored, Some(&cipher)).unwrap(), Some(mtls()));
+        assert!(AgentTls::from_agent(&stored, None).unwrap_err().contains(ENCRYPTION_KEY_ENV));
+        assert!(AgentTls::from_agent(&stored, Some(&other)).is_err());
+        assert!(AgentTls::is_configured(&stored));
+    
+        // Keys stored before sealing still load
+        assert_eq!(AgentTls::from_agent(&agent(&mtls()), None).unwrap(), Some(mtls()));
+    
+        let clients = AgentClients::default().with_cipher(Some(cipher));
+        clients.for_agent(&stored);
+        assert_eq!(clients.cached(), 1);
+        let without_cipher = AgentClients::default();
+        without_cipher.for_agent(&stored);
+        assert_eq!(without_cipher.cached(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_seal_stored_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = crate::db::Database::new(&dir.path().join("test.db")).await.unwrap();
+        let pool = db.pool();
+        let cipher = cipher();
+    
+        // A key stored in plaintext before keys were sealed
+        let agent = AgentRepository::create(pool, "agent-1", "https://agent-1:8686", None).await.unwrap();
+        AgentRepository::set_tls(pool, &agent.id, &mtls()).await.unwrap();
+    
+        assert_eq!(seal_stored_keys(pool, None).await.unwrap(), 0);
+        assert_eq!(seal_stored_keys(pool, Some(&cipher)).await.unwrap(), 1);
+        assert_eq!(seal_stored_keys(pool, Some(&cipher)).await.unwrap(), 0);
+    
+        let stored = AgentRepository::get_by_id(pool, &agent.id).await.unwrap().unwrap();
+        assert!(SecretCipher::is_sealed(stored.tls_client_key.as_deref().unwrap()));
+        assert_eq!(AgentTls::from_agent(&stored, Some(&cipher)).unwrap(), Some(mtls()));
+    
+        // Sealed keys without a cipher stop startup
+        assert!(seal_stored_keys(pool, None).await.unwrap_err().contains(ENCRYPTION_KEY_ENV));
+    
+        db.close().await;
+    }
+    
+    #[test]
+    fn test_debug_redacts_client_key() {
+        let debug = format!("{:?}", mtls());
+        assert!(!debug.contains("PRIVATE KEY"));
+    }
+}
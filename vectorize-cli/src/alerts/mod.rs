@@ -2,10 +2,22 @@
 //!
 //! Provides alerting functionality for agent health and metrics.
 //! Supports multiple notification channels (webhook, Slack, PagerDuty).
+//! Webhook and Slack deliveries are retried with exponential backoff on
+//! network errors and 5xx responses. Webhooks with a signing secret carry an
+//! HMAC-SHA256 signature so receivers can verify them.
 
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use tracing::{info, warn, error};
 
+use crate::db::models::Deployment;
+use crate::health::AgentMetrics;
+
 /// Alert severity levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -48,6 +60,80 @@ pub struct AlertRule {
     pub severity: AlertSeverity,
     pub enabled: bool,
     pub notification_channels: Vec<String>,
+    /// Replaces the default alert message, with `{{field}}` placeholders
+    /// (see `AlertRule::apply_template`)
+    #[serde(default)]
+    pub message_template: Option<String>,
+}
+
+impl AlertRule {
+    /// Render `message_template` into an alert this rule raised
+    ///
+    /// Fields: `agent_name`, `severity`, `source`, `title`, `message` (the
+    /// default text), `error`, `value` (the observed metric), `threshold` and
+    /// `rule_name`. Fields with no value for this alert render empty. Without
+    /// a template the default message is kept.
+    pub fn apply_template(&self, alert: &mut Alert, context: &AlertContext) {
+        let Some(template) = &self.message_template else {
+            return;
+        };
+        
+        let message = render_template(template, |field| {
+            let value = match field {
+                "agent_name" => context.agent_name.clone(),
+                "severity" => Some(alert.severity.to_string()),
+                "source" => Some(alert.source.clone()),
+                "title" => Some(alert.title.clone()),
+                "message" => Some(alert.message.clone()),
+                "error" => context.error.clone(),
+                "value" => context.value.clone(),
+                "threshold" => context.threshold.clone(),
+                "rule_name" => Some(self.name.clone()),
+                _ => return None,
+            };
+            Some(value.unwrap_or_default())
+        });
+        alert.message = message;
+    }
+}
+
+/// Details of a firing alert available to message templates
+#[derive(Debug, Clone, Default)]
+pub struct AlertContext {
+    pub agent_name: Option<String>,
+    pub error: Option<String>,
+    /// Observed metric value that triggered the alert
+    pub value: Option<String>,
+    /// The rule's threshold for that metric
+    pub threshold: Option<String>,
+}
+
+/// Substitute `{{field}}` placeholders using `lookup`
+///
+/// Plain lookups only: no expressions, and substituted values are not
+/// rendered again, so alert contents can't inject placeholders. Fields
+/// `lookup` doesn't know (None) are left as written so typos stay visible.
+pub fn render_template(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let inner = &rest[start + 2..];
+        let Some(end) = inner.find("}}") else {
+            // Unclosed placeholder; keep the remainder verbatim
+            rest = &rest[start..];
+            break;
+        };
+        match lookup(inner[..end].trim()) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &inner[end + 2..];
+    }
+    
+    rendered.push_str(rest);
+    rendered
 }
 
 /// Condition that triggers an alert
@@ -92,6 +178,9 @@ pub enum NotificationChannel {
         url: String,
         #[serde(default)]
         headers: std::collections::HashMap<String, String>,
+        /// Sign deliveries with this secret (see `webhook_signature`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signing_secret: Option<String>,
     },
     /// Slack webhook
     Slack {
@@ -134,11 +223,69 @@ impl NotificationChannel {
     }
 }
 
+/// Header carrying a signed webhook's signature
+pub const SIGNATURE_HEADER: &str = "X-Vectorize-Signature";
+
+/// Header carrying the Unix time a webhook was signed at
+pub const TIMESTAMP_HEADER: &str = "X-Vectorize-Timestamp";
+
+/// Signature of a webhook body, as sent in `X-Vectorize-Signature`
+///
+/// `sha256=` followed by the hex HMAC-SHA256 of `{timestamp}.{body}`, keyed
+/// with the channel's signing secret. Covering the timestamp lets receivers
+/// reject replays of old deliveries.
+pub fn webhook_signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// An alert raised by `AlertManager::evaluate`
+#[derive(Debug, Clone)]
+pub struct FiredAlert {
+    pub rule_id: String,
+    pub alert: Alert,
+    /// Channels from the rule that fired
+    pub notification_channels: Vec<String>,
+}
+
+/// An agent's cumulative event count at a point in time
+#[derive(Debug, Clone, Copy)]
+struct ThroughputSample {
+    events_total: u64,
+    at: DateTime<Utc>,
+}
+
+/// LowThroughput state for one rule and agent
+#[derive(Debug, Clone, Copy, Default)]
+struct ThroughputState {
+    /// Throughput has met the rule's minimum at least once
+    flowing: bool,
+    /// An alert fired and throughput hasn't recovered since
+    firing: bool,
+}
+
+/// Default delivery attempts for webhook and Slack channels
+pub const DEFAULT_DELIVERY_ATTEMPTS: u32 = 3;
+
+/// Default delay before the first retry; doubles on each further retry
+pub const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
 /// Alert manager
 pub struct AlertManager {
     http_client: reqwest::Client,
+    /// Attempts per webhook or Slack delivery, including the first
+    delivery_attempts: u32,
+    retry_base_delay: Duration,
     channels: Vec<NotificationChannel>,
     rules: Vec<AlertRule>,
+    /// Previous metrics reading per agent
+    last_samples: HashMap<String, ThroughputSample>,
+    /// Keyed by (rule id, agent id)
+    throughput_states: HashMap<(String, String), ThroughputState>,
 }
 
 impl AlertManager {
@@ -146,11 +293,25 @@ impl AlertManager {
     pub fn new() -> Self {
         Self {
             http_client: reqwest::Client::new(),
+            delivery_attempts: DEFAULT_DELIVERY_ATTEMPTS,
+            retry_base_delay: DEFAULT_RETRY_BASE_DELAY,
             channels: Vec::new(),
             rules: Vec::new(),
+            last_samples: HashMap::new(),
+            throughput_states: HashMap::new(),
         }
     }
     
+    /// Set how webhook and Slack deliveries are retried
+    ///
+    /// `attempts` includes the first try (1 disables retries). Retry `n`
+    /// waits `base_delay * 2^(n-1)`.
+    pub fn with_retry(mut self, attempts: u32, base_delay: Duration) -> Self {
+        self.delivery_attempts = attempts.max(1);
+        self.retry_base_delay = base_delay;
+        self
+    }
+    
     /// Add a notification channel
     pub fn add_channel(&mut self, channel: NotificationChannel) {
         self.channels.push(channel);
@@ -171,6 +332,72 @@ impl AlertManager {
         &self.channels
     }
     
+    /// Evaluate metric-based rules against an agent's latest metrics snapshot
+    ///
+    /// Throughput is derived from the change in `events_processed_total`
+    /// since the agent's previous snapshot. A `LowThroughput` rule fires once
+    /// when an agent that has met the minimum drops below it, and again only
+    /// after it recovers. The first snapshot (or one after a counter reset)
+    /// only sets a baseline, and agents that have never reached the minimum
+    /// don't alert, so new agents stay quiet. Health-based conditions are
+    /// evaluated from health checks, not here.
+    pub fn evaluate(&mut self, agent_name: &str, metrics: &AgentMetrics, at: DateTime<Utc>) -> Vec<FiredAlert> {
+        let events_total = match metrics.events_processed_total {
+            Some(total) => total,
+            None => return Vec::new(),
+        };
+        
+        let sample = ThroughputSample { events_total, at };
+        let previous = match self.last_samples.insert(metrics.agent_id.clone(), sample) {
+            Some(previous) => previous,
+            None => return Vec::new(),
+        };
+        
+        let elapsed_minutes = (at - previous.at).num_milliseconds() as f64 / 60_000.0;
+        if elapsed_minutes <= 0.0 || events_total < previous.events_total {
+            return Vec::new();
+        }
+        let events_per_minute = (events_total - previous.events_total) as f64 / elapsed_minutes;
+        
+        let mut fired = Vec::new();
+        for rule in self.rules.iter().filter(|r| r.enabled) {
+            let min_events_per_minute = match rule.condition {
+                AlertCondition::LowThroughput { min_events_per_minute } => min_events_per_minute,
+                _ => continue,
+            };
+            
+            let state = self.throughput_states
+                .entry((rule.id.clone(), metrics.agent_id.clone()))
+                .or_default();
+            
+            if events_per_minute >= min_events_per_minute as f64 {
+                state.flowing = true;
+                state.firing = false;
+            } else if state.flowing && !state.firing {
+                state.firing = true;
+                let mut alert = create_low_throughput_alert(
+                    &metrics.agent_id,
+                    agent_name,
+                    events_per_minute,
+                    min_events_per_minute,
+                );
+                rule.apply_template(&mut alert, &AlertContext {
+                    agent_name: Some(agent_name.to_string()),
+                    error: None,
+                    value: Some(format!("{:.0}", events_per_minute)),
+                    threshold: Some(min_events_per_minute.to_string()),
+                });
+                fired.push(FiredAlert {
+                    rule_id: rule.id.clone(),
+                    alert,
+                    notification_channels: rule.notification_channels.clone(),
+                });
+            }
+        }
+        
+        fired
+    }
+    
     /// Send an alert to all configured channels
     pub async fn send_alert(&self, alert: &Alert, channel_ids: &[String]) {
         for channel_id in channel_ids {
@@ -182,24 +409,44 @@ impl AlertManager {
         }
     }
     
+    /// Send an alert to channels defined outside the manager
+    ///
+    /// Used for channels configured per worker group; delivery failures are
+    /// logged like those of `send_alert`.
+    pub async fn notify(&self, alert: &Alert, channels: &[NotificationChannel]) {
+        for channel in channels {
+            if let Err(e) = self.send_to_channel(alert, channel).await {
+                error!("Failed to send alert to {}: {}", channel.name(), e);
+            }
+        }
+    }
+    
     /// Send alert to a specific channel
     async fn send_to_channel(&self, alert: &Alert, channel: &NotificationChannel) -> Result<(), String> {
         match channel {
-            NotificationChannel::Webhook { url, headers, .. } => {
-                let mut request = self.http_client.post(url)
-                    .header("Content-Type", "application/json")
-                    .json(&alert);
+            NotificationChannel::Webhook { url, headers, signing_secret, .. } => {
+                // Serialize once so the signature matches the bytes sent
+                let body = serde_json::to_vec(alert)
+                    .map_err(|e| format!("Failed to serialize alert: {}", e))?;
+                let timestamp = chrono::Utc::now().timestamp();
+                let signature = signing_secret.as_deref()
+                    .map(|secret| webhook_signature(secret, timestamp, &body));
                 
-                for (key, value) in headers {
-                    request = request.header(key, value);
-                }
-                
-                let response = request.send().await
-                    .map_err(|e| format!("Request failed: {}", e))?;
-                
-                if !response.status().is_success() {
-                    return Err(format!("Webhook returned status: {}", response.status()));
-                }
+                self.post_with_retry("Webhook", || {
+                    let mut request = self.http_client.post(url)
+                        .header("Content-Type", "application/json")
+                        .body(body.clone());
+                    
+                    for (key, value) in headers {
+                        request = request.header(key, value);
+                    }
+                    if let Some(signature) = &signature {
+                        request = request
+                            .header(SIGNATURE_HEADER, signature)
+                            .header(TIMESTAMP_HEADER, timestamp.to_string());
+                    }
+                    request
+                }).await?;
                 
                 info!("Alert sent to webhook: {}", alert.title);
                 Ok(())
@@ -234,15 +481,7 @@ impl AlertManager {
                     }]
                 });
                 
-                let response = self.http_client.post(webhook_url)
-                    .json(&payload)
-                    .send()
-                    .await
-                    .map_err(|e| format!("Request failed: {}", e))?;
-                
-                if !response.status().is_success() {
-                    return Err(format!("Slack returned status: {}", response.status()));
-                }
+                self.post_with_retry("Slack", || self.http_client.post(webhook_url).json(&payload)).await?;
                 
                 info!("Alert sent to Slack: {}", alert.title);
                 Ok(())
@@ -292,6 +531,39 @@ impl AlertManager {
             }
         }
     }
+    
+    /// Send a request, retrying network errors and 5xx responses
+    ///
+    /// 4xx responses mean the request itself is wrong and fail immediately.
+    async fn post_with_retry(
+        &self,
+        target: &str,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<(), String> {
+        let mut attempt = 1;
+        loop {
+            let (error, retryable) = match build().send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => (
+                    format!("{} returned status: {}", target, response.status()),
+                    response.status().is_server_error(),
+                ),
+                Err(e) => (format!("Request failed: {}", e), true),
+            };
+            
+            if !retryable || attempt >= self.delivery_attempts {
+                return Err(error);
+            }
+            
+            let delay = self.retry_base_delay * 2u32.saturating_pow(attempt - 1);
+            warn!(
+                "{} delivery failed (attempt {}/{}): {}; retrying in {:?}",
+                target, attempt, self.delivery_attempts, error, delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
 }
 
 impl Default for AlertManager {
@@ -329,6 +601,28 @@ pub fn create_agent_unreachable_alert(agent_id: &str, agent_name: &str, error: &
     }
 }
 
+/// Create an alert for an agent whose pipeline has stalled
+pub fn create_low_throughput_alert(
+    agent_id: &str,
+    agent_name: &str,
+    events_per_minute: f64,
+    min_events_per_minute: u64,
+) -> Alert {
+    Alert {
+        id: format!("agent-low-throughput-{}-{}", agent_id, chrono::Utc::now().timestamp()),
+        severity: AlertSeverity::Warning,
+        title: format!("Agent {} throughput dropped", agent_name),
+        message: format!(
+            "Processing {:.0} events/min, below the minimum of {}",
+            events_per_minute, min_events_per_minute
+        ),
+        source: format!("agent:{}", agent_id),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        resolved: false,
+        resolved_at: None,
+    }
+}
+
 /// Create an alert for a degraded worker group
 pub fn create_group_degraded_alert(group_id: &str, group_name: &str, unhealthy_count: u32, total_count: u32) -> Alert {
     let percentage = (unhealthy_count as f64 / total_count as f64 * 100.0) as u32;
@@ -345,6 +639,35 @@ pub fn create_group_degraded_alert(group_id: &str, group_name: &str, unhealthy_c
     }
 }
 
+/// Create an alert for a deployment that reached a final status
+///
+/// Completed deployments are informational, failed ones critical and
+/// anything else (e.g. cancelled) a warning.
+pub fn create_deployment_result_alert(deployment: &Deployment, status: &str) -> Alert {
+    let severity = match status {
+        "completed" => AlertSeverity::Info,
+        "failed" => AlertSeverity::Critical,
+        _ => AlertSeverity::Warning,
+    };
+    let message = match deployment.error.as_deref() {
+        Some(error) if status == "failed" => format!(
+            "Deployment of config {} failed: {}", deployment.config_version, error
+        ),
+        _ => format!("Deployment of config {} {}", deployment.config_version, status),
+    };
+    
+    Alert {
+        id: format!("deployment-{}-{}", status, deployment.id),
+        severity,
+        title: format!("Deployment {} {}", deployment.id, status),
+        message,
+        source: format!("deployment:{}", deployment.id),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        resolved: false,
+        resolved_at: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -384,6 +707,165 @@ mod tests {
         assert!(alert.message.contains("3/5"));
     }
     
+    fn low_throughput_manager(min_events_per_minute: u64) -> AlertManager {
+        let mut manager = AlertManager::new();
+        manager.add_rule(AlertRule {
+            id: "stalled".to_string(),
+            name: "Pipeline stalled".to_string(),
+            description: None,
+            condition: AlertCondition::LowThroughput { min_events_per_minute },
+            severity: AlertSeverity::Warning,
+            enabled: true,
+            notification_channels: vec!["slack1".to_string()],
+            message_template: None,
+        });
+        manager
+    }
+    
+    /// Feed one snapshot per minute with the given cumulative totals, returning the alerts per snapshot
+    fn feed(manager: &mut AlertManager, totals: &[u64]) -> Vec<usize> {
+        let start = Utc::now();
+        totals.iter().enumerate().map(|(minute, total)| {
+            let metrics = AgentMetrics {
+                agent_id: "abc123".to_string(),
+                events_processed_total: Some(*total),
+                ..Default::default()
+            };
+            let at = start + chrono::Duration::minutes(minute as i64);
+            manager.evaluate("prod-agent-1", &metrics, at).len()
+        }).collect()
+    }
+    
+    #[test]
+    fn test_low_throughput_fires_when_flow_stalls() {
+        let mut manager = low_throughput_manager(100);
+        
+        // 600, 300, 10, 5 events/min, then recovery and a second stall
+        let fired = feed(&mut manager, &[0, 600, 900, 910, 915, 2000, 2001]);
+        assert_eq!(fired, vec![0, 0, 0, 1, 0, 0, 1]);
+        
+        let metrics = AgentMetrics {
+            agent_id: "abc123".to_string(),
+            events_processed_total: Some(2001),
+            ..Default::default()
+        };
+        let alerts = manager.evaluate("prod-agent-1", &metrics, Utc::now() + chrono::Duration::minutes(30));
+        assert!(alerts.is_empty(), "still stalled, already alerted");
+    }
+    
+    #[test]
+    fn test_low_throughput_alert_contents() {
+        let mut manager = low_throughput_manager(100);
+        let start = Utc::now();
+        let snapshot = |total| AgentMetrics {
+            agent_id: "abc123".to_string(),
+            events_processed_total: Some(total),
+            ..Default::default()
+        };
+        
+        manager.evaluate("prod-agent-1", &snapshot(0), start);
+        manager.evaluate("prod-agent-1", &snapshot(500), start + chrono::Duration::minutes(1));
+        let fired = manager.evaluate("prod-agent-1", &snapshot(520), start + chrono::Duration::minutes(2));
+        
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule_id, "stalled");
+        assert_eq!(fired[0].notification_channels, vec!["slack1".to_string()]);
+        assert_eq!(fired[0].alert.severity, AlertSeverity::Warning);
+        assert_eq!(fired[0].alert.source, "agent:abc123");
+        assert!(fired[0].alert.message.contains("20 events/min"));
+    }
+    
+    fn templated_rule(template: &str) -> AlertRule {
+        AlertRule {
+            id: "unhealthy".to_string(),
+            name: "Agent down".to_string(),
+            description: None,
+            condition: AlertCondition::AgentUnhealthy { consecutive_failures: 3 },
+            severity: AlertSeverity::Warning,
+            enabled: true,
+            notification_channels: Vec::new(),
+            message_template: Some(template.to_string()),
+        }
+    }
+    
+    #[test]
+    fn test_message_template_renders_all_fields() {
+        let rule = templated_rule(
+            "[{{ severity }}] {{rule_name}}: {{agent_name}} ({{source}}) {{value}}/{{threshold}} - {{error}} | {{title}} | {{message}}",
+        );
+        let mut alert = create_agent_unhealthy_alert("abc123", "prod-agent-1", Some("timeout"));
+        rule.apply_template(&mut alert, &AlertContext {
+            agent_name: Some("prod-agent-1".to_string()),
+            error: Some("timeout".to_string()),
+            value: Some("0".to_string()),
+            threshold: Some("3".to_string()),
+        });
+        
+        assert_eq!(
+            alert.message,
+            "[warning] Agent down: prod-agent-1 (agent:abc123) 0/3 - timeout | Agent prod-agent-1 is unhealthy | Health check failed: timeout"
+        );
+    }
+    
+    #[test]
+    fn test_message_template_unknown_and_missing_fields() {
+        // Unknown placeholders stay literal; known fields without a value are blank
+        let rule = templated_rule("{{agent_name}} {{nope}} error={{error}} {{unclosed");
+        let mut alert = create_agent_unhealthy_alert("abc123", "prod-agent-1", None);
+        rule.apply_template(&mut alert, &AlertContext {
+            agent_name: Some("{{error}}".to_string()),
+            ..Default::default()
+        });
+        
+        // Substituted values are not rendered again
+        assert_eq!(alert.message, "{{error}} {{nope}} error= {{unclosed");
+        
+        // Without a template the default message is kept
+        let mut rule = rule;
+        rule.message_template = None;
+        let mut alert = create_agent_unhealthy_alert("abc123", "prod-agent-1", None);
+        rule.apply_template(&mut alert, &AlertContext::default());
+        assert_eq!(alert.message, "Agent health check returned unhealthy status");
+    }
+    
+    #[test]
+    fn test_low_throughput_uses_rule_template() {
+        let mut manager = AlertManager::new();
+        manager.add_rule(AlertRule {
+            condition: AlertCondition::LowThroughput { min_events_per_minute: 100 },
+            ..templated_rule("{{agent_name}} at {{value}}/min (min {{threshold}})")
+        });
+        
+        let start = Utc::now();
+        for (minute, total) in [0u64, 500, 520].into_iter().enumerate() {
+            let metrics = AgentMetrics {
+                agent_id: "abc123".to_string(),
+                events_processed_total: Some(total),
+                ..Default::default()
+            };
+            let fired = manager.evaluate("prod-agent-1", &metrics, start + chrono::Duration::minutes(minute as i64));
+            if minute == 2 {
+                assert_eq!(fired[0].alert.message, "prod-agent-1 at 20/min (min 100)");
+            }
+        }
+    }
+    
+    #[test]
+    fn test_low_throughput_quiet_without_baseline() {
+        let mut manager = low_throughput_manager(100);
+        
+        // A new agent that never reaches the minimum doesn't alert
+        assert_eq!(feed(&mut manager, &[0, 5, 10, 12]), vec![0, 0, 0, 0]);
+        
+        // A counter reset (agent restart) resets the baseline instead of alerting
+        let mut manager = low_throughput_manager(100);
+        assert_eq!(feed(&mut manager, &[0, 1000, 50, 1000]), vec![0, 0, 0, 0]);
+        
+        // Snapshots without event counts are ignored
+        let metrics = AgentMetrics { agent_id: "abc123".to_string(), ..Default::default() };
+        assert!(manager.evaluate("prod-agent-1", &metrics, Utc::now()).is_empty());
+    }
+    
     #[test]
     fn test_notification_channel_id() {
         let webhook = NotificationChannel::Webhook {
@@ -391,6 +873,7 @@ mod tests {
             name: "My Webhook".to_string(),
             url: "https://example.com/webhook".to_string(),
             headers: Default::default(),
+            signing_secret: None,
         };
         
         assert_eq!(webhook.id(), "webhook1");
@@ -410,4 +893,115 @@ mod tests {
         
         assert_eq!(manager.channels().len(), 1);
     }
+    
+    /// Serve `statuses` in order, then 200, counting every request
+    async fn spawn_flaky_server(statuses: Vec<u16>) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route("/hook", axum::routing::post(move || {
+            let hit = counter.fetch_add(1, Ordering::SeqCst);
+            let status = statuses.get(hit).copied().unwrap_or(200);
+            async move { axum::http::StatusCode::from_u16(status).unwrap() }
+        }));
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (url, hits)
+    }
+    
+    fn webhook(url: &str) -> NotificationChannel {
+        NotificationChannel::Webhook {
+            id: "webhook1".to_string(),
+            name: "My Webhook".to_string(),
+            url: url.to_string(),
+            headers: Default::default(),
+            signing_secret: None,
+        }
+    }
+    
+    #[test]
+    fn test_webhook_signature() {
+        let signature = webhook_signature(
+            "It's a Secret to Everybody",
+            1_700_000_000,
+            br#"{"title":"Agent down"}"#,
+        );
+        assert_eq!(signature, "sha256=71e73bcac7f4b17d76b44eb409a942bd5d82e1c33830cd128f4745fdc4b50491");
+    }
+    
+    #[tokio::test]
+    async fn test_signed_webhook_delivery() {
+        use axum::http::HeaderMap;
+        use std::sync::{Arc, Mutex};
+        
+        let received: Arc<Mutex<Option<(HeaderMap, axum::body::Bytes)>>> = Arc::default();
+        let sink = received.clone();
+        let app = axum::Router::new().route("/hook", axum::routing::post(move |headers: HeaderMap, body: axum::body::Bytes| {
+            *sink.lock().unwrap() = Some((headers, body));
+            async { axum::http::StatusCode::OK }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let channel = NotificationChannel::Webhook {
+            id: "webhook1".to_string(),
+            name: "Signed".to_string(),
+            url,
+            headers: Default::default(),
+            signing_secret: Some("s3cret".to_string()),
+        };
+        let alert = create_agent_unhealthy_alert("abc123", "prod-agent-1", None);
+        AlertManager::new().send_to_channel(&alert, &channel).await.unwrap();
+        
+        let (headers, body) = received.lock().unwrap().take().unwrap();
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert_eq!(headers[SIGNATURE_HEADER], webhook_signature("s3cret", timestamp, &body).as_str());
+    }
+    
+    #[tokio::test]
+    async fn test_webhook_delivery_retries_server_errors() {
+        use std::sync::atomic::Ordering;
+        
+        let (url, hits) = spawn_flaky_server(vec![503, 502]).await;
+        let manager = AlertManager::new().with_retry(3, Duration::from_millis(1));
+        let alert = create_agent_unhealthy_alert("abc123", "prod-agent-1", None);
+        
+        // Fails twice, delivered on the third try
+        manager.send_to_channel(&alert, &webhook(&url)).await.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        
+        // Gives up once attempts run out
+        let (url, hits) = spawn_flaky_server(vec![503, 503, 503]).await;
+        let err = manager.send_to_channel(&alert, &webhook(&url)).await.unwrap_err();
+        assert!(err.contains("503"));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_delivery_does_not_retry_client_errors() {
+        use std::sync::atomic::Ordering;
+        
+        let (url, hits) = spawn_flaky_server(vec![404]).await;
+        let manager = AlertManager::new().with_retry(3, Duration::from_millis(1));
+        let alert = create_agent_unhealthy_alert("abc123", "prod-agent-1", None);
+        
+        let slack = NotificationChannel::Slack {
+            id: "slack1".to_string(),
+            name: "Slack Channel".to_string(),
+            webhook_url: url,
+            channel: None,
+        };
+        let err = manager.send_to_channel(&alert, &slack).await.unwrap_err();
+        assert!(err.starts_with("Slack returned status: 404"));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
 }
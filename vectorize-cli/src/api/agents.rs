@@ -5,21 +5,31 @@
 //! - Listing and filtering agents
 //! - Updating agent information
 //! - Checking agent health
+//! - Heartbeats from agent sidecars
 
 use axum::{
-    extract::{Path, State, Query},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Body,
+    extract::{Path, State, Query, RawQuery},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use sqlx;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
 use crate::AppState;
-use crate::db::models::AgentResponse;
-use crate::db::repository::AgentRepository;
+use crate::agent_clients::AgentTls;
+use crate::api::{accepts, ApiError};
+use crate::api::deployments::CreateDeploymentResponse;
+use crate::db::DbPool;
+use crate::db::models::{Agent, AgentResponse, AgentStatus};
+use crate::db::repository::{AgentRepository, DeploymentRepository, UptimeCounts, WorkerGroupRepository};
+use crate::deployment::DeploymentExecutor;
+use crate::live::LiveEvent;
+use crate::request_id::WithRequestId;
 
 /// Request to register a new agent
 #[derive(Debug, Deserialize)]
@@ -28,8 +38,17 @@ pub struct RegisterAgentRequest {
     pub name: String,
     /// URL to the agent's GraphQL API (e.g., http://localhost:8686)
     pub url: String,
-    /// Optional worker group to assign the agent to
+    /// Optional worker group to assign the agent to, by ID or name
     pub group_id: Option<String>,
+    /// Create the group by name if it doesn't exist
+    #[serde(default)]
+    pub auto_group: bool,
+    /// Optional key/value tags (replaces existing tags on re-registration)
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    /// TLS settings for the agent's Vector API (replaces existing settings on
+    /// re-registration)
+    pub tls: Option<AgentTls>,
     /// Optional metadata (reserved for future use)
     #[allow(dead_code)]
     pub metadata: Option<serde_json::Value>,
@@ -50,6 +69,13 @@ pub struct ListAgentsQuery {
     pub group_id: Option<String>,
     /// Filter by status
     pub status: Option<String>,
+    /// Maximum number of agents to return
+    pub limit: Option<i64>,
+    /// Number of agents to skip
+    pub offset: Option<i64>,
+    /// Include soft-deleted agents (for audit views)
+    #[serde(default)]
+    pub include_deleted: bool,
 }
 
 /// Request to update an agent
@@ -59,39 +85,118 @@ pub struct UpdateAgentRequest {
     pub name: Option<String>,
     /// New group assignment (optional, use null to unassign)
     pub group_id: Option<Option<String>>,
+    /// Replace the TLS settings for the agent's Vector API (optional, an
+    /// empty object clears them)
+    pub tls: Option<AgentTls>,
+}
+
+/// Request to move an agent to another group
+#[derive(Debug, Deserialize)]
+pub struct MoveAgentRequest {
+    /// Target group (null to unassign)
+    pub group_id: Option<String>,
+    /// Deploy the target group's current config to the agent after moving
+    #[serde(default)]
+    pub deploy: bool,
+}
+
+/// Request to test-deploy a config to one agent
+#[derive(Debug, Default, Deserialize)]
+pub struct AgentDeployRequest {
+    /// Config version to deploy (defaults to the group's current version)
+    pub config_version: Option<String>,
+}
+
+/// Response for moving an agent
+#[derive(Debug, Serialize)]
+pub struct MoveAgentResponse {
+    pub agent: AgentResponse,
+    /// Deployment started for the agent, if `deploy` was set and the group has a config
+    pub deployment_id: Option<String>,
+    pub message: String,
+}
+
+/// Parse repeated `tag=key:value` query parameters
+fn parse_tag_filters(raw_query: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let mut tags = Vec::new();
+    
+    for pair in raw_query.unwrap_or("").split('&') {
+        let (name, value) = match pair.split_once('=') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        if name != "tag" {
+            continue;
+        }
+        
+        let value = urlencoding::decode(&value.replace('+', " "))
+            .map_err(|_| format!("Invalid tag filter encoding: {}", value))?
+            .into_owned();
+        
+        match value.split_once(':') {
+            Some((k, v)) if !k.is_empty() => tags.push((k.to_string(), v.to_string())),
+            _ => return Err(format!("Invalid tag filter '{}', expected key:value", value)),
+        }
+    }
+    
+    Ok(tags)
 }
 
+/// Content type of streamed agent listings
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Agents read from the database per chunk of a streamed listing
+const AGENT_STREAM_CHUNK: i64 = 500;
+
 /// List all agents
+///
+/// Supports repeated `?tag=key:value` filters; an agent must carry every tag
+/// to be included. When `limit` is given only that page is returned, and the
+/// total number of matching agents is reported in the `X-Total-Count` header.
+///
+/// With `Accept: application/x-ndjson` the listing is streamed one agent per
+/// line instead, reading the database in chunks; `limit` and `offset` don't
+/// apply there.
 pub async fn list_agents(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListAgentsQuery>,
-) -> impl IntoResponse {
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     let pool = state.db.pool();
+    let offset = query.offset.unwrap_or(0).max(0);
     
-    let agents = if let Some(group_id) = query.group_id {
-        match AgentRepository::list_by_group(pool, &group_id).await {
-            Ok(agents) => agents,
-            Err(e) => {
-                error!("Failed to list agents by group: {}", e);
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                    "error": "Failed to list agents"
-                }))).into_response();
-            }
-        }
-    } else {
-        match AgentRepository::list(pool).await {
-            Ok(agents) => agents,
-            Err(e) => {
-                error!("Failed to list agents: {}", e);
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                    "error": "Failed to list agents"
-                }))).into_response();
-            }
-        }
-    };
+    let tags = parse_tag_filters(raw_query.as_deref()).map_err(ApiError::bad_request)?;
+    
+    if accepts(&headers, NDJSON_CONTENT_TYPE) {
+        return Ok(stream_agents(pool.clone(), query, tags));
+    }
+    
+    // Without filters the page can be fetched directly from the database
+    if let (Some(limit), None, None, true, false) = (
+        query.limit,
+        &query.group_id,
+        &query.status,
+        tags.is_empty(),
+        query.include_deleted,
+    ) {
+        let total = AgentRepository::count(pool).await
+            .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?;
+        let agents = AgentRepository::list_paginated(pool, limit.max(0), offset).await
+            .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?;
+        
+        let mut agents: Vec<AgentResponse> = agents.into_iter().map(AgentResponse::from).collect();
+        attach_tags(pool, &mut agents).await
+            .map_err(|e| ApiError::internal_logged("Failed to get agent tags", e))?;
+        return Ok((StatusCode::OK, [("X-Total-Count", total.to_string())], Json(agents)).into_response());
+    }
+    
+    let agents = AgentRepository::list_filtered(pool, query.group_id.as_deref(), &tags, query.include_deleted)
+        .await
+        .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?;
     
     // Convert to response format and optionally filter by status
-    let agents: Vec<AgentResponse> = agents
+    let mut agents: Vec<AgentResponse> = agents
         .into_iter()
         .map(AgentResponse::from)
         .filter(|a| {
@@ -103,27 +208,122 @@ pub async fn list_agents(
         })
         .collect();
     
-    (StatusCode::OK, Json(agents)).into_response()
+    // Filtered listings are paginated after filtering
+    if let Some(limit) = query.limit {
+        let total = agents.len();
+        let mut page: Vec<AgentResponse> = agents
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit.max(0) as usize)
+            .collect();
+        attach_tags(pool, &mut page).await
+            .map_err(|e| ApiError::internal_logged("Failed to get agent tags", e))?;
+        return Ok((StatusCode::OK, [("X-Total-Count", total.to_string())], Json(page)).into_response());
+    }
+    
+    attach_tags(pool, &mut agents).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent tags", e))?;
+    Ok((StatusCode::OK, Json(agents)).into_response())
+}
+
+/// Fill in the tags of listed agents, with one query per chunk of agents
+async fn attach_tags(pool: &DbPool, agents: &mut [AgentResponse]) -> Result<(), sqlx::Error> {
+    for chunk in agents.chunks_mut(AGENT_STREAM_CHUNK as usize) {
+        let ids: Vec<String> = chunk.iter().map(|a| a.id.clone()).collect();
+        let mut tags = AgentRepository::get_tags_for(pool, &ids).await?;
+        for agent in chunk {
+            agent.tags = tags.remove(&agent.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|t| (t.key, t.value))
+                .collect();
+        }
+    }
+    Ok(())
+}
+
+/// Position of a streamed agent listing
+struct AgentStream {
+    pool: DbPool,
+    query: ListAgentsQuery,
+    tags: Vec<(String, String)>,
+    /// `(name, id)` of the last agent sent
+    after: Option<(String, String)>,
+    done: bool,
+}
+
+/// Stream matching agents as NDJSON, one chunk of rows at a time
+///
+/// A database error ends the stream early, which clients see as a
+/// truncated response.
+fn stream_agents(pool: DbPool, query: ListAgentsQuery, tags: Vec<(String, String)>) -> Response {
+    let start = AgentStream { pool, query, tags, after: None, done: false };
+    let lines = futures::stream::unfold(start, |mut stream| async move {
+        if stream.done {
+            return None;
+        }
+        
+        let chunk = AgentRepository::list_chunk(
+            &stream.pool,
+            stream.query.group_id.as_deref(),
+            &stream.tags,
+            stream.query.include_deleted,
+            stream.after.as_ref().map(|(name, id)| (name.as_str(), id.as_str())),
+            AGENT_STREAM_CHUNK,
+        ).await;
+        let agents = match chunk {
+            Ok(agents) => agents,
+            Err(e) => {
+                error!("Failed to stream agents: {}", e);
+                stream.done = true;
+                return Some((Err(e), stream));
+            }
+        };
+        
+        stream.done = (agents.len() as i64) < AGENT_STREAM_CHUNK;
+        stream.after = agents.last().map(|a| (a.name.clone(), a.id.clone()));
+        
+        let mut agents: Vec<AgentResponse> = agents
+            .into_iter()
+            .map(AgentResponse::from)
+            .filter(|a| stream.query.status.as_ref().is_none_or(|status| a.status.to_string() == *status))
+            .collect();
+        if let Err(e) = attach_tags(&stream.pool, &mut agents).await {
+            error!("Failed to stream agents: {}", e);
+            stream.done = true;
+            return Some((Err(e), stream));
+        }
+        
+        let mut lines = String::new();
+        for agent in agents {
+            if let Ok(line) = serde_json::to_string(&agent) {
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+        }
+        Some((Ok(lines), stream))
+    });
+    
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+        Body::from_stream(lines),
+    ).into_response()
 }
 
 /// List unassigned agents (agents without a group)
 pub async fn list_unassigned_agents(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    match AgentRepository::list_unassigned(pool).await {
-        Ok(agents) => {
-            let agents: Vec<AgentResponse> = agents.into_iter().map(AgentResponse::from).collect();
-            (StatusCode::OK, Json(agents)).into_response()
-        }
-        Err(e) => {
-            error!("Failed to list unassigned agents: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list unassigned agents"
-            }))).into_response()
-        }
-    }
+    let agents = AgentRepository::list_unassigned(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list unassigned agents", e))?;
+    
+    let mut agents: Vec<AgentResponse> = agents.into_iter().map(AgentResponse::from).collect();
+    attach_tags(pool, &mut agents).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent tags", e))?;
+    Ok((StatusCode::OK, Json(agents)))
 }
 
 /// Assign an agent to a group
@@ -136,110 +336,134 @@ pub async fn assign_agent_to_group(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(request): Json<AssignAgentRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Verify agent exists
-    let agent = match AgentRepository::get_by_id(pool, &id).await {
-        Ok(Some(agent)) => agent,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Agent not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get agent: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get agent"
-            }))).into_response();
-        }
-    };
+    let agent = AgentRepository::get_by_id(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
     
     // If group_id is provided, verify the group exists
     if let Some(ref group_id) = request.group_id {
-        match crate::db::repository::WorkerGroupRepository::get_by_id(pool, group_id).await {
-            Ok(Some(_)) => {}
-            Ok(None) => {
-                return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                    "error": "Group not found"
-                }))).into_response();
-            }
-            Err(e) => {
-                error!("Failed to verify group: {}", e);
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                    "error": "Failed to verify group"
-                }))).into_response();
-            }
-        }
+        WorkerGroupRepository::get_by_id(pool, group_id).await
+            .map_err(|e| ApiError::internal_logged("Failed to verify group", e))?
+            .ok_or_else(|| ApiError::bad_request("Group not found"))?;
     }
     
     // Update the agent's group
-    match AgentRepository::update(
+    let updated = AgentRepository::update(
         pool,
         &id,
         Some(&agent.name),
         Some(request.group_id.as_deref()),
-    ).await {
-        Ok(Some(updated)) => {
-            let action = if request.group_id.is_some() { "assigned to group" } else { "unassigned from group" };
-            info!("Agent '{}' {}", updated.name, action);
-            (StatusCode::OK, Json(AgentResponse::from(updated))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Agent not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to assign agent: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to assign agent"
-            }))).into_response()
-        }
-    }
+    ).await
+        .map_err(|e| ApiError::internal_logged("Failed to assign agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    let action = if request.group_id.is_some() { "assigned to group" } else { "unassigned from group" };
+    info!("Agent '{}' {}", updated.name, action);
+    Ok((StatusCode::OK, Json(AgentResponse::from(updated))))
 }
 
 /// Register a new agent
 pub async fn register_agent(
     State(state): State<Arc<AppState>>,
     Json(request): Json<RegisterAgentRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    // Check if an agent with this name already exists
-    if let Ok(Some(existing)) = AgentRepository::get_by_name(pool, &request.name).await {
-        // Agent exists - update its URL and group if changed, and return it
-        // This allows agents to re-register after restart with potentially different URLs
-        let _ = AgentRepository::update(
-            pool,
-            &existing.id,
-            Some(&request.name),
-            Some(request.group_id.as_deref()),
-        ).await;
+    // Bad PEM material is rejected before anything is stored
+    let tls_client = match request.tls.as_ref().filter(|tls| !tls.is_empty()) {
+        Some(tls) => Some(tls.build_client(None).map_err(ApiError::bad_request)?),
+        None => None,
+    };
+    let sealed_tls = request.tls.as_ref()
+        .map(|tls| tls.seal(state.cipher.as_ref()))
+        .transpose()
+        .map_err(ApiError::bad_request)?;
+    
+    let group_id = match request.group_id.as_deref() {
+        Some(group) => Some(resolve_register_group(&state, group, request.auto_group).await?),
+        None => None,
+    };
+    
+    // An agent already registered at this URL is the same Vector instance, so
+    // re-running provisioning updates it instead of creating a duplicate.
+    // Otherwise match by name, which lets an agent re-register after a restart
+    // with a different URL.
+    let existing = match AgentRepository::get_by_url(pool, &request.url).await {
+        Ok(Some(agent)) => Some(agent),
+        Ok(None) => AgentRepository::get_by_name(pool, &request.name).await.ok().flatten(),
+        Err(e) => return Err(ApiError::internal_logged("Failed to look up agent", e)),
+    };
+    
+    if let Some(existing) = existing {
+        // Renaming the agent at this URL must not take another agent's name
+        if existing.name != request.name {
+            if let Ok(Some(other)) = AgentRepository::get_by_name(pool, &request.name).await {
+                return Err(ApiError::conflict(format!(
+                    "Agent name '{}' is already used by agent {}",
+                    request.name, other.id
+                )));
+            }
+        }
         
-        // Update URL if changed
+        // Update URL if changed, before a restore makes the row active again
         if existing.url != request.url {
-            let _ = sqlx::query("UPDATE agents SET url = ? WHERE id = ?")
+            let _ = sqlx::query("UPDATE agents SET url = $1 WHERE id = $2")
                 .bind(&request.url)
                 .bind(&existing.id)
                 .execute(pool)
                 .await;
         }
         
+        // A previously deleted agent coming back is restored rather than recreated
+        if existing.deleted_at.is_some() {
+            if let Err(e) = AgentRepository::restore(pool, &existing.id).await {
+                error!("Failed to restore agent: {}", e);
+            } else {
+                info!("Restored previously deleted agent '{}'", request.name);
+            }
+        }
+        
+        // Update its name and group if changed, and return it
+        let _ = AgentRepository::update(
+            pool,
+            &existing.id,
+            Some(&request.name),
+            Some(group_id.as_deref()),
+        ).await;
+        
+        if !request.tags.is_empty() {
+            let tags: Vec<(String, String)> = request.tags.clone().into_iter().collect();
+            if let Err(e) = AgentRepository::set_tags(pool, &existing.id, &tags).await {
+                error!("Failed to set agent tags: {}", e);
+            }
+        }
+        
+        if let Some(tls) = &sealed_tls {
+            if let Err(e) = AgentRepository::set_tls(pool, &existing.id, tls).await {
+                error!("Failed to set agent TLS settings: {}", e);
+            }
+        }
+        
         // Re-fetch and return the updated agent
         if let Ok(Some(agent)) = AgentRepository::get_by_id(pool, &existing.id).await {
-            info!("Agent '{}' re-registered (updated URL/group)", request.name);
-            return (StatusCode::OK, Json(RegisterAgentResponse {
+            info!("Agent '{}' re-registered (updated URL/name/group)", request.name);
+            return Ok((StatusCode::OK, Json(RegisterAgentResponse {
                 success: true,
-                agent: Some(AgentResponse::from(agent)),
+                agent: Some(agent_response_with_tags(pool, agent).await),
                 message: "Agent re-registered successfully".to_string(),
-            })).into_response();
+            })));
         }
     }
     
     // Verify the agent is reachable by checking its health endpoint
+    let client = tls_client.unwrap_or_else(|| state.http_client.clone());
     let health_url = format!("{}/health", request.url.trim_end_matches('/'));
-    let health_check = state.http_client.get(&health_url)
+    let health_check = client.get(&health_url)
+        .with_request_id()
         .timeout(std::time::Duration::from_secs(5))
         .send()
         .await;
@@ -251,72 +475,104 @@ pub async fn register_agent(
     };
     
     // Try to get Vector version
-    let version = get_vector_version(&state.http_client, &request.url).await;
+    let version = get_vector_version(&client, &request.url).await;
     
     // Create the agent in the database
-    match AgentRepository::create(
+    let agent = AgentRepository::create(
         pool,
         &request.name,
         &request.url,
-        request.group_id.as_deref(),
-    ).await {
-        Ok(agent) => {
-            // Update status and version
-            let _ = AgentRepository::update_status(
-                pool,
-                &agent.id,
-                initial_status,
-                version.as_deref(),
-            ).await;
-            
-            info!("Registered new agent: {} at {} (status: {})", agent.name, agent.url, initial_status);
-            
-            // Re-fetch to get updated status
-            let updated = AgentRepository::get_by_id(pool, &agent.id).await
-                .ok()
-                .flatten()
-                .map(AgentResponse::from);
-            
-            (StatusCode::CREATED, Json(RegisterAgentResponse {
-                success: true,
-                agent: updated,
-                message: format!("Agent registered successfully (status: {})", initial_status),
-            })).into_response()
+        group_id.as_deref(),
+    ).await
+        .map_err(|e| ApiError::internal_logged("Failed to register agent", e))?;
+    
+    // Update status and version
+    let _ = AgentRepository::update_status(
+        pool,
+        &agent.id,
+        initial_status,
+        version.as_deref(),
+    ).await;
+    
+    if !request.tags.is_empty() {
+        let tags: Vec<(String, String)> = request.tags.clone().into_iter().collect();
+        if let Err(e) = AgentRepository::set_tags(pool, &agent.id, &tags).await {
+            error!("Failed to set agent tags: {}", e);
         }
-        Err(e) => {
-            error!("Failed to register agent: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(RegisterAgentResponse {
-                success: false,
-                agent: None,
-                message: format!("Failed to register agent: {}", e),
-            })).into_response()
+    }
+    
+    if let Some(tls) = &sealed_tls {
+        if let Err(e) = AgentRepository::set_tls(pool, &agent.id, tls).await {
+            error!("Failed to set agent TLS settings: {}", e);
         }
     }
+    
+    info!("Registered new agent: {} at {} (status: {})", agent.name, agent.url, initial_status);
+    
+    // Re-fetch to get updated status
+    let updated = match AgentRepository::get_by_id(pool, &agent.id).await {
+        Ok(Some(agent)) => Some(agent_response_with_tags(pool, agent).await),
+        _ => None,
+    };
+    
+    Ok((StatusCode::CREATED, Json(RegisterAgentResponse {
+        success: true,
+        agent: updated,
+        message: format!("Agent registered successfully (status: {})", initial_status),
+    })))
+}
+
+/// Resolve a registration's group (an ID or a name) to a group ID
+///
+/// With `auto_create`, a group that doesn't exist is created under that name,
+/// along with its config directory. Otherwise a missing group is a 404.
+async fn resolve_register_group(
+    state: &AppState,
+    group: &str,
+    auto_create: bool,
+) -> Result<String, ApiError> {
+    let pool = state.db.pool();
+    
+    if let Some(existing) = WorkerGroupRepository::get_by_id(pool, group).await
+        .map_err(|e| ApiError::internal_logged("Failed to get worker group", e))?
+    {
+        return Ok(existing.id);
+    }
+    if let Some(existing) = WorkerGroupRepository::get_by_name(pool, group).await
+        .map_err(|e| ApiError::internal_logged("Failed to get worker group", e))?
+    {
+        return Ok(existing.id);
+    }
+    
+    if !auto_create {
+        return Err(ApiError::not_found(format!(
+            "Worker group '{}' not found (use auto_group to create it)",
+            group
+        )));
+    }
+    
+    let created = WorkerGroupRepository::create(pool, group, None, None).await
+        .map_err(|e| ApiError::internal_logged("Failed to create worker group", e))?;
+    if let Err(e) = state.git_store.create_group(group) {
+        warn!("Failed to create git directory for group: {}", e);
+    }
+    info!("Created worker group '{}' for agent registration", group);
+    
+    Ok(created.id)
 }
 
 /// Get a specific agent by ID
 pub async fn get_agent(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    match AgentRepository::get_by_id(pool, &id).await {
-        Ok(Some(agent)) => {
-            (StatusCode::OK, Json(AgentResponse::from(agent))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Agent not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to get agent: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get agent"
-            }))).into_response()
-        }
-    }
+    let agent = AgentRepository::get_by_id(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    Ok((StatusCode::OK, Json(agent_response_with_tags(pool, agent).await)))
 }
 
 /// Update an agent
@@ -324,57 +580,286 @@ pub async fn update_agent(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(request): Json<UpdateAgentRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    match AgentRepository::update(
+    if let Some(tls) = request.tls.as_ref().filter(|tls| !tls.is_empty()) {
+        tls.build_client(None).map_err(ApiError::bad_request)?;
+    }
+    let sealed_tls = request.tls.as_ref()
+        .map(|tls| tls.seal(state.cipher.as_ref()))
+        .transpose()
+        .map_err(ApiError::bad_request)?;
+    
+    let mut agent = AgentRepository::update(
         pool,
         &id,
         request.name.as_deref(),
         request.group_id.as_ref().map(|g| g.as_deref()),
-    ).await {
-        Ok(Some(agent)) => {
-            info!("Updated agent: {}", id);
-            (StatusCode::OK, Json(AgentResponse::from(agent))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Agent not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to update agent: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to update agent"
-            }))).into_response()
+    ).await
+        .map_err(|e| ApiError::internal_logged("Failed to update agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    if let Some(tls) = &sealed_tls {
+        agent = AgentRepository::set_tls(pool, &id, tls).await
+            .map_err(|e| ApiError::internal_logged("Failed to update agent", e))?
+            .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    }
+    
+    info!("Updated agent: {}", id);
+    Ok((StatusCode::OK, Json(AgentResponse::from(agent))))
+}
+
+/// Deploy a config version to a single agent
+///
+/// A test deploy: the agent's group config at `config_version` goes to this
+/// agent only, regardless of the group's strategy or approval settings. The
+/// response reports whether the push succeeded.
+pub async fn deploy_to_agent(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    request: Option<Json<AgentDeployRequest>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+    
+    let agent = AgentRepository::get_by_id(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    let group_id = agent.group_id.as_deref()
+        .ok_or_else(|| ApiError::bad_request("Agent is not in a group"))?;
+    let group = WorkerGroupRepository::get_by_id(pool, group_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get group", e))?
+        .ok_or_else(|| ApiError::not_found("Group not found"))?;
+    
+    let config_version = request.config_version
+        .or(group.current_config_version)
+        .ok_or_else(|| ApiError::bad_request("No configuration set for this group"))?;
+    if !matches!(state.git_store.get_config_at_version(&group.name, &config_version), Ok(Some(_))) {
+        return Err(ApiError::not_found(format!(
+            "Version {} has no config for group {}",
+            config_version, group.name
+        )));
+    }
+    
+    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone())
+        .with_events(state.deployment_events.clone())
+        .with_push_limit(state.deployment_push_limit.clone())
+        .with_shutdown(state.shutdown.clone())
+        .with_agent_clients(state.agent_clients.clone());
+    let result = executor.deploy_to_single(&agent.id, &config_version, None).await
+        .map_err(|e| {
+            error!("Failed to deploy to agent {}: {}", agent.id, e);
+            ApiError::bad_request(e)
+        })?;
+    
+    info!("Test deploy of {} to agent '{}': {}", config_version, agent.name, result.status);
+    Ok((StatusCode::CREATED, Json(CreateDeploymentResponse {
+        deployment_id: result.deployment_id,
+        status: result.status,
+        message: result.message,
+        requires_approval: false,
+        queued: false,
+    })))
+}
+
+/// Report from an agent that pulled and applied a config version
+#[derive(Debug, Deserialize)]
+pub struct ConfigAckRequest {
+    /// Config version the agent applied
+    pub version: String,
+    /// Whether the config was applied
+    pub success: bool,
+    /// Why applying failed
+    pub error: Option<String>,
+}
+
+/// Response to a config acknowledgement
+#[derive(Debug, Serialize)]
+pub struct ConfigAckResponse {
+    /// Whether a deployment was waiting on this agent for the version
+    pub acknowledged: bool,
+    /// Deployment whose agent status was updated
+    pub deployment_id: Option<String>,
+}
+
+/// Acknowledge a config version pulled by an agent
+///
+/// Deployments that couldn't push to an agent record it as "Pending sync";
+/// the agent's ack settles that entry as completed or failed. Acks with no
+/// matching pending entry (e.g. the agent polled a version nobody deployed)
+/// are accepted but change nothing.
+pub async fn ack_config(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<ConfigAckRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    if request.version.trim().is_empty() {
+        return Err(ApiError::bad_request("version is required"));
+    }
+    
+    let agent = AgentRepository::get_by_id(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    let error = match request.success {
+        true => None,
+        false => Some(
+            request.error
+                .filter(|e| !e.trim().is_empty())
+                .unwrap_or_else(|| "Agent failed to apply config".to_string()),
+        ),
+    };
+    
+    let deployment_id = DeploymentRepository::ack_pending_sync(pool, &agent.id, &request.version, error.as_deref())
+        .await
+        .map_err(|e| ApiError::internal_logged("Failed to record config acknowledgement", e))?;
+    
+    match (&deployment_id, &error) {
+        (Some(deployment_id), None) => info!(
+            "Agent '{}' applied {} for deployment {}", agent.name, request.version, deployment_id
+        ),
+        (Some(deployment_id), Some(error)) => warn!(
+            "Agent '{}' failed to apply {} for deployment {}: {}", agent.name, request.version, deployment_id, error
+        ),
+        (None, _) => info!("Agent '{}' acknowledged {} with no pending deployment", agent.name, request.version),
+    }
+    
+    Ok((StatusCode::OK, Json(ConfigAckResponse {
+        acknowledged: deployment_id.is_some(),
+        deployment_id,
+    })))
+}
+
+/// Heartbeat from an agent's sidecar
+#[derive(Debug, Default, Deserialize)]
+pub struct HeartbeatRequest {
+    /// Health of the local Vector instance as seen by the sidecar
+    pub vector_health: Option<AgentStatus>,
+}
+
+/// Whether `path` is an agent heartbeat, which is neither audited nor
+/// refused during maintenance
+pub fn is_heartbeat_path(path: &str) -> bool {
+    path.strip_prefix("/api/v1/agents/")
+        .and_then(|rest| rest.strip_suffix("/heartbeat"))
+        .is_some_and(|id| !id.is_empty() && !id.contains('/'))
+}
+
+/// Record that an agent's sidecar is alive
+///
+/// Keeps `last_seen` fresh for agents the control plane can't poll (e.g.
+/// behind a firewall allowing only outbound traffic), so they aren't marked
+/// unreachable by the stale agent sweep.
+pub async fn heartbeat(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    request: Option<Json<HeartbeatRequest>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    let request = request.map(|Json(r)| r).unwrap_or_default();
+    
+    let previous = AgentRepository::get_by_id(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    let status = request.vector_health.map(|health| health.to_string());
+    let agent = AgentRepository::heartbeat(pool, &id, status.as_deref()).await
+        .map_err(|e| ApiError::internal_logged("Failed to record heartbeat", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    if agent.status != previous.status {
+        state.live_events.publish(LiveEvent::AgentStatusChanged {
+            agent_id: agent.id.clone(),
+            agent_name: agent.name.clone(),
+            previous_status: previous.status,
+            status: agent.status.clone(),
+            error: None,
+        });
+    }
+    
+    Ok((StatusCode::OK, Json(AgentResponse::from(agent))))
+}
+
+/// Move an agent to another group without re-registering it
+///
+/// The agent keeps its id, health history and deployment history. With
+/// `deploy`, the target group's current config is pushed to just this agent;
+/// a failed push is reported in the message but doesn't undo the move.
+pub async fn move_agent(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<MoveAgentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let group = match request.group_id.as_deref() {
+        Some(group_id) => Some(
+            WorkerGroupRepository::get_by_id(pool, group_id).await
+                .map_err(|e| ApiError::internal_logged("Failed to get group", e))?
+                .ok_or_else(|| ApiError::not_found("Group not found"))?,
+        ),
+        None => None,
+    };
+    
+    let agent = AgentRepository::set_group(pool, &id, request.group_id.as_deref()).await
+        .map_err(|e| ApiError::internal_logged("Failed to move agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    let mut message = match &group {
+        Some(group) => format!("Agent moved to group {}", group.name),
+        None => "Agent unassigned from group".to_string(),
+    };
+    info!("Agent '{}': {}", agent.name, message);
+    
+    let mut deployment_id = None;
+    if request.deploy {
+        match group.as_ref().and_then(|g| g.current_config_version.as_deref().map(|v| (g, v))) {
+            Some((group, version)) => {
+                let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone())
+                    .with_events(state.deployment_events.clone())
+                    .with_push_limit(state.deployment_push_limit.clone())
+                    .with_shutdown(state.shutdown.clone())
+                    .with_agent_clients(state.agent_clients.clone());
+                match executor.deploy_to_single_agent(&group.id, &agent.id, version, None).await {
+                    Ok(result) => {
+                        message = format!("{}; {}", message, result.message);
+                        deployment_id = Some(result.deployment_id);
+                    }
+                    Err(e) => {
+                        warn!("Failed to deploy group {} config to moved agent {}: {}", group.name, agent.id, e);
+                        message = format!("{}; deploying its config failed: {}", message, e);
+                    }
+                }
+            }
+            None => message = format!("{}; no config to deploy", message),
         }
     }
+    
+    Ok((StatusCode::OK, Json(MoveAgentResponse {
+        agent: AgentResponse::from(agent),
+        deployment_id,
+        message,
+    })))
 }
 
 /// Delete an agent
 pub async fn delete_agent(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    match AgentRepository::delete(pool, &id).await {
-        Ok(true) => {
-            info!("Deleted agent: {}", id);
-            (StatusCode::NO_CONTENT, "").into_response()
-        }
-        Ok(false) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Agent not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to delete agent: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to delete agent"
-            }))).into_response()
-        }
+    let deleted = AgentRepository::delete(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to delete agent", e))?;
+    if !deleted {
+        return Err(ApiError::not_found("Agent not found"));
     }
+    
+    info!("Deleted agent: {}", id);
+    Ok((StatusCode::NO_CONTENT, ""))
 }
 
 /// Get agent health history
@@ -382,28 +867,19 @@ pub async fn get_agent_health(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Query(params): Query<HealthQuery>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     let limit = params.limit.unwrap_or(100);
     
     // Verify agent exists
     if let Ok(None) = AgentRepository::get_by_id(pool, &id).await {
-        return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-            "error": "Agent not found"
-        }))).into_response();
+        return Err(ApiError::not_found("Agent not found"));
     }
     
-    match AgentRepository::get_health_checks(pool, &id, limit).await {
-        Ok(checks) => {
-            (StatusCode::OK, Json(checks)).into_response()
-        }
-        Err(e) => {
-            error!("Failed to get health checks: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get health checks"
-            }))).into_response()
-        }
-    }
+    let checks = AgentRepository::get_health_checks(pool, &id, limit).await
+        .map_err(|e| ApiError::internal_logged("Failed to get health checks", e))?;
+    
+    Ok((StatusCode::OK, Json(checks)))
 }
 
 #[derive(Debug, Deserialize)]
@@ -411,6 +887,176 @@ pub struct HealthQuery {
     pub limit: Option<i64>,
 }
 
+/// Query parameters for uptime endpoints
+#[derive(Debug, Deserialize)]
+pub struct UptimeQuery {
+    /// Start of the window (RFC 3339); defaults to 24 hours ago
+    pub since: Option<String>,
+}
+
+/// An uptime response with the start of the window it covers
+#[derive(Debug, Serialize)]
+pub struct UptimeReport<T> {
+    /// RFC 3339
+    pub since: String,
+    #[serde(flatten)]
+    pub uptime: T,
+}
+
+/// Uptime of one agent over a window
+#[derive(Debug, Serialize)]
+pub struct AgentUptimeResponse {
+    pub agent_id: String,
+    pub agent_name: String,
+    pub healthy_checks: i64,
+    pub total_checks: i64,
+    /// Healthy checks as a percentage; null when no checks were recorded
+    pub uptime_percentage: Option<f64>,
+}
+
+impl AgentUptimeResponse {
+    pub(crate) fn new(agent_id: String, agent_name: String, counts: UptimeCounts) -> Self {
+        Self {
+            agent_id,
+            agent_name,
+            healthy_checks: counts.healthy_checks,
+            total_checks: counts.total_checks,
+            uptime_percentage: counts.percentage(),
+        }
+    }
+}
+
+/// Resolve an uptime window start to the `checked_at` format
+///
+/// Returns the RFC 3339 form for the response and the database form.
+pub(crate) fn uptime_window(since: Option<&str>) -> Result<(String, String), ApiError> {
+    let since = match since {
+        Some(since) => chrono::DateTime::parse_from_rfc3339(since)
+            .map_err(|e| ApiError::bad_request(format!("Invalid 'since' timestamp: {}", e)))?
+            .with_timezone(&chrono::Utc),
+        None => chrono::Utc::now() - chrono::Duration::hours(24),
+    };
+    Ok((since.to_rfc3339(), since.format("%Y-%m-%d %H:%M:%S").to_string()))
+}
+
+/// Get an agent's uptime from its recorded health checks
+pub async fn get_agent_uptime(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<UptimeQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    let (since, since_db) = uptime_window(params.since.as_deref())?;
+    
+    let agent = AgentRepository::get_by_id(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    let counts = AgentRepository::uptime_counts(pool, &agent.id, &since_db).await
+        .map_err(|e| ApiError::internal_logged("Failed to compute uptime", e))?;
+    
+    Ok((StatusCode::OK, Json(UptimeReport {
+        since,
+        uptime: AgentUptimeResponse::new(agent.id, agent.name, counts),
+    })))
+}
+
+/// Drift between an agent's running config and its group's current config
+#[derive(Debug, Serialize)]
+pub struct ConfigDiffResponse {
+    pub agent_id: String,
+    pub group_id: String,
+    /// Group config version compared against
+    pub config_version: Option<String>,
+    pub in_sync: bool,
+    /// Unified diff from the group config to the running config; empty when in sync
+    pub diff: String,
+}
+
+/// Compare an agent's running config with its group's current config
+///
+/// The agent's config comes from its `/api/config` endpoint. Both sides are
+/// re-rendered as TOML with sorted keys before diffing, so formatting,
+/// comments and key order don't count as drift.
+pub async fn get_agent_config_diff(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let agent = AgentRepository::get_by_id(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    let group_id = agent.group_id.clone()
+        .ok_or_else(|| ApiError::bad_request("Agent is not in a group"))?;
+    let group = WorkerGroupRepository::get_by_id(pool, &group_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get group", e))?
+        .ok_or_else(|| ApiError::not_found("Group not found"))?;
+    
+    let expected = state.git_store.read_config(&group.name)
+        .map_err(|e| ApiError::internal_logged("Failed to read group config", e))?
+        .ok_or_else(|| ApiError::bad_request("No configuration set for this group"))?;
+    let expected: toml::Value = toml::from_str(&expected)
+        .map_err(|e| ApiError::internal_logged("Group config is not valid TOML", e))?;
+    
+    let config_url = format!("{}/api/config", agent.url.trim_end_matches('/'));
+    let response = state.agent_clients.for_agent(&agent)
+        .get(&config_url)
+        .with_request_id()
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| ApiError::bad_gateway(format!("Failed to fetch running config from agent: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(ApiError::bad_gateway(format!(
+            "Agent returned status {} for its running config",
+            response.status()
+        )));
+    }
+    let running: serde_json::Value = response.json().await
+        .map_err(|e| ApiError::bad_gateway(format!("Agent returned an invalid config: {}", e)))?;
+    let running = toml::Value::try_from(running)
+        .map_err(|e| ApiError::bad_gateway(format!("Agent returned an invalid config: {}", e)))?;
+    
+    let expected = normalized_config(&expected)?;
+    let running = normalized_config(&running)?;
+    let in_sync = expected == running;
+    let diff = if in_sync {
+        String::new()
+    } else {
+        crate::git_store::GitStore::diff_text(
+            &format!("{}/config.toml", group.name), &expected,
+            &format!("agent/{}/config.toml", agent.name), &running,
+        ).map_err(|e| ApiError::internal_logged("Failed to diff configs", e))?
+    };
+    
+    if !in_sync {
+        warn!("Agent '{}' is running a config that differs from group '{}'", agent.name, group.name);
+    }
+    
+    Ok((StatusCode::OK, Json(ConfigDiffResponse {
+        agent_id: agent.id,
+        group_id,
+        config_version: group.current_config_version,
+        in_sync,
+        diff,
+    })))
+}
+
+/// Render a config as TOML with sorted keys and uniform formatting
+fn normalized_config(config: &toml::Value) -> Result<String, ApiError> {
+    toml::to_string_pretty(config)
+        .map_err(|e| ApiError::internal_logged("Failed to render config", e))
+}
+
+/// Build an agent response including its tags
+async fn agent_response_with_tags(pool: &crate::db::DbPool, agent: Agent) -> AgentResponse {
+    let tags = AgentRepository::get_tags(pool, &agent.id).await.unwrap_or_default();
+    let mut response = AgentResponse::from(agent);
+    response.tags = tags.into_iter().map(|t| (t.key, t.value)).collect();
+    response
+}
+
 /// Get Vector version from an agent
 async fn get_vector_version(client: &reqwest::Client, base_url: &str) -> Option<String> {
     let graphql_url = format!("{}/graphql", base_url.trim_end_matches('/'));
@@ -420,6 +1066,7 @@ async fn get_vector_version(client: &reqwest::Client, base_url: &str) -> Option<
     });
     
     let response = client.post(&graphql_url)
+        .with_request_id()
         .json(&query)
         .timeout(std::time::Duration::from_secs(5))
         .send()
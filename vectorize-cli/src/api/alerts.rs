@@ -23,6 +23,9 @@ pub struct CreateAlertRuleRequest {
     pub condition: serde_json::Value,
     pub severity: AlertSeverity,
     pub notification_channels: Vec<String>,
+    /// Custom message with `{{field}}` placeholders
+    #[serde(default)]
+    pub message_template: Option<String>,
 }
 
 /// Request to create a notification channel
@@ -47,6 +50,7 @@ pub struct AlertRuleResponse {
     pub severity: AlertSeverity,
     pub enabled: bool,
     pub notification_channels: Vec<String>,
+    pub message_template: Option<String>,
 }
 
 /// Response with notification channels
@@ -92,6 +96,7 @@ pub async fn create_alert_rule(
         severity: request.severity,
         enabled: true,
         notification_channels: request.notification_channels,
+        message_template: request.message_template,
     };
     
     (StatusCode::CREATED, Json(response)).into_response()
@@ -0,0 +1,156 @@
+//! API key management endpoints
+//!
+//! Keys are generated server-side and returned once on creation; only their
+//! SHA-256 hash is stored.
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::info;
+
+use crate::AppState;
+use crate::api::ApiError;
+use crate::db::models::ApiKeyResponse;
+use crate::db::repository::{ApiKeyRepository, RoleRepository};
+use crate::rbac::{self, AuthenticatedUser, require_authenticated, require_permission};
+
+/// Request to create an API key
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// Role the key acts as (defaults to the creator's role)
+    pub role_id: Option<String>,
+    /// Restrict the key to these permissions instead of the role's
+    pub permissions: Option<Vec<String>>,
+    /// Expire the key after this many days
+    pub expires_in_days: Option<u32>,
+}
+
+/// Response for a newly created key
+#[derive(Debug, Serialize)]
+pub struct CreateApiKeyResponse {
+    /// The full key; it cannot be retrieved again
+    pub key: String,
+    pub api_key: ApiKeyResponse,
+}
+
+/// Response with API key list
+#[derive(Debug, Serialize)]
+pub struct ApiKeysResponse {
+    pub api_keys: Vec<ApiKeyResponse>,
+}
+
+/// List API keys
+///
+/// Users with `api_keys_read` see every key; others see only their own.
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<AuthenticatedUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = require_authenticated(user)?;
+    
+    let pool = state.db.pool();
+    let keys = if user.has_permission("api_keys_read") {
+        ApiKeyRepository::list(pool).await
+    } else {
+        ApiKeyRepository::list_by_user(pool, &user.user_id).await
+    };
+    let keys = keys.map_err(|e| ApiError::internal_logged("Failed to list API keys", e))?;
+    
+    let api_keys = keys.into_iter().map(ApiKeyResponse::from).collect();
+    Ok((StatusCode::OK, Json(ApiKeysResponse { api_keys })))
+}
+
+/// Create an API key owned by the caller
+///
+/// A key can't grant more than its creator has: binding another role
+/// requires system admin, and an explicit permission list may only contain
+/// permissions the creator holds.
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<AuthenticatedUser>>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = require_authenticated(user)?;
+    require_permission(&user, "api_keys_write")?;
+    
+    if request.name.trim().is_empty() {
+        return Err(ApiError::bad_request("Name is required"));
+    }
+    
+    let pool = state.db.pool();
+    
+    if let Some(role_id) = &request.role_id {
+        if role_id != &user.role_id && !user.has_permission("system_admin") {
+            return Err(ApiError::forbidden("Only administrators can create keys for another role"));
+        }
+        RoleRepository::get_by_id(pool, role_id).await
+            .map_err(|e| ApiError::internal_logged("Failed to create API key", e))?
+            .ok_or_else(|| ApiError::bad_request(format!("Role not found: {}", role_id)))?;
+    }
+    
+    if let Some(permissions) = &request.permissions {
+        let missing: Vec<&String> = permissions.iter().filter(|p| !user.has_permission(p)).collect();
+        if !missing.is_empty() {
+            return Err(ApiError::forbidden("Cannot grant permissions you do not have")
+                .with_details(serde_json::json!({ "permissions": missing })));
+        }
+    }
+    
+    let permissions = request.permissions.as_ref().map(|p| serde_json::json!(p).to_string());
+    let expires_at = request.expires_in_days.map(|days| {
+        (chrono::Utc::now() + chrono::Duration::days(days as i64)).to_rfc3339()
+    });
+    
+    let generated = rbac::generate_api_key();
+    
+    let api_key = ApiKeyRepository::create(
+        pool,
+        request.name.trim(),
+        &generated.hash,
+        &generated.prefix,
+        Some(&user.user_id),
+        request.role_id.as_deref(),
+        permissions.as_deref(),
+        expires_at.as_deref(),
+    ).await
+        .map_err(|e| ApiError::internal_logged("Failed to create API key", e))?;
+    
+    info!("API key {} created by {}", api_key.key_prefix, user.user_id);
+    Ok((StatusCode::CREATED, Json(CreateApiKeyResponse {
+        key: generated.key,
+        api_key: ApiKeyResponse::from(api_key),
+    })))
+}
+
+/// Revoke an API key
+///
+/// Users can revoke their own keys; revoking others' requires `api_keys_delete`.
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<AuthenticatedUser>>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = require_authenticated(user)?;
+    
+    let pool = state.db.pool();
+    
+    let api_key = ApiKeyRepository::get_by_id(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to revoke API key", e))?
+        .ok_or_else(|| ApiError::not_found("API key not found"))?;
+    
+    if api_key.user_id.as_deref() != Some(user.user_id.as_str()) {
+        require_permission(&user, "api_keys_delete")?;
+    }
+    
+    ApiKeyRepository::revoke(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to revoke API key", e))?;
+    
+    info!("API key {} revoked by {}", api_key.key_prefix, user.user_id);
+    Ok(StatusCode::NO_CONTENT)
+}
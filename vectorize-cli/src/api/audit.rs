@@ -1,27 +1,41 @@
 //! Audit logging API endpoints
 //!
-//! Provides endpoints for viewing audit logs.
+//! Provides endpoints for viewing audit logs, and the middleware that records
+//! every mutating API call into them.
 
 use axum::{
-    extract::{Extension, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::Body,
+    extract::{ConnectInfo, Extension, MatchedPath, Query, Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tracing::error;
 
 use crate::AppState;
+use crate::api::ApiError;
+use crate::api::agents::is_heartbeat_path;
 use crate::db::models::AuditLogResponse;
 use crate::db::repository::AuditLogRepository;
-use crate::rbac::{AuthenticatedUser, require_permission};
+use crate::rbac::{AuthenticatedUser, require_authenticated, require_permission};
+
+/// Header the CLI uses to identify the operator (`--user` / `VECTORIZE_USER`)
+pub const CLI_USER_HEADER: &str = "x-vectorize-user";
+
+/// JSON bodies up to this size are buffered to summarize them in the audit log
+const MAX_SUMMARY_BODY_BYTES: usize = 64 * 1024;
 
 /// Query parameters for audit log listing
 #[derive(Debug, Deserialize)]
 pub struct AuditLogQuery {
     /// Filter by actor ID (user ID)
     pub actor_id: Option<String>,
+    /// Filter by user (matches actor ID or actor name)
+    pub user: Option<String>,
     /// Filter by action type
     pub action: Option<String>,
     /// Filter by resource type
@@ -44,53 +58,49 @@ pub struct AuditLogListResponse {
 /// List audit log entries
 pub async fn list_audit_logs(
     State(state): State<Arc<AppState>>,
-    Extension(user): Extension<AuthenticatedUser>,
+    user: Option<Extension<AuthenticatedUser>>,
     Query(query): Query<AuditLogQuery>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "audit_read") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let user = require_authenticated(user)?;
+    require_permission(&user, "audit_read")?;
     
-    let limit = query.limit.unwrap_or(50).min(1000);
-    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(50).clamp(1, 1000);
+    let offset = query.offset.unwrap_or(0).max(0);
     
     let pool = state.db.pool();
     
     // Get entries
-    let entries = match AuditLogRepository::list(
+    let entries = AuditLogRepository::list(
         pool,
         query.actor_id.as_deref(),
+        query.user.as_deref(),
         query.action.as_deref(),
         query.resource_type.as_deref(),
         limit,
         offset,
-    ).await {
-        Ok(entries) => entries,
-        Err(e) => {
-            error!("Failed to list audit logs: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list audit logs"
-            }))).into_response();
-        }
-    };
+    ).await
+        .map_err(|e| ApiError::internal_logged("Failed to list audit logs", e))?;
     
     // Get total count for pagination
-    let total: i64 = match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM audit_log")
-        .fetch_one(pool)
-        .await
-    {
+    let total = match AuditLogRepository::count(
+        pool,
+        query.actor_id.as_deref(),
+        query.user.as_deref(),
+        query.action.as_deref(),
+        query.resource_type.as_deref(),
+    ).await {
         Ok(count) => count,
         Err(_) => entries.len() as i64,
     };
     
     let entry_responses: Vec<AuditLogResponse> = entries.into_iter().map(AuditLogResponse::from).collect();
     
-    (StatusCode::OK, Json(AuditLogListResponse {
+    Ok((StatusCode::OK, Json(AuditLogListResponse {
         entries: entry_responses,
         total,
         limit,
         offset,
-    })).into_response()
+    })))
 }
 
 /// Available actions for filtering
@@ -109,11 +119,10 @@ pub struct AuditAction {
 
 /// List available audit actions
 pub async fn list_audit_actions(
-    Extension(user): Extension<AuthenticatedUser>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "audit_read") {
-        return resp;
-    }
+    user: Option<Extension<AuthenticatedUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = require_authenticated(user)?;
+    require_permission(&user, "audit_read")?;
     
     let actions = vec![
         // Authentication
@@ -155,7 +164,156 @@ pub async fn list_audit_actions(
         AuditAction { name: "alert.delete".into(), description: "Alert rule deleted".into(), category: "Alerts".into() },
     ];
     
-    (StatusCode::OK, Json(AuditActionsResponse { actions })).into_response()
+    Ok((StatusCode::OK, Json(AuditActionsResponse { actions })))
+}
+
+// =============================================================================
+// Audit Middleware
+// =============================================================================
+
+/// Middleware that records every mutating API call in the audit log
+///
+/// Runs inside `rbac::optional_auth_middleware` so the authenticated user is
+/// available. Requests without a token are attributed to an anonymous actor;
+/// the CLI user header is unverified, so it is only kept as
+/// `details.claimed_user`. Only the top-level field names of JSON bodies are
+/// kept, never their values.
+pub async fn record_mutations(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    if !is_audited(req.method(), &path) {
+        return next.run(req).await;
+    }
+    
+    let method = req.method().clone();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| path.clone());
+    let user = req.extensions().get::<AuthenticatedUser>().cloned();
+    let ip_address = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+    let headers = req.headers().clone();
+    
+    let (req, body) = match summarize_body(req).await {
+        Ok(summarized) => summarized,
+        Err(resp) => return resp,
+    };
+    
+    let response = next.run(req).await;
+    let status = response.status();
+    
+    let (actor_type, actor_id) = match &user {
+        Some(user) => ("user", Some(user.user_id.clone())),
+        None => ("anonymous", None),
+    };
+    let (resource_type, resource_id) = resource_from_path(&route, &path);
+    let result = match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => "denied",
+        s if s.is_client_error() || s.is_server_error() => "failure",
+        _ => "success",
+    };
+    
+    let mut details = serde_json::json!({
+        "method": method.as_str(),
+        "path": path,
+        "status": status.as_u16(),
+    });
+    if let Some(body) = body {
+        details["body"] = body;
+    }
+    if user.is_none() {
+        if let Some(name) = header_str(&headers, CLI_USER_HEADER) {
+            details["claimed_user"] = name.into();
+        }
+    }
+    
+    log_audit_event(
+        state.db.pool(),
+        actor_type,
+        actor_id.as_deref(),
+        None,
+        &format!("{} {}", method, route),
+        resource_type.as_deref(),
+        resource_id.as_deref(),
+        Some(details),
+        ip_address.as_deref(),
+        header_str(&headers, header::USER_AGENT.as_str()),
+        result,
+    ).await;
+    
+    response
+}
+
+/// Whether a request is recorded: non-read methods on API paths, except the
+/// handshake and agent heartbeats
+fn is_audited(method: &Method, path: &str) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        && path.starts_with("/api/")
+        && path != "/api/v1/handshake"
+        && !is_heartbeat_path(path)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok()).filter(|v| !v.is_empty())
+}
+
+/// Buffer small JSON bodies and summarize them as their top-level field names
+///
+/// Returns the request with its body restored. Other bodies pass through
+/// untouched and get no summary.
+async fn summarize_body(req: Request) -> Result<(Request, Option<serde_json::Value>), Response> {
+    let is_json = header_str(req.headers(), header::CONTENT_TYPE.as_str())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    let small = header_str(req.headers(), header::CONTENT_LENGTH.as_str())
+        .and_then(|len| len.parse::<usize>().ok())
+        .is_some_and(|len| len <= MAX_SUMMARY_BODY_BYTES);
+    if !is_json || !small {
+        return Ok((req, None));
+    }
+    
+    let (parts, body) = req.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_SUMMARY_BODY_BYTES).await
+        .map_err(|_| ApiError::bad_request("Failed to read request body").into_response())?;
+    
+    let summary = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(fields)) => Some(serde_json::json!({
+            "fields": fields.keys().collect::<Vec<_>>(),
+        })),
+        _ => None,
+    };
+    
+    Ok((Request::from_parts(parts, Body::from(bytes)), summary))
+}
+
+/// Derive the resource type and ID from an `/api/v1/<type>/<id>/...` route
+///
+/// The ID is only taken when the route has a parameter in that position, so
+/// `/api/v1/agents/unassigned` yields no ID.
+fn resource_from_path(route: &str, path: &str) -> (Option<String>, Option<String>) {
+    let Some(route) = route.strip_prefix("/api/v1/") else {
+        return (None, None);
+    };
+    let path = path.strip_prefix("/api/v1/").unwrap_or(path);
+    
+    let mut route_segments = route.split('/');
+    let mut path_segments = path.split('/');
+    
+    let resource_type = route_segments.next().filter(|s| !s.is_empty()).map(str::to_string);
+    path_segments.next();
+    
+    let resource_id = match (route_segments.next(), path_segments.next()) {
+        (Some(param), Some(id)) if param.starts_with(':') => Some(id.to_string()),
+        _ => None,
+    };
+    
+    (resource_type, resource_id)
 }
 
 // =============================================================================
@@ -163,9 +321,9 @@ pub async fn list_audit_actions(
 // =============================================================================
 
 /// Log an audit event
-#[allow(dead_code)]
+#[allow(clippy::too_many_arguments)]
 pub async fn log_audit_event(
-    pool: &sqlx::SqlitePool,
+    pool: &crate::db::DbPool,
     actor_type: &str,
     actor_id: Option<&str>,
     actor_name: Option<&str>,
@@ -195,3 +353,37 @@ pub async fn log_audit_event(
         error!("Failed to create audit log: {}", e);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_is_audited() {
+        assert!(is_audited(&Method::POST, "/api/v1/groups"));
+        assert!(is_audited(&Method::DELETE, "/api/v1/agents/a1"));
+        assert!(is_audited(&Method::POST, "/api/config"));
+        assert!(!is_audited(&Method::GET, "/api/v1/groups"));
+        assert!(!is_audited(&Method::OPTIONS, "/api/v1/groups"));
+        assert!(!is_audited(&Method::POST, "/login"));
+        assert!(!is_audited(&Method::POST, "/api/v1/handshake"));
+        assert!(!is_audited(&Method::POST, "/api/v1/agents/a1/heartbeat"));
+    }
+    
+    #[test]
+    fn test_resource_from_path() {
+        assert_eq!(
+            resource_from_path("/api/v1/groups/:id/deploy", "/api/v1/groups/g1/deploy"),
+            (Some("groups".to_string()), Some("g1".to_string()))
+        );
+        assert_eq!(
+            resource_from_path("/api/v1/agents", "/api/v1/agents"),
+            (Some("agents".to_string()), None)
+        );
+        assert_eq!(
+            resource_from_path("/api/v1/agents/unassigned", "/api/v1/agents/unassigned"),
+            (Some("agents".to_string()), None)
+        );
+        assert_eq!(resource_from_path("/api/config", "/api/config"), (None, None));
+    }
+}
@@ -4,6 +4,10 @@
 //! - First-time setup wizard
 //! - User login/logout
 //! - Session management
+//!
+//! Headless installs can skip the wizard: `VECTORIZE_BOOTSTRAP_ADMIN`
+//! creates the admin at startup, and `VECTORIZE_BOOTSTRAP_TOKEN` makes
+//! `/setup/init` require a one-time token.
 
 use axum::{
     extract::State,
@@ -12,22 +16,33 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tracing::{info, warn, error};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
 
 use crate::AppState;
-use crate::db::models::UserResponse;
+use crate::api::ApiError;
+use crate::db::Database;
+use crate::db::models::{User, UserResponse};
 use crate::db::repository::UserRepository;
 
+/// Environment variable holding `username:email:password` for the first admin
+pub const BOOTSTRAP_ADMIN_ENV: &str = "VECTORIZE_BOOTSTRAP_ADMIN";
+
+/// Environment variable holding the one-time token for `/setup/init`
+pub const BOOTSTRAP_TOKEN_ENV: &str = "VECTORIZE_BOOTSTRAP_TOKEN";
+
 /// Response for setup status check
 #[derive(Debug, Serialize)]
 pub struct SetupStatusResponse {
     /// Whether setup has been completed (admin user exists)
     pub is_setup: bool,
+    /// Whether `/setup/init` needs a bootstrap token
+    pub requires_bootstrap_token: bool,
     /// Application version
     pub version: String,
 }
@@ -41,6 +56,9 @@ pub struct SetupInitRequest {
     pub email: String,
     /// Password for the admin user
     pub password: String,
+    /// One-time token, required when `VECTORIZE_BOOTSTRAP_TOKEN` is set
+    #[serde(default)]
+    pub bootstrap_token: Option<String>,
 }
 
 /// Response for setup initialization
@@ -69,124 +87,246 @@ pub struct LoginResponse {
     pub user: Option<UserResponse>,
 }
 
-/// Check if initial setup has been completed
-pub async fn setup_status(
-    State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    match state.db.is_fresh().await {
-        Ok(is_fresh) => {
-            (StatusCode::OK, Json(SetupStatusResponse {
-                is_setup: !is_fresh,
-                version: env!("CARGO_PKG_VERSION").to_string(),
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to check setup status: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to check setup status"
-            }))).into_response()
+/// Admin account for a non-interactive install
+///
+/// Parsed from `username:email:password`; the password is everything after
+/// the second colon, so it may contain colons itself.
+#[derive(Clone)]
+pub struct BootstrapAdmin {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+}
+
+impl BootstrapAdmin {
+    /// Read `VECTORIZE_BOOTSTRAP_ADMIN`, if set
+    pub fn from_env() -> Result<Option<Self>, String> {
+        match std::env::var(BOOTSTRAP_ADMIN_ENV) {
+            Ok(value) if !value.trim().is_empty() => value.parse().map(Some),
+            _ => Ok(None),
         }
     }
 }
 
-/// Initialize the application with the first admin user
-pub async fn setup_init(
-    State(state): State<Arc<AppState>>,
-    Json(request): Json<SetupInitRequest>,
-) -> impl IntoResponse {
-    let pool = state.db.pool();
+impl FromStr for BootstrapAdmin {
+    type Err = String;
     
-    // Check if setup has already been completed
-    match state.db.is_fresh().await {
-        Ok(true) => {}, // Fresh database, proceed with setup
-        Ok(false) => {
-            return (StatusCode::CONFLICT, Json(SetupInitResponse {
-                success: false,
-                message: "Setup has already been completed".to_string(),
-                user: None,
-            })).into_response();
-        }
-        Err(e) => {
-            error!("Failed to check setup status: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(SetupInitResponse {
-                success: false,
-                message: "Failed to check setup status".to_string(),
-                user: None,
-            })).into_response();
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut parts = value.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(username), Some(email), Some(password)) => {
+                validate_admin(username, email, password).map_err(|e| format!("{}: {}", BOOTSTRAP_ADMIN_ENV, e))?;
+                Ok(Self {
+                    username: username.to_string(),
+                    email: email.to_string(),
+                    password: password.to_string(),
+                })
+            }
+            _ => Err(format!("{} must be username:email:password", BOOTSTRAP_ADMIN_ENV)),
         }
     }
+}
+
+// The password must never reach the logs
+impl std::fmt::Debug for BootstrapAdmin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BootstrapAdmin")
+            .field("username", &self.username)
+            .field("email", &self.email)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Create the first admin without the setup wizard
+///
+/// Returns `None` and leaves the database alone when setup has already
+/// been completed, so the variable can stay set across restarts.
+pub async fn bootstrap_admin(db: &Database, admin: &BootstrapAdmin) -> Result<Option<User>, String> {
+    let is_fresh = db.is_fresh().await
+        .map_err(|e| format!("Failed to check setup status: {}", e))?;
+    if !is_fresh {
+        return Ok(None);
+    }
     
-    // Validate input
-    if request.username.len() < 3 {
-        return (StatusCode::BAD_REQUEST, Json(SetupInitResponse {
-            success: false,
-            message: "Username must be at least 3 characters".to_string(),
-            user: None,
-        })).into_response();
+    validate_admin(&admin.username, &admin.email, &admin.password)?;
+    let user = create_admin(db, &admin.username, &admin.email, &admin.password)
+        .await
+        .map_err(|e| format!("Failed to create admin user: {}", e))?;
+    
+    info!("Initial admin user created from {}: {}", BOOTSTRAP_ADMIN_ENV, admin.username);
+    Ok(Some(user))
+}
+
+/// One-time token guarding `/setup/init`
+///
+/// Without a token the wizard is open to the first caller, as before. Once
+/// a setup succeeds the token is spent and never accepted again.
+#[derive(Debug, Default)]
+pub struct BootstrapToken {
+    state: Mutex<TokenState>,
+}
+
+#[derive(Debug, Default)]
+enum TokenState {
+    #[default]
+    Unset,
+    Pending(String),
+    Claimed,
+    Spent,
+}
+
+impl BootstrapToken {
+    /// Require `token` for setup; blank tokens are treated as unset
+    pub fn new(token: Option<String>) -> Self {
+        let state = match token {
+            Some(token) if !token.trim().is_empty() => TokenState::Pending(token.trim().to_string()),
+            _ => TokenState::Unset,
+        };
+        Self { state: Mutex::new(state) }
     }
     
-    if request.password.len() < 8 {
-        return (StatusCode::BAD_REQUEST, Json(SetupInitResponse {
-            success: false,
-            message: "Password must be at least 8 characters".to_string(),
-            user: None,
-        })).into_response();
+    /// Read `VECTORIZE_BOOTSTRAP_TOKEN`
+    pub fn from_env() -> Self {
+        Self::new(std::env::var(BOOTSTRAP_TOKEN_ENV).ok())
     }
     
-    // Validate email format (basic check)
-    if !request.email.contains('@') || !request.email.contains('.') {
-        return (StatusCode::BAD_REQUEST, Json(SetupInitResponse {
-            success: false,
-            message: "Invalid email format".to_string(),
-            user: None,
-        })).into_response();
+    /// Whether setup must present a token
+    pub fn is_required(&self) -> bool {
+        !matches!(*self.lock(), TokenState::Unset)
     }
     
-    // Hash the password
-    let password_hash = match hash_password(&request.password) {
-        Ok(hash) => hash,
-        Err(e) => {
-            error!("Failed to hash password: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(SetupInitResponse {
-                success: false,
-                message: "Failed to process password".to_string(),
-                user: None,
-            })).into_response();
+    /// Take the token for one setup attempt
+    ///
+    /// The claim is released again if it is dropped before `spend`, so a
+    /// failed setup can be retried; concurrent attempts are refused.
+    fn claim(&self, candidate: Option<&str>) -> Result<TokenClaim<'_>, ApiError> {
+        let mut state = self.lock();
+        let token = match &*state {
+            TokenState::Unset => return Ok(TokenClaim { owner: self, token: None }),
+            TokenState::Pending(token) => token.clone(),
+            TokenState::Claimed => return Err(ApiError::conflict("Setup is already in progress")),
+            TokenState::Spent => return Err(ApiError::unauthorized("Bootstrap token has already been used")),
+        };
+        
+        match candidate {
+            Some(candidate) if constant_time_eq(candidate.trim(), &token) => {
+                *state = TokenState::Claimed;
+                Ok(TokenClaim { owner: self, token: Some(token) })
+            }
+            Some(_) => Err(ApiError::unauthorized("Invalid bootstrap token")),
+            None => Err(ApiError::unauthorized("A bootstrap token is required to complete setup")),
         }
-    };
+    }
     
-    // Create the admin user
-    match UserRepository::create(
-        pool,
-        &request.username,
-        &request.email,
-        &password_hash,
-        "admin", // Use the built-in admin role
-    ).await {
-        Ok(user) => {
-            info!("Initial admin user created: {}", request.username);
-            (StatusCode::CREATED, Json(SetupInitResponse {
-                success: true,
-                message: "Admin user created successfully".to_string(),
-                user: Some(UserResponse::from(user)),
-            })).into_response()
+    fn lock(&self) -> std::sync::MutexGuard<'_, TokenState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A claimed bootstrap token, returned to the pool unless spent
+struct TokenClaim<'a> {
+    owner: &'a BootstrapToken,
+    token: Option<String>,
+}
+
+impl TokenClaim<'_> {
+    fn spend(mut self) {
+        if self.token.take().is_some() {
+            *self.owner.lock() = TokenState::Spent;
         }
-        Err(e) => {
-            error!("Failed to create admin user: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(SetupInitResponse {
-                success: false,
-                message: format!("Failed to create admin user: {}", e),
-                user: None,
-            })).into_response()
+    }
+}
+
+impl Drop for TokenClaim<'_> {
+    fn drop(&mut self) {
+        if let Some(token) = self.token.take() {
+            *self.owner.lock() = TokenState::Pending(token);
         }
     }
 }
 
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Check the fields of a new admin account
+fn validate_admin(username: &str, email: &str, password: &str) -> Result<(), String> {
+    if username.len() < 3 {
+        return Err("Username must be at least 3 characters".to_string());
+    }
+    
+    if password.len() < 8 {
+        return Err("Password must be at least 8 characters".to_string());
+    }
+    
+    // Validate email format (basic check)
+    if !email.contains('@') || !email.contains('.') {
+        return Err("Invalid email format".to_string());
+    }
+    
+    Ok(())
+}
+
+/// Hash the password and create the user with the built-in admin role
+async fn create_admin(db: &Database, username: &str, email: &str, password: &str) -> Result<User, String> {
+    let password_hash = hash_password(password)?;
+    
+    UserRepository::create(db.pool(), username, email, &password_hash, "admin")
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Check if initial setup has been completed
+pub async fn setup_status(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let is_fresh = state.db.is_fresh().await
+        .map_err(|e| ApiError::internal_logged("Failed to check setup status", e))?;
+    
+    Ok((StatusCode::OK, Json(SetupStatusResponse {
+        is_setup: !is_fresh,
+        requires_bootstrap_token: is_fresh && state.bootstrap_token.is_required(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    })))
+}
+
+/// Initialize the application with the first admin user
+pub async fn setup_init(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SetupInitRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Check if setup has already been completed
+    let is_fresh = state.db.is_fresh().await
+        .map_err(|e| ApiError::internal_logged("Failed to check setup status", e))?;
+    if !is_fresh {
+        return Err(ApiError::conflict("Setup has already been completed"));
+    }
+    
+    // Validate input
+    validate_admin(&request.username, &request.email, &request.password)
+        .map_err(ApiError::bad_request)?;
+    
+    // Holding the claim keeps a second caller from racing this one
+    let claim = state.bootstrap_token.claim(request.bootstrap_token.as_deref())?;
+    
+    let user = create_admin(&state.db, &request.username, &request.email, &request.password)
+        .await
+        .map_err(|e| ApiError::internal_logged("Failed to create admin user", e))?;
+    claim.spend();
+    
+    info!("Initial admin user created: {}", request.username);
+    Ok((StatusCode::CREATED, Json(SetupInitResponse {
+        success: true,
+        message: "Admin user created successfully".to_string(),
+        user: Some(UserResponse::from(user)),
+    })))
+}
+
 /// Login with username/email and password
 pub async fn login(
     State(state): State<Arc<AppState>>,
     Json(request): Json<LoginRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Try to find user by username or email
@@ -196,58 +336,24 @@ pub async fn login(
         UserRepository::get_by_username(pool, &request.identifier).await
     };
     
-    let user = match user {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            return (StatusCode::UNAUTHORIZED, Json(LoginResponse {
-                success: false,
-                message: "Invalid credentials".to_string(),
-                token: None,
-                user: None,
-            })).into_response();
-        }
-        Err(e) => {
-            error!("Database error during login: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(LoginResponse {
-                success: false,
-                message: "Login failed".to_string(),
-                token: None,
-                user: None,
-            })).into_response();
-        }
-    };
+    let user = user
+        .map_err(|e| ApiError::internal_logged("Login failed", e))?
+        .ok_or_else(|| ApiError::unauthorized("Invalid credentials"))?;
     
     // Check if user is active
     if !user.is_active {
-        return (StatusCode::UNAUTHORIZED, Json(LoginResponse {
-            success: false,
-            message: "Account is disabled".to_string(),
-            token: None,
-            user: None,
-        })).into_response();
+        return Err(ApiError::unauthorized("Account is disabled"));
     }
     
     // Verify password
     let password_hash = match &user.password_hash {
         Some(hash) => hash,
-        None => {
-            // SSO user trying to login with password
-            return (StatusCode::UNAUTHORIZED, Json(LoginResponse {
-                success: false,
-                message: "Please use SSO to login".to_string(),
-                token: None,
-                user: None,
-            })).into_response();
-        }
+        // SSO user trying to login with password
+        None => return Err(ApiError::unauthorized("Please use SSO to login")),
     };
     
     if !verify_password(&request.password, password_hash) {
-        return (StatusCode::UNAUTHORIZED, Json(LoginResponse {
-            success: false,
-            message: "Invalid credentials".to_string(),
-            token: None,
-            user: None,
-        })).into_response();
+        return Err(ApiError::unauthorized("Invalid credentials"));
     }
     
     // Update last login time
@@ -256,27 +362,17 @@ pub async fn login(
     }
     
     // Generate JWT token
-    let token = match generate_jwt(&user.id, &user.role_id) {
-        Ok(token) => token,
-        Err(e) => {
-            error!("Failed to generate JWT: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(LoginResponse {
-                success: false,
-                message: "Failed to create session".to_string(),
-                token: None,
-                user: None,
-            })).into_response();
-        }
-    };
+    let token = generate_jwt(&user.id, &user.role_id)
+        .map_err(|e| ApiError::internal_logged("Failed to create session", e))?;
     
     info!("User logged in: {}", user.username.as_deref().unwrap_or("unknown"));
     
-    (StatusCode::OK, Json(LoginResponse {
+    Ok((StatusCode::OK, Json(LoginResponse {
         success: true,
         message: "Login successful".to_string(),
         token: Some(token),
         user: Some(UserResponse::from(user)),
-    })).into_response()
+    })))
 }
 
 /// Logout (invalidate session)
@@ -293,9 +389,7 @@ pub async fn logout() -> impl IntoResponse {
 pub async fn current_user() -> impl IntoResponse {
     // For now, return unauthorized - will be implemented with auth middleware
     // TODO: Extract user from JWT token in auth middleware
-    (StatusCode::UNAUTHORIZED, Json(serde_json::json!({
-        "error": "Not authenticated"
-    }))).into_response()
+    ApiError::unauthorized("Not authenticated")
 }
 
 /// Hash a password using Argon2
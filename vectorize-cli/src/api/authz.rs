@@ -0,0 +1,245 @@
+//! Route authorization
+//!
+//! Every API route declares what it requires in `ROUTES`, and `authorize`
+//! checks it for the matched route before the handler runs: authenticated
+//! callers lacking the permission get 403. A route missing from the table
+//! requires `system_admin`, so a new route can't silently skip
+//! authorization.
+//!
+//! Anonymous requests only reach `Public` and `Handler` routes; anything
+//! else gets 401. Deployments whose web UI or agent sidecars don't
+//! authenticate yet can opt in to anonymous access with
+//! `VECTORIZE_ALLOW_ANONYMOUS` (see `rbac::AnonymousAccess`).
+
+use axum::{
+    extract::{MatchedPath, Request},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::api::ApiError;
+use crate::rbac::{AnonymousAccess, AuthenticatedUser, Permission::*, Requires};
+
+/// Prefix the API router is nested under
+const API_PREFIX: &str = "/api/v1";
+
+/// Requirement for routes without an entry
+const UNDECLARED: Requires = Requires::One(SystemAdmin);
+
+/// What each route requires: (method, path under `/api/v1`, requirement)
+const ROUTES: &[(&str, &str, Requires)] = &[
+    // Agents
+    ("GET", "/agents", Requires::One(AgentsRead)),
+    ("POST", "/agents", Requires::One(AgentsWrite)),
+    ("GET", "/agents/unassigned", Requires::One(AgentsRead)),
+    ("POST", "/agents/health-check", Requires::One(AgentsRead)),
+    ("GET", "/agents/:id", Requires::One(AgentsRead)),
+    ("PUT", "/agents/:id", Requires::One(AgentsWrite)),
+    ("PATCH", "/agents/:id", Requires::One(AgentsWrite)),
+    ("DELETE", "/agents/:id", Requires::One(AgentsDelete)),
+    ("GET", "/agents/:id/health", Requires::One(AgentsRead)),
+    ("GET", "/agents/:id/uptime", Requires::One(AgentsRead)),
+    ("POST", "/agents/:id/assign", Requires::One(AgentsWrite)),
+    ("POST", "/agents/:id/deploy", Requires::One(GroupsDeploy)),
+    ("POST", "/agents/:id/config/ack", Requires::One(AgentsWrite)),
+    ("POST", "/agents/:id/heartbeat", Requires::One(AgentsWrite)),
+    ("GET", "/agents/:id/config-diff", Requires::Any(&[AgentsRead, ConfigsRead])),
+
+    // Worker groups
+    ("GET", "/groups", Requires::One(GroupsRead)),
+    ("POST", "/groups", Requires::One(GroupsWrite)),
+    ("GET", "/groups/:id", Requires::One(GroupsRead)),
+    ("PUT", "/groups/:id", Requires::One(GroupsWrite)),
+    ("DELETE", "/groups/:id", Requires::One(GroupsDelete)),
+    ("GET", "/groups/:id/agents", Requires::One(GroupsRead)),
+    ("GET", "/groups/:id/uptime", Requires::One(GroupsRead)),
+    ("POST", "/groups/:id/clone", Requires::One(GroupsWrite)),
+    ("POST", "/groups/:id/freeze", Requires::One(GroupsDeploy)),
+    ("POST", "/groups/:id/unfreeze", Requires::One(GroupsDeploy)),
+    ("GET", "/groups/:id/deploy-preview", Requires::One(GroupsRead)),
+    ("POST", "/groups/:id/deploy", Requires::One(GroupsDeploy)),
+
+    // Group configs and history
+    ("GET", "/groups/:id/config", Requires::One(ConfigsRead)),
+    ("PUT", "/groups/:id/config", Requires::One(ConfigsWrite)),
+    ("POST", "/groups/:id/config/copy-from", Requires::One(ConfigsWrite)),
+    ("POST", "/groups/:id/config/validate", Requires::One(ConfigsValidate)),
+    ("GET", "/groups/:id/config/:version", Requires::One(ConfigsRead)),
+    ("GET", "/groups/:id/history", Requires::One(ConfigsRead)),
+    ("GET", "/groups/:id/history/search", Requires::One(ConfigsRead)),
+    ("GET", "/groups/:id/diff", Requires::One(ConfigsRead)),
+    ("POST", "/groups/:id/rollback", Requires::One(ConfigsRollback)),
+    ("GET", "/groups/:id/rollback/preview", Requires::One(ConfigsRead)),
+    ("POST", "/groups/:id/from-template", Requires::One(ConfigsWrite)),
+    ("GET", "/config/history", Requires::Any(&[ConfigsRead, AuditRead])),
+    ("GET", "/templates", Requires::One(ConfigsRead)),
+
+    // Deployments
+    ("GET", "/groups/:id/deployments", Requires::One(GroupsRead)),
+    ("POST", "/groups/:id/deployments", Requires::One(GroupsDeploy)),
+    ("GET", "/groups/:id/versions", Requires::One(GroupsRead)),
+    ("POST", "/deployments/bulk", Requires::One(SystemAdmin)),
+    ("GET", "/deployments/:id", Requires::One(GroupsRead)),
+    ("POST", "/deployments/:id/approve", Requires::One(GroupsDeploy)),
+    ("POST", "/deployments/:id/reject", Requires::One(GroupsDeploy)),
+    ("POST", "/deployments/:id/cancel", Requires::One(GroupsDeploy)),
+    ("GET", "/deployments/:id/events", Requires::One(GroupsRead)),
+    ("GET", "/deployments/:id/report", Requires::One(GroupsRead)),
+    ("GET", "/ws", Requires::Any(&[AgentsRead, GroupsRead])),
+
+    // Tap/sample
+    ("GET", "/tap/config", Requires::One(AgentsRead)),
+    ("GET", "/tap/redaction", Requires::One(AgentsRead)),
+    ("GET", "/tap/:agent_id/sample", Requires::One(AgentsRead)),
+    ("GET", "/tap/:agent_id/rate-limit", Requires::One(AgentsRead)),
+    ("GET", "/tap/:agent_id/ws-info", Requires::One(AgentsRead)),
+
+    // Git remotes and branches; remotes hold credentials
+    ("GET", "/git/remotes", Requires::One(ConfigsRead)),
+    ("POST", "/git/remotes", Requires::One(SystemAdmin)),
+    ("DELETE", "/git/remotes/:name", Requires::One(SystemAdmin)),
+    ("POST", "/git/remotes/:name/push", Requires::One(ConfigsWrite)),
+    ("POST", "/git/remotes/:name/pull", Requires::One(ConfigsWrite)),
+    ("POST", "/git/remotes/:name/sync", Requires::One(ConfigsWrite)),
+    ("GET", "/git/remotes/:name/status", Requires::One(ConfigsRead)),
+    ("GET", "/git/remotes/:name/incoming", Requires::One(ConfigsRead)),
+    ("GET", "/git/commits/:hash/verify", Requires::One(ConfigsRead)),
+    ("GET", "/git/conflicts", Requires::One(ConfigsRead)),
+    ("POST", "/git/conflicts/resolve", Requires::One(ConfigsWrite)),
+    ("POST", "/git/merge/abort", Requires::One(ConfigsWrite)),
+    ("GET", "/git/branches", Requires::One(ConfigsRead)),
+    ("POST", "/git/branches", Requires::One(ConfigsWrite)),
+    ("GET", "/git/branches/compare", Requires::One(ConfigsRead)),
+    ("POST", "/git/branches/:name/checkout", Requires::One(ConfigsWrite)),
+
+    // Health, metrics and topology
+    ("GET", "/health/fleet", Requires::One(AgentsRead)),
+    ("GET", "/health/agents", Requires::One(AgentsRead)),
+    ("GET", "/health/agents/:id/history", Requires::One(AgentsRead)),
+    ("GET", "/metrics", Requires::Any(&[AgentsRead, SystemRead])),
+    ("GET", "/metrics/:id", Requires::Any(&[AgentsRead, SystemRead])),
+    ("GET", "/metrics/:id/top", Requires::Any(&[AgentsRead, SystemRead])),
+    ("GET", "/topology", Requires::One(AgentsRead)),
+
+    // Alerts
+    ("GET", "/alerts/rules", Requires::One(AlertsRead)),
+    ("POST", "/alerts/rules", Requires::One(AlertsWrite)),
+    ("DELETE", "/alerts/rules/:id", Requires::One(AlertsDelete)),
+    ("GET", "/alerts/channels", Requires::One(AlertsRead)),
+    ("POST", "/alerts/channels", Requires::One(AlertsWrite)),
+    ("DELETE", "/alerts/channels/:id", Requires::One(AlertsDelete)),
+    ("POST", "/alerts/channels/:id/test", Requires::One(AlertsWrite)),
+
+    // API keys: users may list and revoke their own without api_keys_*
+    ("GET", "/api-keys", Requires::Handler),
+    ("POST", "/api-keys", Requires::One(ApiKeysWrite)),
+    ("DELETE", "/api-keys/:id", Requires::Handler),
+
+    // System
+    ("GET", "/system/maintenance", Requires::Public),
+    ("PUT", "/system/maintenance", Requires::One(SystemAdmin)),
+    ("GET", "/system/instances", Requires::One(SystemRead)),
+    ("GET", "/search", Requires::Any(&[AgentsRead, GroupsRead])),
+    ("GET", "/audit", Requires::One(AuditRead)),
+    ("GET", "/audit/actions", Requires::One(AuditRead)),
+
+    // Component catalog
+    ("GET", "/components/schema", Requires::One(ConfigsRead)),
+    ("GET", "/components/schema/:kind/:type", Requires::One(ConfigsRead)),
+
+    // Validation and functional tests
+    ("POST", "/validate", Requires::One(ConfigsValidate)),
+    ("POST", "/validate/quick", Requires::One(ConfigsValidate)),
+    ("POST", "/validate/lint", Requires::One(ConfigsValidate)),
+    ("POST", "/config/explain", Requires::One(ConfigsValidate)),
+    ("POST", "/config/analyze", Requires::One(ConfigsValidate)),
+    ("POST", "/config/convert", Requires::One(ConfigsValidate)),
+    ("GET", "/test", Requires::One(ConfigsRead)),
+    ("POST", "/test", Requires::One(ConfigsValidate)),
+    ("GET", "/test/:id", Requires::One(ConfigsRead)),
+
+    // Setup, handshake and authentication
+    ("GET", "/setup/status", Requires::Public),
+    ("POST", "/setup/init", Requires::Public),
+    ("POST", "/handshake", Requires::Public),
+    ("POST", "/auth/login", Requires::Public),
+    ("POST", "/auth/logout", Requires::Public),
+    ("GET", "/auth/me", Requires::Handler),
+];
+
+/// Requirement declared for `method` on the route pattern `path`
+pub fn required(method: &Method, path: &str) -> Requires {
+    let path = path.strip_prefix(API_PREFIX).unwrap_or(path);
+    // HEAD is served by the GET handler
+    let method = if *method == Method::HEAD { "GET" } else { method.as_str() };
+
+    match ROUTES.iter().find(|(m, p, _)| *m == method && *p == path) {
+        Some((_, _, requires)) => *requires,
+        None => {
+            warn!("No permission declared for {} {}; requiring system_admin", method, path);
+            UNDECLARED
+        }
+    }
+}
+
+/// Route layer enforcing `ROUTES`
+pub async fn authorize(req: Request, next: Next) -> Response {
+    let path = req.extensions().get::<MatchedPath>().map(|p| p.as_str()).unwrap_or_default();
+    let requires = required(req.method(), path);
+
+    let result = match req.extensions().get::<AuthenticatedUser>() {
+        Some(user) => requires.check(user),
+        None => check_anonymous(requires, req.extensions().get::<AnonymousAccess>().is_some()),
+    };
+    match result {
+        Ok(()) => next.run(req).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Refuse a request without a user with 401 unless the route is public,
+/// its handler decides, or anonymous access is enabled
+fn check_anonymous(requires: Requires, anonymous_allowed: bool) -> Result<(), ApiError> {
+    match requires {
+        Requires::Public | Requires::Handler => Ok(()),
+        _ if anonymous_allowed => Ok(()),
+        _ => Err(ApiError::unauthorized("Authentication required")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_declared_once() {
+        let mut seen = std::collections::HashSet::new();
+        for (method, path, _) in ROUTES {
+            assert!(seen.insert((method, path)), "{} {} declared twice", method, path);
+        }
+    }
+
+    #[test]
+    fn test_required_lookup() {
+        assert_eq!(required(&Method::GET, "/api/v1/groups/:id"), Requires::One(GroupsRead));
+        assert_eq!(required(&Method::HEAD, "/groups/:id"), Requires::One(GroupsRead));
+        assert_eq!(required(&Method::DELETE, "/groups/:id"), Requires::One(GroupsDelete));
+        assert_eq!(required(&Method::POST, "/auth/login"), Requires::Public);
+
+        // Fails closed
+        assert_eq!(required(&Method::POST, "/api/v1/not-declared"), UNDECLARED);
+    }
+
+    #[test]
+    fn test_anonymous_requests() {
+        assert!(check_anonymous(Requires::Public, false).is_ok());
+        assert!(check_anonymous(Requires::Handler, false).is_ok());
+        assert!(matches!(check_anonymous(Requires::One(GroupsRead), false), Err(ApiError::Unauthorized(_))));
+        assert!(matches!(check_anonymous(UNDECLARED, false), Err(ApiError::Unauthorized(_))));
+
+        // Legacy anonymous mode
+        assert!(check_anonymous(Requires::One(GroupsRead), true).is_ok());
+    }
+}
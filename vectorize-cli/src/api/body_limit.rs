@@ -0,0 +1,105 @@
+//! Request body size limits
+//!
+//! Config and validation endpoints accept whole Vector configs, so their
+//! bodies are capped to stop a huge paste from exhausting memory. The cap is
+//! generous (configs with thousands of components are expected) but finite,
+//! and oversized requests get a JSON 413 instead of a bare connection reset.
+
+use axum::{
+    extract::{DefaultBodyLimit, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower_http::limit::RequestBodyLimitLayer;
+use tracing::warn;
+
+use super::ApiError;
+
+/// Default cap on config and validation request bodies (5 MB)
+pub const DEFAULT_MAX_CONFIG_BODY_BYTES: usize = 5 * 1024 * 1024;
+
+/// Body cap from `VECTORIZE_MAX_CONFIG_BODY_BYTES`, falling back to the default
+pub fn max_config_body_bytes_from_env() -> usize {
+    std::env::var("VECTORIZE_MAX_CONFIG_BODY_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n: &usize| n > 0)
+        .unwrap_or(DEFAULT_MAX_CONFIG_BODY_BYTES)
+}
+
+/// Cap request bodies on every route in `router` at `limit` bytes
+///
+/// Replaces axum's default extractor limit, so `limit` may be larger than
+/// axum's 2 MB default.
+pub fn limit_request_bodies<S>(router: Router<S>, limit: usize) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .route_layer(RequestBodyLimitLayer::new(limit))
+        .route_layer(DefaultBodyLimit::disable())
+        .route_layer(middleware::from_fn_with_state(limit, payload_too_large_json))
+}
+
+/// Rewrite 413 responses as a JSON error naming the limit
+async fn payload_too_large_json(
+    State(limit): State<usize>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let response = next.run(req).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE {
+        return response;
+    }
+
+    warn!("Rejected request body over {} bytes on {}", limit, path);
+    ApiError::PayloadTooLarge {
+        message: format!(
+            "Request body is too large; the limit is {} bytes (set VECTORIZE_MAX_CONFIG_BODY_BYTES to raise it)",
+            limit
+        ),
+        limit_bytes: limit,
+    }
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::post};
+    use tower::ServiceExt;
+
+    async fn echo_len(body: String) -> String {
+        body.len().to_string()
+    }
+
+    fn app(limit: usize) -> Router {
+        limit_request_bodies(Router::new().route("/", post(echo_len)), limit)
+    }
+
+    #[tokio::test]
+    async fn test_body_under_limit_passes() {
+        let response = app(16)
+            .oneshot(Request::post("/").body(Body::from("a".repeat(16))).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_body_over_limit_is_json_413() {
+        let response = app(16)
+            .oneshot(Request::post("/").body(Body::from("a".repeat(17))).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["details"]["limit_bytes"], 16);
+        assert!(json["error"]["message"].as_str().unwrap().contains("16 bytes"));
+    }
+}
@@ -0,0 +1,32 @@
+//! Component catalog endpoints
+//!
+//! Serves the shared component catalog so the UI pipeline builder can list
+//! component types and their fields.
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use vectorize_shared::component_schema::{self, ComponentKind};
+
+use crate::api::ApiError;
+
+/// Get the full component catalog
+pub async fn get_component_catalog() -> impl IntoResponse {
+    (StatusCode::OK, Json(component_schema::catalog()))
+}
+
+/// Get the schema for one component type
+pub async fn get_component_schema(
+    Path((kind, component_type)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    let kind: ComponentKind = kind.parse().map_err(ApiError::bad_request)?;
+    
+    let schema = component_schema::component_schema(kind, &component_type).ok_or_else(|| {
+        ApiError::not_found(format!("Unknown component type: {}.{}", kind.table_name(), component_type))
+    })?;
+    
+    Ok((StatusCode::OK, Json(schema)))
+}
@@ -5,26 +5,55 @@
 //! - Deployment strategies (rolling, canary)
 //! - Approval workflows
 //! - Deployment history and status
+//! - Live progress via server-sent events
+//! - Shareable reports of finished deployments
+//! - Redeploying every group at once
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Extension, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use tracing::{info, error};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, error, warn};
 
 use crate::AppState;
-use crate::db::repository::{DeploymentRepository, WorkerGroupRepository};
+use crate::api::{accepts, ApiError};
+use crate::db::models::WorkerGroup;
+use crate::db::repository::{AgentRepository, DeploymentRepository, IdempotencyKeyRepository, WorkerGroupRepository};
+use crate::rbac::{require_authenticated, require_permission, AuthenticatedUser};
 use crate::deployment::{
-    DeploymentExecutor, DeploymentOptions, RollingOptions, CanaryOptions,
-    check_version_consistency,
+    AbortResult, DeploymentEvent, DeploymentExecutor, DeploymentOptions, RollingOptions, CanaryOptions,
+    check_version_consistency, frozen_message,
 };
+use crate::deployment::events::is_terminal_status;
+use crate::deployment::report::{seconds_between, DeploymentReport, ReportAgent, ReportApproval, ReportTiming};
+use crate::git_store::GitStore;
+
+/// Header carrying a client-chosen key that makes deployment creation safe to retry
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Header set on a response replayed for a repeated idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// How long a processed idempotency key is remembered
+const IDEMPOTENCY_WINDOW_HOURS: i64 = 24;
+
+/// Longest accepted idempotency key
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// How long a claimed key without a response blocks retries
+///
+/// A request that dies between claiming and recording its key would
+/// otherwise hold the key for the whole replay window.
+const IDEMPOTENCY_CLAIM_LEASE_SECS: i64 = 60;
 
 /// Request to create a new deployment
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateDeploymentRequest {
     /// Config version to deploy (defaults to current)
     pub config_version: Option<String>,
@@ -34,13 +63,17 @@ pub struct CreateDeploymentRequest {
     pub rolling_options: Option<RollingOptions>,
     /// Canary deployment options
     pub canary_options: Option<CanaryOptions>,
+    /// Timeout for each agent push in seconds (default: 30)
+    pub per_agent_timeout_secs: Option<u64>,
+    /// Start the deployment at this RFC 3339 time instead of immediately
+    pub scheduled_at: Option<String>,
     /// Force deployment even if version mismatch
     #[serde(default)]
     pub force: bool,
 }
 
 /// Response for deployment creation
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct CreateDeploymentResponse {
     pub deployment_id: String,
     pub status: String,
@@ -63,6 +96,7 @@ pub struct DeploymentStatusResponse {
     pub created_by: Option<String>,
     pub approved_by: Option<String>,
     pub error: Option<String>,
+    pub scheduled_at: Option<String>,
     pub stats: DeploymentStatsResponse,
     pub agents: Vec<DeploymentAgentResponse>,
 }
@@ -73,6 +107,9 @@ pub struct DeploymentStatsResponse {
     pub total: u32,
     pub completed: u32,
     pub failed: u32,
+    pub timed_out: u32,
+    /// Agents skipped because the server shut down mid-deployment
+    pub cancelled: u32,
     pub in_progress: u32,
     pub pending: u32,
 }
@@ -90,7 +127,17 @@ pub struct DeploymentAgentResponse {
 /// Query parameters for deployment list
 #[derive(Debug, Deserialize)]
 pub struct DeploymentListQuery {
+    /// Number of deployments to return (default 50, max 1000)
     pub limit: Option<i64>,
+    /// Offset for pagination
+    pub offset: Option<i64>,
+    /// Only deployments with this status
+    pub status: Option<String>,
+    /// Only deployments created at or after this date or RFC 3339 time
+    pub since: Option<String>,
+    /// Only deployments created before this RFC 3339 time, or through the
+    /// end of this date
+    pub until: Option<String>,
 }
 
 /// Response for version check
@@ -122,81 +169,320 @@ pub struct RejectDeploymentRequest {
     pub reason: Option<String>,
 }
 
+/// Request to redeploy every group's current config
+#[derive(Debug, Deserialize)]
+pub struct BulkDeploymentRequest {
+    /// Deploy even to groups whose agents run mismatched Vector versions
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// What a bulk deployment did for one group
+#[derive(Debug, Serialize)]
+pub struct BulkDeploymentOutcome {
+    pub group_name: String,
+    /// Set when a deployment was started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Why no deployment was started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+/// Response for a bulk deployment
+#[derive(Debug, Serialize)]
+pub struct BulkDeploymentResponse {
+    pub started: usize,
+    pub skipped: usize,
+    /// Outcome per group ID
+    pub groups: BTreeMap<String, BulkDeploymentOutcome>,
+}
+
 // =============================================================================
 // API Endpoints
 // =============================================================================
 
 /// Create a new deployment for a group
+///
+/// With an `Idempotency-Key` header, a repeat of the same key for the same
+/// group within 24 hours returns the original response instead of creating
+/// another deployment, so clients can retry safely on network errors. Reusing
+/// a key with a different request body is rejected with 422.
 pub async fn create_deployment(
     State(state): State<Arc<AppState>>,
     Path(group_id): Path<String>,
+    user: Option<Extension<AuthenticatedUser>>,
+    headers: HeaderMap,
     Json(request): Json<CreateDeploymentRequest>,
-) -> impl IntoResponse {
+) -> Result<Response, ApiError> {
     let pool = state.db.pool();
     
     // Get the group
-    let group = match WorkerGroupRepository::get_by_id(pool, &group_id).await {
-        Ok(Some(group)) => group,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Group not found"
-            }))).into_response();
+    let group = WorkerGroupRepository::get_by_id(pool, &group_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get group", e))?
+        .ok_or_else(|| ApiError::not_found("Group not found"))?;
+    
+    let idempotency_key = idempotency_key(&headers)?;
+    if let Some(ref key) = idempotency_key {
+        if let Some(replayed) = replay_or_claim(pool, &group_id, key, &request_hash(&request)).await? {
+            return Ok(replayed);
+        }
+    }
+    
+    let result = start_deployment(&state, &group_id, group, request, user.as_deref()).await;
+    
+    if let Some(ref key) = idempotency_key {
+        let recorded = match &result {
+            Ok(response) => {
+                let body = serde_json::to_string(response).unwrap_or_default();
+                IdempotencyKeyRepository::complete(pool, &group_id, key, &response.deployment_id, &body).await
+            }
+            // Nothing was created, so a retry with the same key should run again
+            Err(_) => IdempotencyKeyRepository::release(pool, &group_id, key).await,
+        };
+        if let Err(e) = recorded {
+            warn!("Failed to record idempotency key for group {}: {}", group_id, e);
         }
-        Err(e) => {
-            error!("Failed to get group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get group"
-            }))).into_response();
+    }
+    
+    Ok((StatusCode::CREATED, Json(result?)).into_response())
+}
+
+/// Validate and start a deployment for `group`
+///
+/// A frozen group only accepts `force` deployments from a `system_admin`.
+async fn start_deployment(
+    state: &AppState,
+    group_id: &str,
+    group: WorkerGroup,
+    request: CreateDeploymentRequest,
+    user: Option<&AuthenticatedUser>,
+) -> Result<CreateDeploymentResponse, ApiError> {
+    if group.frozen {
+        let admin = user.is_some_and(|u| u.has_permission("system_admin"));
+        if !(request.force && admin) {
+            return Err(ApiError::conflict(frozen_message(&group.name))
+                .with_details(serde_json::json!({ "frozen": true })));
         }
-    };
+        warn!(
+            "Deploying to frozen group {} (forced by {})",
+            group.name,
+            user.map(|u| u.user_id.as_str()).unwrap_or_default()
+        );
+    }
+    
+    let defaults = DeploymentOptions::group_defaults(&group);
     
     // Get config version
     let config_version = match &request.config_version {
         Some(v) => v.clone(),
-        None => {
-            match group.current_config_version {
-                Some(v) => v,
-                None => {
-                    return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                        "error": "No configuration set for this group"
-                    }))).into_response();
-                }
-            }
-        }
+        None => group.current_config_version
+            .ok_or_else(|| ApiError::bad_request("No configuration set for this group"))?,
     };
     
-    // Build options
-    let options = DeploymentOptions {
-        rolling: request.rolling_options.clone(),
-        canary: request.canary_options.clone(),
+    let scheduled_at = request.scheduled_at.as_deref()
+        .map(chrono::DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|e| ApiError::bad_request(format!("Invalid scheduled_at (expected RFC 3339): {}", e)))?
+        .map(|at| at.with_timezone(&chrono::Utc));
+    
+    // Options given in the request override the group's defaults
+    let overridden = request.rolling_options.is_some()
+        || request.canary_options.is_some()
+        || request.per_agent_timeout_secs.is_some();
+    let options = if overridden {
+        let mut options = defaults.unwrap_or_default();
+        if let Some(rolling) = request.rolling_options.clone() {
+            options.rolling = Some(rolling);
+        }
+        if let Some(canary) = request.canary_options.clone() {
+            options.canary = Some(canary);
+        }
+        if let Some(timeout) = request.per_agent_timeout_secs {
+            options.per_agent_timeout_secs = timeout;
+        }
+        Some(options)
+    } else {
+        defaults
     };
     
     // Create executor
-    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone());
+    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone())
+        .with_events(state.deployment_events.clone())
+        .with_push_limit(state.deployment_push_limit.clone())
+        .with_shutdown(state.shutdown.clone())
+        .with_agent_clients(state.agent_clients.clone());
     
     // Start deployment
-    match executor.start_deployment(
-        &group_id,
+    let result = executor.start_deployment(
+        group_id,
         &config_version,
-        Some(options),
+        options,
         None, // TODO: get from auth context
         request.force,
-    ).await {
-        Ok(result) => {
-            (StatusCode::CREATED, Json(CreateDeploymentResponse {
-                deployment_id: result.deployment_id,
-                status: result.status,
-                message: result.message,
-                requires_approval: result.requires_approval,
-                queued: result.queued,
-            })).into_response()
+        scheduled_at,
+    ).await.map_err(|e| {
+        error!("Failed to create deployment: {}", e);
+        ApiError::bad_request(e)
+    })?;
+    
+    Ok(CreateDeploymentResponse {
+        deployment_id: result.deployment_id,
+        status: result.status,
+        message: result.message,
+        requires_approval: result.requires_approval,
+        queued: result.queued,
+    })
+}
+
+/// Redeploy every group's current config (requires `system_admin`)
+///
+/// Groups without a config or without healthy agents are skipped. Each
+/// started deployment uses the group's strategy and default options, and
+/// all of them share the global agent push limit.
+pub async fn bulk_deploy(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<AuthenticatedUser>>,
+    Json(request): Json<BulkDeploymentRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = require_authenticated(user)?;
+    require_permission(&user, "system_admin")?;
+    
+    let pool = state.db.pool();
+    let groups = WorkerGroupRepository::list(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list worker groups", e))?;
+    
+    info!("Bulk deployment of {} groups requested by {}", groups.len(), user.user_id);
+    
+    let force = request.force;
+    let runs = groups.into_iter().map(|group| {
+        let state = &state;
+        let user = &user;
+        async move {
+            let group_id = group.id.clone();
+            let mut outcome = BulkDeploymentOutcome {
+                group_name: group.name.clone(),
+                deployment_id: None,
+                status: None,
+                skipped: None,
+            };
+            
+            let skip = if group.current_config_version.is_none() {
+                Some("No configuration set for this group".to_string())
+            } else {
+                match WorkerGroupRepository::get_agent_health_counts(state.db.pool(), &group_id).await {
+                    Ok((_, 0, _)) => Some("No healthy agents".to_string()),
+                    Ok(_) => None,
+                    Err(e) => Some(format!("Failed to count agents: {}", e)),
+                }
+            };
+            if let Some(reason) = skip {
+                outcome.skipped = Some(reason);
+                return (group_id, outcome);
+            }
+            
+            let request = CreateDeploymentRequest {
+                config_version: None,
+                strategy: None,
+                rolling_options: None,
+                canary_options: None,
+                per_agent_timeout_secs: None,
+                scheduled_at: None,
+                force,
+            };
+            match start_deployment(state, &group_id, group, request, Some(user)).await {
+                Ok(response) => {
+                    outcome.deployment_id = Some(response.deployment_id);
+                    outcome.status = Some(response.status);
+                }
+                Err(e) => {
+                    warn!("Bulk deployment skipped group {}: {}", group_id, e);
+                    outcome.skipped = Some(e.message());
+                }
+            }
+            (group_id, outcome)
         }
-        Err(e) => {
-            error!("Failed to create deployment: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": e
-            }))).into_response()
+    });
+    let groups: BTreeMap<_, _> = futures::future::join_all(runs).await.into_iter().collect();
+    
+    let started = groups.values().filter(|o| o.deployment_id.is_some()).count();
+    Ok(Json(BulkDeploymentResponse {
+        started,
+        skipped: groups.len() - started,
+        groups,
+    }))
+}
+
+/// The `Idempotency-Key` header, if the request has one
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+    let value = match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let key = value.to_str()
+        .map_err(|_| ApiError::bad_request("Idempotency-Key must be visible ASCII"))?
+        .trim();
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(ApiError::bad_request(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_IDEMPOTENCY_KEY_LEN
+        )));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// SHA-256 of the parsed request, so formatting differences don't count as a new body
+fn request_hash(request: &CreateDeploymentRequest) -> String {
+    use sha2::{Digest, Sha256};
+    let body = serde_json::to_string(request).unwrap_or_default();
+    format!("{:x}", Sha256::digest(body.as_bytes()))
+}
+
+/// Replay the stored response for a repeated key, or claim a new key
+///
+/// Returns None once the key is claimed and the request should proceed.
+async fn replay_or_claim(
+    pool: &crate::db::DbPool,
+    group_id: &str,
+    key: &str,
+    request_hash: &str,
+) -> Result<Option<Response>, ApiError> {
+    let now = chrono::Utc::now();
+    let since = (now - chrono::Duration::hours(IDEMPOTENCY_WINDOW_HOURS))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    if let Err(e) = IdempotencyKeyRepository::delete_expired(pool, &since).await {
+        warn!("Failed to prune expired idempotency keys: {}", e);
+    }
+    
+    let stale_before = (now - chrono::Duration::seconds(IDEMPOTENCY_CLAIM_LEASE_SECS))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    let claimed = IdempotencyKeyRepository::claim(pool, group_id, key, request_hash, &stale_before).await
+        .map_err(|e| ApiError::internal_logged("Failed to check idempotency key", e))?;
+    if claimed {
+        return Ok(None);
+    }
+    
+    let existing = IdempotencyKeyRepository::get(pool, group_id, key, &since).await
+        .map_err(|e| ApiError::internal_logged("Failed to check idempotency key", e))?;
+    if existing.as_ref().and_then(|k| k.request_hash.as_deref()).is_some_and(|h| h != request_hash) {
+        return Err(ApiError::unprocessable("This Idempotency-Key was already used with a different request body"));
+    }
+    match existing.and_then(|k| k.response) {
+        Some(body) => {
+            let response: CreateDeploymentResponse = serde_json::from_str(&body)
+                .map_err(|e| ApiError::internal_logged("Failed to read stored deployment response", e))?;
+            info!("Replaying deployment {} for idempotency key on group {}", response.deployment_id, group_id);
+            Ok(Some((
+                StatusCode::CREATED,
+                [(IDEMPOTENT_REPLAYED_HEADER, "true")],
+                Json(response),
+            ).into_response()))
         }
+        None => Err(ApiError::conflict("A request with this Idempotency-Key is still in progress")),
     }
 }
 
@@ -204,43 +490,18 @@ pub async fn create_deployment(
 pub async fn get_deployment(
     State(state): State<Arc<AppState>>,
     Path(deployment_id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    let deployment = match DeploymentRepository::get_by_id(pool, &deployment_id).await {
-        Ok(Some(d)) => d,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Deployment not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get deployment: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get deployment"
-            }))).into_response();
-        }
-    };
+    let deployment = DeploymentRepository::get_by_id(pool, &deployment_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get deployment", e))?
+        .ok_or_else(|| ApiError::not_found("Deployment not found"))?;
     
-    let stats = match DeploymentRepository::get_stats(pool, &deployment_id).await {
-        Ok(s) => s,
-        Err(e) => {
-            error!("Failed to get stats: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get deployment stats"
-            }))).into_response();
-        }
-    };
+    let stats = DeploymentRepository::get_stats(pool, &deployment_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get deployment stats", e))?;
     
-    let agents = match DeploymentRepository::get_agents(pool, &deployment_id).await {
-        Ok(a) => a,
-        Err(e) => {
-            error!("Failed to get agents: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get deployment agents"
-            }))).into_response();
-        }
-    };
+    let agents = DeploymentRepository::get_agents(pool, &deployment_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get deployment agents", e))?;
     
     let response = DeploymentStatusResponse {
         id: deployment.id,
@@ -254,10 +515,13 @@ pub async fn get_deployment(
         created_by: deployment.created_by,
         approved_by: deployment.approved_by,
         error: deployment.error,
+        scheduled_at: deployment.scheduled_at,
         stats: DeploymentStatsResponse {
             total: stats.total,
             completed: stats.completed,
             failed: stats.failed,
+            timed_out: stats.timed_out,
+            cancelled: stats.cancelled,
             in_progress: stats.in_progress,
             pending: stats.pending,
         },
@@ -270,71 +534,221 @@ pub async fn get_deployment(
         }).collect(),
     };
     
-    (StatusCode::OK, Json(response)).into_response()
+    Ok((StatusCode::OK, Json(response)))
 }
 
-/// List deployments for a group
+/// Get a shareable report of a deployment
+///
+/// JSON by default; `Accept: text/markdown` or `text/html` returns the
+/// rendered report as a download instead. The config diff is left out for
+/// callers without `configs_read`.
+pub async fn get_deployment_report(
+    State(state): State<Arc<AppState>>,
+    Path(deployment_id): Path<String>,
+    headers: HeaderMap,
+    user: Option<Extension<AuthenticatedUser>>,
+) -> Result<Response, ApiError> {
+    let pool = state.db.pool();
+    
+    let deployment = DeploymentRepository::get_by_id(pool, &deployment_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get deployment", e))?
+        .ok_or_else(|| ApiError::not_found("Deployment not found"))?;
+    let group = WorkerGroupRepository::get_by_id(pool, &deployment.group_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get worker group", e))?
+        .ok_or_else(|| ApiError::not_found("Worker group not found"))?;
+    let previous_version = DeploymentRepository::previous_completed(pool, &deployment).await
+        .map_err(|e| ApiError::internal_logged("Failed to get previous deployment", e))?
+        .map(|d| d.config_version);
+    
+    // First deployments diff against an empty config
+    let can_read_configs = user.as_ref().is_none_or(|u| u.has_permission("configs_read"));
+    let config_diff = if can_read_configs {
+        let diff = match &previous_version {
+            Some(previous) => state.git_store.diff_config(&group.name, previous, &deployment.config_version),
+            None => state.git_store.get_config_at_version(&group.name, &deployment.config_version)
+                .and_then(|config| {
+                    let path = GitStore::config_repo_path(&group.name);
+                    GitStore::diff_text(&path, "", &path, &config.unwrap_or_default())
+                }),
+        };
+        diff.inspect_err(|e| warn!("No config diff for deployment {}: {}", deployment.id, e)).ok()
+    } else {
+        None
+    };
+    
+    let names: HashMap<String, String> = AgentRepository::list_including_deleted(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?
+        .into_iter()
+        .map(|a| (a.id, a.name))
+        .collect();
+    let agents: Vec<ReportAgent> = DeploymentRepository::get_agents(pool, &deployment_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get deployment agents", e))?
+        .into_iter()
+        .map(|a| ReportAgent {
+            agent_name: names.get(&a.agent_id).cloned(),
+            duration_secs: seconds_between(a.started_at.as_deref(), a.completed_at.as_deref()),
+            agent_id: a.agent_id,
+            status: a.status,
+            started_at: a.started_at,
+            completed_at: a.completed_at,
+            error: a.error,
+        })
+        .collect();
+    
+    // Approvals and rejections are stored as "<time> by <user>"
+    let split_note = |note: Option<String>, by: Option<String>| match note {
+        Some(note) => match note.split_once(" by ") {
+            Some((at, who)) => (Some(at.to_string()), by.or_else(|| Some(who.to_string()))),
+            None => (Some(note), by),
+        },
+        None => (None, by),
+    };
+    let (approved_at, approved_by) = split_note(deployment.approved_at, deployment.approved_by);
+    let (rejected_at, rejected_by) = split_note(deployment.rejected_at, deployment.rejected_by);
+    
+    let report = DeploymentReport {
+        group_id: deployment.group_id,
+        group_name: group.name,
+        config_version: deployment.config_version,
+        previous_version,
+        strategy: deployment.strategy,
+        status: deployment.status,
+        created_by: deployment.created_by,
+        error: deployment.error,
+        approval: ReportApproval {
+            required: group.requires_approval,
+            approved_by,
+            approved_at,
+            rejected_by,
+            rejected_at,
+            rejection_reason: deployment.rejection_reason,
+        },
+        timing: ReportTiming {
+            duration_secs: seconds_between(deployment.started_at.as_deref(), deployment.completed_at.as_deref()),
+            created_at: deployment.created_at,
+            scheduled_at: deployment.scheduled_at,
+            started_at: deployment.started_at,
+            completed_at: deployment.completed_at,
+        },
+        outcomes: DeploymentReport::count_outcomes(&agents),
+        agents,
+        config_diff,
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        deployment_id: deployment.id,
+    };
+    
+    let (content_type, extension, body) = if accepts(&headers, "text/markdown") {
+        ("text/markdown; charset=utf-8", "md", report.to_markdown())
+    } else if accepts(&headers, "text/html") {
+        ("text/html; charset=utf-8", "html", report.to_html())
+    } else {
+        return Ok((StatusCode::OK, Json(report)).into_response());
+    };
+    let disposition = format!("attachment; filename=\"{}\"", report.file_name(extension));
+    
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ).into_response())
+}
+
+/// List deployments for a group, newest first
 pub async fn list_deployments(
     State(state): State<Arc<AppState>>,
     Path(group_id): Path<String>,
     Query(params): Query<DeploymentListQuery>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
-    let limit = params.limit.unwrap_or(50);
-    
-    match DeploymentRepository::list_by_group(pool, &group_id, limit).await {
-        Ok(deployments) => {
-            let responses: Vec<serde_json::Value> = deployments.into_iter().map(|d| {
-                serde_json::json!({
-                    "id": d.id,
-                    "config_version": d.config_version,
-                    "strategy": d.strategy,
-                    "status": d.status,
-                    "created_at": d.created_at,
-                    "completed_at": d.completed_at,
-                    "error": d.error,
-                })
-            }).collect();
-            
-            (StatusCode::OK, Json(serde_json::json!({
-                "deployments": responses
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to list deployments: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list deployments"
-            }))).into_response()
-        }
+    let limit = params.limit.unwrap_or(50).clamp(1, 1000);
+    let offset = params.offset.unwrap_or(0).max(0);
+    let status = params.status.as_deref().filter(|s| !s.is_empty());
+    let since = params.since.as_deref().map(|v| history_bound("since", v, false)).transpose()?;
+    let until = params.until.as_deref().map(|v| history_bound("until", v, true)).transpose()?;
+    
+    let deployments = DeploymentRepository::list_for_group(
+        pool,
+        &group_id,
+        status,
+        since.as_deref(),
+        until.as_deref(),
+        limit,
+        offset,
+    ).await
+        .map_err(|e| ApiError::internal_logged("Failed to list deployments", e))?;
+    let total = DeploymentRepository::count_for_group(pool, &group_id, status, since.as_deref(), until.as_deref())
+        .await
+        .map_err(|e| ApiError::internal_logged("Failed to count deployments", e))?;
+    
+    let responses: Vec<serde_json::Value> = deployments.into_iter().map(|d| {
+        serde_json::json!({
+            "id": d.id,
+            "config_version": d.config_version,
+            "strategy": d.strategy,
+            "status": d.status,
+            "created_at": d.created_at,
+            "completed_at": d.completed_at,
+            "error": d.error,
+        })
+    }).collect();
+    
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "deployments": responses,
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+    }))))
+}
+
+/// A history date bound in the stored timestamp format
+fn history_bound(name: &str, value: &str, end_of_day: bool) -> Result<String, ApiError> {
+    Ok(parse_time_bound(name, value, end_of_day)?.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// Parse a `since`/`until` query bound
+///
+/// Accepts RFC 3339 times and plain dates. A date as `until` covers that
+/// whole day, so the bound becomes the start of the next one.
+pub(crate) fn parse_time_bound(
+    name: &str,
+    value: &str,
+    end_of_day: bool,
+) -> Result<chrono::DateTime<chrono::Utc>, ApiError> {
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(at.with_timezone(&chrono::Utc));
     }
+    
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        ApiError::bad_request(format!("Invalid {} (expected YYYY-MM-DD or RFC 3339): {}", name, value))
+    })?;
+    let date = if end_of_day { date.succ_opt().unwrap_or(date) } else { date };
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc())
 }
 
 /// Check version consistency for a group
 pub async fn check_versions(
     State(state): State<Arc<AppState>>,
     Path(group_id): Path<String>,
-) -> impl IntoResponse {
-    match check_version_consistency(&state.db, &group_id).await {
-        Ok(result) => {
-            let response = VersionCheckResponse {
-                consistent: result.consistent,
-                versions: result.versions.into_iter().map(|v| VersionInfoResponse {
-                    version: v.version,
-                    agents: v.agents,
-                }).collect(),
-                message: result.message.clone(),
-                can_deploy: result.consistent,
-            };
-            
-            (StatusCode::OK, Json(response)).into_response()
-        }
-        Err(e) => {
-            error!("Failed to check versions: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e
-            }))).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let result = check_version_consistency(&state.db, &group_id).await.map_err(|e| {
+        error!("Failed to check versions: {}", e);
+        ApiError::internal(e)
+    })?;
+    
+    let response = VersionCheckResponse {
+        consistent: result.consistent,
+        versions: result.versions.into_iter().map(|v| VersionInfoResponse {
+            version: v.version,
+            agents: v.agents,
+        }).collect(),
+        message: result.message.clone(),
+        can_deploy: result.consistent,
+    };
+    
+    Ok((StatusCode::OK, Json(response)))
 }
 
 /// Approve a pending deployment
@@ -342,24 +756,23 @@ pub async fn approve_deployment(
     State(state): State<Arc<AppState>>,
     Path(deployment_id): Path<String>,
     Json(request): Json<ApproveDeploymentRequest>,
-) -> impl IntoResponse {
-    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone());
-    
-    match executor.approve_deployment(&deployment_id, &request.approved_by).await {
-        Ok(_) => {
-            info!("Deployment {} approved", deployment_id);
-            (StatusCode::OK, Json(serde_json::json!({
-                "success": true,
-                "message": "Deployment approved and started"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to approve deployment: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": e
-            }))).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone())
+        .with_events(state.deployment_events.clone())
+        .with_push_limit(state.deployment_push_limit.clone())
+        .with_shutdown(state.shutdown.clone())
+        .with_agent_clients(state.agent_clients.clone());
+    
+    executor.approve_deployment(&deployment_id, &request.approved_by).await.map_err(|e| {
+        error!("Failed to approve deployment: {}", e);
+        ApiError::bad_request(e)
+    })?;
+    
+    info!("Deployment {} approved", deployment_id);
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "success": true,
+        "message": "Deployment approved and started"
+    }))))
 }
 
 /// Reject a pending deployment
@@ -367,46 +780,126 @@ pub async fn reject_deployment(
     State(state): State<Arc<AppState>>,
     Path(deployment_id): Path<String>,
     Json(request): Json<RejectDeploymentRequest>,
-) -> impl IntoResponse {
-    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone());
-    
-    match executor.reject_deployment(&deployment_id, &request.rejected_by, request.reason.as_deref()).await {
-        Ok(_) => {
-            info!("Deployment {} rejected", deployment_id);
-            (StatusCode::OK, Json(serde_json::json!({
-                "success": true,
-                "message": "Deployment rejected"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to reject deployment: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": e
-            }))).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone())
+        .with_events(state.deployment_events.clone())
+        .with_push_limit(state.deployment_push_limit.clone())
+        .with_shutdown(state.shutdown.clone())
+        .with_agent_clients(state.agent_clients.clone());
+    
+    executor.reject_deployment(&deployment_id, &request.rejected_by, request.reason.as_deref()).await.map_err(|e| {
+        error!("Failed to reject deployment: {}", e);
+        ApiError::bad_request(e)
+    })?;
+    
+    info!("Deployment {} rejected", deployment_id);
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "success": true,
+        "message": "Deployment rejected"
+    }))))
+}
+
+/// Query parameters for cancelling a deployment
+#[derive(Debug, Default, Deserialize)]
+pub struct CancelDeploymentQuery {
+    /// Also revert agents the deployment already updated
+    #[serde(default)]
+    pub revert: bool,
+}
+
+/// Response for an aborted and reverted deployment
+#[derive(Debug, Serialize)]
+pub struct AbortDeploymentResponse {
+    pub success: bool,
+    #[serde(flatten)]
+    pub result: AbortResult,
 }
 
 /// Cancel a deployment
+///
+/// Agents already updated keep the new config, unless `revert` is set:
+/// then they are returned to the version they ran before.
 pub async fn cancel_deployment(
     State(state): State<Arc<AppState>>,
     Path(deployment_id): Path<String>,
-) -> impl IntoResponse {
-    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone());
-    
-    match executor.cancel_deployment(&deployment_id).await {
-        Ok(_) => {
-            info!("Deployment {} cancelled", deployment_id);
-            (StatusCode::OK, Json(serde_json::json!({
-                "success": true,
-                "message": "Deployment cancelled"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to cancel deployment: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": e
-            }))).into_response()
-        }
+    Query(params): Query<CancelDeploymentQuery>,
+) -> Result<Response, ApiError> {
+    let executor = DeploymentExecutor::new(state.db.clone(), state.git_store.clone())
+        .with_events(state.deployment_events.clone())
+        .with_push_limit(state.deployment_push_limit.clone())
+        .with_shutdown(state.shutdown.clone())
+        .with_agent_clients(state.agent_clients.clone());
+    
+    if params.revert {
+        let result = executor.abort_deployment(&deployment_id).await.map_err(|e| {
+            error!("Failed to abort deployment: {}", e);
+            ApiError::bad_request(e)
+        })?;
+        
+        info!("Deployment {} aborted", deployment_id);
+        return Ok(Json(AbortDeploymentResponse { success: true, result }).into_response());
     }
+    
+    executor.cancel_deployment(&deployment_id).await.map_err(|e| {
+        error!("Failed to cancel deployment: {}", e);
+        ApiError::bad_request(e)
+    })?;
+    
+    info!("Deployment {} cancelled", deployment_id);
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "success": true,
+        "message": "Deployment cancelled"
+    }))).into_response())
+}
+
+/// Stream live progress for a deployment as server-sent events
+/// 
+/// Each event is named after its type (`agent_started`, `batch_completed`,
+/// ...) with the JSON event as data. The stream ends after the `finished`
+/// event; for a deployment that already finished, that is the only event.
+pub async fn deployment_events(
+    State(state): State<Arc<AppState>>,
+    Path(deployment_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Subscribe before reading the status so no transition is missed in between
+    let rx = state.deployment_events.subscribe(&deployment_id);
+    
+    let deployment = DeploymentRepository::get_by_id(state.db.pool(), &deployment_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get deployment", e))?
+        .ok_or_else(|| ApiError::not_found("Deployment not found"))?;
+    
+    let already_finished = is_terminal_status(&deployment.status).then(|| DeploymentEvent::Finished {
+        status: deployment.status.clone(),
+        error: deployment.error.clone(),
+    });
+    
+    let stream = futures::stream::unfold(
+        (rx, already_finished, false),
+        |(mut rx, pending, done)| async move {
+            if done {
+                return None;
+            }
+            if let Some(event) = pending {
+                return Some((sse_event(&event), (rx, None, true)));
+            }
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        let terminal = event.is_terminal();
+                        return Some((sse_event(&event), (rx, None, terminal)));
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Deployment event stream lagged, skipped {} events", skipped);
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+    
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn sse_event(event: &DeploymentEvent) -> Result<Event, axum::Error> {
+    Event::default().event(event.name()).json_data(event)
 }
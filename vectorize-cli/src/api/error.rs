@@ -0,0 +1,358 @@
+//! API error type
+//!
+//! Every error response has the same shape:
+//!
+//! ```json
+//! { "error": { "code": "not_found", "message": "Agent not found", "details": null } }
+//! ```
+//!
+//! `code` is stable and machine-readable, `message` is for humans, and
+//! `details` carries extra structured context (e.g. the permission that was
+//! missing) when there is any.
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use tracing::error;
+use vectorize_shared::{ProtocolCompatibility, MIN_SUPPORTED_PROTOCOL_VERSION, PROTOCOL_VERSION};
+
+/// Error returned by API handlers
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// Malformed or invalid input (400)
+    BadRequest { message: String, details: Option<Value> },
+    /// Missing or invalid credentials (401)
+    Unauthorized(String),
+    /// Authenticated but not allowed (403)
+    Forbidden { message: String, details: Option<Value> },
+    /// Resource does not exist (404)
+    NotFound(String),
+    /// Conflicts with current state, e.g. a duplicate name (409)
+    Conflict { message: String, details: Option<Value> },
+    /// Request body over the configured limit (413)
+    PayloadTooLarge { message: String, limit_bytes: usize },
+    /// Well-formed but can't be processed, e.g. a reused idempotency key (422)
+    Unprocessable { message: String, details: Option<Value> },
+    /// Client speaks a protocol version this server can't (426)
+    ProtocolMismatch { compatibility: ProtocolCompatibility, client_version: u32 },
+    /// Rate limit exceeded (429)
+    RateLimited { retry_after_secs: u64 },
+    /// An upstream service (Vector, an agent) failed (502)
+    BadGateway(String),
+    /// A dependency is temporarily unavailable (503)
+    Unavailable { message: String, details: Option<Value> },
+    /// Unexpected server-side failure (500)
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::BadRequest { message: message.into(), details: None }
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::Unauthorized(message.into())
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::Forbidden { message: message.into(), details: None }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::Conflict { message: message.into(), details: None }
+    }
+
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        ApiError::Unprocessable { message: message.into(), details: None }
+    }
+
+    pub fn bad_gateway(message: impl Into<String>) -> Self {
+        ApiError::BadGateway(message.into())
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        ApiError::Unavailable { message: message.into(), details: None }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::Internal(message.into())
+    }
+
+    /// Log `cause` and return a 500 with a generic `message`
+    ///
+    /// Keeps database and I/O errors out of responses while leaving a trace
+    /// in the server log.
+    pub fn internal_logged(message: impl Into<String>, cause: impl std::fmt::Display) -> Self {
+        let message = message.into();
+        error!("{}: {}", message, cause);
+        ApiError::Internal(message)
+    }
+
+    /// Attach structured details (400, 403, 409, 422 and 503 only; ignored otherwise)
+    pub fn with_details(self, details: Value) -> Self {
+        match self {
+            ApiError::BadRequest { message, .. } => ApiError::BadRequest { message, details: Some(details) },
+            ApiError::Forbidden { message, .. } => ApiError::Forbidden { message, details: Some(details) },
+            ApiError::Conflict { message, .. } => ApiError::Conflict { message, details: Some(details) },
+            ApiError::Unprocessable { message, .. } => ApiError::Unprocessable { message, details: Some(details) },
+            ApiError::Unavailable { message, .. } => ApiError::Unavailable { message, details: Some(details) },
+            other => other,
+        }
+    }
+
+    /// HTTP status for the error
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict { .. } => StatusCode::CONFLICT,
+            ApiError::PayloadTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Unprocessable { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::ProtocolMismatch { .. } => StatusCode::UPGRADE_REQUIRED,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Unavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Stable machine-readable code
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest { .. } => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden { .. } => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict { .. } => "conflict",
+            ApiError::PayloadTooLarge { .. } => "payload_too_large",
+            ApiError::Unprocessable { .. } => "unprocessable",
+            ApiError::ProtocolMismatch { .. } => "protocol_mismatch",
+            ApiError::RateLimited { .. } => "rate_limited",
+            ApiError::BadGateway(_) => "bad_gateway",
+            ApiError::Unavailable { .. } => "unavailable",
+            ApiError::Internal(_) => "internal",
+        }
+    }
+
+    /// Human-readable message
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::BadRequest { message, .. }
+            | ApiError::Forbidden { message, .. }
+            | ApiError::Conflict { message, .. }
+            | ApiError::PayloadTooLarge { message, .. }
+            | ApiError::Unprocessable { message, .. }
+            | ApiError::Unavailable { message, .. } => message.clone(),
+            ApiError::Unauthorized(message)
+            | ApiError::NotFound(message)
+            | ApiError::BadGateway(message)
+            | ApiError::Internal(message) => message.clone(),
+            ApiError::ProtocolMismatch { compatibility, .. } => compatibility.message().to_string(),
+            ApiError::RateLimited { .. } => "Rate limit exceeded".to_string(),
+        }
+    }
+
+    /// Structured details, if any
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            ApiError::BadRequest { details, .. }
+            | ApiError::Forbidden { details, .. }
+            | ApiError::Conflict { details, .. }
+            | ApiError::Unprocessable { details, .. }
+            | ApiError::Unavailable { details, .. } => details.clone(),
+            ApiError::PayloadTooLarge { limit_bytes, .. } => Some(json!({ "limit_bytes": limit_bytes })),
+            ApiError::ProtocolMismatch { compatibility, client_version } => Some(json!({
+                "reason": compatibility,
+                "client_version": client_version,
+                "server_version": PROTOCOL_VERSION,
+                "min_supported_version": MIN_SUPPORTED_PROTOCOL_VERSION,
+            })),
+            ApiError::RateLimited { retry_after_secs } => Some(json!({ "retry_after_secs": retry_after_secs })),
+            _ => None,
+        }
+    }
+
+    /// The JSON error body
+    pub fn body(&self) -> Value {
+        json!({
+            "error": {
+                "code": self.code(),
+                "message": self.message(),
+                "details": self.details(),
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(self.body());
+        match self {
+            ApiError::RateLimited { retry_after_secs } => {
+                (self.status(), [(header::RETRY_AFTER, retry_after_secs.to_string())], body).into_response()
+            }
+            _ => (self.status(), body).into_response(),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => ApiError::not_found("Not found"),
+            e => ApiError::internal_logged("Database error", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn response_parts(error: ApiError) -> (StatusCode, axum::http::HeaderMap, Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, headers, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn assert_shape(error: ApiError, status: StatusCode, code: &str, message: &str, details: Value) {
+        let (actual_status, _, body) = response_parts(error).await;
+        assert_eq!(actual_status, status);
+        assert_eq!(body["error"]["code"], code);
+        assert_eq!(body["error"]["message"], message);
+        assert_eq!(body["error"]["details"], details);
+        assert_eq!(body.as_object().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bad_request() {
+        assert_shape(ApiError::bad_request("Invalid name"), StatusCode::BAD_REQUEST, "bad_request", "Invalid name", Value::Null).await;
+        assert_shape(
+            ApiError::bad_request("Invalid config").with_details(json!({ "line": 3 })),
+            StatusCode::BAD_REQUEST,
+            "bad_request",
+            "Invalid config",
+            json!({ "line": 3 }),
+        ).await;
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized() {
+        assert_shape(ApiError::unauthorized("No token"), StatusCode::UNAUTHORIZED, "unauthorized", "No token", Value::Null).await;
+    }
+
+    #[tokio::test]
+    async fn test_forbidden() {
+        assert_shape(
+            ApiError::forbidden("Insufficient permissions").with_details(json!({ "required": "users_write" })),
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            "Insufficient permissions",
+            json!({ "required": "users_write" }),
+        ).await;
+    }
+
+    #[tokio::test]
+    async fn test_not_found() {
+        assert_shape(ApiError::not_found("Agent not found"), StatusCode::NOT_FOUND, "not_found", "Agent not found", Value::Null).await;
+    }
+
+    #[tokio::test]
+    async fn test_conflict() {
+        assert_shape(ApiError::conflict("Name taken"), StatusCode::CONFLICT, "conflict", "Name taken", Value::Null).await;
+        assert_shape(
+            ApiError::conflict("Pull left conflicts").with_details(json!({ "conflicts": ["a.toml"] })),
+            StatusCode::CONFLICT,
+            "conflict",
+            "Pull left conflicts",
+            json!({ "conflicts": ["a.toml"] }),
+        ).await;
+    }
+
+    #[tokio::test]
+    async fn test_payload_too_large() {
+        assert_shape(
+            ApiError::PayloadTooLarge { message: "Too big".to_string(), limit_bytes: 10 },
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "payload_too_large",
+            "Too big",
+            json!({ "limit_bytes": 10 }),
+        ).await;
+    }
+
+    #[tokio::test]
+    async fn test_unprocessable() {
+        assert_shape(ApiError::unprocessable("Key reused"), StatusCode::UNPROCESSABLE_ENTITY, "unprocessable", "Key reused", Value::Null).await;
+    }
+
+    #[tokio::test]
+    async fn test_protocol_mismatch() {
+        assert_shape(
+            ApiError::ProtocolMismatch { compatibility: ProtocolCompatibility::ClientTooNew, client_version: PROTOCOL_VERSION + 1 },
+            StatusCode::UPGRADE_REQUIRED,
+            "protocol_mismatch",
+            ProtocolCompatibility::ClientTooNew.message(),
+            json!({
+                "reason": "client_too_new",
+                "client_version": PROTOCOL_VERSION + 1,
+                "server_version": PROTOCOL_VERSION,
+                "min_supported_version": MIN_SUPPORTED_PROTOCOL_VERSION,
+            }),
+        ).await;
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let error = ApiError::RateLimited { retry_after_secs: 7 };
+        let (_, headers, _) = response_parts(error.clone()).await;
+        assert_eq!(headers.get(header::RETRY_AFTER).unwrap(), "7");
+
+        assert_shape(
+            error,
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limited",
+            "Rate limit exceeded",
+            json!({ "retry_after_secs": 7 }),
+        ).await;
+    }
+
+    #[tokio::test]
+    async fn test_bad_gateway() {
+        assert_shape(ApiError::bad_gateway("Agent unreachable"), StatusCode::BAD_GATEWAY, "bad_gateway", "Agent unreachable", Value::Null).await;
+    }
+
+    #[tokio::test]
+    async fn test_unavailable() {
+        assert_shape(ApiError::unavailable("Vector not running"), StatusCode::SERVICE_UNAVAILABLE, "unavailable", "Vector not running", Value::Null).await;
+    }
+
+    #[tokio::test]
+    async fn test_internal() {
+        assert_shape(ApiError::internal_logged("Failed to list agents", "disk I/O error"), StatusCode::INTERNAL_SERVER_ERROR, "internal", "Failed to list agents", Value::Null).await;
+    }
+
+    #[tokio::test]
+    async fn test_from_sqlx_error() {
+        assert_eq!(ApiError::from(sqlx::Error::RowNotFound).status(), StatusCode::NOT_FOUND);
+        assert_eq!(ApiError::from(sqlx::Error::PoolClosed).status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -4,23 +4,44 @@
 //! - Remote repository configuration
 //! - Push/pull synchronization
 //! - Branch management
+//! - Merge conflict resolution after a failed pull
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::{info, warn, error};
 
 use crate::AppState;
+use crate::api::ApiError;
+use crate::git_store::{ConflictResolution, GitStoreError};
 
 // =============================================================================
 // Request/Response Types
 // =============================================================================
 
+/// Query for comparing two branches
+#[derive(Debug, Deserialize)]
+pub struct CompareBranchesQuery {
+    pub base: String,
+    pub compare: String,
+}
+
+/// Request to resolve a conflicted file
+#[derive(Debug, Deserialize)]
+pub struct ResolveConflictRequest {
+    /// Path relative to the repository root
+    pub path: String,
+    /// "ours", "theirs" or "content"
+    pub resolution: String,
+    /// Merged file content (required for "content")
+    pub content: Option<String>,
+}
+
 /// Request to configure a remote
 #[derive(Debug, Deserialize)]
 pub struct ConfigureRemoteRequest {
@@ -34,6 +55,20 @@ pub struct SyncRequest {
     pub branch: Option<String>,
 }
 
+/// Request to push to a remote
+#[derive(Debug, Deserialize)]
+pub struct PushRequest {
+    pub branch: Option<String>,
+    /// Only publish changes to these groups; everything else stays local
+    pub groups: Option<Vec<String>>,
+}
+
+/// Query for previewing incoming changes
+#[derive(Debug, Deserialize)]
+pub struct IncomingQuery {
+    pub branch: Option<String>,
+}
+
 /// Request to create a branch
 #[derive(Debug, Deserialize)]
 pub struct CreateBranchRequest {
@@ -54,106 +89,100 @@ pub struct RemoteResponse {
 /// List all configured remotes
 pub async fn list_remotes(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    match state.git_store.list_remotes() {
-        Ok(remotes) => {
-            (StatusCode::OK, Json(serde_json::json!({
-                "remotes": remotes
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to list remotes: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let remotes = state.git_store.list_remotes().map_err(|e| {
+        error!("Failed to list remotes: {}", e);
+        ApiError::internal(e.to_string())
+    })?;
+    
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "remotes": remotes
+    }))))
 }
 
 /// Configure a remote
 pub async fn configure_remote(
     State(state): State<Arc<AppState>>,
     Json(request): Json<ConfigureRemoteRequest>,
-) -> impl IntoResponse {
-    match state.git_store.configure_remote(&request.name, &request.url) {
-        Ok(_) => {
-            info!("Configured remote '{}' -> {}", request.name, request.url);
-            (StatusCode::OK, Json(RemoteResponse {
-                success: true,
-                message: format!("Remote '{}' configured", request.name),
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to configure remote: {}", e);
-            (StatusCode::BAD_REQUEST, Json(RemoteResponse {
-                success: false,
-                message: e.to_string(),
-            })).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    state.git_store.configure_remote(&request.name, &request.url).map_err(|e| {
+        error!("Failed to configure remote: {}", e);
+        ApiError::bad_request(e.to_string())
+    })?;
+    
+    info!("Configured remote '{}' -> {}", request.name, request.url);
+    Ok((StatusCode::OK, Json(RemoteResponse {
+        success: true,
+        message: format!("Remote '{}' configured", request.name),
+    })))
 }
 
 /// Remove a remote
 pub async fn delete_remote(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> impl IntoResponse {
-    match state.git_store.remove_remote(&name) {
-        Ok(_) => {
-            info!("Removed remote '{}'", name);
-            (StatusCode::OK, Json(RemoteResponse {
-                success: true,
-                message: format!("Remote '{}' removed", name),
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to remove remote: {}", e);
-            (StatusCode::BAD_REQUEST, Json(RemoteResponse {
-                success: false,
-                message: e.to_string(),
-            })).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    state.git_store.remove_remote(&name).map_err(|e| {
+        error!("Failed to remove remote: {}", e);
+        ApiError::bad_request(e.to_string())
+    })?;
+    
+    info!("Removed remote '{}'", name);
+    Ok((StatusCode::OK, Json(RemoteResponse {
+        success: true,
+        message: format!("Remote '{}' removed", name),
+    })))
 }
 
 /// Push to a remote
+///
+/// With `groups`, only changes to those groups are published.
 pub async fn push_to_remote(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-    Json(request): Json<SyncRequest>,
-) -> impl IntoResponse {
-    match state.git_store.push(&name, request.branch.as_deref()) {
-        Ok(result) => {
-            info!("Pushed to remote '{}' ({})", name, result.branch);
-            (StatusCode::OK, Json(result)).into_response()
+    Json(request): Json<PushRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let result = match &request.groups {
+        Some(groups) => {
+            let paths: Vec<String> = groups.iter().map(|g| format!("groups/{}", g)).collect();
+            let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+            state.git_store.push_paths(&name, request.branch.as_deref(), &paths)
         }
-        Err(e) => {
-            error!("Failed to push: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "success": false,
-                "error": e.to_string()
-            }))).into_response()
-        }
-    }
+        None => state.git_store.push(&name, request.branch.as_deref()),
+    };
+    let result = result.map_err(|e| {
+        error!("Failed to push: {}", e);
+        ApiError::bad_request(e.to_string())
+    })?;
+    
+    info!("Pushed to remote '{}' ({})", name, result.branch);
+    Ok((StatusCode::OK, Json(result)))
 }
 
 /// Pull from a remote
+///
+/// A pull that leaves merge conflicts returns 409 with the conflicted files
+/// in `details.conflicts`.
 pub async fn pull_from_remote(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
     Json(request): Json<SyncRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     match state.git_store.pull(&name, request.branch.as_deref()) {
         Ok(result) => {
             info!("Pulled from remote '{}' ({})", name, result.branch);
-            (StatusCode::OK, Json(result)).into_response()
+            Ok((StatusCode::OK, Json(result)))
+        }
+        Err(GitStoreError::Conflict(message)) => {
+            warn!("Pull from '{}' left conflicts", name);
+            let conflicts = state.git_store.list_conflicts().unwrap_or_default();
+            Err(ApiError::conflict(message).with_details(serde_json::json!({
+                "conflicts": conflicts
+            })))
         }
         Err(e) => {
             error!("Failed to pull: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "success": false,
-                "error": e.to_string()
-            }))).into_response()
+            Err(ApiError::bad_request(e.to_string()))
         }
     }
 }
@@ -163,103 +192,207 @@ pub async fn sync_with_remote(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
     Json(request): Json<SyncRequest>,
-) -> impl IntoResponse {
-    match state.git_store.sync(&name, request.branch.as_deref()) {
-        Ok(result) => {
-            info!("Synced with remote '{}'", name);
-            (StatusCode::OK, Json(result)).into_response()
-        }
-        Err(e) => {
-            error!("Failed to sync: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "success": false,
-                "error": e.to_string()
-            }))).into_response()
+) -> Result<impl IntoResponse, ApiError> {
+    let result = state.git_store.sync(&name, request.branch.as_deref()).map_err(|e| {
+        error!("Failed to sync: {}", e);
+        ApiError::bad_request(e.to_string())
+    })?;
+    
+    info!("Synced with remote '{}'", name);
+    Ok((StatusCode::OK, Json(result)))
+}
+
+/// Fetch and preview what a pull from a remote would bring in, without merging
+pub async fn preview_incoming(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<IncomingQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let incoming = state.git_store.preview_incoming(&name, query.branch.as_deref()).map_err(|e| match e {
+        GitStoreError::NotFound(message) => ApiError::not_found(message),
+        e => {
+            error!("Failed to preview incoming changes: {}", e);
+            ApiError::bad_request(e.to_string())
         }
-    }
+    })?;
+    
+    Ok((StatusCode::OK, Json(incoming)))
 }
 
 /// Get sync status with a remote
 pub async fn get_sync_status(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> impl IntoResponse {
-    match state.git_store.sync_status(&name, None) {
-        Ok(status) => {
-            (StatusCode::OK, Json(status)).into_response()
+) -> Result<impl IntoResponse, ApiError> {
+    let status = state.git_store.sync_status(&name, None).map_err(|e| {
+        error!("Failed to get sync status: {}", e);
+        ApiError::bad_request(e.to_string())
+    })?;
+    
+    Ok((StatusCode::OK, Json(status)))
+}
+
+/// List files conflicted by a failed pull
+pub async fn list_conflicts(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let conflicts = state.git_store.list_conflicts().map_err(|e| {
+        error!("Failed to list conflicts: {}", e);
+        ApiError::internal(e.to_string())
+    })?;
+    
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "conflicts": conflicts
+    }))))
+}
+
+/// Resolve a conflicted file; the merge is committed once none remain
+pub async fn resolve_conflict(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ResolveConflictRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let resolution = match (request.resolution.as_str(), request.content) {
+        ("ours", _) => ConflictResolution::Ours,
+        ("theirs", _) => ConflictResolution::Theirs,
+        ("content", Some(content)) => ConflictResolution::Content(content),
+        ("content", None) => {
+            return Err(ApiError::bad_request("content is required when resolution is 'content'"));
         }
-        Err(e) => {
-            error!("Failed to get sync status: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+        (other, _) => {
+            return Err(ApiError::bad_request(format!(
+                "Invalid resolution '{}': expected ours, theirs or content",
+                other
+            )));
         }
-    }
+    };
+    
+    let commit = state.git_store.resolve_conflict(&request.path, resolution).map_err(|e| match e {
+        GitStoreError::NotFound(message) => ApiError::not_found(message),
+        e => {
+            error!("Failed to resolve conflict: {}", e);
+            ApiError::internal(e.to_string())
+        }
+    })?;
+    
+    let remaining = state.git_store.list_conflicts().unwrap_or_default();
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "success": true,
+        "merged": commit.is_some(),
+        "commit": commit,
+        "remaining": remaining
+    }))))
 }
 
-/// List branches
-pub async fn list_branches(
+/// Abort an in-progress merge, restoring the state before the pull
+pub async fn abort_merge(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
-    match state.git_store.list_branches() {
-        Ok(branches) => {
-            let current = state.git_store.current_branch().unwrap_or_default();
-            (StatusCode::OK, Json(serde_json::json!({
-                "current": current,
-                "branches": branches
-            }))).into_response()
+) -> Result<impl IntoResponse, ApiError> {
+    state.git_store.abort_merge().map_err(|e| match e {
+        GitStoreError::NotFound(message) => ApiError::conflict(message),
+        e => {
+            error!("Failed to abort merge: {}", e);
+            ApiError::internal(e.to_string())
         }
-        Err(e) => {
-            error!("Failed to list branches: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+    })?;
+    
+    info!("Aborted merge");
+    Ok((StatusCode::OK, Json(RemoteResponse {
+        success: true,
+        message: "Merge aborted".to_string(),
+    })))
+}
+
+/// Check a commit's signature
+pub async fn verify_commit(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    let signature = state.git_store.verify_commit(&hash).map_err(|e| match e {
+        GitStoreError::Git(e) if e.code() == git2::ErrorCode::NotFound || e.class() == git2::ErrorClass::Invalid => {
+            ApiError::not_found(format!("Commit not found: {}", hash))
         }
-    }
+        e => {
+            error!("Failed to verify commit: {}", e);
+            ApiError::internal(e.to_string())
+        }
+    })?;
+    
+    Ok((StatusCode::OK, Json(signature)))
+}
+
+/// List branches
+pub async fn list_branches(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let branches = state.git_store.list_branches().map_err(|e| {
+        error!("Failed to list branches: {}", e);
+        ApiError::internal(e.to_string())
+    })?;
+    
+    let current = state.git_store.current_branch().unwrap_or_default();
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "current": current,
+        "branches": branches
+    }))))
+}
+
+/// Compare two branches
+///
+/// Shows the config changes in `compare` that are not yet in `base`, e.g.
+/// what is pending promotion from staging to main.
+pub async fn compare_branches(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CompareBranchesQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let (diff, changed_groups) = state.git_store.diff_branches(&query.base, &query.compare)
+        .and_then(|diff| Ok((diff, state.git_store.changed_groups(&query.base, &query.compare)?)))
+        .map_err(|e| match e {
+            GitStoreError::NotFound(message) => ApiError::not_found(message),
+            e => {
+                error!("Failed to compare branches: {}", e);
+                ApiError::internal(e.to_string())
+            }
+        })?;
+    
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "base": query.base,
+        "compare": query.compare,
+        "has_changes": !changed_groups.is_empty() || !diff.is_empty(),
+        "changed_groups": changed_groups,
+        "diff": diff
+    }))))
 }
 
 /// Create a new branch
 pub async fn create_branch(
     State(state): State<Arc<AppState>>,
     Json(request): Json<CreateBranchRequest>,
-) -> impl IntoResponse {
-    match state.git_store.create_branch(&request.name) {
-        Ok(_) => {
-            info!("Created branch '{}'", request.name);
-            (StatusCode::CREATED, Json(RemoteResponse {
-                success: true,
-                message: format!("Branch '{}' created", request.name),
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to create branch: {}", e);
-            (StatusCode::BAD_REQUEST, Json(RemoteResponse {
-                success: false,
-                message: e.to_string(),
-            })).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    state.git_store.create_branch(&request.name).map_err(|e| {
+        error!("Failed to create branch: {}", e);
+        ApiError::bad_request(e.to_string())
+    })?;
+    
+    info!("Created branch '{}'", request.name);
+    Ok((StatusCode::CREATED, Json(RemoteResponse {
+        success: true,
+        message: format!("Branch '{}' created", request.name),
+    })))
 }
 
 /// Checkout a branch
 pub async fn checkout_branch(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
-) -> impl IntoResponse {
-    match state.git_store.checkout_branch(&name) {
-        Ok(_) => {
-            info!("Checked out branch '{}'", name);
-            (StatusCode::OK, Json(RemoteResponse {
-                success: true,
-                message: format!("Checked out branch '{}'", name),
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to checkout branch: {}", e);
-            (StatusCode::BAD_REQUEST, Json(RemoteResponse {
-                success: false,
-                message: e.to_string(),
-            })).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    state.git_store.checkout_branch(&name).map_err(|e| {
+        error!("Failed to checkout branch: {}", e);
+        ApiError::bad_request(e.to_string())
+    })?;
+    
+    info!("Checked out branch '{}'", name);
+    Ok((StatusCode::OK, Json(RemoteResponse {
+        success: true,
+        message: format!("Checked out branch '{}'", name),
+    })))
 }
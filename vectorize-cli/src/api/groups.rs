@@ -7,9 +7,9 @@
 //! - Version history and rollback
 
 use axum::{
-    extract::{Path, State, Query},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Extension, Path, State, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
@@ -17,8 +17,16 @@ use std::sync::Arc;
 use tracing::{info, warn, error};
 
 use crate::AppState;
-use crate::db::models::{WorkerGroupResponse, AgentResponse};
-use crate::db::repository::{WorkerGroupRepository, AgentRepository};
+use crate::alerts::NotificationChannel;
+use crate::api::{accepts, ApiError};
+use crate::api::deployments::parse_time_bound;
+use crate::api::agents::{uptime_window, AgentUptimeResponse, UptimeQuery, UptimeReport};
+use crate::db::models::{WorkerGroup, WorkerGroupResponse, AgentResponse};
+use crate::db::repository::{WorkerGroupRepository, AgentRepository, DeploymentRepository, UptimeCounts};
+use crate::deployment::{frozen_message, DeploymentOptions};
+use crate::git_store::GitStoreError;
+use crate::rbac::{require_authenticated, require_permission, AuthenticatedUser};
+use crate::validation::{ConfigValidator, ValidationWarning, VectorVersion};
 
 /// Request to create a new worker group
 #[derive(Debug, Deserialize)]
@@ -35,6 +43,8 @@ pub struct CreateGroupRequest {
     pub requires_approval: bool,
     /// List of user IDs/emails who can approve deployments
     pub approvers: Option<Vec<String>>,
+    /// Options used by deployments that don't specify their own
+    pub default_deployment_options: Option<DeploymentOptions>,
 }
 
 fn default_strategy() -> String {
@@ -49,6 +59,40 @@ pub struct UpdateGroupRequest {
     pub deployment_strategy: Option<String>,
     pub requires_approval: Option<bool>,
     pub approvers: Option<Vec<String>>,
+    /// Alert channels notified when the group's deployments finish;
+    /// an empty list disables notifications
+    pub notification_channels: Option<Vec<NotificationChannel>>,
+    /// Options used by deployments that don't specify their own; `null`
+    /// clears them
+    #[serde(default, deserialize_with = "present")]
+    pub default_deployment_options: Option<Option<DeploymentOptions>>,
+    /// Override auto-deregistration of unreachable agents; `null` falls
+    /// back to the server setting
+    #[serde(default, deserialize_with = "present")]
+    pub auto_deregister: Option<Option<bool>>,
+    #[serde(default, deserialize_with = "present")]
+    pub deregister_after_secs: Option<Option<u64>>,
+}
+
+/// Tell an explicit `null` (`Some(None)`) apart from a missing field (`None`)
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Validate default deployment options and serialize them for storage
+fn deployment_defaults_json(options: Option<&DeploymentOptions>) -> Result<Option<String>, ApiError> {
+    let Some(options) = options else {
+        return Ok(None);
+    };
+    options.validate()
+        .map_err(|e| ApiError::bad_request(format!("Invalid default_deployment_options: {}", e)))?;
+    serde_json::to_string(options)
+        .map(Some)
+        .map_err(|e| ApiError::internal_logged("Failed to serialize deployment options", e))
 }
 
 /// Request to update group configuration
@@ -56,6 +100,10 @@ pub struct UpdateGroupRequest {
 pub struct UpdateConfigRequest {
     /// TOML configuration content
     pub config: String,
+    /// Version the editor loaded; the write is refused with 409 if the
+    /// group's config has changed since (also accepted as `If-Match`)
+    #[serde(default)]
+    pub base_version: Option<String>,
     /// Optional commit message (reserved for future use)
     #[allow(dead_code)]
     pub message: Option<String>,
@@ -76,12 +124,58 @@ pub struct RollbackRequest {
     pub version: String,
 }
 
+/// Request to copy another group's configuration
+#[derive(Debug, Deserialize)]
+pub struct CopyConfigRequest {
+    /// Group to copy from
+    pub source_group_id: String,
+    /// Commit hash of the source config (default: latest)
+    pub version: Option<String>,
+}
+
+/// Query parameters for deleting a group
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteGroupQuery {
+    /// Cancel active deployments and unassign agents instead of refusing
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Query parameters for listing groups
+#[derive(Debug, Deserialize)]
+pub struct ListGroupsQuery {
+    /// Maximum number of groups to return
+    pub limit: Option<i64>,
+    /// Number of groups to skip
+    pub offset: Option<i64>,
+}
+
 /// Query parameters for history
 #[derive(Debug, Deserialize)]
 pub struct HistoryQuery {
     pub limit: Option<usize>,
 }
 
+/// Query parameters for the config history of all groups
+#[derive(Debug, Deserialize)]
+pub struct ConfigHistoryQuery {
+    /// Earliest commit time (RFC 3339 or YYYY-MM-DD)
+    pub since: Option<String>,
+    /// Latest commit time, exclusive (a plain date includes that day)
+    pub until: Option<String>,
+}
+
+/// Query parameters for history search
+#[derive(Debug, Deserialize)]
+pub struct HistorySearchQuery {
+    /// Text to find in commit messages and config diffs
+    pub q: String,
+    pub limit: Option<usize>,
+    /// Only search commit messages (faster on large repositories)
+    #[serde(default)]
+    pub message_only: bool,
+}
+
 /// Query parameters for diff
 #[derive(Debug, Deserialize)]
 pub struct DiffQuery {
@@ -99,6 +193,13 @@ pub struct ConfigAtVersionResponse {
     pub group_name: String,
 }
 
+/// Query for validating a stored config
+#[derive(Debug, Deserialize)]
+pub struct ValidateStoredConfigQuery {
+    /// Commit hash to validate (defaults to the current config)
+    pub version: Option<String>,
+}
+
 /// Response for diff
 #[derive(Debug, Serialize)]
 pub struct DiffResponse {
@@ -135,145 +236,247 @@ pub struct AgentDeployResult {
     pub message: String,
 }
 
+/// Fetch a worker group, mapping a missing group to 404
+pub(crate) async fn find_group(pool: &crate::db::DbPool, id: &str, label: &str) -> Result<WorkerGroup, ApiError> {
+    WorkerGroupRepository::get_by_id(pool, id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get worker group", e))?
+        .ok_or_else(|| ApiError::not_found(format!("{} not found", label)))
+}
+
 /// List all worker groups
+///
+/// When `limit` is given only that page is returned, and the total number of
+/// groups is reported in the `X-Total-Count` header.
 pub async fn list_groups(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+    Query(query): Query<ListGroupsQuery>,
+) -> Result<Response, ApiError> {
     let pool = state.db.pool();
     
-    match WorkerGroupRepository::list(pool).await {
-        Ok(groups) => {
-            // Convert to response format with agent counts and health status
-            let mut responses: Vec<WorkerGroupResponse> = Vec::new();
-            
-            for group in groups {
-                // Get real agent health counts from the database
-                let (total, healthy, unhealthy) = WorkerGroupRepository::get_agent_health_counts(pool, &group.id)
-                    .await
-                    .unwrap_or((0, 0, 0));
-                
-                let mut response = WorkerGroupResponse::from(group);
-                response.agent_count = Some(total);
-                response.healthy_count = Some(healthy);
-                response.unhealthy_count = Some(unhealthy);
-                responses.push(response);
-            }
-            
-            (StatusCode::OK, Json(responses)).into_response()
-        }
-        Err(e) => {
-            error!("Failed to list worker groups: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list worker groups"
-            }))).into_response()
+    let (groups, total) = match query.limit {
+        Some(limit) => {
+            let total = WorkerGroupRepository::count(pool).await
+                .map_err(|e| ApiError::internal_logged("Failed to list worker groups", e))?;
+            let offset = query.offset.unwrap_or(0).max(0);
+            (WorkerGroupRepository::list_paginated(pool, limit.max(0), offset).await, Some(total))
         }
+        None => (WorkerGroupRepository::list(pool).await, None),
+    };
+    let groups = groups.map_err(|e| ApiError::internal_logged("Failed to list worker groups", e))?;
+    
+    // Convert to response format with agent counts and health status
+    let mut responses: Vec<WorkerGroupResponse> = Vec::new();
+    
+    for group in groups {
+        // Get real agent health counts from the database
+        let (total, healthy, unhealthy) = WorkerGroupRepository::get_agent_health_counts(pool, &group.id)
+            .await
+            .unwrap_or((0, 0, 0));
+        
+        let mut response = WorkerGroupResponse::from(group);
+        response.agent_count = Some(total);
+        response.healthy_count = Some(healthy);
+        response.unhealthy_count = Some(unhealthy);
+        responses.push(response);
     }
+    
+    Ok(match total {
+        Some(total) => {
+            (StatusCode::OK, [("X-Total-Count", total.to_string())], Json(responses)).into_response()
+        }
+        None => (StatusCode::OK, Json(responses)).into_response(),
+    })
 }
 
 /// Create a new worker group
 pub async fn create_group(
     State(state): State<Arc<AppState>>,
     Json(request): Json<CreateGroupRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Check if group with this name already exists
     if let Ok(Some(_)) = WorkerGroupRepository::get_by_name(pool, &request.name).await {
-        return (StatusCode::CONFLICT, Json(serde_json::json!({
-            "error": "A worker group with this name already exists"
-        }))).into_response();
+        return Err(ApiError::conflict("A worker group with this name already exists"));
     }
     
     // Validate deployment strategy
     if !["basic", "rolling", "canary"].contains(&request.deployment_strategy.as_str()) {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "Invalid deployment strategy. Must be: basic, rolling, or canary"
-        }))).into_response();
+        return Err(ApiError::bad_request("Invalid deployment strategy. Must be: basic, rolling, or canary"));
     }
+    let defaults_json = deployment_defaults_json(request.default_deployment_options.as_ref())?;
     
     // Create the group in database
-    match WorkerGroupRepository::create(
+    let group = WorkerGroupRepository::create(
         pool,
         &request.name,
         request.description.as_deref(),
         None, // TODO: get from auth context
+    ).await.map_err(|e| ApiError::internal_logged("Failed to create worker group", e))?;
+    
+    // Update with additional fields
+    let approvers_json = request.approvers
+        .map(|a| serde_json::to_string(&a).unwrap_or_default());
+    
+    if let Err(e) = WorkerGroupRepository::update(
+        pool,
+        &group.id,
+        None,
+        None,
+        Some(&request.deployment_strategy),
+        Some(request.requires_approval),
+        approvers_json.as_deref(),
     ).await {
-        Ok(group) => {
-            // Update with additional fields
-            let approvers_json = request.approvers
-                .map(|a| serde_json::to_string(&a).unwrap_or_default());
-            
-            if let Err(e) = WorkerGroupRepository::update(
-                pool,
-                &group.id,
-                None,
-                None,
-                Some(&request.deployment_strategy),
-                Some(request.requires_approval),
-                approvers_json.as_deref(),
-            ).await {
-                warn!("Failed to update group settings: {}", e);
-            }
-            
-            // Create the group directory in git store
-            if let Err(e) = state.git_store.create_group(&request.name) {
-                warn!("Failed to create git directory for group: {}", e);
-            }
-            
-            info!("Created worker group: {}", request.name);
-            
-            // Fetch the updated group
-            match WorkerGroupRepository::get_by_id(pool, &group.id).await {
-                Ok(Some(updated)) => {
-                    (StatusCode::CREATED, Json(WorkerGroupResponse::from(updated))).into_response()
-                }
-                _ => {
-                    (StatusCode::CREATED, Json(WorkerGroupResponse::from(group))).into_response()
-                }
-            }
+        warn!("Failed to update group settings: {}", e);
+    }
+    if defaults_json.is_some() {
+        if let Err(e) = WorkerGroupRepository::set_default_deployment_options(pool, &group.id, defaults_json.as_deref()).await {
+            warn!("Failed to set default deployment options: {}", e);
         }
-        Err(e) => {
-            error!("Failed to create worker group: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to create worker group: {}", e)
-            }))).into_response()
+    }
+    
+    // Create the group directory in git store
+    if let Err(e) = state.git_store.create_group(&request.name) {
+        warn!("Failed to create git directory for group: {}", e);
+    }
+    
+    info!("Created worker group: {}", request.name);
+    
+    // Fetch the updated group
+    let group = match WorkerGroupRepository::get_by_id(pool, &group.id).await {
+        Ok(Some(updated)) => updated,
+        _ => group,
+    };
+    Ok((StatusCode::CREATED, Json(WorkerGroupResponse::from(group))))
+}
+
+/// Request to clone a worker group
+#[derive(Debug, Deserialize)]
+pub struct CloneGroupRequest {
+    /// Name of the new group (must be unique)
+    pub name: String,
+    /// Description for the new group (defaults to the source's)
+    pub description: Option<String>,
+    /// Move the source group's agents to the new group
+    ///
+    /// An agent belongs to one group and its URL is unique, so membership
+    /// can be moved but not duplicated.
+    #[serde(default)]
+    pub move_agents: bool,
+}
+
+/// Response for a cloned group
+#[derive(Debug, Serialize)]
+pub struct CloneGroupResponse {
+    #[serde(flatten)]
+    pub group: WorkerGroupResponse,
+    pub source_group_id: String,
+    /// Version of the source config that was copied
+    pub source_version: String,
+    pub agents_moved: usize,
+}
+
+/// Clone a worker group
+///
+/// The new group gets the source's deployment settings and a copy of its
+/// current config, committed to the new group's own directory so the two
+/// histories are independent from here on.
+pub async fn clone_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<CloneGroupRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let source = find_group(pool, &id, "Worker group").await?;
+    
+    if let Ok(Some(_)) = WorkerGroupRepository::get_by_name(pool, &request.name).await {
+        return Err(ApiError::conflict("A worker group with this name already exists"));
+    }
+    
+    // Check the source config exists before creating anything
+    let source_version = match source.current_config_version.clone() {
+        Some(version) => version,
+        None => state.git_store.head_hash()
+            .map_err(|e| ApiError::internal_logged("Failed to get current version", e))?,
+    };
+    match state.git_store.get_config_at_version(&source.name, &source_version) {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(ApiError::not_found(format!("Group {} has no config to clone", source.name))),
+        Err(e) => return Err(ApiError::internal_logged("Failed to read source config", e)),
+    }
+    
+    let description = request.description.as_deref().or(source.description.as_deref());
+    let group = WorkerGroupRepository::create(pool, &request.name, description, None)
+        .await
+        .map_err(|e| ApiError::internal_logged("Failed to create worker group", e))?;
+    
+    WorkerGroupRepository::update(
+        pool,
+        &group.id,
+        None,
+        None,
+        Some(&source.deployment_strategy),
+        Some(source.requires_approval),
+        source.approvers.as_deref(),
+    ).await.map_err(|e| ApiError::internal_logged("Failed to copy group settings", e))?;
+    WorkerGroupRepository::set_default_deployment_options(pool, &group.id, source.default_deployment_options.as_deref())
+        .await
+        .map_err(|e| ApiError::internal_logged("Failed to copy group settings", e))?;
+    
+    if let Err(e) = state.git_store.create_group(&request.name) {
+        warn!("Failed to create git directory for group: {}", e);
+    }
+    let new_version = state.git_store.copy_config(&source.name, &request.name, Some(&source_version))
+        .map_err(|e| ApiError::internal_logged("Failed to copy configuration", e))?;
+    WorkerGroupRepository::update_config_version(pool, &group.id, &new_version)
+        .await
+        .map_err(|e| ApiError::internal_logged("Failed to update group config version", e))?;
+    
+    let mut agents_moved = 0;
+    if request.move_agents {
+        let agents = AgentRepository::list_by_group(pool, &source.id).await
+            .map_err(|e| ApiError::internal_logged("Failed to list group agents", e))?;
+        for agent in agents {
+            AgentRepository::update(pool, &agent.id, None, Some(Some(&group.id))).await
+                .map_err(|e| ApiError::internal_logged("Failed to move agent", e))?;
+            agents_moved += 1;
         }
     }
+    
+    info!("Cloned worker group {} to {} ({} agents moved)", source.name, request.name, agents_moved);
+    
+    let group = WorkerGroupRepository::get_by_id(pool, &group.id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get worker group", e))?
+        .unwrap_or(group);
+    Ok((StatusCode::CREATED, Json(CloneGroupResponse {
+        group: WorkerGroupResponse::from(group),
+        source_group_id: source.id,
+        source_version,
+        agents_moved,
+    })))
 }
 
 /// Get a specific worker group
 pub async fn get_group(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => {
-            // Get real agent health counts from the database
-            let (total, healthy, unhealthy) = WorkerGroupRepository::get_agent_health_counts(pool, &id)
-                .await
-                .unwrap_or((0, 0, 0));
-            
-            let mut response = WorkerGroupResponse::from(group);
-            response.agent_count = Some(total);
-            response.healthy_count = Some(healthy);
-            response.unhealthy_count = Some(unhealthy);
-            
-            (StatusCode::OK, Json(response)).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Worker group not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get worker group"
-            }))).into_response()
-        }
-    }
+    let group = find_group(pool, &id, "Worker group").await?;
+    
+    // Get real agent health counts from the database
+    let (total, healthy, unhealthy) = WorkerGroupRepository::get_agent_health_counts(pool, &id)
+        .await
+        .unwrap_or((0, 0, 0));
+    
+    let mut response = WorkerGroupResponse::from(group);
+    response.agent_count = Some(total);
+    response.healthy_count = Some(healthy);
+    response.unhealthy_count = Some(unhealthy);
+    
+    Ok((StatusCode::OK, Json(response)))
 }
 
 /// Update a worker group
@@ -281,22 +484,28 @@ pub async fn update_group(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(request): Json<UpdateGroupRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Validate deployment strategy if provided
     if let Some(ref strategy) = request.deployment_strategy {
         if !["basic", "rolling", "canary"].contains(&strategy.as_str()) {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Invalid deployment strategy. Must be: basic, rolling, or canary"
-            }))).into_response();
+            return Err(ApiError::bad_request("Invalid deployment strategy. Must be: basic, rolling, or canary"));
         }
     }
     
+    let defaults_json = request.default_deployment_options.as_ref()
+        .map(|o| deployment_defaults_json(o.as_ref()))
+        .transpose()?;
+    
+    if request.deregister_after_secs == Some(Some(0)) {
+        return Err(ApiError::bad_request("deregister_after_secs must be greater than 0"));
+    }
+    
     let approvers_json = request.approvers
         .map(|a| serde_json::to_string(&a).unwrap_or_default());
     
-    match WorkerGroupRepository::update(
+    let group = WorkerGroupRepository::update(
         pool,
         &id,
         request.name.as_deref(),
@@ -304,188 +513,275 @@ pub async fn update_group(
         request.deployment_strategy.as_deref(),
         request.requires_approval,
         approvers_json.as_deref(),
-    ).await {
-        Ok(Some(group)) => {
-            info!("Updated worker group: {}", id);
-            (StatusCode::OK, Json(WorkerGroupResponse::from(group))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Worker group not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to update worker group: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to update worker group"
-            }))).into_response()
+    ).await
+        .map_err(|e| ApiError::internal_logged("Failed to update worker group", e))?
+        .ok_or_else(|| ApiError::not_found("Worker group not found"))?;
+    
+    let group = match request.notification_channels {
+        Some(channels) => {
+            let channels_json = (!channels.is_empty())
+                .then(|| serde_json::to_string(&channels).unwrap_or_default());
+            WorkerGroupRepository::set_notification_channels(pool, &id, channels_json.as_deref())
+                .await
+                .map_err(|e| ApiError::internal_logged("Failed to update notification channels", e))?
+                .unwrap_or(group)
         }
+        None => group,
+    };
+    
+    let group = match defaults_json {
+        Some(defaults) => WorkerGroupRepository::set_default_deployment_options(pool, &id, defaults.as_deref())
+            .await
+            .map_err(|e| ApiError::internal_logged("Failed to update default deployment options", e))?
+            .unwrap_or(group),
+        None => group,
+    };
+    
+    // Either setting can be changed on its own
+    let group = if request.auto_deregister.is_some() || request.deregister_after_secs.is_some() {
+        let enabled = request.auto_deregister.unwrap_or(group.auto_deregister);
+        let after_secs = request.deregister_after_secs
+            .map(|secs| secs.map(|s| s as i64))
+            .unwrap_or(group.deregister_after_secs);
+        WorkerGroupRepository::set_auto_deregister(pool, &id, enabled, after_secs)
+            .await
+            .map_err(|e| ApiError::internal_logged("Failed to update auto-deregistration", e))?
+            .unwrap_or(group)
+    } else {
+        group
+    };
+    
+    info!("Updated worker group: {}", id);
+    Ok((StatusCode::OK, Json(WorkerGroupResponse::from(group))))
+}
+
+/// Freeze a group, blocking new deployments (requires `groups_deploy`)
+///
+/// Deployments already running are not affected.
+pub async fn freeze_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    user: Option<Extension<AuthenticatedUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_group_frozen(&state, &id, user, true).await
+}
+
+/// Unfreeze a group, allowing deployments again (requires `groups_deploy`)
+pub async fn unfreeze_group(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    user: Option<Extension<AuthenticatedUser>>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_group_frozen(&state, &id, user, false).await
+}
+
+async fn set_group_frozen(
+    state: &AppState,
+    id: &str,
+    user: Option<Extension<AuthenticatedUser>>,
+    frozen: bool,
+) -> Result<Json<WorkerGroupResponse>, ApiError> {
+    let user = require_authenticated(user)?;
+    require_permission(&user, "groups_deploy")?;
+    
+    let group = WorkerGroupRepository::set_frozen(state.db.pool(), id, frozen)
+        .await
+        .map_err(|e| ApiError::internal_logged("Failed to update worker group", e))?
+        .ok_or_else(|| ApiError::not_found("Worker group not found"))?;
+    
+    if frozen {
+        warn!("Worker group {} frozen by {}", group.name, user.user_id);
+    } else {
+        info!("Worker group {} unfrozen by {}", group.name, user.user_id);
     }
+    Ok(Json(WorkerGroupResponse::from(group)))
 }
 
 /// Delete a worker group
+///
+/// Refuses with 409 while the group has assigned agents or an active
+/// deployment, unless `?force=true`: active deployments are then cancelled
+/// and agents unassigned.
 pub async fn delete_group(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+    Query(query): Query<DeleteGroupQuery>,
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Get the group first to get its name
-    let group_name = match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => group.name,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Worker group not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get worker group"
-            }))).into_response();
-        }
-    };
+    let group_name = find_group(pool, &id, "Worker group").await?.name;
     
     // Check if group has agents assigned
     let agent_count = WorkerGroupRepository::get_agent_count(pool, &id)
         .await
         .unwrap_or(0);
     
-    if agent_count > 0 {
-        return (StatusCode::CONFLICT, Json(serde_json::json!({
-            "error": format!("Cannot delete group with {} assigned agents. Unassign agents first.", agent_count)
-        }))).into_response();
+    if agent_count > 0 && !query.force {
+        return Err(ApiError::conflict(format!(
+            "Cannot delete group with {} assigned agents. Unassign agents first.",
+            agent_count
+        )));
     }
     
-    // Delete from database
-    match WorkerGroupRepository::delete(pool, &id).await {
-        Ok(true) => {
-            // Delete from git store
-            if let Err(e) = state.git_store.delete_group(&group_name) {
-                warn!("Failed to delete git directory for group: {}", e);
-            }
-            
-            info!("Deleted worker group: {} ({})", group_name, id);
-            (StatusCode::NO_CONTENT, "").into_response()
+    // Check for a deployment the executor may still be working on
+    match DeploymentRepository::get_active_for_group(pool, &id).await {
+        Ok(Some(deployment)) if !query.force => {
+            return Err(ApiError::conflict(format!(
+                "Cannot delete group with active deployment {} ({}). Wait for it to finish or cancel it.",
+                deployment.id, deployment.status
+            )).with_details(serde_json::json!({
+                "deployment_id": deployment.id
+            })));
         }
-        Ok(false) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Worker group not found"
-            }))).into_response()
+        Ok(Some(mut deployment)) => {
+            // Forced: cancel every active deployment so the executor stops
+            loop {
+                if let Err(e) = DeploymentRepository::update_status(pool, &deployment.id, "cancelled", Some("Worker group deleted")).await {
+                    warn!("Failed to cancel deployment {}: {}", deployment.id, e);
+                    break;
+                }
+                info!("Cancelled deployment {} for deleted group {}", deployment.id, group_name);
+                match DeploymentRepository::get_active_for_group(pool, &id).await {
+                    Ok(Some(next)) => deployment = next,
+                    _ => break,
+                }
+            }
         }
+        Ok(None) => {}
         Err(e) => {
-            error!("Failed to delete worker group: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to delete worker group"
-            }))).into_response()
+            return Err(ApiError::internal_logged("Failed to delete worker group", e));
         }
     }
+    
+    // Delete from database
+    let deleted = WorkerGroupRepository::delete(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to delete worker group", e))?;
+    if !deleted {
+        return Err(ApiError::not_found("Worker group not found"));
+    }
+    
+    // Delete from git store
+    if let Err(e) = state.git_store.delete_group(&group_name) {
+        warn!("Failed to delete git directory for group: {}", e);
+    }
+    
+    info!("Deleted worker group: {} ({})", group_name, id);
+    Ok((StatusCode::NO_CONTENT, ""))
 }
 
 /// List agents in a worker group
 pub async fn list_group_agents(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Verify group exists
     if let Ok(None) = WorkerGroupRepository::get_by_id(pool, &id).await {
-        return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-            "error": "Worker group not found"
-        }))).into_response();
+        return Err(ApiError::not_found("Worker group not found"));
     }
     
-    match AgentRepository::list_by_group(pool, &id).await {
-        Ok(agents) => {
-            let responses: Vec<AgentResponse> = agents.into_iter().map(AgentResponse::from).collect();
-            (StatusCode::OK, Json(responses)).into_response()
-        }
-        Err(e) => {
-            error!("Failed to list group agents: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list group agents"
-            }))).into_response()
-        }
-    }
+    let agents = AgentRepository::list_by_group(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to list group agents", e))?;
+    let responses: Vec<AgentResponse> = agents.into_iter().map(AgentResponse::from).collect();
+    Ok((StatusCode::OK, Json(responses)))
+}
+
+/// Uptime of a worker group, rolled up over its agents
+#[derive(Debug, Serialize)]
+pub struct GroupUptimeResponse {
+    pub group_id: String,
+    pub group_name: String,
+    pub healthy_checks: i64,
+    pub total_checks: i64,
+    /// Healthy checks across all agents as a percentage; null without checks
+    pub uptime_percentage: Option<f64>,
+    pub agents: Vec<AgentUptimeResponse>,
+}
+
+/// Get a worker group's uptime from its agents' recorded health checks
+///
+/// The rollup weights agents by how many checks they had in the window.
+pub async fn get_group_uptime(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<UptimeQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    let (since, since_db) = uptime_window(params.since.as_deref())?;
+    
+    let group = find_group(pool, &id, "Worker group").await?;
+    let counts = AgentRepository::group_uptime_counts(pool, &group.id, &since_db).await
+        .map_err(|e| ApiError::internal_logged("Failed to compute group uptime", e))?;
+    
+    let total = counts.iter().fold(UptimeCounts::default(), |sum, (_, _, c)| sum + *c);
+    Ok((StatusCode::OK, Json(UptimeReport {
+        since,
+        uptime: GroupUptimeResponse {
+            group_id: group.id,
+            group_name: group.name,
+            healthy_checks: total.healthy_checks,
+            total_checks: total.total_checks,
+            uptime_percentage: total.percentage(),
+            agents: counts.into_iter()
+                .map(|(agent_id, agent_name, counts)| AgentUptimeResponse::new(agent_id, agent_name, counts))
+                .collect(),
+        },
+    })))
 }
 
 /// Get current configuration for a worker group
 pub async fn get_group_config(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    // Get the group
-    let group = match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => group,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Worker group not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get worker group"
-            }))).into_response();
-        }
-    };
+    let group = find_group(pool, &id, "Worker group").await?;
     
     // Read config from git store
-    match state.git_store.read_config(&group.name) {
-        Ok(Some(config)) => {
-            (StatusCode::OK, Json(serde_json::json!({
-                "config": config,
-                "version": group.current_config_version,
-                "group_name": group.name
-            }))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::OK, Json(serde_json::json!({
-                "config": null,
-                "version": null,
-                "group_name": group.name,
-                "message": "No configuration set for this group"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to read group config: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to read group configuration"
-            }))).into_response()
+    let config = state.git_store.read_config(&group.name)
+        .map_err(|e| ApiError::internal_logged("Failed to read group configuration", e))?;
+    
+    // The version doubles as an ETag for conditional writes (If-Match)
+    let mut response_headers = HeaderMap::new();
+    if let (Some(_), Some(version)) = (&config, &group.current_config_version) {
+        if let Ok(etag) = format!("\"{}\"", version).parse() {
+            response_headers.insert(header::ETAG, etag);
         }
     }
+    
+    Ok((StatusCode::OK, response_headers, Json(match config {
+        Some(config) => serde_json::json!({
+            "config": config,
+            "version": group.current_config_version,
+            "group_name": group.name
+        }),
+        None => serde_json::json!({
+            "config": null,
+            "version": null,
+            "group_name": group.name,
+            "message": "No configuration set for this group"
+        }),
+    })))
 }
 
 /// Update configuration for a worker group
+///
+/// A config that fails validation is rejected with 400 and the validation
+/// errors in `details.errors`. With a `base_version` (or `If-Match`), a config
+/// changed by someone else since that version is rejected with 409, with the
+/// current version and a diff from the base in the details.
 pub async fn update_group_config(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(request): Json<UpdateConfigRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    // Get the group
-    let group = match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => group,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(UpdateConfigResponse {
-                success: false,
-                version: None,
-                message: "Worker group not found".to_string(),
-            })).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(UpdateConfigResponse {
-                success: false,
-                version: None,
-                message: format!("Failed to get worker group: {}", e),
-            })).into_response();
-        }
-    };
+    let group = find_group(pool, &id, "Worker group").await?;
     
     // Validate configuration using full validator
     let vector_bin = state.vector_process.get_binary_path();
@@ -497,11 +793,8 @@ pub async fn update_group_config(
             .map(|e| e.message.clone())
             .unwrap_or_else(|| "Configuration validation failed".to_string());
         
-        return (StatusCode::BAD_REQUEST, Json(UpdateConfigResponse {
-            success: false,
-            version: None,
-            message: format!("Validation failed: {}", error_msg),
-        })).into_response();
+        return Err(ApiError::bad_request(format!("Validation failed: {}", error_msg))
+            .with_details(serde_json::json!({ "errors": validation.errors })));
     }
     
     // Log warnings if any
@@ -509,31 +802,53 @@ pub async fn update_group_config(
         warn!("Config validation warning: {}", warning.message);
     }
     
+    let base_version = request.base_version.clone().or_else(|| {
+        headers.get(header::IF_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().trim_matches('"').to_string())
+            .filter(|v| !v.is_empty() && v != "*")
+    });
+    
     // Write config to git store (auto-commits)
-    match state.git_store.write_config(&group.name, &request.config) {
-        Ok(commit_hash) => {
-            // Update the group's current config version
-            if let Err(e) = WorkerGroupRepository::update_config_version(pool, &id, &commit_hash).await {
-                warn!("Failed to update group config version: {}", e);
-            }
-            
-            info!("Updated config for group {} (version: {})", group.name, &commit_hash[..8]);
-            
-            (StatusCode::OK, Json(UpdateConfigResponse {
-                success: true,
-                version: Some(commit_hash),
-                message: "Configuration updated successfully".to_string(),
-            })).into_response()
+    let written = match &base_version {
+        Some(base) => state.git_store.write_config_if(&group.name, &request.config, base),
+        None => state.git_store.write_config(&group.name, &request.config),
+    };
+    let commit_hash = match written {
+        Ok(hash) => hash,
+        Err(GitStoreError::StaleConfig { expected, current, .. }) => {
+            let diff = state.git_store.diff_config(&group.name, &expected, &current)
+                .unwrap_or_else(|e| {
+                    warn!("Failed to diff stale config for group {}: {}", group.name, e);
+                    String::new()
+                });
+            return Err(ApiError::conflict("Configuration was changed by someone else since it was loaded")
+                .with_details(serde_json::json!({
+                    "base_version": expected,
+                    "current_version": current,
+                    "diff": diff,
+                })));
         }
-        Err(e) => {
-            error!("Failed to write group config: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(UpdateConfigResponse {
-                success: false,
-                version: None,
-                message: format!("Failed to write configuration: {}", e),
-            })).into_response()
+        Err(GitStoreError::NotFound(message)) => return Err(ApiError::bad_request(message)),
+        Err(GitStoreError::Invalid(errors)) => {
+            return Err(ApiError::bad_request(format!("Validation failed: {}", errors[0].message))
+                .with_details(serde_json::json!({ "errors": errors })));
         }
+        Err(e) => return Err(ApiError::internal_logged("Failed to write configuration", e)),
+    };
+    
+    // Update the group's current config version
+    if let Err(e) = WorkerGroupRepository::update_config_version(pool, &id, &commit_hash).await {
+        warn!("Failed to update group config version: {}", e);
     }
+    
+    info!("Updated config for group {} (version: {})", group.name, &commit_hash[..8]);
+    
+    Ok((StatusCode::OK, Json(UpdateConfigResponse {
+        success: true,
+        version: Some(commit_hash),
+        message: "Configuration updated successfully".to_string(),
+    })))
 }
 
 /// Get configuration history for a worker group
@@ -541,38 +856,88 @@ pub async fn get_group_history(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Query(params): Query<HistoryQuery>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     let limit = params.limit.unwrap_or(50);
     
-    // Get the group
-    let group = match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => group,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Worker group not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get worker group"
-            }))).into_response();
-        }
-    };
+    let group = find_group(pool, &id, "Worker group").await?;
     
     // Get history from git store
-    match state.git_store.get_history(Some(&group.name), limit) {
-        Ok(history) => {
-            (StatusCode::OK, Json(history)).into_response()
-        }
-        Err(e) => {
-            error!("Failed to get group history: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get configuration history"
-            }))).into_response()
-        }
+    let history = state.git_store.get_history(Some(&group.name), limit)
+        .map_err(|e| ApiError::internal_logged("Failed to get configuration history", e))?;
+    Ok((StatusCode::OK, Json(history)))
+}
+
+/// Config changes across all groups, oldest first
+///
+/// For audit exports: each entry names the group, commit, author and time.
+/// With `Accept: text/csv` the same rows are returned as CSV.
+pub async fn get_config_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ConfigHistoryQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let since = params.since.as_deref().map(|v| parse_time_bound("since", v, false)).transpose()?;
+    let until = params.until.as_deref().map(|v| parse_time_bound("until", v, true)).transpose()?;
+    
+    let changes = state.git_store.config_changes(since, until)
+        .map_err(|e| ApiError::internal_logged("Failed to get configuration history", e))?;
+    
+    if !accepts(&headers, "text/csv") {
+        return Ok((StatusCode::OK, Json(changes)).into_response());
+    }
+    
+    let mut csv = String::from("timestamp,group,commit,author,message\r\n");
+    for change in &changes {
+        let fields = [
+            change.commit.timestamp.as_str(),
+            change.group.as_str(),
+            change.commit.hash.as_str(),
+            change.commit.author.as_str(),
+            change.commit.message.trim(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
     }
+    
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"config-history.csv\""),
+        ],
+        csv,
+    ).into_response())
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Search a worker group's configuration history
+pub async fn search_group_history(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<HistorySearchQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    let limit = params.limit.unwrap_or(50);
+    
+    if params.q.trim().is_empty() {
+        return Err(ApiError::bad_request("Search query must not be empty"));
+    }
+    
+    let group = find_group(pool, &id, "Worker group").await?;
+    
+    let matches = state.git_store.search_history(Some(&group.name), &params.q, limit, params.message_only)
+        .map_err(|e| ApiError::internal_logged("Failed to search configuration history", e))?;
+    Ok((StatusCode::OK, Json(matches)))
 }
 
 /// Rollback configuration for a worker group to a previous version
@@ -580,96 +945,140 @@ pub async fn rollback_group_config(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(request): Json<RollbackRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    // Get the group
-    let group = match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => group,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(UpdateConfigResponse {
-                success: false,
-                version: None,
-                message: "Worker group not found".to_string(),
-            })).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(UpdateConfigResponse {
-                success: false,
-                version: None,
-                message: format!("Failed to get worker group: {}", e),
-            })).into_response();
-        }
-    };
+    let group = find_group(pool, &id, "Worker group").await?;
     
     // Perform rollback in git store
-    match state.git_store.rollback(&group.name, &request.version) {
-        Ok(new_hash) => {
-            // Update the group's current config version
-            if let Err(e) = WorkerGroupRepository::update_config_version(pool, &id, &new_hash).await {
-                warn!("Failed to update group config version: {}", e);
-            }
-            
-            info!("Rolled back config for group {} to version {} (new: {})", 
-                group.name, &request.version[..8.min(request.version.len())], &new_hash[..8]);
-            
-            (StatusCode::OK, Json(UpdateConfigResponse {
-                success: true,
-                version: Some(new_hash),
-                message: format!("Configuration rolled back to version {}", &request.version[..8.min(request.version.len())]),
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to rollback group config: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(UpdateConfigResponse {
-                success: false,
-                version: None,
-                message: format!("Failed to rollback configuration: {}", e),
-            })).into_response()
-        }
+    let new_hash = state.git_store.rollback(&group.name, &request.version)
+        .map_err(|e| ApiError::internal_logged("Failed to rollback configuration", e))?;
+    
+    // Update the group's current config version
+    if let Err(e) = WorkerGroupRepository::update_config_version(pool, &id, &new_hash).await {
+        warn!("Failed to update group config version: {}", e);
+    }
+    
+    info!("Rolled back config for group {} to version {} (new: {})", 
+        group.name, &request.version[..8.min(request.version.len())], &new_hash[..8]);
+    
+    Ok((StatusCode::OK, Json(UpdateConfigResponse {
+        success: true,
+        version: Some(new_hash),
+        message: format!("Configuration rolled back to version {}", &request.version[..8.min(request.version.len())]),
+    })))
+}
+
+/// Preview a rollback without applying it
+///
+/// Returns the diff from the current config to `version`, i.e. exactly what
+/// `POST /groups/:id/rollback` with the same version would change.
+pub async fn preview_rollback_group_config(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(request): Query<RollbackRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let group = find_group(pool, &id, "Worker group").await?;
+    
+    let head = state.git_store.head_hash()
+        .map_err(|e| ApiError::internal_logged("Failed to get current version", e))?;
+    let diff = state.git_store.preview_rollback(&group.name, &request.version)
+        .map_err(|e| match e {
+            GitStoreError::NotFound(message) => ApiError::not_found(message),
+            e => ApiError::internal_logged("Failed to preview rollback", e),
+        })?;
+    
+    let has_changes = !diff.trim().is_empty();
+    Ok((StatusCode::OK, Json(DiffResponse {
+        from_version: head,
+        to_version: request.version,
+        diff,
+        has_changes,
+    })))
+}
+
+/// Copy another group's configuration into this group
+///
+/// Used to promote a validated config, e.g. from staging to prod.
+pub async fn copy_group_config(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<CopyConfigRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let target = find_group(pool, &id, "Worker group").await?;
+    let source = find_group(pool, &request.source_group_id, "Source group").await?;
+    
+    let new_hash = state.git_store.copy_config(&source.name, &target.name, request.version.as_deref())
+        .map_err(|e| match e {
+            GitStoreError::NotFound(message) => ApiError::not_found(message),
+            e => ApiError::internal_logged("Failed to copy configuration", e),
+        })?;
+    
+    if let Err(e) = WorkerGroupRepository::update_config_version(pool, &id, &new_hash).await {
+        warn!("Failed to update group config version: {}", e);
     }
+    
+    Ok((StatusCode::OK, Json(UpdateConfigResponse {
+        success: true,
+        version: Some(new_hash),
+        message: format!("Configuration copied from {}", source.name),
+    })))
 }
 
 /// Get configuration at a specific version
 pub async fn get_group_config_at_version(
     State(state): State<Arc<AppState>>,
     Path((id, version)): Path<(String, String)>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    // Get the group
-    let group = match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => group,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Worker group not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get worker group"
-            }))).into_response();
-        }
-    };
+    let group = find_group(pool, &id, "Worker group").await?;
     
     // Get config at version
-    match state.git_store.get_config_at_version(&group.name, &version) {
-        Ok(config) => {
-            (StatusCode::OK, Json(ConfigAtVersionResponse {
-                config,
-                version,
-                group_name: group.name,
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to get config at version: {}", e);
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": format!("Failed to get config at version: {}", e)
-            }))).into_response()
-        }
-    }
+    let config = state.git_store.get_config_at_version(&group.name, &version).map_err(|e| {
+        error!("Failed to get config at version: {}", e);
+        ApiError::not_found(format!("Failed to get config at version: {}", e))
+    })?;
+    
+    Ok((StatusCode::OK, Json(ConfigAtVersionResponse {
+        config,
+        version,
+        group_name: group.name,
+    })))
+}
+
+/// Validate a group's stored config, at HEAD or a given version
+///
+/// Runs the same validator as config updates. An invalid config is still a
+/// 200; the result's `valid` flag carries the outcome.
+pub async fn validate_group_config(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<ValidateStoredConfigQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let group = find_group(pool, &id, "Worker group").await?;
+    
+    let config = match &params.version {
+        Some(version) => state.git_store.get_config_at_version(&group.name, version)
+            .map_err(|e| ApiError::not_found(format!("Failed to get config at version: {}", e)))?,
+        None => state.git_store.read_config(&group.name)
+            .map_err(|e| ApiError::internal_logged("Failed to read group configuration", e))?,
+    };
+    let config = config.ok_or_else(|| match &params.version {
+        Some(version) => ApiError::not_found(format!("No configuration for this group at version {}", version)),
+        None => ApiError::not_found("No configuration set for this group"),
+    })?;
+    
+    let vector_bin = state.vector_process.get_binary_path();
+    let result = ConfigValidator::new(vector_bin).validate(&config);
+    
+    Ok(Json(result))
 }
 
 /// Get diff between two versions
@@ -677,72 +1086,139 @@ pub async fn get_group_diff(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Query(params): Query<DiffQuery>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    // Get the group (verify it exists)
-    let _group = match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => group,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Worker group not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get worker group"
-            }))).into_response();
-        }
-    };
+    // Verify the group exists
+    find_group(pool, &id, "Worker group").await?;
     
     // Handle "current" as HEAD
-    let from_hash = if params.from == "current" {
-        match state.git_store.head_hash() {
-            Ok(hash) => hash,
-            Err(e) => {
-                error!("Failed to get HEAD: {}", e);
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                    "error": "Failed to get current version"
-                }))).into_response();
-            }
+    let resolve = |version: &str| -> Result<String, ApiError> {
+        if version == "current" {
+            state.git_store.head_hash()
+                .map_err(|e| ApiError::internal_logged("Failed to get current version", e))
+        } else {
+            Ok(version.to_string())
         }
-    } else {
-        params.from.clone()
     };
+    let from_hash = resolve(&params.from)?;
+    let to_hash = resolve(&params.to)?;
     
-    let to_hash = if params.to == "current" {
-        match state.git_store.head_hash() {
-            Ok(hash) => hash,
-            Err(e) => {
-                error!("Failed to get HEAD: {}", e);
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                    "error": "Failed to get current version"
-                }))).into_response();
-            }
+    // Get diff
+    let diff = state.git_store.diff(&from_hash, &to_hash).map_err(|e| {
+        error!("Failed to get diff: {}", e);
+        ApiError::internal(format!("Failed to get diff: {}", e))
+    })?;
+    
+    let has_changes = !diff.trim().is_empty();
+    Ok((StatusCode::OK, Json(DiffResponse {
+        from_version: from_hash,
+        to_version: to_hash,
+        diff,
+        has_changes,
+    })))
+}
+
+/// Query parameters for a deploy preview
+#[derive(Debug, Deserialize)]
+pub struct DeployPreviewQuery {
+    /// Target config version (defaults to the group's current version)
+    pub version: Option<String>,
+}
+
+/// One agent in a deploy preview
+#[derive(Debug, Serialize)]
+pub struct DeployPreviewAgent {
+    pub agent_id: String,
+    pub agent_name: String,
+    /// Cached health status from the last health check
+    pub status: String,
+    /// Whether the last health check succeeded
+    pub reachable: bool,
+    /// Config version the agent last applied, if it was ever deployed to
+    pub current_version: Option<String>,
+    /// Whether deploying the target version would change this agent
+    pub would_change: bool,
+    /// Vector version from the last health check
+    pub vector_version: Option<String>,
+}
+
+/// Response for a deploy preview
+#[derive(Debug, Serialize)]
+pub struct DeployPreviewResponse {
+    pub group_id: String,
+    pub target_version: String,
+    pub agents: Vec<DeployPreviewAgent>,
+    pub total: usize,
+    pub would_change: usize,
+    pub unreachable: usize,
+    /// Oldest Vector version reported by the group's agents
+    pub oldest_vector_version: Option<String>,
+    /// Components the oldest agent's Vector doesn't support yet
+    pub compatibility_warnings: Vec<ValidationWarning>,
+}
+
+/// Preview a group-wide deploy, agent by agent
+///
+/// Built only from stored state (deployment history and cached health), so
+/// no agent is contacted. The target config is checked against the oldest
+/// Vector version the agents last reported; agents that never reported one
+/// are left out of that check.
+pub async fn preview_group_deploy(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<DeployPreviewQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let group = find_group(pool, &id, "Worker group").await?;
+    let target_version = params.version
+        .or(group.current_config_version)
+        .ok_or_else(|| ApiError::bad_request("Group has no config to deploy"))?;
+    
+    let config = match state.git_store.get_config_at_version(&group.name, &target_version) {
+        Ok(Some(config)) => config,
+        Ok(None) | Err(_) => {
+            return Err(ApiError::not_found(format!(
+                "Version {} has no config for group {}",
+                target_version, group.name
+            )));
         }
-    } else {
-        params.to.clone()
     };
     
-    // Get diff
-    match state.git_store.diff(&from_hash, &to_hash) {
-        Ok(diff) => {
-            let has_changes = !diff.trim().is_empty();
-            (StatusCode::OK, Json(DiffResponse {
-                from_version: from_hash,
-                to_version: to_hash,
-                diff,
-                has_changes,
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to get diff: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to get diff: {}", e)
-            }))).into_response()
+    let agents = AgentRepository::list_by_group(pool, &group.id).await
+        .map_err(|e| ApiError::internal_logged("Failed to list group agents", e))?;
+    let oldest_vector_version = VectorVersion::oldest(agents.iter().filter_map(|a| a.vector_version.as_deref()));
+    let compatibility_warnings = match oldest_vector_version {
+        Some(oldest) => ConfigValidator::new(None).check_version_compatibility(&config, &oldest.to_string()),
+        None => Vec::new(),
+    };
+    let mut applied = DeploymentRepository::last_applied_versions(pool, &group.id).await
+        .map_err(|e| ApiError::internal_logged("Failed to load deployment history", e))?;
+    
+    let agents: Vec<DeployPreviewAgent> = agents.into_iter().map(|agent| {
+        let current_version = applied.remove(&agent.id);
+        DeployPreviewAgent {
+            would_change: current_version.as_deref() != Some(target_version.as_str()),
+            reachable: agent.status == "healthy",
+            agent_id: agent.id,
+            agent_name: agent.name,
+            status: agent.status,
+            current_version,
+            vector_version: agent.vector_version,
         }
-    }
+    }).collect();
+    
+    Ok((StatusCode::OK, Json(DeployPreviewResponse {
+        group_id: group.id,
+        total: agents.len(),
+        would_change: agents.iter().filter(|a| a.would_change).count(),
+        unreachable: agents.iter().filter(|a| !a.reachable).count(),
+        target_version,
+        agents,
+        oldest_vector_version: oldest_vector_version.map(|v| v.to_string()),
+        compatibility_warnings,
+    })))
 }
 
 /// Deploy configuration to agents in a worker group
@@ -750,101 +1226,43 @@ pub async fn deploy_to_group(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(request): Json<DeployRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    // Get the group
-    let group = match WorkerGroupRepository::get_by_id(pool, &id).await {
-        Ok(Some(group)) => group,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(DeployResponse {
-                success: false,
-                message: "Worker group not found".to_string(),
-                deployed_to: vec![],
-                version: String::new(),
-            })).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get worker group: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(DeployResponse {
-                success: false,
-                message: format!("Failed to get worker group: {}", e),
-                deployed_to: vec![],
-                version: String::new(),
-            })).into_response();
-        }
-    };
+    let group = find_group(pool, &id, "Worker group").await?;
+    if group.frozen {
+        return Err(ApiError::conflict(frozen_message(&group.name))
+            .with_details(serde_json::json!({ "frozen": true })));
+    }
     
     // Get the config to deploy (config is verified to exist but agents pull it themselves)
-    let (_config, version) = if let Some(ref ver) = request.version {
+    let version = if let Some(ref ver) = request.version {
         // Deploy specific version
-        match state.git_store.get_config_at_version(&group.name, ver) {
-            Ok(Some(cfg)) => (cfg, ver.clone()),
-            Ok(None) => {
-                return (StatusCode::NOT_FOUND, Json(DeployResponse {
-                    success: false,
-                    message: format!("Config not found at version {}", ver),
-                    deployed_to: vec![],
-                    version: String::new(),
-                })).into_response();
-            }
-            Err(e) => {
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(DeployResponse {
-                    success: false,
-                    message: format!("Failed to get config: {}", e),
-                    deployed_to: vec![],
-                    version: String::new(),
-                })).into_response();
-            }
-        }
+        state.git_store.get_config_at_version(&group.name, ver)
+            .map_err(|e| ApiError::internal(format!("Failed to get config: {}", e)))?
+            .ok_or_else(|| ApiError::not_found(format!("Config not found at version {}", ver)))?;
+        ver.clone()
     } else {
         // Deploy current config
-        match state.git_store.read_config(&group.name) {
-            Ok(Some(cfg)) => {
-                let ver = group.current_config_version.clone().unwrap_or_else(|| {
-                    state.git_store.head_hash().unwrap_or_default()
-                });
-                (cfg, ver)
-            }
-            Ok(None) => {
-                return (StatusCode::NOT_FOUND, Json(DeployResponse {
-                    success: false,
-                    message: "No configuration set for this group".to_string(),
-                    deployed_to: vec![],
-                    version: String::new(),
-                })).into_response();
-            }
-            Err(e) => {
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(DeployResponse {
-                    success: false,
-                    message: format!("Failed to read config: {}", e),
-                    deployed_to: vec![],
-                    version: String::new(),
-                })).into_response();
-            }
-        }
+        state.git_store.read_config(&group.name)
+            .map_err(|e| ApiError::internal(format!("Failed to read config: {}", e)))?
+            .ok_or_else(|| ApiError::not_found("No configuration set for this group"))?;
+        group.current_config_version.clone().unwrap_or_else(|| {
+            state.git_store.head_hash().unwrap_or_default()
+        })
     };
     
     // Get agents in the group
-    let agents = match AgentRepository::list_by_group(pool, &id).await {
-        Ok(agents) => agents,
-        Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(DeployResponse {
-                success: false,
-                message: format!("Failed to list agents: {}", e),
-                deployed_to: vec![],
-                version: version.clone(),
-            })).into_response();
-        }
-    };
+    let agents = AgentRepository::list_by_group(pool, &id).await
+        .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?;
     
     if agents.is_empty() {
-        return (StatusCode::OK, Json(DeployResponse {
+        return Ok((StatusCode::OK, Json(DeployResponse {
             success: true,
             message: "No agents in group to deploy to".to_string(),
             deployed_to: vec![],
             version,
-        })).into_response();
+        })));
     }
     
     // Filter agents if specific IDs provided
@@ -879,10 +1297,10 @@ pub async fn deploy_to_group(
     
     info!("Deployment to group {} complete: {}/{} agents", group.name, success_count, results.len());
     
-    (StatusCode::OK, Json(DeployResponse {
+    Ok((StatusCode::OK, Json(DeployResponse {
         success: all_success,
         message: format!("Deployment initiated for {} agents", success_count),
         deployed_to: results,
         version,
-    })).into_response()
+    })))
 }
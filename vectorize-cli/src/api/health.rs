@@ -3,19 +3,26 @@
 //! Provides endpoints for monitoring agent health status, metrics, and topology.
 
 use axum::{
-    extract::{Path, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
     http::StatusCode,
     response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{info, warn, error};
+use std::time::Duration;
+use tracing::{debug, info, warn};
 
 use crate::AppState;
+use crate::api::ApiError;
 use crate::db::models::AgentStatus;
 use crate::db::repository::AgentRepository;
-use crate::health::{fetch_agent_metrics, AgentMetrics};
+use crate::health::{fetch_agent_metrics, is_stale, AgentMetrics};
+use crate::health::top::{top_interval, TopSnapshot};
+use crate::request_id::{self, WithRequestId};
 
 /// Health check result for a single agent
 #[derive(Debug, Serialize)]
@@ -39,6 +46,13 @@ pub struct HealthSummary {
     pub agents: Vec<AgentHealthResult>,
 }
 
+/// Request to run health checks now
+#[derive(Debug, Deserialize)]
+pub struct TriggerHealthCheckRequest {
+    /// Agents to check (all agents if omitted)
+    pub agent_ids: Option<Vec<String>>,
+}
+
 /// Fleet health summary (for dashboard UI)
 #[derive(Debug, Serialize)]
 pub struct FleetHealth {
@@ -46,6 +60,8 @@ pub struct FleetHealth {
     pub healthy: u32,
     pub unhealthy: u32,
     pub unknown: u32,
+    /// Agents without a successful poll or heartbeat within the threshold
+    pub stale: u32,
     pub version_distribution: Vec<VersionCount>,
 }
 
@@ -59,23 +75,19 @@ pub struct VersionCount {
 /// Get fleet health summary for dashboard
 pub async fn get_fleet_health(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Get all agents
-    let agents = match AgentRepository::list(pool).await {
-        Ok(agents) => agents,
-        Err(e) => {
-            error!("Failed to list agents for fleet health: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list agents"
-            }))).into_response();
-        }
-    };
+    let agents = AgentRepository::list(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?;
     
     let mut healthy_count: u32 = 0;
     let mut unhealthy_count: u32 = 0;
     let mut unknown_count: u32 = 0;
+    let mut stale_count: u32 = 0;
+    let now = chrono::Utc::now();
+    let stale_after = state.health_monitor.stale_after();
     let mut version_counts: std::collections::HashMap<String, u32> = std::collections::HashMap::new();
     
     for agent in &agents {
@@ -84,6 +96,9 @@ pub async fn get_fleet_health(
             "unhealthy" => unhealthy_count += 1,
             _ => unknown_count += 1,
         }
+        if is_stale(agent.last_seen.as_deref(), &agent.registered_at, now, stale_after) {
+            stale_count += 1;
+        }
         
         if let Some(ref version) = agent.vector_version {
             *version_counts.entry(version.clone()).or_insert(0) += 1;
@@ -100,28 +115,22 @@ pub async fn get_fleet_health(
         healthy: healthy_count,
         unhealthy: unhealthy_count,
         unknown: unknown_count,
+        stale: stale_count,
         version_distribution,
     };
     
-    (StatusCode::OK, Json(fleet_health)).into_response()
+    Ok((StatusCode::OK, Json(fleet_health)))
 }
 
 /// Check health of all registered agents
 pub async fn check_all_agents(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Get all agents
-    let agents = match AgentRepository::list(pool).await {
-        Ok(agents) => agents,
-        Err(e) => {
-            error!("Failed to list agents for health check: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list agents"
-            }))).into_response();
-        }
-    };
+    let agents = AgentRepository::list(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?;
     
     let mut results = Vec::new();
     let mut healthy_count = 0;
@@ -130,7 +139,7 @@ pub async fn check_all_agents(
     
     // Check each agent
     for agent in agents {
-        let result = check_agent_health(&state.http_client, &agent.id, &agent.name, &agent.url).await;
+        let result = check_agent_health(&state.agent_clients.for_agent(&agent), &agent.id, &agent.name, &agent.url).await;
         
         // Update agent status in database
         let status_str = result.status.to_string();
@@ -176,7 +185,47 @@ pub async fn check_all_agents(
         summary.healthy, summary.total, summary.unhealthy, summary.unreachable
     );
     
-    (StatusCode::OK, Json(summary)).into_response()
+    Ok((StatusCode::OK, Json(summary)))
+}
+
+/// Run health checks now instead of waiting for the monitor's next interval
+///
+/// Checks the given agents (all if the body or `agent_ids` is omitted) and
+/// returns the fresh results once every check has finished. Unknown agent
+/// ids are a 400 with their ids in `details.unknown_agents`.
+pub async fn trigger_health_check(
+    State(state): State<Arc<AppState>>,
+    request: Option<Json<TriggerHealthCheckRequest>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let results = match request.and_then(|Json(r)| r.agent_ids) {
+        Some(agent_ids) => {
+            let mut unknown = Vec::new();
+            for id in &agent_ids {
+                let agent = AgentRepository::get_by_id(pool, id).await
+                    .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?;
+                if agent.is_none() {
+                    unknown.push(id.clone());
+                }
+            }
+            if !unknown.is_empty() {
+                return Err(ApiError::bad_request(format!("Unknown agents: {}", unknown.join(", ")))
+                    .with_details(serde_json::json!({ "unknown_agents": unknown })));
+            }
+            
+            state.health_monitor.check_agents(&agent_ids).await
+        }
+        None => state.health_monitor.check_all_agents().await,
+    };
+    
+    info!(
+        "Manual health check complete: {}/{} healthy",
+        results.iter().filter(|r| r.healthy).count(),
+        results.len()
+    );
+    
+    Ok((StatusCode::OK, Json(results)))
 }
 
 /// Check health of a single agent
@@ -191,6 +240,7 @@ async fn check_agent_health(
     let start = std::time::Instant::now();
     
     let health_response = client.get(&health_url)
+        .with_request_id()
         .timeout(std::time::Duration::from_secs(5))
         .send()
         .await;
@@ -248,6 +298,7 @@ async fn get_vector_version(client: &reqwest::Client, base_url: &str) -> Option<
     });
     
     let response = client.post(&graphql_url)
+        .with_request_id()
         .json(&query)
         .timeout(std::time::Duration::from_secs(5))
         .send()
@@ -280,18 +331,11 @@ pub struct AggregatedMetrics {
 /// Get metrics for all agents
 pub async fn get_all_metrics(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    let agents = match AgentRepository::list(pool).await {
-        Ok(agents) => agents,
-        Err(e) => {
-            error!("Failed to list agents for metrics: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list agents"
-            }))).into_response();
-        }
-    };
+    let agents = AgentRepository::list(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?;
     
     let mut metrics = Vec::new();
     let mut total_events: u64 = 0;
@@ -301,13 +345,13 @@ pub async fn get_all_metrics(
     let mut handles = Vec::new();
     
     for agent in &agents {
-        let client = state.http_client.clone();
+        let client = state.agent_clients.for_agent(agent);
         let agent_id = agent.id.clone();
         let agent_url = agent.url.clone();
         
-        handles.push(tokio::spawn(async move {
+        handles.push(tokio::spawn(request_id::propagate(async move {
             fetch_agent_metrics(&client, &agent_id, &agent_url).await
-        }));
+        })));
     }
     
     for handle in handles {
@@ -326,34 +370,93 @@ pub async fn get_all_metrics(
         collected_at: chrono::Utc::now().to_rfc3339(),
     };
     
-    (StatusCode::OK, Json(response)).into_response()
+    Ok((StatusCode::OK, Json(response)))
 }
 
 /// Get metrics for a specific agent
 pub async fn get_agent_metrics(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    let agent = match AgentRepository::get_by_id(pool, &agent_id).await {
-        Ok(Some(agent)) => agent,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Agent not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get agent: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get agent"
-            }))).into_response();
-        }
-    };
+    let agent = AgentRepository::get_by_id(pool, &agent_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
     
-    let metrics = fetch_agent_metrics(&state.http_client, &agent.id, &agent.url).await;
+    let metrics = fetch_agent_metrics(&state.agent_clients.for_agent(&agent), &agent.id, &agent.url).await;
     
-    (StatusCode::OK, Json(metrics)).into_response()
+    Ok((StatusCode::OK, Json(metrics)))
+}
+
+/// Query parameters for the live throughput stream
+#[derive(Debug, Deserialize)]
+pub struct TopQuery {
+    /// Seconds between snapshots (1-60, default 2)
+    pub interval_secs: Option<u64>,
+}
+
+/// Stream per-component throughput for an agent over a WebSocket
+///
+/// Like `vector top`: each interval the agent's metrics are fetched and a
+/// `TopSnapshot` with rates since the previous fetch is sent as JSON text.
+/// The first snapshot carries totals only.
+pub async fn agent_top(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<String>,
+    Query(query): Query<TopQuery>,
+    ws: WebSocketUpgrade,
+) -> Result<impl IntoResponse, ApiError> {
+    let agent = AgentRepository::get_by_id(state.db.pool(), &agent_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
+    
+    let client = state.agent_clients.for_agent(&agent);
+    let interval = top_interval(query.interval_secs);
+    Ok(ws.on_upgrade(move |socket| stream_top(socket, client, agent.id, agent.url, interval)))
+}
+
+async fn stream_top(
+    mut socket: WebSocket,
+    client: reqwest::Client,
+    agent_id: String,
+    agent_url: String,
+    interval: Duration,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut previous: Option<(AgentMetrics, tokio::time::Instant)> = None;
+    
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let metrics = fetch_agent_metrics(&client, &agent_id, &agent_url).await;
+                let now = tokio::time::Instant::now();
+                let snapshot = match &previous {
+                    Some((before, at)) => TopSnapshot::from_metrics(Some(before), &metrics, now - *at),
+                    None => TopSnapshot::from_metrics(None, &metrics, Duration::ZERO),
+                };
+                previous = Some((metrics, now));
+                
+                let text = match serde_json::to_string(&snapshot) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        warn!("Failed to serialize throughput snapshot: {}", e);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    
+    debug!("Throughput stream for agent {} closed", agent_id);
 }
 
 // =============================================================================
@@ -390,18 +493,11 @@ pub struct AggregatedTopology {
 /// Get aggregated topology from all agents
 pub async fn get_aggregated_topology(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
-    let agents = match AgentRepository::list(pool).await {
-        Ok(agents) => agents,
-        Err(e) => {
-            error!("Failed to list agents for topology: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list agents"
-            }))).into_response();
-        }
-    };
+    let agents = AgentRepository::list(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list agents", e))?;
     
     let mut topologies = Vec::new();
     let mut total_components = 0;
@@ -411,7 +507,7 @@ pub async fn get_aggregated_topology(
     
     // Fetch topology from each agent
     for agent in &agents {
-        if let Some(topology) = fetch_agent_topology(&state.http_client, &agent.id, &agent.name, &agent.url).await {
+        if let Some(topology) = fetch_agent_topology(&state.agent_clients.for_agent(agent), &agent.id, &agent.name, &agent.url).await {
             for comp in &topology.components {
                 total_components += 1;
                 match comp.component_type.as_str() {
@@ -434,7 +530,7 @@ pub async fn get_aggregated_topology(
         agents: topologies,
     };
     
-    (StatusCode::OK, Json(response)).into_response()
+    Ok((StatusCode::OK, Json(response)))
 }
 
 /// Fetch topology from an agent
@@ -463,6 +559,7 @@ async fn fetch_agent_topology(
     let body = serde_json::json!({ "query": query });
     
     let response = client.post(&graphql_url)
+        .with_request_id()
         .header("Content-Type", "application/json")
         .json(&body)
         .timeout(std::time::Duration::from_secs(5))
@@ -514,25 +611,16 @@ pub struct HealthHistoryEntry {
 pub async fn get_agent_health_history(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Verify agent exists
     if let Ok(None) = AgentRepository::get_by_id(pool, &agent_id).await {
-        return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-            "error": "Agent not found"
-        }))).into_response();
+        return Err(ApiError::not_found("Agent not found"));
     }
     
-    let history = match AgentRepository::get_health_checks(pool, &agent_id, 100).await {
-        Ok(checks) => checks,
-        Err(e) => {
-            error!("Failed to get health history: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get health history"
-            }))).into_response();
-        }
-    };
+    let history = AgentRepository::get_health_checks(pool, &agent_id, 100).await
+        .map_err(|e| ApiError::internal_logged("Failed to get health history", e))?;
     
     let entries: Vec<HealthHistoryEntry> = history.into_iter().map(|h| {
         HealthHistoryEntry {
@@ -543,8 +631,8 @@ pub async fn get_agent_health_history(
         }
     }).collect();
     
-    (StatusCode::OK, Json(serde_json::json!({
+    Ok((StatusCode::OK, Json(serde_json::json!({
         "agent_id": agent_id,
         "history": entries
-    }))).into_response()
+    }))))
 }
@@ -0,0 +1,17 @@
+//! Local Vector instances
+//!
+//! Lists the Vector processes this Vectorize manages, so clients can pick
+//! which instance (and API port) to target.
+
+use axum::{extract::State, Json};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::vector_manager::{self, InstanceInfo};
+
+/// List running local Vector instances, by name
+pub async fn list_instances(
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<InstanceInfo>> {
+    Json(vector_manager::list_instances(&state.vector_instances).await)
+}
@@ -0,0 +1,66 @@
+//! Live update WebSocket endpoint
+//!
+//! Pushes agent status changes and deployment progress from the control
+//! plane's live-update bus to connected dashboards as JSON text messages.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::IntoResponse,
+};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, warn};
+
+use crate::AppState;
+use crate::live::LiveEvent;
+
+/// Upgrade to a WebSocket streaming live events
+///
+/// Each message is a `LiveEvent` serialized as JSON. Messages from the client
+/// are ignored apart from close frames. A client that falls too far behind
+/// skips the events it missed and should refetch the agent list.
+pub async fn live_updates(
+    State(state): State<Arc<AppState>>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    // Subscribe before the upgrade completes so no event is missed in between
+    let rx = state.live_events.subscribe();
+    ws.on_upgrade(move |socket| forward_events(socket, rx))
+}
+
+async fn forward_events(
+    mut socket: WebSocket,
+    mut rx: tokio::sync::broadcast::Receiver<LiveEvent>,
+) {
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) => {
+                    let text = match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            warn!("Failed to serialize live event: {}", e);
+                            continue;
+                        }
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Live update subscriber lagged, skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    debug!("Live update connection closed");
+}
@@ -0,0 +1,179 @@
+//! Maintenance mode
+//!
+//! While enabled the control plane is read-only: mutating API calls are
+//! refused with 503 so an upgrade can't leave half-applied changes behind.
+//! Reads, logins and the toggle itself still work; the health monitor and
+//! deployments already running are not affected. Scheduled deployments that
+//! come due stay `scheduled` until maintenance ends.
+
+use axum::{
+    extract::{Extension, Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::AppState;
+use crate::api::ApiError;
+use crate::api::agents::is_heartbeat_path;
+use crate::rbac::{require_authenticated, require_permission, AuthenticatedUser};
+
+/// Environment variable that starts the server in maintenance mode
+pub const MAINTENANCE_MODE_ENV: &str = "VECTORIZE_MAINTENANCE_MODE";
+
+/// Endpoint reporting and toggling maintenance mode
+pub const MAINTENANCE_PATH: &str = "/api/v1/system/maintenance";
+
+/// Paths still accepting writes during maintenance, so admins can sign in
+/// and turn it off again
+const EXEMPT_PATHS: &[&str] = &[MAINTENANCE_PATH, "/api/v1/auth/login", "/api/v1/auth/logout"];
+
+/// Current maintenance state
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    /// Shown to clients whose writes are refused
+    pub message: Option<String>,
+    /// When maintenance was enabled (UTC)
+    pub since: Option<String>,
+    pub enabled_by: Option<String>,
+}
+
+/// Shared maintenance mode flag
+#[derive(Debug, Default)]
+pub struct MaintenanceMode {
+    status: Mutex<MaintenanceStatus>,
+}
+
+impl MaintenanceMode {
+    /// Start enabled or disabled
+    pub fn new(enabled: bool) -> Self {
+        let mode = Self::default();
+        if enabled {
+            mode.set(true, None, None);
+        }
+        mode
+    }
+    
+    /// Read `VECTORIZE_MAINTENANCE_MODE` (`true`/`1`/`yes`/`on`)
+    pub fn from_env() -> Self {
+        let enabled = std::env::var(MAINTENANCE_MODE_ENV)
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+            .unwrap_or(false);
+        if enabled {
+            warn!("Starting in maintenance mode ({} is set)", MAINTENANCE_MODE_ENV);
+        }
+        Self::new(enabled)
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        self.lock().enabled
+    }
+    
+    pub fn status(&self) -> MaintenanceStatus {
+        self.lock().clone()
+    }
+    
+    /// Turn maintenance on or off
+    pub fn set(&self, enabled: bool, message: Option<String>, by: Option<String>) -> MaintenanceStatus {
+        let mut status = self.lock();
+        *status = if enabled {
+            MaintenanceStatus {
+                enabled: true,
+                message,
+                since: Some(chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+                enabled_by: by,
+            }
+        } else {
+            MaintenanceStatus::default()
+        };
+        status.clone()
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, MaintenanceStatus> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Refuse mutating requests with 503 while maintenance mode is on
+pub async fn reject_writes(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let read_only = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    // Heartbeats keep flowing so agents don't all go stale meanwhile
+    if read_only || EXEMPT_PATHS.contains(&req.uri().path()) || is_heartbeat_path(req.uri().path()) {
+        return next.run(req).await;
+    }
+    
+    let status = state.maintenance.status();
+    if !status.enabled {
+        return next.run(req).await;
+    }
+    
+    ApiError::unavailable("Maintenance in progress; changes are disabled until it ends")
+        .with_details(serde_json::json!({
+            "maintenance": true,
+            "message": status.message,
+            "since": status.since,
+        }))
+        .into_response()
+}
+
+/// Request to toggle maintenance mode
+#[derive(Debug, Deserialize)]
+pub struct SetMaintenanceRequest {
+    pub enabled: bool,
+    /// Optional note for clients, e.g. the expected end time
+    pub message: Option<String>,
+}
+
+/// Get the maintenance state
+pub async fn get_maintenance(
+    State(state): State<Arc<AppState>>,
+) -> Json<MaintenanceStatus> {
+    Json(state.maintenance.status())
+}
+
+/// Turn maintenance mode on or off (requires `system_admin`)
+pub async fn set_maintenance(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<AuthenticatedUser>>,
+    Json(request): Json<SetMaintenanceRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = require_authenticated(user)?;
+    require_permission(&user, "system_admin")?;
+    
+    let status = state.maintenance.set(request.enabled, request.message, Some(user.user_id.clone()));
+    if status.enabled {
+        warn!("Maintenance mode enabled by {}", user.user_id);
+    } else {
+        info!("Maintenance mode disabled by {}", user.user_id);
+    }
+    Ok(Json(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_set_and_clear() {
+        let mode = MaintenanceMode::default();
+        assert!(!mode.is_enabled());
+    
+        let status = mode.set(true, Some("Upgrading to 2.0".to_string()), Some("admin".to_string()));
+        assert!(mode.is_enabled());
+        assert_eq!(status.message.as_deref(), Some("Upgrading to 2.0"));
+        assert!(status.since.is_some());
+    
+        let status = mode.set(false, Some("ignored".to_string()), None);
+        assert!(!mode.is_enabled());
+        assert!(status.message.is_none() && status.since.is_none());
+    }
+}
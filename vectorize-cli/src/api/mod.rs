@@ -6,26 +6,47 @@
 //! - Configuration (deployment, versioning, validation)
 //! - Deployments (strategies, approval workflows)
 //! - Authentication (setup, login, API keys, SSO)
+//! - Per-route authorization
 //! - Alerts (rules, notification channels)
 //! - Users and Roles (RBAC)
 //! - Audit logging
+//! - Component catalog (types and fields for the pipeline builder)
+//! - Config templates
 //! - Live data sampling (tap)
 //! - Git remote sync
+//! - Live updates over WebSocket
+//! - Protocol version handshake
+//! - Maintenance mode
+//! - Local Vector instances
+//! - Global search
 
 pub mod agents;
 pub mod alerts;
+pub mod api_keys;
 pub mod audit;
 pub mod auth;
+pub mod authz;
+pub mod body_limit;
+pub mod components;
+pub mod error;
 pub mod deployments;
 pub mod git;
 pub mod groups;
 pub mod health;
+pub mod instances;
+pub mod live;
+pub mod maintenance;
+pub mod protocol;
 pub mod roles;
+pub mod search;
 pub mod tap;
+pub mod templates;
 pub mod users;
 pub mod validation;
 
 use axum::{
+    http::{header, HeaderMap},
+    middleware,
     Router,
     routing::{get, post, delete},
 };
@@ -33,39 +54,68 @@ use std::sync::Arc;
 
 use crate::AppState;
 
+pub use error::ApiError;
+
 /// Create the API router with all control plane endpoints
 /// Note: User/Role/Audit endpoints require JWT authentication.
-/// The auth middleware must be applied at the server level.
+/// The auth middleware must be applied at the server level; route
+/// permissions are enforced by `authz::authorize`.
 pub fn create_api_router() -> Router<Arc<AppState>> {
     Router::new()
         // Agent endpoints
         .route("/agents", get(agents::list_agents).post(agents::register_agent))
         .route("/agents/unassigned", get(agents::list_unassigned_agents))
-        .route("/agents/:id", get(agents::get_agent).put(agents::update_agent).delete(agents::delete_agent))
+        .route("/agents/health-check", post(health::trigger_health_check))
+        .route("/agents/:id", get(agents::get_agent).put(agents::update_agent).patch(agents::move_agent).delete(agents::delete_agent))
         .route("/agents/:id/health", get(agents::get_agent_health))
+        .route("/agents/:id/uptime", get(agents::get_agent_uptime))
         .route("/agents/:id/assign", post(agents::assign_agent_to_group))
+        .route("/agents/:id/deploy", post(agents::deploy_to_agent))
+        .route("/agents/:id/config/ack", post(agents::ack_config))
+        .route("/agents/:id/heartbeat", post(agents::heartbeat))
+        .route("/agents/:id/config-diff", get(agents::get_agent_config_diff))
         
         // Worker group endpoints
         .route("/groups", get(groups::list_groups).post(groups::create_group))
         .route("/groups/:id", get(groups::get_group).put(groups::update_group).delete(groups::delete_group))
         .route("/groups/:id/agents", get(groups::list_group_agents))
-        .route("/groups/:id/config", get(groups::get_group_config).put(groups::update_group_config))
+        .route("/groups/:id/uptime", get(groups::get_group_uptime))
+        .route("/groups/:id/clone", post(groups::clone_group))
+        .route("/groups/:id/freeze", post(groups::freeze_group))
+        .route("/groups/:id/unfreeze", post(groups::unfreeze_group))
+        .route("/groups/:id/config/copy-from", post(groups::copy_group_config))
+        .route("/groups/:id/config/validate", post(groups::validate_group_config))
         .route("/groups/:id/config/:version", get(groups::get_group_config_at_version))
         .route("/groups/:id/history", get(groups::get_group_history))
+        .route("/groups/:id/history/search", get(groups::search_group_history))
+        .route("/config/history", get(groups::get_config_history))
         .route("/groups/:id/diff", get(groups::get_group_diff))
         .route("/groups/:id/rollback", post(groups::rollback_group_config))
+        .route("/groups/:id/rollback/preview", get(groups::preview_rollback_group_config))
         .route("/groups/:id/deploy", post(groups::deploy_to_group))
+        .route("/groups/:id/deploy-preview", get(groups::preview_group_deploy))
+        .route("/groups/:id/from-template", post(templates::create_config_from_template))
+        
+        // Config templates
+        .route("/templates", get(templates::list_templates))
         
         // Deployment endpoints
         .route("/groups/:id/deployments", get(deployments::list_deployments).post(deployments::create_deployment))
         .route("/groups/:id/versions", get(deployments::check_versions))
+        .route("/deployments/bulk", post(deployments::bulk_deploy))
         .route("/deployments/:id", get(deployments::get_deployment))
         .route("/deployments/:id/approve", post(deployments::approve_deployment))
         .route("/deployments/:id/reject", post(deployments::reject_deployment))
         .route("/deployments/:id/cancel", post(deployments::cancel_deployment))
+        .route("/deployments/:id/events", get(deployments::deployment_events))
+        .route("/deployments/:id/report", get(deployments::get_deployment_report))
+        
+        // Live agent status and deployment updates
+        .route("/ws", get(live::live_updates))
         
         // Tap/Sample endpoints
         .route("/tap/config", get(tap::get_tap_config))
+        .route("/tap/redaction", get(tap::get_redaction_rules))
         .route("/tap/:agent_id/sample", get(tap::sample_agent))
         .route("/tap/:agent_id/rate-limit", get(tap::check_rate_limit))
         .route("/tap/:agent_id/ws-info", get(tap::get_websocket_info))
@@ -77,7 +127,13 @@ pub fn create_api_router() -> Router<Arc<AppState>> {
         .route("/git/remotes/:name/pull", post(git::pull_from_remote))
         .route("/git/remotes/:name/sync", post(git::sync_with_remote))
         .route("/git/remotes/:name/status", get(git::get_sync_status))
+        .route("/git/remotes/:name/incoming", get(git::preview_incoming))
+        .route("/git/commits/:hash/verify", get(git::verify_commit))
+        .route("/git/conflicts", get(git::list_conflicts))
+        .route("/git/conflicts/resolve", post(git::resolve_conflict))
+        .route("/git/merge/abort", post(git::abort_merge))
         .route("/git/branches", get(git::list_branches).post(git::create_branch))
+        .route("/git/branches/compare", get(git::compare_branches))
         .route("/git/branches/:name/checkout", post(git::checkout_branch))
         
         // Health monitoring
@@ -88,6 +144,7 @@ pub fn create_api_router() -> Router<Arc<AppState>> {
         // Metrics
         .route("/metrics", get(health::get_all_metrics))
         .route("/metrics/:id", get(health::get_agent_metrics))
+        .route("/metrics/:id/top", get(health::agent_top))
         
         // Topology
         .route("/topology", get(health::get_aggregated_topology))
@@ -99,20 +156,84 @@ pub fn create_api_router() -> Router<Arc<AppState>> {
         .route("/alerts/channels/:id", delete(alerts::delete_channel))
         .route("/alerts/channels/:id/test", post(alerts::test_channel))
         
-        // Validation (Layers 1-3)
-        .route("/validate", post(validation::validate_config))
-        .route("/validate/quick", post(validation::validate_quick))
+        // API keys
+        .route("/api-keys", get(api_keys::list_api_keys).post(api_keys::create_api_key))
+        .route("/api-keys/:id", delete(api_keys::revoke_api_key))
         
-        // Functional Testing (Layer 4)
-        .route("/test", get(validation::list_test_results).post(validation::start_functional_test))
+        // Maintenance mode (toggling requires system_admin)
+        .route("/system/maintenance", get(maintenance::get_maintenance).put(maintenance::set_maintenance))
+        
+        // Local Vector instances
+        .route("/system/instances", get(instances::list_instances))
+        
+        // Global search
+        .route("/search", get(search::search))
+        
+        // Audit log (requires a token with audit_read)
+        .route("/audit", get(audit::list_audit_logs))
+        .route("/audit/actions", get(audit::list_audit_actions))
+        
+        // Component catalog
+        .route("/components/schema", get(components::get_component_catalog))
+        .route("/components/schema/:kind/:type", get(components::get_component_schema))
+        
+        // Functional Testing (Layer 4) results
         .route("/test/:id", get(validation::get_test_result))
         
         // Setup wizard (always public)
         .route("/setup/status", get(auth::setup_status))
         .route("/setup/init", post(auth::setup_init))
         
+        // Protocol version handshake (always public)
+        .route("/handshake", post(protocol::handshake))
+        
         // Authentication (always public)
         .route("/auth/login", post(auth::login))
         .route("/auth/logout", post(auth::logout))
         .route("/auth/me", get(auth::current_user))
+        
+        // Endpoints taking whole configs, with a capped body size
+        .merge(body_limit::limit_request_bodies(
+            config_body_routes(),
+            body_limit::max_config_body_bytes_from_env(),
+        ))
+        
+        // Authenticated callers need the permission declared in `authz::ROUTES`
+        .route_layer(middleware::from_fn(authz::authorize))
+        
+        // Clients declaring an incompatible protocol version get a 426
+        .layer(middleware::from_fn(protocol::enforce_protocol_version))
+}
+
+/// Routes that accept a full config in the request body
+fn config_body_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/groups/:id/config", get(groups::get_group_config).put(groups::update_group_config))
+        
+        // Validation (Layers 1-3)
+        .route("/validate", post(validation::validate_config))
+        .route("/validate/quick", post(validation::validate_quick))
+        .route("/validate/lint", post(validation::lint_config))
+        .route("/config/explain", post(validation::explain_config))
+        .route("/config/analyze", post(validation::analyze_config))
+        .route("/config/convert", post(validation::convert_config))
+        
+        // Functional Testing (Layer 4)
+        .route("/test", get(validation::list_test_results).post(validation::start_functional_test))
+}
+
+/// Whether the request's `Accept` header lists `media_type`
+///
+/// Used by endpoints offering another representation (NDJSON, CSV) besides
+/// their default JSON.
+pub(crate) fn accepts(headers: &HeaderMap, media_type: &str) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media| {
+            let media = media.split(';').next().unwrap_or("").trim();
+            media.eq_ignore_ascii_case(media_type)
+        })
 }
@@ -0,0 +1,168 @@
+//! Protocol version negotiation
+//!
+//! The UI and the control plane exchange `vectorize_shared::messages` types.
+//! A UI built against a different schema would silently mis-parse them, so
+//! clients either call the handshake endpoint on startup or send the
+//! `X-Vectorize-Protocol` header on each request; an incompatible version gets
+//! a 426 `protocol_mismatch` error saying which side needs updating.
+//!
+//! Requests without the header (curl, the CLI) are not checked.
+
+use axum::{
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::warn;
+use vectorize_shared::{
+    Handshake, HandshakeResponse, ProtocolCompatibility, MIN_SUPPORTED_PROTOCOL_VERSION,
+    PROTOCOL_VERSION, PROTOCOL_VERSION_HEADER,
+};
+
+use super::ApiError;
+
+/// Check a client's protocol version, returning the error to send on mismatch
+pub fn check_client_version(client_version: u32) -> Result<(), ApiError> {
+    let compatibility = ProtocolCompatibility::check(client_version);
+    if compatibility.is_compatible() {
+        return Ok(());
+    }
+
+    warn!(
+        "Rejected client with protocol version {} (server speaks {}, accepts {}+)",
+        client_version, PROTOCOL_VERSION, MIN_SUPPORTED_PROTOCOL_VERSION
+    );
+    Err(ApiError::ProtocolMismatch { compatibility, client_version })
+}
+
+/// Exchange protocol versions with a client
+pub async fn handshake(
+    Json(request): Json<Handshake>,
+) -> Result<impl IntoResponse, ApiError> {
+    check_client_version(request.protocol_version)?;
+
+    Ok(Json(HandshakeResponse {
+        protocol_version: PROTOCOL_VERSION,
+        min_protocol_version: MIN_SUPPORTED_PROTOCOL_VERSION,
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+    }))
+}
+
+/// Reject requests whose `X-Vectorize-Protocol` header names an incompatible version
+pub async fn enforce_protocol_version(req: Request, next: Next) -> Response {
+    if let Some(value) = req.headers().get(PROTOCOL_VERSION_HEADER) {
+        let client_version = match value.to_str().ok().and_then(|v| v.trim().parse::<u32>().ok()) {
+            Some(version) => version,
+            None => {
+                return ApiError::bad_request(format!(
+                    "{} must be a protocol version number",
+                    PROTOCOL_VERSION_HEADER
+                ))
+                .into_response();
+            }
+        };
+        if let Err(e) = check_client_version(client_version) {
+            return e.into_response();
+        }
+    }
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::StatusCode,
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route("/handshake", post(handshake))
+            .layer(middleware::from_fn(enforce_protocol_version))
+    }
+
+    async fn send(request: axum::http::Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    fn with_version(version: &str) -> axum::http::Request<Body> {
+        axum::http::Request::get("/")
+            .header(PROTOCOL_VERSION_HEADER, version)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn handshake_request(version: u32) -> axum::http::Request<Body> {
+        axum::http::Request::post("/handshake")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "protocol_version": version }).to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_without_header_passes() {
+        let (status, _) = send(axum::http::Request::get("/").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_matching_header_passes() {
+        let (status, _) = send(with_version(&PROTOCOL_VERSION.to_string())).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_newer_client_header_is_rejected() {
+        let (status, json) = send(with_version(&(PROTOCOL_VERSION + 1).to_string())).await;
+        assert_eq!(status, StatusCode::UPGRADE_REQUIRED);
+        assert_eq!(json["error"]["code"], "protocol_mismatch");
+        assert_eq!(json["error"]["details"]["reason"], "client_too_new");
+        assert!(json["error"]["message"].as_str().unwrap().contains("too new"));
+    }
+
+    #[tokio::test]
+    async fn test_older_client_header_is_rejected() {
+        // Nothing is too old while every version is still supported
+        let Some(old_version) = MIN_SUPPORTED_PROTOCOL_VERSION.checked_sub(1) else {
+            return;
+        };
+        let (status, json) = send(with_version(&old_version.to_string())).await;
+        assert_eq!(status, StatusCode::UPGRADE_REQUIRED);
+        assert_eq!(json["error"]["details"]["reason"], "client_too_old");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_header_is_bad_request() {
+        let (status, json) = send(with_version("v2")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["error"]["code"], "bad_request");
+    }
+
+    #[tokio::test]
+    async fn test_handshake_matching_version() {
+        let (status, json) = send(handshake_request(PROTOCOL_VERSION)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(json["min_protocol_version"], MIN_SUPPORTED_PROTOCOL_VERSION);
+        assert_eq!(json["server_version"], env!("CARGO_PKG_VERSION"));
+    }
+
+    #[tokio::test]
+    async fn test_handshake_mismatched_version() {
+        let (status, json) = send(handshake_request(PROTOCOL_VERSION + 1)).await;
+        assert_eq!(status, StatusCode::UPGRADE_REQUIRED);
+        assert_eq!(json["error"]["code"], "protocol_mismatch");
+        assert_eq!(json["error"]["details"]["server_version"], PROTOCOL_VERSION);
+    }
+}
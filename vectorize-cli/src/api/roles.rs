@@ -10,9 +10,10 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::info;
 
 use crate::AppState;
+use crate::api::ApiError;
 use crate::db::models::RoleResponse;
 use crate::db::repository::RoleRepository;
 use crate::rbac::{AuthenticatedUser, Permission, require_permission};
@@ -57,25 +58,15 @@ pub struct PermissionInfo {
 pub async fn list_roles(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthenticatedUser>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "roles_read") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "roles_read")?;
     
     let pool = state.db.pool();
     
-    match RoleRepository::list(pool).await {
-        Ok(roles) => {
-            let role_responses: Vec<RoleResponse> = roles.into_iter().map(RoleResponse::from).collect();
-            (StatusCode::OK, Json(RolesResponse { roles: role_responses })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to list roles: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list roles"
-            }))).into_response()
-        }
-    }
+    let roles = RoleRepository::list(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list roles", e))?;
+    let role_responses: Vec<RoleResponse> = roles.into_iter().map(RoleResponse::from).collect();
+    Ok((StatusCode::OK, Json(RolesResponse { roles: role_responses })))
 }
 
 /// Get a specific role
@@ -83,29 +74,15 @@ pub async fn get_role(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthenticatedUser>,
     Path(role_id): Path<String>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "roles_read") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "roles_read")?;
     
     let pool = state.db.pool();
     
-    match RoleRepository::get_by_id(pool, &role_id).await {
-        Ok(Some(role)) => {
-            (StatusCode::OK, Json(RoleResponse::from(role))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Role not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to get role: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get role"
-            }))).into_response()
-        }
-    }
+    let role = RoleRepository::get_by_id(pool, &role_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get role", e))?
+        .ok_or_else(|| ApiError::not_found("Role not found"))?;
+    Ok((StatusCode::OK, Json(RoleResponse::from(role))))
 }
 
 /// Create a new custom role
@@ -113,16 +90,12 @@ pub async fn create_role(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthenticatedUser>,
     Json(request): Json<CreateRoleRequest>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "roles_write") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "roles_write")?;
     
     // Validate role name
     if request.name.is_empty() || request.name.len() > 50 {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "Role name must be between 1 and 50 characters"
-        }))).into_response();
+        return Err(ApiError::bad_request("Role name must be between 1 and 50 characters"));
     }
     
     // Validate permissions
@@ -134,10 +107,8 @@ pub async fn create_role(
     for perm in &request.permissions {
         let normalized = perm.to_lowercase().replace("_", "");
         if !valid_permissions.iter().any(|p| p == &normalized) {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": format!("Invalid permission: {}", perm),
-                "valid_permissions": valid_permissions
-            }))).into_response();
+            return Err(ApiError::bad_request(format!("Invalid permission: {}", perm))
+                .with_details(serde_json::json!({ "valid_permissions": valid_permissions })));
         }
     }
     
@@ -145,24 +116,15 @@ pub async fn create_role(
     
     // Check if role name already exists
     if let Ok(Some(_)) = RoleRepository::get_by_id(pool, &request.name.to_lowercase()).await {
-        return (StatusCode::CONFLICT, Json(serde_json::json!({
-            "error": "Role with this name already exists"
-        }))).into_response();
+        return Err(ApiError::conflict("Role with this name already exists"));
     }
     
     // Create role
-    match RoleRepository::create(pool, &request.name, request.description.as_deref(), &request.permissions).await {
-        Ok(role) => {
-            info!("Role created: {} by {}", request.name, user.user_id);
-            (StatusCode::CREATED, Json(RoleResponse::from(role))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to create role: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to create role"
-            }))).into_response()
-        }
-    }
+    let role = RoleRepository::create(pool, &request.name, request.description.as_deref(), &request.permissions).await
+        .map_err(|e| ApiError::internal_logged("Failed to create role", e))?;
+    
+    info!("Role created: {} by {}", request.name, user.user_id);
+    Ok((StatusCode::CREATED, Json(RoleResponse::from(role))))
 }
 
 /// Update a custom role
@@ -171,34 +133,19 @@ pub async fn update_role(
     Extension(user): Extension<AuthenticatedUser>,
     Path(role_id): Path<String>,
     Json(request): Json<UpdateRoleRequest>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "roles_write") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "roles_write")?;
     
     let pool = state.db.pool();
     
     // Get existing role
-    let existing = match RoleRepository::get_by_id(pool, &role_id).await {
-        Ok(Some(role)) => role,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Role not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get role: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Database error"
-            }))).into_response();
-        }
-    };
+    let existing = RoleRepository::get_by_id(pool, &role_id).await
+        .map_err(|e| ApiError::internal_logged("Database error", e))?
+        .ok_or_else(|| ApiError::not_found("Role not found"))?;
     
     // Cannot modify built-in roles
     if existing.is_builtin {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({
-            "error": "Cannot modify built-in roles"
-        }))).into_response();
+        return Err(ApiError::forbidden("Cannot modify built-in roles"));
     }
     
     // Validate permissions if provided
@@ -211,9 +158,7 @@ pub async fn update_role(
         for perm in perms {
             let normalized = perm.to_lowercase().replace("_", "");
             if !valid_permissions.iter().any(|p| p == &normalized) {
-                return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                    "error": format!("Invalid permission: {}", perm)
-                }))).into_response();
+                return Err(ApiError::bad_request(format!("Invalid permission: {}", perm)));
             }
         }
     }
@@ -230,11 +175,11 @@ pub async fn update_role(
     let result = sqlx::query_as::<_, crate::db::models::Role>(
         r#"
         UPDATE roles SET 
-            name = ?,
-            description = ?,
-            permissions = ?,
-            updated_at = datetime('now')
-        WHERE id = ? AND is_builtin = 0
+            name = $1,
+            description = $2,
+            permissions = $3,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $4 AND is_builtin = FALSE
         RETURNING *
         "#
     )
@@ -243,25 +188,12 @@ pub async fn update_role(
     .bind(&permissions_json)
     .bind(&role_id)
     .fetch_optional(pool)
-    .await;
+    .await
+    .map_err(|e| ApiError::internal_logged("Failed to update role", e))?;
     
-    match result {
-        Ok(Some(updated)) => {
-            info!("Role updated: {} by {}", role_id, user.user_id);
-            (StatusCode::OK, Json(RoleResponse::from(updated))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Role not found or is built-in"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to update role: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to update role"
-            }))).into_response()
-        }
-    }
+    let updated = result.ok_or_else(|| ApiError::not_found("Role not found or is built-in"))?;
+    info!("Role updated: {} by {}", role_id, user.user_id);
+    Ok((StatusCode::OK, Json(RoleResponse::from(updated))))
 }
 
 /// Delete a custom role
@@ -269,62 +201,45 @@ pub async fn delete_role(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthenticatedUser>,
     Path(role_id): Path<String>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "roles_delete") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "roles_delete")?;
     
     let pool = state.db.pool();
     
     // Check if role is built-in
     if let Ok(Some(role)) = RoleRepository::get_by_id(pool, &role_id).await {
         if role.is_builtin {
-            return (StatusCode::FORBIDDEN, Json(serde_json::json!({
-                "error": "Cannot delete built-in roles"
-            }))).into_response();
+            return Err(ApiError::forbidden("Cannot delete built-in roles"));
         }
     }
     
     // Check if any users have this role
-    let user_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE role_id = ?")
+    let user_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users WHERE role_id = $1")
         .bind(&role_id)
         .fetch_one(pool)
         .await
         .unwrap_or((0,));
     
     if user_count.0 > 0 {
-        return (StatusCode::CONFLICT, Json(serde_json::json!({
-            "error": "Cannot delete role that is assigned to users",
-            "user_count": user_count.0
-        }))).into_response();
+        return Err(ApiError::conflict("Cannot delete role that is assigned to users")
+            .with_details(serde_json::json!({ "user_count": user_count.0 })));
     }
     
-    match RoleRepository::delete(pool, &role_id).await {
-        Ok(true) => {
-            info!("Role deleted: {} by {}", role_id, user.user_id);
-            (StatusCode::NO_CONTENT).into_response()
-        }
-        Ok(false) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Role not found or is built-in"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to delete role: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to delete role"
-            }))).into_response()
-        }
+    let deleted = RoleRepository::delete(pool, &role_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to delete role", e))?;
+    if !deleted {
+        return Err(ApiError::not_found("Role not found or is built-in"));
     }
+    
+    info!("Role deleted: {} by {}", role_id, user.user_id);
+    Ok(StatusCode::NO_CONTENT)
 }
 
 /// List all available permissions
 pub async fn list_permissions(
     Extension(user): Extension<AuthenticatedUser>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "roles_read") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "roles_read")?;
     
     let permissions: Vec<PermissionInfo> = vec![
         // Agent permissions
@@ -372,5 +287,5 @@ pub async fn list_permissions(
         PermissionInfo { name: "system_admin".into(), description: "Full system access".into(), category: "System".into() },
     ];
     
-    (StatusCode::OK, Json(PermissionsResponse { permissions })).into_response()
+    Ok((StatusCode::OK, Json(PermissionsResponse { permissions })))
 }
@@ -0,0 +1,199 @@
+//! Global search
+//!
+//! One query across agents, worker groups and deployments for the UI's
+//! search box. Each category is capped, and hits are ranked exact match
+//! first, then prefix, then substring.
+
+use axum::{
+    extract::{Extension, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::api::ApiError;
+use crate::db::repository::{AgentRepository, DeploymentRepository, WorkerGroupRepository};
+use crate::rbac::AuthenticatedUser;
+
+/// Default hits per category
+pub const DEFAULT_SEARCH_LIMIT: i64 = 10;
+
+/// Most hits per category a client can ask for
+pub const MAX_SEARCH_LIMIT: i64 = 50;
+
+/// Query parameters for a search
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    /// Hits per category
+    pub limit: Option<i64>,
+}
+
+/// What a search hit is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Agent,
+    Group,
+    Deployment,
+}
+
+/// One search hit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    pub id: String,
+    pub label: String,
+    /// Field the query matched, e.g. `name`, `url` or `tag`
+    pub match_field: String,
+}
+
+/// Search response
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub query: String,
+    /// Best matches first
+    pub results: Vec<SearchResult>,
+}
+
+/// How well `value` matches `query`: 0 exact, 1 prefix, 2 substring
+fn match_rank(value: &str, query: &str) -> Option<u8> {
+    let value = value.to_lowercase();
+    if value == query {
+        Some(0)
+    } else if value.starts_with(query) {
+        Some(1)
+    } else if value.contains(query) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Best-ranked field matching `query`; earlier fields win ties
+fn best_match<'a>(fields: &[(&'a str, &str)], query: &str) -> Option<(u8, &'a str)> {
+    fields.iter()
+        .filter_map(|(field, value)| match_rank(value, query).map(|rank| (rank, *field)))
+        .min_by_key(|(rank, _)| *rank)
+}
+
+/// Search agents, worker groups and deployments
+///
+/// Authenticated callers only see categories they can read: agents need
+/// `agents_read`, groups and deployments `groups_read`.
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+    user: Option<Extension<AuthenticatedUser>>,
+) -> Result<Json<SearchResponse>, ApiError> {
+    let pool = state.db.pool();
+
+    let text = query.q.trim();
+    if text.is_empty() {
+        return Err(ApiError::bad_request("Search query must not be empty"));
+    }
+    let needle = text.to_lowercase();
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+    let can_read = |permission: &str| user.as_ref().is_none_or(|u| u.has_permission(permission));
+    let mut hits: Vec<(u8, SearchResult)> = Vec::new();
+
+    if can_read("agents_read") {
+        let agents = AgentRepository::search(pool, text, limit)
+            .await
+            .map_err(|e| ApiError::internal_logged("Failed to search agents", e))?;
+        let ids: Vec<String> = agents.iter().map(|a| a.id.clone()).collect();
+        let mut tags = AgentRepository::get_tags_for(pool, &ids)
+            .await
+            .map_err(|e| ApiError::internal_logged("Failed to get agent tags", e))?;
+        for agent in agents {
+            let tag_values: Vec<String> = tags.remove(&agent.id).unwrap_or_default().iter()
+                .flat_map(|t| [t.key.clone(), t.value.clone(), format!("{}={}", t.key, t.value)])
+                .collect();
+            let mut fields = vec![("name", agent.name.as_str()), ("url", agent.url.as_str())];
+            fields.extend(tag_values.iter().map(|v| ("tag", v.as_str())));
+
+            if let Some((rank, field)) = best_match(&fields, &needle) {
+                hits.push((rank, SearchResult {
+                    kind: SearchKind::Agent,
+                    id: agent.id,
+                    label: agent.name,
+                    match_field: field.to_string(),
+                }));
+            }
+        }
+    }
+
+    if can_read("groups_read") {
+        let groups = WorkerGroupRepository::search(pool, text, limit)
+            .await
+            .map_err(|e| ApiError::internal_logged("Failed to search worker groups", e))?;
+        for group in groups {
+            if let Some(rank) = match_rank(&group.name, &needle) {
+                hits.push((rank, SearchResult {
+                    kind: SearchKind::Group,
+                    id: group.id,
+                    label: group.name,
+                    match_field: "name".to_string(),
+                }));
+            }
+        }
+
+        let deployments = DeploymentRepository::search(pool, text, limit)
+            .await
+            .map_err(|e| ApiError::internal_logged("Failed to search deployments", e))?;
+        let mut group_names: HashMap<String, String> = HashMap::new();
+        for deployment in deployments {
+            let Some((rank, field)) = best_match(&[("id", deployment.id.as_str()), ("status", deployment.status.as_str())], &needle) else {
+                continue;
+            };
+            if !group_names.contains_key(&deployment.group_id) {
+                let name = WorkerGroupRepository::get_by_id(pool, &deployment.group_id)
+                    .await
+                    .map_err(|e| ApiError::internal_logged("Failed to get worker group", e))?
+                    .map(|g| g.name)
+                    .unwrap_or_else(|| deployment.group_id.clone());
+                group_names.insert(deployment.group_id.clone(), name);
+            }
+            hits.push((rank, SearchResult {
+                kind: SearchKind::Deployment,
+                label: format!(
+                    "{} deployment of {} ({})",
+                    group_names[&deployment.group_id],
+                    deployment.config_version.chars().take(8).collect::<String>(),
+                    deployment.status
+                ),
+                id: deployment.id,
+                match_field: field.to_string(),
+            }));
+        }
+    }
+
+    // Stable, so hits keep their per-category order within a rank
+    hits.sort_by_key(|(rank, result)| (*rank, result.kind));
+
+    Ok(Json(SearchResponse {
+        query: text.to_string(),
+        results: hits.into_iter().map(|(_, result)| result).collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_rank() {
+        assert_eq!(match_rank("Edge", "edge"), Some(0));
+        assert_eq!(match_rank("edge-eu", "edge"), Some(1));
+        assert_eq!(match_rank("prod-edge", "edge"), Some(2));
+        assert_eq!(match_rank("core", "edge"), None);
+
+        let fields = [("name", "prod-edge"), ("url", "http://edge:8686")];
+        assert_eq!(best_match(&fields, "edge"), Some((2, "name")));
+        let fields = [("name", "web-1"), ("tag", "edge")];
+        assert_eq!(best_match(&fields, "edge"), Some((0, "tag")));
+    }
+}
@@ -3,6 +3,7 @@
 //! Provides endpoints for:
 //! - Sampling live data from agents
 //! - Rate limit status
+//! - Redaction rules for sampled events
 //! - Agent connection info for WebSocket streaming
 
 use axum::{
@@ -16,8 +17,9 @@ use std::sync::Arc;
 use tracing::{info, error};
 
 use crate::AppState;
+use crate::api::ApiError;
 use crate::db::repository::AgentRepository;
-use crate::tap::{TapService, SampleRequest, RateLimitConfig};
+use crate::tap::{redaction_rules_from_env, SampleRequest, TapError, REDACTED};
 
 // =============================================================================
 // Request/Response Types
@@ -48,6 +50,8 @@ pub struct SampleApiResponse {
 /// Response for rate limit status
 #[derive(Debug, Serialize)]
 pub struct RateLimitStatusResponse {
+    pub agent_id: String,
+    /// Whether a sample started now would be accepted
     pub can_sample: bool,
     pub message: String,
     pub config: RateLimitConfigResponse,
@@ -70,24 +74,13 @@ pub async fn sample_agent(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
     Query(params): Query<SampleQuery>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Get the agent
-    let agent = match AgentRepository::get_by_id(pool, &agent_id).await {
-        Ok(Some(agent)) => agent,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Agent not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get agent: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get agent"
-            }))).into_response();
-        }
-    };
+    let agent = AgentRepository::get_by_id(pool, &agent_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
     
     // Build sample request
     let patterns: Vec<String> = params.patterns
@@ -101,50 +94,52 @@ pub async fn sample_agent(
         timeout_secs: params.timeout.unwrap_or(5),
     };
     
-    // Create tap service
-    let tap_service = TapService::new(RateLimitConfig::default());
-    
-    // Sample from agent
-    match tap_service.sample(&agent.url, &request).await {
-        Ok(response) => {
-            (StatusCode::OK, Json(SampleApiResponse {
-                agent_id: agent.id,
-                agent_name: agent.name,
-                events: response.events.into_iter().map(|e| e.event).collect(),
-                count: response.count,
-                duration_ms: response.duration_ms,
-                message: "Sample complete. Use WebSocket for real-time streaming.".to_string(),
-            })).into_response()
+    // Sample from agent; holds a concurrency slot until it finishes
+    let response = state.tap_service.sample(&agent.url, &request).await.map_err(|e| match e {
+        TapError::RateLimited(e) => {
+            info!("Sample from agent {} rejected: {}", agent_id, e);
+            ApiError::RateLimited { retry_after_secs: e.retry_after_secs() }
         }
-        Err(e) => {
+        e => {
             error!("Failed to sample from agent: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
+            ApiError::bad_request(e.to_string())
         }
-    }
+    })?;
+    
+    let redaction = redaction_rules_from_env();
+    Ok((StatusCode::OK, Json(SampleApiResponse {
+        agent_id: agent.id,
+        agent_name: agent.name,
+        events: response.events.into_iter()
+            .map(|mut e| {
+                redaction.redact(&mut e.event);
+                e.event
+            })
+            .collect(),
+        count: response.count,
+        duration_ms: response.duration_ms,
+        message: "Sample complete. Use WebSocket for real-time streaming.".to_string(),
+    })))
 }
 
 /// Check rate limit status for an agent
 pub async fn check_rate_limit(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Verify agent exists
     if let Ok(None) = AgentRepository::get_by_id(pool, &agent_id).await {
-        return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-            "error": "Agent not found"
-        }))).into_response();
+        return Err(ApiError::not_found("Agent not found"));
     }
     
-    let tap_service = TapService::new(RateLimitConfig::default());
-    let config = RateLimitConfig::default();
+    let config = state.tap_service.config();
     
-    match tap_service.can_sample(&agent_id).await {
+    Ok(match state.tap_service.can_sample(&agent_id).await {
         Ok(()) => {
             (StatusCode::OK, Json(RateLimitStatusResponse {
+                agent_id,
                 can_sample: true,
                 message: "Sampling is allowed".to_string(),
                 config: RateLimitConfigResponse {
@@ -156,6 +151,7 @@ pub async fn check_rate_limit(
         }
         Err(e) => {
             (StatusCode::TOO_MANY_REQUESTS, Json(RateLimitStatusResponse {
+                agent_id,
                 can_sample: false,
                 message: e.to_string(),
                 config: RateLimitConfigResponse {
@@ -165,7 +161,7 @@ pub async fn check_rate_limit(
                 },
             })).into_response()
         }
-    }
+    })
 }
 
 /// Get WebSocket connection info for an agent
@@ -175,45 +171,40 @@ pub async fn check_rate_limit(
 pub async fn get_websocket_info(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let pool = state.db.pool();
     
     // Get the agent
-    let agent = match AgentRepository::get_by_id(pool, &agent_id).await {
-        Ok(Some(agent)) => agent,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "Agent not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get agent: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get agent"
-            }))).into_response();
-        }
-    };
+    let agent = AgentRepository::get_by_id(pool, &agent_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get agent", e))?
+        .ok_or_else(|| ApiError::not_found("Agent not found"))?;
     
     info!("WebSocket info requested for agent {} at {}", agent_id, agent.url);
     
+    // Streams connect to the agent directly, so they can't hold a slot, but
+    // one isn't started while sampling is at its limit
+    if let Err(e) = state.tap_service.can_sample(&agent_id).await {
+        return Err(ApiError::RateLimited { retry_after_secs: e.retry_after_secs() });
+    }
+    
     // Convert agent URL to WebSocket URL
     let ws_url = agent.url
         .replace("http://", "ws://")
         .replace("https://", "wss://");
     let graphql_ws_url = format!("{}/graphql", ws_url.trim_end_matches('/'));
     
-    (StatusCode::OK, Json(serde_json::json!({
+    Ok((StatusCode::OK, Json(serde_json::json!({
         "agent_id": agent.id,
         "agent_name": agent.name,
         "websocket_url": graphql_ws_url,
         "protocol": "graphql-transport-ws",
         "message": "Connect directly to this WebSocket URL for real-time streaming"
-    }))).into_response()
+    }))))
 }
 
 /// Get tap configuration
-pub async fn get_tap_config() -> impl IntoResponse {
-    let config = RateLimitConfig::default();
+pub async fn get_tap_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let config = state.tap_service.config();
     
     (StatusCode::OK, Json(serde_json::json!({
         "rate_limiting": {
@@ -226,3 +217,16 @@ pub async fn get_tap_config() -> impl IntoResponse {
         "websocket_enabled": true,
     }))).into_response()
 }
+
+/// Get the redaction rules for sampled events
+///
+/// The tap viewer applies these to events before display, download or copy.
+/// A session may add its own rules on top, but can't remove these.
+pub async fn get_redaction_rules() -> impl IntoResponse {
+    let rules = redaction_rules_from_env();
+    
+    (StatusCode::OK, Json(serde_json::json!({
+        "fields": rules.fields,
+        "replacement": REDACTED,
+    }))).into_response()
+}
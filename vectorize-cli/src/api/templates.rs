@@ -0,0 +1,100 @@
+//! Config template API endpoints
+//!
+//! Templates live in the config repository under `templates/`; see
+//! `git_store::templates` for the file format.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::AppState;
+use crate::api::ApiError;
+use crate::api::groups::{find_group, UpdateConfigResponse};
+use crate::db::repository::WorkerGroupRepository;
+use crate::git_store::{GitStoreError, TemplateInfo};
+
+/// Response with template list
+#[derive(Debug, Serialize)]
+pub struct TemplatesResponse {
+    pub templates: Vec<TemplateInfo>,
+}
+
+/// Request to set a group's config from a template
+#[derive(Debug, Deserialize)]
+pub struct FromTemplateRequest {
+    /// Template name (file name without `.toml`)
+    pub template: String,
+    /// Values for the template's variables
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+/// List available templates and their variables
+pub async fn list_templates(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let templates = state.git_store.list_templates()
+        .map_err(|e| ApiError::internal_logged("Failed to list templates", e))?;
+    Ok((StatusCode::OK, Json(TemplatesResponse { templates })))
+}
+
+/// Render a template and commit it as the group's config
+///
+/// The rendered config is validated like a direct config update. Missing
+/// required variables are a 400 with their names in `details.missing`.
+pub async fn create_config_from_template(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(request): Json<FromTemplateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let pool = state.db.pool();
+    
+    let group = find_group(pool, &id, "Worker group").await?;
+    
+    let config = state.git_store.render_template(&request.template, &request.variables)
+        .map_err(template_error)?;
+    
+    let vector_bin = state.vector_process.get_binary_path();
+    let validation = crate::validation::ConfigValidator::new(vector_bin).validate(&config);
+    if !validation.valid {
+        let error_msg = validation.errors.first()
+            .map(|e| e.message.clone())
+            .unwrap_or_else(|| "Configuration validation failed".to_string());
+        return Err(ApiError::bad_request(format!("Rendered template failed validation: {}", error_msg))
+            .with_details(serde_json::json!({ "errors": validation.errors })));
+    }
+    
+    let commit_hash = state.git_store.instantiate_template(&request.template, &group.name, request.variables)
+        .map_err(template_error)?;
+    
+    if let Err(e) = WorkerGroupRepository::update_config_version(pool, &id, &commit_hash).await {
+        warn!("Failed to update group config version: {}", e);
+    }
+    
+    info!("Set config for group {} from template {} (version: {})", group.name, request.template, &commit_hash[..8]);
+    
+    Ok((StatusCode::OK, Json(UpdateConfigResponse {
+        success: true,
+        version: Some(commit_hash),
+        message: format!("Configuration created from template {}", request.template),
+    })))
+}
+
+fn template_error(e: GitStoreError) -> ApiError {
+    match e {
+        GitStoreError::NotFound(message) => ApiError::not_found(message),
+        GitStoreError::MissingVariables(missing) => {
+            ApiError::bad_request(format!("Missing template variables: {}", missing.join(", ")))
+                .with_details(serde_json::json!({ "missing": missing }))
+        }
+        GitStoreError::Template(message) => ApiError::bad_request(message),
+        e => ApiError::internal_logged("Failed to instantiate template", e),
+    }
+}
@@ -10,13 +10,14 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::info;
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
     Argon2,
 };
 
 use crate::AppState;
+use crate::api::ApiError;
 use crate::db::models::UserResponse;
 use crate::db::repository::UserRepository;
 use crate::rbac::{AuthenticatedUser, require_permission};
@@ -50,25 +51,15 @@ pub struct UsersResponse {
 pub async fn list_users(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthenticatedUser>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "users_read") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "users_read")?;
     
     let pool = state.db.pool();
     
-    match UserRepository::list(pool).await {
-        Ok(users) => {
-            let user_responses: Vec<UserResponse> = users.into_iter().map(UserResponse::from).collect();
-            (StatusCode::OK, Json(UsersResponse { users: user_responses })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to list users: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to list users"
-            }))).into_response()
-        }
-    }
+    let users = UserRepository::list(pool).await
+        .map_err(|e| ApiError::internal_logged("Failed to list users", e))?;
+    let user_responses: Vec<UserResponse> = users.into_iter().map(UserResponse::from).collect();
+    Ok((StatusCode::OK, Json(UsersResponse { users: user_responses })))
 }
 
 /// Get a specific user by ID
@@ -76,32 +67,18 @@ pub async fn get_user(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthenticatedUser>,
     Path(user_id): Path<String>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     // Users can always view their own profile
     if user.user_id != user_id {
-        if let Err(resp) = require_permission(&user, "users_read") {
-            return resp;
-        }
+        require_permission(&user, "users_read")?;
     }
     
     let pool = state.db.pool();
     
-    match UserRepository::get_by_id(pool, &user_id).await {
-        Ok(Some(u)) => {
-            (StatusCode::OK, Json(UserResponse::from(u))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "User not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to get user: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to get user"
-            }))).into_response()
-        }
-    }
+    let u = UserRepository::get_by_id(pool, &user_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to get user", e))?
+        .ok_or_else(|| ApiError::not_found("User not found"))?;
+    Ok((StatusCode::OK, Json(UserResponse::from(u))))
 }
 
 /// Create a new user
@@ -109,70 +86,44 @@ pub async fn create_user(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthenticatedUser>,
     Json(request): Json<CreateUserRequest>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "users_write") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "users_write")?;
     
     // Validate input
     if request.username.len() < 3 {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "Username must be at least 3 characters"
-        }))).into_response();
+        return Err(ApiError::bad_request("Username must be at least 3 characters"));
     }
     
     if request.password.len() < 8 {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "Password must be at least 8 characters"
-        }))).into_response();
+        return Err(ApiError::bad_request("Password must be at least 8 characters"));
     }
     
     if !request.email.contains('@') || !request.email.contains('.') {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "Invalid email format"
-        }))).into_response();
+        return Err(ApiError::bad_request("Invalid email format"));
     }
     
     let pool = state.db.pool();
     
     // Check if username already exists
     if let Ok(Some(_)) = UserRepository::get_by_username(pool, &request.username).await {
-        return (StatusCode::CONFLICT, Json(serde_json::json!({
-            "error": "Username already exists"
-        }))).into_response();
+        return Err(ApiError::conflict("Username already exists"));
     }
     
     // Check if email already exists
     if let Ok(Some(_)) = UserRepository::get_by_email(pool, &request.email).await {
-        return (StatusCode::CONFLICT, Json(serde_json::json!({
-            "error": "Email already exists"
-        }))).into_response();
+        return Err(ApiError::conflict("Email already exists"));
     }
     
     // Hash password
-    let password_hash = match hash_password(&request.password) {
-        Ok(hash) => hash,
-        Err(e) => {
-            error!("Failed to hash password: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to process password"
-            }))).into_response();
-        }
-    };
+    let password_hash = hash_password(&request.password)
+        .map_err(|e| ApiError::internal_logged("Failed to process password", e))?;
     
     // Create user
-    match UserRepository::create(pool, &request.username, &request.email, &password_hash, &request.role_id).await {
-        Ok(new_user) => {
-            info!("User created: {} by {}", request.username, user.user_id);
-            (StatusCode::CREATED, Json(UserResponse::from(new_user))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to create user: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to create user"
-            }))).into_response()
-        }
-    }
+    let new_user = UserRepository::create(pool, &request.username, &request.email, &password_hash, &request.role_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to create user", e))?;
+    
+    info!("User created: {} by {}", request.username, user.user_id);
+    Ok((StatusCode::CREATED, Json(UserResponse::from(new_user))))
 }
 
 /// Update a user
@@ -181,41 +132,26 @@ pub async fn update_user(
     Extension(auth_user): Extension<AuthenticatedUser>,
     Path(user_id): Path<String>,
     Json(request): Json<UpdateUserRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     // Users can update their own profile (limited fields)
     // Admin permission required for full updates
     let is_self = auth_user.user_id == user_id;
     
     if !is_self {
-        if let Err(resp) = require_permission(&auth_user, "users_write") {
-            return resp;
-        }
+        require_permission(&auth_user, "users_write")?;
     }
     
     // Non-admins cannot change their own role
     if is_self && request.role_id.is_some() && !auth_user.has_permission("users_write") {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({
-            "error": "Cannot change your own role"
-        }))).into_response();
+        return Err(ApiError::forbidden("Cannot change your own role"));
     }
     
     let pool = state.db.pool();
     
     // Get existing user
-    let existing = match UserRepository::get_by_id(pool, &user_id).await {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "User not found"
-            }))).into_response();
-        }
-        Err(e) => {
-            error!("Failed to get user: {}", e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Database error"
-            }))).into_response();
-        }
-    };
+    let existing = UserRepository::get_by_id(pool, &user_id).await
+        .map_err(|e| ApiError::internal_logged("Database error", e))?
+        .ok_or_else(|| ApiError::not_found("User not found"))?;
     
     // Build update query
     let existing_username = existing.username.unwrap_or_default();
@@ -228,18 +164,10 @@ pub async fn update_user(
     // Hash new password if provided
     let new_password_hash = if let Some(ref password) = request.password {
         if password.len() < 8 {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Password must be at least 8 characters"
-            }))).into_response();
+            return Err(ApiError::bad_request("Password must be at least 8 characters"));
         }
-        Some(match hash_password(password) {
-            Ok(hash) => hash,
-            Err(_) => {
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                    "error": "Failed to process password"
-                }))).into_response();
-            }
-        })
+        Some(hash_password(password)
+            .map_err(|e| ApiError::internal_logged("Failed to process password", e))?)
     } else {
         None
     };
@@ -248,13 +176,13 @@ pub async fn update_user(
     let result = sqlx::query_as::<_, crate::db::models::User>(
         r#"
         UPDATE users SET 
-            username = ?,
-            email = ?,
-            password_hash = COALESCE(?, password_hash),
-            role_id = ?,
-            is_active = ?,
-            updated_at = datetime('now')
-        WHERE id = ?
+            username = $1,
+            email = $2,
+            password_hash = COALESCE($3, password_hash),
+            role_id = $4,
+            is_active = $5,
+            updated_at = CURRENT_TIMESTAMP
+        WHERE id = $6
         RETURNING *
         "#
     )
@@ -265,25 +193,12 @@ pub async fn update_user(
     .bind(new_active)
     .bind(&user_id)
     .fetch_optional(pool)
-    .await;
+    .await
+    .map_err(|e| ApiError::internal_logged("Failed to update user", e))?;
     
-    match result {
-        Ok(Some(updated)) => {
-            info!("User updated: {} by {}", user_id, auth_user.user_id);
-            (StatusCode::OK, Json(UserResponse::from(updated))).into_response()
-        }
-        Ok(None) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "User not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to update user: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to update user"
-            }))).into_response()
-        }
-    }
+    let updated = result.ok_or_else(|| ApiError::not_found("User not found"))?;
+    info!("User updated: {} by {}", user_id, auth_user.user_id);
+    Ok((StatusCode::OK, Json(UserResponse::from(updated))))
 }
 
 /// Delete a user
@@ -291,37 +206,24 @@ pub async fn delete_user(
     State(state): State<Arc<AppState>>,
     Extension(user): Extension<AuthenticatedUser>,
     Path(user_id): Path<String>,
-) -> impl IntoResponse {
-    if let Err(resp) = require_permission(&user, "users_delete") {
-        return resp;
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    require_permission(&user, "users_delete")?;
     
     // Cannot delete yourself
     if user.user_id == user_id {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-            "error": "Cannot delete your own account"
-        }))).into_response();
+        return Err(ApiError::bad_request("Cannot delete your own account"));
     }
     
     let pool = state.db.pool();
     
-    match UserRepository::delete(pool, &user_id).await {
-        Ok(true) => {
-            info!("User deleted: {} by {}", user_id, user.user_id);
-            (StatusCode::NO_CONTENT).into_response()
-        }
-        Ok(false) => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": "User not found"
-            }))).into_response()
-        }
-        Err(e) => {
-            error!("Failed to delete user: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to delete user"
-            }))).into_response()
-        }
+    let deleted = UserRepository::delete(pool, &user_id).await
+        .map_err(|e| ApiError::internal_logged("Failed to delete user", e))?;
+    if !deleted {
+        return Err(ApiError::not_found("User not found"));
     }
+    
+    info!("User deleted: {} by {}", user_id, user.user_id);
+    Ok(StatusCode::NO_CONTENT)
 }
 
 /// Hash a password using Argon2
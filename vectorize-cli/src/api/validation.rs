@@ -7,11 +7,14 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{info, error};
 
 use crate::AppState;
-use crate::validation::{ConfigValidator, ValidationResult, FunctionalTestRequest};
+use crate::api::ApiError;
+use crate::validation::{ConfigValidator, ValidationResult, FunctionalTestRequest, TransformResult};
+use crate::validation::convert::{self, ConfigFormat, ConversionError};
 
 /// Request to validate a configuration
 #[derive(Debug, Deserialize)]
@@ -21,6 +24,9 @@ pub struct ValidateRequest {
     /// Whether to run Vector's validate command (slower but more thorough)
     #[serde(default)]
     pub use_vector: bool,
+    /// Warn about environment variables unset on this server
+    #[serde(default)]
+    pub check_env: bool,
 }
 
 /// Response for validation
@@ -82,7 +88,7 @@ pub async fn validate_config(
     
     // Get Vector binary path from state if available
     let vector_bin = state.vector_process.get_binary_path();
-    let validator = ConfigValidator::new(vector_bin);
+    let validator = ConfigValidator::new(vector_bin).with_env_check(request.check_env);
     
     let result = if request.use_vector {
         validator.validate_with_vector(&request.config)
@@ -115,6 +121,132 @@ pub async fn validate_quick(
     (status, Json(ValidateResponse::from(result)))
 }
 
+/// Request to lint a configuration
+#[derive(Debug, Deserialize)]
+pub struct LintRequest {
+    /// Configuration content (TOML)
+    pub config: String,
+    /// Apply production-only rules (e.g. console sinks)
+    #[serde(default)]
+    pub production: bool,
+}
+
+/// Response for lint
+#[derive(Debug, Serialize)]
+pub struct LintResponse {
+    /// Best-practice warnings
+    pub warnings: Vec<ValidationWarningResponse>,
+    /// Summary message
+    pub message: String,
+}
+
+/// Lint a configuration for best-practice warnings
+///
+/// Advisory only: always returns 200, and has no bearing on whether the
+/// config can be deployed.
+pub async fn lint_config(
+    Json(request): Json<LintRequest>,
+) -> impl IntoResponse {
+    let validator = ConfigValidator::new(None).with_production(request.production);
+    let warnings = validator.lint(&request.config);
+    
+    let message = match warnings.len() {
+        0 => "No lint warnings".to_string(),
+        1 => "1 lint warning".to_string(),
+        n => format!("{} lint warnings", n),
+    };
+    
+    Json(LintResponse {
+        warnings: warnings.into_iter().map(|w| ValidationWarningResponse {
+            code: w.code,
+            message: w.message,
+            component: w.component,
+        }).collect(),
+        message,
+    })
+}
+
+/// Request to explain a configuration
+#[derive(Debug, Deserialize)]
+pub struct ExplainRequest {
+    /// Configuration content (TOML)
+    pub config: String,
+}
+
+/// Parse a configuration into its component graph
+///
+/// Returns nodes, edges from input references, and orphan and cycle flags,
+/// so clients can render the topology without parsing TOML. Only a TOML
+/// syntax error is rejected; other problems are reported in the graph.
+pub async fn explain_config(
+    Json(request): Json<ExplainRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let graph = ConfigValidator::new(None).explain(&request.config).map_err(|e| {
+        ApiError::bad_request(e.message.clone()).with_details(serde_json::json!({
+            "code": e.code,
+            "line": e.line,
+            "column": e.column,
+        }))
+    })?;
+    
+    Ok(Json(graph))
+}
+
+/// Summarize a configuration's size and complexity
+///
+/// Component counts by kind and type, VRL transforms, the longest pipeline
+/// and fan-out hotspots, to help reviewers gauge a change. Takes the same
+/// body as `explain`, and likewise only rejects TOML syntax errors.
+pub async fn analyze_config(
+    Json(request): Json<ExplainRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let report = ConfigValidator::new(None).analyze(&request.config).map_err(|e| {
+        ApiError::bad_request(e.message.clone()).with_details(serde_json::json!({
+            "code": e.code,
+            "line": e.line,
+            "column": e.column,
+        }))
+    })?;
+    
+    Ok(Json(report))
+}
+
+/// Request to convert a configuration between formats
+#[derive(Debug, Deserialize)]
+pub struct ConvertRequest {
+    /// Configuration content in the `from` format
+    pub config: String,
+    pub from: ConfigFormat,
+    pub to: ConfigFormat,
+}
+
+/// Response for a format conversion
+#[derive(Debug, Serialize)]
+pub struct ConvertResponse {
+    /// Configuration content in the `format` format
+    pub config: String,
+    pub format: ConfigFormat,
+}
+
+/// Convert a configuration between TOML, YAML and JSON
+///
+/// Component order is kept. A config holding a value the target format
+/// can't represent is rejected with the path of that value.
+pub async fn convert_config(
+    Json(request): Json<ConvertRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let config = convert::convert(&request.config, request.from, request.to).map_err(|e| {
+        let details = match &e {
+            ConversionError::Parse { line, .. } => serde_json::json!({ "code": e.code(), "line": line }),
+            ConversionError::Lossy { path, .. } => serde_json::json!({ "code": e.code(), "path": path }),
+            ConversionError::Write { .. } => serde_json::json!({ "code": e.code() }),
+        };
+        ApiError::bad_request(e.to_string()).with_details(details)
+    })?;
+    
+    Ok(Json(ConvertResponse { config, format: request.to }))
+}
+
 // =============================================================================
 // Functional Testing Endpoints (Layer 4)
 // =============================================================================
@@ -131,6 +263,9 @@ pub struct StartTestRequest {
     /// Timeout in seconds (default: 30)
     #[serde(default = "default_test_timeout")]
     pub timeout_secs: u64,
+    /// Include per-event before/after diffs in the results
+    #[serde(default)]
+    pub diff: bool,
 }
 
 fn default_test_timeout() -> u64 { 30 }
@@ -156,13 +291,15 @@ pub struct TestResultResponse {
     pub errors: Vec<String>,
     pub started_at: String,
     pub completed_at: Option<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub transform_results: HashMap<String, TransformResult>,
 }
 
 /// Start a functional test
 pub async fn start_functional_test(
     State(state): State<Arc<AppState>>,
     Json(request): Json<StartTestRequest>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     info!("Starting functional test with {} sample events", request.sample_events.len());
     
     // Build the test request
@@ -171,52 +308,43 @@ pub async fn start_functional_test(
         sample_events: request.sample_events,
         source_id: request.source_id,
         timeout_secs: request.timeout_secs,
+        diff: request.diff,
     };
     
     // Run the test
-    match state.functional_test_service.run_test(test_request).await {
-        Ok(test_id) => {
-            (StatusCode::ACCEPTED, Json(StartTestResponse {
-                test_id,
-                status: "running".to_string(),
-                message: "Functional test started".to_string(),
-            })).into_response()
-        }
-        Err(e) => {
-            error!("Failed to start functional test: {}", e);
-            (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": e.to_string()
-            }))).into_response()
-        }
-    }
+    let test_id = state.functional_test_service.run_test(test_request).await.map_err(|e| {
+        error!("Failed to start functional test: {}", e);
+        ApiError::bad_request(e.to_string())
+    })?;
+    
+    Ok((StatusCode::ACCEPTED, Json(StartTestResponse {
+        test_id,
+        status: "running".to_string(),
+        message: "Functional test started".to_string(),
+    })))
 }
 
 /// Get functional test status/results
 pub async fn get_test_result(
     State(state): State<Arc<AppState>>,
     Path(test_id): Path<String>,
-) -> impl IntoResponse {
-    match state.functional_test_service.get_result(&test_id).await {
-        Some(result) => {
-            (StatusCode::OK, Json(TestResultResponse {
-                test_id: result.test_id,
-                status: format!("{:?}", result.status).to_lowercase(),
-                input_events: result.input_events,
-                output_events: result.output_events,
-                output_count: result.output_count,
-                dropped_count: result.dropped_count,
-                duration_ms: result.duration_ms,
-                errors: result.errors,
-                started_at: result.started_at,
-                completed_at: result.completed_at,
-            })).into_response()
-        }
-        None => {
-            (StatusCode::NOT_FOUND, Json(serde_json::json!({
-                "error": format!("Test {} not found", test_id)
-            }))).into_response()
-        }
-    }
+) -> Result<impl IntoResponse, ApiError> {
+    let result = state.functional_test_service.get_result(&test_id).await
+        .ok_or_else(|| ApiError::not_found(format!("Test {} not found", test_id)))?;
+    
+    Ok((StatusCode::OK, Json(TestResultResponse {
+        test_id: result.test_id,
+        status: format!("{:?}", result.status).to_lowercase(),
+        input_events: result.input_events,
+        output_events: result.output_events,
+        output_count: result.output_count,
+        dropped_count: result.dropped_count,
+        duration_ms: result.duration_ms,
+        errors: result.errors,
+        started_at: result.started_at,
+        completed_at: result.completed_at,
+        transform_results: result.transform_results,
+    })))
 }
 
 /// List recent test results
@@ -236,6 +364,7 @@ pub async fn list_test_results(
         errors: r.errors,
         started_at: r.started_at,
         completed_at: r.completed_at,
+        transform_results: r.transform_results,
     }).collect();
     
     (StatusCode::OK, Json(response))
@@ -2,20 +2,37 @@
 //!
 //! Provides commands for managing:
 //! - Agents (list, register, delete)
-//! - Groups (list, create, delete)
+//! - Groups (list, create, update, delete, clone)
 //! - Config (get, set, validate)
 //! - Deployments (create, status, approve)
+//! - Database (backup, restore, migrations)
 
-use clap::Subcommand;
+use clap::{Args, Subcommand};
 use reqwest::Client;
 use serde_json::json;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use vectorize::db::Database;
+use vectorize::deployment::{CanaryOptions, DeploymentOptions, RollingOptions};
 
 /// Base URL for API calls
 fn get_api_url(url: &str) -> String {
     format!("{}/api/v1", url.trim_end_matches('/'))
 }
 
+/// Message from an API error body (`{ "error": { "code", "message", "details" } }`)
+fn error_message(error: &serde_json::Value) -> &str {
+    error["error"]["message"].as_str().unwrap_or("Unknown error")
+}
+
+/// Parse a `key=value` tag argument
+pub fn parse_tag(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("invalid tag '{}', expected key=value", s)),
+    }
+}
+
 /// CLI client for Vectorize API
 pub struct CliClient {
     client: Client,
@@ -23,10 +40,17 @@ pub struct CliClient {
 }
 
 impl CliClient {
-    pub fn new(base_url: &str) -> Self {
+    /// Create a client that identifies itself as `user` for audit logging
+    pub fn new(base_url: &str, user: &str) -> Self {
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(user) {
+            headers.insert(vectorize::api::audit::CLI_USER_HEADER, value);
+        }
+        
         Self {
             client: Client::builder()
                 .timeout(Duration::from_secs(30))
+                .default_headers(headers)
                 .build()
                 .unwrap_or_default(),
             base_url: get_api_url(base_url),
@@ -42,7 +66,7 @@ impl CliClient {
 pub enum AgentCommands {
     /// List all registered agents
     List {
-        /// Output format (json, table)
+        /// Output format (json, ndjson, table)
         #[arg(short, long, default_value = "table")]
         format: String,
     },
@@ -59,9 +83,29 @@ pub enum AgentCommands {
         /// Agent URL (Vector API endpoint)
         #[arg(short, long)]
         url: String,
-        /// Worker group ID to assign
+        /// Worker group to assign, by ID or name
         #[arg(short, long)]
         group: Option<String>,
+        /// Create the group if it doesn't exist
+        #[arg(long, requires = "group")]
+        auto_group: bool,
+        /// Tag to apply, as key=value (repeatable)
+        #[arg(long = "tag", value_parser = parse_tag)]
+        tags: Vec<(String, String)>,
+    },
+    /// Move an agent to another group, keeping its history
+    Move {
+        /// Agent ID
+        id: String,
+        /// Worker group ID to move to
+        #[arg(short, long, required_unless_present = "unassign")]
+        group: Option<String>,
+        /// Remove the agent from its group instead
+        #[arg(long, conflicts_with = "group")]
+        unassign: bool,
+        /// Deploy the group's current config to the agent after moving
+        #[arg(long)]
+        deploy: bool,
     },
     /// Delete an agent
     Delete {
@@ -76,6 +120,25 @@ pub enum AgentCommands {
 impl AgentCommands {
     pub async fn execute(&self, client: &CliClient) -> anyhow::Result<()> {
         match self {
+            AgentCommands::List { format } if format == "ndjson" => {
+                // Streamed by the server, so print agents as they arrive
+                let mut resp = client.client
+                    .get(format!("{}/agents", client.base_url))
+                    .header(reqwest::header::ACCEPT, "application/x-ndjson")
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                
+                let mut pending = Vec::new();
+                while let Some(chunk) = resp.chunk().await? {
+                    pending.extend_from_slice(&chunk);
+                    while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+                        let line: Vec<u8> = pending.drain(..=end).collect();
+                        println!("{}", String::from_utf8_lossy(&line[..end]));
+                    }
+                }
+                Ok(())
+            }
             AgentCommands::List { format } => {
                 let resp = client.client
                     .get(format!("{}/agents", client.base_url))
@@ -111,17 +174,24 @@ impl AgentCommands {
                     println!("{}", serde_json::to_string_pretty(&agent)?);
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
-            AgentCommands::Register { name, url, group } => {
+            AgentCommands::Register { name, url, group, auto_group, tags } => {
                 let mut body = json!({
                     "name": name,
                     "url": url,
                 });
                 if let Some(g) = group {
                     body["group_id"] = json!(g);
+                    body["auto_group"] = json!(auto_group);
+                }
+                if !tags.is_empty() {
+                    let tags: serde_json::Map<String, serde_json::Value> = tags.iter()
+                        .map(|(k, v)| (k.clone(), json!(v)))
+                        .collect();
+                    body["tags"] = serde_json::Value::Object(tags);
                 }
                 
                 let resp = client.client
@@ -136,7 +206,26 @@ impl AgentCommands {
                     println!("ID: {}", result["agent"]["id"].as_str().unwrap_or("-"));
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
+                }
+                Ok(())
+            }
+            AgentCommands::Move { id, group, unassign: _, deploy } => {
+                let resp = client.client
+                    .patch(format!("{}/agents/{}", client.base_url, id))
+                    .json(&json!({ "group_id": group, "deploy": deploy }))
+                    .send()
+                    .await?;
+                
+                if resp.status().is_success() {
+                    let result: serde_json::Value = resp.json().await?;
+                    println!("{}", result["message"].as_str().unwrap_or("Agent moved."));
+                    if let Some(deployment_id) = result["deployment_id"].as_str() {
+                        println!("Deployment: {}", deployment_id);
+                    }
+                } else {
+                    let error: serde_json::Value = resp.json().await?;
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -155,7 +244,7 @@ impl AgentCommands {
                     println!("Agent {} deleted successfully.", id);
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -194,6 +283,33 @@ pub enum GroupCommands {
         /// Require approval for deployments
         #[arg(long)]
         requires_approval: bool,
+        #[command(flatten)]
+        defaults: DeploymentDefaultsArgs,
+    },
+    /// Update a worker group's settings
+    Update {
+        /// Group ID
+        id: String,
+        /// New group name
+        #[arg(short, long)]
+        name: Option<String>,
+        /// New description
+        #[arg(short, long)]
+        description: Option<String>,
+        /// Deployment strategy (basic, rolling, canary)
+        #[arg(short, long)]
+        strategy: Option<String>,
+        /// Require approval for deployments
+        #[arg(long)]
+        requires_approval: Option<bool>,
+        #[command(flatten)]
+        defaults: DeploymentDefaultsArgs,
+        /// Remove the group's default deployment options
+        #[arg(long, conflicts_with_all = [
+            "batch_size", "batch_percentage", "batch_delay", "max_failures",
+            "canary_percentage", "canary_wait", "auto_promote", "per_agent_timeout",
+        ])]
+        clear_deployment_defaults: bool,
     },
     /// Delete a worker group
     Delete {
@@ -208,6 +324,101 @@ pub enum GroupCommands {
         /// Group ID
         id: String,
     },
+    /// Create a new group with another group's settings and config
+    Clone {
+        /// Group ID to clone
+        id: String,
+        /// Name of the new group
+        #[arg(short, long)]
+        name: String,
+        /// Description (defaults to the source group's)
+        #[arg(short, long)]
+        description: Option<String>,
+        /// Move the source group's agents to the new group
+        #[arg(long)]
+        move_agents: bool,
+    },
+}
+
+/// Default deployment options for a group
+///
+/// Deployments to the group use these unless they pass options of their own.
+#[derive(Args, Debug)]
+pub struct DeploymentDefaultsArgs {
+    /// Agents per rolling batch
+    #[arg(long, conflicts_with = "batch_percentage")]
+    batch_size: Option<u32>,
+    /// Rolling batch size as a percentage of the agents
+    #[arg(long)]
+    batch_percentage: Option<u32>,
+    /// Seconds to wait between rolling batches
+    #[arg(long)]
+    batch_delay: Option<u64>,
+    /// Failures allowed before a rolling deployment stops
+    #[arg(long)]
+    max_failures: Option<u32>,
+    /// Percentage of agents in the canary
+    #[arg(long)]
+    canary_percentage: Option<u32>,
+    /// Seconds to wait before promoting the canary
+    #[arg(long)]
+    canary_wait: Option<u64>,
+    /// Promote the canary automatically after the wait
+    #[arg(long)]
+    auto_promote: Option<bool>,
+    /// Timeout for each agent push in seconds
+    #[arg(long)]
+    per_agent_timeout: Option<u64>,
+}
+
+impl DeploymentDefaultsArgs {
+    /// Whether no option was given
+    fn is_empty(&self) -> bool {
+        self.apply(DeploymentOptions::default()).is_none()
+    }
+    
+    /// `base` with the given options applied, or `None` if none were given
+    fn apply(&self, mut base: DeploymentOptions) -> Option<DeploymentOptions> {
+        let mut changed = false;
+        
+        if self.batch_size.is_some() || self.batch_percentage.is_some()
+            || self.batch_delay.is_some() || self.max_failures.is_some()
+        {
+            let rolling = base.rolling.get_or_insert_with(RollingOptions::default);
+            if self.batch_size.is_some() || self.batch_percentage.is_some() {
+                rolling.batch_size = self.batch_size;
+                rolling.batch_percentage = self.batch_percentage;
+            }
+            if let Some(delay) = self.batch_delay {
+                rolling.batch_delay_secs = delay;
+            }
+            if let Some(max_failures) = self.max_failures {
+                rolling.max_failures = max_failures;
+            }
+            changed = true;
+        }
+        
+        if self.canary_percentage.is_some() || self.canary_wait.is_some() || self.auto_promote.is_some() {
+            let canary = base.canary.get_or_insert_with(CanaryOptions::default);
+            if let Some(percentage) = self.canary_percentage {
+                canary.canary_percentage = percentage;
+            }
+            if let Some(wait) = self.canary_wait {
+                canary.canary_wait_secs = wait;
+            }
+            if let Some(auto_promote) = self.auto_promote {
+                canary.auto_promote = auto_promote;
+            }
+            changed = true;
+        }
+        
+        if let Some(timeout) = self.per_agent_timeout {
+            base.per_agent_timeout_secs = timeout;
+            changed = true;
+        }
+        
+        changed.then_some(base)
+    }
 }
 
 impl GroupCommands {
@@ -249,16 +460,17 @@ impl GroupCommands {
                     println!("{}", serde_json::to_string_pretty(&group)?);
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
-            GroupCommands::Create { name, description, strategy, requires_approval } => {
+            GroupCommands::Create { name, description, strategy, requires_approval, defaults } => {
                 let body = json!({
                     "name": name,
                     "description": description,
                     "deployment_strategy": strategy,
                     "requires_approval": requires_approval,
+                    "default_deployment_options": defaults.apply(DeploymentOptions::default()),
                 });
                 
                 let resp = client.client
@@ -273,7 +485,59 @@ impl GroupCommands {
                     println!("ID: {}", result["id"].as_str().unwrap_or("-"));
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
+                }
+                Ok(())
+            }
+            GroupCommands::Update {
+                id, name, description, strategy, requires_approval, defaults, clear_deployment_defaults,
+            } => {
+                let mut body = json!({
+                    "name": name,
+                    "description": description,
+                    "deployment_strategy": strategy,
+                    "requires_approval": requires_approval,
+                });
+                
+                if *clear_deployment_defaults {
+                    body["default_deployment_options"] = serde_json::Value::Null;
+                } else if !defaults.is_empty() {
+                    // Flags adjust the group's current defaults rather than replacing them
+                    let resp = client.client
+                        .get(format!("{}/groups/{}", client.base_url, id))
+                        .send()
+                        .await?;
+                    if !resp.status().is_success() {
+                        let error: serde_json::Value = resp.json().await?;
+                        eprintln!("Error: {}", error_message(&error));
+                        return Ok(());
+                    }
+                    let group: serde_json::Value = resp.json().await?;
+                    let current = serde_json::from_value(group["default_deployment_options"].clone())
+                        .unwrap_or_default();
+                    body["default_deployment_options"] = json!(defaults.apply(current));
+                }
+                
+                // Leave unset fields unchanged
+                if let Some(fields) = body.as_object_mut() {
+                    fields.retain(|key, value| !value.is_null() || key == "default_deployment_options");
+                }
+                
+                let resp = client.client
+                    .put(format!("{}/groups/{}", client.base_url, id))
+                    .json(&body)
+                    .send()
+                    .await?;
+                
+                if resp.status().is_success() {
+                    let group: serde_json::Value = resp.json().await?;
+                    println!("Group {} updated.", id);
+                    if !group["default_deployment_options"].is_null() {
+                        println!("Default deployment options: {}", group["default_deployment_options"]);
+                    }
+                } else {
+                    let error: serde_json::Value = resp.json().await?;
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -292,7 +556,7 @@ impl GroupCommands {
                     println!("Group {} deleted successfully.", id);
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -316,7 +580,34 @@ impl GroupCommands {
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
+                }
+                Ok(())
+            }
+            GroupCommands::Clone { id, name, description, move_agents } => {
+                let body = json!({
+                    "name": name,
+                    "description": description,
+                    "move_agents": move_agents,
+                });
+                
+                let resp = client.client
+                    .post(format!("{}/groups/{}/clone", client.base_url, id))
+                    .json(&body)
+                    .send()
+                    .await?;
+                
+                if resp.status().is_success() {
+                    let result: serde_json::Value = resp.json().await?;
+                    println!("Group cloned successfully!");
+                    println!("ID: {}", result["id"].as_str().unwrap_or("-"));
+                    println!("Config version: {}", result["current_config_version"].as_str().unwrap_or("-"));
+                    if *move_agents {
+                        println!("Agents moved: {}", result["agents_moved"].as_u64().unwrap_or(0));
+                    }
+                } else {
+                    let error: serde_json::Value = resp.json().await?;
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -345,14 +636,20 @@ pub enum ConfigCommands {
         /// Path to config file
         #[arg(short, long)]
         file: String,
+        /// Version the file was based on; refuse to overwrite newer changes
+        #[arg(long)]
+        base_version: Option<String>,
     },
     /// Validate a configuration
     Validate {
         /// Path to config file (or - for stdin)
         file: String,
-        /// Validation mode (quick, syntax, full)
+        /// Validation mode (quick, syntax, full, lint)
         #[arg(short, long, default_value = "full")]
         mode: String,
+        /// Apply production-only lint rules (lint mode)
+        #[arg(long)]
+        production: bool,
     },
     /// Show configuration history
     History {
@@ -361,6 +658,12 @@ pub enum ConfigCommands {
         /// Number of entries to show
         #[arg(short, long, default_value = "10")]
         limit: usize,
+        /// Only show commits whose message or config diff contains this text
+        #[arg(short, long)]
+        search: Option<String>,
+        /// With --search, match commit messages only (faster on large histories)
+        #[arg(long, requires = "search")]
+        message_only: bool,
     },
     /// Rollback to a previous version
     Rollback {
@@ -368,6 +671,21 @@ pub enum ConfigCommands {
         group_id: String,
         /// Version to rollback to
         version: String,
+        /// Skip the preview and confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Sync the config repository with a git remote (pull then push)
+    Sync {
+        /// Remote name
+        #[arg(short, long, default_value = "origin")]
+        remote: String,
+        /// Branch to sync (default: main)
+        #[arg(short, long)]
+        branch: Option<String>,
+        /// Only fetch and show what a pull would bring in
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
@@ -392,16 +710,16 @@ impl ConfigCommands {
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
-            ConfigCommands::Set { group_id, file } => {
+            ConfigCommands::Set { group_id, file, base_version } => {
                 let config = std::fs::read_to_string(file)?;
                 
                 let resp = client.client
                     .put(format!("{}/groups/{}/config", client.base_url, group_id))
-                    .json(&json!({ "config": config }))
+                    .json(&json!({ "config": config, "base_version": base_version }))
                     .send()
                     .await?;
                 
@@ -413,13 +731,15 @@ impl ConfigCommands {
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["message"].as_str()
-                        .or(error["error"].as_str())
-                        .unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
+                    if let Some(diff) = error["error"]["details"]["diff"].as_str() {
+                        println!("Current version: {}", error["error"]["details"]["current_version"].as_str().unwrap_or(""));
+                        println!("{}", diff);
+                    }
                 }
                 Ok(())
             }
-            ConfigCommands::Validate { file, mode } => {
+            ConfigCommands::Validate { file, mode, production } => {
                 let config = if file == "-" {
                     use std::io::Read;
                     let mut buffer = String::new();
@@ -429,6 +749,32 @@ impl ConfigCommands {
                     std::fs::read_to_string(file)?
                 };
                 
+                // Lint is advisory: print warnings, never fail
+                if mode == "lint" {
+                    let result: serde_json::Value = client.client
+                        .post(format!("{}/validate/lint", client.base_url))
+                        .json(&json!({ "config": config, "production": production }))
+                        .send()
+                        .await?
+                        .json()
+                        .await?;
+                    
+                    let warnings = result["warnings"].as_array().cloned().unwrap_or_default();
+                    if warnings.is_empty() {
+                        println!("✓ No lint warnings");
+                    } else {
+                        println!("Lint warnings:");
+                        for warning in &warnings {
+                            println!(
+                                "  - [{}] {}",
+                                warning["code"].as_str().unwrap_or("-"),
+                                warning["message"].as_str().unwrap_or("-")
+                            );
+                        }
+                    }
+                    return Ok(());
+                }
+                
                 // Use /validate or /validate/quick based on mode
                 let endpoint = if mode == "quick" {
                     format!("{}/validate/quick", client.base_url)
@@ -475,7 +821,41 @@ impl ConfigCommands {
                 
                 Ok(())
             }
-            ConfigCommands::History { group_id, limit } => {
+            ConfigCommands::History { group_id, limit, search: Some(query), message_only } => {
+                let resp = client.client
+                    .get(format!("{}/groups/{}/history/search", client.base_url, group_id))
+                    .query(&[
+                        ("q", query.clone()),
+                        ("limit", limit.to_string()),
+                        ("message_only", message_only.to_string()),
+                    ])
+                    .send()
+                    .await?;
+                
+                if resp.status().is_success() {
+                    let matches: Vec<serde_json::Value> = resp.json().await?;
+                    if matches.is_empty() {
+                        println!("No commits match '{}'.", query);
+                        return Ok(());
+                    }
+                    for entry in matches {
+                        println!("{:<12} {:<20} {}",
+                            entry["hash"].as_str().unwrap_or("-").chars().take(8).collect::<String>(),
+                            entry["timestamp"].as_str().unwrap_or("-"),
+                            entry["message"].as_str().unwrap_or("-").lines().next().unwrap_or("-"),
+                        );
+                        println!("    {}: {}",
+                            entry["matched_in"].as_str().unwrap_or("-"),
+                            entry["snippet"].as_str().unwrap_or(""),
+                        );
+                    }
+                } else {
+                    let error: serde_json::Value = resp.json().await?;
+                    eprintln!("Error: {}", error_message(&error));
+                }
+                Ok(())
+            }
+            ConfigCommands::History { group_id, limit, .. } => {
                 let resp = client.client
                     .get(format!("{}/groups/{}/history?limit={}", client.base_url, group_id, limit))
                     .send()
@@ -494,11 +874,109 @@ impl ConfigCommands {
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
+                }
+                Ok(())
+            }
+            ConfigCommands::Sync { remote, branch, dry_run: true } => {
+                let mut request = client.client
+                    .get(format!("{}/git/remotes/{}/incoming", client.base_url, remote));
+                if let Some(branch) = branch {
+                    request = request.query(&[("branch", branch)]);
+                }
+                let resp = request.send().await?;
+                
+                if resp.status().is_success() {
+                    let incoming: serde_json::Value = resp.json().await?;
+                    println!("{}/{}: {} ahead, {} behind",
+                        remote,
+                        incoming["branch"].as_str().unwrap_or("-"),
+                        incoming["ahead"].as_u64().unwrap_or(0),
+                        incoming["behind"].as_u64().unwrap_or(0),
+                    );
+                    
+                    let commits = incoming["commits"].as_array().cloned().unwrap_or_default();
+                    if commits.is_empty() {
+                        println!("No incoming commits.");
+                    } else {
+                        println!("\nIncoming commits:");
+                        for commit in commits {
+                            println!("  {} {}",
+                                commit["short_hash"].as_str().unwrap_or("-"),
+                                commit["message"].as_str().unwrap_or("-").lines().next().unwrap_or("-"),
+                            );
+                        }
+                    }
+                    
+                    if incoming["has_changes"].as_bool().unwrap_or(false) {
+                        println!("\nChanges from local HEAD:\n");
+                        println!("{}", incoming["diff"].as_str().unwrap_or(""));
+                    }
+                } else {
+                    let error: serde_json::Value = resp.json().await?;
+                    eprintln!("Error: {}", error_message(&error));
+                }
+                Ok(())
+            }
+            ConfigCommands::Sync { remote, branch, dry_run: false } => {
+                let resp = client.client
+                    .post(format!("{}/git/remotes/{}/sync", client.base_url, remote))
+                    .json(&json!({ "branch": branch }))
+                    .send()
+                    .await?;
+                
+                if resp.status().is_success() {
+                    let result: serde_json::Value = resp.json().await?;
+                    if result["pull_success"].as_bool().unwrap_or(false) {
+                        println!("Pulled from {}.", remote);
+                    } else if let Some(error) = result["pull_error"].as_str() {
+                        eprintln!("Pull failed: {}", error);
+                    }
+                    if result["push_success"].as_bool().unwrap_or(false) {
+                        println!("Pushed to {}.", remote);
+                    } else if let Some(error) = result["push_error"].as_str() {
+                        eprintln!("Push failed: {}", error);
+                    }
+                } else {
+                    let error: serde_json::Value = resp.json().await?;
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
-            ConfigCommands::Rollback { group_id, version } => {
+            ConfigCommands::Rollback { group_id, version, yes } => {
+                if !*yes {
+                    let resp = client.client
+                        .get(format!("{}/groups/{}/rollback/preview", client.base_url, group_id))
+                        .query(&[("version", version)])
+                        .send()
+                        .await?;
+                    
+                    if !resp.status().is_success() {
+                        let error: serde_json::Value = resp.json().await?;
+                        eprintln!("Error: {}", error_message(&error));
+                        return Ok(());
+                    }
+                    
+                    let preview: serde_json::Value = resp.json().await?;
+                    if !preview["has_changes"].as_bool().unwrap_or(true) {
+                        println!("Group {} is already at version {}; nothing to roll back.", group_id, version);
+                        return Ok(());
+                    }
+                    
+                    println!("Rolling back will apply these changes:\n");
+                    println!("{}", preview["diff"].as_str().unwrap_or(""));
+                    use std::io::Write;
+                    print!("Roll back group {} to {}? [y/N] ", group_id, version);
+                    std::io::stdout().flush()?;
+                    
+                    let mut answer = String::new();
+                    std::io::stdin().read_line(&mut answer)?;
+                    if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                        println!("Rollback cancelled.");
+                        return Ok(());
+                    }
+                }
+                
                 let resp = client.client
                     .post(format!("{}/groups/{}/rollback", client.base_url, group_id))
                     .json(&json!({ "version": version }))
@@ -513,9 +991,7 @@ impl ConfigCommands {
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["message"].as_str()
-                        .or(error["error"].as_str())
-                        .unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -532,13 +1008,24 @@ pub enum DeployCommands {
     /// Create a new deployment
     Create {
         /// Group ID to deploy to
-        group_id: String,
+        #[arg(required_unless_present = "agent")]
+        group_id: Option<String>,
+        /// Test-deploy to this one agent only, using its group's config
+        #[arg(long, conflicts_with_all = ["group_id", "at", "idempotency_key"])]
+        agent: Option<String>,
         /// Config version to deploy (defaults to current)
         #[arg(short, long)]
         version: Option<String>,
         /// Force deployment even with version mismatch
         #[arg(long)]
         force: bool,
+        /// Schedule the deployment for a later time (RFC 3339, e.g. 2024-06-01T02:00:00Z)
+        #[arg(long)]
+        at: Option<String>,
+        /// Key identifying this deployment intent; retrying with the same key
+        /// within 24 hours returns the original deployment instead of a new one
+        #[arg(long)]
+        idempotency_key: Option<String>,
     },
     /// Get deployment status
     Status {
@@ -552,6 +1039,18 @@ pub enum DeployCommands {
         /// Number of entries to show
         #[arg(short, long, default_value = "10")]
         limit: i64,
+        /// Number of entries to skip
+        #[arg(long, default_value = "0")]
+        offset: i64,
+        /// Only show deployments with this status (e.g. failed, completed)
+        #[arg(long)]
+        status: Option<String>,
+        /// Only show deployments created on or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only show deployments created up to this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        until: Option<String>,
     },
     /// Approve a pending deployment
     Approve {
@@ -570,6 +1069,9 @@ pub enum DeployCommands {
     Cancel {
         /// Deployment ID
         id: String,
+        /// Also revert agents already updated to the version they ran before
+        #[arg(long)]
+        revert: bool,
     },
     /// Check version consistency for a group
     Versions {
@@ -581,29 +1083,62 @@ pub enum DeployCommands {
 impl DeployCommands {
     pub async fn execute(&self, client: &CliClient, username: &str) -> anyhow::Result<()> {
         match self {
-            DeployCommands::Create { group_id, version, force } => {
+            DeployCommands::Create { agent: Some(agent_id), version, .. } => {
+                let resp = client.client
+                    .post(format!("{}/agents/{}/deploy", client.base_url, agent_id))
+                    .json(&json!({ "config_version": version }))
+                    .send()
+                    .await?;
+                
+                if resp.status().is_success() {
+                    let result: serde_json::Value = resp.json().await?;
+                    println!("Test deployment to agent {}", agent_id);
+                    println!("ID: {}", result["deployment_id"].as_str().unwrap_or("-"));
+                    println!("Status: {}", result["status"].as_str().unwrap_or("-"));
+                    println!("{}", result["message"].as_str().unwrap_or("-"));
+                } else {
+                    let error: serde_json::Value = resp.json().await?;
+                    eprintln!("Error: {}", error_message(&error));
+                }
+                Ok(())
+            }
+            DeployCommands::Create { group_id, agent: None, version, force, at, idempotency_key } => {
+                let group_id = group_id.as_deref().unwrap_or_default();
                 let mut body = json!({ "force": force });
                 if let Some(v) = version {
                     body["config_version"] = json!(v);
                 }
+                if let Some(at) = at {
+                    body["scheduled_at"] = json!(at);
+                }
                 
-                let resp = client.client
+                let mut req = client.client
                     .post(format!("{}/groups/{}/deployments", client.base_url, group_id))
-                    .json(&body)
-                    .send()
-                    .await?;
+                    .json(&body);
+                if let Some(key) = idempotency_key {
+                    req = req.header(vectorize::api::deployments::IDEMPOTENCY_KEY_HEADER, key);
+                }
+                let resp = req.send().await?;
                 
                 if resp.status().is_success() {
+                    let replayed = resp.headers().contains_key(vectorize::api::deployments::IDEMPOTENT_REPLAYED_HEADER);
                     let result: serde_json::Value = resp.json().await?;
-                    println!("Deployment created!");
+                    if replayed {
+                        println!("Deployment already created with this idempotency key");
+                    } else {
+                        println!("Deployment created!");
+                    }
                     println!("ID: {}", result["deployment_id"].as_str().unwrap_or("-"));
                     println!("Status: {}", result["status"].as_str().unwrap_or("-"));
+                    if result["status"] == "scheduled" {
+                        println!("{}", result["message"].as_str().unwrap_or("-"));
+                    }
                     if result["requires_approval"].as_bool().unwrap_or(false) {
                         println!("\nNote: This deployment requires approval before execution.");
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -626,18 +1161,32 @@ impl DeployCommands {
                         println!("  Total: {}", stats["total"].as_i64().unwrap_or(0));
                         println!("  Completed: {}", stats["completed"].as_i64().unwrap_or(0));
                         println!("  Failed: {}", stats["failed"].as_i64().unwrap_or(0));
+                        println!("  Timed Out: {}", stats["timed_out"].as_i64().unwrap_or(0));
                         println!("  In Progress: {}", stats["in_progress"].as_i64().unwrap_or(0));
+                        println!("  Cancelled: {}", stats["cancelled"].as_i64().unwrap_or(0));
                         println!("  Pending: {}", stats["pending"].as_i64().unwrap_or(0));
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
-            DeployCommands::List { group_id, limit } => {
+            DeployCommands::List { group_id, limit, offset, status, since, until } => {
+                let mut query = vec![("limit", limit.to_string()), ("offset", offset.to_string())];
+                if let Some(status) = status {
+                    query.push(("status", status.clone()));
+                }
+                if let Some(since) = since {
+                    query.push(("since", since.clone()));
+                }
+                if let Some(until) = until {
+                    query.push(("until", until.clone()));
+                }
+                
                 let resp = client.client
-                    .get(format!("{}/groups/{}/deployments?limit={}", client.base_url, group_id, limit))
+                    .get(format!("{}/groups/{}/deployments", client.base_url, group_id))
+                    .query(&query)
                     .send()
                     .await?;
                 
@@ -657,10 +1206,15 @@ impl DeployCommands {
                                 dep["created_at"].as_str().unwrap_or("-"),
                             );
                         }
+                        
+                        if let Some(total) = result["total"].as_i64() {
+                            println!();
+                            println!("Showing {} of {} deployment(s)", deps.len(), total);
+                        }
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -675,7 +1229,7 @@ impl DeployCommands {
                     println!("Deployment {} approved and started.", id);
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -695,21 +1249,34 @@ impl DeployCommands {
                     println!("Deployment {} rejected.", id);
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
-            DeployCommands::Cancel { id } => {
+            DeployCommands::Cancel { id, revert } => {
                 let resp = client.client
                     .post(format!("{}/deployments/{}/cancel", client.base_url, id))
+                    .query(&[("revert", revert)])
                     .send()
                     .await?;
                 
-                if resp.status().is_success() {
+                if resp.status().is_success() && *revert {
+                    let result: serde_json::Value = resp.json().await?;
+                    println!("Deployment {} aborted.", id);
+                    println!("{}", result["message"].as_str().unwrap_or("-"));
+                    for (label, key) in [("Revert failed", "revert_failed"), ("No previous version", "not_reverted")] {
+                        let agents: Vec<&str> = result[key].as_array()
+                            .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+                            .unwrap_or_default();
+                        if !agents.is_empty() {
+                            println!("{}: {}", label, agents.join(", "));
+                        }
+                    }
+                } else if resp.status().is_success() {
                     println!("Deployment {} cancelled.", id);
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
                 }
                 Ok(())
             }
@@ -740,7 +1307,100 @@ impl DeployCommands {
                     }
                 } else {
                     let error: serde_json::Value = resp.json().await?;
-                    eprintln!("Error: {}", error["error"].as_str().unwrap_or("Unknown error"));
+                    eprintln!("Error: {}", error_message(&error));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Database Commands
+// =============================================================================
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommands {
+    /// Back up the database (safe while the server is running)
+    Backup {
+        /// Destination file
+        path: PathBuf,
+    },
+    /// Restore the database from a backup (the server must be stopped)
+    Restore {
+        /// Backup file to restore
+        path: PathBuf,
+    },
+    /// Inspect or roll back schema migrations
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MigrateCommands {
+    /// Show which migrations are applied
+    Status,
+    /// Roll back a migration and every migration applied after it
+    Rollback {
+        /// Migration version (e.g. 006_agent_soft_delete)
+        version: String,
+    },
+}
+
+impl DbCommands {
+    pub async fn execute(&self, db_path: &Path) -> anyhow::Result<()> {
+        match self {
+            DbCommands::Backup { path } => {
+                let db = Database::open_existing(db_path).await?;
+                let result = db.backup(path).await;
+                db.close().await;
+                result?;
+                
+                println!("✓ Database backed up to {}", path.display());
+                Ok(())
+            }
+            DbCommands::Restore { path } => {
+                Database::restore(path, db_path).await?;
+                println!("✓ Database restored from {}", path.display());
+                Ok(())
+            }
+            DbCommands::Migrate { command } => {
+                let db = Database::open_existing(db_path).await?;
+                
+                match command {
+                    MigrateCommands::Status => {
+                        let status = db.migration_status().await;
+                        db.close().await;
+                        
+                        println!("{:<28} {:<10} {:<20}", "VERSION", "STATUS", "APPLIED");
+                        println!("{}", "-".repeat(60));
+                        for m in status? {
+                            let state = match (&m.applied_at, m.checksum_matches) {
+                                (None, _) => "pending",
+                                (Some(_), true) => "applied",
+                                (Some(_), false) => "modified",
+                            };
+                            println!("{:<28} {:<10} {:<20}",
+                                m.version,
+                                state,
+                                m.applied_at.as_deref().unwrap_or("-"),
+                            );
+                        }
+                    }
+                    MigrateCommands::Rollback { version } => {
+                        let result = db.rollback_migration(version).await;
+                        db.close().await;
+                        
+                        let rolled_back = result?;
+                        if rolled_back.is_empty() {
+                            println!("Nothing to roll back, {} is not applied", version);
+                        }
+                        for v in rolled_back {
+                            println!("✓ Rolled back {}", v);
+                        }
+                    }
                 }
                 Ok(())
             }
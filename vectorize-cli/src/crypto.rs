@@ -0,0 +1,160 @@
+//! Encryption at rest for stored secrets
+//!
+//! Secrets such as OIDC client secrets are sealed with AES-256-GCM under a
+//! key from `VECTORIZE_ENCRYPTION_KEY` (32 bytes, base64-encoded) before
+//! they are written to the database. Every value gets a fresh random nonce;
+//! the stored form is `v1:<base64(nonce || ciphertext)>`.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use thiserror::Error;
+
+/// Environment variable holding the base64-encoded encryption key
+pub const ENCRYPTION_KEY_ENV: &str = "VECTORIZE_ENCRYPTION_KEY";
+
+const PREFIX: &str = "v1:";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Secret encryption errors
+#[derive(Error, Debug)]
+pub enum CryptoError {
+    #[error("Invalid encryption key: {0}")]
+    InvalidKey(String),
+    
+    #[error("Malformed encrypted value")]
+    Malformed,
+    
+    #[error("Failed to decrypt secret (wrong key or corrupted value)")]
+    Decrypt,
+    
+    #[error("Failed to encrypt secret")]
+    Encrypt,
+}
+
+/// Seals and opens secrets stored in the database
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    /// Create a cipher from a raw 32-byte key
+    pub fn new(key: &[u8]) -> Result<Self, CryptoError> {
+        if key.len() != KEY_LEN {
+            return Err(CryptoError::InvalidKey(format!(
+                "expected {} bytes, got {}",
+                KEY_LEN,
+                key.len()
+            )));
+        }
+        let cipher = Aes256Gcm::new_from_slice(key)
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+        Ok(Self { cipher })
+    }
+    
+    /// Create a cipher from a base64-encoded key
+    pub fn from_base64(key: &str) -> Result<Self, CryptoError> {
+        let bytes = STANDARD.decode(key.trim())
+            .map_err(|e| CryptoError::InvalidKey(format!("not valid base64: {}", e)))?;
+        Self::new(&bytes)
+    }
+    
+    /// Read the key from `VECTORIZE_ENCRYPTION_KEY`, if set
+    pub fn from_env() -> Result<Option<Self>, CryptoError> {
+        match std::env::var(ENCRYPTION_KEY_ENV) {
+            Ok(key) if !key.trim().is_empty() => Self::from_base64(&key).map(Some),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Generate a new random key, base64-encoded
+    pub fn generate_key() -> String {
+        STANDARD.encode(Aes256Gcm::generate_key(&mut OsRng))
+    }
+    
+    /// Encrypt a secret for storage
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, CryptoError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| CryptoError::Encrypt)?;
+    
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", PREFIX, STANDARD.encode(sealed)))
+    }
+    
+    /// Whether a stored value looks like the output of [`SecretCipher::encrypt`]
+    pub fn is_sealed(value: &str) -> bool {
+        value.starts_with(PREFIX)
+    }
+    
+    /// Decrypt a value produced by [`SecretCipher::encrypt`]
+    pub fn decrypt(&self, sealed: &str) -> Result<String, CryptoError> {
+        let encoded = sealed.strip_prefix(PREFIX).ok_or(CryptoError::Malformed)?;
+        let bytes = STANDARD.decode(encoded).map_err(|_| CryptoError::Malformed)?;
+        if bytes.len() < NONCE_LEN {
+            return Err(CryptoError::Malformed);
+        }
+    
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self.cipher.decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CryptoError::Decrypt)?;
+        String::from_utf8(plaintext).map_err(|_| CryptoError::Decrypt)
+    }
+}
+
+impl std::fmt::Debug for SecretCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretCipher").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn cipher() -> SecretCipher {
+        SecretCipher::from_base64(&SecretCipher::generate_key()).unwrap()
+    }
+    
+    #[test]
+    fn test_encrypt_decrypt_round_trip() {
+        let cipher = cipher();
+    
+        let sealed = cipher.encrypt("client-secret-value").unwrap();
+        assert!(sealed.starts_with(PREFIX));
+        assert!(!sealed.contains("client-secret-value"));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "client-secret-value");
+    
+        // A fresh nonce per value means equal secrets don't look equal at rest
+        assert_ne!(cipher.encrypt("client-secret-value").unwrap(), sealed);
+    
+        assert_eq!(cipher.decrypt(&cipher.encrypt("").unwrap()).unwrap(), "");
+    }
+    
+    #[test]
+    fn test_decrypt_rejects_wrong_key_and_tampering() {
+        let key = cipher();
+        let sealed = key.encrypt("client-secret-value").unwrap();
+    
+        assert!(matches!(cipher().decrypt(&sealed), Err(CryptoError::Decrypt)));
+    
+        let mut bytes = STANDARD.decode(&sealed[PREFIX.len()..]).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        let tampered = format!("{}{}", PREFIX, STANDARD.encode(bytes));
+        assert!(matches!(key.decrypt(&tampered), Err(CryptoError::Decrypt)));
+    
+        assert!(matches!(key.decrypt("plaintext"), Err(CryptoError::Malformed)));
+        assert!(matches!(key.decrypt("v1:AAAA"), Err(CryptoError::Malformed)));
+    }
+    
+    #[test]
+    fn test_key_validation() {
+        assert!(SecretCipher::new(&[0u8; 32]).is_ok());
+        assert!(matches!(SecretCipher::new(&[0u8; 16]), Err(CryptoError::InvalidKey(_))));
+        assert!(matches!(SecretCipher::from_base64("not base64!"), Err(CryptoError::InvalidKey(_))));
+    }
+}
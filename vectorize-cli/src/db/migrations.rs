@@ -1,64 +1,260 @@
 //! Database migrations for Vectorize
 //!
 //! Migrations are run in order on startup. Each migration is idempotent.
+//!
+//! Applied migrations are tracked in `schema_migrations` along with a checksum
+//! of their SQL, so a migration edited after it shipped is caught on startup
+//! instead of silently diverging between environments. Every migration has
+//! down SQL so a bad deploy can be rolled back.
+//!
+//! Migration SQL is written for SQLite and translated by `dialect` when
+//! built with the `postgres` feature.
 
-use sqlx::SqlitePool;
+use super::{DbPool, DbTransaction};
+use serde::Serialize;
 use tracing::info;
 
-/// Run all migrations in order
-pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    // Create migrations tracking table
+/// A schema migration with its forward and rollback SQL
+struct Migration {
+    version: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+/// All migrations, in the order they are applied
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: "001_initial_schema", up: MIGRATION_001_INITIAL_SCHEMA, down: MIGRATION_001_DOWN },
+    Migration { version: "002_audit_log", up: MIGRATION_002_AUDIT_LOG, down: MIGRATION_002_DOWN },
+    Migration { version: "003_worker_groups", up: MIGRATION_003_WORKER_GROUPS, down: MIGRATION_003_DOWN },
+    Migration { version: "004_deployments", up: MIGRATION_004_DEPLOYMENTS, down: MIGRATION_004_DOWN },
+    Migration { version: "005_agent_tags", up: MIGRATION_005_AGENT_TAGS, down: MIGRATION_005_DOWN },
+    Migration { version: "006_agent_soft_delete", up: MIGRATION_006_AGENT_SOFT_DELETE, down: MIGRATION_006_DOWN },
+    Migration { version: "007_api_key_roles", up: MIGRATION_007_API_KEY_ROLES, down: MIGRATION_007_DOWN },
+    Migration { version: "008_deployment_schedule", up: MIGRATION_008_DEPLOYMENT_SCHEDULE, down: MIGRATION_008_DOWN },
+    Migration { version: "009_idempotency_keys", up: MIGRATION_009_IDEMPOTENCY_KEYS, down: MIGRATION_009_DOWN },
+    Migration { version: "010_agent_unique_url", up: MIGRATION_010_AGENT_UNIQUE_URL, down: MIGRATION_010_DOWN },
+    Migration { version: "011_sso_providers", up: MIGRATION_011_SSO_PROVIDERS, down: MIGRATION_011_DOWN },
+    Migration { version: "012_agent_tls", up: MIGRATION_012_AGENT_TLS, down: MIGRATION_012_DOWN },
+    Migration { version: "013_group_notification_channels", up: MIGRATION_013_GROUP_NOTIFICATION_CHANNELS, down: MIGRATION_013_DOWN },
+    Migration { version: "014_group_deployment_defaults", up: MIGRATION_014_GROUP_DEPLOYMENT_DEFAULTS, down: MIGRATION_014_DOWN },
+    Migration { version: "015_sso_sessions", up: MIGRATION_015_SSO_SESSIONS, down: MIGRATION_015_DOWN },
+    Migration { version: "016_group_freeze", up: MIGRATION_016_GROUP_FREEZE, down: MIGRATION_016_DOWN },
+    Migration { version: "017_group_auto_deregister", up: MIGRATION_017_GROUP_AUTO_DEREGISTER, down: MIGRATION_017_DOWN },
+    Migration { version: "018_builtin_role_permissions", up: MIGRATION_018_BUILTIN_ROLE_PERMISSIONS, down: MIGRATION_018_DOWN },
+    Migration { version: "019_idempotency_request_hash", up: MIGRATION_019_IDEMPOTENCY_REQUEST_HASH, down: MIGRATION_019_DOWN },
+];
+
+/// Names of all migrations known to this version
+pub fn migration_names() -> Vec<&'static str> {
+    MIGRATIONS.iter().map(|m| m.version).collect()
+}
+
+/// Status of a single migration
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub version: String,
+    /// When the migration was applied (None if pending)
+    pub applied_at: Option<String>,
+    /// False if the migration's SQL changed after it was applied
+    pub checksum_matches: bool,
+}
+
+/// Checksum of a migration's SQL (64-bit FNV-1a, hex encoded)
+///
+/// Computed over the SQLite source so it is identical on every backend.
+fn checksum(sql: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in sql.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Execute a migration's statements inside a transaction
+///
+/// SQL is split on semicolons, so migration comments must not contain them.
+async fn execute_script(tx: &mut DbTransaction<'_>, sql: &str) -> Result<(), sqlx::Error> {
+    let sql = dialect(sql);
+    for statement in sql.split(';').filter(|s| !s.trim().is_empty()) {
+        sqlx::query(statement)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Create the tracking table and carry over history from the legacy `_migrations` table
+async fn ensure_tracking_table(pool: &DbPool) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
-        CREATE TABLE IF NOT EXISTS _migrations (
-            id INTEGER PRIMARY KEY,
-            name TEXT NOT NULL UNIQUE,
-            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version TEXT PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            checksum TEXT NOT NULL
         )
         "#
     )
     .execute(pool)
     .await?;
     
-    // Define migrations
-    let migrations: Vec<(&str, &str)> = vec![
-        ("001_initial_schema", MIGRATION_001_INITIAL_SCHEMA),
-        ("002_audit_log", MIGRATION_002_AUDIT_LOG),
-        ("003_worker_groups", MIGRATION_003_WORKER_GROUPS),
-        ("004_deployments", MIGRATION_004_DEPLOYMENTS),
-    ];
+    // Databases created before checksums were tracked only recorded names
+    let legacy: Vec<(String, String)> = sqlx::query_as("SELECT name, applied_at FROM _migrations")
+        .fetch_all(pool)
+        .await
+        .unwrap_or_default();
+    
+    for (name, applied_at) in legacy {
+        if let Some(migration) = MIGRATIONS.iter().find(|m| m.version == name) {
+            sqlx::query(
+                r#"
+                INSERT INTO schema_migrations (version, applied_at, checksum)
+                VALUES ($1, $2, $3)
+                ON CONFLICT DO NOTHING
+                "#
+            )
+            .bind(migration.version)
+            .bind(&applied_at)
+            .bind(checksum(migration.up))
+            .execute(pool)
+            .await?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Applied migrations as (version, applied_at, checksum)
+async fn applied_migrations(pool: &DbPool) -> Result<Vec<(String, String, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT version, applied_at, checksum FROM schema_migrations ORDER BY version")
+        .fetch_all(pool)
+        .await
+}
+
+/// Run all migrations in order
+///
+/// Fails without applying anything if an already-applied migration's
+/// checksum no longer matches its SQL.
+pub async fn run_migrations(pool: &DbPool) -> Result<(), sqlx::Error> {
+    ensure_tracking_table(pool).await?;
+    
+    let applied = applied_migrations(pool).await?;
+    
+    // Drift detection
+    for (version, _, stored) in &applied {
+        if let Some(migration) = MIGRATIONS.iter().find(|m| m.version == version.as_str()) {
+            if checksum(migration.up) != *stored {
+                return Err(sqlx::Error::Configuration(
+                    format!(
+                        "Migration {} has changed since it was applied (checksum mismatch)",
+                        version
+                    )
+                    .into(),
+                ));
+            }
+        }
+    }
     
     // Run each migration if not already applied
-    for (name, sql) in migrations {
-        let applied: Option<(i64,)> = sqlx::query_as(
-            "SELECT id FROM _migrations WHERE name = ?"
-        )
-        .bind(name)
-        .fetch_optional(pool)
-        .await?;
+    for migration in MIGRATIONS {
+        if applied.iter().any(|(version, _, _)| version == migration.version) {
+            continue;
+        }
+        
+        info!("Applying migration: {}", migration.version);
         
-        if applied.is_none() {
-            info!("Applying migration: {}", name);
-            
-            // Execute the migration SQL
-            // Split by semicolons and execute each statement
-            for statement in sql.split(';').filter(|s| !s.trim().is_empty()) {
-                sqlx::query(statement)
-                    .execute(pool)
-                    .await?;
+        let mut tx = pool.begin().await?;
+        execute_script(&mut tx, migration.up).await?;
+        
+        // Record the migration
+        sqlx::query("INSERT INTO schema_migrations (version, checksum) VALUES ($1, $2)")
+            .bind(migration.version)
+            .bind(checksum(migration.up))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        
+        info!("Migration {} applied successfully", migration.version);
+    }
+    
+    Ok(())
+}
+
+/// Report every known migration and whether it has been applied
+pub async fn migration_status(pool: &DbPool) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    ensure_tracking_table(pool).await?;
+    let applied = applied_migrations(pool).await?;
+    
+    Ok(MIGRATIONS
+        .iter()
+        .map(|migration| {
+            let record = applied.iter().find(|(version, _, _)| version == migration.version);
+            MigrationStatus {
+                version: migration.version.to_string(),
+                applied_at: record.map(|(_, applied_at, _)| applied_at.clone()),
+                checksum_matches: record
+                    .map(|(_, _, stored)| *stored == checksum(migration.up))
+                    .unwrap_or(true),
             }
-            
-            // Record the migration
-            sqlx::query("INSERT INTO _migrations (name) VALUES (?)")
-                .bind(name)
-                .execute(pool)
-                .await?;
-            
-            info!("Migration {} applied successfully", name);
+        })
+        .collect())
+}
+
+/// Roll back `version` and every migration applied after it, newest first
+///
+/// Returns the versions that were rolled back.
+pub async fn rollback_migration(pool: &DbPool, version: &str) -> Result<Vec<String>, sqlx::Error> {
+    let position = MIGRATIONS
+        .iter()
+        .position(|m| m.version == version)
+        .ok_or_else(|| sqlx::Error::Configuration(format!("Unknown migration: {}", version).into()))?;
+    
+    ensure_tracking_table(pool).await?;
+    let applied = applied_migrations(pool).await?;
+    
+    let mut rolled_back = Vec::new();
+    for migration in MIGRATIONS[position..].iter().rev() {
+        if !applied.iter().any(|(v, _, _)| v == migration.version) {
+            continue;
         }
+        
+        info!("Rolling back migration: {}", migration.version);
+        
+        let mut tx = pool.begin().await?;
+        execute_script(&mut tx, migration.down).await?;
+        sqlx::query("DELETE FROM schema_migrations WHERE version = $1")
+            .bind(migration.version)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        
+        // Keep the legacy table from resurrecting the migration on next start
+        let _ = sqlx::query("DELETE FROM _migrations WHERE name = $1")
+            .bind(migration.version)
+            .execute(pool)
+            .await;
+        
+        rolled_back.push(migration.version.to_string());
     }
     
-    Ok(())
+    Ok(rolled_back)
+}
+
+/// Translate SQLite column types for the compiled-in backend
+///
+/// PostgreSQL has no AUTOINCREMENT, and its INTEGER is 32-bit while the
+/// models decode integer columns as i64.
+#[cfg(feature = "postgres")]
+fn dialect(sql: &str) -> String {
+    sql.replace("INTEGER PRIMARY KEY AUTOINCREMENT", "BIGSERIAL PRIMARY KEY")
+        .replace("INTEGER PRIMARY KEY", "BIGSERIAL PRIMARY KEY")
+        .replace("INTEGER", "BIGINT")
+}
+
+/// Translate SQLite column types for the compiled-in backend
+#[cfg(not(feature = "postgres"))]
+fn dialect(sql: &str) -> String {
+    sql.to_string()
 }
 
 /// Migration 001: Initial schema
@@ -70,8 +266,8 @@ CREATE TABLE IF NOT EXISTS roles (
     name TEXT NOT NULL UNIQUE,
     description TEXT,
     permissions TEXT NOT NULL DEFAULT '[]',  -- JSON array of permission strings
-    is_builtin INTEGER NOT NULL DEFAULT 0,
-    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    is_builtin BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at TEXT
 );
 
@@ -84,8 +280,8 @@ CREATE TABLE IF NOT EXISTS users (
     sso_provider TEXT,                        -- 'local', 'oidc', 'saml'
     sso_subject TEXT,                         -- External user ID from SSO
     role_id TEXT NOT NULL,
-    is_active INTEGER NOT NULL DEFAULT 1,
-    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at TEXT,
     last_login TEXT,
     FOREIGN KEY (role_id) REFERENCES roles(id)
@@ -99,7 +295,7 @@ CREATE TABLE IF NOT EXISTS api_keys (
     key_prefix TEXT NOT NULL,                 -- First 8 chars for identification
     user_id TEXT,                             -- Optional: associated user
     permissions TEXT,                         -- Optional: override permissions (JSON)
-    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     expires_at TEXT,
     last_used TEXT,
     revoked_at TEXT,
@@ -113,7 +309,7 @@ CREATE TABLE IF NOT EXISTS sessions (
     refresh_token_hash TEXT NOT NULL,
     user_agent TEXT,
     ip_address TEXT,
-    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     expires_at TEXT NOT NULL,
     revoked_at TEXT,
     FOREIGN KEY (user_id) REFERENCES users(id)
@@ -128,7 +324,7 @@ CREATE TABLE IF NOT EXISTS agents (
     status TEXT NOT NULL DEFAULT 'unknown',   -- healthy, unhealthy, unreachable, unknown
     vector_version TEXT,
     last_seen TEXT,
-    registered_at TEXT NOT NULL DEFAULT (datetime('now')),
+    registered_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     metadata TEXT                             -- JSON for extra data
 );
 
@@ -136,10 +332,10 @@ CREATE TABLE IF NOT EXISTS agents (
 CREATE TABLE IF NOT EXISTS health_checks (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     agent_id TEXT NOT NULL,
-    healthy INTEGER NOT NULL,
+    healthy BOOLEAN NOT NULL,
     latency_ms INTEGER,
     error TEXT,
-    checked_at TEXT NOT NULL DEFAULT (datetime('now')),
+    checked_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
 );
 
@@ -154,12 +350,22 @@ CREATE INDEX IF NOT EXISTS idx_api_keys_prefix ON api_keys(key_prefix);
 CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id)
 "#;
 
+/// Migration 001 rollback
+const MIGRATION_001_DOWN: &str = r#"
+DROP TABLE IF EXISTS health_checks;
+DROP TABLE IF EXISTS agents;
+DROP TABLE IF EXISTS sessions;
+DROP TABLE IF EXISTS api_keys;
+DROP TABLE IF EXISTS users;
+DROP TABLE IF EXISTS roles
+"#;
+
 /// Migration 002: Audit log table
 const MIGRATION_002_AUDIT_LOG: &str = r#"
 -- Audit log table
 CREATE TABLE IF NOT EXISTS audit_log (
     id TEXT PRIMARY KEY,
-    timestamp TEXT NOT NULL DEFAULT (datetime('now')),
+    timestamp TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     actor_type TEXT NOT NULL,                 -- 'user', 'api_key', 'system'
     actor_id TEXT,                            -- user_id or api_key_id
     actor_name TEXT,                          -- username or key name (for display)
@@ -179,6 +385,11 @@ CREATE INDEX IF NOT EXISTS idx_audit_action ON audit_log(action);
 CREATE INDEX IF NOT EXISTS idx_audit_resource ON audit_log(resource_type, resource_id)
 "#;
 
+/// Migration 002 rollback
+const MIGRATION_002_DOWN: &str = r#"
+DROP TABLE IF EXISTS audit_log
+"#;
+
 /// Migration 003: Worker groups table
 const MIGRATION_003_WORKER_GROUPS: &str = r#"
 -- Worker groups table
@@ -187,11 +398,11 @@ CREATE TABLE IF NOT EXISTS worker_groups (
     name TEXT NOT NULL UNIQUE,
     description TEXT,
     deployment_strategy TEXT NOT NULL DEFAULT 'rolling',  -- rolling, canary, blue_green, all_at_once
-    requires_approval INTEGER NOT NULL DEFAULT 0,
+    requires_approval BOOLEAN NOT NULL DEFAULT FALSE,
     approvers TEXT,                           -- JSON array of user IDs/emails
     config_path TEXT,                         -- Path in git repo
     current_config_version TEXT,              -- Git commit hash
-    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     updated_at TEXT,
     created_by TEXT,
     FOREIGN KEY (created_by) REFERENCES users(id)
@@ -204,6 +415,11 @@ CREATE TABLE IF NOT EXISTS worker_groups (
 CREATE INDEX IF NOT EXISTS idx_groups_name ON worker_groups(name)
 "#;
 
+/// Migration 003 rollback
+const MIGRATION_003_DOWN: &str = r#"
+DROP TABLE IF EXISTS worker_groups
+"#;
+
 /// Migration 004: Deployments table
 const MIGRATION_004_DEPLOYMENTS: &str = r#"
 -- Deployments table
@@ -214,7 +430,7 @@ CREATE TABLE IF NOT EXISTS deployments (
     strategy TEXT NOT NULL,                   -- basic, rolling, canary
     status TEXT NOT NULL DEFAULT 'pending',   -- pending, pending_approval, in_progress, completed, failed, cancelled
     options TEXT,                             -- JSON with strategy options
-    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
     started_at TEXT,
     completed_at TEXT,
     created_by TEXT,
@@ -249,3 +465,240 @@ CREATE INDEX IF NOT EXISTS idx_deployments_status ON deployments(status);
 CREATE INDEX IF NOT EXISTS idx_deployment_agents_deployment ON deployment_agents(deployment_id);
 CREATE INDEX IF NOT EXISTS idx_deployment_agents_agent ON deployment_agents(agent_id)
 "#;
+
+/// Migration 004 rollback
+const MIGRATION_004_DOWN: &str = r#"
+DROP TABLE IF EXISTS deployment_agents;
+DROP TABLE IF EXISTS deployments
+"#;
+
+/// Migration 005: Agent tags table
+const MIGRATION_005_AGENT_TAGS: &str = r#"
+-- Agent tags (key/value labels, one value per key)
+CREATE TABLE IF NOT EXISTS agent_tags (
+    agent_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (agent_id, key),
+    FOREIGN KEY (agent_id) REFERENCES agents(id) ON DELETE CASCADE
+);
+
+-- Index for tag lookups
+CREATE INDEX IF NOT EXISTS idx_agent_tags_key_value ON agent_tags(key, value)
+"#;
+
+/// Migration 005 rollback
+const MIGRATION_005_DOWN: &str = r#"
+DROP TABLE IF EXISTS agent_tags
+"#;
+
+/// Migration 006: Soft delete for agents
+const MIGRATION_006_AGENT_SOFT_DELETE: &str = r#"
+-- Soft-deleted agents keep their row so history stays intact
+ALTER TABLE agents ADD COLUMN deleted_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_agents_deleted ON agents(deleted_at)
+"#;
+
+/// Migration 006 rollback
+const MIGRATION_006_DOWN: &str = r#"
+-- The index must go before the column it covers
+DROP INDEX IF EXISTS idx_agents_deleted;
+ALTER TABLE agents DROP COLUMN deleted_at
+"#;
+
+/// Migration 007: Role binding and hash lookup for API keys
+const MIGRATION_007_API_KEY_ROLES: &str = r#"
+-- Keys without a role act with their owner's role
+ALTER TABLE api_keys ADD COLUMN role_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_hash ON api_keys(key_hash)
+"#;
+
+/// Migration 007 rollback
+const MIGRATION_007_DOWN: &str = r#"
+DROP INDEX IF EXISTS idx_api_keys_hash;
+ALTER TABLE api_keys DROP COLUMN role_id
+"#;
+
+/// Migration 008: Scheduled deployments
+const MIGRATION_008_DEPLOYMENT_SCHEDULE: &str = r#"
+-- UTC time a 'scheduled' deployment becomes due
+ALTER TABLE deployments ADD COLUMN scheduled_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_deployments_scheduled ON deployments(scheduled_at)
+"#;
+
+/// Migration 008 rollback
+const MIGRATION_008_DOWN: &str = r#"
+DROP INDEX IF EXISTS idx_deployments_scheduled;
+ALTER TABLE deployments DROP COLUMN scheduled_at
+"#;
+
+/// Migration 009: Idempotency keys for deployment creation
+const MIGRATION_009_IDEMPOTENCY_KEYS: &str = r#"
+-- Keys are scoped to a group, and rows older than the replay window are pruned
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    idempotency_key TEXT NOT NULL,
+    group_id TEXT NOT NULL,
+    deployment_id TEXT,
+    response TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (group_id, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created ON idempotency_keys(created_at)
+"#;
+
+/// Migration 009 rollback
+const MIGRATION_009_DOWN: &str = r#"
+DROP INDEX IF EXISTS idx_idempotency_keys_created;
+DROP TABLE IF EXISTS idempotency_keys
+"#;
+
+/// Migration 010: One active agent per URL
+const MIGRATION_010_AGENT_UNIQUE_URL: &str = r#"
+-- Older duplicates from repeated registration are soft-deleted, keeping the latest
+UPDATE agents SET deleted_at = CURRENT_TIMESTAMP
+WHERE deleted_at IS NULL AND EXISTS (
+    SELECT 1 FROM agents newer
+    WHERE newer.url = agents.url
+      AND newer.deleted_at IS NULL
+      AND (newer.registered_at > agents.registered_at
+           OR (newer.registered_at = agents.registered_at AND newer.id > agents.id))
+);
+
+-- Soft-deleted agents keep their URL, so only active rows are constrained
+CREATE UNIQUE INDEX IF NOT EXISTS idx_agents_url ON agents(url) WHERE deleted_at IS NULL
+"#;
+
+/// Migration 010 rollback
+const MIGRATION_010_DOWN: &str = r#"
+DROP INDEX IF EXISTS idx_agents_url
+"#;
+
+/// Migration 011: Persisted SSO providers
+const MIGRATION_011_SSO_PROVIDERS: &str = r#"
+-- The client secret is kept out of the config JSON and stored encrypted
+CREATE TABLE IF NOT EXISTS sso_providers (
+    id TEXT PRIMARY KEY,
+    config TEXT NOT NULL,                     -- SsoProviderConfig JSON, without secrets
+    client_secret TEXT,                       -- AES-GCM sealed with VECTORIZE_ENCRYPTION_KEY
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+)
+"#;
+
+/// Migration 011 rollback
+const MIGRATION_011_DOWN: &str = r#"
+DROP TABLE IF EXISTS sso_providers
+"#;
+
+/// Migration 012: Per-agent TLS settings for the Vector API
+const MIGRATION_012_AGENT_TLS: &str = r#"
+ALTER TABLE agents ADD COLUMN tls_ca_cert TEXT;
+ALTER TABLE agents ADD COLUMN tls_client_cert TEXT;
+ALTER TABLE agents ADD COLUMN tls_client_key TEXT;
+ALTER TABLE agents ADD COLUMN tls_insecure_skip_verify BOOLEAN NOT NULL DEFAULT FALSE
+"#;
+
+/// Migration 012 rollback
+const MIGRATION_012_DOWN: &str = r#"
+ALTER TABLE agents DROP COLUMN tls_insecure_skip_verify;
+ALTER TABLE agents DROP COLUMN tls_client_key;
+ALTER TABLE agents DROP COLUMN tls_client_cert;
+ALTER TABLE agents DROP COLUMN tls_ca_cert
+"#;
+
+/// Migration 013: Alert channels notified about a group's deployments
+const MIGRATION_013_GROUP_NOTIFICATION_CHANNELS: &str = r#"
+-- JSON array of NotificationChannel
+ALTER TABLE worker_groups ADD COLUMN notification_channels TEXT
+"#;
+
+/// Migration 013 rollback
+const MIGRATION_013_DOWN: &str = r#"
+ALTER TABLE worker_groups DROP COLUMN notification_channels
+"#;
+
+/// Migration 014: Default deployment options per group
+const MIGRATION_014_GROUP_DEPLOYMENT_DEFAULTS: &str = r#"
+-- DeploymentOptions JSON
+ALTER TABLE worker_groups ADD COLUMN default_deployment_options TEXT
+"#;
+
+/// Migration 014 rollback
+const MIGRATION_014_DOWN: &str = r#"
+ALTER TABLE worker_groups DROP COLUMN default_deployment_options
+"#;
+
+/// Migration 015: Provider refresh tokens for SSO sessions
+const MIGRATION_015_SSO_SESSIONS: &str = r#"
+-- One row per SSO login session, so the provider token can be renewed silently
+CREATE TABLE IF NOT EXISTS sso_sessions (
+    session_id TEXT PRIMARY KEY,
+    user_id TEXT,
+    provider_id TEXT NOT NULL,
+    refresh_token TEXT NOT NULL,              -- AES-GCM sealed with VECTORIZE_ENCRYPTION_KEY
+    access_token_expires_at TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+)
+"#;
+
+/// Migration 015 rollback
+const MIGRATION_015_DOWN: &str = r#"
+DROP TABLE IF EXISTS sso_sessions
+"#;
+
+/// Migration 016: Per-group deployment freeze
+const MIGRATION_016_GROUP_FREEZE: &str = r#"
+ALTER TABLE worker_groups ADD COLUMN frozen BOOLEAN NOT NULL DEFAULT FALSE
+"#;
+
+/// Migration 016 rollback
+const MIGRATION_016_DOWN: &str = r#"
+ALTER TABLE worker_groups DROP COLUMN frozen
+"#;
+
+/// Migration 017: Per-group override of agent auto-deregistration
+const MIGRATION_017_GROUP_AUTO_DEREGISTER: &str = r#"
+-- NULL inherits the server-wide setting
+ALTER TABLE worker_groups ADD COLUMN auto_deregister BOOLEAN;
+ALTER TABLE worker_groups ADD COLUMN deregister_after_secs INTEGER
+"#;
+
+/// Migration 017 rollback
+const MIGRATION_017_DOWN: &str = r#"
+ALTER TABLE worker_groups DROP COLUMN deregister_after_secs;
+ALTER TABLE worker_groups DROP COLUMN auto_deregister
+"#;
+
+/// Migration 018: Canonical permission names for the built-in roles
+const MIGRATION_018_BUILTIN_ROLE_PERMISSIONS: &str = r#"
+-- The roles were seeded with names like agents:* that RBAC never recognised.
+-- Roles an administrator has since edited are left alone.
+UPDATE roles SET permissions = '["agents_read","agents_write","agents_delete","groups_read","groups_write","groups_deploy","configs_read","configs_write","configs_rollback","configs_validate","alerts_read","alerts_write","system_read"]'
+WHERE id = 'operator' AND permissions = '["agents:*","groups:*","config:*","deployments:*","tap:*","git:read"]';
+UPDATE roles SET permissions = '["agents_read","groups_read","configs_read","alerts_read","system_read"]'
+WHERE id = 'viewer' AND permissions = '["agents:read","groups:read","config:read","topology:read","metrics:read"]'
+"#;
+
+/// Migration 018 rollback
+const MIGRATION_018_DOWN: &str = r#"
+UPDATE roles SET permissions = '["agents:*","groups:*","config:*","deployments:*","tap:*","git:read"]'
+WHERE id = 'operator' AND permissions = '["agents_read","agents_write","agents_delete","groups_read","groups_write","groups_deploy","configs_read","configs_write","configs_rollback","configs_validate","alerts_read","alerts_write","system_read"]';
+UPDATE roles SET permissions = '["agents:read","groups:read","config:read","topology:read","metrics:read"]'
+WHERE id = 'viewer' AND permissions = '["agents_read","groups_read","configs_read","alerts_read","system_read"]'
+"#;
+
+/// Migration 019: Request fingerprint for idempotency keys
+const MIGRATION_019_IDEMPOTENCY_REQUEST_HASH: &str = r#"
+-- SHA-256 of the request body, so a reused key with a different body is rejected
+ALTER TABLE idempotency_keys ADD COLUMN request_hash TEXT
+"#;
+
+/// Migration 019 rollback
+const MIGRATION_019_DOWN: &str = r#"
+ALTER TABLE idempotency_keys DROP COLUMN request_hash
+"#;
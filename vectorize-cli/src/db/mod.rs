@@ -1,34 +1,165 @@
 //! Database module for Vectorize runtime storage
 //!
-//! Uses SQLite for storing:
+//! Uses SQLite by default (or PostgreSQL with the `postgres` feature) for storing:
 //! - Agent registry
 //! - Health check history
 //! - Users and roles (RBAC)
 //! - API keys
 //! - Sessions
 //! - Audit logs
+//!
+//! Queries are written in the subset of SQL both backends understand:
+//! `$N` placeholders, `CURRENT_TIMESTAMP` and `ON CONFLICT` upserts.
 
 pub mod migrations;
 pub mod models;
 pub mod repository;
 
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
-use std::path::Path;
-use std::str::FromStr;
-use tracing::info;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Connection pool for the compiled-in backend
+#[cfg(not(feature = "postgres"))]
+pub type DbPool = sqlx::SqlitePool;
+
+/// Connection pool for the compiled-in backend
+#[cfg(feature = "postgres")]
+pub type DbPool = sqlx::PgPool;
+
+/// Transaction on the compiled-in backend
+#[cfg(not(feature = "postgres"))]
+pub type DbTransaction<'a> = sqlx::Transaction<'a, sqlx::Sqlite>;
+
+/// Transaction on the compiled-in backend
+#[cfg(feature = "postgres")]
+pub type DbTransaction<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
+
+/// Supported database backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseBackend {
+    Sqlite,
+    Postgres,
+}
+
+impl DatabaseBackend {
+    /// Detect the backend from a connection URL (`sqlite:` or `postgres:`)
+    pub fn from_url(url: &str) -> Option<Self> {
+        if url.starts_with("sqlite:") {
+            Some(Self::Sqlite)
+        } else if url.starts_with("postgres:") || url.starts_with("postgresql:") {
+            Some(Self::Postgres)
+        } else {
+            None
+        }
+    }
+    
+    /// The backend this binary was built for
+    pub fn compiled() -> Self {
+        if cfg!(feature = "postgres") {
+            Self::Postgres
+        } else {
+            Self::Sqlite
+        }
+    }
+}
+
+impl std::fmt::Display for DatabaseBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sqlite => write!(f, "sqlite"),
+            Self::Postgres => write!(f, "postgres"),
+        }
+    }
+}
+
+/// Connection pool settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseConfig {
+    /// Most connections the pool opens
+    pub max_connections: u32,
+    /// Connections kept open while idle
+    pub min_connections: u32,
+    /// How long a query waits for a free connection before failing
+    pub acquire_timeout: Duration,
+    /// Cancel statements running longer than this (PostgreSQL only)
+    pub statement_timeout: Option<Duration>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(30),
+            statement_timeout: None,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Load settings from the environment, falling back to the defaults
+    ///
+    /// - `VECTORIZE_DB_MAX_CONNECTIONS` / `VECTORIZE_DB_MIN_CONNECTIONS`
+    /// - `VECTORIZE_DB_ACQUIRE_TIMEOUT_SECS`
+    /// - `VECTORIZE_DB_STATEMENT_TIMEOUT_MS` (0 or unset disables)
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+    
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let defaults = Self::default();
+        let read = |name: &str| {
+            let value = lookup(name)?;
+            match value.trim().parse::<u64>() {
+                Ok(n) => Some(n),
+                Err(_) => {
+                    warn!("Ignoring invalid {}: {:?}", name, value);
+                    None
+                }
+            }
+        };
+        
+        let max_connections = read("VECTORIZE_DB_MAX_CONNECTIONS")
+            .filter(|n| *n > 0)
+            .map(|n| n.min(u32::MAX as u64) as u32)
+            .unwrap_or(defaults.max_connections);
+        let min_connections = read("VECTORIZE_DB_MIN_CONNECTIONS")
+            .map(|n| n.min(max_connections as u64) as u32)
+            .unwrap_or(defaults.min_connections);
+        
+        Self {
+            max_connections,
+            min_connections,
+            acquire_timeout: read("VECTORIZE_DB_ACQUIRE_TIMEOUT_SECS")
+                .filter(|n| *n > 0)
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.acquire_timeout),
+            statement_timeout: read("VECTORIZE_DB_STATEMENT_TIMEOUT_MS")
+                .filter(|n| *n > 0)
+                .map(Duration::from_millis),
+        }
+    }
+}
 
 /// Database connection pool
 #[derive(Clone)]
 pub struct Database {
-    pool: SqlitePool,
+    pool: DbPool,
+    backend: DatabaseBackend,
 }
 
 impl Database {
-    /// Create a new database connection
+    /// Create a new SQLite database connection
     /// 
     /// If the database file doesn't exist, it will be created.
     /// Migrations are run automatically on startup.
     pub async fn new(db_path: &Path) -> Result<Self, sqlx::Error> {
+        Self::new_with_config(db_path, &DatabaseConfig::default()).await
+    }
+    
+    /// Create a new SQLite database connection with custom pool settings
+    pub async fn new_with_config(db_path: &Path, config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| {
@@ -36,21 +167,60 @@ impl Database {
             })?;
         }
         
-        let db_url = format!("sqlite:{}?mode=rwc", db_path.display());
         info!("Connecting to database: {}", db_path.display());
+        Self::connect_with_config(&format!("sqlite:{}?mode=rwc", db_path.display()), config).await
+    }
+    
+    /// Open an existing SQLite database without running migrations
+    /// 
+    /// Used by maintenance commands (backup, migration status) that must
+    /// inspect the schema as it is rather than upgrade it.
+    pub async fn open_existing(db_path: &Path) -> Result<Self, sqlx::Error> {
+        if !db_path.is_file() {
+            return Err(sqlx::Error::Configuration(
+                format!("No database found at {}", db_path.display()).into(),
+            ));
+        }
         
-        let options = SqliteConnectOptions::from_str(&db_url)?
-            .create_if_missing(true)
-            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
-            .synchronous(sqlx::sqlite::SqliteSynchronous::Normal)
-            .busy_timeout(std::time::Duration::from_secs(30));
+        let url = format!("sqlite:{}", db_path.display());
+        let backend = DatabaseBackend::Sqlite;
+        if backend != DatabaseBackend::compiled() {
+            return Err(sqlx::Error::Configuration(
+                format!("This build supports the {} backend only", DatabaseBackend::compiled()).into(),
+            ));
+        }
         
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect_with(options)
-            .await?;
+        let pool = Self::open_pool(&url, &DatabaseConfig::default()).await?;
+        Ok(Self { pool, backend })
+    }
+    
+    /// Create a new database connection from a connection URL
+    /// 
+    /// Accepts `sqlite:` URLs, or `postgres:` URLs when built with the
+    /// `postgres` feature. Migrations are run automatically on startup.
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        Self::connect_with_config(url, &DatabaseConfig::default()).await
+    }
+    
+    /// Create a new database connection from a URL with custom pool settings
+    pub async fn connect_with_config(url: &str, config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
+        let backend = DatabaseBackend::from_url(url).ok_or_else(|| {
+            sqlx::Error::Configuration("Database URL must start with sqlite: or postgres:".into())
+        })?;
+        
+        if backend != DatabaseBackend::compiled() {
+            return Err(sqlx::Error::Configuration(
+                format!(
+                    "This build supports the {} backend only (got a {} URL)",
+                    DatabaseBackend::compiled(),
+                    backend
+                )
+                .into(),
+            ));
+        }
         
-        let db = Self { pool };
+        let pool = Self::open_pool(url, config).await?;
+        let db = Self { pool, backend };
         
         // Run migrations
         db.run_migrations().await?;
@@ -58,16 +228,71 @@ impl Database {
         // Seed default data (roles, etc.)
         db.seed_defaults().await?;
         
-        info!("Database initialized successfully");
+        info!("Database initialized successfully ({})", backend);
         Ok(db)
     }
     
+    #[cfg(not(feature = "postgres"))]
+    async fn open_pool(url: &str, config: &DatabaseConfig) -> Result<DbPool, sqlx::Error> {
+        use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+        use std::str::FromStr;
+        
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .journal_mode(sqlx::sqlite::SqliteJournalMode::Wal)
+            .synchronous(sqlx::sqlite::SqliteSynchronous::Normal)
+            .busy_timeout(std::time::Duration::from_secs(30));
+        
+        if config.statement_timeout.is_some() {
+            warn!("Statement timeout is not supported on SQLite and will be ignored");
+        }
+        
+        SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(config.acquire_timeout)
+            .connect_with(options)
+            .await
+    }
+    
+    #[cfg(feature = "postgres")]
+    async fn open_pool(url: &str, config: &DatabaseConfig) -> Result<DbPool, sqlx::Error> {
+        use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+        use std::str::FromStr;
+        
+        let mut options = PgConnectOptions::from_str(url)?;
+        if let Some(timeout) = config.statement_timeout {
+            options = options.options([("statement_timeout", timeout.as_millis().to_string())]);
+        }
+        
+        PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(config.acquire_timeout)
+            .connect_with(options)
+            .await
+    }
+    
     /// Run database migrations
     async fn run_migrations(&self) -> Result<(), sqlx::Error> {
         info!("Running database migrations...");
         migrations::run_migrations(&self.pool).await
     }
     
+    /// Report which migrations are applied and whether any have drifted
+    pub async fn migration_status(&self) -> Result<Vec<migrations::MigrationStatus>, sqlx::Error> {
+        migrations::migration_status(&self.pool).await
+    }
+    
+    /// Roll back `version` and every migration applied after it
+    /// 
+    /// The rolled-back migrations are re-applied on the next startup, so this
+    /// is meant to be followed by running the previous release.
+    pub async fn rollback_migration(&self, version: &str) -> Result<Vec<String>, sqlx::Error> {
+        info!("Rolling back migrations through {}", version);
+        migrations::rollback_migration(&self.pool, version).await
+    }
+    
     /// Seed default data (built-in roles, etc.)
     async fn seed_defaults(&self) -> Result<(), sqlx::Error> {
         info!("Seeding default data...");
@@ -75,15 +300,16 @@ impl Database {
         // Create built-in roles if they don't exist
         let roles = [
             ("admin", "Administrator", r#"["*"]"#, true),
-            ("operator", "Operator", r#"["agents:*","groups:*","config:*","deployments:*","tap:*","git:read"]"#, true),
-            ("viewer", "Viewer", r#"["agents:read","groups:read","config:read","topology:read","metrics:read"]"#, true),
+            ("operator", "Operator", r#"["agents_read","agents_write","agents_delete","groups_read","groups_write","groups_deploy","configs_read","configs_write","configs_rollback","configs_validate","alerts_read","alerts_write","system_read"]"#, true),
+            ("viewer", "Viewer", r#"["agents_read","groups_read","configs_read","alerts_read","system_read"]"#, true),
         ];
         
         for (id, name, permissions, is_builtin) in roles {
             sqlx::query(
                 r#"
-                INSERT OR IGNORE INTO roles (id, name, description, permissions, is_builtin, created_at)
-                VALUES (?, ?, ?, ?, ?, datetime('now'))
+                INSERT INTO roles (id, name, description, permissions, is_builtin, created_at)
+                VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP)
+                ON CONFLICT DO NOTHING
                 "#
             )
             .bind(id)
@@ -98,11 +324,21 @@ impl Database {
         Ok(())
     }
     
+    /// Close the pool, waiting for in-flight queries to finish
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+    
     /// Get the connection pool
-    pub fn pool(&self) -> &SqlitePool {
+    pub fn pool(&self) -> &DbPool {
         &self.pool
     }
     
+    /// Get the backend this database is running on
+    pub fn backend(&self) -> DatabaseBackend {
+        self.backend
+    }
+    
     /// Check if this is a fresh database (no users exist)
     pub async fn is_fresh(&self) -> Result<bool, sqlx::Error> {
         let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
@@ -111,9 +347,171 @@ impl Database {
         Ok(count.0 == 0)
     }
     
-    /// Close the database connection
-    pub async fn close(&self) {
-        self.pool.close().await;
+    /// Write a consistent point-in-time copy of the database to `dest`
+    /// 
+    /// Uses `VACUUM INTO`, which reads from a single snapshot and is safe to
+    /// run while the server keeps writing under WAL. `dest` must not exist.
+    pub async fn backup(&self, dest: &Path) -> Result<(), sqlx::Error> {
+        if self.backend != DatabaseBackend::Sqlite {
+            return Err(sqlx::Error::Configuration(
+                "Backups are only supported for SQLite, use pg_dump for PostgreSQL".into(),
+            ));
+        }
+        
+        if dest.exists() {
+            return Err(sqlx::Error::Configuration(
+                format!("Backup destination already exists: {}", dest.display()).into(),
+            ));
+        }
+        
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        sqlx::query("VACUUM INTO $1")
+            .bind(dest.to_string_lossy().to_string())
+            .execute(&self.pool)
+            .await?;
+        
+        info!("Database backed up to {}", dest.display());
+        Ok(())
+    }
+    
+    /// Replace the SQLite database at `db_path` with the backup at `src`
+    /// 
+    /// Refuses to run while a server holds the database lock. The backup is
+    /// staged next to the database and its schema version checked before it
+    /// is swapped in, so a bad file never replaces a good one.
+    pub async fn restore(src: &Path, db_path: &Path) -> Result<(), sqlx::Error> {
+        if !src.is_file() {
+            return Err(sqlx::Error::Configuration(
+                format!("Backup file not found: {}", src.display()).into(),
+            ));
+        }
+        
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        // Held until the swap is done, so a server can't start mid-restore
+        let _lock = Self::lock(db_path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::WouldBlock => {
+                sqlx::Error::Configuration(format!("{}, stop the server first", e).into())
+            }
+            _ => sqlx::Error::Io(e),
+        })?;
+        
+        let staged = sidecar_path(db_path, ".restore");
+        std::fs::copy(src, &staged)?;
+        
+        if let Err(e) = Self::validate_backup(&staged).await {
+            remove_sqlite_files(&staged);
+            return Err(e);
+        }
+        
+        // Stale WAL files from the old database must not be replayed onto the new one
+        for suffix in ["-wal", "-shm"] {
+            let _ = std::fs::remove_file(sidecar_path(db_path, suffix));
+        }
+        std::fs::rename(&staged, db_path)?;
+        remove_sqlite_files(&staged);
+        
+        info!("Database restored from {}", src.display());
+        Ok(())
+    }
+    
+    /// Check that a backup is a Vectorize database this version can open
+    async fn validate_backup(path: &Path) -> Result<(), sqlx::Error> {
+        let options = sqlx::sqlite::SqliteConnectOptions::new().filename(path);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+        
+        // Backups taken before schema_migrations existed only have the legacy table
+        let applied: Result<Vec<(String,)>, sqlx::Error> = match sqlx::query_as("SELECT version FROM schema_migrations")
+            .fetch_all(&pool)
+            .await
+        {
+            Ok(rows) => Ok(rows),
+            Err(_) => sqlx::query_as("SELECT name FROM _migrations").fetch_all(&pool).await,
+        };
+        pool.close().await;
+        
+        let applied = applied.map_err(|_| {
+            sqlx::Error::Configuration("Backup is not a Vectorize database (no migration history)".into())
+        })?;
+        
+        let known = migrations::migration_names();
+        if let Some((unknown,)) = applied.iter().find(|(name,)| !known.contains(&name.as_str())) {
+            return Err(sqlx::Error::Configuration(
+                format!(
+                    "Backup was written by a newer Vectorize (unknown migration {}), upgrade before restoring",
+                    unknown
+                )
+                .into(),
+            ));
+        }
+        
+        Ok(())
+    }
+    
+    /// Take an exclusive OS lock on the SQLite database at `db_path`
+    /// 
+    /// Fails with [`std::io::ErrorKind::WouldBlock`] while another process
+    /// holds it. The lock is released when the returned guard is dropped or
+    /// the process exits, so a crashed server never leaves the database
+    /// locked. The lock file itself stays behind and only records the PID of
+    /// the last holder.
+    pub fn lock(db_path: &Path) -> std::io::Result<DatabaseLock> {
+        use std::io::Write;
+        
+        let path = lock_path(db_path);
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(std::fs::TryLockError::WouldBlock) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::WouldBlock,
+                    format!("Database is in use by a running server (lock file {})", path.display()),
+                ));
+            }
+            Err(std::fs::TryLockError::Error(e)) => return Err(e),
+        }
+        
+        file.set_len(0)?;
+        file.write_all(std::process::id().to_string().as_bytes())?;
+        Ok(DatabaseLock { _file: file })
+    }
+}
+
+/// Guard for a server's lock on its database file
+#[derive(Debug)]
+pub struct DatabaseLock {
+    _file: std::fs::File,
+}
+
+/// Path of a file that sits next to the database, e.g. `vectorize.db-wal`
+fn sidecar_path(db_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = db_path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn lock_path(db_path: &Path) -> PathBuf {
+    sidecar_path(db_path, ".lock")
+}
+
+/// Remove a SQLite file along with its WAL and shared-memory files
+fn remove_sqlite_files(path: &Path) {
+    let _ = std::fs::remove_file(path);
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(sidecar_path(path, suffix));
     }
 }
 
@@ -147,6 +545,105 @@ mod tests {
         db.close().await;
     }
     
+    #[tokio::test]
+    async fn test_builtin_role_permissions_are_recognised() {
+        let (db, _dir) = create_test_db().await;
+        
+        let permissions = |db: &Database| {
+            let pool = db.pool().clone();
+            async move {
+                let rows: Vec<(String, String)> = sqlx::query_as("SELECT id, permissions FROM roles WHERE id != 'admin'")
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap();
+                rows.into_iter()
+                    .flat_map(|(_, json)| serde_json::from_str::<Vec<String>>(&json).unwrap())
+                    .collect::<Vec<_>>()
+            }
+        };
+        let all_parse = |names: &[String]| names.iter().all(|p| p.parse::<crate::rbac::Permission>().is_ok());
+        assert!(all_parse(&permissions(&db).await));
+        
+        // Databases seeded with the old colon names are rewritten
+        db.rollback_migration("018_builtin_role_permissions").await.unwrap();
+        assert!(permissions(&db).await.contains(&"agents:*".to_string()));
+        db.run_migrations().await.unwrap();
+        assert!(all_parse(&permissions(&db).await));
+        
+        db.close().await;
+    }
+    
+    #[test]
+    fn test_backend_from_url() {
+        assert_eq!(DatabaseBackend::from_url("sqlite:/tmp/vectorize.db"), Some(DatabaseBackend::Sqlite));
+        assert_eq!(DatabaseBackend::from_url("sqlite::memory:"), Some(DatabaseBackend::Sqlite));
+        assert_eq!(DatabaseBackend::from_url("postgres://localhost/vectorize"), Some(DatabaseBackend::Postgres));
+        assert_eq!(DatabaseBackend::from_url("postgresql://localhost/vectorize"), Some(DatabaseBackend::Postgres));
+        assert_eq!(DatabaseBackend::from_url("mysql://localhost/vectorize"), None);
+    }
+    
+    #[test]
+    fn test_database_config_from_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+        };
+        
+        assert_eq!(DatabaseConfig::from_lookup(env(&[])), DatabaseConfig::default());
+        
+        let config = DatabaseConfig::from_lookup(env(&[
+            ("VECTORIZE_DB_MAX_CONNECTIONS", "20"),
+            ("VECTORIZE_DB_MIN_CONNECTIONS", "2"),
+            ("VECTORIZE_DB_ACQUIRE_TIMEOUT_SECS", "5"),
+            ("VECTORIZE_DB_STATEMENT_TIMEOUT_MS", "1500"),
+        ]));
+        assert_eq!(config.max_connections, 20);
+        assert_eq!(config.min_connections, 2);
+        assert_eq!(config.acquire_timeout, Duration::from_secs(5));
+        assert_eq!(config.statement_timeout, Some(Duration::from_millis(1500)));
+        
+        // Invalid values fall back; min never exceeds max
+        let config = DatabaseConfig::from_lookup(env(&[
+            ("VECTORIZE_DB_MAX_CONNECTIONS", "0"),
+            ("VECTORIZE_DB_MIN_CONNECTIONS", "50"),
+            ("VECTORIZE_DB_ACQUIRE_TIMEOUT_SECS", "soon"),
+            ("VECTORIZE_DB_STATEMENT_TIMEOUT_MS", "0"),
+        ]));
+        assert_eq!(config.max_connections, 5);
+        assert_eq!(config.min_connections, 5);
+        assert_eq!(config.acquire_timeout, Duration::from_secs(30));
+        assert_eq!(config.statement_timeout, None);
+    }
+    
+    #[tokio::test]
+    async fn test_database_config_applied_to_pool() {
+        let dir = tempdir().unwrap();
+        let config = DatabaseConfig {
+            max_connections: 1,
+            min_connections: 1,
+            acquire_timeout: Duration::from_secs(2),
+            statement_timeout: None,
+        };
+        let db = Database::new_with_config(&dir.path().join("test.db"), &config).await.unwrap();
+        
+        let options = db.pool().options();
+        assert_eq!(options.get_max_connections(), 1);
+        assert_eq!(options.get_min_connections(), 1);
+        assert_eq!(options.get_acquire_timeout(), Duration::from_secs(2));
+        
+        // A single connection still serves sequential queries
+        for i in 0..10 {
+            AgentRepository::create(db.pool(), &format!("agent{}", i), &format!("http://localhost:{}", 8080 + i), None).await.unwrap();
+        }
+        assert_eq!(AgentRepository::count(db.pool()).await.unwrap(), 10);
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_connect_rejects_unknown_url() {
+        assert!(Database::connect("mysql://localhost/vectorize").await.is_err());
+    }
+    
     // =========================================================================
     // Agent Repository Tests
     // =========================================================================
@@ -202,6 +699,20 @@ mod tests {
         db.close().await;
     }
     
+    #[tokio::test]
+    async fn test_agent_url_unique_among_active() {
+        let (db, _dir) = create_test_db().await;
+        
+        let agent = AgentRepository::create(db.pool(), "agent1", "http://localhost:8080", None).await.unwrap();
+        assert!(AgentRepository::create(db.pool(), "agent2", "http://localhost:8080", None).await.is_err());
+        
+        // A soft-deleted agent no longer holds its URL
+        AgentRepository::delete(db.pool(), &agent.id).await.unwrap();
+        AgentRepository::create(db.pool(), "agent2", "http://localhost:8080", None).await.unwrap();
+        
+        db.close().await;
+    }
+    
     #[tokio::test]
     async fn test_agent_list() {
         let (db, _dir) = create_test_db().await;
@@ -216,6 +727,67 @@ mod tests {
         db.close().await;
     }
     
+    #[tokio::test]
+    async fn test_agent_list_paginated() {
+        let (db, _dir) = create_test_db().await;
+        
+        AgentRepository::create(db.pool(), "agent1", "http://localhost:8081", None).await.unwrap();
+        AgentRepository::create(db.pool(), "agent2", "http://localhost:8082", None).await.unwrap();
+        AgentRepository::create(db.pool(), "agent3", "http://localhost:8083", None).await.unwrap();
+        
+        let page = AgentRepository::list_paginated(db.pool(), 2, 0).await.unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].name, "agent1");
+        assert_eq!(page[1].name, "agent2");
+        
+        let page = AgentRepository::list_paginated(db.pool(), 2, 2).await.unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].name, "agent3");
+        
+        let page = AgentRepository::list_paginated(db.pool(), 2, 10).await.unwrap();
+        assert!(page.is_empty());
+        
+        let total = AgentRepository::count(db.pool()).await.unwrap();
+        assert_eq!(total, 3);
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_agent_list_chunk() {
+        let (db, _dir) = create_test_db().await;
+        
+        let group = WorkerGroupRepository::create(db.pool(), "edge", None, None).await.unwrap();
+        for i in 1..=5 {
+            let group_id = (i % 2 == 1).then_some(group.id.as_str());
+            AgentRepository::create(db.pool(), &format!("agent{}", i), &format!("http://localhost:808{}", i), group_id)
+                .await
+                .unwrap();
+        }
+        
+        // Reading chunk by chunk yields every agent once, in order
+        let mut names = Vec::new();
+        let mut after: Option<(String, String)> = None;
+        loop {
+            let cursor = after.as_ref().map(|(name, id)| (name.as_str(), id.as_str()));
+            let chunk = AgentRepository::list_chunk(db.pool(), None, &[], false, cursor, 2).await.unwrap();
+            names.extend(chunk.iter().map(|a| a.name.clone()));
+            match chunk.last() {
+                Some(last) if chunk.len() == 2 => after = Some((last.name.clone(), last.id.clone())),
+                _ => break,
+            }
+        }
+        assert_eq!(names, ["agent1", "agent2", "agent3", "agent4", "agent5"]);
+        
+        let chunk = AgentRepository::list_chunk(db.pool(), Some(&group.id), &[], false, None, 10)
+            .await
+            .unwrap();
+        let names: Vec<_> = chunk.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["agent1", "agent3", "agent5"]);
+        
+        db.close().await;
+    }
+    
     #[tokio::test]
     async fn test_agent_update() {
         let (db, _dir) = create_test_db().await;
@@ -268,6 +840,16 @@ mod tests {
         let not_found = AgentRepository::get_by_id(db.pool(), &agent.id).await.unwrap();
         assert!(not_found.is_none());
         
+        // Soft-deleted agents are hidden from list but the row is kept
+        assert!(AgentRepository::list(db.pool()).await.unwrap().is_empty());
+        let all = AgentRepository::list_including_deleted(db.pool()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(all[0].deleted_at.is_some());
+        
+        // Deleting twice should return false
+        let deleted_again = AgentRepository::delete(db.pool(), &agent.id).await.unwrap();
+        assert!(!deleted_again);
+        
         // Delete nonexistent should return false
         let not_deleted = AgentRepository::delete(db.pool(), "nonexistent").await.unwrap();
         assert!(!not_deleted);
@@ -275,6 +857,57 @@ mod tests {
         db.close().await;
     }
     
+    #[tokio::test]
+    async fn test_agent_soft_delete_restore() {
+        let (db, _dir) = create_test_db().await;
+        
+        let agent = AgentRepository::create(db.pool(), "agent1", "http://localhost:8080", None)
+            .await
+            .unwrap();
+        AgentRepository::record_health_check(db.pool(), &agent.id, true, Some(10), None).await.unwrap();
+        
+        AgentRepository::delete(db.pool(), &agent.id).await.unwrap();
+        
+        // Health history survives the soft delete
+        let checks = AgentRepository::get_health_checks(db.pool(), &agent.id, 10).await.unwrap();
+        assert_eq!(checks.len(), 1);
+        
+        let restored = AgentRepository::restore(db.pool(), &agent.id).await.unwrap();
+        assert!(restored);
+        
+        let found = AgentRepository::get_by_id(db.pool(), &agent.id).await.unwrap();
+        assert!(found.is_some());
+        assert!(found.unwrap().deleted_at.is_none());
+        
+        // Restoring an active agent is a no-op
+        let restored_again = AgentRepository::restore(db.pool(), &agent.id).await.unwrap();
+        assert!(!restored_again);
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_agent_purge() {
+        let (db, _dir) = create_test_db().await;
+        
+        let agent = AgentRepository::create(db.pool(), "agent1", "http://localhost:8080", None)
+            .await
+            .unwrap();
+        AgentRepository::record_health_check(db.pool(), &agent.id, true, Some(10), None).await.unwrap();
+        AgentRepository::delete(db.pool(), &agent.id).await.unwrap();
+        
+        let purged = AgentRepository::purge(db.pool(), &agent.id).await.unwrap();
+        assert!(purged);
+        
+        let all = AgentRepository::list_including_deleted(db.pool()).await.unwrap();
+        assert!(all.is_empty());
+        
+        let checks = AgentRepository::get_health_checks(db.pool(), &agent.id, 10).await.unwrap();
+        assert!(checks.is_empty());
+        
+        db.close().await;
+    }
+    
     #[tokio::test]
     async fn test_agent_health_checks() {
         let (db, _dir) = create_test_db().await;
@@ -298,6 +931,145 @@ mod tests {
         db.close().await;
     }
     
+    #[tokio::test]
+    async fn test_agent_uptime_percentage() {
+        let (db, _dir) = create_test_db().await;
+        let since = "2000-01-01 00:00:00";
+        
+        let agent = AgentRepository::create(db.pool(), "agent1", "http://localhost:8080", None).await.unwrap();
+        assert_eq!(AgentRepository::uptime_percentage(db.pool(), &agent.id, since).await.unwrap(), None);
+        
+        for healthy in [true, true, true, false] {
+            AgentRepository::record_health_check(db.pool(), &agent.id, healthy, None, None).await.unwrap();
+        }
+        // Checks before the window are ignored
+        sqlx::query("INSERT INTO health_checks (agent_id, healthy, checked_at) VALUES ($1, FALSE, '1999-12-31 23:00:00')")
+            .bind(&agent.id)
+            .execute(db.pool())
+            .await
+            .unwrap();
+        
+        let counts = AgentRepository::uptime_counts(db.pool(), &agent.id, since).await.unwrap();
+        assert_eq!(counts, UptimeCounts { healthy_checks: 3, total_checks: 4 });
+        assert_eq!(AgentRepository::uptime_percentage(db.pool(), &agent.id, since).await.unwrap(), Some(75.0));
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_group_uptime_counts() {
+        let (db, _dir) = create_test_db().await;
+        let since = "2000-01-01 00:00:00";
+        
+        let group = WorkerGroupRepository::create(db.pool(), "prod", None, None).await.unwrap();
+        let up = AgentRepository::create(db.pool(), "agent1", "http://localhost:8081", Some(&group.id)).await.unwrap();
+        let flaky = AgentRepository::create(db.pool(), "agent2", "http://localhost:8082", Some(&group.id)).await.unwrap();
+        AgentRepository::create(db.pool(), "agent3", "http://localhost:8083", Some(&group.id)).await.unwrap();
+        
+        for _ in 0..3 {
+            AgentRepository::record_health_check(db.pool(), &up.id, true, None, None).await.unwrap();
+        }
+        AgentRepository::record_health_check(db.pool(), &flaky.id, true, None, None).await.unwrap();
+        AgentRepository::record_health_check(db.pool(), &flaky.id, false, None, Some("timeout")).await.unwrap();
+        
+        let counts = AgentRepository::group_uptime_counts(db.pool(), &group.id, since).await.unwrap();
+        let summary: Vec<(&str, Option<f64>)> = counts.iter()
+            .map(|(_, name, counts)| (name.as_str(), counts.percentage()))
+            .collect();
+        // Agents without checks are listed with no percentage
+        assert_eq!(summary, vec![("agent1", Some(100.0)), ("agent2", Some(50.0)), ("agent3", None)]);
+        
+        let total = counts.iter().fold(UptimeCounts::default(), |sum, (_, _, c)| sum + *c);
+        assert_eq!(total.percentage(), Some(80.0));
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_agent_tags() {
+        let (db, _dir) = create_test_db().await;
+        
+        let agent = AgentRepository::create(db.pool(), "agent1", "http://localhost:8080", None)
+            .await
+            .unwrap();
+        
+        // No tags initially
+        let tags = AgentRepository::get_tags(db.pool(), &agent.id).await.unwrap();
+        assert!(tags.is_empty());
+        
+        let new_tags = vec![
+            ("env".to_string(), "prod".to_string()),
+            ("region".to_string(), "us".to_string()),
+        ];
+        AgentRepository::set_tags(db.pool(), &agent.id, &new_tags).await.unwrap();
+        
+        let tags = AgentRepository::get_tags(db.pool(), &agent.id).await.unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].key, "env");
+        assert_eq!(tags[0].value, "prod");
+        assert_eq!(tags[1].key, "region");
+        
+        // Setting tags replaces the existing set
+        AgentRepository::set_tags(db.pool(), &agent.id, &[("env".to_string(), "staging".to_string())])
+            .await
+            .unwrap();
+        
+        let tags = AgentRepository::get_tags(db.pool(), &agent.id).await.unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].value, "staging");
+        
+        // Batched lookup leaves out untagged agents
+        let untagged = AgentRepository::create(db.pool(), "agent2", "http://localhost:8081", None)
+            .await
+            .unwrap();
+        let all = AgentRepository::get_tags_for(db.pool(), &[agent.id.clone(), untagged.id.clone()])
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[&agent.id][0].value, "staging");
+        assert!(AgentRepository::get_tags_for(db.pool(), &[]).await.unwrap().is_empty());
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_agent_list_by_tag() {
+        let (db, _dir) = create_test_db().await;
+        
+        let agent1 = AgentRepository::create(db.pool(), "agent1", "http://localhost:8081", None).await.unwrap();
+        let agent2 = AgentRepository::create(db.pool(), "agent2", "http://localhost:8082", None).await.unwrap();
+        AgentRepository::create(db.pool(), "agent3", "http://localhost:8083", None).await.unwrap();
+        
+        AgentRepository::set_tags(db.pool(), &agent1.id, &[
+            ("env".to_string(), "prod".to_string()),
+            ("region".to_string(), "us".to_string()),
+        ]).await.unwrap();
+        AgentRepository::set_tags(db.pool(), &agent2.id, &[
+            ("env".to_string(), "prod".to_string()),
+            ("region".to_string(), "eu".to_string()),
+        ]).await.unwrap();
+        
+        let prod = AgentRepository::list_by_tag(db.pool(), &[("env".to_string(), "prod".to_string())])
+            .await
+            .unwrap();
+        assert_eq!(prod.len(), 2);
+        
+        // Multiple tags use AND semantics
+        let prod_us = AgentRepository::list_by_tag(db.pool(), &[
+            ("env".to_string(), "prod".to_string()),
+            ("region".to_string(), "us".to_string()),
+        ]).await.unwrap();
+        assert_eq!(prod_us.len(), 1);
+        assert_eq!(prod_us[0].name, "agent1");
+        
+        let none = AgentRepository::list_by_tag(db.pool(), &[("env".to_string(), "dev".to_string())])
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+        
+        db.close().await;
+    }
+    
     // =========================================================================
     // Worker Group Repository Tests
     // =========================================================================
@@ -354,6 +1126,29 @@ mod tests {
         db.close().await;
     }
     
+    #[tokio::test]
+    async fn test_worker_group_list_paginated() {
+        let (db, _dir) = create_test_db().await;
+        
+        WorkerGroupRepository::create(db.pool(), "production", None, None).await.unwrap();
+        WorkerGroupRepository::create(db.pool(), "development", None, None).await.unwrap();
+        WorkerGroupRepository::create(db.pool(), "staging", None, None).await.unwrap();
+        
+        let page = WorkerGroupRepository::list_paginated(db.pool(), 2, 0).await.unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].name, "development");
+        assert_eq!(page[1].name, "production");
+        
+        let page = WorkerGroupRepository::list_paginated(db.pool(), 2, 2).await.unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].name, "staging");
+        
+        let total = WorkerGroupRepository::count(db.pool()).await.unwrap();
+        assert_eq!(total, 3);
+        
+        db.close().await;
+    }
+    
     #[tokio::test]
     async fn test_worker_group_update() {
         let (db, _dir) = create_test_db().await;
@@ -395,6 +1190,36 @@ mod tests {
         db.close().await;
     }
     
+    #[tokio::test]
+    async fn test_worker_group_delete_cleans_up_references() {
+        let (db, _dir) = create_test_db().await;
+        
+        let group = WorkerGroupRepository::create(db.pool(), "doomed", None, None).await.unwrap();
+        let active = AgentRepository::create(db.pool(), "agent1", "http://localhost:8081", Some(&group.id)).await.unwrap();
+        let removed = AgentRepository::create(db.pool(), "agent2", "http://localhost:8082", Some(&group.id)).await.unwrap();
+        AgentRepository::delete(db.pool(), &removed.id).await.unwrap();
+        
+        let deployment = DeploymentRepository::create(db.pool(), &group.id, "abc123", "basic", None, None).await.unwrap();
+        DeploymentRepository::add_agent(db.pool(), &deployment.id, &active.id).await.unwrap();
+        DeploymentRepository::update_status(db.pool(), &deployment.id, "completed", None).await.unwrap();
+        
+        assert!(WorkerGroupRepository::delete(db.pool(), &group.id).await.unwrap());
+        
+        // Agents are unassigned rather than left pointing at a missing group
+        let agent = AgentRepository::get_by_id(db.pool(), &active.id).await.unwrap().unwrap();
+        assert!(agent.group_id.is_none());
+        let dangling: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = $1")
+            .bind(&group.id)
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(dangling.0, 0);
+        
+        assert!(DeploymentRepository::get_by_id(db.pool(), &deployment.id).await.unwrap().is_none());
+        
+        db.close().await;
+    }
+    
     #[tokio::test]
     async fn test_worker_group_agent_count() {
         let (db, _dir) = create_test_db().await;
@@ -435,6 +1260,31 @@ mod tests {
         db.close().await;
     }
     
+    #[tokio::test]
+    async fn test_agent_set_group() {
+        let (db, _dir) = create_test_db().await;
+        
+        let group1 = WorkerGroupRepository::create(db.pool(), "group1", None, None).await.unwrap();
+        let group2 = WorkerGroupRepository::create(db.pool(), "group2", None, None).await.unwrap();
+        let agent = AgentRepository::create(db.pool(), "agent1", "http://localhost:8081", Some(&group1.id)).await.unwrap();
+        AgentRepository::record_health_check(db.pool(), &agent.id, true, Some(5), None).await.unwrap();
+        
+        let moved = AgentRepository::set_group(db.pool(), &agent.id, Some(&group2.id)).await.unwrap().unwrap();
+        assert_eq!(moved.id, agent.id);
+        assert_eq!(moved.group_id.as_deref(), Some(group2.id.as_str()));
+        assert!(AgentRepository::list_by_group(db.pool(), &group1.id).await.unwrap().is_empty());
+        
+        // History stays with the agent
+        assert_eq!(AgentRepository::get_health_checks(db.pool(), &agent.id, 10).await.unwrap().len(), 1);
+        
+        let unassigned = AgentRepository::set_group(db.pool(), &agent.id, None).await.unwrap().unwrap();
+        assert!(unassigned.group_id.is_none());
+        
+        assert!(AgentRepository::set_group(db.pool(), "missing", None).await.unwrap().is_none());
+        
+        db.close().await;
+    }
+    
     // =========================================================================
     // User Repository Tests
     // =========================================================================
@@ -507,6 +1357,188 @@ mod tests {
         db.close().await;
     }
     
+    // =========================================================================
+    // API Key Tests
+    // =========================================================================
+    
+    #[tokio::test]
+    async fn test_api_key_create_lookup_revoke() {
+        let (db, _dir) = create_test_db().await;
+        let pool = db.pool();
+        
+        let user = UserRepository::create(pool, "ops", "ops@test.com", "hash", "operator").await.unwrap();
+        let key = ApiKeyRepository::create(pool, "ci", "hash-1", "vz_abcdefgh", Some(&user.id), Some("viewer"), None, None).await.unwrap();
+        assert_eq!(key.role_id.as_deref(), Some("viewer"));
+        assert!(key.revoked_at.is_none());
+        
+        let found = ApiKeyRepository::get_by_hash(pool, "hash-1").await.unwrap().unwrap();
+        assert_eq!(found.id, key.id);
+        assert!(ApiKeyRepository::get_by_hash(pool, "hash-2").await.unwrap().is_none());
+        
+        assert_eq!(ApiKeyRepository::list_by_user(pool, &user.id).await.unwrap().len(), 1);
+        assert_eq!(ApiKeyRepository::list(pool).await.unwrap().len(), 1);
+        
+        assert!(ApiKeyRepository::revoke(pool, &key.id).await.unwrap());
+        assert!(!ApiKeyRepository::revoke(pool, &key.id).await.unwrap());
+        
+        // Revoked keys no longer authenticate but stay listed
+        assert!(ApiKeyRepository::get_by_hash(pool, "hash-1").await.unwrap().is_none());
+        let revoked = ApiKeyRepository::get_by_id(pool, &key.id).await.unwrap().unwrap();
+        assert!(revoked.revoked_at.is_some());
+        
+        db.close().await;
+    }
+    
+    // =========================================================================
+    // Audit Log Tests
+    // =========================================================================
+    
+    #[tokio::test]
+    async fn test_audit_log_list_and_count() {
+        let (db, _dir) = create_test_db().await;
+        let pool = db.pool();
+        
+        AuditLogRepository::create(pool, "user", Some("u1"), None, "POST /api/v1/groups", Some("groups"), None, None, None, None, "success").await.unwrap();
+        AuditLogRepository::create(pool, "cli", None, Some("ops"), "DELETE /api/v1/agents/:id", Some("agents"), Some("a1"), None, None, None, "failure").await.unwrap();
+        AuditLogRepository::create(pool, "user", Some("u2"), None, "POST /api/v1/agents", Some("agents"), None, None, None, None, "success").await.unwrap();
+        
+        let all = AuditLogRepository::list(pool, None, None, None, None, 10, 0).await.unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(AuditLogRepository::count(pool, None, None, None, None).await.unwrap(), 3);
+        
+        // `user` matches the actor ID or name
+        let by_id = AuditLogRepository::list(pool, None, Some("u1"), None, None, 10, 0).await.unwrap();
+        assert_eq!(by_id.len(), 1);
+        assert_eq!(by_id[0].action, "POST /api/v1/groups");
+        
+        let by_name = AuditLogRepository::list(pool, None, Some("ops"), None, None, 10, 0).await.unwrap();
+        assert_eq!(by_name.len(), 1);
+        assert_eq!(by_name[0].resource_id.as_deref(), Some("a1"));
+        
+        assert_eq!(AuditLogRepository::count(pool, None, None, None, Some("agents")).await.unwrap(), 2);
+        assert_eq!(AuditLogRepository::list(pool, None, None, None, Some("agents"), 1, 0).await.unwrap().len(), 1);
+        
+        db.close().await;
+    }
+    
+    async fn create_history(db: &Database) -> String {
+        let pool = db.pool();
+        let group = WorkerGroupRepository::create(pool, "history", None, None).await.unwrap();
+        
+        // One deployment a day through January, every third one failed
+        for day in 1..=9 {
+            let deployment = DeploymentRepository::create(pool, &group.id, &format!("v{}", day), "basic", None, None).await.unwrap();
+            let status = if day % 3 == 0 { "failed" } else { "completed" };
+            DeploymentRepository::update_status(pool, &deployment.id, status, None).await.unwrap();
+            sqlx::query("UPDATE deployments SET created_at = $1 WHERE id = $2")
+                .bind(format!("2024-01-{:02} 12:00:00", day))
+                .bind(&deployment.id)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+        
+        group.id
+    }
+    
+    #[tokio::test]
+    async fn test_deployment_history_status_and_date_filters() {
+        let (db, _dir) = create_test_db().await;
+        let pool = db.pool();
+        let group_id = create_history(&db).await;
+        
+        let versions = |deployments: Vec<crate::db::models::Deployment>| {
+            deployments.into_iter().map(|d| d.config_version).collect::<Vec<_>>()
+        };
+        
+        let failed = DeploymentRepository::list_for_group(pool, &group_id, Some("failed"), None, None, 50, 0).await.unwrap();
+        assert_eq!(versions(failed), vec!["v9", "v6", "v3"]);
+        assert_eq!(DeploymentRepository::count_for_group(pool, &group_id, Some("failed"), None, None).await.unwrap(), 3);
+        
+        // `since` is inclusive, `until` exclusive
+        let range = DeploymentRepository::list_for_group(
+            pool, &group_id, None, Some("2024-01-03 00:00:00"), Some("2024-01-06 00:00:00"), 50, 0,
+        ).await.unwrap();
+        assert_eq!(versions(range), vec!["v5", "v4", "v3"]);
+        
+        let combined = DeploymentRepository::list_for_group(
+            pool, &group_id, Some("failed"), Some("2024-01-04 00:00:00"), None, 50, 0,
+        ).await.unwrap();
+        assert_eq!(versions(combined), vec!["v9", "v6"]);
+        
+        assert!(DeploymentRepository::list_for_group(pool, "other", None, None, None, 50, 0).await.unwrap().is_empty());
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_deployment_history_pagination() {
+        let (db, _dir) = create_test_db().await;
+        let pool = db.pool();
+        let group_id = create_history(&db).await;
+        let group_id = group_id.as_str();
+        
+        let page = |limit, offset| async move {
+            DeploymentRepository::list_for_group(pool, group_id, None, None, None, limit, offset)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|d| d.config_version)
+                .collect::<Vec<_>>()
+        };
+        
+        assert_eq!(page(4, 0).await, vec!["v9", "v8", "v7", "v6"]);
+        assert_eq!(page(4, 4).await, vec!["v5", "v4", "v3", "v2"]);
+        assert_eq!(page(4, 8).await, vec!["v1"]);
+        assert!(page(4, 12).await.is_empty());
+        assert_eq!(DeploymentRepository::count_for_group(pool, group_id, None, None, None).await.unwrap(), 9);
+        
+        db.close().await;
+    }
+        
+    #[tokio::test]
+    async fn test_previous_completed_deployment() {
+        let (db, _dir) = create_test_db().await;
+        let pool = db.pool();
+        let group_id = create_history(&db).await;
+        
+        let find = |version: &str| {
+            let version = version.to_string();
+            let group_id = group_id.clone();
+            async move {
+                DeploymentRepository::list_for_group(pool, &group_id, None, None, None, 20, 0)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .find(|d| d.config_version == version)
+                    .unwrap()
+            }
+        };
+        let previous = |deployment| async move {
+            DeploymentRepository::previous_completed(pool, &deployment)
+                .await
+                .unwrap()
+                .map(|d| d.config_version)
+        };
+        
+        // Failed deployments are skipped
+        assert_eq!(previous(find("v4").await).await.as_deref(), Some("v2"));
+        assert_eq!(previous(find("v1").await).await, None);
+        
+        // Deployments created in the same second fall back to ID order
+        sqlx::query("UPDATE deployments SET created_at = '2024-02-01 12:00:00' WHERE config_version IN ('v7', 'v8')")
+            .execute(pool)
+            .await
+            .unwrap();
+        let (v7, v8) = (find("v7").await, find("v8").await);
+        let (earlier, later) = if v7.id < v8.id { (v7, v8) } else { (v8, v7) };
+        let earlier_version = earlier.config_version.clone();
+        assert_eq!(previous(later).await, Some(earlier_version));
+        assert_eq!(previous(earlier).await.as_deref(), Some("v5"));
+        
+        db.close().await;
+    }
+    
     // =========================================================================
     // Config Version Tests
     // =========================================================================
@@ -525,4 +1557,177 @@ mod tests {
         
         db.close().await;
     }
+    
+    // =========================================================================
+    // Backup / Restore Tests
+    // =========================================================================
+    
+    #[tokio::test]
+    async fn test_backup_and_reopen() {
+        let (db, dir) = create_test_db().await;
+        
+        UserRepository::create(db.pool(), "admin", "admin@test.com", "hash", "admin").await.unwrap();
+        
+        let backup_path = dir.path().join("backups").join("vectorize-backup.db");
+        db.backup(&backup_path).await.unwrap();
+        
+        // A second backup to the same path is refused rather than overwriting
+        assert!(db.backup(&backup_path).await.is_err());
+        db.close().await;
+        
+        let copy = Database::new(&backup_path).await.unwrap();
+        assert!(!copy.is_fresh().await.unwrap());
+        
+        let user = UserRepository::get_by_username(copy.pool(), "admin").await.unwrap().unwrap();
+        assert_eq!(user.email.as_deref(), Some("admin@test.com"));
+        assert_eq!(user.role_id, "admin");
+        
+        let roles = RoleRepository::list(copy.pool()).await.unwrap();
+        assert_eq!(roles.len(), 3);
+        
+        copy.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_restore() {
+        let (db, dir) = create_test_db().await;
+        
+        UserRepository::create(db.pool(), "admin", "admin@test.com", "hash", "admin").await.unwrap();
+        let backup_path = dir.path().join("backup.db");
+        db.backup(&backup_path).await.unwrap();
+        db.close().await;
+        
+        let target = dir.path().join("restored.db");
+        
+        // Refused while a server holds the lock
+        let lock = Database::lock(&target).unwrap();
+        let err = Database::restore(&backup_path, &target).await.unwrap_err();
+        assert!(err.to_string().contains("stop the server first"));
+        drop(lock);
+        
+        // A lock file left behind by a crashed server doesn't block
+        std::fs::write(dir.path().join("restored.db.lock"), "999999").unwrap();
+        Database::restore(&backup_path, &target).await.unwrap();
+        
+        let restored = Database::new(&target).await.unwrap();
+        assert!(UserRepository::get_by_username(restored.pool(), "admin").await.unwrap().is_some());
+        restored.close().await;
+        
+        // Files that aren't Vectorize databases are rejected
+        let bogus = dir.path().join("bogus.db");
+        std::fs::write(&bogus, b"").unwrap();
+        assert!(Database::restore(&bogus, &target).await.is_err());
+    }
+    
+    #[test]
+    fn test_lock_is_exclusive() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vectorize.db");
+        
+        let lock = Database::lock(&path).unwrap();
+        let err = Database::lock(&path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+        
+        drop(lock);
+        let _lock = Database::lock(&path).unwrap();
+        let pid = std::fs::read_to_string(dir.path().join("vectorize.db.lock")).unwrap();
+        assert_eq!(pid, std::process::id().to_string());
+    }
+    
+    // =========================================================================
+    // Migration Tests
+    // =========================================================================
+    
+    #[tokio::test]
+    async fn test_idempotency_key_lifecycle() {
+        let (db, _dir) = create_test_db().await;
+        let pool = db.pool();
+        
+        // Claims made after `fresh` are still live, everything is older than `stale`
+        let (fresh, stale) = ("2000-01-01 00:00:00", "9999-01-01 00:00:00");
+        assert!(IdempotencyKeyRepository::claim(pool, "g1", "key", "h1", fresh).await.unwrap());
+        assert!(!IdempotencyKeyRepository::claim(pool, "g1", "key", "h1", fresh).await.unwrap());
+        // Keys are scoped to a group
+        assert!(IdempotencyKeyRepository::claim(pool, "g2", "key", "h1", fresh).await.unwrap());
+        
+        let pending = IdempotencyKeyRepository::get(pool, "g1", "key", "2000-01-01 00:00:00").await.unwrap().unwrap();
+        assert!(pending.response.is_none());
+        assert_eq!(pending.request_hash.as_deref(), Some("h1"));
+        
+        // An abandoned claim is taken over by the same request, never by a different one
+        assert!(!IdempotencyKeyRepository::claim(pool, "g1", "key", "h2", stale).await.unwrap());
+        assert!(IdempotencyKeyRepository::claim(pool, "g1", "key", "h1", stale).await.unwrap());
+        
+        IdempotencyKeyRepository::complete(pool, "g1", "key", "d1", "{}").await.unwrap();
+        let done = IdempotencyKeyRepository::get(pool, "g1", "key", "2000-01-01 00:00:00").await.unwrap().unwrap();
+        assert_eq!(done.deployment_id.as_deref(), Some("d1"));
+        assert_eq!(done.response.as_deref(), Some("{}"));
+        // A completed key is never taken over
+        assert!(!IdempotencyKeyRepository::claim(pool, "g1", "key", "h1", stale).await.unwrap());
+        
+        // Rows older than the window are ignored, then pruned
+        assert!(IdempotencyKeyRepository::get(pool, "g1", "key", "9999-01-01 00:00:00").await.unwrap().is_none());
+        assert_eq!(IdempotencyKeyRepository::delete_expired(pool, "9999-01-01 00:00:00").await.unwrap(), 2);
+        
+        // A released key can be claimed again
+        assert!(IdempotencyKeyRepository::claim(pool, "g1", "key", "h1", fresh).await.unwrap());
+        IdempotencyKeyRepository::release(pool, "g1", "key").await.unwrap();
+        assert!(IdempotencyKeyRepository::claim(pool, "g1", "key", "h2", fresh).await.unwrap());
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_migration_status() {
+        let (db, _dir) = create_test_db().await;
+        
+        let status = db.migration_status().await.unwrap();
+        assert_eq!(status.len(), migrations::migration_names().len());
+        assert!(status.iter().all(|m| m.applied_at.is_some() && m.checksum_matches));
+        
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_migration_rollback() {
+        let (db, dir) = create_test_db().await;
+        
+        let rolled_back = db.rollback_migration("005_agent_tags").await.unwrap();
+        assert_eq!(rolled_back, vec!["019_idempotency_request_hash", "018_builtin_role_permissions", "017_group_auto_deregister", "016_group_freeze", "015_sso_sessions", "014_group_deployment_defaults", "013_group_notification_channels", "012_agent_tls", "011_sso_providers", "010_agent_unique_url", "009_idempotency_keys", "008_deployment_schedule", "007_api_key_roles", "006_agent_soft_delete", "005_agent_tags"]);
+        
+        let status = db.migration_status().await.unwrap();
+        let pending: Vec<&str> = status.iter()
+            .filter(|m| m.applied_at.is_none())
+            .map(|m| m.version.as_str())
+            .collect();
+        assert_eq!(pending, vec!["005_agent_tags", "006_agent_soft_delete", "007_api_key_roles", "008_deployment_schedule", "009_idempotency_keys", "010_agent_unique_url", "011_sso_providers", "012_agent_tls", "013_group_notification_channels", "014_group_deployment_defaults", "015_sso_sessions", "016_group_freeze", "017_group_auto_deregister", "018_builtin_role_permissions", "019_idempotency_request_hash"]);
+        
+        // Tables from the rolled-back migration are gone
+        assert!(sqlx::query("SELECT * FROM agent_tags").fetch_all(db.pool()).await.is_err());
+        
+        assert!(db.rollback_migration("999_missing").await.is_err());
+        db.close().await;
+        
+        // Reopening re-applies them
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let status = db.migration_status().await.unwrap();
+        assert!(status.iter().all(|m| m.applied_at.is_some()));
+        db.close().await;
+    }
+    
+    #[tokio::test]
+    async fn test_migration_drift_detected() {
+        let (db, dir) = create_test_db().await;
+        
+        sqlx::query("UPDATE schema_migrations SET checksum = 'tampered' WHERE version = '002_audit_log'")
+            .execute(db.pool())
+            .await
+            .unwrap();
+        
+        let status = db.migration_status().await.unwrap();
+        assert!(!status.iter().find(|m| m.version == "002_audit_log").unwrap().checksum_matches);
+        db.close().await;
+        
+        assert!(Database::new(&dir.path().join("test.db")).await.is_err());
+    }
 }
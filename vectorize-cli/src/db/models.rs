@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::HashMap;
 
 // =============================================================================
 // Agent Models
@@ -53,6 +54,15 @@ pub struct Agent {
     pub last_seen: Option<String>,
     pub registered_at: String,
     pub metadata: Option<String>,
+    pub deleted_at: Option<String>,
+    /// CA certificate (PEM) for the agent's Vector API
+    pub tls_ca_cert: Option<String>,
+    /// Client certificate (PEM) for mutual TLS
+    pub tls_client_cert: Option<String>,
+    /// Client private key (PEM) for mutual TLS, sealed with `crate::crypto::SecretCipher`
+    pub tls_client_key: Option<String>,
+    /// Skip server certificate verification (development only)
+    pub tls_insecure_skip_verify: bool,
 }
 
 /// Agent for API responses (with parsed status)
@@ -68,10 +78,19 @@ pub struct AgentResponse {
     pub registered_at: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tags: HashMap<String, String>,
+    /// Whether TLS settings are stored for the agent (certificates and keys
+    /// are never returned)
+    #[serde(default)]
+    pub tls_configured: bool,
 }
 
 impl From<Agent> for AgentResponse {
     fn from(agent: Agent) -> Self {
+        let tls_configured = crate::agent_clients::AgentTls::is_configured(&agent);
         Self {
             id: agent.id,
             name: agent.name,
@@ -82,6 +101,9 @@ impl From<Agent> for AgentResponse {
             last_seen: agent.last_seen,
             registered_at: agent.registered_at,
             metadata: agent.metadata.and_then(|m| serde_json::from_str(&m).ok()),
+            deleted_at: agent.deleted_at,
+            tags: HashMap::new(),
+            tls_configured,
         }
     }
 }
@@ -97,6 +119,14 @@ pub struct HealthCheck {
     pub checked_at: String,
 }
 
+/// Agent tag (key/value label)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AgentTag {
+    pub agent_id: String,
+    pub key: String,
+    pub value: String,
+}
+
 // =============================================================================
 // User & Auth Models
 // =============================================================================
@@ -184,8 +214,7 @@ impl From<User> for UserResponse {
     }
 }
 
-/// API key database model (used in future auth phase)
-#[allow(dead_code)]
+/// API key database model
 #[derive(Debug, Clone, Serialize, FromRow)]
 pub struct ApiKey {
     pub id: String,
@@ -195,14 +224,15 @@ pub struct ApiKey {
     pub key_prefix: String,
     pub user_id: Option<String>,
     pub permissions: Option<String>,
+    /// Role the key acts as (None = the owner's role)
+    pub role_id: Option<String>,
     pub created_at: String,
     pub expires_at: Option<String>,
     pub last_used: Option<String>,
     pub revoked_at: Option<String>,
 }
 
-/// API key for API responses (used in future auth phase)
-#[allow(dead_code)]
+/// API key for API responses
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiKeyResponse {
     pub id: String,
@@ -210,6 +240,7 @@ pub struct ApiKeyResponse {
     pub key_prefix: String,
     pub user_id: Option<String>,
     pub permissions: Option<Vec<String>>,
+    pub role_id: Option<String>,
     pub created_at: String,
     pub expires_at: Option<String>,
     pub last_used: Option<String>,
@@ -224,6 +255,7 @@ impl From<ApiKey> for ApiKeyResponse {
             key_prefix: key.key_prefix,
             user_id: key.user_id,
             permissions: key.permissions.and_then(|p| serde_json::from_str(&p).ok()),
+            role_id: key.role_id,
             created_at: key.created_at,
             expires_at: key.expires_at,
             last_used: key.last_used,
@@ -264,6 +296,12 @@ pub struct WorkerGroup {
     pub created_at: String,
     pub updated_at: Option<String>,
     pub created_by: Option<String>,
+    pub notification_channels: Option<String>,  // JSON array of NotificationChannel
+    pub default_deployment_options: Option<String>,  // DeploymentOptions JSON
+    pub frozen: bool,
+    /// Overrides the server-wide auto-deregistration setting when set
+    pub auto_deregister: Option<bool>,
+    pub deregister_after_secs: Option<i64>,
 }
 
 /// Worker group for API responses
@@ -281,6 +319,20 @@ pub struct WorkerGroupResponse {
     pub agent_count: Option<i64>,
     pub healthy_count: Option<i64>,
     pub unhealthy_count: Option<i64>,
+    /// IDs of the alert channels notified about deployments
+    #[serde(default)]
+    pub notification_channels: Vec<String>,
+    /// Options used by deployments that don't specify their own
+    #[serde(default)]
+    pub default_deployment_options: Option<serde_json::Value>,
+    /// New deployments are blocked while the group is frozen
+    #[serde(default)]
+    pub frozen: bool,
+    /// Per-group auto-deregistration override; `null` uses the server setting
+    #[serde(default)]
+    pub auto_deregister: Option<bool>,
+    #[serde(default)]
+    pub deregister_after_secs: Option<i64>,
 }
 
 impl From<WorkerGroup> for WorkerGroupResponse {
@@ -298,6 +350,17 @@ impl From<WorkerGroup> for WorkerGroupResponse {
             agent_count: None,
             healthy_count: None,
             unhealthy_count: None,
+            notification_channels: group.notification_channels.as_deref()
+                .and_then(|c| serde_json::from_str::<Vec<serde_json::Value>>(c).ok())
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|c| c["id"].as_str().map(str::to_string))
+                .collect(),
+            default_deployment_options: group.default_deployment_options
+                .and_then(|o| serde_json::from_str(&o).ok()),
+            frozen: group.frozen,
+            auto_deregister: group.auto_deregister,
+            deregister_after_secs: group.deregister_after_secs,
         }
     }
 }
@@ -313,6 +376,7 @@ impl From<WorkerGroup> for WorkerGroupResponse {
 pub enum DeploymentStatus {
     Pending,
     PendingApproval,
+    Scheduled,
     Queued,
     InProgress,
     Completed,
@@ -325,6 +389,7 @@ impl From<String> for DeploymentStatus {
         match s.to_lowercase().as_str() {
             "pending" => DeploymentStatus::Pending,
             "pending_approval" => DeploymentStatus::PendingApproval,
+            "scheduled" => DeploymentStatus::Scheduled,
             "queued" => DeploymentStatus::Queued,
             "in_progress" => DeploymentStatus::InProgress,
             "completed" => DeploymentStatus::Completed,
@@ -340,6 +405,7 @@ impl std::fmt::Display for DeploymentStatus {
         match self {
             DeploymentStatus::Pending => write!(f, "pending"),
             DeploymentStatus::PendingApproval => write!(f, "pending_approval"),
+            DeploymentStatus::Scheduled => write!(f, "scheduled"),
             DeploymentStatus::Queued => write!(f, "queued"),
             DeploymentStatus::InProgress => write!(f, "in_progress"),
             DeploymentStatus::Completed => write!(f, "completed"),
@@ -369,6 +435,8 @@ pub struct Deployment {
     pub rejected_at: Option<String>,
     pub rejection_reason: Option<String>,
     pub error: Option<String>,
+    /// When a scheduled deployment becomes due (UTC)
+    pub scheduled_at: Option<String>,
 }
 
 /// Deployment agent status (used in future deployment phase)
@@ -384,12 +452,52 @@ pub struct DeploymentAgent {
     pub error: Option<String>,
 }
 
+/// A processed `Idempotency-Key` for deployment creation
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct IdempotencyKey {
+    pub idempotency_key: String,
+    pub group_id: String,
+    /// Set once the deployment is created; None while the request is in flight
+    pub deployment_id: Option<String>,
+    /// JSON body returned for the original request
+    pub response: Option<String>,
+    /// SHA-256 of the original request body; None for keys claimed before it was recorded
+    pub request_hash: Option<String>,
+    /// When the key was claimed; an unfinished claim is abandoned once it is old enough
+    pub created_at: String,
+}
+
+/// A persisted SSO provider
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SsoProviderRecord {
+    pub id: String,
+    /// `SsoProviderConfig` JSON; never contains the client secret
+    pub config: String,
+    /// Client secret sealed with `crate::crypto::SecretCipher`
+    pub client_secret: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Provider tokens kept for an SSO login session
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SsoSession {
+    pub session_id: String,
+    pub user_id: Option<String>,
+    pub provider_id: String,
+    /// Refresh token sealed with `crate::crypto::SecretCipher`
+    pub refresh_token: String,
+    /// When the current provider access token expires (UTC)
+    pub access_token_expires_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
 // =============================================================================
 // Audit Log Models
 // =============================================================================
 
-/// Audit log entry (used in future audit phase)
-#[allow(dead_code)]
+/// Audit log entry
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct AuditLogEntry {
     pub id: String,
@@ -406,8 +514,7 @@ pub struct AuditLogEntry {
     pub result: String,
 }
 
-/// Audit log for API responses (used in future audit phase)
-#[allow(dead_code)]
+/// Audit log for API responses
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditLogResponse {
     pub id: String,
@@ -2,11 +2,28 @@
 //!
 //! Provides CRUD operations for all database models.
 
-use sqlx::SqlitePool;
+use super::DbPool;
 use uuid::Uuid;
 
 use super::models::*;
 
+/// `LIKE` pattern matching `text` anywhere, with wildcards in it escaped
+///
+/// Use with `ESCAPE '\'`.
+pub fn contains_pattern(text: &str) -> String {
+    format!("%{}%", escape_like(text))
+}
+
+/// `LIKE` pattern matching values starting with `text`, escaped like
+/// [`contains_pattern`]
+pub fn prefix_pattern(text: &str) -> String {
+    format!("{}%", escape_like(text))
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
 // =============================================================================
 // Agent Repository
 // =============================================================================
@@ -16,7 +33,7 @@ pub struct AgentRepository;
 impl AgentRepository {
     /// Create a new agent
     pub async fn create(
-        pool: &SqlitePool,
+        pool: &DbPool,
         name: &str,
         url: &str,
         group_id: Option<&str>,
@@ -26,7 +43,7 @@ impl AgentRepository {
         sqlx::query_as::<_, Agent>(
             r#"
             INSERT INTO agents (id, name, url, group_id, status)
-            VALUES (?, ?, ?, ?, 'unknown')
+            VALUES ($1, $2, $3, $4, 'unknown')
             RETURNING *
             "#
         )
@@ -39,54 +56,234 @@ impl AgentRepository {
     }
     
     /// Get agent by ID
-    pub async fn get_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Agent>, sqlx::Error> {
-        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE id = ?")
+    pub async fn get_by_id(pool: &DbPool, id: &str) -> Result<Option<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE id = $1 AND deleted_at IS NULL")
             .bind(id)
             .fetch_optional(pool)
             .await
     }
     
     /// Get agent by URL
-    pub async fn get_by_url(pool: &SqlitePool, url: &str) -> Result<Option<Agent>, sqlx::Error> {
-        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE url = ?")
+    pub async fn get_by_url(pool: &DbPool, url: &str) -> Result<Option<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE url = $1 AND deleted_at IS NULL")
             .bind(url)
             .fetch_optional(pool)
             .await
     }
     
     /// Get agent by name
-    pub async fn get_by_name(pool: &SqlitePool, name: &str) -> Result<Option<Agent>, sqlx::Error> {
-        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE name = ?")
+    ///
+    /// Names stay reserved after a soft delete, so this also returns
+    /// soft-deleted agents; check `deleted_at` before using the result.
+    pub async fn get_by_name(pool: &DbPool, name: &str) -> Result<Option<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE name = $1")
             .bind(name)
             .fetch_optional(pool)
             .await
     }
     
     /// List all agents
-    pub async fn list(pool: &SqlitePool) -> Result<Vec<Agent>, sqlx::Error> {
+    pub async fn list(pool: &DbPool) -> Result<Vec<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE deleted_at IS NULL ORDER BY name")
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// List all agents, including soft-deleted ones (for audit views)
+    pub async fn list_including_deleted(pool: &DbPool) -> Result<Vec<Agent>, sqlx::Error> {
         sqlx::query_as::<_, Agent>("SELECT * FROM agents ORDER BY name")
             .fetch_all(pool)
             .await
     }
     
+    /// List a page of agents ordered by name, then ID, so pages never overlap
+    pub async fn list_paginated(
+        pool: &DbPool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE deleted_at IS NULL ORDER BY name, id LIMIT $1 OFFSET $2")
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// List agents in a group and/or carrying all of `tags`, by name
+    ///
+    /// Soft-deleted agents are only included with `include_deleted`.
+    pub async fn list_filtered(
+        pool: &DbPool,
+        group_id: Option<&str>,
+        tags: &[(String, String)],
+        include_deleted: bool,
+    ) -> Result<Vec<Agent>, sqlx::Error> {
+        Self::list_chunk(pool, group_id, tags, include_deleted, None, i64::MAX).await
+    }
+    
+    /// List the next chunk of agents after the `(name, id)` cursor
+    ///
+    /// Agents are ordered by name, then ID, so a caller can read a large
+    /// listing chunk by chunk, passing the last agent of each chunk as the
+    /// cursor for the next. Filters match `list_by_group` and `list_by_tag`.
+    pub async fn list_chunk(
+        pool: &DbPool,
+        group_id: Option<&str>,
+        tags: &[(String, String)],
+        include_deleted: bool,
+        after: Option<(&str, &str)>,
+        limit: i64,
+    ) -> Result<Vec<Agent>, sqlx::Error> {
+        let mut query = String::from("SELECT * FROM agents WHERE 1 = 1");
+        let mut param = 0;
+        let mut next_param = || {
+            param += 1;
+            param
+        };
+    
+        if !include_deleted {
+            query.push_str(" AND deleted_at IS NULL");
+        }
+        if group_id.is_some() {
+            query.push_str(&format!(" AND group_id = ${}", next_param()));
+        }
+        for _ in tags {
+            query.push_str(&format!(
+                " AND id IN (SELECT agent_id FROM agent_tags WHERE key = ${} AND value = ${})",
+                next_param(),
+                next_param()
+            ));
+        }
+        if after.is_some() {
+            let (name, id) = (next_param(), next_param());
+            query.push_str(&format!(" AND (name > ${name} OR (name = ${name} AND id > ${id}))"));
+        }
+        query.push_str(&format!(" ORDER BY name, id LIMIT ${}", next_param()));
+    
+        let mut q = sqlx::query_as::<_, Agent>(&query);
+        if let Some(group_id) = group_id {
+            q = q.bind(group_id);
+        }
+        for (key, value) in tags {
+            q = q.bind(key).bind(value);
+        }
+        if let Some((name, id)) = after {
+            q = q.bind(name).bind(id);
+        }
+        q.bind(limit).fetch_all(pool).await
+    }
+    
+    /// Agents whose name, URL, or a tag (`key`, `value` or `key=value`)
+    /// contains `query`, ignoring case
+    ///
+    /// Exact matches come first, then prefix matches, then the rest, each
+    /// by name. Ranking happens before `limit` is applied.
+    pub async fn search(pool: &DbPool, query: &str, limit: i64) -> Result<Vec<Agent>, sqlx::Error> {
+        let query = query.to_lowercase();
+        sqlx::query_as::<_, Agent>(
+            r#"
+            SELECT * FROM agents
+            WHERE deleted_at IS NULL
+              AND (lower(name) LIKE $1 ESCAPE '\' OR lower(url) LIKE $1 ESCAPE '\'
+                   OR id IN (
+                       SELECT agent_id FROM agent_tags
+                       WHERE lower(key) LIKE $1 ESCAPE '\' OR lower(value) LIKE $1 ESCAPE '\'
+                          OR lower(key || '=' || value) LIKE $1 ESCAPE '\'
+                   ))
+            ORDER BY CASE
+                WHEN lower(name) = $2 OR lower(url) = $2
+                     OR id IN (
+                         SELECT agent_id FROM agent_tags
+                         WHERE lower(key) = $2 OR lower(value) = $2
+                            OR lower(key || '=' || value) = $2
+                     ) THEN 0
+                WHEN lower(name) LIKE $3 ESCAPE '\' OR lower(url) LIKE $3 ESCAPE '\'
+                     OR id IN (
+                         SELECT agent_id FROM agent_tags
+                         WHERE lower(key) LIKE $3 ESCAPE '\' OR lower(value) LIKE $3 ESCAPE '\'
+                            OR lower(key || '=' || value) LIKE $3 ESCAPE '\'
+                     ) THEN 1
+                ELSE 2
+            END, name
+            LIMIT $4
+            "#
+        )
+        .bind(contains_pattern(&query))
+        .bind(&query)
+        .bind(prefix_pattern(&query))
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Count all agents
+    pub async fn count(pool: &DbPool) -> Result<i64, sqlx::Error> {
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE deleted_at IS NULL")
+            .fetch_one(pool)
+            .await?;
+        Ok(count.0)
+    }
+    
     /// List agents by group
-    pub async fn list_by_group(pool: &SqlitePool, group_id: &str) -> Result<Vec<Agent>, sqlx::Error> {
-        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE group_id = ? ORDER BY name")
+    pub async fn list_by_group(pool: &DbPool, group_id: &str) -> Result<Vec<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE group_id = $1 AND deleted_at IS NULL ORDER BY name")
             .bind(group_id)
             .fetch_all(pool)
             .await
     }
     
+    /// Replace an agent's TLS settings for its Vector API
+    ///
+    /// The client key must already be sealed with `AgentTls::seal`. Pass
+    /// `AgentTls::default()` to clear the settings.
+    pub async fn set_tls(
+        pool: &DbPool,
+        id: &str,
+        tls: &crate::agent_clients::AgentTls,
+    ) -> Result<Option<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>(
+            r#"
+            UPDATE agents SET tls_ca_cert = $1, tls_client_cert = $2, tls_client_key = $3, tls_insecure_skip_verify = $4
+            WHERE id = $5 AND deleted_at IS NULL
+            RETURNING *
+            "#
+        )
+        .bind(&tls.ca_cert)
+        .bind(&tls.client_cert)
+        .bind(&tls.client_key)
+        .bind(tls.insecure_skip_verify)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+    
+    /// List agents with a stored TLS client key, including deleted ones
+    pub async fn list_with_tls_client_key(pool: &DbPool) -> Result<Vec<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE tls_client_key IS NOT NULL ORDER BY name")
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// Replace only an agent's TLS client key, which must already be sealed
+    pub async fn set_tls_client_key(pool: &DbPool, id: &str, client_key: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE agents SET tls_client_key = $1 WHERE id = $2")
+            .bind(client_key)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+    
     /// List unassigned agents (no group)
-    pub async fn list_unassigned(pool: &SqlitePool) -> Result<Vec<Agent>, sqlx::Error> {
-        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE group_id IS NULL ORDER BY name")
+    pub async fn list_unassigned(pool: &DbPool) -> Result<Vec<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>("SELECT * FROM agents WHERE group_id IS NULL AND deleted_at IS NULL ORDER BY name")
             .fetch_all(pool)
             .await
     }
     
     /// Update agent
     pub async fn update(
-        pool: &SqlitePool,
+        pool: &DbPool,
         id: &str,
         name: Option<&str>,
         group_id: Option<Option<&str>>,  // None = don't update, Some(None) = set to NULL
@@ -105,8 +302,8 @@ impl AgentRepository {
         
         sqlx::query_as::<_, Agent>(
             r#"
-            UPDATE agents SET name = ?, group_id = ?
-            WHERE id = ?
+            UPDATE agents SET name = $1, group_id = $2
+            WHERE id = $3
             RETURNING *
             "#
         )
@@ -117,9 +314,29 @@ impl AgentRepository {
         .await
     }
     
+    /// Move an agent to another group (`None` unassigns it)
+    ///
+    /// Keeps the agent's id, so its health and deployment history follow it.
+    pub async fn set_group(
+        pool: &DbPool,
+        id: &str,
+        group_id: Option<&str>,
+    ) -> Result<Option<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>(
+            "UPDATE agents SET group_id = $1 WHERE id = $2 AND deleted_at IS NULL RETURNING *"
+        )
+        .bind(group_id)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+    
     /// Update agent status
+    ///
+    /// `last_seen` only moves when the agent was found healthy, so failed
+    /// polls don't hide an agent that has gone quiet.
     pub async fn update_status(
-        pool: &SqlitePool,
+        pool: &DbPool,
         id: &str,
         status: &str,
         vector_version: Option<&str>,
@@ -127,8 +344,9 @@ impl AgentRepository {
         sqlx::query(
             r#"
             UPDATE agents 
-            SET status = ?, vector_version = ?, last_seen = datetime('now')
-            WHERE id = ?
+            SET status = $1, vector_version = $2,
+                last_seen = CASE WHEN $1 = 'healthy' THEN CURRENT_TIMESTAMP ELSE last_seen END
+            WHERE id = $3
             "#
         )
         .bind(status)
@@ -139,18 +357,221 @@ impl AgentRepository {
         Ok(())
     }
     
-    /// Delete agent
-    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
-        let result = sqlx::query("DELETE FROM agents WHERE id = ?")
+    /// Record a heartbeat from the agent's sidecar
+    ///
+    /// Bumps `last_seen` and, if the sidecar reported it, sets the status.
+    /// Without a reported status an agent marked unreachable goes back to
+    /// `unknown` until the next poll.
+    pub async fn heartbeat(
+        pool: &DbPool,
+        id: &str,
+        status: Option<&str>,
+    ) -> Result<Option<Agent>, sqlx::Error> {
+        sqlx::query_as::<_, Agent>(
+            r#"
+            UPDATE agents
+            SET last_seen = CURRENT_TIMESTAMP,
+                status = COALESCE($1, CASE WHEN status = 'unreachable' THEN 'unknown' ELSE status END)
+            WHERE id = $2 AND deleted_at IS NULL
+            RETURNING *
+            "#
+        )
+        .bind(status)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+    
+    /// Mark agents not seen since `cutoff` as unreachable
+    ///
+    /// Agents never seen count from their registration. Returns the agents
+    /// as they were before the change.
+    pub async fn mark_unreachable_since(pool: &DbPool, cutoff: &str) -> Result<Vec<Agent>, sqlx::Error> {
+        let stale = sqlx::query_as::<_, Agent>(
+            r#"
+            SELECT * FROM agents
+            WHERE deleted_at IS NULL AND status != 'unreachable'
+              AND COALESCE(last_seen, registered_at) < $1
+            "#
+        )
+        .bind(cutoff)
+        .fetch_all(pool)
+        .await?;
+        
+        // Re-check the cutoff so a heartbeat landing in between wins
+        let mut marked = Vec::new();
+        for agent in stale {
+            let result = sqlx::query(
+                "UPDATE agents SET status = 'unreachable' WHERE id = $1 AND COALESCE(last_seen, registered_at) < $2"
+            )
+            .bind(&agent.id)
+            .bind(cutoff)
+            .execute(pool)
+            .await?;
+            if result.rows_affected() > 0 {
+                marked.push(agent);
+            }
+        }
+        Ok(marked)
+    }
+    
+    /// Delete agent (soft delete)
+    ///
+    /// The row, its health checks and deployment history are kept; the agent
+    /// is just hidden from normal queries.
+    pub async fn delete(pool: &DbPool, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE agents SET deleted_at = CURRENT_TIMESTAMP WHERE id = $1 AND deleted_at IS NULL"
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Soft-delete an agent not seen since `cutoff`
+    ///
+    /// Checks `last_seen` in the same statement, so an agent that heartbeats
+    /// just before is kept.
+    pub async fn deregister_unseen_since(pool: &DbPool, id: &str, cutoff: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE agents SET deleted_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND deleted_at IS NULL AND COALESCE(last_seen, registered_at) < $2
+            "#
+        )
+        .bind(id)
+        .bind(cutoff)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Restore a soft-deleted agent
+    pub async fn restore(pool: &DbPool, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE agents SET deleted_at = NULL WHERE id = $1 AND deleted_at IS NOT NULL")
             .bind(id)
             .execute(pool)
             .await?;
         Ok(result.rows_affected() > 0)
     }
     
+    /// Permanently delete an agent and its history
+    pub async fn purge(pool: &DbPool, id: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        
+        sqlx::query("DELETE FROM deployment_agents WHERE agent_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        
+        // Health checks and tags are removed by ON DELETE CASCADE
+        let result = sqlx::query("DELETE FROM agents WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Replace all tags for an agent
+    pub async fn set_tags(
+        pool: &DbPool,
+        agent_id: &str,
+        tags: &[(String, String)],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        
+        sqlx::query("DELETE FROM agent_tags WHERE agent_id = $1")
+            .bind(agent_id)
+            .execute(&mut *tx)
+            .await?;
+        
+        for (key, value) in tags {
+            sqlx::query(
+                r#"
+                INSERT INTO agent_tags (agent_id, key, value)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (agent_id, key) DO UPDATE SET value = excluded.value
+                "#
+            )
+            .bind(agent_id)
+            .bind(key)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await
+    }
+    
+    /// Get tags for an agent
+    pub async fn get_tags(pool: &DbPool, agent_id: &str) -> Result<Vec<AgentTag>, sqlx::Error> {
+        sqlx::query_as::<_, AgentTag>("SELECT * FROM agent_tags WHERE agent_id = $1 ORDER BY key")
+            .bind(agent_id)
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// Tags of each of `agent_ids`, keyed by agent ID
+    ///
+    /// Agents without tags are absent.
+    pub async fn get_tags_for(
+        pool: &DbPool,
+        agent_ids: &[String],
+    ) -> Result<std::collections::HashMap<String, Vec<AgentTag>>, sqlx::Error> {
+        if agent_ids.is_empty() {
+            return Ok(Default::default());
+        }
+        
+        let placeholders: Vec<String> = (1..=agent_ids.len()).map(|i| format!("${}", i)).collect();
+        let query = format!(
+            "SELECT * FROM agent_tags WHERE agent_id IN ({}) ORDER BY agent_id, key",
+            placeholders.join(", ")
+        );
+        
+        let mut q = sqlx::query_as::<_, AgentTag>(&query);
+        for id in agent_ids {
+            q = q.bind(id);
+        }
+        
+        let mut tags: std::collections::HashMap<String, Vec<AgentTag>> = Default::default();
+        for tag in q.fetch_all(pool).await? {
+            tags.entry(tag.agent_id.clone()).or_default().push(tag);
+        }
+        Ok(tags)
+    }
+    
+    /// List agents that have all of the given tags
+    pub async fn list_by_tag(
+        pool: &DbPool,
+        tags: &[(String, String)],
+    ) -> Result<Vec<Agent>, sqlx::Error> {
+        let mut query = String::from("SELECT * FROM agents WHERE deleted_at IS NULL");
+        
+        for i in 0..tags.len() {
+            query.push_str(&format!(
+                " AND id IN (SELECT agent_id FROM agent_tags WHERE key = ${} AND value = ${})",
+                i * 2 + 1,
+                i * 2 + 2
+            ));
+        }
+        
+        query.push_str(" ORDER BY name");
+        
+        let mut q = sqlx::query_as::<_, Agent>(&query);
+        
+        for (key, value) in tags {
+            q = q.bind(key).bind(value);
+        }
+        
+        q.fetch_all(pool).await
+    }
+    
     /// Record health check
     pub async fn record_health_check(
-        pool: &SqlitePool,
+        pool: &DbPool,
         agent_id: &str,
         healthy: bool,
         latency_ms: Option<i64>,
@@ -159,7 +580,7 @@ impl AgentRepository {
         sqlx::query(
             r#"
             INSERT INTO health_checks (agent_id, healthy, latency_ms, error)
-            VALUES (?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4)
             "#
         )
         .bind(agent_id)
@@ -173,16 +594,16 @@ impl AgentRepository {
     
     /// Get recent health checks for an agent
     pub async fn get_health_checks(
-        pool: &SqlitePool,
+        pool: &DbPool,
         agent_id: &str,
         limit: i64,
     ) -> Result<Vec<HealthCheck>, sqlx::Error> {
         sqlx::query_as::<_, HealthCheck>(
             r#"
             SELECT * FROM health_checks 
-            WHERE agent_id = ? 
+            WHERE agent_id = $1 
             ORDER BY checked_at DESC 
-            LIMIT ?
+            LIMIT $2
             "#
         )
         .bind(agent_id)
@@ -190,6 +611,93 @@ impl AgentRepository {
         .fetch_all(pool)
         .await
     }
+    
+    /// Count an agent's health checks recorded at or after `since`
+    pub async fn uptime_counts(
+        pool: &DbPool,
+        agent_id: &str,
+        since: &str,
+    ) -> Result<UptimeCounts, sqlx::Error> {
+        let (healthy_checks, total_checks): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM(CASE WHEN healthy THEN 1 ELSE 0 END), 0), COUNT(*)
+            FROM health_checks
+            WHERE agent_id = $1 AND checked_at >= $2
+            "#
+        )
+        .bind(agent_id)
+        .bind(since)
+        .fetch_one(pool)
+        .await?;
+        Ok(UptimeCounts { healthy_checks, total_checks })
+    }
+    
+    /// Percentage of an agent's health checks since `since` that were healthy
+    ///
+    /// None if no checks were recorded in the window.
+    pub async fn uptime_percentage(
+        pool: &DbPool,
+        agent_id: &str,
+        since: &str,
+    ) -> Result<Option<f64>, sqlx::Error> {
+        Ok(Self::uptime_counts(pool, agent_id, since).await?.percentage())
+    }
+    
+    /// Health check counts since `since` for each active agent in a group,
+    /// as (agent id, agent name, counts), ordered by name
+    pub async fn group_uptime_counts(
+        pool: &DbPool,
+        group_id: &str,
+        since: &str,
+    ) -> Result<Vec<(String, String, UptimeCounts)>, sqlx::Error> {
+        let rows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT a.id, a.name,
+                   COALESCE(SUM(CASE WHEN h.healthy THEN 1 ELSE 0 END), 0),
+                   COUNT(h.id)
+            FROM agents a
+            LEFT JOIN health_checks h ON h.agent_id = a.id AND h.checked_at >= $2
+            WHERE a.group_id = $1 AND a.deleted_at IS NULL
+            GROUP BY a.id, a.name
+            ORDER BY a.name
+            "#
+        )
+        .bind(group_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+        
+        Ok(rows.into_iter()
+            .map(|(id, name, healthy_checks, total_checks)| {
+                (id, name, UptimeCounts { healthy_checks, total_checks })
+            })
+            .collect())
+    }
+}
+
+/// Healthy and total health checks over a time window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+pub struct UptimeCounts {
+    pub healthy_checks: i64,
+    pub total_checks: i64,
+}
+
+impl UptimeCounts {
+    /// Healthy checks as a percentage of all checks, or None without checks
+    pub fn percentage(&self) -> Option<f64> {
+        (self.total_checks > 0).then(|| self.healthy_checks as f64 * 100.0 / self.total_checks as f64)
+    }
+}
+
+impl std::ops::Add for UptimeCounts {
+    type Output = Self;
+    
+    fn add(self, other: Self) -> Self {
+        Self {
+            healthy_checks: self.healthy_checks + other.healthy_checks,
+            total_checks: self.total_checks + other.total_checks,
+        }
+    }
 }
 
 // =============================================================================
@@ -201,7 +709,7 @@ pub struct WorkerGroupRepository;
 impl WorkerGroupRepository {
     /// Create a new worker group
     pub async fn create(
-        pool: &SqlitePool,
+        pool: &DbPool,
         name: &str,
         description: Option<&str>,
         created_by: Option<&str>,
@@ -211,7 +719,7 @@ impl WorkerGroupRepository {
         sqlx::query_as::<_, WorkerGroup>(
             r#"
             INSERT INTO worker_groups (id, name, description, created_by)
-            VALUES (?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4)
             RETURNING *
             "#
         )
@@ -224,31 +732,77 @@ impl WorkerGroupRepository {
     }
     
     /// Get group by ID
-    pub async fn get_by_id(pool: &SqlitePool, id: &str) -> Result<Option<WorkerGroup>, sqlx::Error> {
-        sqlx::query_as::<_, WorkerGroup>("SELECT * FROM worker_groups WHERE id = ?")
+    pub async fn get_by_id(pool: &DbPool, id: &str) -> Result<Option<WorkerGroup>, sqlx::Error> {
+        sqlx::query_as::<_, WorkerGroup>("SELECT * FROM worker_groups WHERE id = $1")
             .bind(id)
             .fetch_optional(pool)
             .await
     }
     
     /// Get group by name
-    pub async fn get_by_name(pool: &SqlitePool, name: &str) -> Result<Option<WorkerGroup>, sqlx::Error> {
-        sqlx::query_as::<_, WorkerGroup>("SELECT * FROM worker_groups WHERE name = ?")
+    pub async fn get_by_name(pool: &DbPool, name: &str) -> Result<Option<WorkerGroup>, sqlx::Error> {
+        sqlx::query_as::<_, WorkerGroup>("SELECT * FROM worker_groups WHERE name = $1")
             .bind(name)
             .fetch_optional(pool)
             .await
     }
     
     /// List all groups
-    pub async fn list(pool: &SqlitePool) -> Result<Vec<WorkerGroup>, sqlx::Error> {
+    pub async fn list(pool: &DbPool) -> Result<Vec<WorkerGroup>, sqlx::Error> {
         sqlx::query_as::<_, WorkerGroup>("SELECT * FROM worker_groups ORDER BY name")
             .fetch_all(pool)
             .await
     }
     
+    /// List a page of groups ordered by name
+    pub async fn list_paginated(
+        pool: &DbPool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<WorkerGroup>, sqlx::Error> {
+        sqlx::query_as::<_, WorkerGroup>("SELECT * FROM worker_groups ORDER BY name LIMIT $1 OFFSET $2")
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// Groups whose name contains `query`, ignoring case
+    ///
+    /// Ranked like [`AgentRepository::search`] before `limit` is applied.
+    pub async fn search(pool: &DbPool, query: &str, limit: i64) -> Result<Vec<WorkerGroup>, sqlx::Error> {
+        let query = query.to_lowercase();
+        sqlx::query_as::<_, WorkerGroup>(
+            r#"
+            SELECT * FROM worker_groups
+            WHERE lower(name) LIKE $1 ESCAPE '\'
+            ORDER BY CASE
+                WHEN lower(name) = $2 THEN 0
+                WHEN lower(name) LIKE $3 ESCAPE '\' THEN 1
+                ELSE 2
+            END, name
+            LIMIT $4
+            "#
+        )
+        .bind(contains_pattern(&query))
+        .bind(&query)
+        .bind(prefix_pattern(&query))
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Count all groups
+    pub async fn count(pool: &DbPool) -> Result<i64, sqlx::Error> {
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM worker_groups")
+            .fetch_one(pool)
+            .await?;
+        Ok(count.0)
+    }
+    
     /// Update group
     pub async fn update(
-        pool: &SqlitePool,
+        pool: &DbPool,
         id: &str,
         name: Option<&str>,
         description: Option<Option<&str>>,
@@ -265,13 +819,13 @@ impl WorkerGroupRepository {
         sqlx::query_as::<_, WorkerGroup>(
             r#"
             UPDATE worker_groups SET 
-                name = ?,
-                description = ?,
-                deployment_strategy = ?,
-                requires_approval = ?,
-                approvers = ?,
-                updated_at = datetime('now')
-            WHERE id = ?
+                name = $1,
+                description = $2,
+                deployment_strategy = $3,
+                requires_approval = $4,
+                approvers = $5,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $6
             RETURNING *
             "#
         )
@@ -285,17 +839,101 @@ impl WorkerGroupRepository {
         .await
     }
     
+    /// Replace the alert channels notified about the group's deployments
+    ///
+    /// `channels` is a JSON array of `NotificationChannel`; `None` clears them.
+    pub async fn set_notification_channels(
+        pool: &DbPool,
+        id: &str,
+        channels: Option<&str>,
+    ) -> Result<Option<WorkerGroup>, sqlx::Error> {
+        sqlx::query_as::<_, WorkerGroup>(
+            r#"
+            UPDATE worker_groups SET notification_channels = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $2
+            RETURNING *
+            "#
+        )
+        .bind(channels)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+    
+    /// Replace the group's default deployment options
+    ///
+    /// `options` is `DeploymentOptions` JSON; `None` clears the defaults.
+    pub async fn set_default_deployment_options(
+        pool: &DbPool,
+        id: &str,
+        options: Option<&str>,
+    ) -> Result<Option<WorkerGroup>, sqlx::Error> {
+        sqlx::query_as::<_, WorkerGroup>(
+            r#"
+            UPDATE worker_groups SET default_deployment_options = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $2
+            RETURNING *
+            "#
+        )
+        .bind(options)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+    
+    /// Freeze or unfreeze deployments to the group
+    pub async fn set_frozen(
+        pool: &DbPool,
+        id: &str,
+        frozen: bool,
+    ) -> Result<Option<WorkerGroup>, sqlx::Error> {
+        sqlx::query_as::<_, WorkerGroup>(
+            r#"
+            UPDATE worker_groups SET frozen = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $2
+            RETURNING *
+            "#
+        )
+        .bind(frozen)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+    
+    /// Override auto-deregistration of the group's agents
+    ///
+    /// `None` for either setting falls back to the server-wide one.
+    pub async fn set_auto_deregister(
+        pool: &DbPool,
+        id: &str,
+        enabled: Option<bool>,
+        after_secs: Option<i64>,
+    ) -> Result<Option<WorkerGroup>, sqlx::Error> {
+        sqlx::query_as::<_, WorkerGroup>(
+            r#"
+            UPDATE worker_groups SET auto_deregister = $1, deregister_after_secs = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $3
+            RETURNING *
+            "#
+        )
+        .bind(enabled)
+        .bind(after_secs)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+    
     /// Update group config version
     pub async fn update_config_version(
-        pool: &SqlitePool,
+        pool: &DbPool,
         id: &str,
         version: &str,
     ) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
             UPDATE worker_groups 
-            SET current_config_version = ?, updated_at = datetime('now')
-            WHERE id = ?
+            SET current_config_version = $1, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $2
             "#
         )
         .bind(version)
@@ -306,17 +944,40 @@ impl WorkerGroupRepository {
     }
     
     /// Delete group
-    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
-        let result = sqlx::query("DELETE FROM worker_groups WHERE id = ?")
+    ///
+    /// Agents in the group (including soft-deleted ones) are unassigned, and
+    /// the group's deployment history is removed with it. Callers should
+    /// check for active deployments first.
+    pub async fn delete(pool: &DbPool, id: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        
+        sqlx::query("UPDATE agents SET group_id = NULL WHERE group_id = $1")
             .bind(id)
-            .execute(pool)
+            .execute(&mut *tx)
             .await?;
+        
+        sqlx::query("DELETE FROM deployment_agents WHERE deployment_id IN (SELECT id FROM deployments WHERE group_id = $1)")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        
+        sqlx::query("DELETE FROM deployments WHERE group_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        
+        let result = sqlx::query("DELETE FROM worker_groups WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        
+        tx.commit().await?;
         Ok(result.rows_affected() > 0)
     }
     
     /// Get agent count for group
-    pub async fn get_agent_count(pool: &SqlitePool, id: &str) -> Result<i64, sqlx::Error> {
-        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = ?")
+    pub async fn get_agent_count(pool: &DbPool, id: &str) -> Result<i64, sqlx::Error> {
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = $1 AND deleted_at IS NULL")
             .bind(id)
             .fetch_one(pool)
             .await?;
@@ -325,18 +986,18 @@ impl WorkerGroupRepository {
     
     /// Get agent health counts for a group
     /// Returns (total, healthy, unhealthy)
-    pub async fn get_agent_health_counts(pool: &SqlitePool, group_id: &str) -> Result<(i64, i64, i64), sqlx::Error> {
-        let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = ?")
+    pub async fn get_agent_health_counts(pool: &DbPool, group_id: &str) -> Result<(i64, i64, i64), sqlx::Error> {
+        let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = $1 AND deleted_at IS NULL")
             .bind(group_id)
             .fetch_one(pool)
             .await?;
             
-        let healthy: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = ? AND status = 'healthy'")
+        let healthy: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = $1 AND status = 'healthy' AND deleted_at IS NULL")
             .bind(group_id)
             .fetch_one(pool)
             .await?;
             
-        let unhealthy: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = ? AND status = 'unhealthy'")
+        let unhealthy: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE group_id = $1 AND status = 'unhealthy' AND deleted_at IS NULL")
             .bind(group_id)
             .fetch_one(pool)
             .await?;
@@ -354,7 +1015,7 @@ pub struct UserRepository;
 impl UserRepository {
     /// Create a new user
     pub async fn create(
-        pool: &SqlitePool,
+        pool: &DbPool,
         username: &str,
         email: &str,
         password_hash: &str,
@@ -365,7 +1026,7 @@ impl UserRepository {
         sqlx::query_as::<_, User>(
             r#"
             INSERT INTO users (id, username, email, password_hash, sso_provider, role_id)
-            VALUES (?, ?, ?, ?, 'local', ?)
+            VALUES ($1, $2, $3, $4, 'local', $5)
             RETURNING *
             "#
         )
@@ -380,24 +1041,24 @@ impl UserRepository {
     
     /// Get user by ID (used in future auth phase)
     #[allow(dead_code)]
-    pub async fn get_by_id(pool: &SqlitePool, id: &str) -> Result<Option<User>, sqlx::Error> {
-        sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?")
+    pub async fn get_by_id(pool: &DbPool, id: &str) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
             .bind(id)
             .fetch_optional(pool)
             .await
     }
     
     /// Get user by username
-    pub async fn get_by_username(pool: &SqlitePool, username: &str) -> Result<Option<User>, sqlx::Error> {
-        sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = ?")
+    pub async fn get_by_username(pool: &DbPool, username: &str) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
             .bind(username)
             .fetch_optional(pool)
             .await
     }
     
     /// Get user by email
-    pub async fn get_by_email(pool: &SqlitePool, email: &str) -> Result<Option<User>, sqlx::Error> {
-        sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = ?")
+    pub async fn get_by_email(pool: &DbPool, email: &str) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
             .bind(email)
             .fetch_optional(pool)
             .await
@@ -405,15 +1066,15 @@ impl UserRepository {
     
     /// List all users (used in future auth phase)
     #[allow(dead_code)]
-    pub async fn list(pool: &SqlitePool) -> Result<Vec<User>, sqlx::Error> {
+    pub async fn list(pool: &DbPool) -> Result<Vec<User>, sqlx::Error> {
         sqlx::query_as::<_, User>("SELECT * FROM users ORDER BY username")
             .fetch_all(pool)
             .await
     }
     
     /// Update last login
-    pub async fn update_last_login(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE users SET last_login = datetime('now') WHERE id = ?")
+    pub async fn update_last_login(pool: &DbPool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE users SET last_login = CURRENT_TIMESTAMP WHERE id = $1")
             .bind(id)
             .execute(pool)
             .await?;
@@ -422,8 +1083,8 @@ impl UserRepository {
     
     /// Delete user (used in future auth phase)
     #[allow(dead_code)]
-    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
-        let result = sqlx::query("DELETE FROM users WHERE id = ?")
+    pub async fn delete(pool: &DbPool, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM users WHERE id = $1")
             .bind(id)
             .execute(pool)
             .await?;
@@ -441,15 +1102,15 @@ pub struct RoleRepository;
 #[allow(dead_code)]
 impl RoleRepository {
     /// Get role by ID
-    pub async fn get_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Role>, sqlx::Error> {
-        sqlx::query_as::<_, Role>("SELECT * FROM roles WHERE id = ?")
+    pub async fn get_by_id(pool: &DbPool, id: &str) -> Result<Option<Role>, sqlx::Error> {
+        sqlx::query_as::<_, Role>("SELECT * FROM roles WHERE id = $1")
             .bind(id)
             .fetch_optional(pool)
             .await
     }
     
     /// List all roles
-    pub async fn list(pool: &SqlitePool) -> Result<Vec<Role>, sqlx::Error> {
+    pub async fn list(pool: &DbPool) -> Result<Vec<Role>, sqlx::Error> {
         sqlx::query_as::<_, Role>("SELECT * FROM roles ORDER BY name")
             .fetch_all(pool)
             .await
@@ -457,7 +1118,7 @@ impl RoleRepository {
     
     /// Create a custom role
     pub async fn create(
-        pool: &SqlitePool,
+        pool: &DbPool,
         name: &str,
         description: Option<&str>,
         permissions: &[String],
@@ -468,7 +1129,7 @@ impl RoleRepository {
         sqlx::query_as::<_, Role>(
             r#"
             INSERT INTO roles (id, name, description, permissions, is_builtin)
-            VALUES (?, ?, ?, ?, 0)
+            VALUES ($1, $2, $3, $4, FALSE)
             RETURNING *
             "#
         )
@@ -481,8 +1142,8 @@ impl RoleRepository {
     }
     
     /// Delete a custom role (cannot delete built-in roles)
-    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
-        let result = sqlx::query("DELETE FROM roles WHERE id = ? AND is_builtin = 0")
+    pub async fn delete(pool: &DbPool, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM roles WHERE id = $1 AND is_builtin = FALSE")
             .bind(id)
             .execute(pool)
             .await?;
@@ -491,21 +1152,23 @@ impl RoleRepository {
 }
 
 // =============================================================================
-// API Key Repository (used in future auth phase)
+// API Key Repository
 // =============================================================================
 
-#[allow(dead_code)]
 pub struct ApiKeyRepository;
 
-#[allow(dead_code)]
 impl ApiKeyRepository {
-    /// Create a new API key (returns the full key only once)
+    /// Create a new API key record
+    ///
+    /// Only the hash is stored; the caller returns the raw key to the user once.
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
-        pool: &SqlitePool,
+        pool: &DbPool,
         name: &str,
         key_hash: &str,
         key_prefix: &str,
         user_id: Option<&str>,
+        role_id: Option<&str>,
         permissions: Option<&str>,
         expires_at: Option<&str>,
     ) -> Result<ApiKey, sqlx::Error> {
@@ -513,8 +1176,8 @@ impl ApiKeyRepository {
         
         sqlx::query_as::<_, ApiKey>(
             r#"
-            INSERT INTO api_keys (id, name, key_hash, key_prefix, user_id, permissions, expires_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO api_keys (id, name, key_hash, key_prefix, user_id, role_id, permissions, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING *
             "#
         )
@@ -523,16 +1186,32 @@ impl ApiKeyRepository {
         .bind(key_hash)
         .bind(key_prefix)
         .bind(user_id)
+        .bind(role_id)
         .bind(permissions)
         .bind(expires_at)
         .fetch_one(pool)
         .await
     }
     
+    /// Get API key by ID
+    pub async fn get_by_id(pool: &DbPool, id: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+    
+    /// List all API keys, including revoked ones
+    pub async fn list(pool: &DbPool) -> Result<Vec<ApiKey>, sqlx::Error> {
+        sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys ORDER BY created_at DESC")
+            .fetch_all(pool)
+            .await
+    }
+    
     /// Get API key by hash (for authentication)
-    pub async fn get_by_hash(pool: &SqlitePool, key_hash: &str) -> Result<Option<ApiKey>, sqlx::Error> {
+    pub async fn get_by_hash(pool: &DbPool, key_hash: &str) -> Result<Option<ApiKey>, sqlx::Error> {
         sqlx::query_as::<_, ApiKey>(
-            "SELECT * FROM api_keys WHERE key_hash = ? AND revoked_at IS NULL"
+            "SELECT * FROM api_keys WHERE key_hash = $1 AND revoked_at IS NULL"
         )
         .bind(key_hash)
         .fetch_optional(pool)
@@ -540,9 +1219,9 @@ impl ApiKeyRepository {
     }
     
     /// List API keys for a user
-    pub async fn list_by_user(pool: &SqlitePool, user_id: &str) -> Result<Vec<ApiKey>, sqlx::Error> {
+    pub async fn list_by_user(pool: &DbPool, user_id: &str) -> Result<Vec<ApiKey>, sqlx::Error> {
         sqlx::query_as::<_, ApiKey>(
-            "SELECT * FROM api_keys WHERE user_id = ? ORDER BY created_at DESC"
+            "SELECT * FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC"
         )
         .bind(user_id)
         .fetch_all(pool)
@@ -550,8 +1229,8 @@ impl ApiKeyRepository {
     }
     
     /// Update last used timestamp
-    pub async fn update_last_used(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE api_keys SET last_used = datetime('now') WHERE id = ?")
+    pub async fn update_last_used(pool: &DbPool, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE api_keys SET last_used = CURRENT_TIMESTAMP WHERE id = $1")
             .bind(id)
             .execute(pool)
             .await?;
@@ -559,9 +1238,9 @@ impl ApiKeyRepository {
     }
     
     /// Revoke an API key
-    pub async fn revoke(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    pub async fn revoke(pool: &DbPool, id: &str) -> Result<bool, sqlx::Error> {
         let result = sqlx::query(
-            "UPDATE api_keys SET revoked_at = datetime('now') WHERE id = ? AND revoked_at IS NULL"
+            "UPDATE api_keys SET revoked_at = CURRENT_TIMESTAMP WHERE id = $1 AND revoked_at IS NULL"
         )
         .bind(id)
         .execute(pool)
@@ -571,17 +1250,16 @@ impl ApiKeyRepository {
 }
 
 // =============================================================================
-// Audit Log Repository (used in future audit phase)
+// Audit Log Repository
 // =============================================================================
 
-#[allow(dead_code)]
 pub struct AuditLogRepository;
 
-#[allow(dead_code)]
 impl AuditLogRepository {
     /// Create audit log entry
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
-        pool: &SqlitePool,
+        pool: &DbPool,
         actor_type: &str,
         actor_id: Option<&str>,
         actor_name: Option<&str>,
@@ -601,7 +1279,7 @@ impl AuditLogRepository {
                 id, actor_type, actor_id, actor_name, action, 
                 resource_type, resource_id, details, ip_address, user_agent, result
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             RETURNING *
             "#
         )
@@ -620,42 +1298,82 @@ impl AuditLogRepository {
         .await
     }
     
-    /// List audit log entries with optional filters
+    /// List audit log entries with optional filters, newest first
+    ///
+    /// `user` matches either the actor ID or the actor name.
     pub async fn list(
-        pool: &SqlitePool,
+        pool: &DbPool,
         actor_id: Option<&str>,
+        user: Option<&str>,
         action: Option<&str>,
         resource_type: Option<&str>,
         limit: i64,
         offset: i64,
     ) -> Result<Vec<AuditLogEntry>, sqlx::Error> {
-        let mut query = String::from("SELECT * FROM audit_log WHERE 1=1");
+        let (filter, binds) = Self::filter_clause(actor_id, user, action, resource_type);
+        let query = format!(
+            "SELECT * FROM audit_log WHERE 1=1{} ORDER BY timestamp DESC LIMIT ${} OFFSET ${}",
+            filter,
+            binds.len() + 1,
+            binds.len() + 2,
+        );
         
-        if actor_id.is_some() {
-            query.push_str(" AND actor_id = ?");
-        }
-        if action.is_some() {
-            query.push_str(" AND action = ?");
-        }
-        if resource_type.is_some() {
-            query.push_str(" AND resource_type = ?");
+        let mut q = sqlx::query_as::<_, AuditLogEntry>(&query);
+        for value in binds {
+            q = q.bind(value);
         }
         
-        query.push_str(" ORDER BY timestamp DESC LIMIT ? OFFSET ?");
+        q.bind(limit).bind(offset).fetch_all(pool).await
+    }
+    
+    /// Count audit log entries matching the same filters as [`Self::list`]
+    pub async fn count(
+        pool: &DbPool,
+        actor_id: Option<&str>,
+        user: Option<&str>,
+        action: Option<&str>,
+        resource_type: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let (filter, binds) = Self::filter_clause(actor_id, user, action, resource_type);
+        let query = format!("SELECT COUNT(*) FROM audit_log WHERE 1=1{}", filter);
+        
+        let mut q = sqlx::query_scalar::<_, i64>(&query);
+        for value in binds {
+            q = q.bind(value);
+        }
         
-        let mut q = sqlx::query_as::<_, AuditLogEntry>(&query);
+        q.fetch_one(pool).await
+    }
+    
+    /// Build the `AND ...` filter clause and its bind values, in order
+    fn filter_clause<'a>(
+        actor_id: Option<&'a str>,
+        user: Option<&'a str>,
+        action: Option<&'a str>,
+        resource_type: Option<&'a str>,
+    ) -> (String, Vec<&'a str>) {
+        let mut clause = String::new();
+        let mut binds = Vec::new();
         
         if let Some(aid) = actor_id {
-            q = q.bind(aid);
+            binds.push(aid);
+            clause.push_str(&format!(" AND actor_id = ${}", binds.len()));
+        }
+        if let Some(u) = user {
+            binds.push(u);
+            binds.push(u);
+            clause.push_str(&format!(" AND (actor_id = ${} OR actor_name = ${})", binds.len() - 1, binds.len()));
         }
         if let Some(a) = action {
-            q = q.bind(a);
+            binds.push(a);
+            clause.push_str(&format!(" AND action = ${}", binds.len()));
         }
         if let Some(rt) = resource_type {
-            q = q.bind(rt);
+            binds.push(rt);
+            clause.push_str(&format!(" AND resource_type = ${}", binds.len()));
         }
         
-        q.bind(limit).bind(offset).fetch_all(pool).await
+        (clause, binds)
     }
 }
 
@@ -668,7 +1386,7 @@ pub struct DeploymentRepository;
 impl DeploymentRepository {
     /// Create a new deployment
     pub async fn create(
-        pool: &SqlitePool,
+        pool: &DbPool,
         group_id: &str,
         config_version: &str,
         strategy: &str,
@@ -680,7 +1398,7 @@ impl DeploymentRepository {
         sqlx::query_as::<_, Deployment>(
             r#"
             INSERT INTO deployments (id, group_id, config_version, strategy, status, options, created_by)
-            VALUES (?, ?, ?, ?, 'pending', ?, ?)
+            VALUES ($1, $2, $3, $4, 'pending', $5, $6)
             RETURNING *
             "#
         )
@@ -694,22 +1412,122 @@ impl DeploymentRepository {
         .await
     }
     
-    /// Get deployment by ID
-    pub async fn get_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Deployment>, sqlx::Error> {
-        sqlx::query_as::<_, Deployment>("SELECT * FROM deployments WHERE id = ?")
-            .bind(id)
-            .fetch_optional(pool)
-            .await
+    /// Get deployment by ID
+    pub async fn get_by_id(pool: &DbPool, id: &str) -> Result<Option<Deployment>, sqlx::Error> {
+        sqlx::query_as::<_, Deployment>("SELECT * FROM deployments WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+    
+    /// A page of a group's deployment history, newest first
+    ///
+    /// `since` and `until` bound `created_at` (inclusive and exclusive) and
+    /// use the stored `YYYY-MM-DD HH:MM:SS` format.
+    pub async fn list_for_group(
+        pool: &DbPool,
+        group_id: &str,
+        status: Option<&str>,
+        since: Option<&str>,
+        until: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Deployment>, sqlx::Error> {
+        let (filter, binds) = Self::history_filter(group_id, status, since, until);
+        let query = format!(
+            "SELECT * FROM deployments WHERE {} ORDER BY created_at DESC, id DESC LIMIT ${} OFFSET ${}",
+            filter,
+            binds.len() + 1,
+            binds.len() + 2,
+        );
+        
+        let mut q = sqlx::query_as::<_, Deployment>(&query);
+        for value in binds {
+            q = q.bind(value);
+        }
+        
+        q.bind(limit).bind(offset).fetch_all(pool).await
+    }
+    
+    /// Deployments whose id or status contains `query`, ignoring case
+    ///
+    /// Ranked like [`AgentRepository::search`] before `limit` is applied,
+    /// newest first within a rank.
+    pub async fn search(pool: &DbPool, query: &str, limit: i64) -> Result<Vec<Deployment>, sqlx::Error> {
+        let query = query.to_lowercase();
+        sqlx::query_as::<_, Deployment>(
+            r#"
+            SELECT * FROM deployments
+            WHERE lower(id) LIKE $1 ESCAPE '\' OR lower(status) LIKE $1 ESCAPE '\'
+            ORDER BY CASE
+                WHEN lower(id) = $2 OR lower(status) = $2 THEN 0
+                WHEN lower(id) LIKE $3 ESCAPE '\' OR lower(status) LIKE $3 ESCAPE '\' THEN 1
+                ELSE 2
+            END, created_at DESC, id DESC
+            LIMIT $4
+            "#
+        )
+        .bind(contains_pattern(&query))
+        .bind(&query)
+        .bind(prefix_pattern(&query))
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Count deployments matching the same filters as [`Self::list_for_group`]
+    pub async fn count_for_group(
+        pool: &DbPool,
+        group_id: &str,
+        status: Option<&str>,
+        since: Option<&str>,
+        until: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let (filter, binds) = Self::history_filter(group_id, status, since, until);
+        let query = format!("SELECT COUNT(*) FROM deployments WHERE {}", filter);
+        
+        let mut q = sqlx::query_scalar::<_, i64>(&query);
+        for value in binds {
+            q = q.bind(value);
+        }
+        
+        q.fetch_one(pool).await
+    }
+    
+    /// Build the history filter clause and its bind values, in order
+    fn history_filter<'a>(
+        group_id: &'a str,
+        status: Option<&'a str>,
+        since: Option<&'a str>,
+        until: Option<&'a str>,
+    ) -> (String, Vec<&'a str>) {
+        let mut clause = "group_id = $1".to_string();
+        let mut binds = vec![group_id];
+        
+        if let Some(status) = status {
+            binds.push(status);
+            clause.push_str(&format!(" AND status = ${}", binds.len()));
+        }
+        if let Some(since) = since {
+            binds.push(since);
+            clause.push_str(&format!(" AND created_at >= ${}", binds.len()));
+        }
+        if let Some(until) = until {
+            binds.push(until);
+            clause.push_str(&format!(" AND created_at < ${}", binds.len()));
+        }
+        
+        (clause, binds)
     }
     
     /// List deployments for a group
     pub async fn list_by_group(
-        pool: &SqlitePool,
+        pool: &DbPool,
         group_id: &str,
         limit: i64,
     ) -> Result<Vec<Deployment>, sqlx::Error> {
         sqlx::query_as::<_, Deployment>(
-            "SELECT * FROM deployments WHERE group_id = ? ORDER BY created_at DESC LIMIT ?"
+            "SELECT * FROM deployments WHERE group_id = $1 ORDER BY created_at DESC LIMIT $2"
         )
         .bind(group_id)
         .bind(limit)
@@ -719,33 +1537,90 @@ impl DeploymentRepository {
     
     /// Get active deployment for a group (in_progress or queued)
     pub async fn get_active_for_group(
-        pool: &SqlitePool,
+        pool: &DbPool,
         group_id: &str,
     ) -> Result<Option<Deployment>, sqlx::Error> {
         sqlx::query_as::<_, Deployment>(
-            "SELECT * FROM deployments WHERE group_id = ? AND status IN ('pending', 'queued', 'in_progress', 'pending_approval') ORDER BY created_at DESC LIMIT 1"
+            "SELECT * FROM deployments WHERE group_id = $1 AND status IN ('pending', 'queued', 'in_progress', 'pending_approval') ORDER BY created_at DESC LIMIT 1"
         )
         .bind(group_id)
         .fetch_optional(pool)
         .await
     }
     
+    /// List deployments that are executing, across all groups
+    pub async fn list_in_progress(pool: &DbPool) -> Result<Vec<Deployment>, sqlx::Error> {
+        sqlx::query_as::<_, Deployment>(
+            "SELECT * FROM deployments WHERE status = 'in_progress' ORDER BY created_at ASC"
+        )
+        .fetch_all(pool)
+        .await
+    }
+    
     /// Get queued deployments for a group
     pub async fn get_queued_for_group(
-        pool: &SqlitePool,
+        pool: &DbPool,
         group_id: &str,
     ) -> Result<Vec<Deployment>, sqlx::Error> {
         sqlx::query_as::<_, Deployment>(
-            "SELECT * FROM deployments WHERE group_id = ? AND status = 'queued' ORDER BY created_at ASC"
+            "SELECT * FROM deployments WHERE group_id = $1 AND status = 'queued' ORDER BY created_at ASC"
         )
         .bind(group_id)
         .fetch_all(pool)
         .await
     }
     
+    /// Mark a deployment as scheduled to run at `scheduled_at` (UTC)
+    pub async fn schedule(
+        pool: &DbPool,
+        id: &str,
+        scheduled_at: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE deployments SET status = 'scheduled', scheduled_at = $1 WHERE id = $2"
+        )
+        .bind(scheduled_at)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+    
+    /// Scheduled deployments due at or before `now`, oldest first
+    pub async fn list_due_scheduled(
+        pool: &DbPool,
+        now: &str,
+    ) -> Result<Vec<Deployment>, sqlx::Error> {
+        sqlx::query_as::<_, Deployment>(
+            "SELECT * FROM deployments WHERE status = 'scheduled' AND scheduled_at <= $1 ORDER BY scheduled_at ASC"
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await
+    }
+    
+    /// Move a scheduled deployment to `status`
+    ///
+    /// Returns false if it is no longer scheduled (e.g. it was cancelled),
+    /// so a deployment is only ever claimed once.
+    pub async fn claim_scheduled(
+        pool: &DbPool,
+        id: &str,
+        status: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE deployments SET status = $1 WHERE id = $2 AND status = 'scheduled'"
+        )
+        .bind(status)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+    
     /// Update deployment status
     pub async fn update_status(
-        pool: &SqlitePool,
+        pool: &DbPool,
         id: &str,
         status: &str,
         error: Option<&str>,
@@ -755,7 +1630,7 @@ impl DeploymentRepository {
         // Set started_at when transitioning to in_progress
         if status == "in_progress" {
             sqlx::query(
-                "UPDATE deployments SET status = ?, started_at = ?, error = ? WHERE id = ?"
+                "UPDATE deployments SET status = $1, started_at = $2, error = $3 WHERE id = $4"
             )
             .bind(status)
             .bind(&now)
@@ -767,7 +1642,7 @@ impl DeploymentRepository {
         // Set completed_at when transitioning to completed or failed
         else if status == "completed" || status == "failed" || status == "cancelled" {
             sqlx::query(
-                "UPDATE deployments SET status = ?, completed_at = ?, error = ? WHERE id = ?"
+                "UPDATE deployments SET status = $1, completed_at = $2, error = $3 WHERE id = $4"
             )
             .bind(status)
             .bind(&now)
@@ -777,7 +1652,7 @@ impl DeploymentRepository {
             .await?;
         } else {
             sqlx::query(
-                "UPDATE deployments SET status = ?, error = ? WHERE id = ?"
+                "UPDATE deployments SET status = $1, error = $2 WHERE id = $3"
             )
             .bind(status)
             .bind(error)
@@ -791,7 +1666,7 @@ impl DeploymentRepository {
     /// Approve a deployment
     /// Note: approved_by can be a user ID or name (stored in approved_at comment)
     pub async fn approve(
-        pool: &SqlitePool,
+        pool: &DbPool,
         id: &str,
         approved_by: &str,
     ) -> Result<(), sqlx::Error> {
@@ -801,7 +1676,7 @@ impl DeploymentRepository {
         // Don't set approved_by as FK since we may not have a valid user ID
         // Store approval info in approved_at field
         sqlx::query(
-            "UPDATE deployments SET status = 'queued', approved_at = ? WHERE id = ? AND status = 'pending_approval'"
+            "UPDATE deployments SET status = 'queued', approved_at = $1 WHERE id = $2 AND status = 'pending_approval'"
         )
         .bind(&approval_note)
         .bind(id)
@@ -813,7 +1688,7 @@ impl DeploymentRepository {
     /// Reject a deployment
     /// Note: rejected_by can be a user ID or name (stored in rejected_at comment)
     pub async fn reject(
-        pool: &SqlitePool,
+        pool: &DbPool,
         id: &str,
         rejected_by: &str,
         reason: Option<&str>,
@@ -824,7 +1699,7 @@ impl DeploymentRepository {
         // Don't set rejected_by as FK since we may not have a valid user ID
         // Store rejection info in rejected_at field
         sqlx::query(
-            "UPDATE deployments SET status = 'cancelled', rejected_at = ?, rejection_reason = ? WHERE id = ? AND status = 'pending_approval'"
+            "UPDATE deployments SET status = 'cancelled', rejected_at = $1, rejection_reason = $2 WHERE id = $3 AND status = 'pending_approval'"
         )
         .bind(&rejection_note)
         .bind(reason)
@@ -836,14 +1711,14 @@ impl DeploymentRepository {
     
     /// Add an agent to a deployment
     pub async fn add_agent(
-        pool: &SqlitePool,
+        pool: &DbPool,
         deployment_id: &str,
         agent_id: &str,
     ) -> Result<DeploymentAgent, sqlx::Error> {
         sqlx::query_as::<_, DeploymentAgent>(
             r#"
             INSERT INTO deployment_agents (deployment_id, agent_id, status)
-            VALUES (?, ?, 'pending')
+            VALUES ($1, $2, 'pending')
             RETURNING *
             "#
         )
@@ -855,11 +1730,11 @@ impl DeploymentRepository {
     
     /// Get agents for a deployment
     pub async fn get_agents(
-        pool: &SqlitePool,
+        pool: &DbPool,
         deployment_id: &str,
     ) -> Result<Vec<DeploymentAgent>, sqlx::Error> {
         sqlx::query_as::<_, DeploymentAgent>(
-            "SELECT * FROM deployment_agents WHERE deployment_id = ? ORDER BY id"
+            "SELECT * FROM deployment_agents WHERE deployment_id = $1 ORDER BY id"
         )
         .bind(deployment_id)
         .fetch_all(pool)
@@ -868,7 +1743,7 @@ impl DeploymentRepository {
     
     /// Update deployment agent status
     pub async fn update_agent_status(
-        pool: &SqlitePool,
+        pool: &DbPool,
         deployment_id: &str,
         agent_id: &str,
         status: &str,
@@ -878,7 +1753,7 @@ impl DeploymentRepository {
         
         if status == "in_progress" {
             sqlx::query(
-                "UPDATE deployment_agents SET status = ?, started_at = ?, error = ? WHERE deployment_id = ? AND agent_id = ?"
+                "UPDATE deployment_agents SET status = $1, started_at = $2, error = $3 WHERE deployment_id = $4 AND agent_id = $5"
             )
             .bind(status)
             .bind(&now)
@@ -887,9 +1762,9 @@ impl DeploymentRepository {
             .bind(agent_id)
             .execute(pool)
             .await?;
-        } else if status == "completed" || status == "failed" {
+        } else if status == "completed" || status == "failed" || status == "timeout" {
             sqlx::query(
-                "UPDATE deployment_agents SET status = ?, completed_at = ?, error = ? WHERE deployment_id = ? AND agent_id = ?"
+                "UPDATE deployment_agents SET status = $1, completed_at = $2, error = $3 WHERE deployment_id = $4 AND agent_id = $5"
             )
             .bind(status)
             .bind(&now)
@@ -900,7 +1775,7 @@ impl DeploymentRepository {
             .await?;
         } else {
             sqlx::query(
-                "UPDATE deployment_agents SET status = ?, error = ? WHERE deployment_id = ? AND agent_id = ?"
+                "UPDATE deployment_agents SET status = $1, error = $2 WHERE deployment_id = $3 AND agent_id = $4"
             )
             .bind(status)
             .bind(error)
@@ -912,26 +1787,88 @@ impl DeploymentRepository {
         Ok(())
     }
     
-    /// Get next pending agent for a deployment (for rolling deployments)
-    pub async fn get_next_pending_agent(
-        pool: &SqlitePool,
+    /// Mark a pending deployment agent as in progress
+    ///
+    /// Returns false if the agent is no longer pending, e.g. because the
+    /// deployment was cancelled while it waited for a push slot.
+    pub async fn start_agent(
+        pool: &DbPool,
+        deployment_id: &str,
+        agent_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        
+        let result = sqlx::query(
+            "UPDATE deployment_agents SET status = 'in_progress', started_at = $1, error = NULL WHERE deployment_id = $2 AND agent_id = $3 AND status = 'pending'"
+        )
+        .bind(&now)
+        .bind(deployment_id)
+        .bind(agent_id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Mark a deployment's pending agents as cancelled, leaving running pushes alone
+    pub async fn cancel_pending_agents(
+        pool: &DbPool,
+        deployment_id: &str,
+        reason: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        
+        let result = sqlx::query(
+            "UPDATE deployment_agents SET status = 'cancelled', completed_at = $1, error = $2 WHERE deployment_id = $3 AND status = 'pending'"
+        )
+        .bind(&now)
+        .bind(reason)
+        .bind(deployment_id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+    
+    /// Mark a deployment's pending and in-progress agents as cancelled
+    pub async fn cancel_unfinished_agents(
+        pool: &DbPool,
+        deployment_id: &str,
+        reason: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        
+        let result = sqlx::query(
+            "UPDATE deployment_agents SET status = 'cancelled', completed_at = $1, error = $2 WHERE deployment_id = $3 AND status IN ('pending', 'in_progress')"
+        )
+        .bind(&now)
+        .bind(reason)
+        .bind(deployment_id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+    
+    /// Get the next `limit` pending agents for a deployment (for rolling deployments)
+    pub async fn get_next_pending_agents(
+        pool: &DbPool,
         deployment_id: &str,
-    ) -> Result<Option<DeploymentAgent>, sqlx::Error> {
+        limit: i64,
+    ) -> Result<Vec<DeploymentAgent>, sqlx::Error> {
         sqlx::query_as::<_, DeploymentAgent>(
-            "SELECT * FROM deployment_agents WHERE deployment_id = ? AND status = 'pending' ORDER BY id LIMIT 1"
+            "SELECT * FROM deployment_agents WHERE deployment_id = $1 AND status = 'pending' ORDER BY id LIMIT $2"
         )
         .bind(deployment_id)
-        .fetch_optional(pool)
+        .bind(limit)
+        .fetch_all(pool)
         .await
     }
     
     /// Check if all agents are completed
     pub async fn all_agents_completed(
-        pool: &SqlitePool,
+        pool: &DbPool,
         deployment_id: &str,
     ) -> Result<bool, sqlx::Error> {
         let pending: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = ? AND status NOT IN ('completed', 'failed')"
+            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = $1 AND status NOT IN ('completed', 'failed', 'timeout', 'cancelled')"
         )
         .bind(deployment_id)
         .fetch_one(pool)
@@ -942,32 +1879,46 @@ impl DeploymentRepository {
     
     /// Get deployment stats (for progress tracking)
     pub async fn get_stats(
-        pool: &SqlitePool,
+        pool: &DbPool,
         deployment_id: &str,
     ) -> Result<DeploymentStats, sqlx::Error> {
         let total: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = ?"
+            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = $1"
         )
         .bind(deployment_id)
         .fetch_one(pool)
         .await?;
         
         let completed: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = ? AND status = 'completed'"
+            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = $1 AND status = 'completed'"
         )
         .bind(deployment_id)
         .fetch_one(pool)
         .await?;
         
         let failed: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = ? AND status = 'failed'"
+            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = $1 AND status = 'failed'"
+        )
+        .bind(deployment_id)
+        .fetch_one(pool)
+        .await?;
+        
+        let timed_out: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = $1 AND status = 'timeout'"
         )
         .bind(deployment_id)
         .fetch_one(pool)
         .await?;
         
         let in_progress: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = ? AND status = 'in_progress'"
+            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = $1 AND status = 'in_progress'"
+        )
+        .bind(deployment_id)
+        .fetch_one(pool)
+        .await?;
+        
+        let cancelled: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM deployment_agents WHERE deployment_id = $1 AND status = 'cancelled'"
         )
         .bind(deployment_id)
         .fetch_one(pool)
@@ -977,10 +1928,141 @@ impl DeploymentRepository {
             total: total.0 as u32,
             completed: completed.0 as u32,
             failed: failed.0 as u32,
+            timed_out: timed_out.0 as u32,
             in_progress: in_progress.0 as u32,
-            pending: (total.0 - completed.0 - failed.0 - in_progress.0) as u32,
+            cancelled: cancelled.0 as u32,
+            pending: (total.0 - completed.0 - failed.0 - timed_out.0 - in_progress.0 - cancelled.0) as u32,
         })
     }
+    
+    /// Settle an agent's newest "Pending sync" row for a config version
+    ///
+    /// Pull-based agents report back once they have applied a version: the
+    /// row becomes completed without an error, or failed with `error`.
+    /// Returns the deployment the row belongs to, or None when nothing was
+    /// waiting on this agent for that version.
+    pub async fn ack_pending_sync(
+        pool: &DbPool,
+        agent_id: &str,
+        config_version: &str,
+        error: Option<&str>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let row: Option<(i64, String)> = sqlx::query_as(
+            r#"
+            SELECT da.id, da.deployment_id
+            FROM deployment_agents da
+            JOIN deployments d ON d.id = da.deployment_id
+            WHERE da.agent_id = $1 AND d.config_version = $2
+              AND da.status = 'completed' AND da.error = $3
+            ORDER BY da.id DESC
+            LIMIT 1
+            "#
+        )
+        .bind(agent_id)
+        .bind(config_version)
+        .bind(crate::deployment::PENDING_SYNC)
+        .fetch_optional(pool)
+        .await?;
+        
+        let Some((id, deployment_id)) = row else {
+            return Ok(None);
+        };
+        
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let status = if error.is_some() { "failed" } else { "completed" };
+        // Guarded on the marker so concurrent acks settle the row once
+        let result = sqlx::query(
+            "UPDATE deployment_agents SET status = $1, completed_at = $2, error = $3 WHERE id = $4 AND error = $5"
+        )
+        .bind(status)
+        .bind(&now)
+        .bind(error)
+        .bind(id)
+        .bind(crate::deployment::PENDING_SYNC)
+        .execute(pool)
+        .await?;
+        
+        Ok((result.rows_affected() > 0).then_some(deployment_id))
+    }
+    
+    /// Config version each active agent in a group last applied
+    ///
+    /// Taken from deployment history: the newest deployment that completed
+    /// on the agent without an error. Pushes recorded as "Pending sync" (the
+    /// agent was unreachable) don't count. Agents never deployed to are absent.
+    pub async fn last_applied_versions(
+        pool: &DbPool,
+        group_id: &str,
+    ) -> Result<std::collections::HashMap<String, String>, sqlx::Error> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT da.agent_id, d.config_version
+            FROM deployment_agents da
+            JOIN deployments d ON d.id = da.deployment_id
+            JOIN agents a ON a.id = da.agent_id
+            WHERE a.group_id = $1 AND a.deleted_at IS NULL
+              AND da.status = 'completed' AND da.error IS NULL
+            ORDER BY da.completed_at, da.id
+            "#
+        )
+        .bind(group_id)
+        .fetch_all(pool)
+        .await?;
+        
+        // Later rows overwrite earlier ones, leaving the newest per agent
+        Ok(rows.into_iter().collect())
+    }
+    
+    /// The group's last completed deployment created before `deployment`
+    ///
+    /// Deployments created in the same second are ordered by ID, as in
+    /// [`Self::list_for_group`].
+    pub async fn previous_completed(
+        pool: &DbPool,
+        deployment: &Deployment,
+    ) -> Result<Option<Deployment>, sqlx::Error> {
+        sqlx::query_as::<_, Deployment>(
+            r#"
+            SELECT * FROM deployments
+            WHERE group_id = $1 AND status = 'completed'
+              AND (created_at < $3 OR (created_at = $3 AND id < $2))
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+            "#
+        )
+        .bind(&deployment.group_id)
+        .bind(&deployment.id)
+        .bind(&deployment.created_at)
+        .fetch_optional(pool)
+        .await
+    }
+    
+    /// Config version each agent of a deployment applied before it
+    ///
+    /// Same rules as [`Self::last_applied_versions`], limited to deployment
+    /// agent rows added before this deployment's. Agents with no earlier
+    /// applied version are absent.
+    pub async fn previous_versions(
+        pool: &DbPool,
+        deployment_id: &str,
+    ) -> Result<std::collections::HashMap<String, String>, sqlx::Error> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT da.agent_id, d.config_version
+            FROM deployment_agents da
+            JOIN deployments d ON d.id = da.deployment_id
+            WHERE da.agent_id IN (SELECT agent_id FROM deployment_agents WHERE deployment_id = $1)
+              AND da.id < (SELECT MIN(id) FROM deployment_agents WHERE deployment_id = $1)
+              AND da.status = 'completed' AND da.error IS NULL
+            ORDER BY da.id
+            "#
+        )
+        .bind(deployment_id)
+        .fetch_all(pool)
+        .await?;
+        
+        Ok(rows.into_iter().collect())
+    }
 }
 
 /// Deployment statistics
@@ -988,7 +2070,230 @@ impl DeploymentRepository {
 pub struct DeploymentStats {
     pub total: u32,
     pub completed: u32,
+    /// Agents whose push failed with an error
     pub failed: u32,
+    /// Agents that did not respond within the per-agent timeout
+    pub timed_out: u32,
     pub in_progress: u32,
+    /// Agents never started because the deployment was stopped
+    pub cancelled: u32,
     pub pending: u32,
 }
+
+// =============================================================================
+// Idempotency Key Repository
+// =============================================================================
+
+pub struct IdempotencyKeyRepository;
+
+impl IdempotencyKeyRepository {
+    /// Look up a key for a group, ignoring rows created before `since`
+    pub async fn get(
+        pool: &DbPool,
+        group_id: &str,
+        key: &str,
+        since: &str,
+    ) -> Result<Option<IdempotencyKey>, sqlx::Error> {
+        sqlx::query_as::<_, IdempotencyKey>(
+            "SELECT * FROM idempotency_keys WHERE group_id = $1 AND idempotency_key = $2 AND created_at >= $3"
+        )
+        .bind(group_id)
+        .bind(key)
+        .bind(since)
+        .fetch_optional(pool)
+        .await
+    }
+    
+    /// Reserve a key before processing the request
+    ///
+    /// Returns false if the key is already taken, so only one of several
+    /// concurrent requests with the same key goes ahead. An unfinished claim
+    /// made before `stale_before` for the same request is taken over, since
+    /// the request that made it never completed or released it.
+    pub async fn claim(
+        pool: &DbPool,
+        group_id: &str,
+        key: &str,
+        request_hash: &str,
+        stale_before: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO idempotency_keys (idempotency_key, group_id, request_hash) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"
+        )
+        .bind(key)
+        .bind(group_id)
+        .bind(request_hash)
+        .execute(pool)
+        .await?;
+        if result.rows_affected() == 1 {
+            return Ok(true);
+        }
+        
+        let result = sqlx::query(
+            r#"
+            UPDATE idempotency_keys SET created_at = CURRENT_TIMESTAMP, request_hash = $1
+            WHERE group_id = $2 AND idempotency_key = $3 AND response IS NULL AND created_at < $4
+              AND (request_hash IS NULL OR request_hash = $1)
+            "#
+        )
+        .bind(request_hash)
+        .bind(group_id)
+        .bind(key)
+        .bind(stale_before)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+    
+    /// Record the outcome of a claimed key
+    pub async fn complete(
+        pool: &DbPool,
+        group_id: &str,
+        key: &str,
+        deployment_id: &str,
+        response: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE idempotency_keys SET deployment_id = $1, response = $2 WHERE group_id = $3 AND idempotency_key = $4"
+        )
+        .bind(deployment_id)
+        .bind(response)
+        .bind(group_id)
+        .bind(key)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+    
+    /// Release a claimed key whose request failed, so it can be retried
+    pub async fn release(
+        pool: &DbPool,
+        group_id: &str,
+        key: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE group_id = $1 AND idempotency_key = $2")
+            .bind(group_id)
+            .bind(key)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+    
+    /// Delete keys created before `before`
+    pub async fn delete_expired(pool: &DbPool, before: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < $1")
+            .bind(before)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+// =============================================================================
+// SSO Provider Repository
+// =============================================================================
+
+pub struct SsoProviderRepository;
+
+impl SsoProviderRepository {
+    /// Insert or replace a provider
+    ///
+    /// `client_secret` must already be encrypted.
+    pub async fn upsert(
+        pool: &DbPool,
+        id: &str,
+        config: &str,
+        client_secret: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO sso_providers (id, config, client_secret) VALUES ($1, $2, $3)
+            ON CONFLICT (id) DO UPDATE SET
+                config = excluded.config,
+                client_secret = excluded.client_secret,
+                updated_at = CURRENT_TIMESTAMP
+            "#
+        )
+        .bind(id)
+        .bind(config)
+        .bind(client_secret)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+    
+    /// List all providers
+    pub async fn list(pool: &DbPool) -> Result<Vec<SsoProviderRecord>, sqlx::Error> {
+        sqlx::query_as::<_, SsoProviderRecord>("SELECT * FROM sso_providers ORDER BY id")
+            .fetch_all(pool)
+            .await
+    }
+    
+    /// Delete a provider
+    pub async fn delete(pool: &DbPool, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM sso_providers WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+// =============================================================================
+// SSO Session Repository
+// =============================================================================
+
+pub struct SsoSessionRepository;
+
+impl SsoSessionRepository {
+    /// Record a session's provider tokens
+    ///
+    /// `refresh_token` must already be encrypted. Passing `None` keeps the
+    /// stored one, for providers that don't rotate refresh tokens.
+    pub async fn upsert(
+        pool: &DbPool,
+        session_id: &str,
+        user_id: Option<&str>,
+        provider_id: &str,
+        refresh_token: Option<&str>,
+        access_token_expires_at: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO sso_sessions (session_id, user_id, provider_id, refresh_token, access_token_expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (session_id) DO UPDATE SET
+                user_id = COALESCE(excluded.user_id, sso_sessions.user_id),
+                provider_id = excluded.provider_id,
+                refresh_token = COALESCE(excluded.refresh_token, sso_sessions.refresh_token),
+                access_token_expires_at = excluded.access_token_expires_at,
+                updated_at = CURRENT_TIMESTAMP
+            "#
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(provider_id)
+        .bind(refresh_token)
+        .bind(access_token_expires_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+    
+    /// Get a session's tokens
+    pub async fn get(pool: &DbPool, session_id: &str) -> Result<Option<SsoSession>, sqlx::Error> {
+        sqlx::query_as::<_, SsoSession>("SELECT * FROM sso_sessions WHERE session_id = $1")
+            .bind(session_id)
+            .fetch_optional(pool)
+            .await
+    }
+    
+    /// Forget a session's tokens, e.g. on logout
+    pub async fn delete(pool: &DbPool, session_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM sso_sessions WHERE session_id = $1")
+            .bind(session_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,170 @@
+//! Deployment Progress Events
+//!
+//! The deployment executor publishes status transitions to a broadcast
+//! channel per deployment; the SSE endpoint subscribes and forwards them so
+//! clients don't have to poll. Events are also forwarded to the control
+//! plane's live-update bus when one is attached.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::live::{LiveEvent, LiveEventBus};
+
+/// Events buffered per deployment before slow subscribers start lagging
+const CHANNEL_CAPACITY: usize = 256;
+
+/// A deployment status transition
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeploymentEvent {
+    /// Execution started
+    Started { strategy: String },
+    /// Config push to an agent started
+    AgentStarted { agent_id: String },
+    /// Config push to an agent succeeded
+    AgentCompleted { agent_id: String, message: Option<String> },
+    /// Config push to an agent failed
+    AgentFailed { agent_id: String, error: String },
+    /// A rolling batch started
+    BatchStarted { batch: u32, agents: usize },
+    /// A rolling batch finished
+    BatchCompleted { batch: u32, failures: u32 },
+    /// Canary agents are deployed and the rollout is paused for validation
+    CanaryPaused { agents: usize, wait_secs: u64 },
+    /// Canary passed and the rollout continues to the remaining agents
+    CanaryPromoted { agents: usize },
+    /// The deployment reached a terminal state
+    Finished { status: String, error: Option<String> },
+}
+
+impl DeploymentEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeploymentEvent::Started { .. } => "started",
+            DeploymentEvent::AgentStarted { .. } => "agent_started",
+            DeploymentEvent::AgentCompleted { .. } => "agent_completed",
+            DeploymentEvent::AgentFailed { .. } => "agent_failed",
+            DeploymentEvent::BatchStarted { .. } => "batch_started",
+            DeploymentEvent::BatchCompleted { .. } => "batch_completed",
+            DeploymentEvent::CanaryPaused { .. } => "canary_paused",
+            DeploymentEvent::CanaryPromoted { .. } => "canary_promoted",
+            DeploymentEvent::Finished { .. } => "finished",
+        }
+    }
+
+    /// Whether this is the last event for the deployment
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, DeploymentEvent::Finished { .. })
+    }
+}
+
+/// Whether a deployment status is terminal
+pub fn is_terminal_status(status: &str) -> bool {
+    matches!(status, "completed" | "failed" | "cancelled")
+}
+
+/// Broadcast channels for deployment events, keyed by deployment ID
+#[derive(Debug, Default)]
+pub struct DeploymentEventBus {
+    channels: Mutex<HashMap<String, broadcast::Sender<DeploymentEvent>>>,
+    live: Option<Arc<LiveEventBus>>,
+}
+
+impl DeploymentEventBus {
+    /// Create a new event bus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also forward every event to the live-update bus
+    pub fn with_live_events(mut self, live: Arc<LiveEventBus>) -> Self {
+        self.live = Some(live);
+        self
+    }
+
+    /// Subscribe to events for a deployment
+    pub fn subscribe(&self, deployment_id: &str) -> broadcast::Receiver<DeploymentEvent> {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels
+            .entry(deployment_id.to_string())
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Publish an event for a deployment
+    ///
+    /// Events with no subscribers are dropped. The channel is removed after a
+    /// terminal event, which closes every subscriber's stream.
+    pub fn publish(&self, deployment_id: &str, event: DeploymentEvent) {
+        if let Some(live) = &self.live {
+            live.publish(LiveEvent::Deployment {
+                deployment_id: deployment_id.to_string(),
+                event: event.clone(),
+            });
+        }
+
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+
+        if event.is_terminal() {
+            if let Some(sender) = channels.remove(deployment_id) {
+                let _ = sender.send(event);
+            }
+        } else if let Some(sender) = channels.get(deployment_id) {
+            if sender.send(event).is_err() {
+                // Every subscriber went away
+                channels.remove(deployment_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_subscriber_receives_events_until_finished() {
+        let bus = DeploymentEventBus::new();
+        let mut rx = bus.subscribe("dep-1");
+
+        bus.publish("dep-1", DeploymentEvent::AgentStarted { agent_id: "a1".to_string() });
+        bus.publish("dep-2", DeploymentEvent::AgentStarted { agent_id: "other".to_string() });
+        bus.publish("dep-1", DeploymentEvent::Finished { status: "completed".to_string(), error: None });
+
+        assert_eq!(rx.recv().await.unwrap(), DeploymentEvent::AgentStarted { agent_id: "a1".to_string() });
+        assert!(rx.recv().await.unwrap().is_terminal());
+
+        // The channel is closed after the terminal event
+        assert!(matches!(rx.recv().await, Err(broadcast::error::RecvError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn test_events_are_forwarded_to_live_bus() {
+        let live = Arc::new(LiveEventBus::new());
+        let bus = DeploymentEventBus::new().with_live_events(live.clone());
+        let mut live_rx = live.subscribe();
+
+        // Forwarded even when nobody follows this deployment
+        bus.publish("dep-1", DeploymentEvent::Started { strategy: "basic".to_string() });
+
+        assert_eq!(
+            live_rx.recv().await.unwrap(),
+            LiveEvent::Deployment {
+                deployment_id: "dep-1".to_string(),
+                event: DeploymentEvent::Started { strategy: "basic".to_string() },
+            }
+        );
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let event = DeploymentEvent::AgentFailed { agent_id: "a1".to_string(), error: "boom".to_string() };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "agent_failed");
+        assert_eq!(json["agent_id"], "a1");
+        assert_eq!(event.name(), "agent_failed");
+    }
+}
@@ -4,17 +4,31 @@
 //! - Basic: Deploy to all agents simultaneously
 //! - Rolling: Deploy one-by-one or in batches
 //! - Canary: Deploy to subset, validate, then roll out
+//!
+//! Progress is published as [`DeploymentEvent`]s for live streaming.
+
+pub mod events;
+pub mod report;
+pub mod shutdown;
+
+pub use events::{DeploymentEvent, DeploymentEventBus};
+pub use shutdown::{ShutdownCoordinator, SHUTDOWN_REASON};
 
 use std::sync::Arc;
 use std::time::Duration;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use tracing::{info, warn, error, debug};
-use tokio::sync::RwLock;
+use tracing::{info, warn, error, debug, instrument, Instrument};
+use tokio::sync::{RwLock, Semaphore};
 
+use crate::agent_clients::AgentClients;
+use crate::alerts::{create_deployment_result_alert, AlertManager, NotificationChannel};
+use crate::api::maintenance::MaintenanceMode;
 use crate::db::Database;
 use crate::db::models::{Deployment, DeploymentAgent, WorkerGroup};
 use crate::db::repository::{DeploymentRepository, DeploymentStats, AgentRepository, WorkerGroupRepository};
 use crate::git_store::GitStore;
+use crate::request_id::{self, WithRequestId};
 
 // =============================================================================
 // Deployment Strategy Configuration
@@ -56,8 +70,12 @@ impl std::fmt::Display for DeploymentStrategy {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RollingOptions {
     /// Number of agents to deploy to in parallel (default: 1)
-    #[serde(default = "default_batch_size")]
-    pub batch_size: u32,
+    #[serde(default)]
+    pub batch_size: Option<u32>,
+    /// Batch size as a percentage of the deployment's agents, rounded down
+    /// to at least one (exclusive with `batch_size`)
+    #[serde(default)]
+    pub batch_percentage: Option<u32>,
     /// Wait time between batches in seconds (default: 30)
     #[serde(default = "default_batch_delay")]
     pub batch_delay_secs: u64,
@@ -69,7 +87,6 @@ pub struct RollingOptions {
     pub max_failures: u32,
 }
 
-fn default_batch_size() -> u32 { 1 }
 fn default_batch_delay() -> u64 { 30 }
 fn default_true() -> bool { true }
 fn default_max_failures() -> u32 { 1 }
@@ -77,7 +94,8 @@ fn default_max_failures() -> u32 { 1 }
 impl Default for RollingOptions {
     fn default() -> Self {
         Self {
-            batch_size: 1,
+            batch_size: None,
+            batch_percentage: None,
             batch_delay_secs: 30,
             pause_on_failure: true,
             max_failures: 1,
@@ -85,6 +103,25 @@ impl Default for RollingOptions {
     }
 }
 
+impl RollingOptions {
+    /// Reject option combinations the executor can't honour
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.batch_size, self.batch_percentage) {
+            (Some(_), Some(_)) => Err("batch_size and batch_percentage are mutually exclusive".to_string()),
+            (_, Some(p)) if p == 0 || p > 100 => Err("batch_percentage must be between 1 and 100".to_string()),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Number of agents per batch for a deployment of `total_agents`
+    pub fn batch_size_for(&self, total_agents: usize) -> usize {
+        match self.batch_percentage {
+            Some(p) => (total_agents * p as usize / 100).max(1),
+            None => self.batch_size.unwrap_or(1) as usize,
+        }
+    }
+}
+
 /// Options for canary deployments
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanaryOptions {
@@ -99,6 +136,12 @@ pub struct CanaryOptions {
     /// Whether to auto-promote after wait (default: false - requires manual approval)
     #[serde(default)]
     pub auto_promote: bool,
+    /// How canary agents are picked when `canary_agents` isn't given
+    #[serde(default)]
+    pub canary_selection: CanarySelection,
+    /// Seed for `random` selection (default: derived from the deployment ID)
+    #[serde(default)]
+    pub canary_seed: Option<u64>,
 }
 
 fn default_canary_percentage() -> u32 { 10 }
@@ -111,15 +154,126 @@ impl Default for CanaryOptions {
             canary_agents: None,
             canary_wait_secs: 300,
             auto_promote: false,
+            canary_selection: CanarySelection::First,
+            canary_seed: None,
+        }
+    }
+}
+
+impl CanaryOptions {
+    /// Reject option combinations the executor can't honour
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.canary_selection {
+            CanarySelection::ByTag(tag) => parse_tag_selector(tag).map(|_| ()),
+            _ => Ok(()),
         }
     }
 }
 
+/// How canary agents are picked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanarySelection {
+    /// The deployment's first agents
+    #[default]
+    First,
+    /// A seeded shuffle, so the same seed picks the same canary
+    Random,
+    /// Only agents with a tag, given as `key=value`
+    ByTag(String),
+    /// Agents processing the fewest events per second
+    LeastLoaded,
+}
+
+/// Split a `key=value` tag selector
+fn parse_tag_selector(tag: &str) -> Result<(String, String), String> {
+    match tag.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.trim().to_string())),
+        _ => Err(format!("canary_selection by_tag must be key=value, got '{}'", tag)),
+    }
+}
+
+/// Shuffle `items` with an RNG seeded by `seed`
+fn seeded_shuffle<T>(items: &mut [T], seed: u64) {
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+    items.shuffle(&mut StdRng::seed_from_u64(seed));
+}
+
+/// Default seed for a random canary, stable for a given deployment (FNV-1a)
+fn deployment_seed(deployment_id: &str) -> u64 {
+    deployment_id.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Average events per second since Vector started, if the agent reported it
+fn events_per_sec(metrics: &crate::health::AgentMetrics) -> Option<f64> {
+    match (metrics.events_processed_total, metrics.uptime_seconds) {
+        (Some(events), Some(uptime)) if uptime > 0 => Some(events as f64 / uptime as f64),
+        _ => None,
+    }
+}
+
+/// Order agent IDs least loaded first; agents without metrics go last
+fn order_by_load(ids: &mut [String], load: &std::collections::HashMap<String, f64>) {
+    ids.sort_by(|a, b| match (load.get(a), load.get(b)) {
+        (Some(a), Some(b)) => a.total_cmp(b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
 /// Combined deployment options
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeploymentOptions {
     pub rolling: Option<RollingOptions>,
     pub canary: Option<CanaryOptions>,
+    /// Timeout for each agent push in seconds (default: 30)
+    #[serde(default = "default_per_agent_timeout")]
+    pub per_agent_timeout_secs: u64,
+}
+
+fn default_per_agent_timeout() -> u64 { 30 }
+
+impl Default for DeploymentOptions {
+    fn default() -> Self {
+        Self {
+            rolling: None,
+            canary: None,
+            per_agent_timeout_secs: 30,
+        }
+    }
+}
+
+impl DeploymentOptions {
+    /// Timeout for each agent push
+    pub fn per_agent_timeout(&self) -> Duration {
+        Duration::from_secs(self.per_agent_timeout_secs.max(1))
+    }
+    
+    /// Reject option combinations the executor can't honour
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(rolling) = &self.rolling {
+            rolling.validate()?;
+        }
+        match &self.canary {
+            Some(canary) => canary.validate(),
+            None => Ok(()),
+        }
+    }
+    
+    /// The defaults stored on a group, if any
+    pub fn group_defaults(group: &WorkerGroup) -> Option<Self> {
+        let options = group.default_deployment_options.as_deref()?;
+        match serde_json::from_str(options) {
+            Ok(options) => Some(options),
+            Err(e) => {
+                warn!("Ignoring invalid default deployment options for group {}: {}", group.name, e);
+                None
+            }
+        }
+    }
 }
 
 // =============================================================================
@@ -136,6 +290,21 @@ pub struct DeploymentResult {
     pub queued: bool,
 }
 
+/// Result of aborting a deployment
+#[derive(Debug, Clone, Serialize)]
+pub struct AbortResult {
+    pub deployment_id: String,
+    /// Deployments that put agents back, one per version returned to
+    pub revert_deployments: Vec<String>,
+    /// Updated agents now back on their previous version
+    pub reverted: Vec<String>,
+    /// Updated agents whose revert push failed
+    pub revert_failed: Vec<String>,
+    /// Updated agents with no earlier applied version to return to
+    pub not_reverted: Vec<String>,
+    pub message: String,
+}
+
 /// Result of deploying to a single agent
 #[derive(Debug, Clone, Serialize)]
 pub struct AgentDeployResult {
@@ -218,11 +387,57 @@ pub struct VersionInfo {
 // Deployment Executor
 // =============================================================================
 
+/// Default cap on concurrent agent pushes across all deployments
+pub const DEFAULT_MAX_CONCURRENT_PUSHES: usize = 50;
+
+/// Read the push cap from `VECTORIZE_DEPLOY_MAX_CONCURRENT_PUSHES`,
+/// falling back to [`DEFAULT_MAX_CONCURRENT_PUSHES`]
+pub fn max_concurrent_pushes_from_env() -> usize {
+    std::env::var("VECTORIZE_DEPLOY_MAX_CONCURRENT_PUSHES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&limit: &usize| limit > 0)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_PUSHES)
+}
+
+/// How often the scheduler looks for due deployments
+pub const SCHEDULER_INTERVAL_SECS: u64 = 15;
+
+/// Error for deployments requested after shutdown has begun
+const SHUTTING_DOWN: &str = "Server is shutting down; not accepting new deployments";
+
+/// Error for deployments to a frozen group
+pub fn frozen_message(group_name: &str) -> String {
+    format!("Group '{}' is frozen; deployments are blocked until it is unfrozen", group_name)
+}
+
+/// Error recorded on deployments stopped with a plain cancel
+pub const CANCELLED_BY_USER: &str = "Cancelled by user";
+
+/// Error recorded on deployments aborted and reverted
+pub const ABORTED_BY_USER: &str = "Aborted by user";
+
+/// Marker on agents that couldn't be pushed to and will pull the config
+///
+/// Cleared once the agent acknowledges the version it applied.
+pub const PENDING_SYNC: &str = "Pending sync";
+
 /// Deployment executor service
+#[derive(Clone)]
 pub struct DeploymentExecutor {
     db: Database,
     git_store: Arc<GitStore>,
-    http_client: reqwest::Client,
+    /// HTTP clients for agents, including per-agent TLS clients
+    clients: Arc<AgentClients>,
+    /// Delivers deployment results to the group's notification channels
+    alerts: Arc<AlertManager>,
+    events: Arc<DeploymentEventBus>,
+    /// Permits for agent pushes, shared by every executor in the process
+    push_limit: Arc<Semaphore>,
+    /// Stops new deployments and drains running ones on shutdown
+    shutdown: Arc<ShutdownCoordinator>,
+    /// Holds scheduled deployments while the control plane is read-only
+    maintenance: Arc<MaintenanceMode>,
     #[allow(dead_code)]
     running: Arc<RwLock<bool>>,
 }
@@ -233,12 +448,59 @@ impl DeploymentExecutor {
         Self {
             db,
             git_store,
-            http_client: reqwest::Client::new(),
+            clients: Arc::new(AgentClients::default()),
+            alerts: Arc::new(AlertManager::new()),
+            events: Arc::new(DeploymentEventBus::new()),
+            push_limit: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_PUSHES)),
+            shutdown: Arc::new(ShutdownCoordinator::new()),
+            maintenance: Arc::new(MaintenanceMode::default()),
             running: Arc::new(RwLock::new(false)),
         }
     }
     
+    /// Publish progress events to a shared event bus
+    pub fn with_events(mut self, events: Arc<DeploymentEventBus>) -> Self {
+        self.events = events;
+        self
+    }
+    
+    /// Share a concurrent push limit with other executors
+    ///
+    /// Each agent push holds one permit, so the total number of pushes in
+    /// flight across all deployments never exceeds the semaphore's size.
+    pub fn with_push_limit(mut self, push_limit: Arc<Semaphore>) -> Self {
+        self.push_limit = push_limit;
+        self
+    }
+    
+    /// Share the process's shutdown coordinator
+    pub fn with_shutdown(mut self, shutdown: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+    
+    /// Share the server's maintenance mode flag
+    pub fn with_maintenance(mut self, maintenance: Arc<MaintenanceMode>) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+    
+    /// Share cached agent HTTP clients with other executors
+    pub fn with_agent_clients(mut self, clients: Arc<AgentClients>) -> Self {
+        self.clients = clients;
+        self
+    }
+    
+    /// Deliver deployment result alerts through a shared alert manager
+    pub fn with_alerts(mut self, alerts: Arc<AlertManager>) -> Self {
+        self.alerts = alerts;
+        self
+    }
+    
     /// Start a new deployment
+    ///
+    /// With a future `scheduled_at` the deployment is stored as `scheduled`
+    /// and left for the scheduler; a time in the past starts it immediately.
     pub async fn start_deployment(
         &self,
         group_id: &str,
@@ -246,15 +508,31 @@ impl DeploymentExecutor {
         options: Option<DeploymentOptions>,
         created_by: Option<&str>,
         force: bool,
+        scheduled_at: Option<DateTime<Utc>>,
     ) -> Result<DeploymentResult, String> {
         let pool = self.db.pool();
         
+        if self.shutdown.is_shutting_down() {
+            return Err(SHUTTING_DOWN.to_string());
+        }
+        
         // Get the group
         let group = WorkerGroupRepository::get_by_id(pool, group_id)
             .await
             .map_err(|e| format!("Failed to get group: {}", e))?
             .ok_or("Group not found")?;
         
+        // Callers only pass `force` for a frozen group on behalf of an admin
+        if group.frozen && !force {
+            return Err(frozen_message(&group.name));
+        }
+        
+        // Fall back to the group's defaults when the caller passed none
+        let options = options.or_else(|| DeploymentOptions::group_defaults(&group));
+        if let Some(options) = &options {
+            options.validate()?;
+        }
+        
         // Check version consistency (unless forced)
         if !force {
             let version_check = check_version_consistency(&self.db, group_id).await?;
@@ -266,6 +544,10 @@ impl DeploymentExecutor {
             }
         }
         
+        if let Some(at) = scheduled_at.filter(|at| *at > Utc::now()) {
+            return self.schedule_deployment(group_id, config_version, &group, options, created_by, at).await;
+        }
+        
         // Check for existing active deployment
         if let Some(_active) = DeploymentRepository::get_active_for_group(pool, group_id)
             .await
@@ -335,6 +617,115 @@ impl DeploymentExecutor {
         })
     }
     
+    /// Deploy a group's config to one of its agents
+    ///
+    /// Used when an agent joins a group that already has a config: the
+    /// version has already gone out to the rest of the group, so this skips
+    /// approval, version checks and the queue, and pushes to just that agent.
+    pub async fn deploy_to_single_agent(
+        &self,
+        group_id: &str,
+        agent_id: &str,
+        config_version: &str,
+        created_by: Option<&str>,
+    ) -> Result<DeploymentResult, String> {
+        if self.shutdown.is_shutting_down() {
+            return Err(SHUTTING_DOWN.to_string());
+        }
+        
+        let deployment = self.create_direct_deployment(group_id, &[agent_id], config_version, created_by).await?;
+        self.execute_deployment(&deployment.id).await?;
+        
+        Ok(DeploymentResult {
+            deployment_id: deployment.id,
+            status: "completed".to_string(),
+            message: format!("Deployed version {} to agent {}", &config_version[..8.min(config_version.len())], agent_id),
+            requires_approval: false,
+            queued: false,
+        })
+    }
+    
+    /// Test a config version on one agent
+    ///
+    /// Pushes `config_version` of the agent's group config to just that
+    /// agent, leaving the rest of the group untouched. The deployment is
+    /// recorded like any other but bypasses the group's strategy, approval
+    /// and queue. A failed push is reported in the result's status rather
+    /// than as an error, since finding out is the point of a test deploy.
+    pub async fn deploy_to_single(
+        &self,
+        agent_id: &str,
+        config_version: &str,
+        created_by: Option<&str>,
+    ) -> Result<DeploymentResult, String> {
+        let pool = self.db.pool();
+        
+        if self.shutdown.is_shutting_down() {
+            return Err(SHUTTING_DOWN.to_string());
+        }
+        
+        let agent = AgentRepository::get_by_id(pool, agent_id)
+            .await
+            .map_err(|e| format!("Failed to get agent: {}", e))?
+            .ok_or("Agent not found")?;
+        let group_id = agent.group_id.as_deref().ok_or("Agent is not in a group")?;
+        let group = WorkerGroupRepository::get_by_id(pool, group_id)
+            .await
+            .map_err(|e| format!("Failed to get group: {}", e))?
+            .ok_or("Group not found")?;
+        
+        // Fail before recording a deployment that can't run
+        let short_version = &config_version[..8.min(config_version.len())];
+        if !matches!(self.git_store.get_config_at_version(&group.name, config_version), Ok(Some(_))) {
+            return Err(format!("Version {} has no config for group {}", short_version, group.name));
+        }
+        
+        let deployment = self.create_direct_deployment(group_id, &[agent_id], config_version, created_by).await?;
+        let (status, message) = match self.execute_deployment(&deployment.id).await {
+            Ok(()) => ("completed", format!("Deployed version {} to agent {}", short_version, agent.name)),
+            Err(e) if e == SHUTDOWN_REASON => ("cancelled", e),
+            Err(e) => ("failed", format!("Deploying version {} to agent {} failed: {}", short_version, agent.name, e)),
+        };
+        
+        Ok(DeploymentResult {
+            deployment_id: deployment.id,
+            status: status.to_string(),
+            message,
+            requires_approval: false,
+            queued: false,
+        })
+    }
+    
+    /// Record a basic-strategy deployment covering only `agent_ids`
+    async fn create_direct_deployment(
+        &self,
+        group_id: &str,
+        agent_ids: &[&str],
+        config_version: &str,
+        created_by: Option<&str>,
+    ) -> Result<Deployment, String> {
+        let pool = self.db.pool();
+        
+        let deployment = DeploymentRepository::create(
+            pool,
+            group_id,
+            config_version,
+            &DeploymentStrategy::Basic.to_string(),
+            None,
+            created_by,
+        )
+        .await
+        .map_err(|e| format!("Failed to create deployment: {}", e))?;
+        
+        for agent_id in agent_ids {
+            DeploymentRepository::add_agent(pool, &deployment.id, agent_id)
+                .await
+                .map_err(|e| format!("Failed to add agent: {}", e))?;
+        }
+        
+        Ok(deployment)
+    }
+    
     /// Queue a deployment (when another is active)
     async fn queue_deployment(
         &self,
@@ -375,10 +766,189 @@ impl DeploymentExecutor {
         })
     }
     
+    /// Store a deployment to be started by the scheduler at `at`
+    ///
+    /// Agents are attached when it fires, so agents joining the group in the
+    /// meantime are included.
+    async fn schedule_deployment(
+        &self,
+        group_id: &str,
+        config_version: &str,
+        group: &WorkerGroup,
+        options: Option<DeploymentOptions>,
+        created_by: Option<&str>,
+        at: DateTime<Utc>,
+    ) -> Result<DeploymentResult, String> {
+        let pool = self.db.pool();
+        
+        let options_json = options.as_ref()
+            .map(|o| serde_json::to_string(o).unwrap_or_default());
+        
+        let deployment = DeploymentRepository::create(
+            pool,
+            group_id,
+            config_version,
+            &group.deployment_strategy,
+            options_json.as_deref(),
+            created_by,
+        )
+        .await
+        .map_err(|e| format!("Failed to create deployment: {}", e))?;
+        
+        DeploymentRepository::schedule(pool, &deployment.id, &at.format("%Y-%m-%d %H:%M:%S").to_string())
+            .await
+            .map_err(|e| format!("Failed to schedule deployment: {}", e))?;
+        
+        info!("Deployment {} scheduled for {}", deployment.id, at.to_rfc3339());
+        
+        Ok(DeploymentResult {
+            deployment_id: deployment.id,
+            status: "scheduled".to_string(),
+            message: format!("Deployment scheduled for {}", at.to_rfc3339()),
+            requires_approval: group.requires_approval,
+            queued: false,
+        })
+    }
+    
+    /// Promote scheduled deployments that are due at `now`
+    ///
+    /// Returns the IDs of deployments that were promoted. Each one then goes
+    /// through the normal flow: approval if the group requires it, the queue
+    /// if another deployment is active, otherwise execution in the background.
+    pub async fn run_due_scheduled(&self, now: DateTime<Utc>) -> Result<Vec<String>, String> {
+        let pool = self.db.pool();
+        
+        // Leave them scheduled for the next start
+        if self.shutdown.is_shutting_down() {
+            return Ok(Vec::new());
+        }
+        
+        // Nothing new starts while the control plane is read-only; due
+        // deployments fire on the first run after maintenance ends
+        if self.maintenance.is_enabled() {
+            return Ok(Vec::new());
+        }
+        
+        let due = DeploymentRepository::list_due_scheduled(pool, &now.format("%Y-%m-%d %H:%M:%S").to_string())
+            .await
+            .map_err(|e| format!("Failed to list scheduled deployments: {}", e))?;
+        
+        let mut promoted = Vec::new();
+        for deployment in due {
+            match self.promote_scheduled(&deployment).await {
+                Ok(true) => promoted.push(deployment.id),
+                Ok(false) => {}
+                Err(e) => {
+                    error!("Failed to start scheduled deployment {}: {}", deployment.id, e);
+                    DeploymentRepository::update_status(pool, &deployment.id, "failed", Some(&e))
+                        .await
+                        .map_err(|e| format!("Failed to update status: {}", e))?;
+                }
+            }
+        }
+        
+        Ok(promoted)
+    }
+    
+    async fn promote_scheduled(&self, deployment: &Deployment) -> Result<bool, String> {
+        let pool = self.db.pool();
+        
+        let group = WorkerGroupRepository::get_by_id(pool, &deployment.group_id)
+            .await
+            .map_err(|e| format!("Failed to get group: {}", e))?
+            .ok_or("Group not found")?;
+        
+        // Stays scheduled and fires once the group is unfrozen
+        if group.frozen {
+            return Ok(false);
+        }
+        
+        let active = DeploymentRepository::get_active_for_group(pool, &group.id)
+            .await
+            .map_err(|e| format!("Failed to check active deployments: {}", e))?;
+        
+        let status = if group.requires_approval {
+            "pending_approval"
+        } else if active.is_some() {
+            "queued"
+        } else {
+            "pending"
+        };
+        
+        // Lost a race with cancellation (or another scheduler tick)
+        if !DeploymentRepository::claim_scheduled(pool, &deployment.id, status)
+            .await
+            .map_err(|e| format!("Failed to claim deployment: {}", e))?
+        {
+            return Ok(false);
+        }
+        
+        let agents = AgentRepository::list_by_group(pool, &group.id)
+            .await
+            .map_err(|e| format!("Failed to list agents: {}", e))?;
+        
+        for agent in &agents {
+            DeploymentRepository::add_agent(pool, &deployment.id, &agent.id)
+                .await
+                .map_err(|e| format!("Failed to add agent: {}", e))?;
+        }
+        
+        info!("Scheduled deployment {} is due ({})", deployment.id, status);
+        
+        if status == "pending" {
+            let executor = self.clone();
+            let deployment_id = deployment.id.clone();
+            tokio::spawn(async move {
+                if let Err(e) = executor.execute_deployment(&deployment_id).await {
+                    error!("Scheduled deployment {} failed: {}", deployment_id, e);
+                }
+            });
+        }
+        
+        Ok(true)
+    }
+    
+    /// Promote due scheduled deployments every `interval`
+    pub fn start_scheduler(self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            info!("Starting deployment scheduler (interval: {}s)", interval.as_secs());
+            
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                
+                match self.run_due_scheduled(Utc::now()).await {
+                    Ok(promoted) if !promoted.is_empty() => {
+                        info!("Promoted {} scheduled deployment(s)", promoted.len());
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Deployment scheduler error: {}", e),
+                }
+            }
+        })
+    }
+    
     /// Execute a deployment
+    ///
+    /// If shutdown begins meanwhile, rolling and canary deployments stop at
+    /// the next batch boundary: agents not yet started are marked `cancelled`
+    /// and so is the deployment, with the reason [`SHUTDOWN_REASON`]. A user
+    /// cancel stops them at the same point, keeping the status it recorded.
+    ///
+    /// Agent pushes carry the current request ID; deployments started by the
+    /// scheduler or an approval get one of their own.
+    #[instrument(name = "deployment", skip_all, fields(deployment_id = %deployment_id, request_id = tracing::field::Empty))]
     pub async fn execute_deployment(&self, deployment_id: &str) -> Result<(), String> {
+        let id = request_id::current_or_new();
+        tracing::Span::current().record("request_id", id.as_str());
+        request_id::scope(id, self.run_deployment(deployment_id)).await
+    }
+    
+    async fn run_deployment(&self, deployment_id: &str) -> Result<(), String> {
         let pool = self.db.pool();
         
+        let _running = self.shutdown.track().ok_or(SHUTTING_DOWN)?;
+        
         let deployment = DeploymentRepository::get_by_id(pool, deployment_id)
             .await
             .map_err(|e| format!("Failed to get deployment: {}", e))?
@@ -399,6 +969,7 @@ impl DeploymentExecutor {
         DeploymentRepository::update_status(pool, deployment_id, "in_progress", None)
             .await
             .map_err(|e| format!("Failed to update status: {}", e))?;
+        let started = std::time::Instant::now();
         
         let strategy = DeploymentStrategy::from(deployment.strategy.as_str());
         let options: DeploymentOptions = deployment.options
@@ -407,22 +978,44 @@ impl DeploymentExecutor {
             .unwrap_or_default();
         
         info!("Executing deployment {} with strategy {:?}", deployment_id, strategy);
+        self.events.publish(deployment_id, DeploymentEvent::Started { strategy: strategy.to_string() });
         
+        let timeout = options.per_agent_timeout();
         let result = match strategy {
             DeploymentStrategy::Basic => {
-                self.execute_basic(deployment_id, &config).await
+                self.execute_basic(deployment_id, &config, timeout).await
             }
             DeploymentStrategy::Rolling => {
                 let rolling_opts = options.rolling.unwrap_or_default();
-                self.execute_rolling(deployment_id, &config, &rolling_opts).await
+                self.execute_rolling(deployment_id, &config, timeout, &rolling_opts).await
             }
             DeploymentStrategy::Canary => {
                 let canary_opts = options.canary.unwrap_or_default();
-                self.execute_canary(deployment_id, &config, &canary_opts).await
+                self.execute_canary(deployment_id, &config, timeout, &canary_opts).await
             }
         };
         
+        // A user cancel has already recorded the final status and event
+        let cancelled_by_user = !matches!(&result, Err(e) if e == SHUTDOWN_REASON)
+            && self.cancellation(deployment_id).await.is_some();
+        if cancelled_by_user {
+            crate::telemetry::record_deployment("cancelled", started.elapsed());
+            info!("Deployment {} stopped after being cancelled", deployment_id);
+            self.process_queue(&deployment.group_id).await?;
+            return result;
+        }
+        
         // Update final status
+        let outcome = match &result {
+            Ok(_) => "completed",
+            Err(e) if e == SHUTDOWN_REASON => "cancelled",
+            Err(_) => "failed",
+        };
+        crate::telemetry::record_deployment(outcome, started.elapsed());
+        self.events.publish(deployment_id, DeploymentEvent::Finished {
+            status: outcome.to_string(),
+            error: result.as_ref().err().cloned(),
+        });
         match result {
             Ok(_) => {
                 DeploymentRepository::update_status(pool, deployment_id, "completed", None)
@@ -430,6 +1023,16 @@ impl DeploymentExecutor {
                     .map_err(|e| format!("Failed to update status: {}", e))?;
                 info!("Deployment {} completed successfully", deployment_id);
             }
+            Err(ref e) if e == SHUTDOWN_REASON => {
+                DeploymentRepository::cancel_unfinished_agents(pool, deployment_id, SHUTDOWN_REASON)
+                    .await
+                    .map_err(|e| format!("Failed to cancel remaining agents: {}", e))?;
+                DeploymentRepository::update_status(pool, deployment_id, "cancelled", Some(SHUTDOWN_REASON))
+                    .await
+                    .map_err(|e| format!("Failed to update status: {}", e))?;
+                warn!("Deployment {} stopped for shutdown", deployment_id);
+                return result;
+            }
             Err(ref e) => {
                 DeploymentRepository::update_status(pool, deployment_id, "failed", Some(e))
                     .await
@@ -438,17 +1041,46 @@ impl DeploymentExecutor {
             }
         }
         
+        self.notify_result(&group, deployment_id, outcome).await;
+        
         // Check for queued deployments
         self.process_queue(&deployment.group_id).await?;
         
         result
     }
     
-    /// Execute basic (all-at-once) deployment
-    async fn execute_basic(&self, deployment_id: &str, config: &str) -> Result<(), String> {
-        let pool = self.db.pool();
-        
-        let agents = DeploymentRepository::get_agents(pool, deployment_id)
+    /// Send a deployment result alert to the group's notification channels
+    async fn notify_result(&self, group: &WorkerGroup, deployment_id: &str, status: &str) {
+        let Some(channels) = group.notification_channels.as_deref() else {
+            return;
+        };
+        let channels: Vec<NotificationChannel> = match serde_json::from_str(channels) {
+            Ok(channels) => channels,
+            Err(e) => {
+                warn!("Ignoring invalid notification channels for group {}: {}", group.name, e);
+                return;
+            }
+        };
+        
+        // Re-read so the alert carries the recorded error
+        let deployment = match DeploymentRepository::get_by_id(self.db.pool(), deployment_id).await {
+            Ok(Some(deployment)) => deployment,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to load deployment {} for notification: {}", deployment_id, e);
+                return;
+            }
+        };
+        
+        let alert = create_deployment_result_alert(&deployment, status);
+        self.alerts.notify(&alert, &channels).await;
+    }
+    
+    /// Execute basic (all-at-once) deployment
+    async fn execute_basic(&self, deployment_id: &str, config: &str, timeout: Duration) -> Result<(), String> {
+        let pool = self.db.pool();
+        
+        let agents = DeploymentRepository::get_agents(pool, deployment_id)
             .await
             .map_err(|e| format!("Failed to get agents: {}", e))?;
         
@@ -456,7 +1088,8 @@ impl DeploymentExecutor {
         
         for agent in agents {
             let pool = pool.clone();
-            let client = self.http_client.clone();
+            let events = self.events.clone();
+            let push_limit = self.push_limit.clone();
             let config = config.to_string();
             let deployment_id = deployment_id.to_string();
             
@@ -467,9 +1100,10 @@ impl DeploymentExecutor {
                 .flatten();
             
             if let Some(info) = agent_info {
-                handles.push(tokio::spawn(async move {
-                    deploy_to_agent(&client, &pool, &deployment_id, &info.id, &info.url, &config).await
-                }));
+                let client = self.clients.for_agent(&info);
+                handles.push(tokio::spawn(request_id::propagate(async move {
+                    deploy_to_agent(&client, &pool, &events, &push_limit, &deployment_id, &info.id, &info.url, &config, timeout).await
+                }).in_current_span()));
             }
         }
         
@@ -495,30 +1129,44 @@ impl DeploymentExecutor {
         &self,
         deployment_id: &str,
         config: &str,
+        timeout: Duration,
         options: &RollingOptions,
     ) -> Result<(), String> {
         let pool = self.db.pool();
         let mut failures = 0;
+        let mut batch_number = 0;
+        
+        let total_agents = DeploymentRepository::get_stats(pool, deployment_id)
+            .await
+            .map_err(|e| format!("Failed to get deployment stats: {}", e))?
+            .total as usize;
+        let batch_size = options.batch_size_for(total_agents);
         
         loop {
-            // Get next batch of pending agents
-            let mut batch = Vec::new();
-            for _ in 0..options.batch_size {
-                if let Some(agent) = DeploymentRepository::get_next_pending_agent(pool, deployment_id)
-                    .await
-                    .map_err(|e| format!("Failed to get next agent: {}", e))?
-                {
-                    batch.push(agent);
-                } else {
-                    break;
-                }
+            // Finish the batch in flight, but don't start another
+            if self.shutdown.is_shutting_down() {
+                return Err(SHUTDOWN_REASON.to_string());
+            }
+            if let Some(reason) = self.cancellation(deployment_id).await {
+                return Err(reason);
             }
             
+            // Get next batch of pending agents
+            let batch = DeploymentRepository::get_next_pending_agents(pool, deployment_id, batch_size as i64)
+                .await
+                .map_err(|e| format!("Failed to get next agents: {}", e))?;
+            
             if batch.is_empty() {
                 break;
             }
             
+            batch_number += 1;
+            let batch_failures_before = failures;
             debug!("Rolling deployment batch: {} agents", batch.len());
+            self.events.publish(deployment_id, DeploymentEvent::BatchStarted {
+                batch: batch_number,
+                agents: batch.len(),
+            });
             
             // Deploy to batch
             for agent in batch {
@@ -529,12 +1177,15 @@ impl DeploymentExecutor {
                 
                 if let Some(info) = agent_info {
                     let result = deploy_to_agent(
-                        &self.http_client,
+                        &self.clients.for_agent(&info),
                         pool,
+                        &self.events,
+                        &self.push_limit,
                         deployment_id,
                         &info.id,
                         &info.url,
                         config,
+                        timeout,
                     ).await;
                     
                     if result.is_err() {
@@ -549,9 +1200,14 @@ impl DeploymentExecutor {
                 }
             }
             
+            self.events.publish(deployment_id, DeploymentEvent::BatchCompleted {
+                batch: batch_number,
+                failures: failures - batch_failures_before,
+            });
+            
             // Wait between batches
             if options.batch_delay_secs > 0 {
-                tokio::time::sleep(Duration::from_secs(options.batch_delay_secs)).await;
+                self.shutdown.sleep(Duration::from_secs(options.batch_delay_secs)).await;
             }
         }
         
@@ -562,11 +1218,65 @@ impl DeploymentExecutor {
         }
     }
     
+    /// Split a canary deployment's agents into the canary and the rest
+    ///
+    /// Explicit `canary_agents` win; otherwise `canary_percentage` of the
+    /// agents are picked by `canary_selection`.
+    async fn split_canary(
+        &self,
+        deployment_id: &str,
+        all_agents: Vec<DeploymentAgent>,
+        options: &CanaryOptions,
+    ) -> Result<(Vec<DeploymentAgent>, Vec<DeploymentAgent>), String> {
+        if let Some(ref ids) = options.canary_agents {
+            return Ok(all_agents.into_iter().partition(|a| ids.contains(&a.agent_id)));
+        }
+        
+        let pool = self.db.pool();
+        let canary_count = ((all_agents.len() as f64) * (options.canary_percentage as f64 / 100.0)).ceil() as usize;
+        let canary_count = canary_count.max(1).min(all_agents.len());
+        
+        let mut ids: Vec<String> = all_agents.iter().map(|a| a.agent_id.clone()).collect();
+        match &options.canary_selection {
+            CanarySelection::First => {}
+            CanarySelection::Random => {
+                seeded_shuffle(&mut ids, options.canary_seed.unwrap_or_else(|| deployment_seed(deployment_id)));
+            }
+            CanarySelection::ByTag(tag) => {
+                let selector = parse_tag_selector(tag)?;
+                let tagged: std::collections::HashSet<String> = AgentRepository::list_by_tag(pool, &[selector])
+                    .await
+                    .map_err(|e| format!("Failed to get tagged agents: {}", e))?
+                    .into_iter()
+                    .map(|a| a.id)
+                    .collect();
+                ids.retain(|id| tagged.contains(id));
+                if ids.is_empty() {
+                    return Err(format!("No agents in the deployment are tagged {}", tag));
+                }
+            }
+            CanarySelection::LeastLoaded => {
+                let mut load = std::collections::HashMap::new();
+                let fetches = ids.iter().map(|id| async move {
+                    let agent = AgentRepository::get_by_id(pool, id).await.ok().flatten()?;
+                    let metrics = crate::health::fetch_agent_metrics(&self.clients.for_agent(&agent), &agent.id, &agent.url).await;
+                    events_per_sec(&metrics).map(|rate| (agent.id, rate))
+                });
+                load.extend(futures::future::join_all(fetches).await.into_iter().flatten());
+                order_by_load(&mut ids, &load);
+            }
+        }
+        ids.truncate(canary_count);
+        
+        Ok(all_agents.into_iter().partition(|a| ids.contains(&a.agent_id)))
+    }
+    
     /// Execute canary deployment
     async fn execute_canary(
         &self,
         deployment_id: &str,
         config: &str,
+        timeout: Duration,
         options: &CanaryOptions,
     ) -> Result<(), String> {
         let pool = self.db.pool();
@@ -575,29 +1285,7 @@ impl DeploymentExecutor {
             .await
             .map_err(|e| format!("Failed to get agents: {}", e))?;
         
-        // Determine canary agents
-        let canary_count = if let Some(ref ids) = options.canary_agents {
-            ids.len()
-        } else {
-            ((all_agents.len() as f64) * (options.canary_percentage as f64 / 100.0)).ceil() as usize
-        };
-        let canary_count = canary_count.max(1).min(all_agents.len());
-        
-        let (canary_agents, remaining_agents) = if let Some(ref ids) = options.canary_agents {
-            let canary: Vec<_> = all_agents.iter()
-                .filter(|a| ids.contains(&a.agent_id))
-                .cloned()
-                .collect();
-            let remaining: Vec<_> = all_agents.iter()
-                .filter(|a| !ids.contains(&a.agent_id))
-                .cloned()
-                .collect();
-            (canary, remaining)
-        } else {
-            let canary: Vec<_> = all_agents.iter().take(canary_count).cloned().collect();
-            let remaining: Vec<_> = all_agents.iter().skip(canary_count).cloned().collect();
-            (canary, remaining)
-        };
+        let (canary_agents, remaining_agents) = self.split_canary(deployment_id, all_agents, options).await?;
         
         info!("Canary deployment: {} canary, {} remaining", canary_agents.len(), remaining_agents.len());
         
@@ -609,19 +1297,34 @@ impl DeploymentExecutor {
                 .flatten();
             
             if let Some(info) = agent_info {
-                deploy_to_agent(&self.http_client, pool, deployment_id, &info.id, &info.url, config).await?;
+                deploy_to_agent(&self.clients.for_agent(&info), pool, &self.events, &self.push_limit, deployment_id, &info.id, &info.url, config, timeout).await?;
             }
         }
         
         // Wait for canary period
         info!("Canary deployed - waiting {} seconds for validation", options.canary_wait_secs);
-        tokio::time::sleep(Duration::from_secs(options.canary_wait_secs)).await;
+        self.events.publish(deployment_id, DeploymentEvent::CanaryPaused {
+            agents: canary_agents.len(),
+            wait_secs: options.canary_wait_secs,
+        });
+        if !self.shutdown.sleep(Duration::from_secs(options.canary_wait_secs)).await {
+            return Err(SHUTDOWN_REASON.to_string());
+        }
+        if let Some(reason) = self.cancellation(deployment_id).await {
+            return Err(reason);
+        }
         
         // Check canary health
         let stats = DeploymentRepository::get_stats(pool, deployment_id)
             .await
             .map_err(|e| format!("Failed to get stats: {}", e))?;
         
+        if stats.timed_out > 0 {
+            return Err(format!(
+                "Canary failed: {} agents failed, {} timed out",
+                stats.failed, stats.timed_out
+            ));
+        }
         if stats.failed > 0 {
             return Err(format!("Canary failed: {} agents failed", stats.failed));
         }
@@ -631,6 +1334,9 @@ impl DeploymentExecutor {
             // For now, we auto-promote after the wait period
             info!("Canary healthy - promoting to remaining agents");
         }
+        self.events.publish(deployment_id, DeploymentEvent::CanaryPromoted {
+            agents: remaining_agents.len(),
+        });
         
         // Deploy to remaining
         for agent in &remaining_agents {
@@ -640,7 +1346,7 @@ impl DeploymentExecutor {
                 .flatten();
             
             if let Some(info) = agent_info {
-                deploy_to_agent(&self.http_client, pool, deployment_id, &info.id, &info.url, config).await?;
+                deploy_to_agent(&self.clients.for_agent(&info), pool, &self.events, &self.push_limit, deployment_id, &info.id, &info.url, config, timeout).await?;
             }
         }
         
@@ -706,23 +1412,196 @@ impl DeploymentExecutor {
             .map_err(|e| format!("Failed to reject: {}", e))?;
         
         info!("Deployment {} rejected by {}", deployment_id, rejected_by);
+        self.events.publish(deployment_id, DeploymentEvent::Finished {
+            status: "cancelled".to_string(),
+            error: reason.map(|r| format!("Rejected: {}", r)),
+        });
         
         Ok(())
     }
     
     /// Cancel a deployment
+    ///
+    /// Agents not yet started are skipped and a running rollout stops at the
+    /// next batch boundary; agents already updated keep the new config. See
+    /// [`Self::abort_deployment`] to revert them too.
     pub async fn cancel_deployment(&self, deployment_id: &str) -> Result<(), String> {
         let pool = self.db.pool();
         
-        DeploymentRepository::update_status(pool, deployment_id, "cancelled", Some("Cancelled by user"))
+        DeploymentRepository::update_status(pool, deployment_id, "cancelled", Some(CANCELLED_BY_USER))
             .await
             .map_err(|e| format!("Failed to cancel: {}", e))?;
+        DeploymentRepository::cancel_pending_agents(pool, deployment_id, CANCELLED_BY_USER)
+            .await
+            .map_err(|e| format!("Failed to cancel remaining agents: {}", e))?;
         
         info!("Deployment {} cancelled", deployment_id);
+        self.events.publish(deployment_id, DeploymentEvent::Finished {
+            status: "cancelled".to_string(),
+            error: Some(CANCELLED_BY_USER.to_string()),
+        });
         
         Ok(())
     }
     
+    /// Abort a deployment and revert the agents it already updated
+    ///
+    /// Stops the rollout like [`Self::cancel_deployment`], waits for pushes
+    /// already in flight, then returns every agent the deployment completed
+    /// on to the version it applied before (see
+    /// [`DeploymentRepository::previous_versions`]). The revert runs as a
+    /// basic deployment per version, so it is recorded like any other.
+    /// Agents with no earlier applied version keep the new config and are
+    /// listed in the result.
+    #[instrument(name = "deployment_abort", skip_all, fields(deployment_id = %deployment_id))]
+    pub async fn abort_deployment(&self, deployment_id: &str) -> Result<AbortResult, String> {
+        let pool = self.db.pool();
+        
+        if self.shutdown.is_shutting_down() {
+            return Err(SHUTTING_DOWN.to_string());
+        }
+        
+        let deployment = DeploymentRepository::get_by_id(pool, deployment_id)
+            .await
+            .map_err(|e| format!("Failed to get deployment: {}", e))?
+            .ok_or("Deployment not found")?;
+        if events::is_terminal_status(&deployment.status) {
+            return Err(format!("Deployment is already {}", deployment.status));
+        }
+        let group = WorkerGroupRepository::get_by_id(pool, &deployment.group_id)
+            .await
+            .map_err(|e| format!("Failed to get group: {}", e))?
+            .ok_or("Group not found")?;
+        
+        DeploymentRepository::update_status(pool, deployment_id, "cancelled", Some(ABORTED_BY_USER))
+            .await
+            .map_err(|e| format!("Failed to cancel: {}", e))?;
+        DeploymentRepository::cancel_pending_agents(pool, deployment_id, ABORTED_BY_USER)
+            .await
+            .map_err(|e| format!("Failed to cancel remaining agents: {}", e))?;
+        self.events.publish(deployment_id, DeploymentEvent::Finished {
+            status: "cancelled".to_string(),
+            error: Some(ABORTED_BY_USER.to_string()),
+        });
+        
+        // A push still in flight would otherwise land after its revert
+        let options: DeploymentOptions = deployment.options
+            .as_ref()
+            .and_then(|o| serde_json::from_str(o).ok())
+            .unwrap_or_default();
+        let deadline = tokio::time::Instant::now() + options.per_agent_timeout() + Duration::from_secs(1);
+        loop {
+            let stats = DeploymentRepository::get_stats(pool, deployment_id)
+                .await
+                .map_err(|e| format!("Failed to get stats: {}", e))?;
+            if stats.in_progress == 0 || tokio::time::Instant::now() >= deadline {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        DeploymentRepository::cancel_unfinished_agents(pool, deployment_id, ABORTED_BY_USER)
+            .await
+            .map_err(|e| format!("Failed to cancel remaining agents: {}", e))?;
+        
+        let agents = DeploymentRepository::get_agents(pool, deployment_id)
+            .await
+            .map_err(|e| format!("Failed to get agents: {}", e))?;
+        let previous = DeploymentRepository::previous_versions(pool, deployment_id)
+            .await
+            .map_err(|e| format!("Failed to get previous versions: {}", e))?;
+        
+        let mut result = AbortResult {
+            deployment_id: deployment_id.to_string(),
+            revert_deployments: Vec::new(),
+            reverted: Vec::new(),
+            revert_failed: Vec::new(),
+            not_reverted: Vec::new(),
+            message: String::new(),
+        };
+        let mut by_version: std::collections::BTreeMap<&str, Vec<&str>> = std::collections::BTreeMap::new();
+        for agent in agents.iter().filter(|a| a.status == "completed") {
+            match previous.get(&agent.agent_id) {
+                // Already on that version before this deployment
+                Some(version) if *version == deployment.config_version => result.reverted.push(agent.agent_id.clone()),
+                Some(version) => by_version.entry(version.as_str()).or_default().push(&agent.agent_id),
+                None => result.not_reverted.push(agent.agent_id.clone()),
+            }
+        }
+        
+        for (version, agent_ids) in by_version {
+            // A version without config would leave the revert stuck pending
+            if !matches!(self.git_store.get_config_at_version(&group.name, version), Ok(Some(_))) {
+                warn!("Cannot revert to version {}: no config for group {}", version, group.name);
+                result.revert_failed.extend(agent_ids.iter().map(|id| id.to_string()));
+                continue;
+            }
+            
+            let revert = self.create_direct_deployment(&group.id, &agent_ids, version, None).await?;
+            if let Err(e) = self.execute_deployment(&revert.id).await {
+                warn!("Revert deployment {} for {} failed: {}", revert.id, deployment_id, e);
+            }
+            let outcomes = DeploymentRepository::get_agents(pool, &revert.id)
+                .await
+                .map_err(|e| format!("Failed to get agents: {}", e))?;
+            for agent in outcomes {
+                if agent.status == "completed" {
+                    result.reverted.push(agent.agent_id);
+                } else {
+                    result.revert_failed.push(agent.agent_id);
+                }
+            }
+            result.revert_deployments.push(revert.id);
+        }
+        
+        result.message = format!(
+            "Deployment aborted: {} agent(s) reverted, {} failed to revert, {} with no previous version",
+            result.reverted.len(), result.revert_failed.len(), result.not_reverted.len(),
+        );
+        info!("Deployment {} aborted: {}", deployment_id, result.message);
+        
+        Ok(result)
+    }
+    
+    /// The recorded reason if the deployment has been cancelled
+    async fn cancellation(&self, deployment_id: &str) -> Option<String> {
+        let deployment = DeploymentRepository::get_by_id(self.db.pool(), deployment_id)
+            .await
+            .ok()
+            .flatten()?;
+        (deployment.status == "cancelled")
+            .then(|| deployment.error.unwrap_or_else(|| CANCELLED_BY_USER.to_string()))
+    }
+    
+    /// Mark deployments still executing as cancelled
+    ///
+    /// For when the shutdown grace period runs out before they finished, so
+    /// none is left `in_progress` with no process running it. Returns the IDs
+    /// of the deployments cancelled.
+    pub async fn cancel_in_progress_for_shutdown(&self) -> Result<Vec<String>, String> {
+        let pool = self.db.pool();
+        
+        let running = DeploymentRepository::list_in_progress(pool)
+            .await
+            .map_err(|e| format!("Failed to list running deployments: {}", e))?;
+        
+        let mut cancelled = Vec::new();
+        for deployment in running {
+            DeploymentRepository::cancel_unfinished_agents(pool, &deployment.id, SHUTDOWN_REASON)
+                .await
+                .map_err(|e| format!("Failed to cancel remaining agents: {}", e))?;
+            DeploymentRepository::update_status(pool, &deployment.id, "cancelled", Some(SHUTDOWN_REASON))
+                .await
+                .map_err(|e| format!("Failed to update status: {}", e))?;
+            self.events.publish(&deployment.id, DeploymentEvent::Finished {
+                status: "cancelled".to_string(),
+                error: Some(SHUTDOWN_REASON.to_string()),
+            });
+            cancelled.push(deployment.id);
+        }
+        
+        Ok(cancelled)
+    }
+    
     /// Get deployment status
     pub async fn get_status(&self, deployment_id: &str) -> Result<DeploymentStatus, String> {
         let pool = self.db.pool();
@@ -749,27 +1628,74 @@ impl DeploymentExecutor {
 }
 
 /// Deploy configuration to a single agent
+///
+/// Waits for a permit from `push_limit` first; the agent stays pending
+/// until one is free. An agent no longer pending by then is skipped.
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "agent_push", skip_all, fields(agent_id = %agent_id))]
 async fn deploy_to_agent(
     client: &reqwest::Client,
-    pool: &sqlx::SqlitePool,
+    pool: &crate::db::DbPool,
+    events: &DeploymentEventBus,
+    push_limit: &Semaphore,
     deployment_id: &str,
     agent_id: &str,
     agent_url: &str,
     config: &str,
+    timeout: Duration,
 ) -> Result<(), String> {
-    // Mark as in progress
-    DeploymentRepository::update_agent_status(pool, deployment_id, agent_id, "in_progress", None)
+    let _permit = push_limit.acquire()
+        .await
+        .map_err(|e| format!("Push limit unavailable: {}", e))?;
+    
+    // Mark as in progress, unless the deployment was cancelled meanwhile
+    let started = DeploymentRepository::start_agent(pool, deployment_id, agent_id)
         .await
         .map_err(|e| format!("Failed to update status: {}", e))?;
+    if !started {
+        return Ok(());
+    }
+    events.publish(deployment_id, DeploymentEvent::AgentStarted { agent_id: agent_id.to_string() });
+    
+    let result = push_config_to_agent(client, pool, deployment_id, agent_id, agent_url, config, timeout).await;
+    
+    match &result {
+        Ok(message) => events.publish(deployment_id, DeploymentEvent::AgentCompleted {
+            agent_id: agent_id.to_string(),
+            message: message.clone(),
+        }),
+        Err(error) => events.publish(deployment_id, DeploymentEvent::AgentFailed {
+            agent_id: agent_id.to_string(),
+            error: error.clone(),
+        }),
+    }
+    
+    result.map(|_| ())
+}
+
+/// Push config to an agent and record the outcome
+/// 
+/// Returns the completion note recorded for the agent, if any. A push that
+/// exceeds `timeout` is recorded with status `timeout` rather than `failed`.
+async fn push_config_to_agent(
+    client: &reqwest::Client,
+    pool: &crate::db::DbPool,
+    deployment_id: &str,
+    agent_id: &str,
+    agent_url: &str,
+    config: &str,
+    timeout: Duration,
+) -> Result<Option<String>, String> {
     
     // In a full implementation, we would POST the config to the agent's API
     // For now, we simulate successful deployment since agents pull configs
     let deploy_url = format!("{}/api/deploy", agent_url.trim_end_matches('/'));
     
     let result = client.post(&deploy_url)
+        .with_request_id()
         .header("Content-Type", "application/toml")
         .body(config.to_string())
-        .timeout(Duration::from_secs(30))
+        .timeout(timeout)
         .send()
         .await;
     
@@ -778,7 +1704,7 @@ async fn deploy_to_agent(
             DeploymentRepository::update_agent_status(pool, deployment_id, agent_id, "completed", None)
                 .await
                 .map_err(|e| format!("Failed to update status: {}", e))?;
-            Ok(())
+            Ok(None)
         }
         Ok(response) => {
             let error = format!("Agent returned status: {}", response.status());
@@ -787,17 +1713,23 @@ async fn deploy_to_agent(
                 .map_err(|e| format!("Failed to update status: {}", e))?;
             Err(error)
         }
+        Err(e) if e.is_timeout() => {
+            let error = format!("Agent did not respond within {}s", timeout.as_secs());
+            DeploymentRepository::update_agent_status(pool, deployment_id, agent_id, "timeout", Some(&error))
+                .await
+                .map_err(|e| format!("Failed to update status: {}", e))?;
+            Err(error)
+        }
         Err(e) => {
             // For now, mark as completed since agents use pull-based config sync
             // In production, this would be a real failure
-            let is_connection_error = e.is_connect() || e.is_timeout();
-            if is_connection_error {
+            if e.is_connect() {
                 // Agent might not have deploy API - mark as pending sync
                 warn!("Agent {} unreachable for push deploy - will pull on next sync", agent_id);
-                DeploymentRepository::update_agent_status(pool, deployment_id, agent_id, "completed", Some("Pending sync"))
+                DeploymentRepository::update_agent_status(pool, deployment_id, agent_id, "completed", Some(PENDING_SYNC))
                     .await
                     .map_err(|e| format!("Failed to update status: {}", e))?;
-                Ok(())
+                Ok(Some(PENDING_SYNC.to_string()))
             } else {
                 let error = format!("Deploy failed: {}", e);
                 DeploymentRepository::update_agent_status(pool, deployment_id, agent_id, "failed", Some(&error))
@@ -832,11 +1764,34 @@ mod tests {
     #[test]
     fn test_rolling_options_default() {
         let opts = RollingOptions::default();
-        assert_eq!(opts.batch_size, 1);
+        assert_eq!(opts.batch_size, None);
+        assert_eq!(opts.batch_size_for(10), 1);
         assert_eq!(opts.batch_delay_secs, 30);
         assert!(opts.pause_on_failure);
     }
     
+    #[test]
+    fn test_rolling_batch_percentage() {
+        let opts = RollingOptions { batch_percentage: Some(20), ..Default::default() };
+        assert!(opts.validate().is_ok());
+        assert_eq!(opts.batch_size_for(10), 2);
+        assert_eq!(opts.batch_size_for(14), 2);
+        assert_eq!(opts.batch_size_for(3), 1);
+        assert_eq!(opts.batch_size_for(0), 1);
+        
+        let opts = RollingOptions { batch_size: Some(3), ..Default::default() };
+        assert_eq!(opts.batch_size_for(10), 3);
+        
+        let both = RollingOptions { batch_size: Some(2), batch_percentage: Some(20), ..Default::default() };
+        assert_eq!(both.validate().unwrap_err(), "batch_size and batch_percentage are mutually exclusive");
+        let zero = RollingOptions { batch_percentage: Some(0), ..Default::default() };
+        assert!(zero.validate().is_err());
+        
+        // Options from the API omit whichever field isn't used
+        let parsed: RollingOptions = serde_json::from_str(r#"{"batch_percentage": 10}"#).unwrap();
+        assert_eq!((parsed.batch_size, parsed.batch_percentage), (None, Some(10)));
+    }
+    
     #[test]
     fn test_canary_options_default() {
         let opts = CanaryOptions::default();
@@ -844,4 +1799,728 @@ mod tests {
         assert_eq!(opts.canary_wait_secs, 300);
         assert!(!opts.auto_promote);
     }
+    
+    #[test]
+    fn test_canary_selection_options() {
+        let parsed: CanaryOptions = serde_json::from_str(r#"{"canary_selection": {"by_tag": "ring=canary"}}"#).unwrap();
+        assert_eq!(parsed.canary_selection, CanarySelection::ByTag("ring=canary".to_string()));
+        assert!(parsed.validate().is_ok());
+        let parsed: CanaryOptions = serde_json::from_str(r#"{"canary_selection": "random", "canary_seed": 7}"#).unwrap();
+        assert_eq!((parsed.canary_selection, parsed.canary_seed), (CanarySelection::Random, Some(7)));
+        assert_eq!(serde_json::from_str::<CanaryOptions>("{}").unwrap().canary_selection, CanarySelection::First);
+        
+        let bad = CanaryOptions { canary_selection: CanarySelection::ByTag("canary".to_string()), ..Default::default() };
+        assert_eq!(bad.validate().unwrap_err(), "canary_selection by_tag must be key=value, got 'canary'");
+        let options = DeploymentOptions { canary: Some(bad), ..Default::default() };
+        assert!(options.validate().is_err());
+    }
+    
+    #[test]
+    fn test_order_by_load() {
+        let mut ids: Vec<String> = ["busy", "silent", "idle", "quiet"].iter().map(|s| s.to_string()).collect();
+        let load = std::collections::HashMap::from([
+            ("busy".to_string(), 900.0),
+            ("idle".to_string(), 0.5),
+            ("quiet".to_string(), 12.0),
+        ]);
+        order_by_load(&mut ids, &load);
+        assert_eq!(ids, vec!["idle", "quiet", "busy", "silent"]);
+        
+        let metrics = crate::health::AgentMetrics {
+            events_processed_total: Some(3600),
+            uptime_seconds: Some(1800),
+            ..Default::default()
+        };
+        assert_eq!(events_per_sec(&metrics), Some(2.0));
+        assert_eq!(events_per_sec(&crate::health::AgentMetrics::default()), None);
+    }
+    
+    /// Executor and a canary deployment over `agents` agents named `agent-0`..
+    async fn canary_fixture(agents: usize) -> (tempfile::TempDir, DeploymentExecutor, String, Vec<DeploymentAgent>) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool();
+        
+        let group = WorkerGroupRepository::create(pool, "fleet", None, None).await.unwrap();
+        let deployment = DeploymentRepository::create(pool, &group.id, "v1", "canary", None, None).await.unwrap();
+        for a in 0..agents {
+            let name = format!("agent-{}", a);
+            let agent = AgentRepository::create(pool, &name, &format!("http://{}:8686", name), Some(&group.id)).await.unwrap();
+            DeploymentRepository::add_agent(pool, &deployment.id, &agent.id).await.unwrap();
+        }
+        let deployment_agents = DeploymentRepository::get_agents(pool, &deployment.id).await.unwrap();
+        (dir, DeploymentExecutor::new(db, git_store), deployment.id, deployment_agents)
+    }
+    
+    fn agent_ids(agents: &[DeploymentAgent]) -> Vec<String> {
+        agents.iter().map(|a| a.agent_id.clone()).collect()
+    }
+    
+    #[tokio::test]
+    async fn test_random_canary_is_reproducible() {
+        let (_dir, executor, deployment_id, agents) = canary_fixture(10).await;
+        let options = CanaryOptions {
+            canary_percentage: 30,
+            canary_selection: CanarySelection::Random,
+            canary_seed: Some(42),
+            ..Default::default()
+        };
+        
+        let (canary, remaining) = executor.split_canary(&deployment_id, agents.clone(), &options).await.unwrap();
+        assert_eq!(canary.len(), 3);
+        assert_eq!(remaining.len(), 7);
+        let (again, _) = executor.split_canary(&deployment_id, agents.clone(), &options).await.unwrap();
+        assert_eq!(agent_ids(&again), agent_ids(&canary));
+        
+        let mut shuffled = agent_ids(&agents);
+        seeded_shuffle(&mut shuffled, 42);
+        let mut expected: Vec<String> = shuffled.into_iter().take(3).collect();
+        // The canary keeps the deployment's agent order
+        expected.sort_by_key(|id| agents.iter().position(|a| &a.agent_id == id));
+        assert_eq!(agent_ids(&canary), expected);
+        
+        // Without a seed the deployment ID seeds the shuffle
+        let unseeded = CanaryOptions { canary_seed: None, ..options };
+        let (first, _) = executor.split_canary(&deployment_id, agents.clone(), &unseeded).await.unwrap();
+        let (second, _) = executor.split_canary(&deployment_id, agents, &unseeded).await.unwrap();
+        assert_eq!(agent_ids(&first), agent_ids(&second));
+    }
+    
+    #[tokio::test]
+    async fn test_canary_by_tag() {
+        let (_dir, executor, deployment_id, agents) = canary_fixture(6).await;
+        let pool = executor.db.pool();
+        let ring = |value: &str| vec![("ring".to_string(), value.to_string())];
+        AgentRepository::set_tags(pool, &agents[1].agent_id, &ring("canary")).await.unwrap();
+        AgentRepository::set_tags(pool, &agents[4].agent_id, &ring("canary")).await.unwrap();
+        AgentRepository::set_tags(pool, &agents[2].agent_id, &ring("main")).await.unwrap();
+        
+        // Half of six agents, but only two carry the tag
+        let options = CanaryOptions {
+            canary_percentage: 50,
+            canary_selection: CanarySelection::ByTag("ring=canary".to_string()),
+            ..Default::default()
+        };
+        let (canary, remaining) = executor.split_canary(&deployment_id, agents.clone(), &options).await.unwrap();
+        assert_eq!(agent_ids(&canary), vec![agents[1].agent_id.clone(), agents[4].agent_id.clone()]);
+        assert_eq!(remaining.len(), 4);
+        
+        // Capped at the canary percentage
+        let options = CanaryOptions { canary_percentage: 10, ..options };
+        let (canary, _) = executor.split_canary(&deployment_id, agents.clone(), &options).await.unwrap();
+        assert_eq!(agent_ids(&canary), vec![agents[1].agent_id.clone()]);
+        
+        // Explicit canary agents still win
+        let explicit = CanaryOptions { canary_agents: Some(vec![agents[0].agent_id.clone()]), ..options };
+        let (canary, _) = executor.split_canary(&deployment_id, agents.clone(), &explicit).await.unwrap();
+        assert_eq!(agent_ids(&canary), vec![agents[0].agent_id.clone()]);
+        
+        let untagged = CanaryOptions {
+            canary_selection: CanarySelection::ByTag("ring=beta".to_string()),
+            ..Default::default()
+        };
+        let err = executor.split_canary(&deployment_id, agents, &untagged).await.unwrap_err();
+        assert_eq!(err, "No agents in the deployment are tagged ring=beta");
+    }
+    
+    /// Agent stand-in that tracks how many pushes are in flight at once
+    #[derive(Default)]
+    struct CountingAgent {
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+        total: std::sync::atomic::AtomicUsize,
+        /// Last config received, by the URL's agent path segment
+        configs: std::sync::Mutex<std::collections::HashMap<String, String>>,
+        /// `X-Request-Id` of each push, in arrival order
+        request_ids: std::sync::Mutex<Vec<Option<String>>>,
+    }
+    
+    /// Agent URLs are unique, so several agents share one server by prefixing
+    /// their URL with a path segment (e.g. `{url}/agent-1`)
+    async fn spawn_counting_agent(delay: Duration) -> (String, Arc<CountingAgent>) {
+        use std::sync::atomic::Ordering;
+        
+        let counter = Arc::new(CountingAgent::default());
+        let deploy = move |axum::extract::State(counter): axum::extract::State<Arc<CountingAgent>>,
+                           uri: axum::http::Uri,
+                           headers: axum::http::HeaderMap,
+                           config: String| async move {
+            let request_id = headers.get(request_id::REQUEST_ID_HEADER).and_then(|v| v.to_str().ok());
+            counter.request_ids.lock().unwrap().push(request_id.map(str::to_string));
+            let now = counter.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            counter.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(delay).await;
+            let agent = uri.path().trim_end_matches("/api/deploy").trim_start_matches('/').to_string();
+            counter.configs.lock().unwrap().insert(agent, config);
+            counter.in_flight.fetch_sub(1, Ordering::SeqCst);
+            counter.total.fetch_add(1, Ordering::SeqCst);
+            "ok"
+        };
+        let app = axum::Router::new()
+            .route("/api/deploy", axum::routing::post(deploy))
+            .route("/:agent/api/deploy", axum::routing::post(deploy))
+            .with_state(counter.clone());
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (url, counter)
+    }
+    
+    #[tokio::test]
+    async fn test_push_limit_shared_across_deployments() {
+        use std::sync::atomic::Ordering;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool();
+        let (url, counter) = spawn_counting_agent(Duration::from_millis(50)).await;
+        
+        let push_limit = Arc::new(Semaphore::new(2));
+        let mut deployments = Vec::new();
+        for g in 0..2 {
+            let group = WorkerGroupRepository::create(pool, &format!("group-{}", g), None, None).await.unwrap();
+            let deployment = DeploymentRepository::create(pool, &group.id, "v1", "basic", None, None).await.unwrap();
+            for a in 0..4 {
+                let name = format!("agent-{}-{}", g, a);
+                let agent = AgentRepository::create(pool, &name, &format!("{}/{}", url, name), Some(&group.id)).await.unwrap();
+                DeploymentRepository::add_agent(pool, &deployment.id, &agent.id).await.unwrap();
+            }
+            deployments.push(deployment.id);
+        }
+        
+        // Two executors, as two API requests would create, sharing one limit
+        let runs = deployments.iter().map(|deployment_id| {
+            let executor = DeploymentExecutor::new(db.clone(), git_store.clone())
+                .with_push_limit(push_limit.clone());
+            let deployment_id = deployment_id.clone();
+            async move { executor.execute_basic(&deployment_id, "[sources]", Duration::from_secs(30)).await }
+        });
+        for result in futures::future::join_all(runs).await {
+            result.unwrap();
+        }
+        
+        assert_eq!(counter.total.load(Ordering::SeqCst), 8);
+        assert_eq!(counter.max_in_flight.load(Ordering::SeqCst), 2);
+        assert_eq!(push_limit.available_permits(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_pushes_forward_request_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool().clone();
+        let (url, counter) = spawn_counting_agent(Duration::ZERO).await;
+        let executor = DeploymentExecutor::new(db.clone(), git_store.clone());
+        
+        let group = WorkerGroupRepository::create(&pool, "edge", None, None).await.unwrap();
+        let version = git_store.write_config("edge", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+        let mut agent_ids = Vec::new();
+        for a in 0..2 {
+            let name = format!("agent-{}", a);
+            let agent = AgentRepository::create(&pool, &name, &format!("{}/{}", url, name), Some(&group.id)).await.unwrap();
+            agent_ids.push(agent.id);
+        }
+        let ids: Vec<&str> = agent_ids.iter().map(String::as_str).collect();
+        
+        // Pushes made while handling a request carry its ID
+        let deployment = executor.create_direct_deployment(&group.id, &ids, &version, None).await.unwrap();
+        request_id::scope("req-123".to_string(), executor.execute_deployment(&deployment.id))
+            .await
+            .unwrap();
+        
+        // Without one, the deployment's pushes share a fresh ID
+        let deployment = executor.create_direct_deployment(&group.id, &ids, &version, None).await.unwrap();
+        executor.execute_deployment(&deployment.id).await.unwrap();
+        
+        let request_ids = counter.request_ids.lock().unwrap().clone();
+        assert_eq!(request_ids.len(), 4);
+        assert!(request_ids[..2].iter().all(|id| id.as_deref() == Some("req-123")));
+        let own = request_ids[2].clone().unwrap();
+        assert_ne!(own, "req-123");
+        assert_eq!(request_ids[3].as_deref(), Some(own.as_str()));
+    }
+    
+    #[tokio::test]
+    async fn test_abort_reverts_half_done_rolling_deploy() {
+        use std::sync::atomic::Ordering;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool().clone();
+        let (url, counter) = spawn_counting_agent(Duration::ZERO).await;
+        let executor = DeploymentExecutor::new(db.clone(), git_store.clone());
+        
+        let group = WorkerGroupRepository::create(&pool, "edge", None, None).await.unwrap();
+        let v1_config = "[sources.v1]\ntype = \"stdin\"\n";
+        let v1 = git_store.write_config("edge", v1_config).unwrap();
+        let v2 = git_store.write_config("edge", "[sources.v2]\ntype = \"stdin\"\n").unwrap();
+        let mut names = Vec::new();
+        let mut agent_ids = Vec::new();
+        for a in 0..4 {
+            let name = format!("agent-{}", a);
+            let agent = AgentRepository::create(&pool, &name, &format!("{}/{}", url, name), Some(&group.id)).await.unwrap();
+            names.push(name);
+            agent_ids.push(agent.id);
+        }
+        let ids: Vec<&str> = agent_ids.iter().map(String::as_str).collect();
+        
+        // Everyone on v1, then roll v2 out two at a time
+        let first = executor.create_direct_deployment(&group.id, &ids, &v1, None).await.unwrap();
+        executor.execute_deployment(&first.id).await.unwrap();
+        assert_eq!(counter.total.load(Ordering::SeqCst), 4);
+        
+        let options = DeploymentOptions {
+            rolling: Some(RollingOptions { batch_size: Some(2), batch_delay_secs: 1, ..Default::default() }),
+            ..Default::default()
+        };
+        let rollout = DeploymentRepository::create(
+            &pool, &group.id, &v2, "rolling", Some(&serde_json::to_string(&options).unwrap()), None,
+        ).await.unwrap();
+        for id in &ids {
+            DeploymentRepository::add_agent(&pool, &rollout.id, id).await.unwrap();
+        }
+        let run = {
+            let executor = executor.clone();
+            let deployment_id = rollout.id.clone();
+            tokio::spawn(async move { executor.execute_deployment(&deployment_id).await })
+        };
+        
+        // Abort while the rollout waits after its first batch
+        for _ in 0..100 {
+            if counter.total.load(Ordering::SeqCst) == 6 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let result = executor.abort_deployment(&rollout.id).await.unwrap();
+        assert_eq!(result.reverted, vec![agent_ids[0].clone(), agent_ids[1].clone()]);
+        assert!(result.revert_failed.is_empty());
+        assert!(result.not_reverted.is_empty());
+        assert_eq!(result.revert_deployments.len(), 1);
+        
+        let revert = DeploymentRepository::get_by_id(&pool, &result.revert_deployments[0]).await.unwrap().unwrap();
+        assert_eq!(revert.config_version, v1);
+        assert_eq!(revert.status, "completed");
+        
+        // The rollout stops at the batch boundary without touching the rest
+        assert_eq!(run.await.unwrap(), Err(ABORTED_BY_USER.to_string()));
+        let rollout = DeploymentRepository::get_by_id(&pool, &rollout.id).await.unwrap().unwrap();
+        assert_eq!(rollout.status, "cancelled");
+        assert_eq!(rollout.error.as_deref(), Some(ABORTED_BY_USER));
+        let stats = DeploymentRepository::get_stats(&pool, &rollout.id).await.unwrap();
+        assert_eq!((stats.completed, stats.cancelled, stats.pending), (2, 2, 0));
+        
+        assert_eq!(counter.total.load(Ordering::SeqCst), 8);
+        let configs = counter.configs.lock().unwrap().clone();
+        assert!(names.iter().all(|name| configs[name] == v1_config));
+        let applied = DeploymentRepository::last_applied_versions(&pool, &group.id).await.unwrap();
+        assert!(agent_ids.iter().all(|id| applied[id] == v1));
+        
+        assert_eq!(
+            executor.abort_deployment(&rollout.id).await.unwrap_err(),
+            "Deployment is already cancelled",
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_rolling_batch_percentage_over_ten_agents() {
+        use std::sync::atomic::Ordering;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool();
+        let (url, counter) = spawn_counting_agent(Duration::ZERO).await;
+        let events = Arc::new(DeploymentEventBus::new());
+        let executor = DeploymentExecutor::new(db.clone(), git_store).with_events(events.clone());
+        
+        let group = WorkerGroupRepository::create(pool, "fleet", None, None).await.unwrap();
+        let deployment = DeploymentRepository::create(pool, &group.id, "v1", "rolling", None, None).await.unwrap();
+        for a in 0..10 {
+            let name = format!("agent-{}", a);
+            let agent = AgentRepository::create(pool, &name, &format!("{}/{}", url, name), Some(&group.id)).await.unwrap();
+            DeploymentRepository::add_agent(pool, &deployment.id, &agent.id).await.unwrap();
+        }
+        
+        let mut rx = events.subscribe(&deployment.id);
+        let options = RollingOptions { batch_percentage: Some(20), batch_delay_secs: 0, ..Default::default() };
+        executor.execute_rolling(&deployment.id, "[sources]", Duration::from_secs(30), &options).await.unwrap();
+        
+        let mut batches = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let DeploymentEvent::BatchStarted { agents, .. } = event {
+                batches.push(agents);
+            }
+        }
+        assert_eq!(batches, vec![2; 5]);
+        assert_eq!(counter.total.load(Ordering::SeqCst), 10);
+    }
+    
+    #[tokio::test]
+    async fn test_start_deployment_rejects_batch_size_with_percentage() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let executor = DeploymentExecutor::new(db.clone(), git_store);
+        let group = WorkerGroupRepository::create(db.pool(), "fleet", None, None).await.unwrap();
+        
+        let options = DeploymentOptions {
+            rolling: Some(RollingOptions { batch_size: Some(2), batch_percentage: Some(20), ..Default::default() }),
+            ..Default::default()
+        };
+        let err = executor.start_deployment(&group.id, "v1", Some(options), None, true, None).await.unwrap_err();
+        assert_eq!(err, "batch_size and batch_percentage are mutually exclusive");
+        assert!(DeploymentRepository::list_by_group(db.pool(), &group.id, 10).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_start_deployment_uses_group_default_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let executor = DeploymentExecutor::new(db.clone(), git_store);
+        let pool = db.pool();
+        
+        // Approval keeps the deployments from running
+        let group = WorkerGroupRepository::create(pool, "fleet", None, None).await.unwrap();
+        WorkerGroupRepository::update(pool, &group.id, None, None, Some("rolling"), Some(true), None).await.unwrap();
+        let defaults = DeploymentOptions {
+            rolling: Some(RollingOptions { batch_size: Some(5), batch_delay_secs: 10, ..Default::default() }),
+            per_agent_timeout_secs: 90,
+            ..Default::default()
+        };
+        WorkerGroupRepository::set_default_deployment_options(pool, &group.id, Some(&serde_json::to_string(&defaults).unwrap()))
+            .await
+            .unwrap();
+        
+        let stored_options = |deployment_id: String| async move {
+            let deployment = DeploymentRepository::get_by_id(pool, &deployment_id).await.unwrap().unwrap();
+            serde_json::from_str::<DeploymentOptions>(&deployment.options.unwrap()).unwrap()
+        };
+        
+        let result = executor.start_deployment(&group.id, "v1", None, None, true, None).await.unwrap();
+        let options = stored_options(result.deployment_id).await;
+        assert_eq!(options.rolling.as_ref().unwrap().batch_size, Some(5));
+        assert_eq!(options.rolling.as_ref().unwrap().batch_delay_secs, 10);
+        assert_eq!(options.per_agent_timeout_secs, 90);
+        
+        // Options passed with the deployment win
+        let explicit = DeploymentOptions {
+            rolling: Some(RollingOptions { batch_size: Some(1), ..Default::default() }),
+            ..Default::default()
+        };
+        let result = executor.start_deployment(&group.id, "v1", Some(explicit), None, true, None).await.unwrap();
+        let options = stored_options(result.deployment_id).await;
+        assert_eq!(options.rolling.unwrap().batch_size, Some(1));
+        assert_eq!(options.per_agent_timeout_secs, 30);
+    }
+    
+    #[tokio::test]
+    async fn test_deploy_to_single_agent_in_group() {
+        use std::sync::atomic::Ordering;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool();
+        let (url, counter) = spawn_counting_agent(Duration::ZERO).await;
+        
+        let group = WorkerGroupRepository::create(pool, "prod", None, None).await.unwrap();
+        WorkerGroupRepository::update(pool, &group.id, None, None, Some("rolling"), Some(true), None).await.unwrap();
+        let version = git_store.write_config("prod", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+        let mut agents = Vec::new();
+        for name in ["edge-1", "edge-2", "edge-3"] {
+            agents.push(AgentRepository::create(pool, name, &format!("{}/{}", url, name), Some(&group.id)).await.unwrap());
+        }
+        // Nested path the agent server doesn't route, so the push gets a 404
+        let broken = AgentRepository::create(pool, "edge-4", &format!("{}/no/such", url), Some(&group.id)).await.unwrap();
+        
+        // Approval and the rolling strategy don't apply to a test deploy
+        let executor = DeploymentExecutor::new(db.clone(), git_store.clone());
+        let result = executor.deploy_to_single(&agents[1].id, &version, None).await.unwrap();
+        assert_eq!(result.status, "completed");
+        assert_eq!(counter.total.load(Ordering::SeqCst), 1);
+        
+        let deployment = DeploymentRepository::get_by_id(pool, &result.deployment_id).await.unwrap().unwrap();
+        assert_eq!(deployment.status, "completed");
+        assert_eq!(deployment.strategy, "basic");
+        assert_eq!(deployment.group_id, group.id);
+        let targets = DeploymentRepository::get_agents(pool, &result.deployment_id).await.unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].agent_id, agents[1].id);
+        assert_eq!(targets[0].status, "completed");
+        
+        // A failed push is the answer, not an error
+        let result = executor.deploy_to_single(&broken.id, &version, None).await.unwrap();
+        assert_eq!(result.status, "failed");
+        let targets = DeploymentRepository::get_agents(pool, &result.deployment_id).await.unwrap();
+        assert_eq!(targets[0].status, "failed");
+        assert_eq!(counter.total.load(Ordering::SeqCst), 1);
+        
+        assert!(executor.deploy_to_single(&agents[0].id, "0000000000000000000000000000000000000000", None).await.is_err());
+        let lone = AgentRepository::create(pool, "lone", "http://127.0.0.1:2", None).await.unwrap();
+        assert_eq!(executor.deploy_to_single(&lone.id, &version, None).await.unwrap_err(), "Agent is not in a group");
+    }
+    
+    async fn scheduling_fixture() -> (tempfile::TempDir, DeploymentExecutor, String, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        
+        let group = WorkerGroupRepository::create(db.pool(), "nightly", None, None).await.unwrap();
+        let version = git_store.write_config("nightly", "[sources]\n").unwrap();
+        
+        (dir, DeploymentExecutor::new(db, git_store), group.id, version)
+    }
+    
+    #[tokio::test]
+    async fn test_past_scheduled_deployment_runs_immediately() {
+        let (_dir, executor, group_id, version) = scheduling_fixture().await;
+        
+        let at = Utc::now() - chrono::Duration::hours(1);
+        let result = executor.start_deployment(&group_id, &version, None, None, true, Some(at)).await.unwrap();
+        assert_eq!(result.status, "in_progress");
+        
+        let deployment = DeploymentRepository::get_by_id(executor.db.pool(), &result.deployment_id).await.unwrap().unwrap();
+        assert_eq!(deployment.status, "completed");
+        assert!(deployment.scheduled_at.is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_future_scheduled_deployment_waits() {
+        let (_dir, executor, group_id, version) = scheduling_fixture().await;
+        let pool = executor.db.pool().clone();
+        
+        let at = Utc::now() + chrono::Duration::hours(1);
+        let result = executor.start_deployment(&group_id, &version, None, None, true, Some(at)).await.unwrap();
+        assert_eq!(result.status, "scheduled");
+        
+        let deployment = DeploymentRepository::get_by_id(&pool, &result.deployment_id).await.unwrap().unwrap();
+        assert_eq!(deployment.status, "scheduled");
+        assert!(deployment.scheduled_at.is_some());
+        
+        // Not due yet
+        assert!(executor.run_due_scheduled(Utc::now()).await.unwrap().is_empty());
+        
+        // Due once the clock passes the scheduled time
+        let later = at + chrono::Duration::minutes(1);
+        assert_eq!(executor.run_due_scheduled(later).await.unwrap(), vec![result.deployment_id.clone()]);
+        for _ in 0..50 {
+            let deployment = DeploymentRepository::get_by_id(&pool, &result.deployment_id).await.unwrap().unwrap();
+            if deployment.status == "completed" {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("scheduled deployment did not complete");
+    }
+    
+    #[tokio::test]
+    async fn test_cancelled_scheduled_deployment_never_fires() {
+        let (_dir, executor, group_id, version) = scheduling_fixture().await;
+        
+        let at = Utc::now() + chrono::Duration::hours(1);
+        let result = executor.start_deployment(&group_id, &version, None, None, true, Some(at)).await.unwrap();
+        executor.cancel_deployment(&result.deployment_id).await.unwrap();
+        
+        let later = at + chrono::Duration::minutes(1);
+        assert!(executor.run_due_scheduled(later).await.unwrap().is_empty());
+        
+        let deployment = DeploymentRepository::get_by_id(executor.db.pool(), &result.deployment_id).await.unwrap().unwrap();
+        assert_eq!(deployment.status, "cancelled");
+    }
+    
+    #[tokio::test]
+    async fn test_frozen_group_blocks_deployments() {
+        let (_dir, executor, group_id, version) = scheduling_fixture().await;
+        let pool = executor.db.pool().clone();
+    
+        let at = Utc::now() + chrono::Duration::hours(1);
+        let scheduled = executor.start_deployment(&group_id, &version, None, None, true, Some(at)).await.unwrap();
+        WorkerGroupRepository::set_frozen(&pool, &group_id, true).await.unwrap();
+    
+        let err = executor.start_deployment(&group_id, &version, None, None, false, None).await.unwrap_err();
+        assert!(err.contains("frozen"));
+    
+        // A scheduled deployment that comes due waits for the unfreeze
+        let later = at + chrono::Duration::minutes(1);
+        assert!(executor.run_due_scheduled(later).await.unwrap().is_empty());
+        let deployment = DeploymentRepository::get_by_id(&pool, &scheduled.deployment_id).await.unwrap().unwrap();
+        assert_eq!(deployment.status, "scheduled");
+    
+        WorkerGroupRepository::set_frozen(&pool, &group_id, false).await.unwrap();
+        assert_eq!(executor.run_due_scheduled(later).await.unwrap(), vec![scheduled.deployment_id]);
+    }
+    
+    #[tokio::test]
+    async fn test_maintenance_holds_scheduled_deployments() {
+        let (_dir, executor, group_id, version) = scheduling_fixture().await;
+        let maintenance = Arc::new(MaintenanceMode::new(true));
+        let executor = executor.with_maintenance(maintenance.clone());
+        
+        let at = Utc::now() + chrono::Duration::hours(1);
+        let scheduled = executor.start_deployment(&group_id, &version, None, None, true, Some(at)).await.unwrap();
+        
+        let later = at + chrono::Duration::minutes(1);
+        assert!(executor.run_due_scheduled(later).await.unwrap().is_empty());
+        let deployment = DeploymentRepository::get_by_id(executor.db.pool(), &scheduled.deployment_id).await.unwrap().unwrap();
+        assert_eq!(deployment.status, "scheduled");
+        
+        maintenance.set(false, None, None);
+        assert_eq!(executor.run_due_scheduled(later).await.unwrap(), vec![scheduled.deployment_id]);
+    }
+    
+    #[tokio::test]
+    async fn test_shutdown_during_rolling_deploy_cancels_remaining_agents() {
+        use std::sync::atomic::Ordering;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool().clone();
+        let (url, counter) = spawn_counting_agent(Duration::from_millis(50)).await;
+        
+        let group = WorkerGroupRepository::create(&pool, "rolling", None, None).await.unwrap();
+        let version = git_store.write_config("rolling", "[sources]\n").unwrap();
+        let options = DeploymentOptions {
+            rolling: Some(RollingOptions { batch_size: Some(1), batch_delay_secs: 60, ..Default::default() }),
+            ..Default::default()
+        };
+        let deployment = DeploymentRepository::create(
+            &pool, &group.id, &version, "rolling", Some(&serde_json::to_string(&options).unwrap()), None,
+        ).await.unwrap();
+        for a in 0..4 {
+            let name = format!("agent-{}", a);
+            let agent = AgentRepository::create(&pool, &name, &format!("{}/{}", url, name), Some(&group.id)).await.unwrap();
+            DeploymentRepository::add_agent(&pool, &deployment.id, &agent.id).await.unwrap();
+        }
+        
+        let shutdown = Arc::new(ShutdownCoordinator::new());
+        let executor = DeploymentExecutor::new(db.clone(), git_store).with_shutdown(shutdown.clone());
+        let run = {
+            let executor = executor.clone();
+            let deployment_id = deployment.id.clone();
+            tokio::spawn(async move { executor.execute_deployment(&deployment_id).await })
+        };
+        
+        // Shut down while the rollout waits between batches
+        for _ in 0..100 {
+            if counter.total.load(Ordering::SeqCst) == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(shutdown.shutdown(Duration::from_secs(5)).await);
+        assert_eq!(run.await.unwrap(), Err(SHUTDOWN_REASON.to_string()));
+        
+        let deployment = DeploymentRepository::get_by_id(&pool, &deployment.id).await.unwrap().unwrap();
+        assert_eq!(deployment.status, "cancelled");
+        assert_eq!(deployment.error.as_deref(), Some(SHUTDOWN_REASON));
+        
+        let stats = DeploymentRepository::get_stats(&pool, &deployment.id).await.unwrap();
+        assert_eq!(stats.completed, 1);
+        assert_eq!(stats.cancelled, 3);
+        assert_eq!(stats.in_progress, 0);
+        assert_eq!(stats.pending, 0);
+        assert_eq!(counter.total.load(Ordering::SeqCst), 1);
+        
+        // No new deployments once shutting down
+        let result = executor.start_deployment(&group.id, "v2", None, None, true, None).await;
+        assert_eq!(result.unwrap_err(), SHUTTING_DOWN);
+    }
+    
+    #[test]
+    fn test_deployment_options_timeout_default() {
+        let opts: DeploymentOptions = serde_json::from_str(r#"{"rolling": null, "canary": null}"#).unwrap();
+        assert_eq!(opts.per_agent_timeout_secs, 30);
+        assert_eq!(DeploymentOptions::default().per_agent_timeout(), Duration::from_secs(30));
+    }
+    
+    #[tokio::test]
+    async fn test_slow_agent_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool();
+        let (url, _counter) = spawn_counting_agent(Duration::from_secs(5)).await;
+        
+        let group = WorkerGroupRepository::create(pool, "slow", None, None).await.unwrap();
+        let deployment = DeploymentRepository::create(pool, &group.id, "v1", "basic", None, None).await.unwrap();
+        let agent = AgentRepository::create(pool, "slow-agent", &url, Some(&group.id)).await.unwrap();
+        DeploymentRepository::add_agent(pool, &deployment.id, &agent.id).await.unwrap();
+        
+        let executor = DeploymentExecutor::new(db.clone(), git_store);
+        let started = std::time::Instant::now();
+        let result = executor.execute_basic(&deployment.id, "[sources]", Duration::from_secs(1)).await;
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        
+        let agents = DeploymentRepository::get_agents(pool, &deployment.id).await.unwrap();
+        assert_eq!(agents[0].status, "timeout");
+        assert!(agents[0].completed_at.is_some());
+        
+        let stats = DeploymentRepository::get_stats(pool, &deployment.id).await.unwrap();
+        assert_eq!(stats.timed_out, 1);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(stats.pending, 0);
+        assert!(DeploymentRepository::all_agents_completed(pool, &deployment.id).await.unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_failed_deployment_notifies_group_channels() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let git_store = Arc::new(GitStore::open_or_init(&dir.path().join("configs")).unwrap());
+        let pool = db.pool();
+        
+        // The agent rejects every push; the webhook records what it receives
+        let received = Arc::new(std::sync::Mutex::new(Vec::<serde_json::Value>::new()));
+        let app = axum::Router::new()
+            .route("/api/deploy", axum::routing::post(|| async {
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR
+            }))
+            .route("/hook", axum::routing::post(
+                |axum::extract::State(received): axum::extract::State<Arc<std::sync::Mutex<Vec<serde_json::Value>>>>,
+                 axum::Json(alert): axum::Json<serde_json::Value>| async move {
+                    received.lock().unwrap().push(alert);
+                },
+            ))
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let group = WorkerGroupRepository::create(pool, "prod", None, None).await.unwrap();
+        let channels = serde_json::to_string(&[NotificationChannel::Webhook {
+            id: "ops".to_string(),
+            name: "Ops webhook".to_string(),
+            url: format!("{}/hook", url),
+            headers: Default::default(),
+            signing_secret: None,
+        }]).unwrap();
+        WorkerGroupRepository::set_notification_channels(pool, &group.id, Some(&channels)).await.unwrap();
+        let version = git_store.write_config("prod", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+        
+        let agent = AgentRepository::create(pool, "agent-1", &url, Some(&group.id)).await.unwrap();
+        let deployment = DeploymentRepository::create(pool, &group.id, &version, "basic", None, None).await.unwrap();
+        DeploymentRepository::add_agent(pool, &deployment.id, &agent.id).await.unwrap();
+        
+        let executor = DeploymentExecutor::new(db.clone(), git_store.clone())
+            .with_alerts(Arc::new(AlertManager::new().with_retry(1, Duration::ZERO)));
+        assert!(executor.execute_deployment(&deployment.id).await.is_err());
+        
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0]["severity"], "critical");
+        assert_eq!(received[0]["source"], format!("deployment:{}", deployment.id));
+    }
 }
@@ -0,0 +1,321 @@
+//! Deployment Reports
+//!
+//! A self-contained record of a finished rollout to share after the fact:
+//! what was deployed and by whom, who approved it, how each agent fared,
+//! how long it took, and the config diff against the group's previous
+//! deployment. Rendered as JSON, Markdown or HTML.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Who approved or rejected a deployment, and when
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ReportApproval {
+    /// The group required approval before deploying
+    pub required: bool,
+    pub approved_by: Option<String>,
+    pub approved_at: Option<String>,
+    pub rejected_by: Option<String>,
+    pub rejected_at: Option<String>,
+    pub rejection_reason: Option<String>,
+}
+
+/// When a deployment moved through its stages (UTC)
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ReportTiming {
+    pub created_at: String,
+    pub scheduled_at: Option<String>,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    /// From start to completion
+    pub duration_secs: Option<i64>,
+}
+
+/// How the deployment went on one agent
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ReportAgent {
+    pub agent_id: String,
+    /// Missing when the agent has since been purged
+    pub agent_name: Option<String>,
+    pub status: String,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub duration_secs: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// A shareable record of one deployment
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DeploymentReport {
+    pub deployment_id: String,
+    pub group_id: String,
+    pub group_name: String,
+    pub config_version: String,
+    /// Version of the group's last completed deployment before this one
+    pub previous_version: Option<String>,
+    pub strategy: String,
+    pub status: String,
+    pub created_by: Option<String>,
+    pub error: Option<String>,
+    pub approval: ReportApproval,
+    pub timing: ReportTiming,
+    /// Agents per outcome (`completed`, `failed`, ...)
+    pub outcomes: BTreeMap<String, usize>,
+    pub agents: Vec<ReportAgent>,
+    /// Unified diff of the group's config against `previous_version`, or
+    /// against an empty config for the first deployment; `None` when the
+    /// versions aren't in the config store
+    pub config_diff: Option<String>,
+    pub generated_at: String,
+}
+
+/// Seconds between two database timestamps (`YYYY-MM-DD HH:MM:SS`)
+pub fn seconds_between(start: Option<&str>, end: Option<&str>) -> Option<i64> {
+    let parse = |t: &str| chrono::NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M:%S").ok();
+    let start = parse(start?)?;
+    let end = parse(end?)?;
+    Some(end.signed_duration_since(start).num_seconds())
+}
+
+impl DeploymentReport {
+    /// Count agents per outcome
+    pub fn count_outcomes(agents: &[ReportAgent]) -> BTreeMap<String, usize> {
+        let mut outcomes = BTreeMap::new();
+        for agent in agents {
+            *outcomes.entry(agent.status.clone()).or_default() += 1;
+        }
+        outcomes
+    }
+
+    /// Suggested file name for a download, e.g. `deployment-1a2b3c4d.md`
+    pub fn file_name(&self, extension: &str) -> String {
+        let short: String = self.deployment_id.chars().take(8).collect();
+        format!("deployment-{}.{}", short, extension)
+    }
+
+    /// Render as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let or_dash = |v: &Option<String>| v.clone().unwrap_or_else(|| "—".to_string());
+
+        let _ = writeln!(md, "# Deployment {} to {}\n", self.deployment_id, self.group_name);
+        let _ = writeln!(md, "| | |\n|---|---|");
+        let _ = writeln!(md, "| Status | {} |", self.status);
+        let _ = writeln!(md, "| Strategy | {} |", self.strategy);
+        let _ = writeln!(md, "| Config version | `{}` |", self.config_version);
+        let _ = writeln!(md, "| Previous version | {} |", self.previous_version.as_ref().map(|v| format!("`{}`", v)).unwrap_or_else(|| "—".to_string()));
+        let _ = writeln!(md, "| Created by | {} |", or_dash(&self.created_by));
+        let _ = writeln!(md, "| Created | {} |", self.timing.created_at);
+        let _ = writeln!(md, "| Started | {} |", or_dash(&self.timing.started_at));
+        let _ = writeln!(md, "| Completed | {} |", or_dash(&self.timing.completed_at));
+        let _ = writeln!(md, "| Duration | {} |", self.timing.duration_secs.map(|s| format!("{}s", s)).unwrap_or_else(|| "—".to_string()));
+        if let Some(error) = &self.error {
+            let _ = writeln!(md, "| Error | {} |", markdown_cell(error));
+        }
+
+        let _ = writeln!(md, "\n## Approval\n");
+        if !self.approval.required {
+            let _ = writeln!(md, "Not required.");
+        } else if let Some(by) = &self.approval.approved_by {
+            let _ = writeln!(md, "Approved by {} at {}.", by, or_dash(&self.approval.approved_at));
+        } else if let Some(by) = &self.approval.rejected_by {
+            let _ = writeln!(md, "Rejected by {} at {}: {}", by, or_dash(&self.approval.rejected_at), or_dash(&self.approval.rejection_reason));
+        } else {
+            let _ = writeln!(md, "Required, not yet given.");
+        }
+
+        let _ = writeln!(md, "\n## Agents\n");
+        let summary: Vec<String> = self.outcomes.iter().map(|(status, n)| format!("{} {}", n, status)).collect();
+        let _ = writeln!(md, "{} agent(s): {}\n", self.agents.len(), if summary.is_empty() { "none".to_string() } else { summary.join(", ") });
+        if !self.agents.is_empty() {
+            let _ = writeln!(md, "| Agent | Status | Started | Completed | Duration | Error |\n|---|---|---|---|---|---|");
+            for agent in &self.agents {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {} |",
+                    markdown_cell(agent.agent_name.as_deref().unwrap_or(&agent.agent_id)),
+                    agent.status,
+                    or_dash(&agent.started_at),
+                    or_dash(&agent.completed_at),
+                    agent.duration_secs.map(|s| format!("{}s", s)).unwrap_or_else(|| "—".to_string()),
+                    agent.error.as_deref().map(markdown_cell).unwrap_or_default(),
+                );
+            }
+        }
+
+        let _ = writeln!(md, "\n## Config Diff\n");
+        match &self.config_diff {
+            Some(diff) if !diff.is_empty() => {
+                let _ = writeln!(md, "```diff\n{}\n```", diff.trim_end());
+            }
+            Some(_) => {
+                let _ = writeln!(md, "No changes.");
+            }
+            None => {
+                let _ = writeln!(md, "Unavailable.");
+            }
+        }
+
+        let _ = writeln!(md, "\n_Generated {}_", self.generated_at);
+        md
+    }
+
+    /// Render as a standalone HTML page
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let text = |v: Option<&str>| escape_html(v.unwrap_or("—"));
+
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Deployment {id}</title>\n\
+             <style>body{{font-family:sans-serif;max-width:60rem;margin:2rem auto}}\
+             table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:.25rem .5rem;text-align:left}}\
+             pre{{background:#f6f8fa;padding:1rem;overflow-x:auto}}</style>\n</head>\n<body>\n\
+             <h1>Deployment {id} to {group}</h1>\n<table>\n",
+            id = escape_html(&self.deployment_id),
+            group = escape_html(&self.group_name),
+        );
+        let duration = self.timing.duration_secs.map(|s| format!("{}s", s));
+        let rows = [
+            ("Status", Some(self.status.as_str())),
+            ("Strategy", Some(self.strategy.as_str())),
+            ("Config version", Some(self.config_version.as_str())),
+            ("Previous version", self.previous_version.as_deref()),
+            ("Created by", self.created_by.as_deref()),
+            ("Created", Some(self.timing.created_at.as_str())),
+            ("Started", self.timing.started_at.as_deref()),
+            ("Completed", self.timing.completed_at.as_deref()),
+            ("Duration", duration.as_deref()),
+            ("Approved by", self.approval.approved_by.as_deref()),
+            ("Rejected by", self.approval.rejected_by.as_deref()),
+            ("Error", self.error.as_deref()),
+        ];
+        for (label, value) in rows {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", label, text(value));
+        }
+        let _ = writeln!(html, "</table>");
+
+        let _ = writeln!(html, "<h2>Agents</h2>\n<table>\n<tr><th>Agent</th><th>Status</th><th>Started</th><th>Completed</th><th>Error</th></tr>");
+        for agent in &self.agents {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(agent.agent_name.as_deref().unwrap_or(&agent.agent_id)),
+                escape_html(&agent.status),
+                text(agent.started_at.as_deref()),
+                text(agent.completed_at.as_deref()),
+                escape_html(agent.error.as_deref().unwrap_or("")),
+            );
+        }
+        let _ = writeln!(html, "</table>");
+
+        let _ = writeln!(html, "<h2>Config Diff</h2>");
+        match &self.config_diff {
+            Some(diff) if !diff.is_empty() => {
+                let _ = writeln!(html, "<pre>{}</pre>", escape_html(diff));
+            }
+            Some(_) => {
+                let _ = writeln!(html, "<p>No changes.</p>");
+            }
+            None => {
+                let _ = writeln!(html, "<p>Unavailable.</p>");
+            }
+        }
+
+        let _ = write!(html, "<p><em>Generated {}</em></p>\n</body>\n</html>\n", escape_html(&self.generated_at));
+        html
+    }
+}
+
+/// Keep a value from breaking out of its Markdown table cell
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> DeploymentReport {
+        let agents = vec![
+            ReportAgent {
+                agent_id: "a1".to_string(),
+                agent_name: Some("edge-1".to_string()),
+                status: "completed".to_string(),
+                started_at: Some("2026-02-03 12:00:00".to_string()),
+                completed_at: Some("2026-02-03 12:00:04".to_string()),
+                duration_secs: Some(4),
+                error: None,
+            },
+            ReportAgent {
+                agent_id: "a2".to_string(),
+                agent_name: None,
+                status: "failed".to_string(),
+                started_at: None,
+                completed_at: None,
+                duration_secs: None,
+                error: Some("HTTP 500 | <timeout>".to_string()),
+            },
+        ];
+        DeploymentReport {
+            deployment_id: "1a2b3c4d-0000".to_string(),
+            group_id: "g1".to_string(),
+            group_name: "edge".to_string(),
+            config_version: "def456".to_string(),
+            previous_version: Some("abc123".to_string()),
+            strategy: "rolling".to_string(),
+            status: "failed".to_string(),
+            created_by: Some("alice".to_string()),
+            error: None,
+            approval: ReportApproval {
+                required: true,
+                approved_by: Some("bob".to_string()),
+                approved_at: Some("2026-02-03 11:59:00".to_string()),
+                ..ReportApproval::default()
+            },
+            timing: ReportTiming {
+                created_at: "2026-02-03 11:58:00".to_string(),
+                ..ReportTiming::default()
+            },
+            outcomes: DeploymentReport::count_outcomes(&agents),
+            agents,
+            config_diff: Some("-level = \"info\"\n+level = \"debug\"\n".to_string()),
+            generated_at: "2026-02-03T12:05:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_seconds_between() {
+        assert_eq!(seconds_between(Some("2026-02-03 12:00:00"), Some("2026-02-03 12:01:30")), Some(90));
+        assert_eq!(seconds_between(Some("2026-02-03 12:00:00"), None), None);
+        assert_eq!(seconds_between(Some("soon"), Some("2026-02-03 12:00:00")), None);
+    }
+
+    #[test]
+    fn test_markdown_and_html_rendering() {
+        let report = report();
+        assert_eq!(report.file_name("md"), "deployment-1a2b3c4d.md");
+
+        let md = report.to_markdown();
+        assert!(md.contains("Approved by bob at 2026-02-03 11:59:00."));
+        assert!(md.contains("2 agent(s): 1 completed, 1 failed"));
+        assert!(md.contains("| edge-1 | completed |"));
+        // Unnamed agents fall back to their id; pipes can't split the cell
+        assert!(md.contains("| a2 | failed |"));
+        assert!(md.contains("HTTP 500 \\| <timeout>"));
+        assert!(md.contains("```diff\n-level = \"info\"\n+level = \"debug\"\n```"));
+
+        let html = report.to_html();
+        assert!(html.contains("<td>HTTP 500 | &lt;timeout&gt;</td>"));
+        assert!(html.contains("<pre>-level = &quot;info&quot;"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Graceful Shutdown
+//!
+//! Tracks running deployments so the server can drain them on exit. Once
+//! shutdown begins no new deployment starts; rolling and canary deployments
+//! stop at the next batch boundary and their remaining agents are marked
+//! cancelled, so nothing is left `in_progress` after the process exits.
+
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Default time to wait for running deployments before exiting anyway
+pub const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 30;
+
+/// Error recorded on deployments and agents stopped by shutdown
+pub const SHUTDOWN_REASON: &str = "Cancelled (shutdown)";
+
+/// Grace period from `VECTORIZE_SHUTDOWN_GRACE_SECS`
+pub fn shutdown_grace_from_env() -> Duration {
+    let secs = std::env::var("VECTORIZE_SHUTDOWN_GRACE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_SHUTDOWN_GRACE_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Coordinates draining running deployments on shutdown
+#[derive(Debug)]
+pub struct ShutdownCoordinator {
+    shutting_down: watch::Sender<bool>,
+    active: watch::Sender<usize>,
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self {
+            shutting_down: watch::channel(false).0,
+            active: watch::channel(0).0,
+        }
+    }
+}
+
+impl ShutdownCoordinator {
+    /// Create a new coordinator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether shutdown has begun
+    pub fn is_shutting_down(&self) -> bool {
+        *self.shutting_down.borrow()
+    }
+
+    /// Number of deployments currently running
+    pub fn active_deployments(&self) -> usize {
+        *self.active.borrow()
+    }
+
+    /// Register a running deployment, or None once shutdown has begun
+    ///
+    /// The deployment counts as running until the guard is dropped.
+    pub fn track(&self) -> Option<DeploymentGuard<'_>> {
+        if self.is_shutting_down() {
+            return None;
+        }
+        self.active.send_modify(|n| *n += 1);
+        Some(DeploymentGuard { coordinator: self })
+    }
+
+    /// Sleep for `duration`, returning early if shutdown begins
+    ///
+    /// Returns true if the full duration elapsed.
+    pub async fn sleep(&self, duration: Duration) -> bool {
+        let mut rx = self.shutting_down.subscribe();
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => true,
+            _ = rx.wait_for(|shutting_down| *shutting_down) => false,
+        }
+    }
+
+    /// Stop accepting deployments and wait for running ones to finish
+    ///
+    /// Returns true if every deployment finished within `grace`.
+    pub async fn shutdown(&self, grace: Duration) -> bool {
+        self.shutting_down.send_replace(true);
+
+        let mut rx = self.active.subscribe();
+        let drained = tokio::time::timeout(grace, rx.wait_for(|active| *active == 0))
+            .await
+            .is_ok();
+        drained
+    }
+}
+
+/// Marks a deployment as running until dropped
+#[derive(Debug)]
+pub struct DeploymentGuard<'a> {
+    coordinator: &'a ShutdownCoordinator,
+}
+
+impl Drop for DeploymentGuard<'_> {
+    fn drop(&mut self) {
+        self.coordinator.active.send_modify(|n| *n -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_running_deployments() {
+        let coordinator = std::sync::Arc::new(ShutdownCoordinator::new());
+        let guard = coordinator.track().unwrap();
+        assert_eq!(coordinator.active_deployments(), 1);
+
+        let waiter = {
+            let coordinator = coordinator.clone();
+            tokio::spawn(async move { coordinator.shutdown(Duration::from_secs(5)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // No new deployments once shutdown has begun
+        assert!(coordinator.is_shutting_down());
+        assert!(coordinator.track().is_none());
+        assert!(!waiter.is_finished());
+
+        drop(guard);
+        assert!(waiter.await.unwrap());
+        assert_eq!(coordinator.active_deployments(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_grace_period_is_bounded() {
+        let coordinator = ShutdownCoordinator::new();
+        let _guard = coordinator.track().unwrap();
+
+        assert!(!coordinator.shutdown(Duration::from_millis(20)).await);
+    }
+
+    #[tokio::test]
+    async fn test_sleep_is_interrupted_by_shutdown() {
+        let coordinator = std::sync::Arc::new(ShutdownCoordinator::new());
+        assert!(coordinator.sleep(Duration::from_millis(1)).await);
+
+        let sleeper = {
+            let coordinator = coordinator.clone();
+            tokio::spawn(async move { coordinator.sleep(Duration::from_secs(60)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        coordinator.shutdown(Duration::from_millis(1)).await;
+
+        assert!(!sleeper.await.unwrap());
+    }
+}
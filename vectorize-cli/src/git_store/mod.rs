@@ -5,15 +5,21 @@
 //! - Auto-commit on config changes
 //! - Version history and rollback
 //! - Optional remote sync (GitHub, GitLab, etc.)
+//! - Optional GPG/SSH commit signing
+//! - Optional validation of configs before they are committed
 
 pub mod repository;
+pub mod signing;
+pub mod templates;
 
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
 use git2::{Repository, Signature, IndexAddOption};
-use tracing::info;
+use tracing::{info, warn};
 use thiserror::Error;
 
+use crate::validation::{ConfigValidator, ValidationError};
+
 /// Git store errors
 #[derive(Error, Debug)]
 pub enum GitStoreError {
@@ -34,13 +40,78 @@ pub enum GitStoreError {
     
     #[error("Lock error: {0}")]
     Lock(String),
+    
+    #[error("Not found: {0}")]
+    NotFound(String),
+    
+    #[error("Signing error: {0}")]
+    Signing(String),
+    
+    #[error("Config for group {group} changed since {expected}: now at {current}")]
+    StaleConfig { group: String, expected: String, current: String },
+    
+    #[error("Invalid template: {0}")]
+    Template(String),
+    
+    #[error("Missing template variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+    
+    #[error("Invalid config: {}", .0.iter().map(|e| e.message.as_str()).collect::<Vec<_>>().join("; "))]
+    Invalid(Vec<ValidationError>),
+}
+
+pub use signing::{CommitSignature, SigningFormat, SigningKey};
+pub use templates::{TemplateInfo, TemplateVariable};
+
+/// Default `.gitignore` for the config repository
+const DEFAULT_GITIGNORE: &str = "\
+# Managed by Vectorize
+# Editor and OS files
+*.swp
+*.swo
+*~
+.#*
+.DS_Store
+Thumbs.db
+.idea/
+.vscode/
+# Temporary and backup files
+*.tmp
+*.bak
+*.orig
+*.rej
+";
+
+/// Environment variable enabling validation of configs before they are committed
+pub const VALIDATE_ON_COMMIT_ENV: &str = "VECTORIZE_VALIDATE_ON_COMMIT";
+
+/// Whether `VECTORIZE_VALIDATE_ON_COMMIT` is set (`true`/`1`/`yes`/`on`)
+pub fn validate_on_commit_from_env() -> bool {
+    std::env::var(VALIDATE_ON_COMMIT_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
 }
 
+/// Scratch ref holding a bundle's HEAD while it is imported
+const BUNDLE_IMPORT_REF: &str = "refs/vectorize/bundle-import";
+
+/// Newest commits whose diffs `search_history` scans; older ones match on message only
+pub const MAX_DIFF_SEARCH_COMMITS: usize = 500;
+
+/// Longest snippet returned with a history search match
+const MAX_SNIPPET_CHARS: usize = 200;
+
 /// Git-based configuration store
 /// Thread-safe via internal Mutex
 pub struct GitStore {
     repo: Mutex<Repository>,
     path: PathBuf,
+    /// Key used to sign new commits; unsigned when `None`
+    signing_key: RwLock<Option<SigningKey>>,
+    /// Serializes config writes so a version check and its commit are atomic
+    config_write: Mutex<()>,
+    /// Validates configs before `write_config` commits them
+    validator: Option<Arc<ConfigValidator>>,
 }
 
 impl GitStore {
@@ -52,6 +123,9 @@ impl GitStore {
         let repo = match Repository::open(path) {
             Ok(repo) => {
                 info!("Opened existing git repository at {}", path.display());
+                // Repositories created before ignore support get the default
+                // rules; committed with the next change
+                Self::ensure_gitignore(path)?;
                 repo
             }
             Err(_) => {
@@ -71,12 +145,33 @@ impl GitStore {
         Ok(Self {
             repo: Mutex::new(repo),
             path: path.to_path_buf(),
+            signing_key: RwLock::new(None),
+            config_write: Mutex::new(()),
+            validator: None,
         })
     }
     
+    /// Validate configs before committing them
+    ///
+    /// `write_config` and `write_config_if` then refuse configs that fail
+    /// validation with `GitStoreError::Invalid`, so invalid config can't be
+    /// stored and later deployed. `write_config_force` skips the check.
+    pub fn with_validator(mut self, validator: Arc<ConfigValidator>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+    
     /// Lock the repository for operations
-    fn lock_repo(&self) -> Result<std::sync::MutexGuard<'_, Repository>, GitStoreError> {
-        self.repo.lock().map_err(|e| GitStoreError::Lock(e.to_string()))
+    ///
+    /// A panic while the lock was held doesn't make the store unusable:
+    /// the lock is recovered and index changes that weren't written are
+    /// dropped, since every operation works from the on-disk index and refs.
+    fn lock_repo(&self) -> Result<MutexGuard<'_, Repository>, GitStoreError> {
+        let (repo, recovered) = lock_unpoisoned(&self.repo, "git repository");
+        if recovered {
+            repo.index()?.read(true)?;
+        }
+        Ok(repo)
     }
     
     /// Create the default directory structure
@@ -100,6 +195,8 @@ impl GitStore {
             }
         }
         
+        Self::ensure_gitignore(path)?;
+        
         // Create README
         let readme_content = r#"# Vectorize Configuration Repository
 
@@ -130,6 +227,39 @@ For remote sync, configure a git remote in Vectorize settings.
         Ok(())
     }
     
+    /// Write the default `.gitignore` if the repository has none
+    fn ensure_gitignore(path: &Path) -> Result<(), GitStoreError> {
+        let gitignore = path.join(".gitignore");
+        if !gitignore.exists() {
+            std::fs::write(&gitignore, DEFAULT_GITIGNORE)?;
+        }
+        Ok(())
+    }
+    
+    /// Add a pattern to `.gitignore` and commit it
+    ///
+    /// Files already tracked that match the pattern are dropped from the
+    /// repository by the same commit.
+    pub fn add_ignore_pattern(&self, pattern: &str) -> Result<String, GitStoreError> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() || pattern.contains('\n') {
+            return Err(GitStoreError::InvalidPath(format!("Invalid ignore pattern: {:?}", pattern)));
+        }
+        
+        let gitignore = self.path.join(".gitignore");
+        let mut content = std::fs::read_to_string(&gitignore).unwrap_or_default();
+        if !content.lines().any(|line| line.trim() == pattern) {
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(pattern);
+            content.push('\n');
+            std::fs::write(&gitignore, content)?;
+        }
+        
+        self.commit(&format!("Ignore {}", pattern))
+    }
+    
     /// Create the initial commit
     fn create_initial_commit(repo: &Repository) -> Result<(), GitStoreError> {
         let mut index = repo.index()?;
@@ -187,14 +317,7 @@ For remote sync, configure a git remote in Vectorize settings.
         let group_path = self.group_path(group_name);
         std::fs::create_dir_all(&group_path)?;
         
-        // Create default group.yaml
-        let group_yaml = format!(
-            "# Group: {}\nname: {}\ncreated_at: {}\n",
-            group_name,
-            group_name,
-            chrono::Utc::now().to_rfc3339()
-        );
-        std::fs::write(group_path.join("group.yaml"), group_yaml)?;
+        self.write_group_metadata(group_name)?;
         
         // Create empty config
         let config = format!(
@@ -209,8 +332,125 @@ For remote sync, configure a git remote in Vectorize settings.
         Ok(group_path)
     }
     
+    /// Write a default `group.yaml` for a group
+    fn write_group_metadata(&self, group_name: &str) -> Result<(), GitStoreError> {
+        let group_yaml = format!(
+            "# Group: {}\nname: {}\ncreated_at: {}\n",
+            group_name,
+            group_name,
+            chrono::Utc::now().to_rfc3339()
+        );
+        std::fs::write(self.group_path(group_name).join("group.yaml"), group_yaml)?;
+        Ok(())
+    }
+    
     /// Write config for a group
+    ///
+    /// With a validator configured, invalid config is refused before anything
+    /// is written.
     pub fn write_config(&self, group_name: &str, config: &str) -> Result<String, GitStoreError> {
+        self.validate(config)?;
+        self.write_config_force(group_name, config)
+    }
+    
+    /// Write config for a group without validating it
+    pub fn write_config_force(&self, group_name: &str, config: &str) -> Result<String, GitStoreError> {
+        let _guard = self.lock_config_write()?;
+        self.write_config_unchecked(group_name, config, &format!("Update config for group: {}", group_name))
+    }
+    
+    /// Run the configured validator, if any
+    fn validate(&self, config: &str) -> Result<(), GitStoreError> {
+        let Some(validator) = &self.validator else {
+            return Ok(());
+        };
+        let result = validator.validate(config);
+        if result.valid {
+            return Ok(());
+        }
+        let mut errors = result.errors;
+        if errors.is_empty() {
+            errors.push(ValidationError {
+                code: "INVALID_CONFIG".to_string(),
+                message: result.message,
+                line: None,
+                column: None,
+                component: None,
+                details: None,
+            });
+        }
+        Err(GitStoreError::Invalid(errors))
+    }
+    
+    /// Write config for a group only if it hasn't changed since `expected_head`
+    ///
+    /// `expected_head` is the commit the editor loaded the config from. If the
+    /// group's config at HEAD differs from the config at that commit, nothing
+    /// is written and `StaleConfig` names the commit that last changed it.
+    /// Commits that only touch other groups don't count as changes.
+    pub fn write_config_if(&self, group_name: &str, config: &str, expected_head: &str) -> Result<String, GitStoreError> {
+        self.validate(config)?;
+        let _guard = self.lock_config_write()?;
+        
+        {
+            let repo = self.lock_repo()?;
+            let path = Self::config_repo_path(group_name);
+            let expected = git2::Oid::from_str(expected_head)
+                .ok()
+                .and_then(|oid| repo.find_commit(oid).ok())
+                .ok_or_else(|| GitStoreError::NotFound(format!("Version {} not found", expected_head)))?;
+            let head = repo.head()?.peel_to_commit()?;
+            
+            if Self::blob_id_at(&expected, &path) != Self::blob_id_at(&head, &path) {
+                let current = Self::last_change(&repo, &path)?
+                    .unwrap_or_else(|| head.id().to_string());
+                return Err(GitStoreError::StaleConfig {
+                    group: group_name.to_string(),
+                    expected: expected_head.to_string(),
+                    current,
+                });
+            }
+        }
+        
+        self.write_config_unchecked(group_name, config, &format!("Update config for group: {}", group_name))
+    }
+    
+    fn lock_config_write(&self) -> Result<MutexGuard<'_, ()>, GitStoreError> {
+        Ok(lock_unpoisoned(&self.config_write, "config write").0)
+    }
+    
+    /// Repository-relative path of a group's config file
+    pub fn config_repo_path(group_name: &str) -> String {
+        format!("groups/{}/config.toml", group_name)
+    }
+    
+    /// Blob ID of `path` in `commit`, `None` if the file doesn't exist there
+    fn blob_id_at(commit: &git2::Commit<'_>, path: &str) -> Option<git2::Oid> {
+        commit.tree().ok()?.get_path(Path::new(path)).ok().map(|entry| entry.id())
+    }
+    
+    /// Most recent commit that changed `path`
+    fn last_change(repo: &Repository, path: &str) -> Result<Option<String>, GitStoreError> {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+        
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            let current = Self::blob_id_at(&commit, path);
+            let changed = match commit.parent(0) {
+                Ok(parent) => Self::blob_id_at(&parent, path) != current,
+                Err(_) => current.is_some(),
+            };
+            if changed {
+                return Ok(Some(commit.id().to_string()));
+            }
+        }
+        
+        Ok(None)
+    }
+    
+    fn write_config_unchecked(&self, group_name: &str, config: &str, message: &str) -> Result<String, GitStoreError> {
         let config_path = self.group_config_path(group_name);
         
         // Ensure group directory exists
@@ -218,10 +458,16 @@ For remote sync, configure a git remote in Vectorize settings.
             std::fs::create_dir_all(parent)?;
         }
         
+        // A group whose directory was never created (e.g. an imported one)
+        // gets its metadata in the same commit, so both stores agree
+        if !self.group_path(group_name).join("group.yaml").exists() {
+            self.write_group_metadata(group_name)?;
+        }
+        
         std::fs::write(&config_path, config)?;
         
         // Commit the change
-        let commit_hash = self.commit(&format!("Update config for group: {}", group_name))?;
+        let commit_hash = self.commit(message)?;
         
         Ok(commit_hash)
     }
@@ -261,6 +507,16 @@ For remote sync, configure a git remote in Vectorize settings.
         // Also handle deletions by updating the index
         index.update_all(["*"].iter(), None)?;
         
+        // add_all skips ignored files, but files tracked before they were
+        // ignored stay in the index; stop tracking them
+        let ignored: Vec<PathBuf> = index.iter()
+            .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned()))
+            .filter(|path| repo.is_path_ignored(path).unwrap_or(false))
+            .collect();
+        for path in &ignored {
+            index.remove_path(path)?;
+        }
+        
         index.write()?;
         
         let tree_id = index.write_tree()?;
@@ -279,14 +535,7 @@ For remote sync, configure a git remote in Vectorize settings.
         
         let parents: Vec<&git2::Commit> = parent.iter().collect();
         
-        let oid = repo.commit(
-            Some("HEAD"),
-            &sig,
-            &sig,
-            message,
-            &tree,
-            &parents,
-        )?;
+        let oid = self.write_commit(&repo, &sig, message, &tree, &parents)?;
         
         let commit_hash = oid.to_string();
         info!("Committed: {} ({})", message, &commit_hash[..8]);
@@ -294,15 +543,111 @@ For remote sync, configure a git remote in Vectorize settings.
         Ok(commit_hash)
     }
     
+    /// Create a commit on HEAD, signed when a signing key is configured
+    fn write_commit(
+        &self,
+        repo: &Repository,
+        sig: &Signature<'_>,
+        message: &str,
+        tree: &git2::Tree<'_>,
+        parents: &[&git2::Commit<'_>],
+    ) -> Result<git2::Oid, GitStoreError> {
+        let signing_key = self.signing_key.read()
+            .map_err(|e| GitStoreError::Lock(e.to_string()))?
+            .clone();
+        
+        if signing_key.is_none() {
+            return Ok(repo.commit(Some("HEAD"), sig, sig, message, tree, parents)?);
+        }
+        
+        let oid = self.create_commit(repo, sig, message, tree, parents)?;
+        
+        // commit_signed doesn't move any refs; advance the branch HEAD points to
+        let head = repo.find_reference("HEAD")?;
+        match head.symbolic_target() {
+            Some(branch) => {
+                repo.reference(branch, oid, true, message)?;
+            }
+            None => repo.set_head_detached(oid)?,
+        }
+        
+        Ok(oid)
+    }
+    
+    /// Create a commit without moving any refs, signed when a signing key
+    /// is configured
+    fn create_commit(
+        &self,
+        repo: &Repository,
+        sig: &Signature<'_>,
+        message: &str,
+        tree: &git2::Tree<'_>,
+        parents: &[&git2::Commit<'_>],
+    ) -> Result<git2::Oid, GitStoreError> {
+        let signing_key = self.signing_key.read()
+            .map_err(|e| GitStoreError::Lock(e.to_string()))?
+            .clone();
+        
+        let Some(key) = signing_key else {
+            return Ok(repo.commit(None, sig, sig, message, tree, parents)?);
+        };
+        
+        let buffer = repo.commit_create_buffer(sig, sig, message, tree, parents)?;
+        let content = buffer.as_str()
+            .ok_or_else(|| GitStoreError::Signing("Commit buffer is not UTF-8".to_string()))?;
+        let signature = signing::sign(&key, content)?;
+        Ok(repo.commit_signed(content, &signature, None)?)
+    }
+    
+    /// Sign future commits with `key`, or stop signing with `None`
+    pub fn set_signing_key(&self, key: Option<SigningKey>) -> Result<(), GitStoreError> {
+        let mut signing_key = self.signing_key.write()
+            .map_err(|e| GitStoreError::Lock(e.to_string()))?;
+        match &key {
+            Some(key) => info!("Signing commits with {:?} key {}", key.format, key.key),
+            None => info!("Commit signing disabled"),
+        }
+        *signing_key = key;
+        Ok(())
+    }
+    
+    /// Check a commit's signature
+    ///
+    /// SSH signatures are verified against the configured signing key when
+    /// it is an SSH key; GPG signatures against the local keyring.
+    pub fn verify_commit(&self, commit_hash: &str) -> Result<CommitSignature, GitStoreError> {
+        let (signature, signed_data) = {
+            let repo = self.lock_repo()?;
+            let oid = git2::Oid::from_str(commit_hash)?;
+            repo.find_commit(oid)?;
+            match repo.extract_signature(&oid, None) {
+                Ok((signature, signed_data)) => (signature.to_vec(), signed_data.to_vec()),
+                Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(CommitSignature::unsigned()),
+                Err(e) => return Err(e.into()),
+            }
+        };
+        
+        let signature = String::from_utf8_lossy(&signature).into_owned();
+        let key = self.signing_key.read()
+            .map_err(|e| GitStoreError::Lock(e.to_string()))?
+            .clone();
+        
+        Ok(CommitSignature {
+            signed: true,
+            format: signing::detect_format(&signature),
+            valid: signing::verify(&signature, &signed_data, key.as_ref())?,
+        })
+    }
+    
     /// Get version history for a group
     pub fn get_history(&self, group_name: Option<&str>, limit: usize) -> Result<Vec<CommitInfo>, GitStoreError> {
         let repo = self.lock_repo()?;
         
         let mut revwalk = repo.revwalk()?;
         revwalk.push_head()?;
-        revwalk.set_sorting(git2::Sort::TIME)?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
         
-        let path_filter = group_name.map(|name| format!("groups/{}/", name));
+        let config_path = group_name.map(Self::config_repo_path);
         
         let mut commits = Vec::new();
         
@@ -314,31 +659,190 @@ For remote sync, configure a git remote in Vectorize settings.
             let oid = oid_result?;
             let commit = repo.find_commit(oid)?;
             
-            // If filtering by group, check if commit affects that path
-            if let Some(ref _path) = path_filter {
-                // For simplicity, we include all commits for now
-                // A more sophisticated implementation would check the diff
-                let message = commit.message().unwrap_or("");
-                if !message.contains(group_name.unwrap_or("")) && !commits.is_empty() {
-                    // Skip commits not related to this group (except first few)
+            // If filtering by group, only keep commits that changed its config
+            if let Some(ref path) = config_path {
+                let current = Self::blob_id_at(&commit, path);
+                let changed = match commit.parent(0) {
+                    Ok(parent) => Self::blob_id_at(&parent, path) != current,
+                    Err(_) => current.is_some(),
+                };
+                if !changed {
                     continue;
                 }
             }
             
-            commits.push(CommitInfo {
-                hash: oid.to_string(),
-                short_hash: oid.to_string()[..8].to_string(),
-                message: commit.message().unwrap_or("").to_string(),
-                author: commit.author().name().unwrap_or("Unknown").to_string(),
-                timestamp: chrono::DateTime::from_timestamp(commit.time().seconds(), 0)
-                    .map(|dt| dt.to_rfc3339())
-                    .unwrap_or_default(),
-            });
+            commits.push(CommitInfo::from_commit(&commit));
         }
         
         Ok(commits)
     }
     
+    /// Search commit messages and config diffs for `query` (case-insensitive)
+    ///
+    /// Returns up to `limit` matches, newest first. With a group, only commits
+    /// that changed that group's config are considered and diffs are limited
+    /// to its config file. Diffs are only scanned for the newest
+    /// `MAX_DIFF_SEARCH_COMMITS` commits; older ones (and all commits with
+    /// `message_only`) are matched on their message alone.
+    pub fn search_history(
+        &self,
+        group_name: Option<&str>,
+        query: &str,
+        limit: usize,
+        message_only: bool,
+    ) -> Result<Vec<HistoryMatch>, GitStoreError> {
+        let needle = query.trim().to_lowercase();
+        if needle.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let repo = self.lock_repo()?;
+        
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+        
+        let config_path = group_name.map(Self::config_repo_path);
+        
+        let mut matches = Vec::new();
+        for (scanned, oid) in revwalk.enumerate() {
+            if matches.len() >= limit {
+                break;
+            }
+            
+            let commit = repo.find_commit(oid?)?;
+            
+            if let Some(ref path) = config_path {
+                let current = Self::blob_id_at(&commit, path);
+                let changed = match commit.parent(0) {
+                    Ok(parent) => Self::blob_id_at(&parent, path) != current,
+                    Err(_) => current.is_some(),
+                };
+                if !changed {
+                    continue;
+                }
+            }
+            
+            let message = commit.message().unwrap_or("");
+            let found = match message.lines().find(|line| line.to_lowercase().contains(&needle)) {
+                Some(line) => Some(("message", line.trim().to_string())),
+                None if !message_only && scanned < MAX_DIFF_SEARCH_COMMITS => {
+                    Self::diff_snippet(&repo, &commit, config_path.as_deref(), &needle)?
+                        .map(|line| ("diff", line))
+                }
+                None => None,
+            };
+            
+            if let Some((matched_in, snippet)) = found {
+                matches.push(HistoryMatch {
+                    commit: CommitInfo::from_commit(&commit),
+                    matched_in: matched_in.to_string(),
+                    snippet: snippet.chars().take(MAX_SNIPPET_CHARS).collect(),
+                });
+            }
+        }
+        
+        Ok(matches)
+    }
+    
+    /// First added or removed line in `commit`'s diff containing `needle`
+    ///
+    /// `needle` must already be lowercase.
+    fn diff_snippet(
+        repo: &Repository,
+        commit: &git2::Commit<'_>,
+        path: Option<&str>,
+        needle: &str,
+    ) -> Result<Option<String>, GitStoreError> {
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let mut options = git2::DiffOptions::new();
+        if let Some(path) = path {
+            options.pathspec(path);
+        }
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut options))?;
+        
+        let mut snippet = None;
+        let printed = diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+            if !matches!(line.origin(), '+' | '-') {
+                return true;
+            }
+            let content = String::from_utf8_lossy(line.content());
+            if content.to_lowercase().contains(needle) {
+                snippet = Some(format!("{}{}", line.origin(), content.trim_end()));
+                // Stop at the first match
+                return false;
+            }
+            true
+        });
+        
+        match (snippet, printed) {
+            (Some(snippet), _) => Ok(Some(snippet)),
+            (None, Ok(())) => Ok(None),
+            (None, Err(e)) => Err(e.into()),
+        }
+    }
+    
+    /// Config changes across all groups committed in `[since, until)`
+    ///
+    /// One entry per group whose config a commit changed, oldest first, so a
+    /// commit touching two groups is listed twice. Commits that only touch
+    /// other files (templates, `.gitignore`) are left out.
+    pub fn config_changes(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        until: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<ConfigChange>, GitStoreError> {
+        let repo = self.lock_repo()?;
+        
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+        
+        let mut changes = Vec::new();
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            let time = commit.time().seconds();
+            if since.is_some_and(|since| time < since.timestamp()) {
+                continue;
+            }
+            if until.is_some_and(|until| time >= until.timestamp()) {
+                continue;
+            }
+            
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            
+            let mut groups: Vec<String> = diff.deltas()
+                .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+                .flatten()
+                .filter_map(|path| Self::config_path_group(path.to_str()?))
+                .collect();
+            groups.sort();
+            groups.dedup();
+            
+            let info = CommitInfo::from_commit(&commit);
+            changes.extend(groups.into_iter().map(|group| (time, ConfigChange { group, commit: info.clone() })));
+        }
+        
+        // Reversing the newest-first walk keeps a parent ahead of a child
+        // committed within the same second
+        changes.reverse();
+        changes.sort_by_key(|(time, _)| *time);
+        Ok(changes.into_iter().map(|(_, change)| change).collect())
+    }
+    
+    /// Group whose config lives at the repository-relative `path`
+    fn config_path_group(path: &str) -> Option<String> {
+        let group = path.strip_prefix("groups/")?.strip_suffix("/config.toml")?;
+        (!group.is_empty() && !group.contains('/')).then(|| group.to_string())
+    }
+    
     /// Get config at a specific version
     pub fn get_config_at_version(&self, group_name: &str, commit_hash: &str) -> Result<Option<String>, GitStoreError> {
         let repo = self.lock_repo()?;
@@ -347,7 +851,7 @@ For remote sync, configure a git remote in Vectorize settings.
         let commit = repo.find_commit(oid)?;
         let tree = commit.tree()?;
         
-        let path = format!("groups/{}/config.toml", group_name);
+        let path = Self::config_repo_path(group_name);
         
         match tree.get_path(Path::new(&path)) {
             Ok(entry) => {
@@ -360,20 +864,70 @@ For remote sync, configure a git remote in Vectorize settings.
         }
     }
     
+    /// Preview what `rollback` would change, without writing anything
+    ///
+    /// Returns the diff of the group's config from HEAD to `commit_hash`;
+    /// empty when the config is already at that version.
+    pub fn preview_rollback(&self, group_name: &str, commit_hash: &str) -> Result<String, GitStoreError> {
+        match self.get_config_at_version(group_name, commit_hash) {
+            Ok(Some(_)) => {}
+            Ok(None) | Err(GitStoreError::Git(_)) => {
+                return Err(GitStoreError::NotFound(format!("Config not found at version {}", commit_hash)));
+            }
+            Err(e) => return Err(e),
+        }
+        
+        let head = self.head_hash()?;
+        self.diff_config(group_name, &head, commit_hash)
+    }
+    
     /// Rollback to a specific version
     pub fn rollback(&self, group_name: &str, commit_hash: &str) -> Result<String, GitStoreError> {
         // Get the config at the specified version
         let old_config = self.get_config_at_version(group_name, commit_hash)?
             .ok_or_else(|| GitStoreError::InvalidPath(format!("Config not found at version {}", commit_hash)))?;
         
-        // Write it as the current config
-        let new_hash = self.write_config(group_name, &old_config)?;
+        // Write it as the current config; it was accepted when first
+        // committed, and a rollback must work even if validation has changed
+        let new_hash = self.write_config_force(group_name, &old_config)?;
         
         info!("Rolled back {} to version {}", group_name, &commit_hash[..8]);
         
         Ok(new_hash)
     }
     
+    /// Copy a group's config to another group
+    ///
+    /// Reads `from_group`'s config at `commit_hash` (HEAD if `None`) and
+    /// commits it as `to_group`'s config, creating the target directory if
+    /// needed. Returns the new commit hash.
+    pub fn copy_config(&self, from_group: &str, to_group: &str, commit_hash: Option<&str>) -> Result<String, GitStoreError> {
+        let source_hash = match commit_hash {
+            Some(hash) => hash.to_string(),
+            None => self.head_hash()?,
+        };
+        
+        let config = self.get_config_at_version(from_group, &source_hash)?
+            .ok_or_else(|| GitStoreError::NotFound(format!(
+                "No config for group {} at version {}", from_group, &source_hash[..8.min(source_hash.len())]
+            )))?;
+        
+        let _guard = self.lock_config_write()?;
+        let config_path = self.group_config_path(to_group);
+        if let Some(parent) = config_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&config_path, &config)?;
+        
+        let new_hash = self.commit(&format!(
+            "Copy config from {}@{} to {}", from_group, &source_hash[..8.min(source_hash.len())], to_group
+        ))?;
+        
+        info!("Copied config from {}@{} to {}", from_group, &source_hash[..8.min(source_hash.len())], to_group);
+        
+        Ok(new_hash)
+    }
+    
     /// Get diff between two versions
     pub fn diff(&self, from_hash: &str, to_hash: &str) -> Result<String, GitStoreError> {
         let repo = self.lock_repo()?;
@@ -389,6 +943,38 @@ For remote sync, configure a git remote in Vectorize settings.
         
         let diff = repo.diff_tree_to_tree(Some(&from_tree), Some(&to_tree), None)?;
         
+        Self::patch_text(&diff)
+    }
+    
+    /// Diff of a group's config file between two versions
+    pub fn diff_config(&self, group_name: &str, from_hash: &str, to_hash: &str) -> Result<String, GitStoreError> {
+        let repo = self.lock_repo()?;
+        
+        let from_tree = repo.find_commit(git2::Oid::from_str(from_hash)?)?.tree()?;
+        let to_tree = repo.find_commit(git2::Oid::from_str(to_hash)?)?.tree()?;
+        
+        let mut options = git2::DiffOptions::new();
+        options.pathspec(Self::config_repo_path(group_name));
+        let diff = repo.diff_tree_to_tree(Some(&from_tree), Some(&to_tree), Some(&mut options))?;
+        
+        Self::patch_text(&diff)
+    }
+    
+    /// Unified diff between two texts that aren't in the repository
+    pub fn diff_text(old_path: &str, old: &str, new_path: &str, new: &str) -> Result<String, GitStoreError> {
+        let mut patch = git2::Patch::from_buffers(
+            old.as_bytes(),
+            Some(Path::new(old_path)),
+            new.as_bytes(),
+            Some(Path::new(new_path)),
+            None,
+        )?;
+        let buf = patch.to_buf()?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+    
+    /// Render a diff as unified patch text
+    fn patch_text(diff: &git2::Diff<'_>) -> Result<String, GitStoreError> {
         let mut diff_text = String::new();
         diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
             let prefix = match line.origin() {
@@ -497,6 +1083,130 @@ For remote sync, configure a git remote in Vectorize settings.
         }
     }
     
+    /// Push only the changes under `paths` to a remote branch
+    ///
+    /// `paths` are relative to the repository root, e.g. `groups/edge`.
+    /// Pushes a new commit on top of the remote tip whose tree is the
+    /// remote's with those paths replaced by the local branch's content, so
+    /// changes anywhere else stay local. The local branch doesn't move; the
+    /// next pull merges the published commit back in.
+    pub fn push_paths(&self, remote: &str, branch: Option<&str>, paths: &[&str]) -> Result<PushResult, GitStoreError> {
+        let branch = branch.unwrap_or("main");
+        
+        if paths.is_empty() {
+            return Err(GitStoreError::InvalidPath("No paths to push".to_string()));
+        }
+        for path in paths {
+            let relative = Path::new(path);
+            let valid = !path.is_empty() && relative.components().all(|c| matches!(c, std::path::Component::Normal(_)));
+            if !valid {
+                return Err(GitStoreError::InvalidPath(format!("Invalid path to push: {:?}", path)));
+            }
+        }
+        
+        self.fetch(remote)?;
+        
+        let commit = {
+            let repo = self.lock_repo()?;
+            let local_tree = Self::branch_tree(&repo, branch)?;
+            let remote_tip = repo.find_reference(&format!("refs/remotes/{}/{}", remote, branch))
+                .ok()
+                .map(|r| r.peel_to_commit())
+                .transpose()?;
+            
+            // Start from the remote's tree and swap in the local content
+            let mut index = git2::Index::new()?;
+            if let Some(tip) = &remote_tip {
+                index.read_tree(&tip.tree()?)?;
+            }
+            for path in paths {
+                index.remove_dir(Path::new(path), 0)?;
+                index.remove_path(Path::new(path))?;
+                for (file, id, mode) in Self::tree_files(&repo, &local_tree, path)? {
+                    index.add(&git2::IndexEntry {
+                        ctime: git2::IndexTime::new(0, 0),
+                        mtime: git2::IndexTime::new(0, 0),
+                        dev: 0,
+                        ino: 0,
+                        mode,
+                        uid: 0,
+                        gid: 0,
+                        file_size: 0,
+                        id,
+                        flags: 0,
+                        flags_extended: 0,
+                        path: file.into_bytes(),
+                    })?;
+                }
+            }
+            let tree_id = index.write_tree_to(&repo)?;
+            
+            if remote_tip.as_ref().is_some_and(|tip| tip.tree_id() == tree_id) {
+                None
+            } else {
+                let tree = repo.find_tree(tree_id)?;
+                let sig = Self::default_signature(&repo)?;
+                let message = format!("Publish {}", paths.join(", "));
+                let parents: Vec<&git2::Commit> = remote_tip.iter().collect();
+                Some(self.create_commit(&repo, &sig, &message, &tree, &parents)?)
+            }
+        };
+        
+        let Some(commit) = commit else {
+            return Ok(PushResult {
+                success: true,
+                remote: remote.to_string(),
+                branch: branch.to_string(),
+                message: "Nothing to push under the given paths".to_string(),
+            });
+        };
+        
+        let output = std::process::Command::new("git")
+            .current_dir(&self.path)
+            .args(["push", remote, &format!("{}:refs/heads/{}", commit, branch)])
+            .output()
+            .map_err(|e| GitStoreError::Git(git2::Error::from_str(&e.to_string())))?;
+        
+        if output.status.success() {
+            info!("Pushed {} to {} ({})", paths.join(", "), remote, branch);
+            Ok(PushResult {
+                success: true,
+                remote: remote.to_string(),
+                branch: branch.to_string(),
+                message: String::from_utf8_lossy(&output.stderr).to_string(),
+            })
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr).to_string();
+            Err(GitStoreError::Git(git2::Error::from_str(&error)))
+        }
+    }
+    
+    /// Files at or under `path` in `tree`, as (path, blob id, mode)
+    ///
+    /// Empty when nothing exists at `path`.
+    fn tree_files(repo: &Repository, tree: &git2::Tree<'_>, path: &str) -> Result<Vec<(String, git2::Oid, u32)>, GitStoreError> {
+        let entry = match tree.get_path(Path::new(path)) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        
+        if entry.kind() != Some(git2::ObjectType::Tree) {
+            return Ok(vec![(path.to_string(), entry.id(), entry.filemode() as u32)]);
+        }
+        
+        let mut files = Vec::new();
+        repo.find_tree(entry.id())?.walk(git2::TreeWalkMode::PreOrder, |root, item| {
+            if item.kind() == Some(git2::ObjectType::Blob) {
+                if let Some(name) = item.name() {
+                    files.push((format!("{}/{}{}", path.trim_end_matches('/'), root, name), item.id(), item.filemode() as u32));
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+        Ok(files)
+    }
+    
     /// Fetch from a remote repository
     pub fn fetch(&self, remote: &str) -> Result<(), GitStoreError> {
         let output = std::process::Command::new("git")
@@ -520,7 +1230,7 @@ For remote sync, configure a git remote in Vectorize settings.
         
         let output = std::process::Command::new("git")
             .current_dir(&self.path)
-            .args(["pull", remote, branch])
+            .args(["pull", "--no-rebase", remote, branch])
             .output()
             .map_err(|e| GitStoreError::Git(git2::Error::from_str(&e.to_string())))?;
         
@@ -539,7 +1249,11 @@ For remote sync, configure a git remote in Vectorize settings.
         } else {
             let error = String::from_utf8_lossy(&output.stderr).to_string();
             
-            // Check for merge conflicts
+            // Check for merge conflicts (git reports these on stdout)
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            if stdout.contains("CONFLICT") || stdout.contains("Automatic merge failed") {
+                return Err(GitStoreError::Conflict(format!("{}{}", stdout, error)));
+            }
             if error.contains("CONFLICT") || error.contains("Automatic merge failed") {
                 return Err(GitStoreError::Conflict(error));
             }
@@ -588,22 +1302,66 @@ For remote sync, configure a git remote in Vectorize settings.
         }
     }
     
-    /// Sync bidirectionally with remote (pull then push)
-    pub fn sync(&self, remote: &str, branch: Option<&str>) -> Result<SyncResult, GitStoreError> {
+    /// Fetch and preview what pulling `branch` from `remote` would bring in
+    ///
+    /// Nothing is merged. The diff is from local HEAD to the fetched remote
+    /// tip, so local commits not on the remote show up as removals.
+    pub fn preview_incoming(&self, remote: &str, branch: Option<&str>) -> Result<IncomingChanges, GitStoreError> {
         let branch = branch.unwrap_or("main");
         
-        // Pull first
-        let pull_result = self.pull(remote, Some(branch));
-        let pull_success = pull_result.is_ok();
-        let pull_error = pull_result.err().map(|e| e.to_string());
+        self.fetch(remote)?;
         
-        if !pull_success {
-            if let Some(ref err) = pull_error {
-                if err.contains("CONFLICT") {
-                    return Err(GitStoreError::Conflict(err.clone()));
-                }
-            }
-        }
+        let repo = self.lock_repo()?;
+        
+        let remote_ref = format!("refs/remotes/{}/{}", remote, branch);
+        let remote_tip = repo.find_reference(&remote_ref)
+            .and_then(|r| r.peel_to_commit())
+            .map_err(|_| GitStoreError::NotFound(format!("Remote branch {}/{}", remote, branch)))?;
+        let head = repo.head()?.peel_to_commit()?;
+        
+        let (ahead, behind) = repo.graph_ahead_behind(head.id(), remote_tip.id())?;
+        
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(remote_tip.id())?;
+        revwalk.hide(head.id())?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            commits.push(CommitInfo::from_commit(&repo.find_commit(oid?)?));
+        }
+        
+        let diff = repo.diff_tree_to_tree(Some(&head.tree()?), Some(&remote_tip.tree()?), None)?;
+        let diff = Self::patch_text(&diff)?;
+        
+        Ok(IncomingChanges {
+            remote: remote.to_string(),
+            branch: branch.to_string(),
+            ahead: ahead as u32,
+            behind: behind as u32,
+            local_head: head.id().to_string(),
+            remote_head: remote_tip.id().to_string(),
+            commits,
+            has_changes: !diff.trim().is_empty(),
+            diff,
+        })
+    }
+    
+    /// Sync bidirectionally with remote (pull then push)
+    pub fn sync(&self, remote: &str, branch: Option<&str>) -> Result<SyncResult, GitStoreError> {
+        let branch = branch.unwrap_or("main");
+        
+        // Pull first
+        let pull_result = self.pull(remote, Some(branch));
+        let pull_success = pull_result.is_ok();
+        let pull_error = pull_result.err().map(|e| e.to_string());
+        
+        if !pull_success {
+            if let Some(ref err) = pull_error {
+                if err.contains("CONFLICT") {
+                    return Err(GitStoreError::Conflict(err.clone()));
+                }
+            }
+        }
         
         // Push if pull succeeded
         let (push_success, push_error) = if pull_success {
@@ -625,6 +1383,247 @@ For remote sync, configure a git remote in Vectorize settings.
         })
     }
     
+    // =========================================================================
+    // Offline Transfer (git bundles)
+    // =========================================================================
+    
+    /// Write every ref and its full history to a git bundle at `dest`
+    ///
+    /// For air-gapped setups: the file can be carried to another control
+    /// plane and applied with [`import_bundle`](Self::import_bundle).
+    /// Uncommitted changes are not included.
+    pub fn export_bundle(&self, dest: &Path) -> Result<BundleExport, GitStoreError> {
+        let dest = Self::absolute(dest)?;
+        let repo = self.lock_repo()?;
+        
+        self.git_cli(&["bundle", "create", &dest.to_string_lossy(), "--all"])?;
+        
+        let size_bytes = std::fs::metadata(&dest)?.len();
+        let head = repo.head()?.peel_to_commit()?.id().to_string();
+        info!("Exported config bundle at {} to {}", &head[..8], dest.display());
+        Ok(BundleExport {
+            path: dest.display().to_string(),
+            head,
+            size_bytes,
+        })
+    }
+    
+    /// Apply a bundle made by [`export_bundle`](Self::export_bundle)
+    ///
+    /// The bundle's HEAD fast-forwards the current branch. A store that only
+    /// has its initial commit takes the bundle's history as-is. Histories
+    /// that have diverged are rejected with [`GitStoreError::Conflict`]
+    /// rather than merged, so nothing changes until one side is reconciled.
+    pub fn import_bundle(&self, src: &Path) -> Result<BundleImport, GitStoreError> {
+        let src = Self::absolute(src)?;
+        let src = src.to_string_lossy();
+        let _guard = self.lock_config_write()?;
+        let repo = self.lock_repo()?;
+        
+        self.git_cli(&["bundle", "verify", "--quiet", &src])?;
+        self.git_cli(&["fetch", "--no-tags", &src, &format!("+HEAD:{}", BUNDLE_IMPORT_REF)])?;
+        
+        let result = (|| -> Result<BundleImport, GitStoreError> {
+            let incoming = repo.find_reference(BUNDLE_IMPORT_REF)?.peel_to_commit()?;
+            let head = repo.head()?.peel_to_commit()?;
+            let previous_head = head.id().to_string();
+            
+            let up_to_date = head.id() == incoming.id() || repo.graph_descendant_of(head.id(), incoming.id())?;
+            if up_to_date {
+                return Ok(BundleImport {
+                    previous_head: previous_head.clone(),
+                    head: previous_head,
+                    commits_imported: 0,
+                    updated: false,
+                });
+            }
+            
+            let fast_forward = repo.graph_descendant_of(incoming.id(), head.id())?;
+            let pristine = head.parent_count() == 0;
+            if !fast_forward && !pristine {
+                return Err(GitStoreError::Conflict(format!(
+                    "Bundle history has diverged from the local history (local {}, bundle {})",
+                    &previous_head[..8],
+                    &incoming.id().to_string()[..8],
+                )));
+            }
+            
+            let (commits_imported, _) = repo.graph_ahead_behind(incoming.id(), head.id())?;
+            self.git_cli(&["reset", "--hard", &incoming.id().to_string()])?;
+            // The reset ran through the git CLI, so reload the index from disk
+            repo.index()?.read(true)?;
+            
+            Ok(BundleImport {
+                previous_head,
+                head: incoming.id().to_string(),
+                commits_imported,
+                updated: true,
+            })
+        })();
+        
+        if let Ok(mut reference) = repo.find_reference(BUNDLE_IMPORT_REF) {
+            let _ = reference.delete();
+        }
+        
+        let result = result?;
+        if result.updated {
+            info!(
+                "Imported {} commit(s) from bundle {}, now at {}",
+                result.commits_imported, src, &result.head[..8]
+            );
+        }
+        Ok(result)
+    }
+    
+    /// Run a git CLI command in the repository, returning stdout
+    fn git_cli(&self, args: &[&str]) -> Result<String, GitStoreError> {
+        let output = std::process::Command::new("git")
+            .current_dir(&self.path)
+            .args(args)
+            .output()
+            .map_err(|e| GitStoreError::Git(git2::Error::from_str(&e.to_string())))?;
+        
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr).to_string();
+            Err(GitStoreError::Git(git2::Error::from_str(&error)))
+        }
+    }
+    
+    /// Resolve `path` against the process's working directory, since git
+    /// runs inside the repository
+    fn absolute(path: &Path) -> Result<PathBuf, GitStoreError> {
+        if path.is_absolute() {
+            Ok(path.to_path_buf())
+        } else {
+            Ok(std::env::current_dir()?.join(path))
+        }
+    }
+    
+    // =========================================================================
+    // Conflict Resolution
+    // =========================================================================
+    
+    /// List files left conflicted by a failed pull
+    pub fn list_conflicts(&self) -> Result<Vec<String>, GitStoreError> {
+        let repo = self.lock_repo()?;
+        let mut index = repo.index()?;
+        // The merge ran through the git CLI, so reload the index from disk
+        index.read(true)?;
+        
+        let mut paths = Vec::new();
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        
+        Ok(paths)
+    }
+    
+    /// Resolve one conflicted file
+    ///
+    /// Once the last conflict is resolved the merge is committed and its
+    /// hash returned; until then returns `None`.
+    pub fn resolve_conflict(&self, path: &str, resolution: ConflictResolution) -> Result<Option<String>, GitStoreError> {
+        let repo = self.lock_repo()?;
+        let mut index = repo.index()?;
+        index.read(true)?;
+        
+        let mut conflict = None;
+        for entry in index.conflicts()? {
+            let entry = entry?;
+            let entry_path = entry.our.as_ref().or(entry.their.as_ref()).or(entry.ancestor.as_ref())
+                .map(|e| e.path.clone());
+            if entry_path.as_deref() == Some(path.as_bytes()) {
+                conflict = Some(entry);
+                break;
+            }
+        }
+        let conflict = conflict
+            .ok_or_else(|| GitStoreError::NotFound(format!("No conflict for {}", path)))?;
+        
+        // None means the chosen side deleted the file
+        let content = match resolution {
+            ConflictResolution::Ours => match conflict.our {
+                Some(entry) => Some(repo.find_blob(entry.id)?.content().to_vec()),
+                None => None,
+            },
+            ConflictResolution::Theirs => match conflict.their {
+                Some(entry) => Some(repo.find_blob(entry.id)?.content().to_vec()),
+                None => None,
+            },
+            ConflictResolution::Content(content) => Some(content.into_bytes()),
+        };
+        
+        let relative = Path::new(path);
+        let full_path = self.path.join(relative);
+        index.conflict_remove(relative)?;
+        match content {
+            Some(content) => {
+                if let Some(parent) = full_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&full_path, content)?;
+                index.add_path(relative)?;
+            }
+            None => {
+                if full_path.exists() {
+                    std::fs::remove_file(&full_path)?;
+                }
+                index.remove_path(relative)?;
+            }
+        }
+        index.write()?;
+        info!("Resolved conflict in {}", path);
+        
+        if index.has_conflicts() {
+            return Ok(None);
+        }
+        
+        self.commit_merge(&repo, &mut index).map(Some)
+    }
+    
+    /// Commit a fully resolved merge with HEAD and MERGE_HEAD as parents
+    fn commit_merge(&self, repo: &Repository, index: &mut git2::Index) -> Result<String, GitStoreError> {
+        let head = repo.head()?.peel_to_commit()?;
+        let merge_head = repo.find_reference("MERGE_HEAD")
+            .and_then(|r| r.peel_to_commit())
+            .map_err(|_| GitStoreError::NotFound("No merge in progress".to_string()))?;
+        
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let sig = Self::default_signature(repo)?;
+        let message = format!("Merge {} (conflicts resolved)", &merge_head.id().to_string()[..8]);
+        
+        let oid = self.write_commit(repo, &sig, &message, &tree, &[&head, &merge_head])?;
+        repo.cleanup_state()?;
+        
+        let commit_hash = oid.to_string();
+        info!("Committed: {} ({})", message, &commit_hash[..8]);
+        
+        Ok(commit_hash)
+    }
+    
+    /// Abort an in-progress merge and restore the pre-pull state
+    pub fn abort_merge(&self) -> Result<(), GitStoreError> {
+        let repo = self.lock_repo()?;
+        
+        if repo.state() == git2::RepositoryState::Clean {
+            return Err(GitStoreError::NotFound("No merge in progress".to_string()));
+        }
+        
+        let head = repo.head()?.peel_to_commit()?;
+        repo.reset(head.as_object(), git2::ResetType::Hard, None)?;
+        repo.cleanup_state()?;
+        
+        info!("Aborted merge, reset to {}", &head.id().to_string()[..8]);
+        Ok(())
+    }
+    
     /// Create a new branch
     pub fn create_branch(&self, name: &str) -> Result<(), GitStoreError> {
         let repo = self.lock_repo()?;
@@ -675,6 +1674,60 @@ For remote sync, configure a git remote in Vectorize settings.
         Ok(result)
     }
     
+    /// Get the tree at a branch tip (local, or remote such as `origin/main`)
+    fn branch_tree<'r>(repo: &'r Repository, name: &str) -> Result<git2::Tree<'r>, GitStoreError> {
+        let branch = repo.find_branch(name, git2::BranchType::Local)
+            .or_else(|_| repo.find_branch(name, git2::BranchType::Remote))
+            .map_err(|_| GitStoreError::NotFound(format!("Branch '{}'", name)))?;
+        Ok(branch.get().peel_to_tree()?)
+    }
+    
+    /// Get the unified diff from `base`'s tip to `compare`'s tip
+    ///
+    /// For promotion workflows this is what merging `compare` into `base`
+    /// would change, e.g. `diff_branches("main", "staging")`.
+    pub fn diff_branches(&self, base: &str, compare: &str) -> Result<String, GitStoreError> {
+        let repo = self.lock_repo()?;
+        
+        let base_tree = Self::branch_tree(&repo, base)?;
+        let compare_tree = Self::branch_tree(&repo, compare)?;
+        
+        let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&compare_tree), None)?;
+        
+        Self::patch_text(&diff)
+    }
+    
+    /// Get the names of groups whose files differ between two branch tips
+    pub fn changed_groups(&self, base: &str, compare: &str) -> Result<Vec<String>, GitStoreError> {
+        let repo = self.lock_repo()?;
+        
+        let base_tree = Self::branch_tree(&repo, base)?;
+        let compare_tree = Self::branch_tree(&repo, compare)?;
+        
+        let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&compare_tree), None)?;
+        
+        let mut groups = std::collections::BTreeSet::new();
+        for delta in diff.deltas() {
+            for file in [delta.old_file(), delta.new_file()] {
+                let mut components = match file.path() {
+                    Some(path) => path.components(),
+                    None => continue,
+                };
+                if components.next().and_then(|c| c.as_os_str().to_str()) != Some("groups") {
+                    continue;
+                }
+                // Only files inside a group directory, not groups/.gitkeep
+                if let (Some(group), Some(_)) = (components.next(), components.next()) {
+                    if let Some(group) = group.as_os_str().to_str() {
+                        groups.insert(group.to_string());
+                    }
+                }
+            }
+        }
+        
+        Ok(groups.into_iter().collect())
+    }
+    
     /// Get current branch name
     pub fn current_branch(&self) -> Result<String, GitStoreError> {
         let repo = self.lock_repo()?;
@@ -683,6 +1736,20 @@ For remote sync, configure a git remote in Vectorize settings.
     }
 }
 
+/// Lock `mutex`, taking it back if a panic poisoned it
+///
+/// Returns whether the lock had to be recovered.
+fn lock_unpoisoned<'m, T>(mutex: &'m Mutex<T>, name: &str) -> (MutexGuard<'m, T>, bool) {
+    match mutex.lock() {
+        Ok(guard) => (guard, false),
+        Err(poisoned) => {
+            warn!("Recovering {} lock poisoned by a panic", name);
+            mutex.clear_poison();
+            (poisoned.into_inner(), true)
+        }
+    }
+}
+
 /// Information about a remote
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RemoteInfo {
@@ -700,6 +1767,25 @@ pub struct PushResult {
     pub message: String,
 }
 
+/// A bundle written by `export_bundle`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BundleExport {
+    pub path: String,
+    /// Commit the bundle's HEAD points at
+    pub head: String,
+    pub size_bytes: u64,
+}
+
+/// Result of `import_bundle`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct BundleImport {
+    pub previous_head: String,
+    pub head: String,
+    pub commits_imported: usize,
+    /// False when the store already had everything in the bundle
+    pub updated: bool,
+}
+
 /// Result of a pull operation
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct PullResult {
@@ -720,6 +1806,22 @@ pub struct SyncStatus {
     pub synced: bool,
 }
 
+/// Changes a pull would bring in, from `GitStore::preview_incoming`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IncomingChanges {
+    pub remote: String,
+    pub branch: String,
+    pub ahead: u32,
+    pub behind: u32,
+    pub local_head: String,
+    pub remote_head: String,
+    /// Remote commits not in local HEAD, newest first
+    pub commits: Vec<CommitInfo>,
+    /// Diff from local HEAD to the remote tip
+    pub diff: String,
+    pub has_changes: bool,
+}
+
 /// Result of a sync operation
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SyncResult {
@@ -731,6 +1833,17 @@ pub struct SyncResult {
     pub push_error: Option<String>,
 }
 
+/// How to resolve a conflicted file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictResolution {
+    /// Keep the local version
+    Ours,
+    /// Take the remote version
+    Theirs,
+    /// Replace with hand-merged content
+    Content(String),
+}
+
 /// Information about a branch
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct BranchInfo {
@@ -749,6 +1862,40 @@ pub struct CommitInfo {
     pub timestamp: String,
 }
 
+impl CommitInfo {
+    fn from_commit(commit: &git2::Commit<'_>) -> Self {
+        let hash = commit.id().to_string();
+        Self {
+            short_hash: hash[..8].to_string(),
+            hash,
+            message: commit.message().unwrap_or("").to_string(),
+            author: commit.author().name().unwrap_or("Unknown").to_string(),
+            timestamp: chrono::DateTime::from_timestamp(commit.time().seconds(), 0)
+                .map(|dt| dt.to_rfc3339())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A commit that changed one group's config, from `GitStore::config_changes`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ConfigChange {
+    pub group: String,
+    #[serde(flatten)]
+    pub commit: CommitInfo,
+}
+
+/// A commit found by `GitStore::search_history`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HistoryMatch {
+    #[serde(flatten)]
+    pub commit: CommitInfo,
+    /// Where the query matched: `message` or `diff`
+    pub matched_in: String,
+    /// The matching message line, or the `+`/`-` diff line
+    pub snippet: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -766,6 +1913,84 @@ mod tests {
         assert!(dir.path().join("README.md").exists());
     }
     
+    #[test]
+    fn test_panic_while_locked_does_not_break_store() {
+        let dir = tempdir().unwrap();
+        let store = Arc::new(GitStore::open_or_init(dir.path()).unwrap());
+        store.write_config("edge", "level = \"info\"\n").unwrap();
+        
+        let panicking = store.clone();
+        let result = std::thread::spawn(move || {
+            let _write = panicking.lock_config_write().unwrap();
+            let repo = panicking.lock_repo().unwrap();
+            // Stage a change, then die before committing it
+            std::fs::write(panicking.group_config_path("edge"), "level = \"trace\"\n").unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("groups/edge/config.toml")).unwrap();
+            panic!("simulated panic while committing");
+        }).join();
+        assert!(result.is_err());
+        assert!(store.repo.is_poisoned());
+        assert!(store.config_write.is_poisoned());
+        
+        let commit = store.write_config("edge", "level = \"debug\"\n").unwrap();
+        assert_eq!(store.head_hash().unwrap(), commit);
+        assert_eq!(store.read_config("edge").unwrap().as_deref(), Some("level = \"debug\"\n"));
+        assert_eq!(store.get_history(Some("edge"), 10).unwrap().len(), 2);
+        assert!(!store.repo.is_poisoned());
+        assert!(!store.config_write.is_poisoned());
+    }
+    
+    #[test]
+    fn test_bundle_round_trip() {
+        let source_dir = tempdir().unwrap();
+        let target_dir = tempdir().unwrap();
+        let bundles = tempdir().unwrap();
+        let source = GitStore::open_or_init(source_dir.path()).unwrap();
+        let target = GitStore::open_or_init(target_dir.path()).unwrap();
+        
+        source.write_config("edge", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+        source.write_config("core", "[sources.in]\ntype = \"demo_logs\"\n").unwrap();
+        let bundle = bundles.path().join("configs.bundle");
+        let export = source.export_bundle(&bundle).unwrap();
+        assert_eq!(export.head, source.head_hash().unwrap());
+        assert!(export.size_bytes > 0);
+        
+        // A fresh store takes the bundle's history as-is
+        let import = target.import_bundle(&bundle).unwrap();
+        assert!(import.updated);
+        assert_eq!(import.head, export.head);
+        assert_eq!(target.head_hash().unwrap(), export.head);
+        assert_eq!(target.read_config("edge").unwrap(), source.read_config("edge").unwrap());
+        assert_eq!(target.read_config("core").unwrap(), source.read_config("core").unwrap());
+        let history = target.get_history(Some("edge"), 10).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].message.trim(), "Update config for group: edge");
+        
+        // Importing the same bundle again changes nothing
+        assert!(!target.import_bundle(&bundle).unwrap().updated);
+        
+        // Newer history fast-forwards
+        source.write_config("edge", "[sources.in]\ntype = \"file\"\n").unwrap();
+        source.export_bundle(&bundle).unwrap();
+        let import = target.import_bundle(&bundle).unwrap();
+        assert!(import.updated);
+        assert_eq!(import.commits_imported, 1);
+        assert_eq!(target.read_config("edge").unwrap(), source.read_config("edge").unwrap());
+        
+        // Diverged history is rejected and leaves the store alone
+        target.write_config("edge", "[sources.in]\ntype = \"http_server\"\n").unwrap();
+        source.write_config("core", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+        source.export_bundle(&bundle).unwrap();
+        let local_head = target.head_hash().unwrap();
+        let err = target.import_bundle(&bundle).unwrap_err();
+        assert!(matches!(err, GitStoreError::Conflict(_)));
+        assert_eq!(target.head_hash().unwrap(), local_head);
+        assert!(target.read_config("edge").unwrap().unwrap().contains("http_server"));
+        
+        assert!(target.import_bundle(&bundles.path().join("missing.bundle")).is_err());
+    }
+    
     #[test]
     fn test_reopen_existing_repository() {
         let dir = tempdir().unwrap();
@@ -794,6 +2019,25 @@ mod tests {
         assert!(dir.path().join("groups/production/group.yaml").exists());
     }
     
+    #[test]
+    fn test_write_config_initializes_missing_group() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        
+        // No create_group: the group only exists in the database
+        let config = "[sources.test]\ntype = \"demo_logs\"\n";
+        let commit = store.write_config("imported", config).unwrap();
+        
+        assert!(dir.path().join("groups/imported/config.toml").exists());
+        assert!(dir.path().join("groups/imported/group.yaml").exists());
+        
+        // Both files are committed together
+        let repo = Repository::open(dir.path()).unwrap();
+        let tree = repo.find_commit(git2::Oid::from_str(&commit).unwrap()).unwrap().tree().unwrap();
+        assert!(tree.get_path(Path::new("groups/imported/config.toml")).is_ok());
+        assert!(tree.get_path(Path::new("groups/imported/group.yaml")).is_ok());
+    }
+    
     #[test]
     fn test_create_multiple_groups() {
         let dir = tempdir().unwrap();
@@ -952,6 +2196,506 @@ mod tests {
         assert_eq!(config, "original config");
     }
     
+    #[test]
+    fn test_write_config_if_current_base() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        store.create_group("test").unwrap();
+        store.create_group("other").unwrap();
+        
+        let base = store.write_config("test", "v1").unwrap();
+        
+        // Changes to other groups don't make the base stale
+        store.write_config("other", "unrelated").unwrap();
+        
+        let hash = store.write_config_if("test", "v2", &base).unwrap();
+        assert_eq!(store.read_config("test").unwrap().unwrap(), "v2");
+        assert_eq!(store.head_hash().unwrap(), hash);
+    }
+    
+    #[test]
+    fn test_write_config_if_stale_base() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        store.create_group("test").unwrap();
+        
+        let base = store.write_config("test", "v1\n").unwrap();
+        let theirs = store.write_config("test", "v1\ntheirs\n").unwrap();
+        store.create_group("other").unwrap();
+        
+        match store.write_config_if("test", "v1\nmine\n", &base) {
+            Err(GitStoreError::StaleConfig { expected, current, .. }) => {
+                assert_eq!(expected, base);
+                // The commit that changed this group, not HEAD
+                assert_eq!(current, theirs);
+            }
+            other => panic!("expected StaleConfig, got {:?}", other),
+        }
+        assert_eq!(store.read_config("test").unwrap().unwrap(), "v1\ntheirs\n");
+        
+        let diff = store.diff_config("test", &base, &theirs).unwrap();
+        assert!(diff.contains("+theirs"));
+        assert!(!diff.contains("other"));
+        
+        let result = store.write_config_if("test", "v2", "0000000000000000000000000000000000000000");
+        assert!(matches!(result, Err(GitStoreError::NotFound(_))));
+    }
+    
+    #[test]
+    fn test_config_changes_across_groups() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        let start = chrono::Utc::now() - chrono::Duration::seconds(1);
+        
+        let edge = store.write_config("edge", "v1\n").unwrap();
+        let core = store.write_config("core", "v1\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a config").unwrap();
+        store.commit("Add notes").unwrap();
+        let edge2 = store.write_config("edge", "v2\n").unwrap();
+        
+        let changes = store.config_changes(Some(start), None).unwrap();
+        let seen: Vec<_> = changes.iter().map(|c| (c.group.as_str(), c.commit.hash.as_str())).collect();
+        assert_eq!(seen, [("edge", edge.as_str()), ("core", core.as_str()), ("edge", edge2.as_str())]);
+        
+        // Nothing before `since` or at/after `until`
+        let later = chrono::Utc::now() + chrono::Duration::hours(1);
+        assert!(store.config_changes(Some(later), None).unwrap().is_empty());
+        assert!(store.config_changes(None, Some(start)).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_validator_rejects_invalid_config() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap()
+            .with_validator(Arc::new(ConfigValidator::new(None)));
+        store.create_group("test").unwrap();
+        let head = store.head_hash().unwrap();
+        
+        let valid = "[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"blackhole\"\ninputs = [\"in\"]\n";
+        let version = store.write_config("test", valid).unwrap();
+        assert_ne!(version, head);
+        
+        match store.write_config("test", "[sinks.out\ntype = \"blackhole\"\n") {
+            Err(GitStoreError::Invalid(errors)) => assert_eq!(errors[0].code, "TOML_SYNTAX"),
+            other => panic!("expected Invalid, got {:?}", other),
+        }
+        let result = store.write_config_if("test", "[sinks.out\n", &version);
+        assert!(matches!(result, Err(GitStoreError::Invalid(_))));
+        
+        // Nothing was committed
+        assert_eq!(store.head_hash().unwrap(), version);
+        assert_eq!(store.read_config("test").unwrap().unwrap(), valid);
+    }
+    
+    #[test]
+    fn test_force_write_skips_validator() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap()
+            .with_validator(Arc::new(ConfigValidator::new(None)));
+        store.create_group("test").unwrap();
+        
+        let version = store.write_config_force("test", "[sinks.out\n").unwrap();
+        assert_eq!(store.head_hash().unwrap(), version);
+        assert_eq!(store.read_config("test").unwrap().unwrap(), "[sinks.out\n");
+    }
+    
+    #[test]
+    fn test_search_history() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        store.create_group("edge").unwrap();
+        store.create_group("other").unwrap();
+        
+        store.write_config_unchecked("edge", "[sources.in]\ntype = \"stdin\"\n", "Initial edge pipeline").unwrap();
+        let kafka = store.write_config_unchecked(
+            "edge",
+            "[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"Kafka\"\ninputs = [\"in\"]\n",
+            "Ship logs downstream",
+        ).unwrap();
+        let renamed = store.write_config_unchecked("edge", "[sources.in]\ntype = \"stdin\"\n", "Drop KAFKA sink").unwrap();
+        store.write_config_unchecked("other", "[sinks.k]\ntype = \"kafka\"\n", "Add kafka to other").unwrap();
+        
+        // Message and diff matches, case-insensitive, limited to the group
+        let matches = store.search_history(Some("edge"), "kafka", 10, false).unwrap();
+        let hashes: Vec<&str> = matches.iter().map(|m| m.commit.hash.as_str()).collect();
+        assert_eq!(hashes, vec![renamed.as_str(), kafka.as_str()]);
+        assert_eq!(matches[0].matched_in, "message");
+        assert_eq!(matches[0].snippet, "Drop KAFKA sink");
+        assert_eq!(matches[1].matched_in, "diff");
+        assert_eq!(matches[1].snippet, "+type = \"Kafka\"");
+        
+        // Message-only skips diff matches
+        let matches = store.search_history(Some("edge"), "kafka", 10, true).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].commit.hash, renamed);
+        
+        // Without a group every commit is searched
+        assert_eq!(store.search_history(None, "kafka", 10, false).unwrap().len(), 3);
+        assert_eq!(store.search_history(None, "kafka", 1, false).unwrap().len(), 1);
+        
+        assert!(store.search_history(Some("edge"), "elasticsearch", 10, false).unwrap().is_empty());
+        assert!(store.search_history(Some("edge"), "  ", 10, false).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_preview_rollback_matches_applied_change() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        store.create_group("test").unwrap();
+        store.create_group("other").unwrap();
+        
+        let target = store.write_config("test", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+        store.write_config("test", "[sources.in]\ntype = \"demo_logs\"\n").unwrap();
+        store.write_config("other", "unrelated").unwrap();
+        
+        let before = store.head_hash().unwrap();
+        let preview = store.preview_rollback("test", &target).unwrap();
+        assert!(preview.contains("-type = \"demo_logs\""));
+        assert!(preview.contains("+type = \"stdin\""));
+        
+        // Previewing writes nothing
+        assert_eq!(store.head_hash().unwrap(), before);
+        
+        let after = store.rollback("test", &target).unwrap();
+        assert_eq!(store.diff_config("test", &before, &after).unwrap(), preview);
+        
+        // Nothing left to change
+        assert!(store.preview_rollback("test", &target).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_copy_config() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        
+        store.create_group("staging").unwrap();
+        let validated = store.write_config("staging", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+        store.write_config("staging", "work in progress").unwrap();
+        
+        // HEAD copy into a group that doesn't exist yet
+        let hash = store.copy_config("staging", "prod", None).unwrap();
+        assert_eq!(store.read_config("prod").unwrap(), store.read_config("staging").unwrap());
+        let history = store.get_history(None, 1).unwrap();
+        assert_eq!(history[0].hash, hash);
+        assert!(history[0].message.starts_with("Copy config from staging@"));
+        assert!(history[0].message.ends_with(" to prod"));
+        
+        // Copy a specific version
+        store.copy_config("staging", "prod", Some(&validated)).unwrap();
+        assert_eq!(
+            store.read_config("prod").unwrap(),
+            store.get_config_at_version("staging", &validated).unwrap()
+        );
+    }
+    
+    #[test]
+    fn test_copy_config_missing_source() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        store.create_group("prod").unwrap();
+        
+        let result = store.copy_config("missing", "prod", None);
+        assert!(matches!(result, Err(GitStoreError::NotFound(_))));
+    }
+    
+    #[test]
+    fn test_diff_branches() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        
+        store.create_group("edge").unwrap();
+        store.create_group("core").unwrap();
+        store.write_config("edge", "level = \"info\"\n").unwrap();
+        
+        // Snapshot the promoted state, then keep working on the current branch
+        store.create_branch("release").unwrap();
+        let working = store.current_branch().unwrap();
+        store.write_config("edge", "level = \"debug\"\n").unwrap();
+        store.create_group("new").unwrap();
+        
+        let diff = store.diff_branches("release", &working).unwrap();
+        assert!(diff.contains("-level = \"info\""));
+        assert!(diff.contains("+level = \"debug\""));
+        assert!(!diff.contains("groups/core/"));
+        
+        let changed = store.changed_groups("release", &working).unwrap();
+        assert_eq!(changed, vec!["edge", "new"]);
+        
+        assert!(store.diff_branches("release", "release").unwrap().is_empty());
+        assert!(store.changed_groups("release", "release").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_diff_branches_unknown_branch() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        
+        let working = store.current_branch().unwrap();
+        let result = store.diff_branches(&working, "does-not-exist");
+        assert!(matches!(result, Err(GitStoreError::NotFound(_))));
+    }
+    
+    /// Local store and a clone that have both changed `edge`'s config, with
+    /// the local store left conflicted by a pull
+    fn conflicted_store(dir: &Path) -> GitStore {
+        let local_path = dir.join("local");
+        let remote_path = dir.join("remote");
+        
+        let store = GitStore::open_or_init(&local_path).unwrap();
+        {
+            let repo = store.lock_repo().unwrap();
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "Vectorize").unwrap();
+            config.set_str("user.email", "vectorize@local").unwrap();
+        }
+        store.create_group("edge").unwrap();
+        store.write_config("edge", "level = \"info\"\n").unwrap();
+        
+        let remote = Repository::clone(local_path.to_str().unwrap(), &remote_path).unwrap();
+        std::fs::write(remote_path.join("groups/edge/config.toml"), "level = \"warn\"\n").unwrap();
+        let mut index = remote.index().unwrap();
+        index.add_path(Path::new("groups/edge/config.toml")).unwrap();
+        index.write().unwrap();
+        let tree = remote.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = remote.head().unwrap().peel_to_commit().unwrap();
+        let sig = Signature::now("Remote", "remote@local").unwrap();
+        remote.commit(Some("HEAD"), &sig, &sig, "Remote change", &tree, &[&parent]).unwrap();
+        
+        store.write_config("edge", "level = \"debug\"\n").unwrap();
+        store.configure_remote("origin", remote_path.to_str().unwrap()).unwrap();
+        
+        let branch = store.current_branch().unwrap();
+        let result = store.pull("origin", Some(&branch));
+        assert!(matches!(result, Err(GitStoreError::Conflict(_))), "{:?}", result);
+        
+        store
+    }
+    
+    #[test]
+    fn test_preview_incoming() {
+        let dir = tempdir().unwrap();
+        let local_path = dir.path().join("local");
+        let remote_path = dir.path().join("remote");
+        
+        let store = GitStore::open_or_init(&local_path).unwrap();
+        store.create_group("edge").unwrap();
+        store.write_config("edge", "level = \"info\"\n").unwrap();
+        let before = store.head_hash().unwrap();
+        
+        let remote = Repository::clone(local_path.to_str().unwrap(), &remote_path).unwrap();
+        std::fs::write(remote_path.join("groups/edge/config.toml"), "level = \"warn\"\n").unwrap();
+        let mut index = remote.index().unwrap();
+        index.add_path(Path::new("groups/edge/config.toml")).unwrap();
+        index.write().unwrap();
+        let tree = remote.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = remote.head().unwrap().peel_to_commit().unwrap();
+        let sig = Signature::now("Remote", "remote@local").unwrap();
+        let remote_commit = remote.commit(Some("HEAD"), &sig, &sig, "Raise log level", &tree, &[&parent]).unwrap();
+        
+        store.configure_remote("origin", remote_path.to_str().unwrap()).unwrap();
+        let branch = store.current_branch().unwrap();
+        
+        let incoming = store.preview_incoming("origin", Some(&branch)).unwrap();
+        assert_eq!(incoming.ahead, 0);
+        assert_eq!(incoming.behind, 1);
+        assert_eq!(incoming.remote_head, remote_commit.to_string());
+        assert_eq!(incoming.commits.len(), 1);
+        assert_eq!(incoming.commits[0].message, "Raise log level");
+        assert!(incoming.has_changes);
+        assert!(incoming.diff.contains("-level = \"info\""));
+        assert!(incoming.diff.contains("+level = \"warn\""));
+        
+        // Nothing was merged
+        assert_eq!(store.head_hash().unwrap(), before);
+        assert_eq!(store.read_config("edge").unwrap().as_deref(), Some("level = \"info\"\n"));
+        
+        let result = store.preview_incoming("origin", Some("no-such-branch"));
+        assert!(matches!(result, Err(GitStoreError::NotFound(_))));
+    }
+    
+    #[test]
+    fn test_push_paths_publishes_only_selected_groups() {
+        let dir = tempdir().unwrap();
+        let local_path = dir.path().join("local");
+        let remote_path = dir.path().join("remote.git");
+        
+        let store = GitStore::open_or_init(&local_path).unwrap();
+        store.write_config("edge", "level = \"info\"\n").unwrap();
+        store.write_config("secrets", "token = \"old\"\n").unwrap();
+        let remote = Repository::init_bare(&remote_path).unwrap();
+        store.configure_remote("origin", remote_path.to_str().unwrap()).unwrap();
+        let branch = store.current_branch().unwrap();
+        store.push("origin", Some(&branch)).unwrap();
+        
+        store.write_config("edge", "level = \"debug\"\n").unwrap();
+        store.write_config("secrets", "token = \"new\"\n").unwrap();
+        let local_head = store.head_hash().unwrap();
+        
+        let result = store.push_paths("origin", Some(&branch), &["groups/edge"]).unwrap();
+        assert!(result.success);
+        
+        let published = remote.find_branch(&branch, git2::BranchType::Local).unwrap()
+            .get().peel_to_commit().unwrap();
+        assert_eq!(published.message(), Some("Publish groups/edge"));
+        let read = |path: &str| {
+            let entry = published.tree().unwrap().get_path(Path::new(path)).unwrap();
+            let blob = remote.find_blob(entry.id()).unwrap();
+            String::from_utf8(blob.content().to_vec()).unwrap()
+        };
+        assert_eq!(read("groups/edge/config.toml"), "level = \"debug\"\n");
+        assert_eq!(read("groups/secrets/config.toml"), "token = \"old\"\n");
+        
+        // The local branch is untouched
+        assert_eq!(store.head_hash().unwrap(), local_head);
+        assert_eq!(store.read_config("secrets").unwrap().as_deref(), Some("token = \"new\"\n"));
+        
+        let again = store.push_paths("origin", Some(&branch), &["groups/edge"]).unwrap();
+        assert_eq!(again.message, "Nothing to push under the given paths");
+        
+        assert!(matches!(
+            store.push_paths("origin", Some(&branch), &["../outside"]),
+            Err(GitStoreError::InvalidPath(_))
+        ));
+    }
+    
+    #[test]
+    fn test_resolve_conflict_ours() {
+        let dir = tempdir().unwrap();
+        let store = conflicted_store(dir.path());
+        
+        assert_eq!(store.list_conflicts().unwrap(), vec!["groups/edge/config.toml"]);
+        
+        let hash = store.resolve_conflict("groups/edge/config.toml", ConflictResolution::Ours)
+            .unwrap()
+            .expect("merge committed after last conflict");
+        
+        assert!(store.list_conflicts().unwrap().is_empty());
+        assert_eq!(store.head_hash().unwrap(), hash);
+        assert_eq!(store.get_config_at_version("edge", &hash).unwrap().as_deref(), Some("level = \"debug\"\n"));
+        assert_eq!(store.read_config("edge").unwrap().as_deref(), Some("level = \"debug\"\n"));
+        
+        let repo = store.lock_repo().unwrap();
+        assert_eq!(repo.find_commit(git2::Oid::from_str(&hash).unwrap()).unwrap().parent_count(), 2);
+        assert_eq!(repo.state(), git2::RepositoryState::Clean);
+    }
+    
+    #[test]
+    fn test_resolve_conflict_content() {
+        let dir = tempdir().unwrap();
+        let store = conflicted_store(dir.path());
+        
+        let merged = "level = \"warn\"\n# merged\n";
+        let hash = store.resolve_conflict("groups/edge/config.toml", ConflictResolution::Content(merged.to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(store.get_config_at_version("edge", &hash).unwrap().as_deref(), Some(merged));
+        
+        let result = store.resolve_conflict("groups/edge/config.toml", ConflictResolution::Theirs);
+        assert!(matches!(result, Err(GitStoreError::NotFound(_))));
+    }
+    
+    #[test]
+    fn test_abort_merge() {
+        let dir = tempdir().unwrap();
+        let store = conflicted_store(dir.path());
+        let before = store.head_hash().unwrap();
+        
+        store.abort_merge().unwrap();
+        
+        assert!(store.list_conflicts().unwrap().is_empty());
+        assert_eq!(store.head_hash().unwrap(), before);
+        assert_eq!(store.read_config("edge").unwrap().as_deref(), Some("level = \"debug\"\n"));
+        assert!(matches!(store.abort_merge(), Err(GitStoreError::NotFound(_))));
+    }
+    
+    fn committed_paths(store: &GitStore) -> Vec<String> {
+        let repo = store.lock_repo().unwrap();
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        let mut paths = Vec::new();
+        tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                paths.push(format!("{}{}", root, entry.name().unwrap()));
+            }
+            git2::TreeWalkResult::Ok
+        }).unwrap();
+        paths
+    }
+    
+    #[test]
+    fn test_gitignore_excludes_transient_files() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        assert!(committed_paths(&store).contains(&".gitignore".to_string()));
+        
+        store.create_group("edge").unwrap();
+        std::fs::write(store.group_path("edge").join(".config.toml.swp"), "swap").unwrap();
+        std::fs::write(dir.path().join(".DS_Store"), "finder").unwrap();
+        store.write_config("edge", "changed").unwrap();
+        
+        let paths = committed_paths(&store);
+        assert!(paths.contains(&"groups/edge/config.toml".to_string()));
+        assert!(!paths.iter().any(|p| p.ends_with(".swp") || p.ends_with(".DS_Store")));
+    }
+    
+    #[test]
+    fn test_add_ignore_pattern() {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        
+        store.create_group("edge").unwrap();
+        std::fs::write(store.group_path("edge").join("notes.local"), "scratch").unwrap();
+        store.commit("Add notes").unwrap();
+        assert!(committed_paths(&store).contains(&"groups/edge/notes.local".to_string()));
+        
+        // Ignoring an already-tracked file removes it from the repository
+        store.add_ignore_pattern("*.local").unwrap();
+        store.add_ignore_pattern("*.local").unwrap();
+        let paths = committed_paths(&store);
+        assert!(!paths.contains(&"groups/edge/notes.local".to_string()));
+        assert!(store.group_path("edge").join("notes.local").exists());
+        
+        let gitignore = std::fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert_eq!(gitignore.lines().filter(|l| *l == "*.local").count(), 1);
+        
+        assert!(store.add_ignore_pattern("  ").is_err());
+    }
+    
+    #[test]
+    fn test_ssh_signed_commits() {
+        let dir = tempdir().unwrap();
+        let key_path = dir.path().join("signing_key");
+        let status = std::process::Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "vectorize-test", "-f"])
+            .arg(&key_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        
+        let store = GitStore::open_or_init(&dir.path().join("repo")).unwrap();
+        store.create_group("edge").unwrap();
+        let unsigned = store.write_config("edge", "unsigned").unwrap();
+        
+        store.set_signing_key(Some(SigningKey::ssh(key_path.to_str().unwrap()))).unwrap();
+        let signed = store.write_config("edge", "signed").unwrap();
+        
+        // HEAD advanced to the signed commit
+        assert_eq!(store.head_hash().unwrap(), signed);
+        assert_eq!(store.read_config("edge").unwrap().as_deref(), Some("signed"));
+        assert_eq!(store.get_history(None, 1).unwrap()[0].hash, signed);
+        
+        let signature = store.verify_commit(&signed).unwrap();
+        assert!(signature.signed);
+        assert!(signature.valid);
+        assert_eq!(signature.format, Some(SigningFormat::Ssh));
+        
+        assert_eq!(store.verify_commit(&unsigned).unwrap(), CommitSignature::unsigned());
+        
+        // Back to unsigned commits
+        store.set_signing_key(None).unwrap();
+        let hash = store.write_config("edge", "unsigned again").unwrap();
+        assert!(!store.verify_commit(&hash).unwrap().signed);
+    }
+    
     #[test]
     fn test_list_groups_via_filesystem() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,251 @@
+//! Commit signing
+//!
+//! Signs commits with a GPG or SSH key by shelling out to `gpg` and
+//! `ssh-keygen`, the same tools git itself uses, so keys configured for
+//! git work unchanged. The signature is stored in the commit's `gpgsig`
+//! header for both formats.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::GitStoreError;
+
+/// SSH signature namespace used by git
+const SSH_NAMESPACE: &str = "git";
+
+/// Principal name used in the generated allowed-signers file
+const SSH_PRINCIPAL: &str = "vectorize";
+
+/// Signature format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningFormat {
+    Gpg,
+    Ssh,
+}
+
+impl std::str::FromStr for SigningFormat {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gpg" | "openpgp" => Ok(SigningFormat::Gpg),
+            "ssh" => Ok(SigningFormat::Ssh),
+            _ => Err(format!("Unknown signing format: {}", s)),
+        }
+    }
+}
+
+/// Key used to sign commits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningKey {
+    pub format: SigningFormat,
+    /// GPG key ID, or path to an SSH private key
+    pub key: String,
+}
+
+impl SigningKey {
+    /// Sign with a GPG key from the default keyring
+    pub fn gpg(key_id: impl Into<String>) -> Self {
+        Self { format: SigningFormat::Gpg, key: key_id.into() }
+    }
+    
+    /// Sign with an SSH private key
+    pub fn ssh(private_key_path: impl Into<String>) -> Self {
+        Self { format: SigningFormat::Ssh, key: private_key_path.into() }
+    }
+    
+    /// Read `VECTORIZE_GIT_SIGNING_KEY` and `VECTORIZE_GIT_SIGNING_FORMAT`
+    /// (`gpg` or `ssh`, default `gpg`)
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let key = match std::env::var("VECTORIZE_GIT_SIGNING_KEY") {
+            Ok(key) if !key.trim().is_empty() => key.trim().to_string(),
+            _ => return Ok(None),
+        };
+        let format = match std::env::var("VECTORIZE_GIT_SIGNING_FORMAT") {
+            Ok(format) => format.parse()?,
+            Err(_) => SigningFormat::Gpg,
+        };
+        Ok(Some(Self { format, key }))
+    }
+    
+    /// Public key file for an SSH key
+    fn ssh_public_key_path(&self) -> PathBuf {
+        if self.key.ends_with(".pub") {
+            PathBuf::from(&self.key)
+        } else {
+            PathBuf::from(format!("{}.pub", self.key))
+        }
+    }
+}
+
+/// Signature state of a commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitSignature {
+    /// Whether the commit carries a signature
+    pub signed: bool,
+    /// Format of the signature, if recognized
+    pub format: Option<SigningFormat>,
+    /// Whether the signature validates against the signed content
+    pub valid: bool,
+}
+
+impl CommitSignature {
+    pub fn unsigned() -> Self {
+        Self { signed: false, format: None, valid: false }
+    }
+}
+
+/// Detect the format of an armored signature
+pub fn detect_format(signature: &str) -> Option<SigningFormat> {
+    let signature = signature.trim_start();
+    if signature.starts_with("-----BEGIN SSH SIGNATURE-----") {
+        Some(SigningFormat::Ssh)
+    } else if signature.starts_with("-----BEGIN PGP SIGNATURE-----") {
+        Some(SigningFormat::Gpg)
+    } else {
+        None
+    }
+}
+
+/// Produce an armored detached signature over a commit buffer
+pub(crate) fn sign(key: &SigningKey, content: &str) -> Result<String, GitStoreError> {
+    let mut command = match key.format {
+        SigningFormat::Gpg => {
+            let mut command = Command::new("gpg");
+            command.args(["--batch", "--armor", "--detach-sign", "--local-user", &key.key]);
+            command
+        }
+        SigningFormat::Ssh => {
+            let mut command = Command::new("ssh-keygen");
+            command.args(["-Y", "sign", "-n", SSH_NAMESPACE, "-f", &key.key]);
+            command
+        }
+    };
+    
+    let output = run_with_stdin(&mut command, content.as_bytes())?;
+    if !output.status.success() {
+        return Err(GitStoreError::Signing(format!(
+            "{:?} signing failed: {}",
+            key.format,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    
+    let signature = String::from_utf8(output.stdout)
+        .map_err(|e| GitStoreError::Signing(e.to_string()))?;
+    if detect_format(&signature) != Some(key.format) {
+        return Err(GitStoreError::Signing("Signer produced no signature".to_string()));
+    }
+    
+    Ok(signature)
+}
+
+/// Check a signature against the data it signs
+///
+/// SSH signatures are checked against `key`'s public key when an SSH key is
+/// configured, otherwise only for integrity. GPG signatures are checked
+/// against the local keyring.
+pub(crate) fn verify(signature: &str, signed_data: &[u8], key: Option<&SigningKey>) -> Result<bool, GitStoreError> {
+    let Some(format) = detect_format(signature) else {
+        return Ok(false);
+    };
+    
+    let sig_file = TempFile::new("sig", signature.as_bytes())?;
+    
+    let output = match format {
+        SigningFormat::Gpg => {
+            let mut command = Command::new("gpg");
+            command.args(["--batch", "--verify"]).arg(sig_file.path()).arg("-");
+            run_with_stdin(&mut command, signed_data)?
+        }
+        SigningFormat::Ssh => match key.filter(|k| k.format == SigningFormat::Ssh) {
+            Some(key) => {
+                let public_key = std::fs::read_to_string(key.ssh_public_key_path())?;
+                let allowed = TempFile::new(
+                    "allowed_signers",
+                    format!("{} namespaces=\"{}\" {}\n", SSH_PRINCIPAL, SSH_NAMESPACE, public_key.trim()).as_bytes(),
+                )?;
+                let mut command = Command::new("ssh-keygen");
+                command.args(["-Y", "verify", "-n", SSH_NAMESPACE, "-I", SSH_PRINCIPAL, "-f"])
+                    .arg(allowed.path())
+                    .arg("-s")
+                    .arg(sig_file.path());
+                run_with_stdin(&mut command, signed_data)?
+            }
+            None => {
+                let mut command = Command::new("ssh-keygen");
+                command.args(["-Y", "check-novalidate", "-n", SSH_NAMESPACE, "-s"])
+                    .arg(sig_file.path());
+                run_with_stdin(&mut command, signed_data)?
+            }
+        },
+    };
+    
+    Ok(output.status.success())
+}
+
+fn run_with_stdin(command: &mut Command, input: &[u8]) -> Result<std::process::Output, GitStoreError> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| GitStoreError::Signing(format!("Failed to run {:?}: {}", command.get_program(), e)))?;
+    
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    
+    Ok(child.wait_with_output()?)
+}
+
+/// File in the temp directory, removed on drop
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(suffix: &str, content: &[u8]) -> Result<Self, GitStoreError> {
+        let path = std::env::temp_dir().join(format!("vectorize-{}.{}", Uuid::new_v4(), suffix));
+        std::fs::write(&path, content)?;
+        Ok(Self(path))
+    }
+    
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format("-----BEGIN SSH SIGNATURE-----\nabc"), Some(SigningFormat::Ssh));
+        assert_eq!(detect_format("-----BEGIN PGP SIGNATURE-----\nabc"), Some(SigningFormat::Gpg));
+        assert_eq!(detect_format("garbage"), None);
+    }
+    
+    #[test]
+    fn test_signing_format_from_str() {
+        assert_eq!("SSH".parse::<SigningFormat>().unwrap(), SigningFormat::Ssh);
+        assert_eq!("openpgp".parse::<SigningFormat>().unwrap(), SigningFormat::Gpg);
+        assert!("x509".parse::<SigningFormat>().is_err());
+    }
+    
+    #[test]
+    fn test_ssh_public_key_path() {
+        assert_eq!(SigningKey::ssh("/keys/id_ed25519").ssh_public_key_path(), PathBuf::from("/keys/id_ed25519.pub"));
+        assert_eq!(SigningKey::ssh("/keys/id_ed25519.pub").ssh_public_key_path(), PathBuf::from("/keys/id_ed25519.pub"));
+    }
+}
@@ -0,0 +1,364 @@
+//! Group config templates
+//!
+//! A template is a parameterized Vector config in `templates/{name}.toml`
+//! with `${var}` placeholders, plus an optional `templates/{name}.vars.yaml`
+//! declaring its variables:
+//!
+//! ```yaml
+//! # Region the agents run in; required because it has no default
+//! region:
+//!   description: Cloud region
+//! api_port:
+//!   description: Vector API port
+//!   default: "8686"
+//! ```
+//!
+//! Only declared variables are substituted, so `${ENV_VAR}` references meant
+//! for Vector's own environment interpolation pass through untouched. The
+//! vars file is a flat YAML mapping; only `description`, `default` and
+//! `required` are read.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tracing::info;
+
+use super::{GitStore, GitStoreError};
+
+/// A variable declared by a template
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub description: Option<String>,
+    pub default: Option<String>,
+    /// Must be given when instantiating; defaults to true unless there is a default
+    pub required: bool,
+}
+
+/// A template and the variables it takes
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub variables: Vec<TemplateVariable>,
+}
+
+impl GitStore {
+    /// List the templates in `templates/`, sorted by name
+    pub fn list_templates(&self) -> Result<Vec<TemplateInfo>, GitStoreError> {
+        let dir = self.path().join("templates");
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+    
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let file_name = entry?.file_name();
+            if let Some(name) = file_name.to_str().and_then(|n| n.strip_suffix(".toml")) {
+                if is_valid_template_name(name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+    
+        names
+            .into_iter()
+            .map(|name| {
+                let variables = self.template_variables(&name)?;
+                Ok(TemplateInfo { name, variables })
+            })
+            .collect()
+    }
+    
+    /// Render a template with `vars`, filling in defaults
+    ///
+    /// Fails with `MissingVariables` if a required variable isn't given and
+    /// `Template` if `vars` names a variable the template doesn't declare.
+    pub fn render_template(&self, template_name: &str, vars: &HashMap<String, String>) -> Result<String, GitStoreError> {
+        if !is_valid_template_name(template_name) {
+            return Err(GitStoreError::Template(format!("Invalid template name: {}", template_name)));
+        }
+    
+        let path = self.path().join("templates").join(format!("{}.toml", template_name));
+        if !path.exists() {
+            return Err(GitStoreError::NotFound(format!("Template {} not found", template_name)));
+        }
+        let template = std::fs::read_to_string(path)?;
+        let variables = self.template_variables(template_name)?;
+    
+        let mut unknown: Vec<&str> = vars.keys()
+            .filter(|key| !variables.iter().any(|v| &v.name == *key))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort();
+            return Err(GitStoreError::Template(format!(
+                "Template {} has no variable(s): {}", template_name, unknown.join(", ")
+            )));
+        }
+    
+        let missing: Vec<String> = variables.iter()
+            .filter(|v| v.required && !vars.contains_key(&v.name) && v.default.is_none())
+            .map(|v| v.name.clone())
+            .collect();
+        if !missing.is_empty() {
+            return Err(GitStoreError::MissingVariables(missing));
+        }
+    
+        let values: HashMap<&str, &str> = variables.iter()
+            .map(|v| {
+                let value = vars.get(&v.name).or(v.default.as_ref()).map(String::as_str).unwrap_or("");
+                (v.name.as_str(), value)
+            })
+            .collect();
+    
+        Ok(substitute(&template, &values))
+    }
+    
+    /// Render a template and commit it as a group's config
+    ///
+    /// Returns the new commit hash.
+    pub fn instantiate_template(
+        &self,
+        template_name: &str,
+        group_name: &str,
+        vars: HashMap<String, String>,
+    ) -> Result<String, GitStoreError> {
+        let config = self.render_template(template_name, &vars)?;
+    
+        let _guard = self.lock_config_write()?;
+        let hash = self.write_config_unchecked(
+            group_name,
+            &config,
+            &format!("Instantiate template {} for group: {}", template_name, group_name),
+        )?;
+    
+        info!("Instantiated template {} for group {}", template_name, group_name);
+    
+        Ok(hash)
+    }
+    
+    /// Variables declared in a template's vars file (none if it has no file)
+    fn template_variables(&self, template_name: &str) -> Result<Vec<TemplateVariable>, GitStoreError> {
+        let path = self.path().join("templates").join(format!("{}.vars.yaml", template_name));
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        parse_variables(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// Template names become file names, so keep them to a safe character set
+fn is_valid_template_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn is_valid_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse a vars file: top-level keys are variables, indented keys their properties
+///
+/// `name: value` on one line is shorthand for a variable with that default.
+fn parse_variables(text: &str) -> Result<Vec<TemplateVariable>, GitStoreError> {
+    let mut variables: Vec<TemplateVariable> = Vec::new();
+    let mut required: Vec<Option<bool>> = Vec::new();
+    
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+    
+        let invalid = |reason: &str| GitStoreError::Template(format!("vars file line {}: {}", index + 1, reason));
+        let (key, value) = trimmed.split_once(':').ok_or_else(|| invalid("expected `key: value`"))?;
+        let key = key.trim();
+        let value = unquote(value.trim());
+    
+        if !line.starts_with(char::is_whitespace) {
+            if !is_valid_variable_name(key) {
+                return Err(invalid(&format!("invalid variable name `{}`", key)));
+            }
+            if variables.iter().any(|v| v.name == key) {
+                return Err(invalid(&format!("duplicate variable `{}`", key)));
+            }
+            variables.push(TemplateVariable {
+                name: key.to_string(),
+                description: None,
+                default: (!value.is_empty()).then(|| value.to_string()),
+                required: false,
+            });
+            required.push(None);
+            continue;
+        }
+    
+        let variable = variables.last_mut().ok_or_else(|| invalid("property before any variable"))?;
+        match key {
+            "description" => variable.description = Some(value.to_string()),
+            "default" => variable.default = Some(value.to_string()),
+            "required" => {
+                let flag = value.parse().map_err(|_| invalid("`required` must be true or false"))?;
+                *required.last_mut().expect("pushed with the variable") = Some(flag);
+            }
+            other => return Err(invalid(&format!("unknown property `{}`", other))),
+        }
+    }
+    
+    for (variable, required) in variables.iter_mut().zip(required) {
+        variable.required = required.unwrap_or(variable.default.is_none());
+    }
+    
+    Ok(variables)
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
+
+/// Replace `${name}` for every name in `values`, leaving other `${...}` alone
+fn substitute(template: &str, values: &HashMap<&str, &str>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find('}').and_then(|end| values.get(&after[..end]).map(|value| (end, value))) {
+            Some((end, value)) => {
+                output.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push_str("${");
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    const TEMPLATE: &str = "[api]\naddress = \"0.0.0.0:${api_port}\"\n\n[sinks.out]\ntype = \"http\"\nuri = \"https://logs.${region}.example.com\"\nauth.token = \"${LOG_TOKEN}\"\n";
+    
+    const VARS: &str = "\
+# Edge template variables
+region:
+  description: Cloud region
+api_port:
+  description: \"Vector API port\"
+  default: \"8686\"
+";
+
+    fn store_with_template() -> (GitStore, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let store = GitStore::open_or_init(dir.path()).unwrap();
+        std::fs::write(dir.path().join("templates/edge.toml"), TEMPLATE).unwrap();
+        std::fs::write(dir.path().join("templates/edge.vars.yaml"), VARS).unwrap();
+        (store, dir)
+    }
+    
+    #[test]
+    fn test_parse_variables() {
+        let variables = parse_variables(VARS).unwrap();
+        assert_eq!(variables, vec![
+            TemplateVariable {
+                name: "region".to_string(),
+                description: Some("Cloud region".to_string()),
+                default: None,
+                required: true,
+            },
+            TemplateVariable {
+                name: "api_port".to_string(),
+                description: Some("Vector API port".to_string()),
+                default: Some("8686".to_string()),
+                required: false,
+            },
+        ]);
+    
+        let variables = parse_variables("env: prod\nteam:\n  required: false\n").unwrap();
+        assert_eq!(variables[0].default.as_deref(), Some("prod"));
+        assert!(!variables[1].required);
+    
+        assert!(matches!(parse_variables("  description: orphan\n"), Err(GitStoreError::Template(_))));
+        assert!(matches!(parse_variables("bad-name:\n"), Err(GitStoreError::Template(_))));
+    }
+    
+    #[test]
+    fn test_list_templates() {
+        let (store, dir) = store_with_template();
+        std::fs::write(dir.path().join("templates/bare.toml"), "[api]\nenabled = true\n").unwrap();
+    
+        let templates = store.list_templates().unwrap();
+        let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["bare", "edge"]);
+        assert!(templates[0].variables.is_empty());
+        assert_eq!(templates[1].variables.len(), 2);
+    }
+    
+    #[test]
+    fn test_instantiate_template() {
+        let (store, _dir) = store_with_template();
+        store.create_group("edge-eu").unwrap();
+    
+        let vars = HashMap::from([("region".to_string(), "eu-west-1".to_string())]);
+        let hash = store.instantiate_template("edge", "edge-eu", vars).unwrap();
+    
+        let config = store.read_config("edge-eu").unwrap().unwrap();
+        assert!(config.contains("address = \"0.0.0.0:8686\""));
+        assert!(config.contains("uri = \"https://logs.eu-west-1.example.com\""));
+        // Undeclared placeholders are left for Vector's env interpolation
+        assert!(config.contains("auth.token = \"${LOG_TOKEN}\""));
+    
+        let history = store.get_history(None, 1).unwrap();
+        assert_eq!(history[0].hash, hash);
+        assert_eq!(history[0].message, "Instantiate template edge for group: edge-eu");
+    }
+    
+    #[test]
+    fn test_missing_required_variable() {
+        let (store, _dir) = store_with_template();
+        store.create_group("edge-eu").unwrap();
+        let before = store.read_config("edge-eu").unwrap();
+    
+        let vars = HashMap::from([("api_port".to_string(), "9000".to_string())]);
+        match store.instantiate_template("edge", "edge-eu", vars) {
+            Err(GitStoreError::MissingVariables(missing)) => assert_eq!(missing, vec!["region".to_string()]),
+            other => panic!("expected MissingVariables, got {:?}", other),
+        }
+        assert_eq!(store.read_config("edge-eu").unwrap(), before);
+    }
+    
+    #[test]
+    fn test_unknown_variable_and_template() {
+        let (store, _dir) = store_with_template();
+    
+        let vars = HashMap::from([
+            ("region".to_string(), "eu".to_string()),
+            ("regoin".to_string(), "eu".to_string()),
+        ]);
+        assert!(matches!(store.render_template("edge", &vars), Err(GitStoreError::Template(_))));
+        assert!(matches!(store.render_template("missing", &HashMap::new()), Err(GitStoreError::NotFound(_))));
+        assert!(matches!(store.render_template("../groups/x", &HashMap::new()), Err(GitStoreError::Template(_))));
+    }
+    
+    #[test]
+    fn test_substitute() {
+        let values = HashMap::from([("a", "1")]);
+        assert_eq!(substitute("${a}-${b}-${a", &values), "1-${b}-${a");
+        assert_eq!(substitute("no placeholders", &values), "no placeholders");
+    }
+}
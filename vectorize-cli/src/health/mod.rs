@@ -4,16 +4,27 @@
 //! - Periodically polls agent health endpoints
 //! - Records health check results in the database
 //! - Updates agent status (healthy/unhealthy/unreachable)
-//! - Collects metrics from agents
+//! - Marks agents unreachable once `last_seen` goes stale, whatever the polls say
+//! - Optionally deregisters agents that stay unreachable (e.g. autoscaled VMs)
+//! - Collects metrics from agents, and per-component rates for a live view
 
+pub mod top;
+
+use futures::StreamExt;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
-use tracing::{info, warn, error, debug};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn, error, debug, info_span, Instrument};
 use serde::{Deserialize, Serialize};
 
+use crate::agent_clients::AgentClients;
+use crate::crypto::SecretCipher;
 use crate::db::Database;
-use crate::db::repository::AgentRepository;
+use crate::db::models::{Agent, WorkerGroup};
+use crate::db::repository::{AgentRepository, WorkerGroupRepository};
+use crate::live::{LiveEvent, LiveEventBus};
+use crate::request_id::{self, WithRequestId};
 
 /// Health check result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +49,22 @@ pub struct AgentMetrics {
     pub bytes_processed_total: Option<u64>,
     pub component_errors_total: Option<u64>,
     pub uptime_seconds: Option<u64>,
+    /// Per-component counters the totals are summed from
+    #[serde(default)]
+    pub components: Vec<ComponentMetrics>,
+}
+
+/// Counters for one source, transform or sink
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ComponentMetrics {
+    pub component_id: String,
+    /// `source`, `transform` or `sink`
+    pub kind: String,
+    /// Component type, e.g. `kafka`
+    pub component_type: Option<String>,
+    pub sent_events: Option<u64>,
+    /// Not reported for transforms
+    pub sent_bytes: Option<u64>,
 }
 
 /// Aggregated health status
@@ -47,6 +74,8 @@ pub struct HealthSummary {
     pub healthy_agents: u32,
     pub unhealthy_agents: u32,
     pub unreachable_agents: u32,
+    /// Agents not seen (polled healthy or heartbeating) within the threshold
+    pub stale_agents: u32,
     pub last_check: String,
 }
 
@@ -59,6 +88,16 @@ pub struct HealthMonitorConfig {
     pub timeout_secs: u64,
     /// Number of failed checks before marking unreachable
     pub failure_threshold: u32,
+    /// Seconds without a successful poll or heartbeat before an agent is stale
+    pub stale_after_secs: u64,
+    /// How often to look for stale agents (in seconds)
+    pub stale_sweep_interval_secs: u64,
+    /// Most agents checked at once, so a large fleet can't exhaust sockets
+    pub max_concurrent_checks: usize,
+    /// Soft-delete agents that stay unreachable; groups can override this
+    pub auto_deregister: bool,
+    /// Seconds an agent must be unreachable before it is deregistered
+    pub deregister_after_secs: u64,
 }
 
 impl Default for HealthMonitorConfig {
@@ -67,36 +106,143 @@ impl Default for HealthMonitorConfig {
             check_interval_secs: 30,
             timeout_secs: 10,
             failure_threshold: 3,
+            stale_after_secs: DEFAULT_STALE_AFTER_SECS,
+            stale_sweep_interval_secs: 15,
+            max_concurrent_checks: DEFAULT_MAX_CONCURRENT_CHECKS,
+            auto_deregister: false,
+            deregister_after_secs: DEFAULT_DEREGISTER_AFTER_SECS,
+        }
+    }
+}
+
+/// Default staleness threshold: three missed 30s heartbeats
+pub const DEFAULT_STALE_AFTER_SECS: u64 = 90;
+
+/// Default cap on health checks in flight
+pub const DEFAULT_MAX_CONCURRENT_CHECKS: usize = 50;
+
+/// Default time an agent may stay unreachable before it is deregistered
+pub const DEFAULT_DEREGISTER_AFTER_SECS: u64 = 24 * 60 * 60;
+
+/// When an agent that stopped responding is deregistered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeregisterPolicy {
+    pub enabled: bool,
+    pub after: Duration,
+}
+
+impl HealthMonitorConfig {
+    /// Defaults, with the staleness threshold from
+    /// `VECTORIZE_AGENT_STALE_AFTER_SECS`, the concurrency cap from
+    /// `VECTORIZE_HEALTH_MAX_CONCURRENT_CHECKS` and auto-deregistration from
+    /// `VECTORIZE_AGENT_AUTO_DEREGISTER` / `VECTORIZE_AGENT_DEREGISTER_AFTER_SECS`
+    /// if set
+    pub fn from_env() -> Self {
+        let stale_after_secs = std::env::var("VECTORIZE_AGENT_STALE_AFTER_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs: &u64| secs > 0)
+            .unwrap_or(DEFAULT_STALE_AFTER_SECS);
+        let max_concurrent_checks = std::env::var("VECTORIZE_HEALTH_MAX_CONCURRENT_CHECKS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_CHECKS);
+        let auto_deregister = std::env::var("VECTORIZE_AGENT_AUTO_DEREGISTER")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(false);
+        let deregister_after_secs = std::env::var("VECTORIZE_AGENT_DEREGISTER_AFTER_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs: &u64| secs > 0)
+            .unwrap_or(DEFAULT_DEREGISTER_AFTER_SECS);
+        Self {
+            stale_after_secs,
+            max_concurrent_checks,
+            auto_deregister,
+            deregister_after_secs,
+            ..Self::default()
         }
     }
+    
+    /// Deregistration policy for agents in `group`, with its overrides applied
+    pub fn deregister_policy(&self, group: Option<&WorkerGroup>) -> DeregisterPolicy {
+        let enabled = group
+            .and_then(|g| g.auto_deregister)
+            .unwrap_or(self.auto_deregister);
+        let after_secs = group
+            .and_then(|g| g.deregister_after_secs)
+            .and_then(|secs| u64::try_from(secs).ok())
+            .filter(|&secs| secs > 0)
+            .unwrap_or(self.deregister_after_secs);
+        DeregisterPolicy { enabled, after: Duration::from_secs(after_secs) }
+    }
+}
+
+/// Whether an agent last seen at `last_seen` is stale at `now`
+///
+/// Timestamps are the database's `YYYY-MM-DD HH:MM:SS` (UTC). Agents never
+/// seen count from `registered_at`; unparseable timestamps are not stale.
+pub fn is_stale(
+    last_seen: Option<&str>,
+    registered_at: &str,
+    now: chrono::DateTime<chrono::Utc>,
+    stale_after: Duration,
+) -> bool {
+    let seen = last_seen.unwrap_or(registered_at);
+    match chrono::NaiveDateTime::parse_from_str(seen, "%Y-%m-%d %H:%M:%S") {
+        Ok(seen) => now.signed_duration_since(seen.and_utc()).to_std().is_ok_and(|age| age > stale_after),
+        Err(_) => false,
+    }
 }
 
 /// Health monitoring service
 pub struct HealthMonitor {
     db: Database,
-    http_client: reqwest::Client,
+    /// HTTP clients per agent TLS configuration, reused across check runs
+    clients: AgentClients,
     config: HealthMonitorConfig,
     running: Arc<RwLock<bool>>,
     latest_results: Arc<RwLock<Vec<HealthCheckResult>>>,
+    /// Serializes check runs so a scheduled run can't overwrite fresher manual results
+    check_lock: Mutex<()>,
+    /// Receives agent status changes when attached
+    live_events: Option<Arc<LiveEventBus>>,
 }
 
 impl HealthMonitor {
     /// Create a new health monitor
     pub fn new(db: Database, config: HealthMonitorConfig) -> Self {
+        let timeout = Duration::from_secs(config.timeout_secs);
         let http_client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
+            .timeout(timeout)
             .build()
             .expect("Failed to create HTTP client");
         
         Self {
             db,
-            http_client,
+            clients: AgentClients::new(http_client).with_timeout(timeout),
             config,
             running: Arc::new(RwLock::new(false)),
             latest_results: Arc::new(RwLock::new(Vec::new())),
+            check_lock: Mutex::new(()),
+            live_events: None,
         }
     }
     
+    /// Decrypt agents' TLS client keys with `cipher`
+    pub fn with_cipher(mut self, cipher: Option<SecretCipher>) -> Self {
+        self.clients = self.clients.with_cipher(cipher);
+        self
+    }
+    
+    /// Publish agent status changes to the live-update bus
+    pub fn with_live_events(mut self, live_events: Arc<LiveEventBus>) -> Self {
+        self.live_events = Some(live_events);
+        self
+    }
+    
     /// Start the background health monitoring task
     pub fn start(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
         let monitor = self.clone();
@@ -131,6 +277,42 @@ impl HealthMonitor {
         })
     }
     
+    /// Start the background task marking stale agents unreachable
+    ///
+    /// Runs on its own interval so a slow or stuck check run can't hold it
+    /// up, and stops with the monitor.
+    pub fn start_stale_sweep(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let monitor = self.clone();
+        
+        tokio::spawn(async move {
+            *monitor.running.write().await = true;
+            
+            info!(
+                "Starting stale agent sweep (threshold: {}s)",
+                monitor.config.stale_after_secs
+            );
+            if monitor.config.auto_deregister {
+                info!(
+                    "Deregistering agents unreachable for over {}s",
+                    monitor.config.deregister_after_secs
+                );
+            }
+            
+            let mut interval = tokio::time::interval(Duration::from_secs(monitor.config.stale_sweep_interval_secs));
+            
+            loop {
+                interval.tick().await;
+                
+                if !*monitor.running.read().await {
+                    break;
+                }
+                
+                monitor.mark_stale_agents().await;
+                monitor.deregister_unreachable_agents().await;
+            }
+        })
+    }
+    
     /// Stop the health monitor
     pub async fn stop(&self) {
         let mut running = self.running.write().await;
@@ -138,36 +320,90 @@ impl HealthMonitor {
     }
     
     /// Check health of all registered agents
-    pub async fn check_all_agents(&self) {
+    ///
+    /// Replaces the stored latest results with this run's.
+    pub async fn check_all_agents(&self) -> Vec<HealthCheckResult> {
         debug!("Running health check for all agents");
         
-        let pool = self.db.pool();
+        let _guard = self.check_lock.lock().await;
         
         // Get all agents
-        let agents = match AgentRepository::list(pool).await {
+        let agents = match AgentRepository::list(self.db.pool()).await {
             Ok(agents) => agents,
             Err(e) => {
                 error!("Failed to list agents for health check: {}", e);
-                return;
+                return Vec::new();
             }
         };
         
         if agents.is_empty() {
             debug!("No agents to check");
-            return;
+            return Vec::new();
         }
         
-        let mut results = Vec::new();
+        let results = self.run_checks(agents).await;
+        
+        // Store latest results
+        {
+            let mut latest = self.latest_results.write().await;
+            *latest = results.clone();
+        }
         
-        // Check each agent in parallel
-        let mut handles = Vec::new();
+        debug!("Health check completed");
+        results
+    }
+    
+    /// Check health of specific agents now
+    ///
+    /// Unknown agent ids are skipped. Results for these agents
+    /// replace their entries in the stored latest results; other agents'
+    /// entries are kept.
+    pub async fn check_agents(&self, agent_ids: &[String]) -> Vec<HealthCheckResult> {
+        debug!("Running health check for {} agent(s)", agent_ids.len());
         
-        for agent in agents {
-            let client = self.http_client.clone();
+        let _guard = self.check_lock.lock().await;
+        
+        let mut agents = Vec::new();
+        for id in agent_ids {
+            match AgentRepository::get_by_id(self.db.pool(), id).await {
+                Ok(Some(agent)) => agents.push(agent),
+                Ok(None) => debug!("Skipping health check for unknown agent {}", id),
+                Err(e) => error!("Failed to get agent {} for health check: {}", id, e),
+            }
+        }
+        
+        let results = self.run_checks(agents).await;
+        
+        {
+            let mut latest = self.latest_results.write().await;
+            latest.retain(|existing| !results.iter().any(|r| r.agent_id == existing.agent_id));
+            latest.extend(results.iter().cloned());
+        }
+        
+        results
+    }
+    
+    /// Check agents in parallel, recording each result and updating agent status
+    ///
+    /// At most `max_concurrent_checks` checks run at once; the next agent's
+    /// check starts as soon as one finishes.
+    async fn run_checks(&self, agents: Vec<Agent>) -> Vec<HealthCheckResult> {
+        let pool = self.db.pool().clone();
+        let limit = self.config.max_concurrent_checks.max(1);
+        
+        // Each check is spawned only once a slot is free
+        let checks = futures::stream::iter(agents).map(|agent| {
+            let client = self.clients.for_agent(&agent);
             let pool = pool.clone();
+            let live_events = self.live_events.clone();
+            let span = info_span!("health_check", agent_id = %agent.id);
             
-            let handle = tokio::spawn(async move {
-                let result = check_agent_health(&client, &agent.id, &agent.name, &agent.url).await;
+            tokio::spawn(async move {
+                // One ID per check, shared by its health and GraphQL calls
+                let result = request_id::scope(
+                    request_id::generate(),
+                    check_agent_health(&client, &agent.id, &agent.name, &agent.url),
+                ).await;
                 
                 // Record the health check
                 let _ = AgentRepository::record_health_check(
@@ -187,26 +423,133 @@ impl HealthMonitor {
                     result.vector_version.as_deref(),
                 ).await;
                 
+                if agent.status != status {
+                    if let Some(live_events) = &live_events {
+                        live_events.publish(LiveEvent::AgentStatusChanged {
+                            agent_id: agent.id.clone(),
+                            agent_name: agent.name.clone(),
+                            previous_status: agent.status.clone(),
+                            status: status.to_string(),
+                            error: result.error.clone(),
+                        });
+                    }
+                }
+                
                 result
-            });
-            
-            handles.push(handle);
-        }
+            }.instrument(span))
+        });
         
-        // Wait for all checks to complete
-        for handle in handles {
-            if let Ok(result) = handle.await {
-                results.push(result);
+        checks
+            .buffer_unordered(limit)
+            .filter_map(|handle| async move { handle.ok() })
+            .collect()
+            .await
+    }
+    
+    /// Mark agents without a recent successful poll or heartbeat unreachable
+    ///
+    /// Returns the ids of the agents marked.
+    pub async fn mark_stale_agents(&self) -> Vec<String> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::seconds(self.config.stale_after_secs as i64);
+        let cutoff = cutoff.format("%Y-%m-%d %H:%M:%S").to_string();
+        
+        let stale = match AgentRepository::mark_unreachable_since(self.db.pool(), &cutoff).await {
+            Ok(stale) => stale,
+            Err(e) => {
+                error!("Failed to mark stale agents: {}", e);
+                return Vec::new();
+            }
+        };
+        
+        for agent in &stale {
+            warn!(
+                "Agent '{}' not seen since {}; marking unreachable",
+                agent.name,
+                agent.last_seen.as_deref().unwrap_or("registration")
+            );
+            if let Some(live_events) = &self.live_events {
+                live_events.publish(LiveEvent::AgentStatusChanged {
+                    agent_id: agent.id.clone(),
+                    agent_name: agent.name.clone(),
+                    previous_status: agent.status.clone(),
+                    status: "unreachable".to_string(),
+                    error: Some("No heartbeat or successful health check".to_string()),
+                });
             }
         }
         
-        // Store latest results
-        {
-            let mut latest = self.latest_results.write().await;
-            *latest = results;
+        stale.into_iter().map(|agent| agent.id).collect()
+    }
+    
+    /// Deregister agents unreachable for longer than their policy allows
+    ///
+    /// An agent qualifies once it hasn't been seen for the TTL and its last
+    /// `failure_threshold` health checks all failed, so agents the monitor
+    /// hasn't been able to poll (e.g. after a control plane outage) are
+    /// kept. Returns the ids of the agents deregistered.
+    pub async fn deregister_unreachable_agents(&self) -> Vec<String> {
+        let pool = self.db.pool();
+        
+        let agents = match AgentRepository::list(pool).await {
+            Ok(agents) => agents,
+            Err(e) => {
+                error!("Failed to list agents for deregistration: {}", e);
+                return Vec::new();
+            }
+        };
+        let groups: HashMap<String, WorkerGroup> = match WorkerGroupRepository::list(pool).await {
+            Ok(groups) => groups.into_iter().map(|g| (g.id.clone(), g)).collect(),
+            Err(e) => {
+                error!("Failed to list worker groups for deregistration: {}", e);
+                return Vec::new();
+            }
+        };
+        
+        let now = chrono::Utc::now();
+        let failures_needed = self.config.failure_threshold.max(1) as usize;
+        let mut deregistered = Vec::new();
+        
+        for agent in agents {
+            let group = agent.group_id.as_ref().and_then(|id| groups.get(id));
+            let policy = self.config.deregister_policy(group);
+            if !policy.enabled || !is_stale(agent.last_seen.as_deref(), &agent.registered_at, now, policy.after) {
+                continue;
+            }
+            
+            let checks = match AgentRepository::get_health_checks(pool, &agent.id, failures_needed as i64).await {
+                Ok(checks) => checks,
+                Err(e) => {
+                    error!("Failed to get health checks for agent {}: {}", agent.id, e);
+                    continue;
+                }
+            };
+            if checks.len() < failures_needed || checks.iter().any(|c| c.healthy) {
+                continue;
+            }
+            
+            let cutoff = now - chrono::Duration::seconds(policy.after.as_secs() as i64);
+            let cutoff = cutoff.format("%Y-%m-%d %H:%M:%S").to_string();
+            match AgentRepository::deregister_unseen_since(pool, &agent.id, &cutoff).await {
+                Ok(true) => {
+                    warn!(
+                        "Agent '{}' ({}) unreachable since {}; deregistering",
+                        agent.name,
+                        agent.url,
+                        agent.last_seen.as_deref().unwrap_or("registration")
+                    );
+                    deregistered.push(agent.id);
+                }
+                Ok(false) => {}
+                Err(e) => error!("Failed to deregister agent {}: {}", agent.id, e),
+            }
         }
         
-        debug!("Health check completed");
+        deregistered
+    }
+    
+    /// Staleness threshold
+    pub fn stale_after(&self) -> Duration {
+        Duration::from_secs(self.config.stale_after_secs)
     }
     
     /// Get the latest health check results
@@ -222,12 +565,24 @@ impl HealthMonitor {
         let healthy = results.iter().filter(|r| r.healthy).count() as u32;
         let unhealthy = total - healthy;
         
+        let now = chrono::Utc::now();
+        let stale = match AgentRepository::list(self.db.pool()).await {
+            Ok(agents) => agents.iter()
+                .filter(|a| is_stale(a.last_seen.as_deref(), &a.registered_at, now, self.stale_after()))
+                .count() as u32,
+            Err(e) => {
+                error!("Failed to list agents for health summary: {}", e);
+                0
+            }
+        };
+        
         HealthSummary {
             total_agents: total,
             healthy_agents: healthy,
             unhealthy_agents: unhealthy,
             unreachable_agents: results.iter().filter(|r| r.error.is_some()).count() as u32,
-            last_check: chrono::Utc::now().to_rfc3339(),
+            stale_agents: stale,
+            last_check: now.to_rfc3339(),
         }
     }
 }
@@ -243,7 +598,7 @@ async fn check_agent_health(
     
     let start = std::time::Instant::now();
     
-    match client.get(&health_url).send().await {
+    match client.get(&health_url).with_request_id().send().await {
         Ok(response) => {
             let latency = start.elapsed().as_millis() as i64;
             
@@ -321,6 +676,7 @@ async fn get_agent_info(
     let body = serde_json::json!({ "query": query });
     
     match client.post(&graphql_url)
+        .with_request_id()
         .header("Content-Type", "application/json")
         .json(&body)
         .send()
@@ -364,6 +720,7 @@ pub async fn fetch_agent_metrics(
             componentInfo: components {
                 edges {
                     node {
+                        __typename
                         componentId
                         componentType
                         ... on Source {
@@ -402,44 +759,19 @@ pub async fn fetch_agent_metrics(
     let body = serde_json::json!({ "query": query });
     
     match client.post(&graphql_url)
+        .with_request_id()
         .header("Content-Type", "application/json")
         .json(&body)
         .send()
         .await
     {
-        Ok(response) => {
-            if let Ok(json) = response.json::<serde_json::Value>().await {
-                let uptime = json["data"]["meta"]["uptimeSeconds"].as_u64();
-                
-                // Aggregate metrics from all components
-                let mut total_events: u64 = 0;
-                let mut total_bytes: u64 = 0;
-                
-                if let Some(edges) = json["data"]["componentInfo"]["edges"].as_array() {
-                    for edge in edges {
-                        if let Some(sent) = edge["node"]["metrics"]["sentEventsTotal"]["sentEventsTotal"].as_u64() {
-                            total_events += sent;
-                        }
-                        if let Some(bytes) = edge["node"]["metrics"]["sentBytesTotal"]["sentBytesTotal"].as_u64() {
-                            total_bytes += bytes;
-                        }
-                    }
-                }
-                
-                AgentMetrics {
-                    agent_id: agent_id.to_string(),
-                    events_processed_total: Some(total_events),
-                    bytes_processed_total: Some(total_bytes),
-                    uptime_seconds: uptime,
-                    ..Default::default()
-                }
-            } else {
-                AgentMetrics {
-                    agent_id: agent_id.to_string(),
-                    ..Default::default()
-                }
-            }
-        }
+        Ok(response) => match response.json::<serde_json::Value>().await {
+            Ok(json) => parse_agent_metrics(agent_id, &json),
+            Err(_) => AgentMetrics {
+                agent_id: agent_id.to_string(),
+                ..Default::default()
+            },
+        },
         Err(_) => AgentMetrics {
             agent_id: agent_id.to_string(),
             ..Default::default()
@@ -447,16 +779,365 @@ pub async fn fetch_agent_metrics(
     }
 }
 
+/// Build a metrics snapshot from the GraphQL response of `fetch_agent_metrics`
+///
+/// Totals are summed over all components; components without counters yet
+/// are listed with `None`.
+pub fn parse_agent_metrics(agent_id: &str, json: &serde_json::Value) -> AgentMetrics {
+    // Vector reports counters as floats
+    let counter = |value: &serde_json::Value| value.as_u64().or_else(|| value.as_f64().map(|v| v as u64));
+    
+    let components: Vec<ComponentMetrics> = json["data"]["componentInfo"]["edges"]
+        .as_array()
+        .map(|edges| edges.iter().filter_map(|edge| {
+            let node = &edge["node"];
+            Some(ComponentMetrics {
+                component_id: node["componentId"].as_str()?.to_string(),
+                kind: node["__typename"].as_str().unwrap_or("unknown").to_lowercase(),
+                component_type: node["componentType"].as_str().map(str::to_string),
+                sent_events: counter(&node["metrics"]["sentEventsTotal"]["sentEventsTotal"]),
+                sent_bytes: counter(&node["metrics"]["sentBytesTotal"]["sentBytesTotal"]),
+            })
+        }).collect())
+        .unwrap_or_default();
+    
+    AgentMetrics {
+        agent_id: agent_id.to_string(),
+        events_processed_total: Some(components.iter().filter_map(|c| c.sent_events).sum()),
+        bytes_processed_total: Some(components.iter().filter_map(|c| c.sent_bytes).sum()),
+        uptime_seconds: counter(&json["data"]["meta"]["uptimeSeconds"]),
+        components,
+        ..Default::default()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_parse_agent_metrics_per_component() {
+        let response = serde_json::json!({
+            "data": {
+                "meta": { "uptimeSeconds": 3600.0 },
+                "componentInfo": {
+                    "edges": [
+                        { "node": {
+                            "__typename": "Source", "componentId": "in", "componentType": "kafka",
+                            "metrics": { "sentEventsTotal": { "sentEventsTotal": 1500.0 }, "sentBytesTotal": { "sentBytesTotal": 90000.0 } }
+                        } },
+                        { "node": {
+                            "__typename": "Transform", "componentId": "parse", "componentType": "remap",
+                            "metrics": { "sentEventsTotal": { "sentEventsTotal": 1400 } }
+                        } },
+                        { "node": {
+                            "__typename": "Sink", "componentId": "out", "componentType": "elasticsearch",
+                            "metrics": { "sentEventsTotal": null, "sentBytesTotal": null }
+                        } }
+                    ]
+                }
+            }
+        });
+        
+        let metrics = parse_agent_metrics("agent-1", &response);
+        assert_eq!(metrics.uptime_seconds, Some(3600));
+        assert_eq!(metrics.events_processed_total, Some(2900));
+        assert_eq!(metrics.bytes_processed_total, Some(90000));
+        
+        assert_eq!(metrics.components.len(), 3);
+        assert_eq!(metrics.components[0], ComponentMetrics {
+            component_id: "in".to_string(),
+            kind: "source".to_string(),
+            component_type: Some("kafka".to_string()),
+            sent_events: Some(1500),
+            sent_bytes: Some(90000),
+        });
+        assert_eq!(metrics.components[1].kind, "transform");
+        assert_eq!(metrics.components[1].sent_events, Some(1400));
+        assert_eq!(metrics.components[1].sent_bytes, None);
+        // A stalled sink that hasn't sent anything yet
+        assert_eq!((metrics.components[2].sent_events, metrics.components[2].sent_bytes), (None, None));
+        
+        let empty = parse_agent_metrics("agent-1", &serde_json::json!({ "errors": [] }));
+        assert!(empty.components.is_empty());
+        assert_eq!(empty.events_processed_total, Some(0));
+    }
+    
     #[test]
     fn test_health_monitor_config_default() {
         let config = HealthMonitorConfig::default();
         assert_eq!(config.check_interval_secs, 30);
         assert_eq!(config.timeout_secs, 10);
         assert_eq!(config.failure_threshold, 3);
+        assert_eq!(config.max_concurrent_checks, DEFAULT_MAX_CONCURRENT_CHECKS);
+    }
+    
+    /// Serve a `/health` endpoint that always succeeds
+    async fn spawn_mock_agent() -> String {
+        let app = axum::Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        url
+    }
+    
+    #[tokio::test]
+    async fn test_checks_respect_concurrency_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        // A slow `/health` that records how many requests overlap; agent
+        // URLs must be unique, so each agent gets its own path prefix
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let app = axum::Router::new().route("/:agent/health", axum::routing::get({
+            let (in_flight, peak) = (in_flight.clone(), peak.clone());
+            move || async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                "ok"
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let config = HealthMonitorConfig { max_concurrent_checks: 3, ..HealthMonitorConfig::default() };
+        let monitor = HealthMonitor::new(db.clone(), config);
+        for i in 0..10 {
+            AgentRepository::create(db.pool(), &format!("agent-{}", i), &format!("{}/agent-{}", url, i), None).await.unwrap();
+        }
+        
+        let results = monitor.check_all_agents().await;
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|r| r.healthy));
+        let peak = peak.load(Ordering::SeqCst);
+        assert!(peak <= 3, "{} checks ran at once", peak);
+        assert!(peak > 1, "checks did not run in parallel");
+    }
+    
+    #[tokio::test]
+    async fn test_check_agents_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let monitor = HealthMonitor::new(db.clone(), HealthMonitorConfig::default());
+        
+        let url = spawn_mock_agent().await;
+        let up = AgentRepository::create(db.pool(), "up", &url, None).await.unwrap();
+        let down = AgentRepository::create(db.pool(), "down", "http://127.0.0.1:1", None).await.unwrap();
+        
+        let results = monitor.check_agents(&[up.id.clone(), "missing".to_string()]).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].agent_id, up.id);
+        assert!(results[0].healthy);
+        
+        let agent = AgentRepository::get_by_id(db.pool(), &up.id).await.unwrap().unwrap();
+        assert_eq!(agent.status, "healthy");
+        assert_eq!(AgentRepository::get_health_checks(db.pool(), &up.id, 10).await.unwrap().len(), 1);
+        
+        assert_eq!(monitor.check_all_agents().await.len(), 2);
+        
+        // A targeted run only replaces its own agents' latest results
+        let results = monitor.check_agents(&[down.id.clone()]).await;
+        assert!(!results[0].healthy);
+        let latest = monitor.get_latest_results().await;
+        assert_eq!(latest.len(), 2);
+        assert!(latest.iter().any(|r| r.agent_id == up.id && r.healthy));
+    }
+    
+    #[tokio::test]
+    async fn test_status_changes_are_published() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let live = Arc::new(LiveEventBus::new());
+        let monitor = HealthMonitor::new(db.clone(), HealthMonitorConfig::default())
+            .with_live_events(live.clone());
+        let mut rx = live.subscribe();
+        
+        let url = spawn_mock_agent().await;
+        let agent = AgentRepository::create(db.pool(), "up", &url, None).await.unwrap();
+        
+        monitor.check_agents(&[agent.id.clone()]).await;
+        match rx.try_recv().unwrap() {
+            LiveEvent::AgentStatusChanged { agent_id, status, .. } => {
+                assert_eq!(agent_id, agent.id);
+                assert_eq!(status, "healthy");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        
+        // An unchanged status is not re-published
+        monitor.check_agents(&[agent.id.clone()]).await;
+        assert!(rx.try_recv().is_err());
+    }
+    
+    #[test]
+    fn test_is_stale() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-02-03T12:00:00Z").unwrap().to_utc();
+        let threshold = Duration::from_secs(90);
+        
+        assert!(!is_stale(Some("2026-02-03 11:59:00"), "2026-02-01 00:00:00", now, threshold));
+        assert!(is_stale(Some("2026-02-03 11:58:00"), "2026-02-01 00:00:00", now, threshold));
+        // Never seen: counts from registration
+        assert!(is_stale(None, "2026-02-01 00:00:00", now, threshold));
+        assert!(!is_stale(None, "2026-02-03 11:59:30", now, threshold));
+        assert!(!is_stale(Some("yesterday"), "2026-02-01 00:00:00", now, threshold));
+    }
+    
+    #[tokio::test]
+    async fn test_stale_agents_marked_unreachable() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let live = Arc::new(LiveEventBus::new());
+        let monitor = HealthMonitor::new(db.clone(), HealthMonitorConfig::default())
+            .with_live_events(live.clone());
+        let mut rx = live.subscribe();
+        
+        let old = AgentRepository::create(db.pool(), "old", "http://127.0.0.1:1", None).await.unwrap();
+        let fresh = AgentRepository::create(db.pool(), "fresh", "http://127.0.0.1:2", None).await.unwrap();
+        AgentRepository::update_status(db.pool(), &old.id, "healthy", None).await.unwrap();
+        AgentRepository::heartbeat(db.pool(), &fresh.id, None).await.unwrap();
+        sqlx::query("UPDATE agents SET last_seen = '2020-01-01 00:00:00' WHERE id = $1")
+            .bind(&old.id)
+            .execute(db.pool())
+            .await
+            .unwrap();
+        
+        // A failed poll doesn't count as seeing the agent
+        AgentRepository::update_status(db.pool(), &old.id, "unhealthy", None).await.unwrap();
+        assert_eq!(monitor.get_summary().await.stale_agents, 1);
+        
+        assert_eq!(monitor.mark_stale_agents().await, vec![old.id.clone()]);
+        let agent = AgentRepository::get_by_id(db.pool(), &old.id).await.unwrap().unwrap();
+        assert_eq!(agent.status, "unreachable");
+        assert_eq!(AgentRepository::get_by_id(db.pool(), &fresh.id).await.unwrap().unwrap().status, "unknown");
+        match rx.try_recv().unwrap() {
+            LiveEvent::AgentStatusChanged { agent_id, previous_status, status, .. } => {
+                assert_eq!(agent_id, old.id);
+                assert_eq!(previous_status, "unhealthy");
+                assert_eq!(status, "unreachable");
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        
+        // Already unreachable agents aren't marked again
+        assert!(monitor.mark_stale_agents().await.is_empty());
+        
+        // A heartbeat brings it back
+        let agent = AgentRepository::heartbeat(db.pool(), &old.id, None).await.unwrap().unwrap();
+        assert_eq!(agent.status, "unknown");
+        assert_eq!(monitor.get_summary().await.stale_agents, 0);
+    }
+    
+    /// Pretend an agent was last seen `minutes` ago
+    async fn set_last_seen_minutes_ago(db: &Database, agent_id: &str, minutes: i64) {
+        let seen = chrono::Utc::now() - chrono::Duration::minutes(minutes);
+        sqlx::query("UPDATE agents SET last_seen = $1 WHERE id = $2")
+            .bind(seen.format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(agent_id)
+            .execute(db.pool())
+            .await
+            .unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_unreachable_agents_deregistered_after_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let config = HealthMonitorConfig {
+            auto_deregister: true,
+            deregister_after_secs: 3600,
+            ..HealthMonitorConfig::default()
+        };
+        let monitor = HealthMonitor::new(db.clone(), config);
+        
+        let gone = AgentRepository::create(db.pool(), "gone", "http://127.0.0.1:1", None).await.unwrap();
+        let pinned = AgentRepository::create(db.pool(), "pinned", "http://127.0.0.1:2", None).await.unwrap();
+        let group = WorkerGroupRepository::create(db.pool(), "static", None, None).await.unwrap();
+        AgentRepository::set_group(db.pool(), &pinned.id, Some(&group.id)).await.unwrap();
+        WorkerGroupRepository::set_auto_deregister(db.pool(), &group.id, Some(false), None).await.unwrap();
+        
+        // Down for two hours, and every poll since has failed
+        for agent in [&gone, &pinned] {
+            set_last_seen_minutes_ago(&db, &agent.id, 120).await;
+        }
+        for _ in 0..3 {
+            assert!(monitor.check_all_agents().await.iter().all(|r| !r.healthy));
+        }
+        
+        // Off by default
+        let disabled = HealthMonitor::new(db.clone(), HealthMonitorConfig::default());
+        assert!(disabled.deregister_unreachable_agents().await.is_empty());
+        
+        assert_eq!(monitor.deregister_unreachable_agents().await, vec![gone.id.clone()]);
+        assert!(AgentRepository::get_by_id(db.pool(), &gone.id).await.unwrap().is_none());
+        let deleted = AgentRepository::list_including_deleted(db.pool()).await.unwrap();
+        assert!(deleted.iter().any(|a| a.id == gone.id && a.deleted_at.is_some()));
+        // The group opted out
+        assert!(AgentRepository::get_by_id(db.pool(), &pinned.id).await.unwrap().is_some());
+        
+        // A group can also opt in with its own TTL
+        WorkerGroupRepository::set_auto_deregister(db.pool(), &group.id, None, Some(3 * 3600)).await.unwrap();
+        assert!(monitor.deregister_unreachable_agents().await.is_empty());
+        WorkerGroupRepository::set_auto_deregister(db.pool(), &group.id, None, Some(1800)).await.unwrap();
+        assert_eq!(monitor.deregister_unreachable_agents().await, vec![pinned.id.clone()]);
+    }
+    
+    #[tokio::test]
+    async fn test_agent_recovering_before_ttl_is_kept() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        
+        // A `/health` that fails until the agent comes back
+        let up = Arc::new(AtomicBool::new(false));
+        let app = axum::Router::new().route("/health", axum::routing::get({
+            let up = up.clone();
+            move || async move {
+                if up.load(Ordering::SeqCst) {
+                    axum::http::StatusCode::OK
+                } else {
+                    axum::http::StatusCode::SERVICE_UNAVAILABLE
+                }
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(&dir.path().join("test.db")).await.unwrap();
+        let config = HealthMonitorConfig {
+            auto_deregister: true,
+            deregister_after_secs: 3600,
+            ..HealthMonitorConfig::default()
+        };
+        let monitor = HealthMonitor::new(db.clone(), config);
+        let agent = AgentRepository::create(db.pool(), "flaky", &url, None).await.unwrap();
+        
+        // Failing for half an hour: not long enough
+        set_last_seen_minutes_ago(&db, &agent.id, 30).await;
+        for _ in 0..3 {
+            assert!(!monitor.check_all_agents().await[0].healthy);
+        }
+        assert!(monitor.deregister_unreachable_agents().await.is_empty());
+        
+        // It comes back, so the outage ends there
+        up.store(true, Ordering::SeqCst);
+        assert!(monitor.check_all_agents().await[0].healthy);
+        up.store(false, Ordering::SeqCst);
+        for _ in 0..3 {
+            monitor.check_all_agents().await;
+        }
+        assert!(monitor.deregister_unreachable_agents().await.is_empty());
+        assert!(AgentRepository::get_by_id(db.pool(), &agent.id).await.unwrap().is_some());
     }
     
     #[test]
@@ -466,6 +1147,7 @@ mod tests {
             healthy_agents: 3,
             unhealthy_agents: 2,
             unreachable_agents: 1,
+            stale_agents: 0,
             last_check: "2026-02-03T12:00:00Z".to_string(),
         };
         
@@ -0,0 +1,164 @@
+//! Per-component throughput, `vector top` style
+//!
+//! Vector only reports cumulative counters, so rates come from the delta
+//! between two consecutive metrics snapshots of the same agent.
+
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+use super::{AgentMetrics, ComponentMetrics};
+
+/// Default time between snapshots
+pub const DEFAULT_TOP_INTERVAL_SECS: u64 = 2;
+
+/// Bounds for a client-chosen interval
+pub const MIN_TOP_INTERVAL_SECS: u64 = 1;
+pub const MAX_TOP_INTERVAL_SECS: u64 = 60;
+
+/// One row of the throughput table
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopRow {
+    pub component_id: String,
+    /// `source`, `transform` or `sink`
+    pub kind: String,
+    pub component_type: Option<String>,
+    pub sent_events_total: Option<u64>,
+    pub sent_bytes_total: Option<u64>,
+    /// None on the first snapshot, for new components, and after a counter
+    /// reset (e.g. Vector restarted)
+    pub events_per_sec: Option<f64>,
+    pub bytes_per_sec: Option<f64>,
+}
+
+/// A throughput table for one agent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopSnapshot {
+    pub agent_id: String,
+    /// Seconds between the two snapshots the rates come from
+    pub elapsed_secs: Option<f64>,
+    pub components: Vec<TopRow>,
+    pub collected_at: String,
+}
+
+impl TopSnapshot {
+    /// Rates between `previous` and `current`, taken `elapsed` apart
+    ///
+    /// Without a previous snapshot only the totals are filled in. Rows keep
+    /// the order Vector reports components in.
+    pub fn from_metrics(previous: Option<&AgentMetrics>, current: &AgentMetrics, elapsed: Duration) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64();
+        let previous = previous.filter(|_| elapsed_secs > 0.0);
+
+        let components = current.components.iter().map(|component| {
+            let before = previous.and_then(|p| {
+                p.components.iter().find(|c| c.component_id == component.component_id)
+            });
+            TopRow {
+                component_id: component.component_id.clone(),
+                kind: component.kind.clone(),
+                component_type: component.component_type.clone(),
+                sent_events_total: component.sent_events,
+                sent_bytes_total: component.sent_bytes,
+                events_per_sec: rate(before, component, |c| c.sent_events, elapsed_secs),
+                bytes_per_sec: rate(before, component, |c| c.sent_bytes, elapsed_secs),
+            }
+        }).collect();
+
+        Self {
+            agent_id: current.agent_id.clone(),
+            elapsed_secs: previous.map(|_| elapsed_secs),
+            components,
+            collected_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Per-second change of one counter, if both readings exist and it didn't reset
+fn rate(
+    before: Option<&ComponentMetrics>,
+    after: &ComponentMetrics,
+    counter: impl Fn(&ComponentMetrics) -> Option<u64>,
+    elapsed_secs: f64,
+) -> Option<f64> {
+    let before = counter(before?)?;
+    let after = counter(after)?;
+    let delta = after.checked_sub(before)?;
+    Some(delta as f64 / elapsed_secs)
+}
+
+/// Clamp a requested interval to the allowed range
+pub fn top_interval(requested: Option<u64>) -> Duration {
+    let secs = requested
+        .unwrap_or(DEFAULT_TOP_INTERVAL_SECS)
+        .clamp(MIN_TOP_INTERVAL_SECS, MAX_TOP_INTERVAL_SECS);
+    Duration::from_secs(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(id: &str, events: Option<u64>, bytes: Option<u64>) -> ComponentMetrics {
+        ComponentMetrics {
+            component_id: id.to_string(),
+            kind: "source".to_string(),
+            component_type: Some("kafka".to_string()),
+            sent_events: events,
+            sent_bytes: bytes,
+        }
+    }
+
+    fn metrics(components: Vec<ComponentMetrics>) -> AgentMetrics {
+        AgentMetrics {
+            agent_id: "agent-1".to_string(),
+            components,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rates_from_deltas() {
+        let previous = metrics(vec![
+            component("in", Some(1000), Some(50_000)),
+            component("parse", Some(900), None),
+            component("restarted", Some(5000), None),
+        ]);
+        let current = metrics(vec![
+            component("in", Some(1500), Some(60_000)),
+            component("parse", Some(1300), None),
+            component("restarted", Some(20), None),
+            component("new", Some(10), None),
+        ]);
+
+        let snapshot = TopSnapshot::from_metrics(Some(&previous), &current, Duration::from_secs(2));
+        assert_eq!(snapshot.elapsed_secs, Some(2.0));
+
+        let rows: Vec<(&str, Option<f64>, Option<f64>)> = snapshot.components.iter()
+            .map(|r| (r.component_id.as_str(), r.events_per_sec, r.bytes_per_sec))
+            .collect();
+        assert_eq!(rows, vec![
+            ("in", Some(250.0), Some(5000.0)),
+            ("parse", Some(200.0), None),
+            ("restarted", None, None),
+            ("new", None, None),
+        ]);
+        assert_eq!(snapshot.components[0].sent_events_total, Some(1500));
+    }
+
+    #[test]
+    fn test_first_snapshot_has_totals_only() {
+        let current = metrics(vec![component("in", Some(1500), Some(60_000))]);
+
+        let snapshot = TopSnapshot::from_metrics(None, &current, Duration::ZERO);
+        assert_eq!(snapshot.elapsed_secs, None);
+        assert_eq!(snapshot.components[0].sent_events_total, Some(1500));
+        assert_eq!(snapshot.components[0].events_per_sec, None);
+    }
+
+    #[test]
+    fn test_interval_is_clamped() {
+        assert_eq!(top_interval(None), Duration::from_secs(DEFAULT_TOP_INTERVAL_SECS));
+        assert_eq!(top_interval(Some(0)), Duration::from_secs(MIN_TOP_INTERVAL_SECS));
+        assert_eq!(top_interval(Some(3600)), Duration::from_secs(MAX_TOP_INTERVAL_SECS));
+    }
+}
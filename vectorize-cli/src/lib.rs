@@ -2,16 +2,23 @@
 //!
 //! Core modules for the Vectorize control plane.
 
+pub mod agent_clients;
 pub mod alerts;
 pub mod api;
+pub mod crypto;
 pub mod db;
 pub mod deployment;
 pub mod git_store;
 pub mod health;
+pub mod live;
+pub mod logging;
+pub mod rate_limit;
 pub mod rbac;
+pub mod request_id;
 pub mod server;
 pub mod sso;
 pub mod tap;
+pub mod telemetry;
 pub mod validation;
 pub mod vector_manager;
 
@@ -0,0 +1,150 @@
+//! Live Update Events
+//!
+//! A single broadcast channel carrying agent status changes and deployment
+//! progress for the whole control plane. The health monitor and deployment
+//! executor publish to it, and every `/api/v1/ws` connection subscribes so
+//! dashboards get pushed updates instead of polling the agent list.
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::deployment::DeploymentEvent;
+
+/// Events buffered before slow subscribers start lagging
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// An event pushed to live-update subscribers
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    /// An agent's health status changed
+    AgentStatusChanged {
+        agent_id: String,
+        agent_name: String,
+        /// Status before the check
+        previous_status: String,
+        /// Status after the check (`healthy` or `unhealthy`), or `unreachable`
+        /// once the agent has gone stale
+        status: String,
+        error: Option<String>,
+    },
+    /// Progress for a deployment
+    Deployment {
+        deployment_id: String,
+        event: DeploymentEvent,
+    },
+}
+
+/// Broadcast channel fanning live events out to every subscriber
+#[derive(Debug)]
+pub struct LiveEventBus {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl Default for LiveEventBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl LiveEventBus {
+    /// Create a new event bus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to all live events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publish an event to every current subscriber
+    ///
+    /// Events published while nobody is subscribed are dropped.
+    pub fn publish(&self, event: LiveEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Number of connected subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_changed(agent_id: &str) -> LiveEvent {
+        LiveEvent::AgentStatusChanged {
+            agent_id: agent_id.to_string(),
+            agent_name: format!("{}-name", agent_id),
+            previous_status: "healthy".to_string(),
+            status: "unhealthy".to_string(),
+            error: Some("connection refused".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_every_subscriber_receives_every_event() {
+        let bus = LiveEventBus::new();
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        assert_eq!(bus.subscriber_count(), 2);
+
+        let deployment = LiveEvent::Deployment {
+            deployment_id: "dep-1".to_string(),
+            event: DeploymentEvent::AgentStarted { agent_id: "a1".to_string() },
+        };
+        bus.publish(status_changed("a1"));
+        bus.publish(deployment.clone());
+
+        for rx in [&mut first, &mut second] {
+            assert_eq!(rx.recv().await.unwrap(), status_changed("a1"));
+            assert_eq!(rx.recv().await.unwrap(), deployment);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_late_subscriber_only_sees_new_events() {
+        let bus = LiveEventBus::new();
+
+        // Nobody is listening yet; this is dropped
+        bus.publish(status_changed("a1"));
+
+        let mut rx = bus.subscribe();
+        bus.publish(status_changed("a2"));
+
+        assert_eq!(rx.recv().await.unwrap(), status_changed("a2"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_subscriber_does_not_affect_others() {
+        let bus = LiveEventBus::new();
+        let mut kept = bus.subscribe();
+        drop(bus.subscribe());
+        assert_eq!(bus.subscriber_count(), 1);
+
+        bus.publish(status_changed("a1"));
+        assert_eq!(kept.recv().await.unwrap(), status_changed("a1"));
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let json = serde_json::to_value(LiveEvent::Deployment {
+            deployment_id: "dep-1".to_string(),
+            event: DeploymentEvent::Finished { status: "completed".to_string(), error: None },
+        })
+        .unwrap();
+        assert_eq!(json["type"], "deployment");
+        assert_eq!(json["deployment_id"], "dep-1");
+        assert_eq!(json["event"]["type"], "finished");
+
+        let json = serde_json::to_value(status_changed("a1")).unwrap();
+        assert_eq!(json["type"], "agent_status_changed");
+        assert_eq!(json["status"], "unhealthy");
+    }
+}
@@ -0,0 +1,115 @@
+//! Control plane log output
+//!
+//! Text is the default, for local development. JSON writes one object per
+//! line for log aggregation, with `timestamp`, `level`, `target` and
+//! `message`, plus the fields of the enclosing spans: `span` holds the
+//! innermost one and `spans` the whole stack, so a push can be correlated by
+//! both `deployment_id` and `agent_id`.
+
+use tracing::{Level, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Log output format (`--log-format` / `VECTORIZE_LOG_FORMAT`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// JSON lines
+    Json,
+}
+
+/// Build the subscriber for `format`, writing to stdout
+pub fn subscriber(level: Level, format: LogFormat) -> Box<dyn Subscriber + Send + Sync> {
+    subscriber_with_writer(level, format, std::io::stdout)
+}
+
+fn subscriber_with_writer<W>(level: Level, format: LogFormat, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(writer);
+    
+    match format {
+        LogFormat::Text => Box::new(builder.with_target(false).finish()),
+        LogFormat::Json => Box::new(
+            builder
+                .json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(true)
+                .finish(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing::{info, info_span};
+    
+    /// Collects everything the subscriber writes
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+    
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    fn log_push(format: LogFormat) -> String {
+        let capture = Capture::default();
+        let writer = {
+            let capture = capture.clone();
+            move || capture.clone()
+        };
+        
+        let subscriber = subscriber_with_writer(Level::INFO, format, writer);
+        tracing::subscriber::with_default(subscriber, || {
+            let deployment = info_span!("deployment", deployment_id = "dep-1");
+            let _deployment = deployment.enter();
+            let push = info_span!("agent_push", agent_id = "agent-1");
+            let _push = push.enter();
+            info!("Config pushed");
+        });
+        
+        let output = capture.0.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+    
+    #[test]
+    fn test_json_format_lines_carry_span_fields() {
+        let output = log_push(LogFormat::Json);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", output);
+        
+        let line: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], module_path!());
+        assert_eq!(line["message"], "Config pushed");
+        assert_eq!(line["span"]["agent_id"], "agent-1");
+        assert_eq!(line["spans"][0]["deployment_id"], "dep-1");
+        assert_eq!(line["spans"][1]["agent_id"], "agent-1");
+    }
+    
+    #[test]
+    fn test_text_format_is_default() {
+        assert_eq!(LogFormat::default(), LogFormat::Text);
+        
+        let output = log_push(LogFormat::Text);
+        assert!(output.contains("Config pushed"));
+        assert!(output.contains("dep-1"));
+        assert!(serde_json::from_str::<serde_json::Value>(output.trim()).is_err());
+    }
+}
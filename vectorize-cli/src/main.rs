@@ -6,14 +6,15 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 use tracing::{info, Level};
-use tracing_subscriber::FmtSubscriber;
 
 mod agent;
 mod cli;
 
 // Use library modules
 use vectorize::db;
+use vectorize::deployment;
 use vectorize::git_store;
+use vectorize::logging::{self, LogFormat};
 use vectorize::server;
 use vectorize::vector_manager;
 
@@ -50,6 +51,10 @@ struct Cli {
     #[arg(short, long, action = clap::ArgAction::Count, global = true)]
     verbose: u8,
     
+    /// Log output format (json for log aggregation)
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, env = "VECTORIZE_LOG_FORMAT", global = true)]
+    log_format: LogFormat,
+    
     /// Vectorize server URL for CLI commands
     #[arg(long, default_value = "http://localhost:8080", env = "VECTORIZE_URL", global = true)]
     url: String,
@@ -57,6 +62,14 @@ struct Cli {
     /// Username for CLI commands (for audit logging)
     #[arg(long, default_value = "cli-user", env = "VECTORIZE_USER", global = true)]
     user: String,
+    
+    /// Serve Prometheus /metrics on a separate port (defaults to the web UI port)
+    #[arg(long, env = "VECTORIZE_METRICS_PORT", global = true)]
+    metrics_port: Option<u16>,
+    
+    /// Database connection URL (sqlite: or postgres:), defaults to a SQLite file in the data directory
+    #[arg(long, env = "VECTORIZE_DATABASE_URL", global = true)]
+    database_url: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -91,6 +104,10 @@ enum Commands {
         #[arg(long, env = "VECTORIZE_GROUP")]
         group: Option<String>,
         
+        /// Tag to register with, as key=value (repeatable)
+        #[arg(long = "tag", value_parser = cli::parse_tag)]
+        tags: Vec<(String, String)>,
+        
         /// Vector API URL (local Vector instance)
         #[arg(long, default_value = "http://localhost:8686", env = "VECTOR_API_URL")]
         vector_url: String,
@@ -132,6 +149,12 @@ enum Commands {
         command: cli::DeployCommands,
     },
     
+    /// Back up or restore the local database
+    Db {
+        #[command(subcommand)]
+        command: cli::DbCommands,
+    },
+    
     /// Show version information
     Version,
 }
@@ -147,14 +170,10 @@ async fn main() -> anyhow::Result<()> {
         _ => Level::TRACE,
     };
 
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(log_level)
-        .with_target(false)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
+    tracing::subscriber::set_global_default(logging::subscriber(log_level, cli.log_format))?;
 
     // Create CLI client for management commands
-    let cli_client = cli::CliClient::new(&cli.url);
+    let cli_client = cli::CliClient::new(&cli.url, &cli.user);
     
     match cli.command {
         Some(Commands::Start { no_open }) => {
@@ -168,6 +187,7 @@ async fn main() -> anyhow::Result<()> {
             name,
             api_key,
             group,
+            tags,
             vector_url,
             vector_config_path,
             health_interval,
@@ -178,6 +198,7 @@ async fn main() -> anyhow::Result<()> {
                 name,
                 api_key,
                 group,
+                tags,
                 vector_url,
                 vector_config_path,
                 health_interval,
@@ -196,6 +217,9 @@ async fn main() -> anyhow::Result<()> {
         Some(Commands::Deploy { command }) => {
             command.execute(&cli_client, &cli.user).await?;
         }
+        Some(Commands::Db { command }) => {
+            command.execute(&get_data_dir().join("vectorize.db")).await?;
+        }
         Some(Commands::Version) => {
             println!("Vectorize {}", env!("CARGO_PKG_VERSION"));
             println!("Visual Pipeline Builder for Vector");
@@ -216,10 +240,39 @@ async fn start_vectorize(cli: &Cli, open_browser: bool) -> anyhow::Result<()> {
     let data_dir = get_data_dir();
     info!("Data directory: {}", data_dir.display());
     
-    // Initialize database
+    // Initialize database (the lock keeps `vectorize db restore` off a live file)
     let db_path = data_dir.join("vectorize.db");
-    let db = db::Database::new(&db_path).await
-        .map_err(|e| anyhow::anyhow!("Failed to initialize database: {}", e))?;
+    let _db_lock = match &cli.database_url {
+        Some(_) => None,
+        None => Some(db::Database::lock(&db_path)?),
+    };
+    let db_config = db::DatabaseConfig::from_env();
+    let db = match &cli.database_url {
+        Some(url) => db::Database::connect_with_config(url, &db_config).await,
+        None => db::Database::new_with_config(&db_path, &db_config).await,
+    }
+    .map_err(|e| anyhow::anyhow!("Failed to initialize database: {}", e))?;
+    
+    // Headless installs create the first admin from VECTORIZE_BOOTSTRAP_ADMIN
+    if let Some(admin) = vectorize::api::auth::BootstrapAdmin::from_env().map_err(|e| anyhow::anyhow!(e))? {
+        vectorize::api::auth::bootstrap_admin(&db, &admin)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to bootstrap admin user: {}", e))?;
+    }
+    
+    // Stored SSO client secrets and agent TLS keys are encrypted; refuse to start if they can't be read
+    let cipher = vectorize::crypto::SecretCipher::from_env()
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", vectorize::crypto::ENCRYPTION_KEY_ENV, e))?;
+    let sso = vectorize::sso::SsoManager::load(db.pool(), cipher.as_ref())
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    if !sso.list_providers().is_empty() {
+        info!("Loaded {} SSO provider(s)", sso.list_providers().len());
+    }
+    // Agent TLS keys stored before they were encrypted are sealed now
+    vectorize::agent_clients::seal_stored_keys(db.pool(), cipher.as_ref())
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
     
     // Check if this is a fresh installation
     let is_fresh = db.is_fresh().await.unwrap_or(true);
@@ -229,21 +282,29 @@ async fn start_vectorize(cli: &Cli, open_browser: bool) -> anyhow::Result<()> {
     
     // Initialize git store for configurations
     let configs_dir = data_dir.join("configs");
-    let git_store = git_store::GitStore::open_or_init(&configs_dir)
+    let mut git_store = git_store::GitStore::open_or_init(&configs_dir)
         .map_err(|e| anyhow::anyhow!("Failed to initialize git store: {}", e))?;
+    if git_store::validate_on_commit_from_env() {
+        info!("Validating configs before they are committed");
+        let validator = vectorize::validation::ConfigValidator::new(None);
+        git_store = git_store.with_validator(std::sync::Arc::new(validator));
+    }
+    let signing_key = git_store::SigningKey::from_env()
+        .map_err(|e| anyhow::anyhow!("Invalid commit signing configuration: {}", e))?;
+    if signing_key.is_some() {
+        git_store.set_signing_key(signing_key)
+            .map_err(|e| anyhow::anyhow!("Failed to configure commit signing: {}", e))?;
+    }
 
-    // Start Vector process and get the binary path
-    let vector_process_temp = vector_manager::VectorProcess::new();
-    let (vector_handle, vector_binary_path) = vector_manager::start_vector(
-        &cli.vector_bin,
+    // Start the default Vector instance; more can be started alongside it
+    let vector_instances = vector_manager::VectorInstances::new(cli.vector_bin.clone());
+    let (vector_handle, vector_process) = vector_manager::start_instance(
+        &vector_instances,
+        vector_manager::DEFAULT_INSTANCE,
         cli.config.as_ref(),
         cli.vector_api_port,
-        vector_process_temp.clone(),
     )
     .await?;
-    
-    // Create the final VectorProcess with the binary path
-    let vector_process = vector_manager::VectorProcess::with_binary_path(vector_binary_path);
 
     // Wait a bit for Vector to start
     tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
@@ -252,7 +313,7 @@ async fn start_vectorize(cli: &Cli, open_browser: bool) -> anyhow::Result<()> {
     let ui_url = format!("http://127.0.0.1:{}", cli.port);
     info!("Starting web UI at {}", ui_url);
 
-    let server_handle = server::start_server(cli.port, cli.vector_api_port, vector_process, db.clone(), git_store).await?;
+    let mut running = server::start_server(cli.port, cli.vector_api_port, cli.metrics_port, vector_process, vector_instances, db.clone(), git_store, cipher).await?;
 
     // Open browser
     if open_browser {
@@ -286,13 +347,16 @@ async fn start_vectorize(cli: &Cli, open_browser: bool) -> anyhow::Result<()> {
                 tracing::error!("Vector process error: {}", e);
             }
         }
-        result = server_handle => {
+        result = &mut running.handle => {
             if let Err(e) = result {
                 tracing::error!("Server error: {}", e);
             }
         }
     }
 
+    // Let running deployments reach a consistent state before exiting
+    running.shutdown(deployment::shutdown::shutdown_grace_from_env()).await;
+
     info!("Vectorize stopped.");
     Ok(())
 }
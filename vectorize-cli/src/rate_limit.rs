@@ -0,0 +1,315 @@
+//! Request Rate Limiting
+//!
+//! Token-bucket limiter keyed by an arbitrary string (client IP, API token,
+//! agent ID). Used as API middleware to stop a single client from
+//! overwhelming the control plane, and by the tap service for per-agent
+//! sampling limits.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::api::ApiError;
+use crate::rbac::AuthenticatedUser;
+
+/// Buckets are pruned once the map grows past this many keys
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// =============================================================================
+// Keyed Token Bucket
+// =============================================================================
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token-bucket rate limiter with one bucket per key
+///
+/// Each bucket holds up to `capacity` tokens and refills continuously at
+/// `capacity` tokens per `period`, so bursts up to the full capacity are
+/// allowed after a quiet spell.
+#[derive(Debug)]
+pub struct KeyedRateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl KeyedRateLimiter {
+    /// Create a limiter allowing `capacity` requests per `period` for each key
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        Self {
+            capacity: capacity as f64,
+            refill_per_sec: capacity as f64 / period.as_secs_f64(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a limiter allowing `capacity` requests per minute for each key
+    pub fn per_minute(capacity: u32) -> Self {
+        Self::new(capacity, Duration::from_secs(60))
+    }
+
+    /// Check whether a request for `key` would be allowed, without consuming a token
+    ///
+    /// Returns how long to wait before retrying when the bucket is empty.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = self.refill(&mut buckets, key);
+        self.allow(bucket)
+    }
+
+    /// Consume a token for `key`
+    ///
+    /// Returns how long to wait before retrying when the bucket is empty.
+    pub fn try_acquire(&self, key: &str) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() > PRUNE_THRESHOLD {
+            self.prune(&mut buckets);
+        }
+
+        let bucket = self.refill(&mut buckets, key);
+        self.allow(bucket)?;
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+
+    fn refill<'a>(&self, buckets: &'a mut HashMap<String, TokenBucket>, key: &str) -> &'a mut TokenBucket {
+        let now = Instant::now();
+        let bucket = buckets.entry(key.to_string()).or_insert(TokenBucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.last_refill = now;
+        bucket
+    }
+
+    fn allow(&self, bucket: &TokenBucket) -> Result<(), Duration> {
+        if bucket.tokens >= 1.0 {
+            Ok(())
+        } else if self.refill_per_sec > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec))
+        } else {
+            Err(Duration::MAX)
+        }
+    }
+
+    /// Drop buckets that have refilled completely (they behave like new ones)
+    fn prune(&self, buckets: &mut HashMap<String, TokenBucket>) {
+        let now = Instant::now();
+        buckets.retain(|_, bucket| {
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens + elapsed * self.refill_per_sec < self.capacity
+        });
+    }
+}
+
+// =============================================================================
+// API Middleware
+// =============================================================================
+
+/// API rate limit configuration
+#[derive(Debug, Clone)]
+pub struct ApiRateLimitConfig {
+    /// Requests per minute per client IP (0 disables)
+    pub requests_per_minute_per_ip: u32,
+    /// Requests per minute per authenticated user (0 disables)
+    pub requests_per_minute_per_token: u32,
+}
+
+impl Default for ApiRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute_per_ip: 600,
+            requests_per_minute_per_token: 1200,
+        }
+    }
+}
+
+impl ApiRateLimitConfig {
+    /// Load limits from `VECTORIZE_RATE_LIMIT_PER_IP` and
+    /// `VECTORIZE_RATE_LIMIT_PER_TOKEN`, falling back to the defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |name: &str, default: u32| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            requests_per_minute_per_ip: read("VECTORIZE_RATE_LIMIT_PER_IP", defaults.requests_per_minute_per_ip),
+            requests_per_minute_per_token: read("VECTORIZE_RATE_LIMIT_PER_TOKEN", defaults.requests_per_minute_per_token),
+        }
+    }
+}
+
+/// Per-IP and per-user limiter for API requests
+#[derive(Debug)]
+pub struct ApiRateLimiter {
+    by_ip: Option<KeyedRateLimiter>,
+    by_user: Option<KeyedRateLimiter>,
+}
+
+impl ApiRateLimiter {
+    /// Create a new API rate limiter
+    pub fn new(config: ApiRateLimitConfig) -> Self {
+        let limiter = |rpm: u32| (rpm > 0).then(|| KeyedRateLimiter::per_minute(rpm));
+        Self {
+            by_ip: limiter(config.requests_per_minute_per_ip),
+            by_user: limiter(config.requests_per_minute_per_token),
+        }
+    }
+
+    /// Consume a request from a client IP, returning the retry delay if limited
+    pub fn try_acquire_ip(&self, ip: &str) -> Result<(), Duration> {
+        match &self.by_ip {
+            Some(limiter) => limiter.try_acquire(ip),
+            None => Ok(()),
+        }
+    }
+
+    /// Consume a request from an authenticated user, returning the retry
+    /// delay if limited
+    ///
+    /// Only call this once the user's credentials have been verified, so
+    /// made-up tokens can't each get a bucket of their own.
+    pub fn try_acquire_user(&self, user_id: &str) -> Result<(), Duration> {
+        match &self.by_user {
+            Some(limiter) => limiter.try_acquire(user_id),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Paths that are never rate limited
+const EXEMPT_PATHS: &[&str] = &["/health"];
+
+/// 429 response for a request limited for `retry_after`
+fn limited(retry_after: Duration) -> Response {
+    let retry_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    ApiError::RateLimited { retry_after_secs: retry_secs }.into_response()
+}
+
+/// Middleware enforcing the per-IP limit of [`ApiRateLimiter`]
+///
+/// Every request is charged to its client IP, credentials or not, and this
+/// runs before authentication so floods never reach the credential checks.
+/// Forwarded-for headers are not trusted, so behind a reverse proxy all
+/// clients share the proxy's bucket.
+pub async fn enforce(
+    State(limiter): State<Arc<ApiRateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if EXEMPT_PATHS.contains(&req.uri().path()) {
+        return next.run(req).await;
+    }
+
+    let ip = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    if let Err(retry_after) = limiter.try_acquire_ip(&ip) {
+        warn!("Rate limit exceeded for {} on {}", ip, req.uri().path());
+        return limited(retry_after);
+    }
+
+    next.run(req).await
+}
+
+/// Middleware enforcing the per-user limit of [`ApiRateLimiter`]
+///
+/// Runs after `rbac::optional_auth_middleware`, so only requests whose
+/// credentials were verified are charged, keyed by the authenticated user.
+pub async fn enforce_per_user(
+    State(limiter): State<Arc<ApiRateLimiter>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if let Some(user) = req.extensions().get::<AuthenticatedUser>() {
+        if let Err(retry_after) = limiter.try_acquire_user(&user.user_id) {
+            warn!("Rate limit exceeded for user {} on {}", user.user_id, req.uri().path());
+            return limited(retry_after);
+        }
+    }
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_limits() {
+        let limiter = KeyedRateLimiter::per_minute(3);
+
+        for _ in 0..3 {
+            assert!(limiter.try_acquire("client").is_ok());
+        }
+
+        let retry = limiter.try_acquire("client").unwrap_err();
+        assert!(retry > Duration::ZERO && retry <= Duration::from_secs(20));
+
+        // Other keys have their own bucket
+        assert!(limiter.try_acquire("other").is_ok());
+    }
+
+    #[test]
+    fn test_check_does_not_consume() {
+        let limiter = KeyedRateLimiter::per_minute(1);
+
+        assert!(limiter.check("client").is_ok());
+        assert!(limiter.check("client").is_ok());
+        assert!(limiter.try_acquire("client").is_ok());
+        assert!(limiter.check("client").is_err());
+    }
+
+    #[test]
+    fn test_bucket_refills() {
+        let limiter = KeyedRateLimiter::new(1, Duration::from_millis(20));
+
+        assert!(limiter.try_acquire("client").is_ok());
+        assert!(limiter.try_acquire("client").is_err());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(limiter.try_acquire("client").is_ok());
+    }
+
+    #[test]
+    fn test_api_limiter_charges_ip_regardless_of_token() {
+        let limiter = ApiRateLimiter::new(ApiRateLimitConfig {
+            requests_per_minute_per_ip: 2,
+            requests_per_minute_per_token: 1,
+        });
+
+        // Credentials don't buy a separate bucket: every request counts
+        // against the client IP
+        assert!(limiter.try_acquire_ip("10.0.0.1").is_ok());
+        assert!(limiter.try_acquire_ip("10.0.0.1").is_ok());
+        assert!(limiter.try_acquire_ip("10.0.0.1").is_err());
+        assert!(limiter.try_acquire_ip("10.0.0.2").is_ok());
+
+        // Authenticated users are limited on top of that
+        assert!(limiter.try_acquire_user("user-a").is_ok());
+        assert!(limiter.try_acquire_user("user-a").is_err());
+        assert!(limiter.try_acquire_user("user-b").is_ok());
+    }
+}
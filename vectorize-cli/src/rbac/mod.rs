@@ -4,22 +4,26 @@
 //! - Permission definitions and actions
 //! - Role management (built-in and custom)
 //! - Permission checking middleware
-//! - JWT authentication middleware
+//! - JWT and API key authentication middleware
 
 use axum::{
-    extract::{Request, State},
-    http::{header, StatusCode},
+    extract::{Extension, Request, State},
+    http::header,
     middleware::Next,
     response::{IntoResponse, Response},
-    Json,
 };
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use base64::Engine;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use tracing::{debug, warn};
 
 use crate::AppState;
-use crate::db::repository::RoleRepository;
+use crate::api::ApiError;
+use crate::db::models::ApiKey;
+use crate::db::repository::{ApiKeyRepository, RoleRepository, UserRepository};
 
 // =============================================================================
 // Permission Definitions
@@ -107,6 +111,73 @@ impl Permission {
         ]
     }
     
+    /// Permissions that directly imply this one
+    ///
+    /// Delete implies write and write implies read within a resource, so a
+    /// role that can change something can also see it. Follow the chain
+    /// with `is_granted_by` for the transitive check.
+    pub fn implied_by(self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            AgentsRead => &[AgentsWrite],
+            AgentsWrite => &[AgentsDelete],
+            GroupsRead => &[GroupsWrite, GroupsDeploy],
+            GroupsWrite => &[GroupsDelete],
+            ConfigsRead => &[ConfigsWrite, ConfigsRollback],
+            UsersRead => &[UsersWrite],
+            UsersWrite => &[UsersDelete],
+            RolesRead => &[RolesWrite],
+            RolesWrite => &[RolesDelete],
+            ApiKeysRead => &[ApiKeysWrite],
+            ApiKeysWrite => &[ApiKeysDelete],
+            AlertsRead => &[AlertsWrite],
+            AlertsWrite => &[AlertsDelete],
+            SystemRead => &[SystemAdmin],
+            AgentsDelete | GroupsDelete | GroupsDeploy | ConfigsWrite | ConfigsRollback
+            | ConfigsValidate | UsersDelete | RolesDelete | ApiKeysDelete | AuditRead
+            | AlertsDelete | SystemAdmin => &[],
+        }
+    }
+    
+    /// Canonical name, as stored in roles and API keys
+    pub fn as_str(self) -> &'static str {
+        use Permission::*;
+        match self {
+            AgentsRead => "agents_read",
+            AgentsWrite => "agents_write",
+            AgentsDelete => "agents_delete",
+            GroupsRead => "groups_read",
+            GroupsWrite => "groups_write",
+            GroupsDelete => "groups_delete",
+            GroupsDeploy => "groups_deploy",
+            ConfigsRead => "configs_read",
+            ConfigsWrite => "configs_write",
+            ConfigsRollback => "configs_rollback",
+            ConfigsValidate => "configs_validate",
+            UsersRead => "users_read",
+            UsersWrite => "users_write",
+            UsersDelete => "users_delete",
+            RolesRead => "roles_read",
+            RolesWrite => "roles_write",
+            RolesDelete => "roles_delete",
+            ApiKeysRead => "api_keys_read",
+            ApiKeysWrite => "api_keys_write",
+            ApiKeysDelete => "api_keys_delete",
+            AuditRead => "audit_read",
+            AlertsRead => "alerts_read",
+            AlertsWrite => "alerts_write",
+            AlertsDelete => "alerts_delete",
+            SystemRead => "system_read",
+            SystemAdmin => "system_admin",
+        }
+    }
+    
+    /// Whether `granted` includes this permission or one that implies it
+    pub fn is_granted_by(self, granted: &[Permission]) -> bool {
+        granted.contains(&self)
+            || self.implied_by().iter().any(|implier| implier.is_granted_by(granted))
+    }
+    
     /// Get permissions for the admin role
     pub fn admin_permissions() -> Vec<String> {
         Self::all().iter().map(|p| format!("{:?}", p).to_lowercase()).collect()
@@ -115,9 +186,9 @@ impl Permission {
     /// Get permissions for the operator role
     pub fn operator_permissions() -> Vec<String> {
         vec![
-            "agents_read", "agents_write",
+            "agents_read", "agents_write", "agents_delete",
             "groups_read", "groups_write", "groups_deploy",
-            "configs_read", "configs_write", "configs_validate",
+            "configs_read", "configs_write", "configs_rollback", "configs_validate",
             "alerts_read", "alerts_write",
             "system_read",
         ].into_iter().map(String::from).collect()
@@ -207,20 +278,36 @@ pub struct AuthenticatedUser {
 
 impl AuthenticatedUser {
     /// Check if user has a specific permission
+    ///
+    /// Granted permissions are expanded through `Permission::implied_by`, so
+    /// `agents_write` also satisfies `agents_read`.
     pub fn has_permission(&self, permission: &str) -> bool {
         // System admin has all permissions
-        if self.permissions.contains(&"system_admin".to_string()) {
+        if self.is_admin() {
+            return true;
+        }
+        if self.permissions.iter().any(|p| p == permission) {
             return true;
         }
-        self.permissions.contains(&permission.to_string())
+        match permission.parse::<Permission>() {
+            Ok(required) => required.is_granted_by(&self.granted()),
+            Err(_) => false,
+        }
     }
     
     /// Check if user has any of the specified permissions
     pub fn has_any_permission(&self, permissions: &[&str]) -> bool {
-        if self.permissions.contains(&"system_admin".to_string()) {
-            return true;
-        }
-        permissions.iter().any(|p| self.permissions.contains(&p.to_string()))
+        permissions.iter().any(|p| self.has_permission(p))
+    }
+    
+    /// Granted permissions that name a known `Permission`
+    fn granted(&self) -> Vec<Permission> {
+        self.permissions.iter().filter_map(|p| p.parse().ok()).collect()
+    }
+    
+    /// System admin, or the wildcard granted to the built-in admin role
+    fn is_admin(&self) -> bool {
+        self.permissions.iter().any(|p| p == "system_admin" || p == "*")
     }
 }
 
@@ -234,111 +321,358 @@ fn get_jwt_secret() -> String {
         .unwrap_or_else(|_| "vectorize-development-secret-change-in-production".to_string())
 }
 
-/// Authentication middleware - extracts and validates JWT token
+/// JWT verification settings
+///
+/// Tokens issued by Vectorize itself are HS256 with the shared secret.
+/// Tokens from an external identity provider are RS256 and are verified with
+/// `public_key_pem`; each algorithm only ever uses its own key, so an RS256
+/// token is rejected outright when no public key is configured.
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    /// HMAC secret for HS256 tokens
+    pub secret: String,
+    /// PEM-encoded RSA public key for RS256 tokens
+    pub public_key_pem: Option<String>,
+    /// Required `aud` claim (any of these), when set
+    pub audience: Option<Vec<String>>,
+    /// Required `iss` claim (any of these), when set
+    pub issuer: Option<Vec<String>>,
+}
+
+impl JwtConfig {
+    /// Load settings from the environment
+    ///
+    /// - `VECTORIZE_JWT_SECRET`: HS256 secret
+    /// - `VECTORIZE_JWT_PUBLIC_KEY`: RS256 public key, as PEM or a path to a PEM file
+    /// - `VECTORIZE_JWT_AUDIENCE` / `VECTORIZE_JWT_ISSUER`: comma-separated accepted values
+    pub fn from_env() -> Self {
+        let list = |name: &str| {
+            std::env::var(name).ok().map(|v| {
+                v.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+            }).filter(|v| !v.is_empty())
+        };
+        
+        let public_key_pem = std::env::var("VECTORIZE_JWT_PUBLIC_KEY").ok().and_then(|value| {
+            if value.trim_start().starts_with("-----BEGIN") {
+                Some(value)
+            } else {
+                std::fs::read_to_string(&value)
+                    .map_err(|e| warn!("Failed to read VECTORIZE_JWT_PUBLIC_KEY from {}: {}", value, e))
+                    .ok()
+            }
+        });
+        
+        Self {
+            secret: get_jwt_secret(),
+            public_key_pem,
+            audience: list("VECTORIZE_JWT_AUDIENCE"),
+            issuer: list("VECTORIZE_JWT_ISSUER"),
+        }
+    }
+    
+    /// Verify a token and return its claims
+    pub fn decode(&self, token: &str) -> Result<Claims, String> {
+        let header = decode_header(token).map_err(|e| e.to_string())?;
+        
+        let key = match header.alg {
+            Algorithm::HS256 => DecodingKey::from_secret(self.secret.as_bytes()),
+            Algorithm::RS256 => {
+                let pem = self.public_key_pem.as_ref()
+                    .ok_or_else(|| "RS256 token but no VECTORIZE_JWT_PUBLIC_KEY configured".to_string())?;
+                DecodingKey::from_rsa_pem(pem.as_bytes()).map_err(|e| format!("Invalid JWT public key: {}", e))?
+            }
+            alg => return Err(format!("Unsupported JWT algorithm: {:?}", alg)),
+        };
+        
+        let mut validation = Validation::new(header.alg);
+        match &self.audience {
+            Some(audience) => {
+                validation.set_audience(audience.as_slice());
+                validation.required_spec_claims.insert("aud".to_string());
+            }
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(issuer.as_slice());
+            validation.required_spec_claims.insert("iss".to_string());
+        }
+        
+        decode::<Claims>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Authentication middleware - validates a JWT or API key
 pub async fn auth_middleware(
     State(state): State<Arc<AppState>>,
     mut request: Request,
     next: Next,
 ) -> Response {
-    // Extract token from Authorization header
-    let token = match extract_token(&request) {
-        Some(token) => token,
+    // Extract credential from Authorization / X-API-Key headers
+    let credential = match extract_credential(&request) {
+        Some(credential) => credential,
         None => {
-            return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({
-                "error": "Missing or invalid authorization header"
-            }))).into_response();
+            return ApiError::unauthorized("Missing or invalid authorization header").into_response();
         }
     };
     
-    // Decode and validate token
-    let claims = match decode_token(&token) {
-        Ok(claims) => claims,
-        Err(e) => {
-            debug!("Token validation failed: {}", e);
-            return (StatusCode::UNAUTHORIZED, Json(serde_json::json!({
-                "error": "Invalid or expired token"
-            }))).into_response();
+    let user = match authenticate(&state, &credential).await {
+        Ok(user) => user,
+        Err(AuthError::Invalid(reason)) => {
+            debug!("Authentication failed: {}", reason);
+            return ApiError::unauthorized("Invalid or expired credentials").into_response();
         }
-    };
-    
-    // Get role permissions
-    let permissions = match get_role_permissions(&state, &claims.role).await {
-        Ok(perms) => perms,
-        Err(_) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": "Failed to load permissions"
-            }))).into_response();
+        Err(AuthError::Internal(e)) => {
+            warn!("Authentication error: {}", e);
+            return ApiError::internal("Failed to load permissions").into_response();
         }
     };
     
     // Add authenticated user to request extensions
-    let user = AuthenticatedUser {
-        user_id: claims.sub,
-        role_id: claims.role,
-        permissions,
-    };
-    
     request.extensions_mut().insert(user);
     
     next.run(request).await
 }
 
-/// Optional auth middleware - allows unauthenticated requests but adds user if token present
+/// Environment variable opting in to anonymous access to protected routes
+pub const ALLOW_ANONYMOUS_ENV: &str = "VECTORIZE_ALLOW_ANONYMOUS";
+
+/// Whether `VECTORIZE_ALLOW_ANONYMOUS` is set (`true`/`1`/`yes`/`on`)
+pub fn allow_anonymous_from_env() -> bool {
+    std::env::var(ALLOW_ANONYMOUS_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Marks a request without credentials that may use protected routes
+///
+/// Set by `optional_auth_middleware` when anonymous access is enabled;
+/// `api::authz::authorize` answers 401 to other anonymous requests for
+/// routes that aren't public.
+#[derive(Debug, Clone, Copy)]
+pub struct AnonymousAccess;
+
+/// Optional auth middleware - allows unauthenticated requests but adds user if credentials are valid
+///
+/// Requests without credentials are marked with [`AnonymousAccess`] when
+/// `AppState::allow_anonymous` is set. Invalid credentials never are, so
+/// they can't be dropped into anonymous access.
 pub async fn optional_auth_middleware(
     State(state): State<Arc<AppState>>,
     mut request: Request,
     next: Next,
 ) -> Response {
-    // Try to extract and validate token
-    if let Some(token) = extract_token(&request) {
-        if let Ok(claims) = decode_token(&token) {
-            if let Ok(permissions) = get_role_permissions(&state, &claims.role).await {
-                let user = AuthenticatedUser {
-                    user_id: claims.sub,
-                    role_id: claims.role,
-                    permissions,
-                };
+    match extract_credential(&request) {
+        Some(credential) => {
+            if let Ok(user) = authenticate(&state, &credential).await {
                 request.extensions_mut().insert(user);
             }
         }
+        None if state.allow_anonymous => {
+            request.extensions_mut().insert(AnonymousAccess);
+        }
+        None => {}
     }
     
     next.run(request).await
 }
 
-/// Extract bearer token from request
-fn extract_token(request: &Request) -> Option<String> {
-    // Try Authorization header first
-    if let Some(auth_header) = request.headers().get(header::AUTHORIZATION) {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                return Some(auth_str[7..].to_string());
-            }
+/// Require an authenticated user on routes behind `optional_auth_middleware`
+pub fn require_authenticated(
+    user: Option<Extension<AuthenticatedUser>>,
+) -> Result<AuthenticatedUser, ApiError> {
+    match user {
+        Some(Extension(user)) => Ok(user),
+        None => Err(ApiError::unauthorized("Missing or invalid authorization header")),
+    }
+}
+
+/// A credential presented by the client
+#[derive(Debug, Clone, PartialEq)]
+enum Credential {
+    Jwt(String),
+    ApiKey(String),
+}
+
+/// Why a credential was not accepted
+#[derive(Debug)]
+enum AuthError {
+    /// Unknown, expired or revoked credential (401)
+    Invalid(String),
+    /// Lookup failed (500)
+    Internal(String),
+}
+
+/// Extract the credential from the request
+///
+/// API keys are accepted in `X-API-Key` or as a bearer token carrying the
+/// key prefix; any other bearer token is treated as a JWT.
+fn extract_credential(request: &Request) -> Option<Credential> {
+    if let Some(key) = request.headers().get("X-API-Key").and_then(|v| v.to_str().ok()) {
+        return Some(Credential::ApiKey(key.to_string()));
+    }
+    
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))?;
+    
+    if token.starts_with(API_KEY_PREFIX) {
+        Some(Credential::ApiKey(token.to_string()))
+    } else {
+        Some(Credential::Jwt(token.to_string()))
+    }
+}
+
+/// Resolve a credential to the user it authenticates
+async fn authenticate(state: &AppState, credential: &Credential) -> Result<AuthenticatedUser, AuthError> {
+    match credential {
+        Credential::Jwt(token) => {
+            let claims = decode_token(token).map_err(AuthError::Invalid)?;
+            let permissions = get_role_permissions(state, &claims.role)
+                .await
+                .map_err(AuthError::Internal)?;
+            Ok(AuthenticatedUser {
+                user_id: claims.sub,
+                role_id: claims.role,
+                permissions,
+            })
         }
+        Credential::ApiKey(key) => authenticate_api_key(state, key).await,
+    }
+}
+
+/// Look up an API key by hash and build the user it acts as
+///
+/// The key acts with its own role if it has one, otherwise its owner's.
+/// An explicit permission list on the key narrows that role's permissions;
+/// it never grants more than the role currently does, so demoting the owner
+/// also demotes their keys.
+async fn authenticate_api_key(state: &AppState, key: &str) -> Result<AuthenticatedUser, AuthError> {
+    let pool = state.db.pool();
+    
+    let api_key = ApiKeyRepository::get_by_hash(pool, &hash_api_key(key))
+        .await
+        .map_err(|e| AuthError::Internal(format!("Database error: {}", e)))?
+        .ok_or_else(|| AuthError::Invalid("unknown or revoked API key".to_string()))?;
+    
+    if is_expired(&api_key) {
+        return Err(AuthError::Invalid(format!("API key {} has expired", api_key.key_prefix)));
     }
     
-    // Try X-API-Key header
-    if let Some(api_key) = request.headers().get("X-API-Key") {
-        if let Ok(key) = api_key.to_str() {
-            return Some(key.to_string());
+    let owner = match &api_key.user_id {
+        Some(user_id) => match UserRepository::get_by_id(pool, user_id).await {
+            Ok(Some(user)) if user.is_active => Some(user),
+            Ok(_) => return Err(AuthError::Invalid(format!("owner of API key {} is disabled", api_key.key_prefix))),
+            Err(e) => return Err(AuthError::Internal(format!("Database error: {}", e))),
+        },
+        None => None,
+    };
+    
+    let role_id = match (&api_key.role_id, &owner) {
+        (Some(role_id), _) => role_id.clone(),
+        (None, Some(owner)) => owner.role_id.clone(),
+        (None, None) => return Err(AuthError::Invalid(format!("API key {} has no role", api_key.key_prefix))),
+    };
+    
+    let role_permissions = get_role_permissions(state, &role_id).await.map_err(AuthError::Internal)?;
+    let permissions = match api_key.permissions.as_deref().map(serde_json::from_str::<Vec<String>>) {
+        Some(Ok(permissions)) => {
+            let role = AuthenticatedUser {
+                user_id: String::new(),
+                role_id: role_id.clone(),
+                permissions: role_permissions,
+            };
+            permissions.into_iter().filter(|p| role.has_permission(p)).collect()
         }
+        Some(Err(_)) => return Err(AuthError::Invalid(format!("API key {} has invalid permissions", api_key.key_prefix))),
+        None => role_permissions,
+    };
+    
+    if let Err(e) = ApiKeyRepository::update_last_used(pool, &api_key.id).await {
+        warn!("Failed to update API key last use: {}", e);
     }
     
-    None
+    Ok(AuthenticatedUser {
+        user_id: api_key.user_id.unwrap_or_else(|| format!("api_key:{}", api_key.id)),
+        role_id,
+        permissions,
+    })
 }
 
-/// Decode and validate JWT token
-fn decode_token(token: &str) -> Result<Claims, String> {
-    let secret = get_jwt_secret();
+/// Whether an API key is past its `expires_at`
+///
+/// Accepts RFC 3339 and SQLite `CURRENT_TIMESTAMP` formats; an unparseable
+/// expiry is treated as expired.
+fn is_expired(api_key: &ApiKey) -> bool {
+    let Some(expires_at) = api_key.expires_at.as_deref() else {
+        return false;
+    };
     
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|e| e.to_string())?;
+    let expires_at = chrono::DateTime::parse_from_rfc3339(expires_at)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(expires_at, "%Y-%m-%d %H:%M:%S").map(|t| t.and_utc())
+        });
     
-    Ok(token_data.claims)
+    match expires_at {
+        Ok(expires_at) => expires_at <= chrono::Utc::now(),
+        Err(_) => true,
+    }
+}
+
+// =============================================================================
+// API Keys
+// =============================================================================
+
+/// Prefix identifying Vectorize API keys
+pub const API_KEY_PREFIX: &str = "vz_";
+
+/// A newly generated API key
+#[derive(Debug, Clone)]
+pub struct GeneratedApiKey {
+    /// Full key, shown to the user once and never stored
+    pub key: String,
+    /// SHA-256 hash of the key (stored)
+    pub hash: String,
+    /// Leading characters of the key, stored for identification
+    pub prefix: String,
+}
+
+/// Generate a random API key
+///
+/// Keys carry 256 bits of randomness, so an unsalted SHA-256 hash is enough
+/// to store them and allows lookup by hash.
+pub fn generate_api_key() -> GeneratedApiKey {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    
+    let key = format!(
+        "{}{}",
+        API_KEY_PREFIX,
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    );
+    
+    GeneratedApiKey {
+        hash: hash_api_key(&key),
+        prefix: key[..API_KEY_PREFIX.len() + 8].to_string(),
+        key,
+    }
+}
+
+/// Hash an API key for storage and lookup
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Decode and validate JWT token
+fn decode_token(token: &str) -> Result<Claims, String> {
+    JwtConfig::from_env().decode(token)
 }
 
 /// Get permissions for a role
@@ -369,14 +703,12 @@ async fn get_role_permissions(state: &AppState, role_id: &str) -> Result<Vec<Str
 pub fn require_permission(
     user: &AuthenticatedUser,
     permission: &str,
-) -> Result<(), Response> {
+) -> Result<(), ApiError> {
     if user.has_permission(permission) {
         Ok(())
     } else {
-        Err((StatusCode::FORBIDDEN, Json(serde_json::json!({
-            "error": "Insufficient permissions",
-            "required": permission
-        }))).into_response())
+        Err(ApiError::forbidden("Insufficient permissions")
+            .with_details(serde_json::json!({ "required": permission })))
     }
 }
 
@@ -384,14 +716,42 @@ pub fn require_permission(
 pub fn require_any_permission(
     user: &AuthenticatedUser,
     permissions: &[&str],
-) -> Result<(), Response> {
+) -> Result<(), ApiError> {
     if user.has_any_permission(permissions) {
         Ok(())
     } else {
-        Err((StatusCode::FORBIDDEN, Json(serde_json::json!({
-            "error": "Insufficient permissions",
-            "required_any": permissions
-        }))).into_response())
+        Err(ApiError::forbidden("Insufficient permissions")
+            .with_details(serde_json::json!({ "required_any": permissions })))
+    }
+}
+
+/// What a route requires of an authenticated caller
+///
+/// Declared per route in `api::authz`; `check` runs before the handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requires {
+    /// Nothing (setup, login, handshake)
+    Public,
+    /// Self-service endpoints whose handler decides, e.g. users may manage
+    /// their own API keys without `api_keys_*`
+    Handler,
+    /// This permission, or one implying it
+    One(Permission),
+    /// Any of these permissions
+    Any(&'static [Permission]),
+}
+
+impl Requires {
+    /// Refuse `user` with 403 unless the requirement is met
+    pub fn check(self, user: &AuthenticatedUser) -> Result<(), ApiError> {
+        match self {
+            Requires::Public | Requires::Handler => Ok(()),
+            Requires::One(permission) => require_permission(user, permission.as_str()),
+            Requires::Any(permissions) => {
+                let names: Vec<&str> = permissions.iter().map(|p| p.as_str()).collect();
+                require_any_permission(user, &names)
+            }
+        }
     }
 }
 
@@ -434,6 +794,190 @@ mod tests {
         assert!(user.has_permission("anything"));
     }
     
+    #[test]
+    fn test_permission_names_round_trip() {
+        for permission in Permission::all() {
+            assert_eq!(permission.as_str().parse::<Permission>().unwrap(), permission);
+        }
+    }
+    
+    #[test]
+    fn test_requires_check() {
+        let viewer = user_with(&["groups_read"]);
+        assert!(Requires::Public.check(&viewer).is_ok());
+        assert!(Requires::One(Permission::GroupsRead).check(&viewer).is_ok());
+        assert!(Requires::Any(&[Permission::AuditRead, Permission::GroupsRead]).check(&viewer).is_ok());
+        
+        let err = Requires::One(Permission::GroupsDeploy).check(&viewer).unwrap_err();
+        assert_eq!(err.status(), axum::http::StatusCode::FORBIDDEN);
+        assert!(Requires::Any(&[Permission::AuditRead, Permission::AgentsWrite]).check(&viewer).is_err());
+    }
+    
+    fn user_with(permissions: &[&str]) -> AuthenticatedUser {
+        AuthenticatedUser {
+            user_id: "user1".to_string(),
+            role_id: "custom".to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+    
+    #[test]
+    fn test_write_implies_read() {
+        let user = user_with(&["agents_write"]);
+        assert!(user.has_permission("agents_write"));
+        assert!(user.has_permission("agents_read"));
+        assert!(!user.has_permission("agents_delete"));
+        assert!(user.has_any_permission(&["users_read", "agents_read"]));
+    }
+    
+    #[test]
+    fn test_implications_are_transitive() {
+        let user = user_with(&["groups_delete"]);
+        assert!(user.has_permission("groups_write"));
+        assert!(user.has_permission("groups_read"));
+        assert!(!user.has_permission("groups_deploy"));
+        
+        assert!(user_with(&["groups_deploy"]).has_permission("groups_read"));
+        assert!(user_with(&["configs_rollback"]).has_permission("configs_read"));
+        
+        // Legacy un-underscored names expand too
+        assert!(user_with(&["apikeysdelete"]).has_permission("api_keys_read"));
+    }
+    
+    #[test]
+    fn test_unrelated_permissions_not_granted() {
+        let user = user_with(&["agents_write", "alerts_delete"]);
+        assert!(!user.has_permission("groups_read"));
+        assert!(!user.has_permission("users_read"));
+        assert!(!user.has_permission("audit_read"));
+        assert!(!user.has_permission("system_read"));
+        assert!(!user.has_permission("unknown_permission"));
+        assert!(!user.has_any_permission(&["configs_read", "roles_read"]));
+        
+        // Reading never grants writing
+        assert!(!user_with(&["agents_read"]).has_permission("agents_write"));
+    }
+    
+    #[test]
+    fn test_every_permission_is_granted_by_itself() {
+        for permission in Permission::all() {
+            assert!(permission.is_granted_by(&[permission]));
+            assert!(!permission.implied_by().contains(&permission));
+        }
+    }
+    
+    #[test]
+    fn test_generate_api_key() {
+        let generated = generate_api_key();
+        
+        assert!(generated.key.starts_with(API_KEY_PREFIX));
+        assert!(generated.key.starts_with(&generated.prefix));
+        assert_eq!(generated.hash, hash_api_key(&generated.key));
+        assert_ne!(generated.hash, generated.key);
+        assert_ne!(generate_api_key().key, generated.key);
+    }
+    
+    #[test]
+    fn test_extract_credential() {
+        let request = |name: &str, value: &str| {
+            Request::builder().header(name, value).body(axum::body::Body::empty()).unwrap()
+        };
+        
+        assert_eq!(
+            extract_credential(&request("x-api-key", "vz_abc")),
+            Some(Credential::ApiKey("vz_abc".to_string()))
+        );
+        assert_eq!(
+            extract_credential(&request("authorization", "Bearer vz_abc")),
+            Some(Credential::ApiKey("vz_abc".to_string()))
+        );
+        assert_eq!(
+            extract_credential(&request("authorization", "Bearer eyJhbGc")),
+            Some(Credential::Jwt("eyJhbGc".to_string()))
+        );
+        assert_eq!(extract_credential(&request("authorization", "Basic abc")), None);
+    }
+    
+    const RSA_PRIVATE_KEY: &str = include_str!("../../tests/fixtures/jwt_rs256_private.pem");
+    const RSA_PUBLIC_KEY: &str = include_str!("../../tests/fixtures/jwt_rs256_public.pem");
+    
+    fn jwt_config() -> JwtConfig {
+        JwtConfig {
+            secret: "test-secret".to_string(),
+            public_key_pem: Some(RSA_PUBLIC_KEY.to_string()),
+            audience: None,
+            issuer: None,
+        }
+    }
+    
+    fn sign(alg: Algorithm, claims: serde_json::Value) -> String {
+        use jsonwebtoken::{encode, EncodingKey, Header};
+        
+        let key = match alg {
+            Algorithm::RS256 => EncodingKey::from_rsa_pem(RSA_PRIVATE_KEY.as_bytes()).unwrap(),
+            _ => EncodingKey::from_secret(b"test-secret"),
+        };
+        encode(&Header::new(alg), &claims, &key).unwrap()
+    }
+    
+    fn claims(extra: serde_json::Value) -> serde_json::Value {
+        let now = chrono::Utc::now().timestamp();
+        let mut claims = serde_json::json!({ "sub": "user1", "role": "viewer", "iat": now, "exp": now + 600 });
+        claims.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        claims
+    }
+    
+    #[test]
+    fn test_rs256_token_verified_with_public_key() {
+        let token = sign(Algorithm::RS256, claims(serde_json::json!({})));
+        let decoded = jwt_config().decode(&token).unwrap();
+        assert_eq!(decoded.sub, "user1");
+        assert_eq!(decoded.role, "viewer");
+        
+        // Without a public key, RS256 tokens are rejected
+        let config = JwtConfig { public_key_pem: None, ..jwt_config() };
+        assert!(config.decode(&token).is_err());
+    }
+    
+    #[test]
+    fn test_hs256_token_still_uses_secret() {
+        let token = sign(Algorithm::HS256, claims(serde_json::json!({})));
+        assert!(jwt_config().decode(&token).is_ok());
+        
+        let config = JwtConfig { secret: "other-secret".to_string(), ..jwt_config() };
+        assert!(config.decode(&token).is_err());
+    }
+    
+    #[test]
+    fn test_audience_and_issuer_enforced() {
+        let config = JwtConfig {
+            audience: Some(vec!["vectorize".to_string()]),
+            issuer: Some(vec!["https://idp.example.com".to_string()]),
+            ..jwt_config()
+        };
+        
+        let good = sign(Algorithm::RS256, claims(serde_json::json!({ "aud": "vectorize", "iss": "https://idp.example.com" })));
+        assert!(config.decode(&good).is_ok());
+        
+        let wrong_aud = sign(Algorithm::RS256, claims(serde_json::json!({ "aud": "other", "iss": "https://idp.example.com" })));
+        assert!(config.decode(&wrong_aud).is_err());
+        
+        let missing_iss = sign(Algorithm::RS256, claims(serde_json::json!({ "aud": "vectorize" })));
+        assert!(config.decode(&missing_iss).is_err());
+    }
+    
+    #[test]
+    fn test_wildcard_grants_all_permissions() {
+        let user = AuthenticatedUser {
+            user_id: "user1".to_string(),
+            role_id: "admin".to_string(),
+            permissions: vec!["*".to_string()],
+        };
+        
+        assert!(user.has_permission("audit_read"));
+        assert!(user.has_any_permission(&["users_write"]));
+    }
+    
     #[test]
     fn test_viewer_permissions() {
         let perms = Permission::viewer_permissions();
@@ -0,0 +1,152 @@
+//! Request Correlation IDs
+//!
+//! Every API request gets an ID, taken from the client's `X-Request-Id` when
+//! it sent a usable one. The ID is recorded on the request's tracing span,
+//! echoed in the response and forwarded on calls made to agents while the
+//! request is handled, so one ID ties control plane and agent logs together.
+//! Background work (health checks, scheduled deployments) gets a fresh ID.
+
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use std::future::Future;
+use tracing::{info_span, Instrument};
+
+/// Header carrying the correlation ID, inbound and outbound
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied ID that is kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Generate a new correlation ID
+pub fn generate() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// The ID of the request being handled by this task, if any
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// The current request's ID, or a new one outside of a request
+pub fn current_or_new() -> String {
+    current().unwrap_or_else(generate)
+}
+
+/// Run `future` with `id` as the current request ID
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(id, future).await
+}
+
+/// Carry the current request ID into `future`, e.g. one about to be spawned
+///
+/// Task-locals don't cross `tokio::spawn`, so spawned work would otherwise
+/// lose the ID.
+pub fn propagate<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let id = current();
+    async move {
+        match id {
+            Some(id) => REQUEST_ID.scope(id, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// Adds the correlation header to outbound requests
+pub trait WithRequestId {
+    /// Send the current request ID (or a new one) as `X-Request-Id`
+    fn with_request_id(self) -> Self;
+}
+
+impl WithRequestId for reqwest::RequestBuilder {
+    fn with_request_id(self) -> Self {
+        self.header(REQUEST_ID_HEADER, current_or_new())
+    }
+}
+
+/// A client-supplied ID, if it is short printable ASCII
+fn accept(value: &HeaderValue) -> Option<String> {
+    let id = value.to_str().ok()?.trim();
+    let valid = !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|b| b.is_ascii_graphic());
+    valid.then(|| id.to_string())
+}
+
+/// Middleware assigning each request its correlation ID
+pub async fn assign(req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(accept)
+        .unwrap_or_else(generate);
+    let span = info_span!("request", request_id = %id, method = %req.method(), path = %req.uri().path());
+    
+    let mut response = scope(id.clone(), next.run(req).instrument(span)).await;
+    
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+    
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { current().unwrap_or_default() }))
+            .layer(axum::middleware::from_fn(assign))
+    }
+    
+    #[tokio::test]
+    async fn test_assigns_and_echoes_id() {
+        let response = app()
+            .oneshot(axum::http::Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        assert_eq!(id.len(), 32);
+    
+        // Handlers see the same ID
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], id.as_bytes());
+    }
+    
+    #[tokio::test]
+    async fn test_keeps_client_id_unless_invalid() {
+        let request = |id: &str| {
+            axum::http::Request::builder()
+                .uri("/")
+                .header(REQUEST_ID_HEADER, id)
+                .body(Body::empty())
+                .unwrap()
+        };
+    
+        let response = app().oneshot(request("deploy-42")).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "deploy-42");
+    
+        let response = app().oneshot(request(&"x".repeat(MAX_REQUEST_ID_LEN + 1))).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER].len(), 32);
+    }
+    
+    #[tokio::test]
+    async fn test_propagate_crosses_spawn() {
+        let id = scope("abc".to_string(), async {
+            tokio::spawn(propagate(async { current() })).await.unwrap()
+        })
+        .await;
+        assert_eq!(id.as_deref(), Some("abc"));
+        assert!(current().is_none());
+    }
+}
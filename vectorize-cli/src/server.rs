@@ -6,23 +6,36 @@ use axum::{
     body::Body,
     extract::State,
     http::{header, Request, Response, StatusCode},
+    middleware,
     response::IntoResponse,
     routing::{any, get},
     Router,
     Json,
 };
+use metrics_exporter_prometheus::PrometheusHandle;
 use rust_embed::RustEmbed;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::{info, error};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::{info, error, warn};
 
-use crate::api;
+use crate::agent_clients::AgentClients;
+use crate::api::{self, ApiError};
+use crate::api::auth::BootstrapToken;
+use crate::api::maintenance::MaintenanceMode;
+use crate::crypto::SecretCipher;
 use crate::db::Database;
+use crate::deployment::{self, DeploymentEventBus, DeploymentExecutor, ShutdownCoordinator};
 use crate::git_store::GitStore;
+use crate::health::{HealthMonitor, HealthMonitorConfig};
+use crate::live::LiveEventBus;
+use crate::rate_limit::{self, ApiRateLimitConfig, ApiRateLimiter};
+use crate::rbac;
+use crate::request_id;
 use crate::tap::{TapService, RateLimitConfig};
+use crate::telemetry;
 use crate::validation::FunctionalTestService;
-use crate::vector_manager::VectorProcess;
+use crate::vector_manager::{VectorInstances, VectorProcess};
 
 /// Embedded UI assets (compiled WASM app)
 #[derive(RustEmbed)]
@@ -34,46 +47,142 @@ struct UiAssets;
 pub struct AppState {
     pub vector_api_url: String,
     pub http_client: reqwest::Client,
+    /// Clients for agents' Vector APIs, honouring per-agent TLS settings
+    pub agent_clients: Arc<AgentClients>,
+    /// The default local Vector instance
     pub vector_process: VectorProcess,
+    /// All local Vector instances, including the default one
+    pub vector_instances: VectorInstances,
     pub db: Database,
     pub git_store: Arc<GitStore>,
     pub tap_service: Arc<TapService>,
     pub functional_test_service: Arc<FunctionalTestService>,
+    pub health_monitor: Arc<HealthMonitor>,
+    pub metrics_handle: PrometheusHandle,
+    pub deployment_events: Arc<DeploymentEventBus>,
+    /// Agent status and deployment events for `/api/v1/ws`
+    pub live_events: Arc<LiveEventBus>,
+    /// Caps concurrent agent pushes across all deployments
+    pub deployment_push_limit: Arc<tokio::sync::Semaphore>,
+    /// Drains running deployments on shutdown
+    pub shutdown: Arc<ShutdownCoordinator>,
+    /// One-time token required by `/api/v1/setup/init`, if configured
+    pub bootstrap_token: Arc<BootstrapToken>,
+    /// Refuses mutating API calls while enabled
+    pub maintenance: Arc<MaintenanceMode>,
+    /// Let requests without credentials use protected routes (legacy mode)
+    pub allow_anonymous: bool,
+    /// Seals secrets stored in the database; None without `VECTORIZE_ENCRYPTION_KEY`
+    pub cipher: Option<SecretCipher>,
+}
+
+/// A started server, with the state needed to shut it down cleanly
+pub struct RunningServer {
+    pub handle: tokio::task::JoinHandle<anyhow::Result<()>>,
+    pub state: Arc<AppState>,
+}
+
+impl RunningServer {
+    /// Stop background work and drain running deployments
+    ///
+    /// New deployments are refused from here on. Rolling and canary
+    /// deployments finish their current batch and cancel the rest; whatever
+    /// is still running after `grace` is marked cancelled. The database pool
+    /// is closed last so every status update is flushed.
+    pub async fn shutdown(&self, grace: std::time::Duration) {
+        self.state.health_monitor.stop().await;
+        
+        let active = self.state.shutdown.active_deployments();
+        if active > 0 {
+            info!("Waiting up to {}s for {} running deployment(s)", grace.as_secs(), active);
+        }
+        
+        if !self.state.shutdown.shutdown(grace).await {
+            warn!("Deployments still running after the shutdown grace period; cancelling them");
+            let executor = DeploymentExecutor::new(self.state.db.clone(), self.state.git_store.clone())
+                .with_events(self.state.deployment_events.clone())
+                .with_agent_clients(self.state.agent_clients.clone());
+            match executor.cancel_in_progress_for_shutdown().await {
+                Ok(cancelled) => warn!("Cancelled {} deployment(s) for shutdown", cancelled.len()),
+                Err(e) => error!("Failed to cancel running deployments: {}", e),
+            }
+        }
+        
+        self.state.db.close().await;
+    }
 }
 
 /// Start the web server
+/// 
+/// `/metrics` is served on the main port unless `metrics_port` is set, in
+/// which case it gets its own listener.
+#[allow(clippy::too_many_arguments)]
 pub async fn start_server(
     port: u16,
     vector_api_port: u16,
+    metrics_port: Option<u16>,
     vector_process: VectorProcess,
+    vector_instances: VectorInstances,
     db: Database,
     git_store: GitStore,
-) -> anyhow::Result<tokio::task::JoinHandle<anyhow::Result<()>>> {
+    cipher: Option<SecretCipher>,
+) -> anyhow::Result<RunningServer> {
     // Create services
     let vector_bin = vector_process.get_binary_path();
     let tap_service = Arc::new(TapService::new(RateLimitConfig::default()));
     let functional_test_service = Arc::new(FunctionalTestService::new(vector_bin));
+    let live_events = Arc::new(LiveEventBus::new());
+    let health_monitor = Arc::new(
+        HealthMonitor::new(db.clone(), HealthMonitorConfig::from_env())
+            .with_live_events(live_events.clone())
+            .with_cipher(cipher.clone()),
+    );
+    health_monitor.clone().start();
+    health_monitor.clone().start_stale_sweep();
     
     let state = Arc::new(AppState {
         vector_api_url: format!("http://127.0.0.1:{}", vector_api_port),
         http_client: reqwest::Client::new(),
+        agent_clients: Arc::new(AgentClients::default().with_cipher(cipher.clone())),
         vector_process,
+        vector_instances,
         db,
         git_store: Arc::new(git_store),
         tap_service,
         functional_test_service,
+        health_monitor,
+        metrics_handle: telemetry::install_recorder(),
+        deployment_events: Arc::new(DeploymentEventBus::new().with_live_events(live_events.clone())),
+        live_events,
+        deployment_push_limit: Arc::new(tokio::sync::Semaphore::new(
+            deployment::max_concurrent_pushes_from_env(),
+        )),
+        shutdown: Arc::new(ShutdownCoordinator::new()),
+        bootstrap_token: Arc::new(BootstrapToken::from_env()),
+        maintenance: Arc::new(MaintenanceMode::from_env()),
+        allow_anonymous: rbac::allow_anonymous_from_env(),
+        cipher,
     });
+    if state.allow_anonymous {
+        warn!("{} is set: requests without credentials can use every API route", rbac::ALLOW_ANONYMOUS_ENV);
+    }
+    
+    // Start scheduled deployments once they are due
+    DeploymentExecutor::new(state.db.clone(), state.git_store.clone())
+        .with_events(state.deployment_events.clone())
+        .with_push_limit(state.deployment_push_limit.clone())
+        .with_shutdown(state.shutdown.clone())
+        .with_maintenance(state.maintenance.clone())
+        .with_agent_clients(state.agent_clients.clone())
+        .start_scheduler(std::time::Duration::from_secs(deployment::SCHEDULER_INTERVAL_SECS));
 
-    // CORS configuration
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // CORS configuration (VECTORIZE_CORS_ALLOWED_ORIGINS)
+    let cors = cors_layer(std::env::var("VECTORIZE_CORS_ALLOWED_ORIGINS").ok().as_deref());
 
     // Create the control plane API router
     let control_plane_api = api::create_api_router();
 
-    let app = Router::new()
+    let mut app = Router::new()
         // Health check
         .route("/health", get(health_check))
         // API info endpoint
@@ -83,15 +192,59 @@ pub async fn start_server(
         // Proxy to Vector's GraphQL API
         .route("/api/graphql", any(proxy_graphql))
         // Config endpoints - GET to read current config, POST to deploy new config
-        .route("/api/config", get(get_config).post(deploy_config))
+        .merge(api::body_limit::limit_request_bodies(
+            Router::new().route("/api/config", get(get_config).post(deploy_config)),
+            api::body_limit::max_config_body_bytes_from_env(),
+        ))
         // Control plane API (agents, groups, auth)
         .nest("/api/v1", control_plane_api)
         // Serve UI assets - index.html for root
         .route("/", get(serve_index))
         // Use fallback for all other paths (static files and SPA routing)
-        .fallback(serve_static)
+        .fallback(serve_static);
+    
+    // Prometheus metrics (unauthenticated), optionally on a dedicated port
+    match metrics_port {
+        Some(metrics_port) if metrics_port != port => {
+            let metrics_app = Router::new()
+                .route("/metrics", get(telemetry::metrics_handler))
+                .with_state(state.clone());
+            let metrics_addr = format!("0.0.0.0:{}", metrics_port);
+            let metrics_listener = tokio::net::TcpListener::bind(&metrics_addr).await?;
+            info!("Metrics listening on {}", metrics_addr);
+            
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(metrics_listener, metrics_app).await {
+                    error!("Metrics server error: {}", e);
+                }
+            });
+        }
+        _ => {
+            app = app.route("/metrics", get(telemetry::metrics_handler));
+        }
+    }
+    
+    // Per-IP / per-user request limits (VECTORIZE_RATE_LIMIT_PER_IP / _PER_TOKEN)
+    let rate_limiter = Arc::new(ApiRateLimiter::new(ApiRateLimitConfig::from_env()));
+    
+    // Mutating API calls are audited; the optional auth layer runs first so the
+    // audit record can name the authenticated user. Writes refused during
+    // maintenance are audited too. The per-IP limit applies before
+    // authentication, the per-user one after it.
+    let app = app
+        .layer(middleware::from_fn_with_state(state.clone(), api::maintenance::reject_writes))
+        .layer(middleware::from_fn_with_state(state.clone(), api::audit::record_mutations))
+        .layer(middleware::from_fn_with_state(rate_limiter.clone(), rate_limit::enforce_per_user))
+        .layer(middleware::from_fn_with_state(state.clone(), rbac::optional_auth_middleware))
+        .layer(middleware::from_fn_with_state(rate_limiter, rate_limit::enforce))
+        .layer(middleware::from_fn(telemetry::track_requests))
+        // Outermost so every log line and the response carry the request ID
+        .layer(middleware::from_fn(request_id::assign))
         .layer(cors)
-        .with_state(state);
+        // Merged after the layers so orchestrator probes skip auth, rate
+        // limiting and request metrics
+        .merge(probe_router())
+        .with_state(state.clone());
 
     let addr = format!("0.0.0.0:{}", port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
@@ -99,12 +252,72 @@ pub async fn start_server(
     info!("UI server listening on {}", addr);
 
     let handle = tokio::spawn(async move {
-        axum::serve(listener, app)
+        axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
             .await
             .map_err(|e| anyhow::anyhow!("Server error: {}", e))
     });
 
-    Ok(handle)
+    Ok(RunningServer { handle, state })
+}
+
+/// Build the CORS layer from a comma-separated list of allowed origins
+///
+/// `*` allows any origin (for development). With no origins configured, no
+/// `Access-Control-Allow-Origin` header is sent, so browsers only allow
+/// same-origin requests. Preflights allow the standard methods and the
+/// headers the auth middleware reads.
+pub fn cors_layer(allowed_origins: Option<&str>) -> CorsLayer {
+    let cors = CorsLayer::new()
+        .allow_methods([
+            axum::http::Method::GET,
+            axum::http::Method::POST,
+            axum::http::Method::PUT,
+            axum::http::Method::PATCH,
+            axum::http::Method::DELETE,
+            axum::http::Method::OPTIONS,
+        ])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::IF_MATCH,
+            header::HeaderName::from_static("x-api-key"),
+            header::HeaderName::from_static(api::deployments::IDEMPOTENCY_KEY_HEADER),
+            header::HeaderName::from_static(vectorize_shared::PROTOCOL_VERSION_HEADER),
+            header::HeaderName::from_static(request_id::REQUEST_ID_HEADER),
+        ])
+        .expose_headers([
+            header::HeaderName::from_static(request_id::REQUEST_ID_HEADER),
+            // Paginated agent and group listings report their total here
+            header::HeaderName::from_static("x-total-count"),
+        ]);
+    
+    let origins: Vec<&str> = allowed_origins
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|o| !o.is_empty())
+        .collect();
+    
+    if origins.contains(&"*") {
+        return cors.allow_origin(Any);
+    }
+    
+    let origins: Vec<header::HeaderValue> = origins
+        .into_iter()
+        .filter_map(|origin| match header::HeaderValue::from_str(origin) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                warn!("Ignoring invalid CORS origin: {}", origin);
+                None
+            }
+        })
+        .collect();
+    
+    if origins.is_empty() {
+        cors
+    } else {
+        cors.allow_origin(AllowOrigin::list(origins))
+    }
 }
 
 /// Health check endpoint
@@ -113,6 +326,130 @@ async fn health_check() -> impl IntoResponse {
     (StatusCode::OK, "OK")
 }
 
+// =============================================================================
+// Orchestrator Probes
+// =============================================================================
+
+/// Timeout for the Vector check in `/readyz`
+const READINESS_VECTOR_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Liveness and readiness probes (`/healthz`, `/readyz`), unauthenticated
+pub fn probe_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+/// Outcome of one readiness check
+#[derive(Debug, Serialize)]
+pub struct DependencyCheck {
+    pub name: &'static str,
+    /// `ok`, `failed`, or `skipped` when the dependency isn't in use
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyCheck {
+    fn from_result(name: &'static str, result: Result<(), String>) -> Self {
+        match result {
+            Ok(()) => Self { name, status: "ok", error: None },
+            Err(error) => Self { name, status: "failed", error: Some(error) },
+        }
+    }
+    
+    fn skipped(name: &'static str, reason: &str) -> Self {
+        Self { name, status: "skipped", error: Some(reason.to_string()) }
+    }
+}
+
+/// Liveness: the process is up and serving requests
+async fn healthz() -> impl IntoResponse {
+    (StatusCode::OK, Json(serde_json::json!({ "status": "ok" })))
+}
+
+/// Readiness: every dependency needed to serve traffic is available
+///
+/// Returns 503 listing the failed checks when any dependency is down.
+async fn readyz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let checks = vec![
+        DependencyCheck::from_result("database", check_database(&state).await),
+        DependencyCheck::from_result("migrations", check_migrations(&state).await),
+        DependencyCheck::from_result("git_store", check_git_store(&state)),
+        check_vector(&state).await,
+    ];
+    
+    let failed: Vec<&str> = checks.iter()
+        .filter(|c| c.status == "failed")
+        .map(|c| c.name)
+        .collect();
+    
+    if failed.is_empty() {
+        (StatusCode::OK, Json(serde_json::json!({
+            "status": "ready",
+            "checks": checks
+        }))).into_response()
+    } else {
+        warn!("Readiness check failed: {}", failed.join(", "));
+        (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({
+            "status": "not_ready",
+            "failed": failed,
+            "checks": checks
+        }))).into_response()
+    }
+}
+
+async fn check_database(state: &AppState) -> Result<(), String> {
+    sqlx::query("SELECT 1")
+        .execute(state.db.pool())
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+async fn check_migrations(state: &AppState) -> Result<(), String> {
+    let status = state.db.migration_status().await.map_err(|e| e.to_string())?;
+    
+    let pending: Vec<&str> = status.iter()
+        .filter(|m| m.applied_at.is_none())
+        .map(|m| m.version.as_str())
+        .collect();
+    if !pending.is_empty() {
+        return Err(format!("Pending migrations: {}", pending.join(", ")));
+    }
+    
+    let changed: Vec<&str> = status.iter()
+        .filter(|m| !m.checksum_matches)
+        .map(|m| m.version.as_str())
+        .collect();
+    if !changed.is_empty() {
+        return Err(format!("Migrations changed since applied: {}", changed.join(", ")));
+    }
+    
+    Ok(())
+}
+
+fn check_git_store(state: &AppState) -> Result<(), String> {
+    git2::Repository::open(state.git_store.path())
+        .map(|_| ())
+        .map_err(|e| e.message().to_string())
+}
+
+/// Only checked when Vectorize manages a local Vector process
+async fn check_vector(state: &AppState) -> DependencyCheck {
+    if state.vector_process.pid().await.is_none() {
+        return DependencyCheck::skipped("vector", "No managed Vector process");
+    }
+    
+    let url = format!("{}/health", state.vector_api_url);
+    let result = match state.http_client.get(&url).timeout(READINESS_VECTOR_TIMEOUT).send().await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(format!("Vector health returned {}", response.status())),
+        Err(e) => Err(format!("Failed to reach Vector: {}", e)),
+    };
+    DependencyCheck::from_result("vector", result)
+}
+
 /// API info endpoint - returns Vector API URL and capabilities for the UI
 async fn api_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     // Check if we can deploy (have config path access)
@@ -188,41 +525,23 @@ struct DeployConfigResponse {
 /// Reads the TOML config file and converts it to JSON format
 async fn get_config(
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     // Get the config path - only available in managed mode
-    let config_path = match state.vector_process.config_path().await {
-        Some(path) => path,
-        None => {
-            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
-                "error": "Config not available in standalone mode"
-            }))).into_response();
-        }
-    };
+    let config_path = state.vector_process.config_path().await
+        .ok_or_else(|| ApiError::bad_request("Config not available in standalone mode"))?;
     
     // Read the config file
-    let toml_content = match std::fs::read_to_string(&config_path) {
-        Ok(content) => content,
-        Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to read config file: {}", e)
-            }))).into_response();
-        }
-    };
+    let toml_content = std::fs::read_to_string(&config_path)
+        .map_err(|e| ApiError::internal(format!("Failed to read config file: {}", e)))?;
     
     // Parse TOML to structured config
-    let config: toml::Value = match toml::from_str(&toml_content) {
-        Ok(config) => config,
-        Err(e) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
-                "error": format!("Failed to parse config TOML: {}", e)
-            }))).into_response();
-        }
-    };
+    let config: toml::Value = toml::from_str(&toml_content)
+        .map_err(|e| ApiError::internal(format!("Failed to parse config TOML: {}", e)))?;
     
     // Convert TOML value to JSON for easier handling
     let json_config = toml_to_json(&config);
     
-    (StatusCode::OK, Json(json_config)).into_response()
+    Ok((StatusCode::OK, Json(json_config)))
 }
 
 /// Convert TOML value to JSON value
@@ -7,6 +7,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tracing::debug;
 
+use crate::crypto::{SecretCipher, ENCRYPTION_KEY_ENV};
+use crate::db::DbPool;
+use crate::db::repository::{SsoProviderRepository, SsoSessionRepository};
+
 // =============================================================================
 // SSO Provider Configuration
 // =============================================================================
@@ -45,8 +49,8 @@ pub struct OidcConfig {
     pub issuer: String,
     /// Client ID
     pub client_id: String,
-    /// Client secret (encrypted in storage)
-    #[serde(skip_serializing)]
+    /// Client secret (encrypted in storage, never serialized)
+    #[serde(default, skip_serializing)]
     pub client_secret: String,
     /// Authorization endpoint (auto-discovered if not set)
     pub authorization_endpoint: Option<String>,
@@ -94,6 +98,29 @@ pub struct RoleMappingConfig {
     pub role_claim: String,
     /// Mapping from claim values to Vectorize roles
     pub mappings: HashMap<String, String>,
+    /// Roles from most to least privileged, deciding which mapping wins for
+    /// users in several mapped groups (defaults to admin, operator, viewer)
+    #[serde(default)]
+    pub priority: Vec<String>,
+    /// Give users with no mapped group the default role; when false they
+    /// are denied login
+    #[serde(default = "default_true")]
+    pub allow_unmapped: bool,
+}
+
+/// Built-in roles, most privileged first
+const DEFAULT_ROLE_PRIORITY: &[&str] = &["admin", "operator", "viewer"];
+
+impl RoleMappingConfig {
+    /// Rank of a role, lower is more privileged; unknown roles rank last
+    fn rank(&self, role: &str) -> usize {
+        let position = if self.priority.is_empty() {
+            DEFAULT_ROLE_PRIORITY.iter().position(|r| *r == role)
+        } else {
+            self.priority.iter().position(|r| r == role)
+        };
+        position.unwrap_or(usize::MAX)
+    }
 }
 
 // =============================================================================
@@ -146,6 +173,64 @@ impl SsoManager {
         }
     }
     
+    /// Load persisted providers, decrypting their client secrets
+    ///
+    /// Fails if a stored secret exists but no cipher is given, so a missing
+    /// `VECTORIZE_ENCRYPTION_KEY` stops startup rather than breaking logins.
+    pub async fn load(pool: &DbPool, cipher: Option<&SecretCipher>) -> Result<Self, String> {
+        let records = SsoProviderRepository::list(pool).await
+            .map_err(|e| format!("Failed to load SSO providers: {}", e))?;
+        
+        let mut manager = Self::new();
+        for record in records {
+            let mut config: SsoProviderConfig = serde_json::from_str(&record.config)
+                .map_err(|e| format!("Invalid stored config for SSO provider {}: {}", record.id, e))?;
+            
+            if let (Some(sealed), Some(oidc)) = (&record.client_secret, config.oidc.as_mut()) {
+                let cipher = cipher.ok_or_else(|| format!(
+                    "SSO provider {} has an encrypted client secret but {} is not set",
+                    record.id, ENCRYPTION_KEY_ENV
+                ))?;
+                oidc.client_secret = cipher.decrypt(sealed)
+                    .map_err(|e| format!("SSO provider {}: {}", record.id, e))?;
+            }
+            
+            manager.add_provider(config);
+        }
+        
+        Ok(manager)
+    }
+    
+    /// Persist a provider with its client secret encrypted, then add it
+    pub async fn save_provider(
+        &mut self,
+        pool: &DbPool,
+        config: SsoProviderConfig,
+        cipher: Option<&SecretCipher>,
+    ) -> Result<(), String> {
+        let secret = config.oidc.as_ref()
+            .map(|oidc| oidc.client_secret.as_str())
+            .filter(|secret| !secret.is_empty());
+        let sealed = match secret {
+            Some(secret) => {
+                let cipher = cipher.ok_or_else(|| {
+                    format!("{} must be set to store SSO client secrets", ENCRYPTION_KEY_ENV)
+                })?;
+                Some(cipher.encrypt(secret).map_err(|e| e.to_string())?)
+            }
+            None => None,
+        };
+        
+        // `client_secret` is skipped by serde, so the JSON column never holds it
+        let json = serde_json::to_string(&config)
+            .map_err(|e| format!("Failed to serialize SSO provider: {}", e))?;
+        SsoProviderRepository::upsert(pool, &config.id, &json, sealed.as_deref()).await
+            .map_err(|e| format!("Failed to save SSO provider: {}", e))?;
+        
+        self.add_provider(config);
+        Ok(())
+    }
+    
     /// Add a provider
     pub fn add_provider(&mut self, config: SsoProviderConfig) {
         self.providers.insert(config.id.clone(), config);
@@ -208,6 +293,38 @@ impl SsoManager {
         provider_id: &str,
         code: &str,
         redirect_uri: &str,
+    ) -> Result<OidcTokenResponse, String> {
+        self.request_token(provider_id, &[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+        ])
+        .await
+        .map_err(|e| format!("Token exchange failed: {}", e))
+    }
+    
+    /// Get a fresh access token with a refresh token
+    ///
+    /// Providers that rotate refresh tokens return a new one in the
+    /// response, which must replace the old one.
+    pub async fn refresh_access_token(
+        &self,
+        provider_id: &str,
+        refresh_token: &str,
+    ) -> Result<OidcTokenResponse, String> {
+        self.request_token(provider_id, &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ])
+        .await
+        .map_err(|e| format!("Token refresh failed: {}", e))
+    }
+    
+    /// POST a grant to the provider's token endpoint
+    async fn request_token(
+        &self,
+        provider_id: &str,
+        grant: &[(&str, &str)],
     ) -> Result<OidcTokenResponse, String> {
         let provider = self.providers.get(provider_id)
             .ok_or_else(|| "Provider not found".to_string())?;
@@ -219,29 +336,84 @@ impl SsoManager {
             .map(|s| s.clone())
             .unwrap_or_else(|| format!("{}/oauth2/v1/token", oidc.issuer));
         
-        let params = [
-            ("grant_type", "authorization_code"),
-            ("code", code),
-            ("redirect_uri", redirect_uri),
-            ("client_id", &oidc.client_id),
-            ("client_secret", &oidc.client_secret),
-        ];
+        let mut params = grant.to_vec();
+        params.push(("client_id", oidc.client_id.as_str()));
+        params.push(("client_secret", oidc.client_secret.as_str()));
         
         let response = self.http_client
             .post(&token_endpoint)
             .form(&params)
             .send()
             .await
-            .map_err(|e| format!("Token request failed: {}", e))?;
+            .map_err(|e| format!("request failed: {}", e))?;
         
         if !response.status().is_success() {
-            let error = response.text().await.unwrap_or_default();
-            return Err(format!("Token exchange failed: {}", error));
+            return Err(response.text().await.unwrap_or_default());
         }
         
         response.json::<OidcTokenResponse>()
             .await
-            .map_err(|e| format!("Failed to parse token response: {}", e))
+            .map_err(|e| format!("failed to parse token response: {}", e))
+    }
+    
+    /// Remember a session's provider tokens
+    ///
+    /// The refresh token is encrypted before it is stored. Responses
+    /// without one keep the stored token, since not every provider rotates.
+    pub async fn store_session_tokens(
+        &self,
+        pool: &DbPool,
+        session_id: &str,
+        user_id: Option<&str>,
+        provider_id: &str,
+        tokens: &OidcTokenResponse,
+        cipher: &SecretCipher,
+    ) -> Result<(), String> {
+        let sealed = match tokens.refresh_token.as_deref() {
+            Some(token) => cipher.encrypt(token).map_err(|e| e.to_string())?,
+            // Keep the stored token; SQLite checks NOT NULL before the upsert's
+            // conflict clause could fall back to it
+            None => SsoSessionRepository::get(pool, session_id).await
+                .map_err(|e| format!("Failed to load SSO session: {}", e))?
+                .map(|existing| existing.refresh_token)
+                .ok_or_else(|| "Provider did not return a refresh token".to_string())?,
+        };
+        let expires_at = tokens.expires_in.map(|secs| {
+            (chrono::Utc::now() + chrono::Duration::seconds(secs as i64))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        });
+        
+        SsoSessionRepository::upsert(
+            pool,
+            session_id,
+            user_id,
+            provider_id,
+            Some(&sealed),
+            expires_at.as_deref(),
+        )
+        .await
+        .map_err(|e| format!("Failed to store SSO session: {}", e))
+    }
+    
+    /// Renew a session's access token with its stored refresh token
+    pub async fn renew_session(
+        &self,
+        pool: &DbPool,
+        session_id: &str,
+        cipher: &SecretCipher,
+    ) -> Result<OidcTokenResponse, String> {
+        let session = SsoSessionRepository::get(pool, session_id).await
+            .map_err(|e| format!("Failed to load SSO session: {}", e))?
+            .ok_or_else(|| "SSO session not found".to_string())?;
+        let refresh_token = cipher.decrypt(&session.refresh_token)
+            .map_err(|e| format!("SSO session {}: {}", session_id, e))?;
+        
+        let tokens = self.refresh_access_token(&session.provider_id, &refresh_token).await?;
+        self.store_session_tokens(pool, session_id, None, &session.provider_id, &tokens, cipher).await?;
+        
+        debug!("Renewed SSO session {} with provider {}", session_id, session.provider_id);
+        Ok(tokens)
     }
     
     /// Get user info from OIDC provider
@@ -278,33 +450,43 @@ impl SsoManager {
     }
     
     /// Map user groups/roles to Vectorize role
+    ///
+    /// Users in several mapped groups get the most privileged of their
+    /// roles. Returns `None` when the user has no mapped group and the
+    /// provider doesn't allow unmapped users, i.e. login must be denied.
     pub fn map_role(
         &self,
         provider_id: &str,
         user_info: &OidcUserInfo,
-    ) -> String {
+    ) -> Option<String> {
         let provider = match self.providers.get(provider_id) {
             Some(p) => p,
-            None => return "viewer".to_string(),
+            None => return Some("viewer".to_string()),
         };
         
         let mapping = match &provider.role_mapping {
             Some(m) => m,
-            None => return "viewer".to_string(),
+            None => return Some("viewer".to_string()),
         };
         
-        // Check if user has any mapped groups
-        if let Some(groups) = &user_info.groups {
-            for group in groups {
-                if let Some(role) = mapping.mappings.get(group) {
-                    debug!("Mapped group '{}' to role '{}'", group, role);
-                    return role.clone();
-                }
-            }
+        // Pick the highest-priority role among the user's mapped groups,
+        // breaking ties by name so the result never depends on claim order
+        let best = user_info.groups.iter()
+            .flatten()
+            .filter_map(|group| mapping.mappings.get(group).map(|role| (group, role)))
+            .min_by(|(_, a), (_, b)| mapping.rank(a).cmp(&mapping.rank(b)).then_with(|| a.cmp(b)));
+        if let Some((group, role)) = best {
+            debug!("Mapped group '{}' to role '{}'", group, role);
+            return Some(role.clone());
+        }
+        
+        if !mapping.allow_unmapped {
+            debug!("No role mapping for user '{}' and unmapped users are denied", user_info.sub);
+            return None;
         }
         
         // Return default role
-        mapping.default_role.clone()
+        Some(mapping.default_role.clone())
     }
 }
 
@@ -335,6 +517,163 @@ pub fn generate_nonce() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
+    
+    fn oidc_provider(id: &str, client_secret: &str) -> SsoProviderConfig {
+        SsoProviderConfig {
+            id: id.to_string(),
+            name: id.to_string(),
+            provider_type: SsoProviderType::Oidc,
+            enabled: true,
+            oidc: Some(OidcConfig {
+                issuer: "https://example.okta.com".to_string(),
+                client_id: "client123".to_string(),
+                client_secret: client_secret.to_string(),
+                authorization_endpoint: None,
+                token_endpoint: None,
+                userinfo_endpoint: None,
+                jwks_uri: None,
+                scopes: default_scopes(),
+            }),
+            saml: None,
+            role_mapping: None,
+        }
+    }
+    
+    #[test]
+    fn test_serialized_provider_never_contains_secret() {
+        let config = oidc_provider("okta", "plaintext-client-secret");
+        
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("plaintext-client-secret"));
+        assert!(!json.contains("client_secret"));
+        
+        // Configs without a secret still deserialize
+        let parsed: SsoProviderConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.oidc.unwrap().client_secret, "");
+    }
+    
+    #[tokio::test]
+    async fn test_provider_secret_encrypted_at_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = crate::db::Database::new(&dir.path().join("test.db")).await.unwrap();
+        let cipher = SecretCipher::from_base64(&SecretCipher::generate_key()).unwrap();
+        
+        let mut manager = SsoManager::new();
+        let err = manager.save_provider(db.pool(), oidc_provider("okta", "plaintext-client-secret"), None)
+            .await
+            .unwrap_err();
+        assert!(err.contains(ENCRYPTION_KEY_ENV));
+        
+        manager.save_provider(db.pool(), oidc_provider("okta", "plaintext-client-secret"), Some(&cipher))
+            .await
+            .unwrap();
+        
+        let (config, secret): (String, Option<String>) =
+            sqlx::query_as("SELECT config, client_secret FROM sso_providers WHERE id = 'okta'")
+                .fetch_one(db.pool())
+                .await
+                .unwrap();
+        assert!(!config.contains("plaintext-client-secret"));
+        let secret = secret.unwrap();
+        assert!(!secret.contains("plaintext-client-secret"));
+        
+        let loaded = SsoManager::load(db.pool(), Some(&cipher)).await.unwrap();
+        let oidc = loaded.get_provider("okta").unwrap().oidc.as_ref().unwrap();
+        assert_eq!(oidc.client_secret, "plaintext-client-secret");
+        
+        // Stored secrets without a key refuse to load
+        let err = SsoManager::load(db.pool(), None).await.err().unwrap();
+        assert!(err.contains(ENCRYPTION_KEY_ENV));
+        
+        let other = SecretCipher::from_base64(&SecretCipher::generate_key()).unwrap();
+        assert!(SsoManager::load(db.pool(), Some(&other)).await.is_err());
+        
+        db.close().await;
+    }
+    
+    /// Token endpoint that rotates the refresh token on every other grant
+    async fn spawn_token_endpoint() -> (String, Arc<Mutex<Vec<HashMap<String, String>>>>) {
+        let grants = Arc::new(Mutex::new(Vec::new()));
+        let seen = grants.clone();
+        let app = axum::Router::new().route("/token", axum::routing::post(
+            move |axum::Form(form): axum::Form<HashMap<String, String>>| {
+                let seen = seen.clone();
+                async move {
+                    let mut seen = seen.lock().unwrap();
+                    seen.push(form);
+                    let n = seen.len();
+                    let refresh_token = (n % 2 == 1).then(|| format!("refresh-{}", n + 1));
+                    axum::Json(serde_json::json!({
+                        "access_token": format!("access-{}", n + 1),
+                        "token_type": "Bearer",
+                        "expires_in": 3600,
+                        "refresh_token": refresh_token,
+                    }))
+                }
+            },
+        ));
+        
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (url, grants)
+    }
+    
+    #[tokio::test]
+    async fn test_renew_session_rotates_refresh_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = crate::db::Database::new(&dir.path().join("test.db")).await.unwrap();
+        let cipher = SecretCipher::from_base64(&SecretCipher::generate_key()).unwrap();
+        let (url, grants) = spawn_token_endpoint().await;
+        
+        let mut provider = oidc_provider("okta", "client-secret");
+        provider.oidc.as_mut().unwrap().token_endpoint = Some(url);
+        let mut manager = SsoManager::new();
+        manager.add_provider(provider);
+        
+        let login = OidcTokenResponse {
+            access_token: "access-1".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in: Some(300),
+            id_token: None,
+            refresh_token: Some("refresh-1".to_string()),
+        };
+        manager.store_session_tokens(db.pool(), "session1", Some("user1"), "okta", &login, &cipher)
+            .await
+            .unwrap();
+        let stored = SsoSessionRepository::get(db.pool(), "session1").await.unwrap().unwrap();
+        assert!(!stored.refresh_token.contains("refresh-1"));
+        
+        // First renewal rotates the refresh token
+        let tokens = manager.renew_session(db.pool(), "session1", &cipher).await.unwrap();
+        assert_eq!(tokens.access_token, "access-2");
+        {
+            let grants = grants.lock().unwrap();
+            assert_eq!(grants[0]["grant_type"], "refresh_token");
+            assert_eq!(grants[0]["refresh_token"], "refresh-1");
+            assert_eq!(grants[0]["client_id"], "client123");
+        }
+        let rotated = SsoSessionRepository::get(db.pool(), "session1").await.unwrap().unwrap();
+        assert_ne!(rotated.refresh_token, stored.refresh_token);
+        assert!(!rotated.refresh_token.contains("refresh-2"));
+        assert_eq!(cipher.decrypt(&rotated.refresh_token).unwrap(), "refresh-2");
+        assert_eq!(rotated.user_id.as_deref(), Some("user1"));
+        assert!(rotated.access_token_expires_at.is_some());
+        
+        // Second renewal uses the rotated token; no new one keeps it
+        let tokens = manager.renew_session(db.pool(), "session1", &cipher).await.unwrap();
+        assert_eq!(tokens.access_token, "access-3");
+        assert_eq!(grants.lock().unwrap()[1]["refresh_token"], "refresh-2");
+        let kept = SsoSessionRepository::get(db.pool(), "session1").await.unwrap().unwrap();
+        assert_eq!(cipher.decrypt(&kept.refresh_token).unwrap(), "refresh-2");
+        
+        assert!(manager.renew_session(db.pool(), "missing", &cipher).await.is_err());
+        
+        db.close().await;
+    }
     
     #[test]
     fn test_generate_state() {
@@ -368,6 +707,8 @@ mod tests {
                     ("admins".to_string(), "admin".to_string()),
                     ("operators".to_string(), "operator".to_string()),
                 ].into_iter().collect(),
+                priority: vec![],
+                allow_unmapped: true,
             }),
         };
         
@@ -401,6 +742,8 @@ mod tests {
                 mappings: [
                     ("admins".to_string(), "admin".to_string()),
                 ].into_iter().collect(),
+                priority: vec![],
+                allow_unmapped: true,
             }),
         });
         
@@ -415,7 +758,7 @@ mod tests {
             groups: Some(vec!["admins".to_string()]),
         };
         
-        assert_eq!(manager.map_role("test", &admin_user), "admin");
+        assert_eq!(manager.map_role("test", &admin_user).as_deref(), Some("admin"));
         
         // User without special group
         let regular_user = OidcUserInfo {
@@ -428,6 +771,79 @@ mod tests {
             groups: Some(vec!["users".to_string()]),
         };
         
-        assert_eq!(manager.map_role("test", &regular_user), "viewer");
+        assert_eq!(manager.map_role("test", &regular_user).as_deref(), Some("viewer"));
+    }
+    
+    fn user_in(groups: &[&str]) -> OidcUserInfo {
+        OidcUserInfo {
+            sub: "user1".to_string(),
+            email: None,
+            email_verified: None,
+            name: None,
+            preferred_username: None,
+            picture: None,
+            groups: Some(groups.iter().map(|g| g.to_string()).collect()),
+        }
+    }
+    
+    fn provider_with_mapping(mapping: RoleMappingConfig) -> SsoManager {
+        let mut provider = oidc_provider("test", "secret");
+        provider.role_mapping = Some(mapping);
+        let mut manager = SsoManager::new();
+        manager.add_provider(provider);
+        manager
+    }
+    
+    #[test]
+    fn test_role_mapping_most_privileged_wins() {
+        let mut mapping = RoleMappingConfig {
+            default_role: "viewer".to_string(),
+            role_claim: "groups".to_string(),
+            mappings: [
+                ("readers", "viewer"),
+                ("sre", "operator"),
+                ("platform", "admin"),
+                ("auditors", "auditor"),
+            ].into_iter().map(|(g, r)| (g.to_string(), r.to_string())).collect(),
+            priority: vec![],
+            allow_unmapped: true,
+        };
+        
+        // Built-in order, regardless of claim order
+        let manager = provider_with_mapping(mapping.clone());
+        assert_eq!(manager.map_role("test", &user_in(&["readers", "platform", "sre"])).as_deref(), Some("admin"));
+        assert_eq!(manager.map_role("test", &user_in(&["sre", "readers"])).as_deref(), Some("operator"));
+        assert_eq!(manager.map_role("test", &user_in(&["auditors", "readers"])).as_deref(), Some("viewer"));
+        
+        // Explicit priority list
+        mapping.priority = vec!["auditor".to_string(), "operator".to_string()];
+        let manager = provider_with_mapping(mapping);
+        assert_eq!(manager.map_role("test", &user_in(&["sre", "auditors"])).as_deref(), Some("auditor"));
+        assert_eq!(manager.map_role("test", &user_in(&["readers", "sre"])).as_deref(), Some("operator"));
+    }
+    
+    #[test]
+    fn test_role_mapping_deny_unmapped() {
+        let mapping = RoleMappingConfig {
+            default_role: "viewer".to_string(),
+            role_claim: "groups".to_string(),
+            mappings: [("admins".to_string(), "admin".to_string())].into_iter().collect(),
+            priority: vec![],
+            allow_unmapped: false,
+        };
+        let manager = provider_with_mapping(mapping);
+        
+        assert_eq!(manager.map_role("test", &user_in(&["admins"])).as_deref(), Some("admin"));
+        assert_eq!(manager.map_role("test", &user_in(&["contractors"])), None);
+        
+        let mut no_groups = user_in(&[]);
+        no_groups.groups = None;
+        assert_eq!(manager.map_role("test", &no_groups), None);
+        
+        // Older configs without the field keep granting the default role
+        let json = r#"{"default_role":"viewer","role_claim":"groups","mappings":{}}"#;
+        let parsed: RoleMappingConfig = serde_json::from_str(json).unwrap();
+        assert!(parsed.allow_unmapped);
+        assert!(parsed.priority.is_empty());
     }
 }
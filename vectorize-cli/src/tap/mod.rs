@@ -1,14 +1,20 @@
 //! Tap Service for Live Data Sampling
 //!
 //! Provides a proxy service to sample live data from Vector agents.
-//! Supports rate limiting and production-safe sampling.
+//! Supports rate limiting and production-safe sampling, including redaction
+//! of sensitive fields before events leave the control plane.
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tracing::{info, debug};
 
+use crate::rate_limit::KeyedRateLimiter;
+
+pub use vectorize_shared::redaction::{RedactionRules, REDACTED};
+
 // =============================================================================
 // Types
 // =============================================================================
@@ -87,69 +93,75 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// Redaction rules from `VECTORIZE_TAP_REDACT_FIELDS`
+///
+/// A comma-separated list of field paths. Unset means the default rules; an
+/// empty value turns redaction off.
+pub fn redaction_rules_from_env() -> RedactionRules {
+    match std::env::var("VECTORIZE_TAP_REDACT_FIELDS") {
+        Ok(fields) => RedactionRules::parse(&fields),
+        Err(_) => RedactionRules::default(),
+    }
+}
+
 // =============================================================================
 // Rate Limiter
 // =============================================================================
 
-/// Rate limiter state for an agent
-#[derive(Debug)]
-struct AgentRateLimit {
-    request_timestamps: Vec<Instant>,
-    concurrent_count: u32,
-}
-
-impl Default for AgentRateLimit {
-    fn default() -> Self {
-        Self {
-            request_timestamps: Vec::new(),
-            concurrent_count: 0,
-        }
-    }
-}
-
 /// Rate limiter for tap requests
+///
+/// Requests per minute use the shared token-bucket limiter. Concurrency is
+/// enforced with semaphores, one global and one per agent: a sample holds a
+/// permit from each for its whole duration, released when its guard drops.
 pub struct RateLimiter {
     config: RateLimitConfig,
-    agent_limits: RwLock<std::collections::HashMap<String, AgentRateLimit>>,
-    global_concurrent: RwLock<u32>,
+    requests: KeyedRateLimiter,
+    global: Arc<Semaphore>,
+    agents: Mutex<HashMap<String, Arc<Semaphore>>>,
 }
 
 impl RateLimiter {
     /// Create a new rate limiter
     pub fn new(config: RateLimitConfig) -> Self {
         Self {
+            requests: KeyedRateLimiter::per_minute(config.max_requests_per_minute),
+            global: Arc::new(Semaphore::new(config.global_max_concurrent as usize)),
+            agents: Mutex::new(HashMap::new()),
             config,
-            agent_limits: RwLock::new(std::collections::HashMap::new()),
-            global_concurrent: RwLock::new(0),
         }
     }
     
-    /// Check if a request is allowed
+    /// The limits being enforced
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+    
+    fn agent_semaphore(&self, agent_id: &str) -> Arc<Semaphore> {
+        let mut agents = self.agents.lock().unwrap_or_else(|e| e.into_inner());
+        
+        // Drop semaphores nobody holds a permit from
+        let max = self.config.max_concurrent_per_agent as usize;
+        agents.retain(|id, semaphore| id == agent_id || semaphore.available_permits() < max);
+        
+        agents.entry(agent_id.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(max)))
+            .clone()
+    }
+    
+    /// Check if a request would be allowed right now, without taking a slot
     pub async fn check(&self, agent_id: &str) -> Result<(), RateLimitError> {
-        // Check global limit
-        let global = *self.global_concurrent.read().await;
-        if global >= self.config.global_max_concurrent {
+        if self.global.available_permits() == 0 {
             return Err(RateLimitError::GlobalLimitReached);
         }
         
-        // Check per-agent limits
-        let mut limits = self.agent_limits.write().await;
-        let agent_limit = limits.entry(agent_id.to_string()).or_default();
-        
-        // Clean old timestamps (older than 1 minute)
-        let cutoff = Instant::now() - Duration::from_secs(60);
-        agent_limit.request_timestamps.retain(|t| *t > cutoff);
-        
-        // Check requests per minute
-        if agent_limit.request_timestamps.len() as u32 >= self.config.max_requests_per_minute {
+        if self.requests.check(agent_id).is_err() {
             return Err(RateLimitError::AgentRateLimitReached {
                 agent_id: agent_id.to_string(),
                 requests_per_minute: self.config.max_requests_per_minute,
             });
         }
         
-        // Check concurrent limit
-        if agent_limit.concurrent_count >= self.config.max_concurrent_per_agent {
+        if self.agent_semaphore(agent_id).available_permits() == 0 {
             return Err(RateLimitError::AgentConcurrentLimitReached {
                 agent_id: agent_id.to_string(),
                 max_concurrent: self.config.max_concurrent_per_agent,
@@ -159,47 +171,46 @@ impl RateLimiter {
         Ok(())
     }
     
-    /// Acquire a rate limit slot
+    /// Take a slot for a sample
+    ///
+    /// Fails immediately instead of waiting when a limit is exhausted. The
+    /// slot is released when the returned guard is dropped.
     pub async fn acquire(&self, agent_id: &str) -> Result<RateLimitGuard, RateLimitError> {
-        self.check(agent_id).await?;
+        let rate_limited = || RateLimitError::AgentRateLimitReached {
+            agent_id: agent_id.to_string(),
+            requests_per_minute: self.config.max_requests_per_minute,
+        };
+        self.requests.check(agent_id).map_err(|_| rate_limited())?;
         
-        // Increment counters
-        *self.global_concurrent.write().await += 1;
+        let global = self.global.clone().try_acquire_owned()
+            .map_err(|_| RateLimitError::GlobalLimitReached)?;
+        let agent = self.agent_semaphore(agent_id).try_acquire_owned()
+            .map_err(|_| RateLimitError::AgentConcurrentLimitReached {
+                agent_id: agent_id.to_string(),
+                max_concurrent: self.config.max_concurrent_per_agent,
+            })?;
         
-        let mut limits = self.agent_limits.write().await;
-        let agent_limit = limits.entry(agent_id.to_string()).or_default();
-        agent_limit.request_timestamps.push(Instant::now());
-        agent_limit.concurrent_count += 1;
+        // Only count requests that actually start
+        self.requests.try_acquire(agent_id).map_err(|_| rate_limited())?;
         
+        debug!("Acquired tap slot for {}, {} global slots left", agent_id, self.global.available_permits());
         Ok(RateLimitGuard {
-            agent_id: agent_id.to_string(),
+            _global: global,
+            _agent: agent,
         })
     }
     
-    /// Release a rate limit slot
-    pub async fn release(&self, agent_id: &str) {
-        let global = {
-            let mut g = self.global_concurrent.write().await;
-            if *g > 0 {
-                *g -= 1;
-            }
-            *g
-        };
-        debug!("Released global slot, now at {}", global);
-        
-        let mut limits = self.agent_limits.write().await;
-        if let Some(agent_limit) = limits.get_mut(agent_id) {
-            if agent_limit.concurrent_count > 0 {
-                agent_limit.concurrent_count -= 1;
-            }
-        }
+    /// Samples currently running, across all agents
+    pub fn active_samples(&self) -> usize {
+        self.config.global_max_concurrent as usize - self.global.available_permits()
     }
 }
 
-/// Guard that releases the rate limit slot when dropped
+/// A running sample's slot; released when dropped
+#[derive(Debug)]
 pub struct RateLimitGuard {
-    #[allow(dead_code)]
-    agent_id: String,
+    _global: OwnedSemaphorePermit,
+    _agent: OwnedSemaphorePermit,
 }
 
 /// Rate limit error
@@ -216,6 +227,19 @@ pub enum RateLimitError {
     },
 }
 
+impl RateLimitError {
+    /// Suggested wait before retrying
+    pub fn retry_after_secs(&self) -> u64 {
+        match self {
+            RateLimitError::AgentRateLimitReached { requests_per_minute, .. } => {
+                (60 / (*requests_per_minute).max(1) as u64).max(1)
+            }
+            // Concurrent slots free up as soon as a running sample finishes
+            _ => 1,
+        }
+    }
+}
+
 impl std::fmt::Display for RateLimitError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -297,12 +321,10 @@ impl TapService {
         }
         
         // For now, return an empty response since real sampling uses WebSocket
-        // This endpoint validates connectivity and rate limiting
+        // This endpoint validates connectivity and rate limiting. The slot is
+        // released when `_guard` drops, including on the error paths above.
         let duration_ms = start.elapsed().as_millis() as u64;
         
-        // Release rate limit
-        self.rate_limiter.release(&request.agent_id).await;
-        
         Ok(SampleResponse {
             agent_id: request.agent_id.clone(),
             events: Vec::new(),
@@ -321,6 +343,11 @@ impl TapService {
     pub fn rate_limiter(&self) -> Arc<RateLimiter> {
         self.rate_limiter.clone()
     }
+    
+    /// The limits being enforced
+    pub fn config(&self) -> &RateLimitConfig {
+        self.rate_limiter.config()
+    }
 }
 
 /// Tap service error
@@ -383,15 +410,102 @@ mod tests {
         });
         
         // First request should succeed
-        let _guard1 = limiter.acquire("agent-1").await.unwrap();
+        let guard1 = limiter.acquire("agent-1").await.unwrap();
         
         // Second concurrent request should fail
         let result = limiter.check("agent-1").await;
         assert!(matches!(result, Err(RateLimitError::AgentConcurrentLimitReached { .. })));
+        let result = limiter.acquire("agent-1").await;
+        assert!(matches!(result, Err(RateLimitError::AgentConcurrentLimitReached { .. })));
         
         // Release and try again
-        limiter.release("agent-1").await;
+        drop(guard1);
         let result = limiter.check("agent-1").await;
         assert!(result.is_ok());
     }
+    
+    #[tokio::test]
+    async fn test_rate_limiter_requests_per_minute() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_requests_per_minute: 2,
+            max_concurrent_per_agent: 10,
+            global_max_concurrent: 10,
+        });
+        
+        for _ in 0..2 {
+            drop(limiter.acquire("agent-1").await.unwrap());
+        }
+        
+        let result = limiter.check("agent-1").await;
+        assert!(matches!(result, Err(RateLimitError::AgentRateLimitReached { .. })));
+        assert!(limiter.check("agent-2").await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_global_concurrent_limit() {
+        let config = RateLimitConfig {
+            max_requests_per_minute: 100,
+            max_concurrent_per_agent: 2,
+            global_max_concurrent: 3,
+        };
+        let limiter = RateLimiter::new(config.clone());
+        
+        let mut guards = Vec::new();
+        for i in 0..config.global_max_concurrent {
+            guards.push(limiter.acquire(&format!("agent-{}", i)).await.unwrap());
+        }
+        assert_eq!(limiter.active_samples(), 3);
+        
+        // One more than the global limit, on an otherwise idle agent
+        let result = limiter.acquire("agent-extra").await;
+        assert!(matches!(result, Err(RateLimitError::GlobalLimitReached)));
+        assert!(matches!(limiter.check("agent-extra").await, Err(RateLimitError::GlobalLimitReached)));
+        
+        drop(guards);
+        assert_eq!(limiter.active_samples(), 0);
+        assert!(limiter.acquire("agent-extra").await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_sample_holds_slot_until_finished() {
+        use axum::{routing::post, Router};
+        
+        // An agent that answers slowly, so samples overlap
+        let app = Router::new().route("/graphql", post(|| async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            axum::Json(serde_json::json!({ "data": { "componentErrors": [] } }))
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        
+        let service = Arc::new(TapService::new(RateLimitConfig {
+            max_requests_per_minute: 100,
+            max_concurrent_per_agent: 2,
+            global_max_concurrent: 2,
+        }));
+        let request = |agent_id: &str| SampleRequest {
+            agent_id: agent_id.to_string(),
+            patterns: vec!["*".to_string()],
+            limit: 10,
+            timeout_secs: 5,
+        };
+        
+        let running: Vec<_> = ["a", "b"].into_iter().map(|agent_id| {
+            let (service, url, request) = (service.clone(), url.clone(), request(agent_id));
+            tokio::spawn(async move { service.sample(&url, &request).await })
+        }).collect();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        
+        let result = service.sample(&url, &request("c")).await;
+        assert!(matches!(result, Err(TapError::RateLimited(RateLimitError::GlobalLimitReached))));
+        assert!(service.can_sample("c").await.is_err());
+        
+        for sample in running {
+            assert!(sample.await.unwrap().is_ok());
+        }
+        assert!(service.can_sample("c").await.is_ok());
+    }
 }
@@ -0,0 +1,106 @@
+//! Control plane telemetry
+//!
+//! Exposes Vectorize's own metrics in Prometheus format:
+//! - `vectorize_api_requests_total`: API requests by method, route and status
+//! - `vectorize_agents`: agents by health status (from the health monitor)
+//! - `vectorize_deployments_total`: finished deployments by outcome
+//! - `vectorize_deployment_duration_seconds`: deployment durations by outcome
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::server::AppState;
+
+/// Deployment duration buckets, in seconds
+const DEPLOYMENT_DURATION_BUCKETS: &[f64] = &[1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0];
+
+static RECORDER: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the global Prometheus recorder
+///
+/// The recorder is process-wide, so repeated calls return the same handle.
+pub fn install_recorder() -> PrometheusHandle {
+    RECORDER
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(
+                    Matcher::Full("vectorize_deployment_duration_seconds".to_string()),
+                    DEPLOYMENT_DURATION_BUCKETS,
+                )
+                .expect("Deployment duration buckets must not be empty")
+                .install_recorder()
+                .expect("Failed to install Prometheus recorder")
+        })
+        .clone()
+}
+
+/// Middleware that counts requests by method, matched route and status
+///
+/// Uses the route template (e.g. `/api/v1/agents/:id`) rather than the raw
+/// path so agent IDs don't explode label cardinality.
+pub async fn track_requests(req: Request, next: Next) -> Response {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "fallback".to_string());
+    
+    let response = next.run(req).await;
+    
+    metrics::counter!(
+        "vectorize_api_requests_total",
+        "method" => method,
+        "route" => route,
+        "status" => response.status().as_u16().to_string()
+    )
+    .increment(1);
+    
+    response
+}
+
+/// Record a finished deployment
+pub fn record_deployment(outcome: &'static str, duration: Duration) {
+    metrics::counter!("vectorize_deployments_total", "outcome" => outcome).increment(1);
+    metrics::histogram!("vectorize_deployment_duration_seconds", "outcome" => outcome)
+        .record(duration.as_secs_f64());
+}
+
+/// Prometheus scrape endpoint
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    // Agent gauges are sampled at scrape time from the health monitor
+    let summary = state.health_monitor.get_summary().await;
+    metrics::gauge!("vectorize_agents", "status" => "healthy").set(summary.healthy_agents as f64);
+    metrics::gauge!("vectorize_agents", "status" => "unhealthy").set(summary.unhealthy_agents as f64);
+    metrics::gauge!("vectorize_agents", "status" => "unreachable").set(summary.unreachable_agents as f64);
+    metrics::gauge!("vectorize_agents", "status" => "stale").set(summary.stale_agents as f64);
+    
+    state.metrics_handle.run_upkeep();
+    
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics_handle.render(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_deployment_metrics_rendered() {
+        let handle = install_recorder();
+        record_deployment("completed", Duration::from_secs(3));
+        
+        let output = handle.render();
+        assert!(output.contains("vectorize_deployments_total{outcome=\"completed\"}"));
+        assert!(output.contains("vectorize_deployment_duration_seconds_bucket"));
+    }
+}
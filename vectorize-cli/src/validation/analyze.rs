@@ -0,0 +1,203 @@
+//! Config size and complexity report
+//!
+//! A summary for reviewers gauging how risky a change is: how many
+//! components of each kind, how many VRL programs, how long the longest
+//! pipeline is, and which components fan out to many consumers. Built from
+//! the component graph, so it agrees with `explain`.
+
+use std::collections::{BTreeMap, HashMap};
+use serde::Serialize;
+
+use super::graph::ComponentGraph;
+use super::required_fields::ComponentKind;
+
+/// Components feeding at least this many consumers are reported as hotspots
+pub const FAN_OUT_THRESHOLD: usize = 3;
+
+/// Transform types whose behaviour is a VRL program
+const VRL_TYPES: &[&str] = &["remap"];
+
+/// A component feeding many consumers
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FanOut {
+    pub component: String,
+    /// Number of distinct components reading from it
+    pub consumers: usize,
+}
+
+/// Size and complexity of a config
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ConfigReport {
+    pub sources: usize,
+    pub transforms: usize,
+    pub sinks: usize,
+    /// Component count per type (e.g. `remap`, `aws_s3`)
+    pub types: BTreeMap<String, usize>,
+    /// Transforms running a VRL program
+    pub vrl_transforms: usize,
+    /// Components on the longest source-to-sink path; components in a
+    /// cycle don't extend it
+    pub max_depth: usize,
+    /// Components with at least `FAN_OUT_THRESHOLD` consumers, most first
+    pub fan_out: Vec<FanOut>,
+    pub edges: usize,
+    pub has_cycle: bool,
+}
+
+impl ConfigReport {
+    /// Summarize a component graph
+    pub fn from_graph(graph: &ComponentGraph) -> Self {
+        let mut report = Self {
+            edges: graph.edges.len(),
+            has_cycle: graph.has_cycle,
+            ..Self::default()
+        };
+
+        for node in &graph.nodes {
+            match node.kind {
+                ComponentKind::Source => report.sources += 1,
+                ComponentKind::Transform => report.transforms += 1,
+                ComponentKind::Sink => report.sinks += 1,
+            }
+            if let Some(component_type) = &node.component_type {
+                *report.types.entry(component_type.clone()).or_default() += 1;
+                if node.kind == ComponentKind::Transform && VRL_TYPES.contains(&component_type.as_str()) {
+                    report.vrl_transforms += 1;
+                }
+            }
+        }
+
+        // Distinct consumers: `route.a` and `route.b` into one sink count once
+        let mut downstream: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for edge in &graph.edges {
+            let consumers = downstream.entry(edge.from.as_str()).or_default();
+            if !consumers.contains(&edge.to.as_str()) {
+                consumers.push(edge.to.as_str());
+            }
+        }
+
+        report.fan_out = downstream.iter()
+            .filter(|(_, consumers)| consumers.len() >= FAN_OUT_THRESHOLD)
+            .map(|(component, consumers)| FanOut {
+                component: component.to_string(),
+                consumers: consumers.len(),
+            })
+            .collect();
+        report.fan_out.sort_by_key(|f| std::cmp::Reverse(f.consumers));
+
+        let in_cycle: Vec<&str> = graph.nodes.iter()
+            .filter(|n| n.in_cycle)
+            .map(|n| n.id.as_str())
+            .collect();
+        let mut depths = HashMap::new();
+        report.max_depth = graph.nodes.iter()
+            .filter(|n| !n.in_cycle)
+            .map(|n| depth(&downstream, &in_cycle, n.id.as_str(), &mut depths))
+            .max()
+            .unwrap_or(0);
+
+        report
+    }
+}
+
+/// Components on the longest path starting at `id`, skipping cycle members
+fn depth<'a>(
+    downstream: &BTreeMap<&'a str, Vec<&'a str>>,
+    in_cycle: &[&str],
+    id: &'a str,
+    memo: &mut HashMap<&'a str, usize>,
+) -> usize {
+    if let Some(&known) = memo.get(id) {
+        return known;
+    }
+    let longest = downstream.get(id).into_iter().flatten()
+        .filter(|next| !in_cycle.contains(next))
+        .map(|next| depth(downstream, in_cycle, next, memo))
+        .max()
+        .unwrap_or(0);
+    memo.insert(id, longest + 1);
+    longest + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(config: &str) -> ConfigReport {
+        ConfigReport::from_graph(&ComponentGraph::from_config(&toml::from_str(config).unwrap()))
+    }
+
+    #[test]
+    fn test_counts_depth_and_fan_out() {
+        let report = report(r#"
+[sources.app]
+type = "file"
+include = ["/var/log/app.log"]
+
+[sources.sys]
+type = "journald"
+
+[transforms.parse]
+type = "remap"
+inputs = ["app", "sys"]
+
+[transforms.enrich]
+type = "remap"
+inputs = ["parse"]
+
+[transforms.split]
+type = "route"
+inputs = ["enrich"]
+
+[sinks.errors]
+type = "console"
+inputs = ["split.errors"]
+
+[sinks.archive]
+type = "aws_s3"
+inputs = ["parse", "split._unmatched", "split.errors"]
+
+[sinks.search]
+type = "elasticsearch"
+inputs = ["parse"]
+
+[sinks.debug]
+type = "console"
+inputs = ["parse"]
+"#);
+
+        assert_eq!(report.sources, 2);
+        assert_eq!(report.transforms, 3);
+        assert_eq!(report.sinks, 4);
+        assert_eq!(report.vrl_transforms, 2);
+        assert_eq!(report.types.get("console"), Some(&2));
+        assert_eq!(report.types.get("remap"), Some(&2));
+        // app -> parse -> enrich -> split -> archive
+        assert_eq!(report.max_depth, 5);
+        assert_eq!(report.fan_out, vec![FanOut { component: "parse".to_string(), consumers: 4 }]);
+        assert!(!report.has_cycle);
+    }
+
+    #[test]
+    fn test_cycle_does_not_extend_depth() {
+        let report = report(r#"
+[sources.in]
+type = "stdin"
+
+[transforms.a]
+type = "remap"
+inputs = ["in", "b"]
+
+[transforms.b]
+type = "remap"
+inputs = ["a"]
+
+[sinks.out]
+type = "console"
+inputs = ["in"]
+"#);
+
+        assert!(report.has_cycle);
+        assert_eq!(report.max_depth, 2);
+    }
+}
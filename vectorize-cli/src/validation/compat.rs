@@ -0,0 +1,156 @@
+//! Vector version compatibility
+//!
+//! Component types only exist from the Vector release that introduced them.
+//! Checking a config against the oldest Vector in a group catches, before a
+//! deploy, configs that some agents would reject.
+
+use std::fmt;
+
+use super::ValidationWarning;
+use super::graph::components;
+use super::required_fields::{kind_label, ComponentKind};
+
+/// First Vector release (major, minor) shipping each component type
+///
+/// Only types added after 0.20 are listed; anything else is assumed to be
+/// available everywhere.
+const INTRODUCED_IN: &[(ComponentKind, &str, (u32, u32))] = &[
+    // Sources
+    (ComponentKind::Source, "http_client", (0, 24)),
+    (ComponentKind::Source, "opentelemetry", (0, 26)),
+    (ComponentKind::Source, "static_metrics", (0, 40)),
+    // Transforms
+    (ComponentKind::Transform, "aggregate", (0, 23)),
+    (ComponentKind::Transform, "exclusive_route", (0, 45)),
+    // Sinks
+    (ComponentKind::Sink, "webhdfs", (0, 26)),
+    (ComponentKind::Sink, "gcp_chronicle_unstructured", (0, 26)),
+    (ComponentKind::Sink, "databend", (0, 27)),
+    (ComponentKind::Sink, "greptimedb", (0, 30)),
+    (ComponentKind::Sink, "mqtt", (0, 36)),
+    (ComponentKind::Sink, "opentelemetry", (0, 38)),
+];
+
+/// A Vector release version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VectorVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl VectorVersion {
+    /// Parse a version as agents report it
+    ///
+    /// Accepts `0.40.1`, `v0.40`, `0.40.1-nightly` and the full
+    /// `vector 0.40.1 (x86_64-unknown-linux-gnu ...)` banner.
+    pub fn parse(value: &str) -> Option<Self> {
+        value.split_whitespace().find_map(|token| {
+            let token = token.strip_prefix('v').unwrap_or(token);
+            let core = token.split(['-', '+']).next()?;
+            let mut parts = core.split('.');
+            let major = parts.next()?.parse().ok()?;
+            let minor = parts.next()?.parse().ok()?;
+            let patch = match parts.next() {
+                Some(patch) => patch.parse().ok()?,
+                None => 0,
+            };
+            Some(Self { major, minor, patch })
+        })
+    }
+    
+    /// The oldest parseable version in `versions`
+    pub fn oldest<'a>(versions: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        versions.into_iter().filter_map(Self::parse).min()
+    }
+}
+
+impl fmt::Display for VectorVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Warn about components that need a newer Vector than `min_version`
+pub fn check(config: &toml::Value, min_version: VectorVersion) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+    
+    for kind in [ComponentKind::Source, ComponentKind::Transform, ComponentKind::Sink] {
+        for (name, component) in components(config, kind) {
+            let Some(component_type) = component.get("type").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let Some((_, _, (major, minor))) = INTRODUCED_IN
+                .iter()
+                .find(|(k, t, _)| *k == kind && *t == component_type)
+            else {
+                continue;
+            };
+            
+            let required = VectorVersion { major: *major, minor: *minor, patch: 0 };
+            if required > min_version {
+                warnings.push(ValidationWarning {
+                    code: "VECTOR_VERSION_TOO_OLD".to_string(),
+                    message: format!(
+                        "{} '{}' uses type '{}', which needs Vector {}.{} or newer; the oldest agent runs {}",
+                        kind_label(kind), name, component_type, major, minor, min_version
+                    ),
+                    component: Some(name.clone()),
+                });
+            }
+        }
+    }
+    
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn version(major: u32, minor: u32, patch: u32) -> VectorVersion {
+        VectorVersion { major, minor, patch }
+    }
+    
+    #[test]
+    fn test_parse_reported_versions() {
+        assert_eq!(VectorVersion::parse("0.40.1"), Some(version(0, 40, 1)));
+        assert_eq!(VectorVersion::parse("v0.34"), Some(version(0, 34, 0)));
+        assert_eq!(VectorVersion::parse("0.41.0-nightly"), Some(version(0, 41, 0)));
+        assert_eq!(
+            VectorVersion::parse("vector 0.38.0 (x86_64-unknown-linux-gnu ea0ec6f 2024-05-07)"),
+            Some(version(0, 38, 0))
+        );
+        assert_eq!(VectorVersion::parse("unknown"), None);
+        
+        assert_eq!(VectorVersion::oldest(["0.40.1", "garbage", "0.9.2", "0.30.0"]), Some(version(0, 9, 2)));
+        assert_eq!(VectorVersion::oldest([]), None);
+    }
+    
+    #[test]
+    fn test_component_newer_than_oldest_agent() {
+        let config: toml::Value = toml::from_str(r#"
+[sources.in]
+type = "stdin"
+
+[sinks.metrics_db]
+type = "greptimedb"
+inputs = ["in"]
+endpoint = "greptimedb:4001"
+
+[sinks.out]
+type = "console"
+inputs = ["in"]
+encoding.codec = "json"
+"#).unwrap();
+        
+        let warnings = check(&config, version(0, 28, 1));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "VECTOR_VERSION_TOO_OLD");
+        assert_eq!(warnings[0].component.as_deref(), Some("metrics_db"));
+        assert!(warnings[0].message.contains("needs Vector 0.30"), "{}", warnings[0].message);
+        assert!(warnings[0].message.contains("runs 0.28.1"), "{}", warnings[0].message);
+        
+        assert!(check(&config, version(0, 30, 0)).is_empty());
+    }
+}
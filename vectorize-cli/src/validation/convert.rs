@@ -0,0 +1,407 @@
+//! Config format conversion
+//!
+//! Converts a config between TOML, YAML and JSON by parsing it into an
+//! ordered [`ConfigValue`] and writing that out in the target format. Keys
+//! keep their source order, so components come out in the order they were
+//! written; TOML is the exception only in that a table's plain values are
+//! written before its sub-tables.
+//!
+//! Values the target format cannot hold (a null in TOML, a TOML datetime or
+//! a NaN in JSON) fail the conversion rather than being silently changed.
+
+use std::fmt;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::yaml;
+
+/// Field name the `toml` crate uses to pass datetimes through serde
+const TOML_DATETIME_FIELD: &str = "$__toml_private_datetime";
+
+/// A config file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    Toml,
+    #[serde(alias = "yml")]
+    Yaml,
+    Json,
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Toml => write!(f, "TOML"),
+            Self::Yaml => write!(f, "YAML"),
+            Self::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+/// Why a conversion failed
+#[derive(Debug, thiserror::Error)]
+pub enum ConversionError {
+    /// The input is not valid in its declared format
+    #[error("Invalid {format}: {message}")]
+    Parse {
+        format: ConfigFormat,
+        message: String,
+        line: Option<usize>,
+    },
+    /// The config holds a value the target format cannot represent
+    #[error("Cannot convert to {format}: {reason} at `{path}`")]
+    Lossy {
+        format: ConfigFormat,
+        path: String,
+        reason: String,
+    },
+    /// The target writer rejected the value
+    #[error("Failed to write {format}: {message}")]
+    Write {
+        format: ConfigFormat,
+        message: String,
+    },
+}
+
+impl ConversionError {
+    /// Stable code for API error details
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Parse { .. } => "PARSE_ERROR",
+            Self::Lossy { .. } => "LOSSY_CONVERSION",
+            Self::Write { .. } => "WRITE_ERROR",
+        }
+    }
+    
+    pub(crate) fn parse(format: ConfigFormat, message: impl Into<String>, line: Option<usize>) -> Self {
+        Self::Parse { format, message: message.into(), line }
+    }
+}
+
+/// A config value with table keys in source order
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    /// A TOML date, time or datetime, kept as written
+    Datetime(String),
+    Array(Vec<ConfigValue>),
+    Table(Vec<(String, ConfigValue)>),
+}
+
+impl ConfigValue {
+    /// Value of a key in a table
+    pub fn get(&self, key: &str) -> Option<&ConfigValue> {
+        match self {
+            Self::Table(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    
+    /// Keys of a table, in order
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            Self::Table(entries) => entries.iter().map(|(k, _)| k.as_str()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Convert a config from one format to another
+pub fn convert(config: &str, from: ConfigFormat, to: ConfigFormat) -> Result<String, ConversionError> {
+    let value = parse(config, from)?;
+    write(&value, to)
+}
+
+/// Parse a config in the given format
+///
+/// A config is always a table, so any other top-level value is rejected.
+pub fn parse(config: &str, format: ConfigFormat) -> Result<ConfigValue, ConversionError> {
+    let value = match format {
+        ConfigFormat::Toml => toml::from_str::<ConfigValue>(config).map_err(|e| {
+            let line = e.span().map(|span| config[..span.start].matches('\n').count() + 1);
+            ConversionError::parse(format, e.message(), line)
+        })?,
+        ConfigFormat::Json => serde_json::from_str::<ConfigValue>(config).map_err(|e| {
+            ConversionError::parse(format, e.to_string(), Some(e.line()))
+        })?,
+        ConfigFormat::Yaml => yaml::parse(config)?,
+    };
+    
+    if !matches!(value, ConfigValue::Table(_)) {
+        return Err(ConversionError::parse(format, "the top level of a config must be a table", None));
+    }
+    Ok(value)
+}
+
+/// Write a config in the given format
+pub fn write(value: &ConfigValue, format: ConfigFormat) -> Result<String, ConversionError> {
+    check_representable(value, format, &mut String::new())?;
+    
+    match format {
+        ConfigFormat::Toml => toml::to_string(value).map_err(|e| ConversionError::Write {
+            format,
+            message: e.to_string(),
+        }),
+        ConfigFormat::Json => serde_json::to_string_pretty(value)
+            .map(|json| json + "\n")
+            .map_err(|e| ConversionError::Write { format, message: e.to_string() }),
+        ConfigFormat::Yaml => Ok(yaml::write(value)),
+    }
+}
+
+/// Fail on the first value `format` cannot hold as-is
+fn check_representable(value: &ConfigValue, format: ConfigFormat, path: &mut String) -> Result<(), ConversionError> {
+    let lossy = |path: &str, reason: &str| ConversionError::Lossy {
+        format,
+        path: if path.is_empty() { "(root)".to_string() } else { path.to_string() },
+        reason: reason.to_string(),
+    };
+    
+    match value {
+        ConfigValue::Null if format == ConfigFormat::Toml => {
+            Err(lossy(path.as_str(), "TOML has no null value"))
+        }
+        ConfigValue::Float(f) if format == ConfigFormat::Json && !f.is_finite() => {
+            Err(lossy(path.as_str(), "JSON has no NaN or infinite numbers"))
+        }
+        ConfigValue::Datetime(_) if format != ConfigFormat::Toml => {
+            Err(lossy(path.as_str(), "datetimes only exist in TOML"))
+        }
+        ConfigValue::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{}]", index));
+                check_representable(item, format, path)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        ConfigValue::Table(entries) => {
+            for (key, item) in entries {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                check_representable(item, format, path)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+impl Serialize for ConfigValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Integer(i) => serializer.serialize_i64(*i),
+            Self::Float(f) => serializer.serialize_f64(*f),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Datetime(s) => s.parse::<toml::value::Datetime>()
+                .map_err(serde::ser::Error::custom)?
+                .serialize(serializer),
+            Self::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Self::Table(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ConfigValueVisitor)
+    }
+}
+
+struct ConfigValueVisitor;
+
+impl<'de> Visitor<'de> for ConfigValueVisitor {
+    type Value = ConfigValue;
+    
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a config value")
+    }
+    
+    fn visit_unit<E: de::Error>(self) -> Result<ConfigValue, E> {
+        Ok(ConfigValue::Null)
+    }
+    
+    fn visit_none<E: de::Error>(self) -> Result<ConfigValue, E> {
+        Ok(ConfigValue::Null)
+    }
+    
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<ConfigValue, E> {
+        Ok(ConfigValue::Bool(v))
+    }
+    
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<ConfigValue, E> {
+        Ok(ConfigValue::Integer(v))
+    }
+    
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<ConfigValue, E> {
+        i64::try_from(v)
+            .map(ConfigValue::Integer)
+            .map_err(|_| E::custom(format!("integer {} is too large", v)))
+    }
+    
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<ConfigValue, E> {
+        Ok(ConfigValue::Float(v))
+    }
+    
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<ConfigValue, E> {
+        Ok(ConfigValue::String(v.to_string()))
+    }
+    
+    fn visit_string<E: de::Error>(self, v: String) -> Result<ConfigValue, E> {
+        Ok(ConfigValue::String(v))
+    }
+    
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<ConfigValue, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(ConfigValue::Array(items))
+    }
+    
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<ConfigValue, A::Error> {
+        let mut entries: Vec<(String, ConfigValue)> = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == TOML_DATETIME_FIELD && entries.is_empty() {
+                return Ok(ConfigValue::Datetime(map.next_value()?));
+            }
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(de::Error::custom(format!("duplicate key `{}`", key)));
+            }
+            let value = map.next_value()?;
+            entries.push((key, value));
+        }
+        Ok(ConfigValue::Table(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const CONFIG: &str = r#"[sources.syslog]
+type = "syslog"
+address = "0.0.0.0:514"
+mode = "tcp"
+
+[sources.app]
+type = "file"
+include = ["/var/log/app/*.log"]
+read_from = "beginning"
+
+[transforms.parse]
+type = "remap"
+inputs = ["syslog", "app"]
+source = '''
+. = parse_json!(.message)
+.env = "prod" # tag it
+'''
+
+[transforms.route]
+type = "route"
+inputs = ["parse"]
+route.errors = '.level == "error"'
+
+[sinks.errors]
+type = "console"
+inputs = ["route.errors"]
+encoding.codec = "json"
+
+[sinks.archive]
+type = "aws_s3"
+inputs = ["parse"]
+bucket = "logs"
+batch.max_bytes = 10485760
+batch.timeout_secs = 1.5
+healthcheck.enabled = false
+
+[[sinks.archive.headers]]
+name = "x-team"
+value = "platform"
+"#;
+
+    fn toml_value(config: &str) -> toml::Value {
+        toml::from_str(config).unwrap()
+    }
+    
+    #[test]
+    fn test_toml_yaml_toml_round_trip() {
+        let yaml = convert(CONFIG, ConfigFormat::Toml, ConfigFormat::Yaml).unwrap();
+        let back = convert(&yaml, ConfigFormat::Yaml, ConfigFormat::Toml).unwrap();
+        assert_eq!(toml_value(&back), toml_value(CONFIG));
+    
+        // Multi-line VRL survives as a literal block
+        assert!(yaml.contains("    source: |\n      . = parse_json!(.message)\n"));
+    
+        let json = convert(&yaml, ConfigFormat::Yaml, ConfigFormat::Json).unwrap();
+        let back = convert(&json, ConfigFormat::Json, ConfigFormat::Toml).unwrap();
+        assert_eq!(toml_value(&back), toml_value(CONFIG));
+    }
+    
+    #[test]
+    fn test_component_order_is_preserved() {
+        for format in [ConfigFormat::Yaml, ConfigFormat::Json, ConfigFormat::Toml] {
+            let converted = convert(CONFIG, ConfigFormat::Toml, format).unwrap();
+            let value = parse(&converted, format).unwrap();
+    
+            assert_eq!(value.keys(), vec!["sources", "transforms", "sinks"], "{}", format);
+            assert_eq!(value.get("sources").unwrap().keys(), vec!["syslog", "app"], "{}", format);
+            assert_eq!(value.get("sinks").unwrap().keys(), vec!["errors", "archive"], "{}", format);
+        }
+    }
+    
+    #[test]
+    fn test_lossy_conversions_are_rejected() {
+        let err = convert(r#"{"sinks": {"out": {"type": "console", "buffer": null}}}"#, ConfigFormat::Json, ConfigFormat::Toml)
+            .unwrap_err();
+        assert_eq!(err.code(), "LOSSY_CONVERSION");
+        assert!(matches!(&err, ConversionError::Lossy { path, .. } if path == "sinks.out.buffer"));
+    
+        let err = convert("[sources.in]\nsince = 2024-06-01T00:00:00Z\n", ConfigFormat::Toml, ConfigFormat::Json)
+            .unwrap_err();
+        assert!(matches!(&err, ConversionError::Lossy { path, .. } if path == "sources.in.since"));
+    
+        let err = convert("values:\n  - .nan\n", ConfigFormat::Yaml, ConfigFormat::Json).unwrap_err();
+        assert!(matches!(&err, ConversionError::Lossy { path, .. } if path == "values[0]"));
+    
+        // Datetimes are kept when staying in TOML
+        let toml = convert("[sources.in]\nsince = 2024-06-01T00:00:00Z\n", ConfigFormat::Toml, ConfigFormat::Toml).unwrap();
+        assert!(toml.contains("since = 2024-06-01T00:00:00Z"));
+    }
+    
+    #[test]
+    fn test_invalid_input_is_a_parse_error() {
+        let err = convert("[sources.in\ntype = 1\n", ConfigFormat::Toml, ConfigFormat::Yaml).unwrap_err();
+        assert!(matches!(err, ConversionError::Parse { format: ConfigFormat::Toml, line: Some(1), .. }));
+    
+        let err = convert("[1, 2]", ConfigFormat::Json, ConfigFormat::Toml).unwrap_err();
+        assert_eq!(err.code(), "PARSE_ERROR");
+    
+        let err = convert(r#"{"a": 1, "a": 2}"#, ConfigFormat::Json, ConfigFormat::Yaml).unwrap_err();
+        assert!(err.to_string().contains("duplicate key `a`"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Enrichment table checks
+//!
+//! Validates `[enrichment_tables.*]` definitions and the tables transforms
+//! look up from VRL with `get_enrichment_table_record` /
+//! `find_enrichment_table_records`.
+
+use std::collections::BTreeSet;
+
+use super::{ValidationError, ValidationWarning};
+
+/// Required fields per enrichment table type, as alternatives like
+/// `required_fields`; types not listed are not checked
+const REQUIREMENTS: &[(&str, &[&[&str]])] = &[
+    ("file", &[&["file.path"], &["file.encoding.type"]]),
+    ("geoip", &[&["path"]]),
+    ("mmdb", &[&["path"]]),
+];
+
+/// VRL functions whose first argument names an enrichment table
+const LOOKUP_FUNCTIONS: &[&str] = &["get_enrichment_table_record", "find_enrichment_table_records"];
+
+/// Check enrichment tables and the references to them
+///
+/// A table without a type or a required field, or a lookup of an undefined
+/// table, is an error. Tables no transform looks up only get a warning.
+pub fn check(config: &toml::Value) -> Result<Vec<ValidationWarning>, ValidationError> {
+    let tables = config.get("enrichment_tables").and_then(|v| v.as_table());
+    
+    if let Some(tables) = tables {
+        for (name, table) in tables {
+            let Some(table_type) = table.get("type").and_then(|v| v.as_str()) else {
+                return Err(error(
+                    "MISSING_TYPE",
+                    format!("Enrichment table '{}' has no type specified", name),
+                    name,
+                ));
+            };
+            if let Some(field) = missing_fields(table_type, table).first() {
+                return Err(error(
+                    "MISSING_REQUIRED_FIELD",
+                    format!(
+                        "Enrichment table '{}' of type '{}' is missing required field '{}'",
+                        name, table_type, field
+                    ),
+                    name,
+                ));
+            }
+        }
+    }
+    
+    let mut referenced = BTreeSet::new();
+    if let Some(transforms) = config.get("transforms").and_then(|v| v.as_table()) {
+        for (name, transform) in transforms {
+            let mut lookups = Vec::new();
+            collect_lookups(transform, &mut lookups);
+            for table in lookups {
+                if !tables.is_some_and(|tables| tables.contains_key(&table)) {
+                    return Err(error(
+                        "UNKNOWN_ENRICHMENT_TABLE",
+                        format!("Transform '{}' looks up unknown enrichment table '{}'", name, table),
+                        name,
+                    ));
+                }
+                referenced.insert(table);
+            }
+        }
+    }
+    
+    Ok(tables
+        .into_iter()
+        .flat_map(|tables| tables.keys())
+        .filter(|name| !referenced.contains(name.as_str()))
+        .map(|name| ValidationWarning {
+            code: "UNUSED_ENRICHMENT_TABLE".to_string(),
+            message: format!("Enrichment table '{}' is defined but never looked up", name),
+            component: Some(name.clone()),
+        })
+        .collect())
+}
+
+/// Required fields missing from an enrichment table
+fn missing_fields(table_type: &str, table: &toml::Value) -> Vec<String> {
+    let Some((_, fields)) = REQUIREMENTS.iter().find(|(t, _)| *t == table_type) else {
+        return Vec::new();
+    };
+    
+    fields
+        .iter()
+        .filter(|alternatives| {
+            !alternatives.iter().any(|path| {
+                path.split('.').try_fold(table, |value, key| value.get(key)).is_some()
+            })
+        })
+        .map(|alternatives| alternatives.join(" or "))
+        .collect()
+}
+
+/// Tables looked up by any VRL program in a component's options
+fn collect_lookups(value: &toml::Value, lookups: &mut Vec<String>) {
+    match value {
+        toml::Value::String(program) => lookups.extend(table_lookups(program)),
+        toml::Value::Array(items) => items.iter().for_each(|item| collect_lookups(item, lookups)),
+        toml::Value::Table(table) => table.values().for_each(|item| collect_lookups(item, lookups)),
+        _ => {}
+    }
+}
+
+/// Table names passed as string literals to the lookup functions
+///
+/// Tables named through a variable can't be resolved and are skipped.
+fn table_lookups(program: &str) -> Vec<String> {
+    let mut tables = Vec::new();
+    for function in LOOKUP_FUNCTIONS {
+        for (start, _) in program.match_indices(function) {
+            let rest = program[start + function.len()..].trim_start();
+            // Fallible calls are written `f!(...)`
+            let rest = rest.strip_prefix('!').unwrap_or(rest).trim_start();
+            let Some(args) = rest.strip_prefix('(') else {
+                continue;
+            };
+            let Some(literal) = args.trim_start().strip_prefix('"') else {
+                continue;
+            };
+            if let Some(end) = literal.find('"') {
+                tables.push(literal[..end].to_string());
+            }
+        }
+    }
+    tables
+}
+
+fn error(code: &str, message: String, table: &str) -> ValidationError {
+    ValidationError {
+        code: code.to_string(),
+        message,
+        line: None,
+        column: None,
+        component: Some(table.to_string()),
+        details: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn check_str(config: &str) -> Result<Vec<ValidationWarning>, ValidationError> {
+        check(&toml::from_str(config).unwrap())
+    }
+    
+    #[test]
+    fn test_valid_file_table() {
+        let warnings = check_str(r#"
+            [enrichment_tables.hosts]
+            type = "file"
+            file.path = "/etc/vector/hosts.csv"
+            file.encoding.type = "csv"
+    
+            [enrichment_tables.unused]
+            type = "memory"
+    
+            [transforms.enrich]
+            type = "remap"
+            inputs = ["in"]
+            source = '''
+            row = get_enrichment_table_record!("hosts", { "host": .host })
+            .owner = row.owner
+            '''
+        "#).unwrap();
+    
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "UNUSED_ENRICHMENT_TABLE");
+        assert_eq!(warnings[0].component.as_deref(), Some("unused"));
+    }
+    
+    #[test]
+    fn test_table_missing_type_or_fields() {
+        let err = check_str("[enrichment_tables.hosts]\nfile.path = \"/hosts.csv\"\n").unwrap_err();
+        assert_eq!(err.code, "MISSING_TYPE");
+        assert_eq!(err.component.as_deref(), Some("hosts"));
+    
+        let err = check_str("[enrichment_tables.hosts]\ntype = \"file\"\nfile.encoding.type = \"csv\"\n").unwrap_err();
+        assert_eq!(err.code, "MISSING_REQUIRED_FIELD");
+        assert!(err.message.contains("file.path"));
+    }
+    
+    #[test]
+    fn test_lookup_of_unknown_table() {
+        let err = check_str(r#"
+            [transforms.enrich]
+            type = "filter"
+            inputs = ["in"]
+            condition = 'exists(find_enrichment_table_records("hosts", {}))'
+        "#).unwrap_err();
+        assert_eq!(err.code, "UNKNOWN_ENRICHMENT_TABLE");
+        assert_eq!(err.component.as_deref(), Some("enrich"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Multi-file configurations
+//!
+//! Vector loads every file in a `--config-dir` into one config. Fragments
+//! are merged the same way here so component references resolve across
+//! files in our own validation passes.
+
+use std::collections::HashMap;
+
+use super::ValidationError;
+
+/// A file name safe to write into a scratch config directory
+///
+/// Only the final path component is kept, and `.toml` is added if missing.
+pub fn file_name(name: &str) -> Option<String> {
+    let base = std::path::Path::new(name).file_name()?.to_str()?;
+    if base.starts_with('.') {
+        return None;
+    }
+    
+    if base.ends_with(".toml") {
+        Some(base.to_string())
+    } else {
+        Some(format!("{}.toml", base))
+    }
+}
+
+/// Merge parsed fragments into a single config
+///
+/// Top-level tables (`sources`, `sinks`, ...) are combined key by key and
+/// arrays such as `tests` are concatenated. Defining the same key in two
+/// fragments is an error, as it is for Vector.
+pub fn merge(fragments: &[(String, toml::Value)]) -> Result<toml::Value, ValidationError> {
+    let mut merged = toml::value::Table::new();
+    let mut origins: HashMap<String, &str> = HashMap::new();
+    
+    for (name, value) in fragments {
+        let Some(table) = value.as_table() else {
+            continue;
+        };
+    
+        for (key, value) in table {
+            match (merged.get_mut(key), value) {
+                (None, _) => {
+                    if let Some(section) = value.as_table() {
+                        for id in section.keys() {
+                            origins.insert(format!("{}.{}", key, id), name);
+                        }
+                    }
+                    origins.insert(key.clone(), name);
+                    merged.insert(key.clone(), value.clone());
+                }
+                (Some(toml::Value::Table(existing)), toml::Value::Table(section)) => {
+                    for (id, component) in section {
+                        let path = format!("{}.{}", key, id);
+                        if let Some(first) = origins.get(&path) {
+                            return Err(duplicate(&path, first, name, id));
+                        }
+                        origins.insert(path, name);
+                        existing.insert(id.clone(), component.clone());
+                    }
+                }
+                (Some(toml::Value::Array(existing)), toml::Value::Array(items)) => {
+                    existing.extend(items.iter().cloned());
+                }
+                (Some(_), _) => {
+                    let first = origins.get(key).copied().unwrap_or_default();
+                    return Err(duplicate(key, first, name, key));
+                }
+            }
+        }
+    }
+    
+    Ok(toml::Value::Table(merged))
+}
+
+fn duplicate(path: &str, first: &str, second: &str, component: &str) -> ValidationError {
+    ValidationError {
+        code: "DUPLICATE_COMPONENT".to_string(),
+        message: format!("'{}' is defined in both {} and {}", path, first, second),
+        line: None,
+        column: None,
+        component: Some(component.to_string()),
+        details: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn parse(name: &str, config: &str) -> (String, toml::Value) {
+        (name.to_string(), toml::from_str(config).unwrap())
+    }
+    
+    #[test]
+    fn test_merge_combines_sections() {
+        let merged = merge(&[
+            parse("sources.toml", "[sources.in]\ntype = \"stdin\"\n\n[[tests]]\nname = \"a\""),
+            parse("sinks.toml", "[sinks.out]\ntype = \"console\"\ninputs = [\"in\"]\n\n[[tests]]\nname = \"b\""),
+        ]).unwrap();
+    
+        assert!(merged["sources"].get("in").is_some());
+        assert!(merged["sinks"].get("out").is_some());
+        assert_eq!(merged["tests"].as_array().unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn test_merge_rejects_duplicate_components() {
+        let err = merge(&[
+            parse("a.toml", "[sources.in]\ntype = \"stdin\""),
+            parse("b.toml", "[sources.in]\ntype = \"demo_logs\""),
+        ]).unwrap_err();
+    
+        assert_eq!(err.code, "DUPLICATE_COMPONENT");
+        assert_eq!(err.component.as_deref(), Some("in"));
+        assert!(err.message.contains("a.toml") && err.message.contains("b.toml"));
+    }
+    
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("sinks.toml").as_deref(), Some("sinks.toml"));
+        assert_eq!(file_name("sources").as_deref(), Some("sources.toml"));
+        assert_eq!(file_name("../../etc/passwd").as_deref(), Some("passwd.toml"));
+        assert_eq!(file_name(".."), None);
+        assert_eq!(file_name(".hidden"), None);
+    }
+}
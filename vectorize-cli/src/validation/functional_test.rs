@@ -4,7 +4,7 @@
 //! produce expected output. This is the highest level of validation that
 //! actually executes the pipeline with test data.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::Arc;
@@ -34,6 +34,9 @@ pub struct FunctionalTestRequest {
     /// Timeout in seconds
     #[serde(default = "default_timeout")]
     pub timeout_secs: u64,
+    /// Record each input event next to its output with a field-level diff
+    #[serde(default)]
+    pub diff: bool,
 }
 
 fn default_timeout() -> u64 { 30 }
@@ -76,6 +79,9 @@ pub enum TestStatus {
     Timeout,
 }
 
+/// Key in `transform_results` for the whole transform chain in diff mode
+pub const PIPELINE_RESULT_ID: &str = "__pipeline";
+
 /// Results for a specific transform
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransformResult {
@@ -84,6 +90,116 @@ pub struct TransformResult {
     pub output_count: usize,
     pub dropped_count: usize,
     pub sample_output: Vec<serde_json::Value>,
+    /// Input/output pairs (diff mode only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<EventComparison>,
+}
+
+/// One event before and after the transform chain
+///
+/// Events are paired by position. `after` is `None` when fewer events came
+/// out than went in, and `before` is `None` for extra output events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventComparison {
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<EventDiff>,
+}
+
+/// Field-level differences between two events
+///
+/// Nested objects are flattened to dotted paths (`http.status`); arrays and
+/// scalars are compared as whole values.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EventDiff {
+    pub added: BTreeMap<String, serde_json::Value>,
+    pub removed: BTreeMap<String, serde_json::Value>,
+    pub changed: BTreeMap<String, FieldChange>,
+}
+
+/// A field whose value was changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+impl EventDiff {
+    /// Compare two events
+    pub fn between(before: &serde_json::Value, after: &serde_json::Value) -> Self {
+        let mut before_fields = BTreeMap::new();
+        let mut after_fields = BTreeMap::new();
+        flatten_fields("", before, &mut before_fields);
+        flatten_fields("", after, &mut after_fields);
+        
+        let mut diff = EventDiff::default();
+        for (path, value) in &before_fields {
+            match after_fields.get(path) {
+                None => {
+                    diff.removed.insert(path.clone(), (*value).clone());
+                }
+                Some(new_value) if new_value != value => {
+                    diff.changed.insert(path.clone(), FieldChange {
+                        before: (*value).clone(),
+                        after: (*new_value).clone(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        for (path, value) in after_fields {
+            if !before_fields.contains_key(&path) {
+                diff.added.insert(path, value.clone());
+            }
+        }
+        diff
+    }
+    
+    /// Whether the events are identical
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Collect the leaf values of an event keyed by dotted path
+///
+/// A non-object event is a single leaf with an empty path.
+fn flatten_fields<'a>(
+    prefix: &str,
+    value: &'a serde_json::Value,
+    out: &mut BTreeMap<String, &'a serde_json::Value>,
+) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten_fields(&path, child, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value);
+        }
+    }
+}
+
+/// Pair input and output events by position and diff each pair
+pub fn compare_events(inputs: &[serde_json::Value], outputs: &[serde_json::Value]) -> Vec<EventComparison> {
+    (0..inputs.len().max(outputs.len()))
+        .map(|i| {
+            let before = inputs.get(i);
+            let after = outputs.get(i);
+            let diff = match (before, after) {
+                (Some(before), Some(after)) => Some(EventDiff::between(before, after)),
+                _ => None,
+            };
+            EventComparison {
+                before: before.cloned(),
+                after: after.cloned(),
+                diff,
+            }
+        })
+        .collect()
 }
 
 // =============================================================================
@@ -172,6 +288,17 @@ impl FunctionalTestService {
                 result.output_count = output_events.len();
                 result.dropped_count = request.sample_events.len().saturating_sub(output_events.len());
                 result.status = TestStatus::Completed;
+                
+                if request.diff {
+                    result.transform_results.insert(PIPELINE_RESULT_ID.to_string(), TransformResult {
+                        component_id: PIPELINE_RESULT_ID.to_string(),
+                        input_count: request.sample_events.len(),
+                        output_count: output_events.len(),
+                        dropped_count: result.dropped_count,
+                        sample_output: output_events.iter().take(10).cloned().collect(),
+                        events: compare_events(&request.sample_events, &output_events),
+                    });
+                }
             }
             Err(e) => {
                 result.status = if matches!(e, FunctionalTestError::Timeout) {
@@ -451,6 +578,7 @@ inputs = ["parse"]
             sample_events: vec![serde_json::json!({"message": "test"})],
             source_id: None,
             timeout_secs: 30,
+            diff: false,
         };
         
         let result = service.generate_test_config(&request);
@@ -460,4 +588,57 @@ inputs = ["parse"]
         assert!(config.contains("stdin"));
         assert!(config.contains("__test_output"));
     }
+    
+    #[test]
+    fn test_event_diff() {
+        let before = serde_json::json!({
+            "message": "{\"status\": 500}",
+            "level": "info",
+            "http": {"path": "/", "method": "GET"}
+        });
+        let after = serde_json::json!({
+            "level": "error",
+            "status": 500,
+            "http": {"path": "/"}
+        });
+        
+        let diff = EventDiff::between(&before, &after);
+        assert_eq!(diff.added.keys().collect::<Vec<_>>(), vec!["status"]);
+        assert_eq!(diff.removed.keys().collect::<Vec<_>>(), vec!["http.method", "message"]);
+        assert_eq!(diff.changed["level"], FieldChange {
+            before: serde_json::json!("info"),
+            after: serde_json::json!("error"),
+        });
+        assert!(EventDiff::between(&before, &before).is_empty());
+    }
+    
+    #[test]
+    fn test_compare_events_pairs_by_position() {
+        let inputs = vec![serde_json::json!({"a": 1}), serde_json::json!({"a": 2})];
+        let outputs = vec![serde_json::json!({"a": 1, "b": true})];
+        
+        let comparisons = compare_events(&inputs, &outputs);
+        assert_eq!(comparisons.len(), 2);
+        assert_eq!(comparisons[0].diff.as_ref().unwrap().added.len(), 1);
+        assert!(comparisons[1].after.is_none());
+        assert!(comparisons[1].diff.is_none());
+        
+        let comparisons = compare_events(&inputs[..1], &inputs);
+        assert!(comparisons[1].before.is_none());
+    }
+    
+    #[test]
+    fn test_transform_result_without_events_deserializes() {
+        let json = r#"{
+            "component_id": "parse",
+            "input_count": 1,
+            "output_count": 1,
+            "dropped_count": 0,
+            "sample_output": []
+        }"#;
+        
+        let result: TransformResult = serde_json::from_str(json).unwrap();
+        assert!(result.events.is_empty());
+        assert!(!serde_json::to_string(&result).unwrap().contains("events"));
+    }
 }
@@ -0,0 +1,287 @@
+//! Component graph
+//!
+//! The pipeline topology of a config: one node per source, transform and
+//! sink, and one edge per input reference. Component IDs and inputs are
+//! collected with the same helpers component validation uses, so the graph
+//! agrees with what `validate` checks.
+
+use std::collections::{BTreeMap, HashSet};
+use serde::Serialize;
+
+use super::required_fields::ComponentKind;
+
+/// A component in the graph
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct GraphNode {
+    pub id: String,
+    pub kind: ComponentKind,
+    /// Component type (e.g. `file`, `remap`), if set
+    #[serde(rename = "type")]
+    pub component_type: Option<String>,
+    /// Not connected to the pipeline: a source or transform nothing reads
+    /// from, or a transform or sink without inputs
+    pub orphan: bool,
+    /// Part of an input cycle, which Vector refuses to start with
+    pub in_cycle: bool,
+}
+
+/// An input reference; events flow from `from` to `to`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    /// Named output of `from` (e.g. `errors` for input `route.errors`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+/// An input that names no source or transform
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UnresolvedInput {
+    pub component: String,
+    pub input: String,
+}
+
+/// Topology of a config
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ComponentGraph {
+    /// Sources, then transforms, then sinks
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub unresolved_inputs: Vec<UnresolvedInput>,
+    /// Whether any component is part of a cycle
+    pub has_cycle: bool,
+}
+
+impl ComponentGraph {
+    /// Build the graph of a parsed config
+    pub fn from_config(config: &toml::Value) -> Self {
+        let mut graph = Self::default();
+    
+        // Only sources and transforms produce events
+        let producers: HashSet<&str> = components(config, ComponentKind::Source)
+            .chain(components(config, ComponentKind::Transform))
+            .map(|(id, _)| id.as_str())
+            .collect();
+    
+        for kind in [ComponentKind::Transform, ComponentKind::Sink] {
+            for (id, component) in components(config, kind) {
+                for input in inputs(component).unwrap_or_default() {
+                    match resolve_input(&producers, input) {
+                        Some((from, output)) => graph.edges.push(GraphEdge {
+                            from: from.to_string(),
+                            to: id.clone(),
+                            output: output.map(str::to_string),
+                        }),
+                        None => graph.unresolved_inputs.push(UnresolvedInput {
+                            component: id.clone(),
+                            input: input.to_string(),
+                        }),
+                    }
+                }
+            }
+        }
+    
+        let mut downstream: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for edge in &graph.edges {
+            downstream.entry(edge.from.as_str()).or_default().push(edge.to.as_str());
+        }
+    
+        let mut nodes = Vec::new();
+        for kind in [ComponentKind::Source, ComponentKind::Transform, ComponentKind::Sink] {
+            for (id, component) in components(config, kind) {
+                let has_inputs = graph.edges.iter().any(|e| &e.to == id);
+                let has_outputs = downstream.contains_key(id.as_str());
+                let orphan = match kind {
+                    ComponentKind::Source => !has_outputs,
+                    ComponentKind::Transform => !has_inputs || !has_outputs,
+                    ComponentKind::Sink => !has_inputs,
+                };
+                nodes.push(GraphNode {
+                    id: id.clone(),
+                    kind,
+                    component_type: component.get("type").and_then(|v| v.as_str()).map(str::to_string),
+                    orphan,
+                    in_cycle: kind == ComponentKind::Transform && reaches(&downstream, id, id),
+                });
+            }
+        }
+    
+        graph.has_cycle = nodes.iter().any(|n| n.in_cycle);
+        graph.nodes = nodes;
+        graph
+    }
+}
+
+/// Components of one kind, by ID
+pub(crate) fn components(config: &toml::Value, kind: ComponentKind) -> impl Iterator<Item = (&String, &toml::Value)> {
+    config.get(kind.table_name())
+        .and_then(|v| v.as_table())
+        .into_iter()
+        .flat_map(|table| table.iter())
+}
+
+/// A component's `inputs`, or None if it has no inputs array
+pub(crate) fn inputs(component: &toml::Value) -> Option<Vec<&str>> {
+    component.get("inputs")
+        .and_then(|v| v.as_array())
+        .map(|inputs| inputs.iter().filter_map(|i| i.as_str()).collect())
+}
+
+/// The producer an input refers to, and the named output if any
+///
+/// An exact component ID wins over a named output, so a transform called
+/// `route.errors` is not mistaken for the `errors` output of `route`.
+fn resolve_input<'a>(producers: &HashSet<&str>, input: &'a str) -> Option<(&'a str, Option<&'a str>)> {
+    if producers.contains(input) {
+        return Some((input, None));
+    }
+    match input.split_once('.') {
+        Some((base, output)) if producers.contains(base) => Some((base, Some(output))),
+        _ => None,
+    }
+}
+
+/// Whether `to` is reachable from `from` by following at least one edge
+fn reaches(downstream: &BTreeMap<&str, Vec<&str>>, from: &str, to: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut stack: Vec<&str> = downstream.get(from).cloned().unwrap_or_default();
+    
+    while let Some(current) = stack.pop() {
+        if current == to {
+            return true;
+        }
+        if seen.insert(current) {
+            stack.extend(downstream.get(current).into_iter().flatten());
+        }
+    }
+    
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn graph(config: &str) -> ComponentGraph {
+        ComponentGraph::from_config(&toml::from_str(config).unwrap())
+    }
+    
+    fn edges(graph: &ComponentGraph) -> Vec<(&str, &str, Option<&str>)> {
+        graph.edges.iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.output.as_deref()))
+            .collect()
+    }
+    
+    fn node<'a>(graph: &'a ComponentGraph, id: &str) -> &'a GraphNode {
+        graph.nodes.iter().find(|n| n.id == id).unwrap()
+    }
+    
+    #[test]
+    fn test_multi_stage_edges() {
+        let graph = graph(r#"
+[sources.app]
+type = "file"
+include = ["/var/log/app.log"]
+
+[sources.sys]
+type = "journald"
+
+[transforms.parse]
+type = "remap"
+inputs = ["app", "sys"]
+
+[transforms.split]
+type = "route"
+inputs = ["parse"]
+
+[sinks.errors]
+type = "console"
+inputs = ["split.errors"]
+
+[sinks.archive]
+type = "aws_s3"
+inputs = ["parse", "split._unmatched"]
+"#);
+
+        assert_eq!(edges(&graph), vec![
+            ("app", "parse", None),
+            ("sys", "parse", None),
+            ("parse", "split", None),
+            ("parse", "archive", None),
+            ("split", "archive", Some("_unmatched")),
+            ("split", "errors", Some("errors")),
+        ]);
+        assert!(graph.unresolved_inputs.is_empty());
+        assert!(!graph.has_cycle);
+        assert!(graph.nodes.iter().all(|n| !n.orphan && !n.in_cycle));
+    
+        let ids: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["app", "sys", "parse", "split", "archive", "errors"]);
+        assert_eq!(node(&graph, "split").kind, ComponentKind::Transform);
+        assert_eq!(node(&graph, "archive").component_type.as_deref(), Some("aws_s3"));
+    }
+    
+    #[test]
+    fn test_orphans_and_unresolved_inputs() {
+        let graph = graph(r#"
+[sources.used]
+type = "stdin"
+
+[sources.unused]
+type = "stdin"
+
+[transforms.dead_end]
+type = "remap"
+inputs = ["used"]
+
+[sinks.out]
+type = "console"
+inputs = ["used", "missing"]
+"#);
+
+        assert!(!node(&graph, "used").orphan);
+        assert!(node(&graph, "unused").orphan);
+        assert!(node(&graph, "dead_end").orphan);
+        assert!(!node(&graph, "out").orphan);
+        assert_eq!(graph.unresolved_inputs, vec![UnresolvedInput {
+            component: "out".to_string(),
+            input: "missing".to_string(),
+        }]);
+    }
+    
+    #[test]
+    fn test_cycle_detection() {
+        let graph = graph(r#"
+[sources.in]
+type = "stdin"
+
+[transforms.a]
+type = "remap"
+inputs = ["in", "c"]
+
+[transforms.b]
+type = "remap"
+inputs = ["a"]
+
+[transforms.c]
+type = "remap"
+inputs = ["b"]
+
+[transforms.side]
+type = "remap"
+inputs = ["a"]
+
+[sinks.out]
+type = "console"
+inputs = ["side", "c"]
+"#);
+
+        assert!(graph.has_cycle);
+        let in_cycle: Vec<&str> = graph.nodes.iter()
+            .filter(|n| n.in_cycle)
+            .map(|n| n.id.as_str())
+            .collect();
+        assert_eq!(in_cycle, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,315 @@
+//! Best-practice lint rules
+//!
+//! Opinionated checks for configs that are valid but risky in practice.
+//! Lint findings are advisory: they are reported separately from validation
+//! results and never block a deploy.
+
+use std::collections::{BTreeMap, HashSet};
+
+use super::ValidationWarning;
+use super::graph::components;
+use super::required_fields::{kind_label, ComponentKind};
+
+/// Deprecated component types and what to use instead
+const DEPRECATED_TYPES: &[(ComponentKind, &str, &str)] = &[
+    // Sources
+    (ComponentKind::Source, "generator", "demo_logs"),
+    (ComponentKind::Source, "docker", "docker_logs"),
+    (ComponentKind::Source, "prometheus", "prometheus_scrape"),
+    // Transforms
+    (ComponentKind::Transform, "add_fields", "remap"),
+    (ComponentKind::Transform, "remove_fields", "remap"),
+    (ComponentKind::Transform, "rename_fields", "remap"),
+    (ComponentKind::Transform, "add_tags", "remap"),
+    (ComponentKind::Transform, "remove_tags", "remap"),
+    (ComponentKind::Transform, "json_parser", "remap"),
+    (ComponentKind::Transform, "regex_parser", "remap"),
+    (ComponentKind::Transform, "grok_parser", "remap"),
+    (ComponentKind::Transform, "logfmt_parser", "remap"),
+    (ComponentKind::Transform, "key_value_parser", "remap"),
+    (ComponentKind::Transform, "coercer", "remap"),
+    (ComponentKind::Transform, "split", "remap"),
+    (ComponentKind::Transform, "tokenizer", "remap"),
+    (ComponentKind::Transform, "ansi_stripper", "remap"),
+    (ComponentKind::Transform, "field_filter", "filter"),
+    (ComponentKind::Transform, "swimlanes", "route"),
+    (ComponentKind::Transform, "sampler", "sample"),
+    // Sinks
+    (ComponentKind::Sink, "prometheus", "prometheus_exporter"),
+    (ComponentKind::Sink, "humio", "humio_logs"),
+    (ComponentKind::Sink, "logdna", "mezmo"),
+];
+
+/// Source types that can redeliver unacknowledged events, so sinks fed by
+/// them should acknowledge to avoid data loss
+const ACKNOWLEDGING_SOURCES: &[&str] = &[
+    "kafka",
+    "file",
+    "http_server",
+    "journald",
+    "kubernetes_logs",
+    "aws_sqs",
+    "aws_s3",
+    "splunk_hec",
+    "datadog_agent",
+];
+
+/// Lint a parsed config
+///
+/// `production` enables the rules that only apply to production configs.
+pub fn lint(config: &toml::Value, production: bool) -> Vec<ValidationWarning> {
+    let mut warnings = Vec::new();
+    
+    for kind in [ComponentKind::Source, ComponentKind::Transform, ComponentKind::Sink] {
+        for (name, component) in components(config, kind) {
+            if let Some(warning) = deprecated_type(kind, name, component) {
+                warnings.push(warning);
+            }
+        }
+    }
+    
+    let global_acks = acknowledgements_enabled(config);
+    let graph = InputGraph::new(config);
+    
+    for (name, sink) in components(config, ComponentKind::Sink) {
+        let sink_type = component_type(sink);
+    
+        if production && sink_type == Some("console") {
+            warnings.push(ValidationWarning {
+                code: "CONSOLE_SINK_IN_PROD".to_string(),
+                message: format!(
+                    "Sink '{}' writes to the console in a production group; events are lost when stdout isn't collected",
+                    name
+                ),
+                component: Some(name.clone()),
+            });
+        }
+    
+        warnings.extend(buffer_warnings(name, sink));
+    
+        if !global_acks && !acknowledgements_enabled(sink) {
+            let critical = graph.upstream_sources(name)
+                .into_iter()
+                .filter(|source| ACKNOWLEDGING_SOURCES.contains(&source.1))
+                .map(|source| source.0)
+                .collect::<Vec<_>>();
+            if !critical.is_empty() {
+                warnings.push(ValidationWarning {
+                    code: "NO_ACKS".to_string(),
+                    message: format!(
+                        "Sink '{}' receives data from {} but does not enable acknowledgements; events can be lost on restart",
+                        name,
+                        critical.iter().map(|s| format!("'{}'", s)).collect::<Vec<_>>().join(", ")
+                    ),
+                    component: Some(name.clone()),
+                });
+            }
+        }
+    }
+    
+    warnings
+}
+
+fn component_type(component: &toml::Value) -> Option<&str> {
+    component.get("type").and_then(|v| v.as_str())
+}
+
+fn deprecated_type(kind: ComponentKind, name: &str, component: &toml::Value) -> Option<ValidationWarning> {
+    let component_type = component_type(component)?;
+    let (_, _, replacement) = DEPRECATED_TYPES
+        .iter()
+        .find(|(k, t, _)| *k == kind && *t == component_type)?;
+    
+    Some(ValidationWarning {
+        code: "DEPRECATED_TYPE".to_string(),
+        message: format!(
+            "{} '{}' uses deprecated type '{}'; use '{}' instead",
+            kind_label(kind), name, component_type, replacement
+        ),
+        component: Some(name.to_string()),
+    })
+}
+
+/// `acknowledgements = true` or `acknowledgements.enabled = true`
+fn acknowledgements_enabled(value: &toml::Value) -> bool {
+    match value.get("acknowledgements") {
+        Some(toml::Value::Boolean(enabled)) => *enabled,
+        Some(acks) => acks.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Disk buffers without `max_size` grow until the disk fills up
+fn buffer_warnings(name: &str, sink: &toml::Value) -> Vec<ValidationWarning> {
+    let stages: Vec<&toml::Value> = match sink.get("buffer") {
+        Some(toml::Value::Array(stages)) => stages.iter().collect(),
+        Some(buffer) => vec![buffer],
+        None => return Vec::new(),
+    };
+    
+    stages.into_iter()
+        .filter(|stage| stage.get("type").and_then(|v| v.as_str()) == Some("disk"))
+        .filter(|stage| stage.get("max_size").is_none())
+        .map(|_| ValidationWarning {
+            code: "BUFFER_NO_MAX_SIZE".to_string(),
+            message: format!("Sink '{}' has a disk buffer without max_size", name),
+            component: Some(name.to_string()),
+        })
+        .collect()
+}
+
+/// Component inputs, for tracing a sink back to its sources
+struct InputGraph<'a> {
+    inputs: BTreeMap<&'a str, Vec<&'a str>>,
+    source_types: BTreeMap<&'a str, &'a str>,
+}
+
+impl<'a> InputGraph<'a> {
+    fn new(config: &'a toml::Value) -> Self {
+        let mut inputs = BTreeMap::new();
+        for kind in [ComponentKind::Transform, ComponentKind::Sink] {
+            for (name, component) in components(config, kind) {
+                let component_inputs = component.get("inputs")
+                    .and_then(|v| v.as_array())
+                    .map(|inputs| inputs.iter().filter_map(|i| i.as_str()).collect())
+                    .unwrap_or_default();
+                inputs.insert(name.as_str(), component_inputs);
+            }
+        }
+    
+        let source_types = components(config, ComponentKind::Source)
+            .map(|(name, source)| (name.as_str(), component_type(source).unwrap_or_default()))
+            .collect();
+    
+        Self { inputs, source_types }
+    }
+    
+    /// Sources feeding `component`, directly or through transforms, as
+    /// (name, type) pairs
+    fn upstream_sources(&self, component: &'a str) -> Vec<(&'a str, &'a str)> {
+        let mut sources = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![component];
+    
+        while let Some(current) = stack.pop() {
+            if !seen.insert(current) {
+                continue;
+            }
+            for input in self.inputs.get(current).into_iter().flatten() {
+                // Named outputs (`route.errors`) belong to their component
+                let input = match input.split_once('.') {
+                    Some((base, _)) if !self.is_component(input) => base,
+                    _ => input,
+                };
+                match self.source_types.get(input) {
+                    Some(source_type) => {
+                        if !sources.iter().any(|(name, _)| *name == input) {
+                            sources.push((input, *source_type));
+                        }
+                    }
+                    None => stack.push(input),
+                }
+            }
+        }
+    
+        sources
+    }
+    
+    fn is_component(&self, name: &str) -> bool {
+        self.inputs.contains_key(name) || self.source_types.contains_key(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn lint_str(config: &str, production: bool) -> Vec<String> {
+        let value: toml::Value = toml::from_str(config).unwrap();
+        lint(&value, production).into_iter().map(|w| w.code).collect()
+    }
+    
+    #[test]
+    fn test_console_sink_only_flagged_in_production() {
+        let config = r#"
+[sources.demo]
+type = "demo_logs"
+format = "json"
+
+[sinks.out]
+type = "console"
+inputs = ["demo"]
+encoding.codec = "json"
+"#;
+        assert_eq!(lint_str(config, true), vec!["CONSOLE_SINK_IN_PROD"]);
+        assert!(lint_str(config, false).is_empty());
+    }
+    
+    #[test]
+    fn test_no_acks_traces_through_transforms() {
+        let config = r#"
+[sources.events]
+type = "kafka"
+bootstrap_servers = "kafka:9092"
+group_id = "vector"
+topics = ["events"]
+
+[transforms.split]
+type = "route"
+inputs = ["events"]
+route.errors = '.level == "error"'
+
+[sinks.archive]
+type = "aws_s3"
+inputs = ["split.errors"]
+bucket = "archive"
+
+[sinks.acked]
+type = "aws_s3"
+inputs = ["split.errors"]
+bucket = "archive"
+acknowledgements.enabled = true
+"#;
+        let value: toml::Value = toml::from_str(config).unwrap();
+        let warnings = lint(&value, false);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "NO_ACKS");
+        assert_eq!(warnings[0].component.as_deref(), Some("archive"));
+        assert!(warnings[0].message.contains("'events'"));
+    
+        // A global setting covers every sink
+        let global = format!("acknowledgements.enabled = true\n{}", config);
+        assert!(lint_str(&global, false).is_empty());
+    }
+    
+    #[test]
+    fn test_deprecated_types_and_buffers() {
+        let config = r#"
+[sources.gen]
+type = "generator"
+
+[transforms.parse]
+type = "json_parser"
+inputs = ["gen"]
+
+[sinks.out]
+type = "http"
+inputs = ["parse"]
+uri = "http://example.com"
+buffer.type = "disk"
+"#;
+        assert_eq!(lint_str(config, false), vec!["DEPRECATED_TYPE", "DEPRECATED_TYPE", "BUFFER_NO_MAX_SIZE"]);
+    
+        let config = r#"
+[sinks.out]
+type = "http"
+inputs = ["in"]
+uri = "http://example.com"
+buffer = [
+  { type = "memory", max_events = 1000 },
+  { type = "disk", max_size = 268435488 },
+]
+"#;
+        assert!(lint_str(config, false).is_empty());
+    }
+}
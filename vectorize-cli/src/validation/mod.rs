@@ -6,15 +6,40 @@
 //! - Layer 3: Vector binary validation (via `vector validate`)
 //! - Layer 4: Functional testing with sample data
 //!
-//! Also includes component validation (sources, transforms, sinks exist)
+//! Also includes component validation (sources, transforms, sinks exist) and
+//! required-field checks for common source and sink types, plus an advisory
+//! lint pass for best-practice warnings, a check of component types against
+//! the agents' Vector versions, conversion between TOML, YAML and JSON
+//! config formats, validation of configs split across several files, a
+//! size and complexity report for reviewers, and checks that secret and
+//! environment variable references can be resolved.
 
+pub mod analyze;
+pub mod compat;
+pub mod convert;
+pub mod fragments;
 pub mod functional_test;
+pub mod graph;
+pub mod lint;
+pub mod required_fields;
+mod enrichment;
+mod secrets;
+pub mod vector_output;
+mod yaml;
 
 pub use functional_test::{
     FunctionalTestService, FunctionalTestRequest, FunctionalTestResult,
     TestStatus, TransformResult, FunctionalTestError,
+    EventComparison, EventDiff, FieldChange, compare_events, PIPELINE_RESULT_ID,
 };
 
+pub use analyze::ConfigReport;
+pub use compat::VectorVersion;
+pub use convert::{ConfigFormat, ConversionError};
+pub use graph::ComponentGraph;
+
+use required_fields::ComponentKind;
+
 use std::path::Path;
 use std::process::Command;
 use serde::{Deserialize, Serialize};
@@ -78,6 +103,9 @@ pub struct ValidationError {
     pub column: Option<usize>,
     /// Component ID (if applicable)
     pub component: Option<String>,
+    /// Raw tool output behind the error (if applicable)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
 }
 
 /// Validation warning
@@ -95,12 +123,75 @@ pub struct ValidationWarning {
 pub struct ConfigValidator {
     /// Path to Vector binary
     vector_bin: Option<String>,
+    /// Apply production-only lint rules
+    production: bool,
+    /// Warn about `${VAR}` references unset in this process's environment
+    check_env: bool,
 }
 
 impl ConfigValidator {
     /// Create a new validator
     pub fn new(vector_bin: Option<String>) -> Self {
-        Self { vector_bin }
+        Self { vector_bin, production: false, check_env: false }
+    }
+    
+    /// Lint as a production config (e.g. console sinks are flagged)
+    pub fn with_production(mut self, production: bool) -> Self {
+        self.production = production;
+        self
+    }
+    
+    /// Check environment variable references against this process's
+    /// environment
+    ///
+    /// Only meaningful when the config runs where Vectorize does, e.g. the
+    /// locally managed Vector.
+    pub fn with_env_check(mut self, check_env: bool) -> Self {
+        self.check_env = check_env;
+        self
+    }
+    
+    /// Best-practice warnings for a configuration
+    ///
+    /// Advisory only: lint never affects `valid` or `errors`, so it can't
+    /// block a deploy. Configs that don't parse produce no warnings; use
+    /// `validate` to report syntax errors.
+    pub fn lint(&self, config: &str) -> Vec<ValidationWarning> {
+        match toml::from_str::<toml::Value>(config) {
+            Ok(value) => lint::lint(&value, self.production),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Warnings for component types newer than `min_version`
+    ///
+    /// `min_version` is the oldest Vector the config will run on, e.g. the
+    /// oldest agent in the target group. Advisory like `lint`; configs or
+    /// versions that don't parse produce no warnings.
+    pub fn check_version_compatibility(&self, config: &str, min_version: &str) -> Vec<ValidationWarning> {
+        let Some(min_version) = VectorVersion::parse(min_version) else {
+            return Vec::new();
+        };
+        match toml::from_str::<toml::Value>(config) {
+            Ok(value) => compat::check(&value, min_version),
+            Err(_) => Vec::new(),
+        }
+    }
+    
+    /// Parse a configuration into its component graph
+    ///
+    /// Only TOML syntax errors fail; unknown inputs, orphans and cycles are
+    /// reported in the graph itself.
+    pub fn explain(&self, config: &str) -> Result<ComponentGraph, ValidationError> {
+        let value = self.validate_toml_syntax(config)?;
+        Ok(ComponentGraph::from_config(&value))
+    }
+    
+    /// Size and complexity report of a configuration
+    ///
+    /// Fails only on TOML syntax errors, like `explain`.
+    pub fn analyze(&self, config: &str) -> Result<ConfigReport, ValidationError> {
+        self.explain(config).map(|graph| ConfigReport::from_graph(&graph))
     }
     
     /// Validate a configuration string
@@ -161,6 +252,7 @@ impl ConfigValidator {
                     line,
                     column,
                     component: None,
+                    details: None,
                 })
             }
         }
@@ -181,7 +273,7 @@ impl ConfigValidator {
         };
         
         // Check for unknown top-level keys
-        let known_keys = ["api", "sources", "transforms", "sinks", "tests", "enrichment_tables", "secret"];
+        let known_keys = ["api", "acknowledgements", "sources", "transforms", "sinks", "tests", "enrichment_tables", "secret"];
         for key in table.keys() {
             if !known_keys.contains(&key.as_str()) {
                 warnings.push(ValidationWarning {
@@ -228,42 +320,16 @@ impl ConfigValidator {
         };
         
         // Collect all component IDs
-        let mut source_ids: Vec<String> = Vec::new();
-        let mut transform_ids: Vec<String> = Vec::new();
-        
-        if let Some(sources) = table.get("sources").and_then(|v| v.as_table()) {
-            source_ids.extend(sources.keys().cloned());
-        }
-        
-        if let Some(transforms) = table.get("transforms").and_then(|v| v.as_table()) {
-            transform_ids.extend(transforms.keys().cloned());
-        }
-        
-        let all_output_ids: Vec<&str> = source_ids.iter()
-            .chain(transform_ids.iter())
-            .map(|s| s.as_str())
+        let all_output_ids: Vec<&str> = graph::components(&value, ComponentKind::Source)
+            .chain(graph::components(&value, ComponentKind::Transform))
+            .map(|(id, _)| id.as_str())
             .collect();
         
         // Check transform inputs
         if let Some(transforms) = table.get("transforms").and_then(|v| v.as_table()) {
             for (name, transform) in transforms {
-                if let Some(inputs) = transform.get("inputs").and_then(|v| v.as_array()) {
-                    for input in inputs {
-                        if let Some(input_str) = input.as_str() {
-                            if !all_output_ids.contains(&input_str) {
-                                return Err(ValidationError {
-                                    code: "INVALID_INPUT".to_string(),
-                                    message: format!(
-                                        "Transform '{}' references unknown input '{}'",
-                                        name, input_str
-                                    ),
-                                    line: None,
-                                    column: None,
-                                    component: Some(name.clone()),
-                                });
-                            }
-                        }
-                    }
+                if let Some(inputs) = graph::inputs(transform) {
+                    Self::check_inputs("Transform", name, &inputs, &all_output_ids, &mut warnings)?;
                 } else {
                     warnings.push(ValidationWarning {
                         code: "MISSING_INPUTS".to_string(),
@@ -280,6 +346,7 @@ impl ConfigValidator {
                         line: None,
                         column: None,
                         component: Some(name.clone()),
+                        details: None,
                     });
                 }
             }
@@ -288,23 +355,8 @@ impl ConfigValidator {
         // Check sink inputs
         if let Some(sinks) = table.get("sinks").and_then(|v| v.as_table()) {
             for (name, sink) in sinks {
-                if let Some(inputs) = sink.get("inputs").and_then(|v| v.as_array()) {
-                    for input in inputs {
-                        if let Some(input_str) = input.as_str() {
-                            if !all_output_ids.contains(&input_str) {
-                                return Err(ValidationError {
-                                    code: "INVALID_INPUT".to_string(),
-                                    message: format!(
-                                        "Sink '{}' references unknown input '{}'",
-                                        name, input_str
-                                    ),
-                                    line: None,
-                                    column: None,
-                                    component: Some(name.clone()),
-                                });
-                            }
-                        }
-                    }
+                if let Some(inputs) = graph::inputs(sink) {
+                    Self::check_inputs("Sink", name, &inputs, &all_output_ids, &mut warnings)?;
                 } else {
                     warnings.push(ValidationWarning {
                         code: "MISSING_INPUTS".to_string(),
@@ -321,8 +373,11 @@ impl ConfigValidator {
                         line: None,
                         column: None,
                         component: Some(name.clone()),
+                        details: None,
                     });
                 }
+                
+                Self::check_required_fields(ComponentKind::Sink, name, sink)?;
             }
         }
         
@@ -336,14 +391,105 @@ impl ConfigValidator {
                         line: None,
                         column: None,
                         component: Some(name.clone()),
+                        details: None,
                     });
                 }
+                
+                Self::check_required_fields(ComponentKind::Source, name, source)?;
             }
         }
         
+        // Enrichment tables and the VRL lookups that use them
+        warnings.extend(enrichment::check(&value)?);
+        
+        // Secret backends and environment variables the config interpolates
+        let env_is_set = |name: &str| std::env::var_os(name).is_some();
+        let env_check: Option<&dyn Fn(&str) -> bool> = self.check_env.then_some(&env_is_set);
+        warnings.extend(secrets::check(&value, env_check));
+        
         Ok(warnings)
     }
     
+    /// Check a transform's or sink's `inputs` array
+    ///
+    /// A component reading from itself or from an unknown component is an
+    /// error; an empty array or a repeated input only warns.
+    fn check_inputs(
+        kind: &str,
+        name: &str,
+        inputs: &[&str],
+        all_output_ids: &[&str],
+        warnings: &mut Vec<ValidationWarning>,
+    ) -> Result<(), ValidationError> {
+        if inputs.is_empty() {
+            warnings.push(ValidationWarning {
+                code: "EMPTY_INPUTS".to_string(),
+                message: format!("{} '{}' has an empty inputs list and receives no events", kind, name),
+                component: Some(name.to_string()),
+            });
+        }
+        
+        for (i, input_str) in inputs.iter().enumerate() {
+            if *input_str == name {
+                return Err(ValidationError {
+                    code: "SELF_REFERENCE".to_string(),
+                    message: format!("{} '{}' lists itself as an input", kind, name),
+                    line: None,
+                    column: None,
+                    component: Some(name.to_string()),
+                    details: None,
+                });
+            }
+            
+            if !all_output_ids.contains(input_str) {
+                return Err(ValidationError {
+                    code: "INVALID_INPUT".to_string(),
+                    message: format!(
+                        "{} '{}' references unknown input '{}'",
+                        kind, name, input_str
+                    ),
+                    line: None,
+                    column: None,
+                    component: Some(name.to_string()),
+                    details: None,
+                });
+            }
+            
+            // Warn once per repeated input, at its second occurrence
+            if inputs[..i].iter().filter(|earlier| *earlier == input_str).count() == 1 {
+                warnings.push(ValidationWarning {
+                    code: "DUPLICATE_INPUT".to_string(),
+                    message: format!("{} '{}' lists input '{}' more than once", kind, name, input_str),
+                    component: Some(name.to_string()),
+                });
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Check the type-specific required fields of a source or sink
+    fn check_required_fields(kind: ComponentKind, name: &str, component: &toml::Value) -> Result<(), ValidationError> {
+        let Some(component_type) = component.get("type").and_then(|v| v.as_str()) else {
+            return Ok(());
+        };
+        
+        match required_fields::missing_fields(kind, component_type, component).first() {
+            Some(field) => Err(ValidationError {
+                code: "MISSING_REQUIRED_FIELD".to_string(),
+                message: format!(
+                    "{} '{}' of type '{}' is missing required field '{}'",
+                    required_fields::kind_label(kind), name, component_type, field
+                ),
+                line: None,
+                column: None,
+                component: Some(name.to_string()),
+                details: None,
+            }),
+            None => Ok(()),
+        }
+    }
+    
     /// Run Vector's validate command
     pub fn validate_with_vector(&self, config: &str) -> ValidationResult {
         // First do our own validation
@@ -371,6 +517,77 @@ impl ConfigValidator {
         // Clean up temp file
         let _ = std::fs::remove_file(&temp_file);
         
+        Self::apply_vector_output(result, output, config)
+    }
+    
+    /// Validate a configuration split across several files
+    ///
+    /// Each fragment is a `(name, content)` pair such as
+    /// `("sinks.toml", "...")`. Fragments are merged the way Vector merges a
+    /// `--config-dir`, so an input defined in one file can be referenced
+    /// from another, then validated together with `vector validate
+    /// --config-dir`.
+    pub fn validate_fragments(&self, fragments: &[(String, String)]) -> ValidationResult {
+        let mut parsed = Vec::with_capacity(fragments.len());
+        for (name, content) in fragments {
+            match self.validate_toml_syntax(content) {
+                Ok(value) => parsed.push((name.clone(), value)),
+                Err(mut e) => {
+                    e.message = format!("{}: {}", name, e.message);
+                    return ValidationResult::success().with_error(e);
+                }
+            }
+        }
+        
+        let merged = match fragments::merge(&parsed).map(|value| toml::to_string(&value)) {
+            Ok(Ok(merged)) => merged,
+            Ok(Err(e)) => return ValidationResult::failure(format!("Failed to merge fragments: {}", e)),
+            Err(e) => return ValidationResult::success().with_error(e),
+        };
+        
+        let result = self.validate(&merged);
+        if !result.valid {
+            return result;
+        }
+        
+        let vector_bin = self.vector_bin.clone().unwrap_or_else(|| "vector".to_string());
+        
+        // Write each fragment into its own scratch directory
+        let config_dir = std::env::temp_dir().join(format!("vectorize-validate-{}", uuid::Uuid::new_v4()));
+        if let Err(e) = std::fs::create_dir_all(&config_dir) {
+            return ValidationResult::failure(format!("Failed to create temp dir: {}", e));
+        }
+        
+        let mut written = std::collections::HashSet::new();
+        for (name, content) in fragments {
+            let file_name = match fragments::file_name(name) {
+                Some(file_name) if written.insert(file_name.clone()) => file_name,
+                _ => {
+                    let _ = std::fs::remove_dir_all(&config_dir);
+                    return ValidationResult::failure(format!("Invalid or duplicate fragment name: {}", name));
+                }
+            };
+            if let Err(e) = std::fs::write(config_dir.join(file_name), content) {
+                let _ = std::fs::remove_dir_all(&config_dir);
+                return ValidationResult::failure(format!("Failed to write temp file: {}", e));
+            }
+        }
+        
+        let output = Command::new(&vector_bin)
+            .args(["validate", "--config-dir", config_dir.to_str().unwrap_or("")])
+            .output();
+        
+        let _ = std::fs::remove_dir_all(&config_dir);
+        
+        Self::apply_vector_output(result, output, &merged)
+    }
+    
+    /// Fold the outcome of a `vector validate` run into our own result
+    fn apply_vector_output(
+        result: ValidationResult,
+        output: std::io::Result<std::process::Output>,
+        config: &str,
+    ) -> ValidationResult {
         match output {
             Ok(output) => {
                 if output.status.success() {
@@ -380,8 +597,8 @@ impl ConfigValidator {
                     let stderr = String::from_utf8_lossy(&output.stderr);
                     let stdout = String::from_utf8_lossy(&output.stdout);
                     
-                    // Parse Vector's error output
-                    let error_msg = if !stderr.is_empty() {
+                    // Vector writes diagnostics to stderr, but some versions use stdout
+                    let error_msg = if !stderr.trim().is_empty() {
                         stderr.to_string()
                     } else {
                         stdout.to_string()
@@ -389,13 +606,9 @@ impl ConfigValidator {
                     
                     warn!("Vector validation failed: {}", error_msg);
                     
-                    result.with_error(ValidationError {
-                        code: "VECTOR_VALIDATE".to_string(),
-                        message: format!("Vector validation failed: {}", error_msg.trim()),
-                        line: None,
-                        column: None,
-                        component: None,
-                    })
+                    vector_output::parse_vector_errors(&error_msg, config)
+                        .into_iter()
+                        .fold(result, ValidationResult::with_error)
                 }
             }
             Err(e) => {
@@ -724,6 +937,67 @@ inputs = ["logs", "nonexistent"]
         assert_eq!(result.errors[0].code, "INVALID_INPUT");
     }
     
+    // =========================================================================
+    // Required Field Tests
+    // =========================================================================
+    
+    #[test]
+    fn test_sink_missing_required_field() {
+        let config = r#"
+[sources.demo]
+type = "demo_logs"
+
+[sinks.out]
+type = "http"
+inputs = ["demo"]
+"#;
+        
+        let result = validator().validate(config);
+        
+        assert!(!result.valid);
+        assert_eq!(result.errors[0].code, "MISSING_REQUIRED_FIELD");
+        assert_eq!(result.errors[0].component, Some("out".to_string()));
+        assert!(result.errors[0].message.contains("'uri'"));
+    }
+    
+    #[test]
+    fn test_source_missing_required_field() {
+        let config = r#"
+[sources.app]
+type = "file"
+
+[sinks.console]
+type = "console"
+inputs = ["app"]
+"#;
+        
+        let result = validator().validate(config);
+        
+        assert!(!result.valid);
+        assert_eq!(result.errors[0].code, "MISSING_REQUIRED_FIELD");
+        assert!(result.errors[0].message.contains("'include'"));
+    }
+    
+    #[test]
+    fn test_required_fields_present_and_unknown_type() {
+        let config = r#"
+[sources.demo]
+type = "demo_logs"
+
+[sinks.s3]
+type = "aws_s3"
+inputs = ["demo"]
+bucket = "logs"
+
+[sinks.custom]
+type = "some_future_sink"
+inputs = ["demo"]
+"#;
+        
+        let result = validator().validate(config);
+        assert!(result.valid, "{:?}", result.errors);
+    }
+    
     // =========================================================================
     // Warning Tests
     // =========================================================================
@@ -801,6 +1075,67 @@ type = "console"
         assert!(has_warning);
     }
     
+    #[test]
+    fn test_empty_inputs_warning() {
+        let config = r#"
+[sources.logs]
+type = "demo_logs"
+
+[sinks.console]
+type = "console"
+inputs = []
+"#;
+        
+        let result = validator().validate(config);
+        
+        assert!(result.valid);
+        let codes: Vec<&str> = result.warnings.iter().map(|w| w.code.as_str()).collect();
+        assert!(codes.contains(&"EMPTY_INPUTS"));
+        assert!(!codes.contains(&"MISSING_INPUTS"));
+    }
+    
+    #[test]
+    fn test_self_reference_error() {
+        let config = r#"
+[sources.logs]
+type = "demo_logs"
+
+[transforms.loop]
+type = "remap"
+inputs = ["logs", "loop"]
+
+[sinks.console]
+type = "console"
+inputs = ["loop"]
+"#;
+        
+        let result = validator().validate(config);
+        
+        assert!(!result.valid);
+        assert_eq!(result.errors[0].code, "SELF_REFERENCE");
+        assert_eq!(result.errors[0].component.as_deref(), Some("loop"));
+    }
+    
+    #[test]
+    fn test_duplicate_input_warning() {
+        let config = r#"
+[sources.logs]
+type = "demo_logs"
+
+[sinks.console]
+type = "console"
+inputs = ["logs", "logs", "logs"]
+"#;
+        
+        let result = validator().validate(config);
+        
+        assert!(result.valid);
+        let duplicates = result.warnings.iter()
+            .filter(|w| w.code == "DUPLICATE_INPUT")
+            .count();
+        assert_eq!(duplicates, 1);
+    }
+    
     #[test]
     fn test_warnings() {
         let config = r#"
@@ -868,6 +1203,7 @@ interval = 1
 
 [sources.file_logs]
 type = "file"
+include = ["/var/log/app/*.log"]
 
 [transforms.parse_json]
 type = "remap"
@@ -907,6 +1243,43 @@ inputs = ["parse_json"]
         assert_eq!(result.message, "Test failure");
     }
     
+    #[test]
+    fn test_validate_fragments_resolves_cross_file_inputs() {
+        // A binary that doesn't exist keeps the test to our own passes
+        let validator = ConfigValidator::new(Some("vectorize-missing-vector-bin".to_string()));
+        let sources = r#"
+[sources.app_logs]
+type = "file"
+include = ["/var/log/app/*.log"]
+"#;
+        let sinks = r#"
+[sinks.out]
+type = "console"
+inputs = ["app_logs"]
+encoding.codec = "json"
+"#;
+        
+        // On its own the sink fragment points at an unknown input
+        let result = validator.validate(sinks);
+        assert_eq!(result.errors[0].code, "INVALID_INPUT");
+        
+        let result = validator.validate_fragments(&[
+            ("sources.toml".to_string(), sources.to_string()),
+            ("sinks.toml".to_string(), sinks.to_string()),
+        ]);
+        assert!(result.valid, "{:?}", result.errors);
+        assert!(result.warnings.iter().all(|w| w.code != "NO_SOURCES" && w.code != "NO_SINKS"));
+        assert!(result.warnings.iter().any(|w| w.code == "VECTOR_NOT_FOUND"));
+        
+        // Syntax errors name the fragment they came from
+        let result = validator.validate_fragments(&[
+            ("sources.toml".to_string(), sources.to_string()),
+            ("sinks.toml".to_string(), "[sinks.out".to_string()),
+        ]);
+        assert_eq!(result.errors[0].code, "TOML_SYNTAX");
+        assert!(result.errors[0].message.starts_with("sinks.toml: "));
+    }
+    
     #[test]
     fn test_validation_result_with_error() {
         let result = ValidationResult::success()
@@ -916,6 +1289,7 @@ inputs = ["parse_json"]
                 line: Some(10),
                 column: Some(5),
                 component: Some("test_component".to_string()),
+                details: None,
             });
         
         assert!(!result.valid);
@@ -936,4 +1310,29 @@ inputs = ["parse_json"]
         assert!(result.valid);  // Warnings don't invalidate
         assert_eq!(result.warnings.len(), 1);
     }
+    
+    #[test]
+    fn test_lint_is_separate_from_validation() {
+        let config = r#"
+[sources.demo]
+type = "demo_logs"
+format = "json"
+
+[sinks.out]
+type = "console"
+inputs = ["demo"]
+encoding.codec = "json"
+"#;
+        let production = validator().with_production(true);
+        let result = production.validate(config);
+        assert!(result.valid);
+        assert!(!result.warnings.iter().any(|w| w.code == "CONSOLE_SINK_IN_PROD"));
+        
+        let lint = production.lint(config);
+        assert_eq!(lint.len(), 1);
+        assert_eq!(lint[0].code, "CONSOLE_SINK_IN_PROD");
+        
+        assert!(validator().lint(config).is_empty());
+        assert!(validator().lint("not [valid toml").is_empty());
+    }
 }
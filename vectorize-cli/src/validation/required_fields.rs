@@ -0,0 +1,148 @@
+//! Required configuration fields per component type
+//!
+//! A small built-in table of the options Vector refuses to start without,
+//! so common mistakes are caught before `vector validate` runs. Types not in
+//! the table are not checked.
+//!
+//! Every entry must also be marked required in the shared component catalog
+//! (`vectorize_shared::component_schema`); the catalog is the broader
+//! reference and this table is the subset enforced at validation time.
+
+pub use vectorize_shared::component_schema::ComponentKind;
+
+/// Name used in messages ("Source", "Sink")
+pub fn kind_label(kind: ComponentKind) -> &'static str {
+    match kind {
+        ComponentKind::Source => "Source",
+        ComponentKind::Transform => "Transform",
+        ComponentKind::Sink => "Sink",
+    }
+}
+
+/// Required fields for one component type
+///
+/// Each entry in `fields` is a list of alternatives, at least one of which
+/// must be set. Nested options use dotted paths (e.g. `encoding.codec`).
+#[derive(Debug)]
+pub struct ComponentRequirements {
+    pub kind: ComponentKind,
+    pub component_type: &'static str,
+    pub fields: &'static [&'static [&'static str]],
+}
+
+/// Built-in requirements; add an entry here to cover a new type
+const REQUIREMENTS: &[ComponentRequirements] = &[
+    // Sources
+    ComponentRequirements { kind: ComponentKind::Source, component_type: "file", fields: &[&["include"]] },
+    ComponentRequirements { kind: ComponentKind::Source, component_type: "kafka", fields: &[&["bootstrap_servers"], &["group_id"], &["topics"]] },
+    ComponentRequirements { kind: ComponentKind::Source, component_type: "http_server", fields: &[&["address"]] },
+    ComponentRequirements { kind: ComponentKind::Source, component_type: "socket", fields: &[&["mode"]] },
+    ComponentRequirements { kind: ComponentKind::Source, component_type: "syslog", fields: &[&["mode"]] },
+    ComponentRequirements { kind: ComponentKind::Source, component_type: "exec", fields: &[&["command"], &["mode"]] },
+    // Sinks
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "http", fields: &[&["uri"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "aws_s3", fields: &[&["bucket"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "gcp_cloud_storage", fields: &[&["bucket"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "kafka", fields: &[&["bootstrap_servers"], &["topic"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "elasticsearch", fields: &[&["endpoints", "endpoint"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "loki", fields: &[&["endpoint"], &["labels"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "splunk_hec_logs", fields: &[&["endpoint"], &["default_token"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "datadog_logs", fields: &[&["default_api_key"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "clickhouse", fields: &[&["endpoint"], &["table"]] },
+    ComponentRequirements { kind: ComponentKind::Sink, component_type: "file", fields: &[&["path"]] },
+];
+
+/// Look up the requirements for a component type
+pub fn requirements_for(kind: ComponentKind, component_type: &str) -> Option<&'static ComponentRequirements> {
+    REQUIREMENTS
+        .iter()
+        .find(|r| r.kind == kind && r.component_type == component_type)
+}
+
+/// Required fields missing from a component's config
+///
+/// Alternatives are reported joined with " or ". Unknown types have no
+/// requirements and always pass.
+pub fn missing_fields(kind: ComponentKind, component_type: &str, component: &toml::Value) -> Vec<String> {
+    let Some(requirements) = requirements_for(kind, component_type) else {
+        return Vec::new();
+    };
+    
+    requirements
+        .fields
+        .iter()
+        .filter(|alternatives| !alternatives.iter().any(|path| has_field(component, path)))
+        .map(|alternatives| alternatives.join(" or "))
+        .collect()
+}
+
+fn has_field(component: &toml::Value, path: &str) -> bool {
+    path.split('.')
+        .try_fold(component, |value, key| value.get(key))
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn component(config: &str) -> toml::Value {
+        toml::from_str(config).unwrap()
+    }
+    
+    #[test]
+    fn test_missing_fields() {
+        let sink = component(r#"type = "kafka""#);
+        assert_eq!(missing_fields(ComponentKind::Sink, "kafka", &sink), vec!["bootstrap_servers", "topic"]);
+    
+        let sink = component(r#"
+type = "kafka"
+bootstrap_servers = "localhost:9092"
+topic = "logs"
+"#);
+        assert!(missing_fields(ComponentKind::Sink, "kafka", &sink).is_empty());
+    }
+    
+    #[test]
+    fn test_alternatives_and_unknown_types() {
+        let sink = component(r#"type = "elasticsearch""#);
+        assert_eq!(missing_fields(ComponentKind::Sink, "elasticsearch", &sink), vec!["endpoints or endpoint"]);
+    
+        let sink = component(r#"endpoint = "http://es:9200""#);
+        assert!(missing_fields(ComponentKind::Sink, "elasticsearch", &sink).is_empty());
+    
+        // Unknown types, and types only known for the other kind, pass
+        assert!(missing_fields(ComponentKind::Sink, "my_custom_sink", &sink).is_empty());
+        assert!(missing_fields(ComponentKind::Source, "http", &sink).is_empty());
+    }
+    
+    #[test]
+    fn test_requirements_match_component_catalog() {
+        use vectorize_shared::component_schema::component_schema;
+        
+        for requirement in REQUIREMENTS {
+            let schema = component_schema(requirement.kind, requirement.component_type)
+                .unwrap_or_else(|| panic!("{} missing from catalog", requirement.component_type));
+            
+            for alternatives in requirement.fields {
+                let field = schema.field(alternatives[0]).unwrap_or_else(|| {
+                    panic!("{}.{} missing from catalog", requirement.component_type, alternatives[0])
+                });
+                assert!(field.required, "{}.{} not required in catalog", requirement.component_type, field.name);
+                for alternative in *alternatives {
+                    assert!(field.names().any(|n| n == *alternative));
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn test_nested_field_path() {
+        let value = component(r#"
+[encoding]
+codec = "json"
+"#);
+        assert!(has_field(&value, "encoding.codec"));
+        assert!(!has_field(&value, "encoding.timestamp_format"));
+    }
+}
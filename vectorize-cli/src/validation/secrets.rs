@@ -0,0 +1,236 @@
+//! Secret and environment variable reference checks
+//!
+//! Vector interpolates `${VAR}` / `$VAR` from the environment and
+//! `SECRET[backend.key]` from the `[secret.*]` backends when it loads a
+//! config. A reference to a backend the config doesn't define, or to an
+//! unset variable without a default, only fails on the agent, so these are
+//! flagged up front. Environment variables are only checked on request,
+//! since the control plane's environment is usually not the agents'.
+
+use std::collections::BTreeSet;
+
+use super::ValidationWarning;
+use super::required_fields::ComponentKind;
+
+/// An interpolation in a config string
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Reference {
+    /// `${NAME}`, `$NAME`, or with a default as `${NAME:-default}`
+    Env { name: String, has_default: bool },
+    /// `SECRET[backend.key]`
+    Secret { backend: String, key: String },
+}
+
+/// Warn about unresolved secret and environment variable references
+///
+/// `env_is_set` decides whether a variable is available; without it
+/// environment references are not checked.
+pub fn check(config: &toml::Value, env_is_set: Option<&dyn Fn(&str) -> bool>) -> Vec<ValidationWarning> {
+    let backends: BTreeSet<&str> = config.get("secret")
+        .and_then(|v| v.as_table())
+        .map(|table| table.keys().map(String::as_str).collect())
+        .unwrap_or_default();
+
+    // Collect per component so each warning can name where it is used
+    let mut found: BTreeSet<(Option<String>, Reference)> = BTreeSet::new();
+    let Some(table) = config.as_table() else {
+        return Vec::new();
+    };
+    for (key, value) in table {
+        let kind = [ComponentKind::Source, ComponentKind::Transform, ComponentKind::Sink]
+            .into_iter()
+            .find(|kind| kind.table_name() == key);
+        match (kind, value.as_table()) {
+            (Some(_), Some(components)) => {
+                for (id, component) in components {
+                    let mut refs = Vec::new();
+                    collect(component, &mut refs);
+                    found.extend(refs.into_iter().map(|r| (Some(id.clone()), r)));
+                }
+            }
+            _ => {
+                let mut refs = Vec::new();
+                collect(value, &mut refs);
+                found.extend(refs.into_iter().map(|r| (None, r)));
+            }
+        }
+    }
+
+    let mut warnings = Vec::new();
+    for (component, reference) in found {
+        let location = component.as_ref()
+            .map(|id| format!("Component '{}'", id))
+            .unwrap_or_else(|| "Configuration".to_string());
+        match reference {
+            Reference::Secret { backend, key } if !backends.contains(backend.as_str()) => {
+                warnings.push(ValidationWarning {
+                    code: "UNDECLARED_SECRET".to_string(),
+                    message: format!(
+                        "{} references secret '{}.{}' but no [secret.{}] backend is defined",
+                        location, backend, key, backend
+                    ),
+                    component,
+                });
+            }
+            Reference::Env { name, has_default: false } if env_is_set.is_some_and(|is_set| !is_set(&name)) => {
+                warnings.push(ValidationWarning {
+                    code: "UNDECLARED_ENV".to_string(),
+                    message: format!(
+                        "{} references environment variable '{}', which is not set",
+                        location, name
+                    ),
+                    component,
+                });
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+/// References in every string under a value
+fn collect(value: &toml::Value, refs: &mut Vec<Reference>) {
+    match value {
+        toml::Value::String(s) => refs.extend(references(s)),
+        toml::Value::Array(items) => items.iter().for_each(|item| collect(item, refs)),
+        toml::Value::Table(table) => table.values().for_each(|item| collect(item, refs)),
+        _ => {}
+    }
+}
+
+/// Interpolations in one string, following Vector's syntax
+///
+/// `$$` is an escaped dollar sign. `${NAME-default}` and `${NAME:-default}`
+/// fall back to the default, so they can't be unresolved.
+fn references(s: &str) -> Vec<Reference> {
+    let bytes = s.as_bytes();
+    let word_len = |from: usize| {
+        bytes[from..].iter().take_while(|b| b.is_ascii_alphanumeric() || **b == b'_').count()
+    };
+    let mut refs = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"SECRET[") {
+            let start = i + "SECRET[".len();
+            if let Some(len) = bytes[start..].iter().position(|b| *b == b']') {
+                if let Some((backend, key)) = s[start..start + len].split_once('.') {
+                    refs.push(Reference::Secret { backend: backend.to_string(), key: key.to_string() });
+                }
+                i = start + len + 1;
+                continue;
+            }
+        }
+
+        if bytes[i] == b'$' {
+            match bytes.get(i + 1) {
+                Some(b'$') => {
+                    i += 2;
+                    continue;
+                }
+                Some(b'{') => {
+                    let start = i + 2;
+                    let len = word_len(start);
+                    let rest = &s[start + len..];
+                    let has_default = rest.starts_with('-') || rest.starts_with(":-");
+                    let well_formed = ["}", "-", ":-", "?", ":?"].iter().any(|p| rest.starts_with(p));
+                    if len > 0 && well_formed {
+                        refs.push(Reference::Env { name: s[start..start + len].to_string(), has_default });
+                        i = start + len;
+                    } else {
+                        // Not a variable, but may wrap a secret: `${SECRET[...]}`
+                        i = start;
+                    }
+                    continue;
+                }
+                _ => {
+                    let len = word_len(i + 1);
+                    if len > 0 {
+                        refs.push(Reference::Env { name: s[i + 1..i + 1 + len].to_string(), has_default: false });
+                    }
+                    i += 1 + len;
+                    continue;
+                }
+            }
+        }
+
+        i += 1;
+    }
+
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(name: &str, has_default: bool) -> Reference {
+        Reference::Env { name: name.to_string(), has_default }
+    }
+
+    fn codes(warnings: &[ValidationWarning]) -> Vec<(&str, Option<&str>)> {
+        warnings.iter().map(|w| (w.code.as_str(), w.component.as_deref())).collect()
+    }
+
+    #[test]
+    fn test_reference_syntax() {
+        assert_eq!(references("${HOST}:$PORT"), vec![env("HOST", false), env("PORT", false)]);
+        assert_eq!(references("${LEVEL:-info} ${MODE-fast}"), vec![env("LEVEL", true), env("MODE", true)]);
+        assert_eq!(references("costs $$5 and ${}"), vec![]);
+        assert_eq!(references("${SECRET[vault.api_key]}"), vec![Reference::Secret {
+            backend: "vault".to_string(),
+            key: "api_key".to_string(),
+        }]);
+        assert_eq!(references("naïve SECRET[files.token] ü"), vec![Reference::Secret {
+            backend: "files".to_string(),
+            key: "token".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn test_declared_secret_is_not_flagged() {
+        let config: toml::Value = toml::from_str(r#"
+[secret.vault]
+type = "exec"
+command = ["/usr/bin/fetch-secrets"]
+
+[sinks.out]
+type = "http"
+inputs = ["in"]
+uri = "https://example.com"
+auth.strategy = "bearer"
+auth.token = "SECRET[vault.api_token]"
+"#).unwrap();
+
+        assert!(check(&config, None).is_empty());
+    }
+
+    #[test]
+    fn test_undeclared_secret_backend() {
+        let config: toml::Value = toml::from_str(r#"
+[sinks.out]
+type = "http"
+inputs = ["in"]
+uri = "https://example.com"
+auth.token = "${SECRET[vault.api_token]}"
+"#).unwrap();
+
+        assert_eq!(codes(&check(&config, None)), vec![("UNDECLARED_SECRET", Some("out"))]);
+    }
+
+    #[test]
+    fn test_undeclared_env() {
+        let config: toml::Value = toml::from_str(r#"
+[sources.in]
+type = "kafka"
+bootstrap_servers = "${KAFKA_BROKERS}"
+group_id = "${KAFKA_GROUP:-vector}"
+topics = ["$TOPIC"]
+"#).unwrap();
+        let is_set = |name: &str| name == "TOPIC";
+
+        assert_eq!(codes(&check(&config, Some(&is_set))), vec![("UNDECLARED_ENV", Some("in"))]);
+        // Not checked unless asked for
+        assert!(check(&config, None).is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+//! `vector validate` output parsing
+//!
+//! Vector reports each problem on its own `x ` line, often naming the
+//! component (`Sink "out": ...`) and, for TOML errors, a line and column.
+//! This splits that output into one `ValidationError` per problem so the
+//! editor can point at the offending component. Where Vector gives no
+//! position, the line of the component's table header is used instead.
+
+use super::required_fields::ComponentKind;
+use super::ValidationError;
+
+/// Error code for problems reported by `vector validate`
+pub const VECTOR_VALIDATE: &str = "VECTOR_VALIDATE";
+
+/// Parse `vector validate` output into structured errors
+///
+/// `config` is the config that was validated, used to locate components.
+/// Output without any recognizable error lines becomes a single error
+/// carrying the whole output.
+pub fn parse_vector_errors(output: &str, config: &str) -> Vec<ValidationError> {
+    let errors: Vec<ValidationError> = error_blocks(output)
+        .into_iter()
+        .map(|block| to_error(&block, config))
+        .collect();
+    
+    if !errors.is_empty() {
+        return errors;
+    }
+    
+    let output = output.trim();
+    vec![ValidationError {
+        code: VECTOR_VALIDATE.to_string(),
+        message: format!("Vector validation failed: {}", output.lines().next().unwrap_or("no output")),
+        line: None,
+        column: None,
+        component: None,
+        details: Some(output.to_string()),
+    }]
+}
+
+/// Split output into one block per `x ` error line plus its continuation
+///
+/// Banners (`Failed to load [...]`, dashed rules, `√`/`~` status lines)
+/// end the current block and are otherwise ignored.
+fn error_blocks(output: &str) -> Vec<Vec<&str>> {
+    let mut blocks: Vec<Vec<&str>> = Vec::new();
+    let mut in_block = false;
+    
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+        if let Some(headline) = trimmed.strip_prefix("x ") {
+            blocks.push(vec![headline.trim()]);
+            in_block = true;
+        } else if is_banner(trimmed) {
+            in_block = false;
+        } else if in_block && !trimmed.is_empty() {
+            if let Some(block) = blocks.last_mut() {
+                block.push(line.trim_end());
+            }
+        }
+    }
+    
+    blocks
+}
+
+fn is_banner(line: &str) -> bool {
+    line.starts_with("Failed to load")
+        || line.starts_with("Loaded")
+        || line.starts_with('√')
+        || line.starts_with("~ ")
+        || (!line.is_empty() && line.chars().all(|c| c == '-'))
+}
+
+fn to_error(block: &[&str], config: &str) -> ValidationError {
+    let headline = block[0].trim_end_matches(':');
+    let component = find_component(headline);
+    
+    let (line, column) = match find_position(headline) {
+        Some((line, column)) => (Some(line), Some(column)),
+        None => {
+            let line = component.as_ref().and_then(|(kind, name)| header_line(config, *kind, name));
+            (line, None)
+        }
+    };
+    
+    ValidationError {
+        code: VECTOR_VALIDATE.to_string(),
+        message: headline.to_string(),
+        line,
+        column,
+        component: component.map(|(_, name)| name),
+        details: Some(block.join("\n")),
+    }
+}
+
+/// The component a message is about
+///
+/// Recognizes `Source "id"`, `Transform "id"` and `Sink "id"` (in any case,
+/// so `Input "x" for sink "out"` resolves to `out`) and keys such as
+/// `` `sources.in.type` ``.
+fn find_component(message: &str) -> Option<(ComponentKind, String)> {
+    let lower = message.to_ascii_lowercase();
+    let kinds = [ComponentKind::Source, ComponentKind::Transform, ComponentKind::Sink];
+    
+    let quoted = kinds.iter()
+        .filter_map(|kind| {
+            let label = format!("{} \"", kind.table_name().trim_end_matches('s'));
+            lower.find(&label).map(|at| (at + label.len(), *kind))
+        })
+        .min_by_key(|(at, _)| *at)
+        .and_then(|(start, kind)| {
+            let len = message[start..].find('"')?;
+            Some((kind, message[start..start + len].to_string()))
+        });
+    if quoted.is_some() {
+        return quoted;
+    }
+    
+    kinds.iter().find_map(|kind| {
+        let prefix = format!("`{}.", kind.table_name());
+        let start = message.find(&prefix)? + prefix.len();
+        let name: String = message[start..]
+            .chars()
+            .take_while(|c| !matches!(c, '.' | '`'))
+            .collect();
+        (!name.is_empty()).then_some((*kind, name))
+    })
+}
+
+/// `line N, column M` (or `line N column M`) in a message
+fn find_position(message: &str) -> Option<(usize, usize)> {
+    fn number(s: &str) -> Option<(usize, &str)> {
+        let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        Some((s[..len].parse().ok()?, &s[len..]))
+    }
+    
+    message.match_indices("line ").find_map(|(at, _)| {
+        let (line, rest) = number(&message[at + "line ".len()..])?;
+        let rest = rest.trim_start_matches(',').trim_start().strip_prefix("column ")?;
+        let (column, _) = number(rest)?;
+        Some((line, column))
+    })
+}
+
+/// Line of a component's table header, e.g. `[sinks.out]`
+fn header_line(config: &str, kind: ComponentKind, name: &str) -> Option<usize> {
+    let headers = [
+        format!("[{}.{}]", kind.table_name(), name),
+        format!("[{}.\"{}\"]", kind.table_name(), name),
+    ];
+    config.lines()
+        .position(|line| {
+            let line = line.trim();
+            headers.iter().any(|header| line.starts_with(header.as_str()))
+        })
+        .map(|index| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const CONFIG: &str = r#"[sources.in]
+type = "stdin"
+
+[transforms.parse]
+type = "remap"
+inputs = ["in"]
+source = ". = parse_json!(.message"
+
+[sinks.out]
+type = "console"
+inputs = ["parse", "missing"]
+encoding.codec = "json"
+"#;
+
+    #[test]
+    fn test_component_errors() {
+        let stderr = r#"Failed to load ["/tmp/vectorize-validate-42.toml"]
+----------------------------------------------------
+x Input "missing" for sink "out" doesn't match any components.
+x Transform "parse":
+error[E203]: syntax error
+  ┌─ :1:27
+  │
+1 │ . = parse_json!(.message
+  │                           ^ unexpected end of program
+"#;
+
+        let errors = parse_vector_errors(stderr, CONFIG);
+        assert_eq!(errors.len(), 2);
+    
+        assert_eq!(errors[0].component.as_deref(), Some("out"));
+        assert_eq!(errors[0].line, Some(9));
+        assert_eq!(errors[0].message, r#"Input "missing" for sink "out" doesn't match any components."#);
+    
+        // VRL positions are relative to the program, so the header line is used
+        assert_eq!(errors[1].component.as_deref(), Some("parse"));
+        assert_eq!(errors[1].line, Some(4));
+        assert_eq!(errors[1].column, None);
+        assert_eq!(errors[1].message, r#"Transform "parse""#);
+        let details = errors[1].details.as_deref().unwrap();
+        assert!(details.contains("error[E203]: syntax error"));
+        assert!(details.contains("unexpected end of program"));
+        assert!(errors.iter().all(|e| e.code == VECTOR_VALIDATE));
+    }
+    
+    #[test]
+    fn test_position_and_key_errors() {
+        let stderr = r#"Failed to load ["/tmp/vectorize-validate-42.toml"]
+----------------------------------------------------
+x unknown variant `stdn`, expected one of `file`, `stdin` for key `sources.in.type` at line 2 column 8
+"#;
+
+        let errors = parse_vector_errors(stderr, CONFIG);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].component.as_deref(), Some("in"));
+        assert_eq!((errors[0].line, errors[0].column), (Some(2), Some(8)));
+    
+        let errors = parse_vector_errors("x TOML parse error at line 7, column 40\n", CONFIG);
+        assert_eq!((errors[0].line, errors[0].column), (Some(7), Some(40)));
+        assert_eq!(errors[0].component, None);
+    }
+    
+    #[test]
+    fn test_health_check_and_warning_lines() {
+        let stdout = r#"√ Loaded ["/tmp/vectorize-validate-42.toml"]
+~ Transform "parse" has no consumers
+√ Component configuration
+x Health check for "out" failed: connection refused
+"#;
+
+        let errors = parse_vector_errors(stdout, CONFIG);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, r#"Health check for "out" failed: connection refused"#);
+        assert_eq!(errors[0].component, None);
+        assert_eq!(errors[0].details.as_deref(), Some(errors[0].message.as_str()));
+    }
+    
+    #[test]
+    fn test_unrecognized_output_is_kept_whole() {
+        let errors = parse_vector_errors("error: unexpected argument '--config-toml'\n\nUsage: vector validate\n", CONFIG);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Vector validation failed: error: unexpected argument '--config-toml'");
+        assert_eq!(errors[0].line, None);
+        assert!(errors[0].details.as_deref().unwrap().contains("Usage: vector validate"));
+    }
+}
@@ -0,0 +1,855 @@
+//! YAML reading and writing for config conversion
+//!
+//! Covers the block-style YAML configs are written in: nested mappings and
+//! sequences, plain and quoted scalars, `|`/`>` block scalars, single-line
+//! flow collections (`[a, b]`, `{ k: v }`) and comments. Anchors, aliases,
+//! tags and multiple documents are rejected rather than guessed at.
+//! Scalars resolve with the YAML 1.2 core schema, and strings that other
+//! parsers could read as booleans (`yes`, `off`) are written quoted.
+
+use super::convert::{ConfigFormat, ConfigValue, ConversionError};
+
+// =============================================================================
+// Writing
+// =============================================================================
+
+/// Write a value as a YAML document
+pub(crate) fn write(value: &ConfigValue) -> String {
+    let mut out = String::new();
+    match value {
+        ConfigValue::Table(entries) if !entries.is_empty() => write_table(entries, 0, &mut out),
+        ConfigValue::Array(items) if !items.is_empty() => write_array(items, 0, &mut out),
+        other => {
+            out.push_str(&scalar(other, 0));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn write_table(entries: &[(String, ConfigValue)], indent: usize, out: &mut String) {
+    for (key, value) in entries {
+        out.push_str(&" ".repeat(indent));
+        out.push_str(&string(key, indent, false));
+        out.push(':');
+        match value {
+            ConfigValue::Table(entries) if !entries.is_empty() => {
+                out.push('\n');
+                write_table(entries, indent + 2, out);
+            }
+            ConfigValue::Array(items) if !items.is_empty() => {
+                out.push('\n');
+                write_array(items, indent + 2, out);
+            }
+            other => {
+                out.push(' ');
+                out.push_str(&scalar(other, indent + 2));
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn write_array(items: &[ConfigValue], indent: usize, out: &mut String) {
+    for item in items {
+        let nested = match item {
+            ConfigValue::Table(entries) if !entries.is_empty() => {
+                let mut nested = String::new();
+                write_table(entries, indent + 2, &mut nested);
+                Some(nested)
+            }
+            ConfigValue::Array(items) if !items.is_empty() => {
+                let mut nested = String::new();
+                write_array(items, indent + 2, &mut nested);
+                Some(nested)
+            }
+            _ => None,
+        };
+    
+        out.push_str(&" ".repeat(indent));
+        out.push_str("- ");
+        match nested {
+            // The first nested line goes on the dash line
+            Some(nested) => out.push_str(&nested[indent + 2..]),
+            None => {
+                out.push_str(&scalar(item, indent + 2));
+                out.push('\n');
+            }
+        }
+    }
+}
+
+/// A scalar or empty collection; `indent` is used by block scalars
+fn scalar(value: &ConfigValue, indent: usize) -> String {
+    match value {
+        ConfigValue::Null => "null".to_string(),
+        ConfigValue::Bool(b) => b.to_string(),
+        ConfigValue::Integer(i) => i.to_string(),
+        ConfigValue::Float(f) if f.is_nan() => ".nan".to_string(),
+        ConfigValue::Float(f) if f.is_infinite() => if *f > 0.0 { ".inf" } else { "-.inf" }.to_string(),
+        // Debug keeps the `.0` that marks a whole number as a float
+        ConfigValue::Float(f) => format!("{:?}", f),
+        ConfigValue::String(s) | ConfigValue::Datetime(s) => string(s, indent, true),
+        ConfigValue::Array(_) => "[]".to_string(),
+        ConfigValue::Table(_) => "{}".to_string(),
+    }
+}
+
+/// A string as a plain, literal block or double-quoted scalar
+fn string(s: &str, indent: usize, allow_block: bool) -> String {
+    if allow_block && fits_literal_block(s) {
+        let (header, body) = match s.strip_suffix('\n') {
+            Some(body) => ("|", body),
+            None => ("|-", s),
+        };
+        let mut block = header.to_string();
+        for line in body.split('\n') {
+            block.push('\n');
+            if !line.is_empty() {
+                block.push_str(&" ".repeat(indent));
+                block.push_str(line);
+            }
+        }
+        return block;
+    }
+    
+    if needs_quotes(s) {
+        quote(s)
+    } else {
+        s.to_string()
+    }
+}
+
+/// Whether a multi-line string reads back unchanged from a literal block
+fn fits_literal_block(s: &str) -> bool {
+    s.contains('\n')
+        && !s.starts_with([' ', '\t', '\n'])
+        && !s.ends_with("\n\n")
+        && !s.chars().any(|c| c.is_control() && c != '\n' && c != '\t')
+        && s.split('\n').all(|line| line.is_empty() || !line.trim().is_empty())
+        && s.split('\n').all(|line| !line.ends_with([' ', '\t']))
+}
+
+fn needs_quotes(s: &str) -> bool {
+    const INDICATORS: &[char] = &[
+        '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`',
+    ];
+    const YAML_11_BOOLEANS: &[&str] = &["y", "n", "yes", "no", "on", "off"];
+    
+    s.is_empty()
+        || s == "<<"
+        || resolve_plain(s) != Ok(ConfigValue::String(s.to_string()))
+        || YAML_11_BOOLEANS.contains(&s.to_ascii_lowercase().as_str())
+        || s.starts_with(INDICATORS)
+        || s.starts_with(char::is_whitespace)
+        || s.ends_with(char::is_whitespace)
+        || s.ends_with(':')
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.chars().any(char::is_control)
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// =============================================================================
+// Reading
+// =============================================================================
+
+/// Parse a single YAML document
+pub(crate) fn parse(text: &str) -> Result<ConfigValue, ConversionError> {
+    let mut parser = Parser::new(text)?;
+    
+    let Some(first) = parser.peek()? else {
+        return Ok(ConfigValue::Null);
+    };
+    if parser.lines[first].indent != 0 {
+        return Err(parser.error_at(first, "the document must start at the first column"));
+    }
+    
+    let value = parser.block(0)?;
+    if let Some(index) = parser.peek()? {
+        return Err(parser.error_at(index, "unexpected content"));
+    }
+    Ok(value)
+}
+
+struct Line<'a> {
+    /// Line as written, for block scalars
+    raw: &'a str,
+    indent: usize,
+    /// Indented with a tab, which is only allowed inside block scalars
+    tab: bool,
+    /// Content after the indent, without any comment
+    text: String,
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    pos: usize,
+}
+
+fn error(line: usize, message: impl Into<String>) -> ConversionError {
+    ConversionError::parse(ConfigFormat::Yaml, message, Some(line))
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Result<Self, ConversionError> {
+        let mut lines = Vec::new();
+        let mut seen_content = false;
+    
+        for (index, raw) in text.lines().enumerate() {
+            let indent = raw.len() - raw.trim_start_matches(' ').len();
+            let rest = &raw[indent..];
+            let tab = rest.starts_with('\t');
+    
+            let text = strip_comment(rest).trim_end().to_string();
+            if indent == 0 && (text == "---" || text.starts_with("--- ")) {
+                if seen_content {
+                    return Err(error(index + 1, "multiple documents are not supported"));
+                }
+                // A leading document marker stands in for a blank line
+                lines.push(Line { raw, indent, tab, text: String::new() });
+                continue;
+            }
+            if indent == 0 && (text == "..." || text.starts_with('%')) {
+                return Err(error(index + 1, "directives and document end markers are not supported"));
+            }
+    
+            seen_content |= !text.is_empty();
+            lines.push(Line { raw, indent, tab, text });
+        }
+    
+        Ok(Self { lines, pos: 0 })
+    }
+    
+    fn error_at(&self, index: usize, message: impl Into<String>) -> ConversionError {
+        error(index + 1, message)
+    }
+    
+    /// Index of the next line with content, skipping blanks and comments
+    fn peek(&mut self) -> Result<Option<usize>, ConversionError> {
+        while self.pos < self.lines.len() && self.lines[self.pos].text.is_empty() {
+            self.pos += 1;
+        }
+        match self.lines.get(self.pos) {
+            Some(line) if line.tab => Err(self.error_at(self.pos, "tabs are not allowed for indentation")),
+            Some(_) => Ok(Some(self.pos)),
+            None => Ok(None),
+        }
+    }
+    
+    /// A mapping, sequence or scalar starting at the current line
+    fn block(&mut self, indent: usize) -> Result<ConfigValue, ConversionError> {
+        let index = self.pos;
+        let text = self.lines[index].text.clone();
+    
+        if is_sequence_item(&text) {
+            self.sequence(indent)
+        } else if split_key(&text).map_err(|m| self.error_at(index, m))?.is_some() {
+            self.mapping(indent)
+        } else {
+            self.pos += 1;
+            self.inline_value(&text, index)
+        }
+    }
+    
+    fn mapping(&mut self, indent: usize) -> Result<ConfigValue, ConversionError> {
+        let mut entries: Vec<(String, ConfigValue)> = Vec::new();
+    
+        while let Some(index) = self.peek()? {
+            let line = &self.lines[index];
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return Err(self.error_at(index, "unexpected indentation"));
+            }
+            let text = line.text.clone();
+            let Some((key, rest)) = split_key(&text).map_err(|m| self.error_at(index, m))? else {
+                return Err(self.error_at(index, "expected `key: value`"));
+            };
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(self.error_at(index, format!("duplicate key `{}`", key)));
+            }
+    
+            self.pos += 1;
+            let value = self.value(rest, indent, index, true)?;
+            entries.push((key, value));
+        }
+    
+        Ok(ConfigValue::Table(entries))
+    }
+    
+    fn sequence(&mut self, indent: usize) -> Result<ConfigValue, ConversionError> {
+        let mut items = Vec::new();
+    
+        while let Some(index) = self.peek()? {
+            let line = &self.lines[index];
+            if line.indent < indent || !is_sequence_item(&line.text) {
+                if line.indent > indent {
+                    return Err(self.error_at(index, "unexpected indentation"));
+                }
+                break;
+            }
+            if line.indent > indent {
+                return Err(self.error_at(index, "unexpected indentation"));
+            }
+    
+            let text = line.text.clone();
+            let rest = text[1..].trim_start();
+            let nested = is_sequence_item(rest)
+                || split_key(rest).map_err(|m| self.error_at(index, m))?.is_some();
+    
+            if nested {
+                // `- key: value` and `- - item` start a block at the item's column
+                let column = indent + (text.len() - rest.len());
+                self.lines[index].indent = column;
+                self.lines[index].text = rest.to_string();
+                items.push(self.block(column)?);
+            } else {
+                self.pos += 1;
+                items.push(self.value(rest, indent, index, false)?);
+            }
+        }
+    
+        Ok(ConfigValue::Array(items))
+    }
+    
+    /// The value after `key:` or `-` on line `index`
+    ///
+    /// An empty value continues on the following, more indented lines; in
+    /// a mapping, a sequence may also sit at the key's own indent.
+    fn value(&mut self, rest: &str, indent: usize, index: usize, in_mapping: bool) -> Result<ConfigValue, ConversionError> {
+        if rest.starts_with(['|', '>']) {
+            return self.block_scalar(rest, indent, index);
+        }
+        if !rest.is_empty() {
+            return self.inline_value(rest, index);
+        }
+    
+        match self.peek()? {
+            Some(next) if self.lines[next].indent > indent => {
+                let nested = self.lines[next].indent;
+                self.block(nested)
+            }
+            Some(next) if in_mapping && self.lines[next].indent == indent && is_sequence_item(&self.lines[next].text) => {
+                self.sequence(indent)
+            }
+            _ => Ok(ConfigValue::Null),
+        }
+    }
+    
+    /// A scalar or flow collection written on one line
+    fn inline_value(&self, text: &str, index: usize) -> Result<ConfigValue, ConversionError> {
+        if text.starts_with(['&', '*', '!']) {
+            return Err(self.error_at(index, "anchors, aliases and tags are not supported"));
+        }
+        let mut flow = Flow { chars: text.chars().collect(), pos: 0, depth: 0 };
+        let value = flow.value().map_err(|m| self.error_at(index, m))?;
+        flow.skip_spaces();
+        if flow.pos < flow.chars.len() {
+            return Err(self.error_at(index, format!("unexpected `{}`", flow.chars[flow.pos..].iter().collect::<String>())));
+        }
+        Ok(value)
+    }
+    
+    /// A `|` (literal) or `>` (folded) block scalar
+    fn block_scalar(&mut self, header: &str, indent: usize, index: usize) -> Result<ConfigValue, ConversionError> {
+        let folded = header.starts_with('>');
+        let mut chomp = '=';
+        let mut explicit_indent = None;
+        for c in header[1..].chars() {
+            match c {
+                '-' | '+' => chomp = c,
+                '1'..='9' => explicit_indent = c.to_digit(10).map(|d| indent + d as usize),
+                _ => return Err(self.error_at(index, format!("invalid block scalar header `{}`", header))),
+            }
+        }
+    
+        let mut body: Vec<&str> = Vec::new();
+        let mut block_indent = explicit_indent;
+        while self.pos < self.lines.len() {
+            let raw = self.lines[self.pos].raw;
+            let blank = raw.trim().is_empty();
+            let line_indent = raw.len() - raw.trim_start_matches(' ').len();
+            if !blank && line_indent <= indent {
+                break;
+            }
+            if !blank && block_indent.is_none() {
+                block_indent = Some(line_indent);
+            }
+            match block_indent {
+                Some(block_indent) if !blank && line_indent < block_indent => {
+                    return Err(self.error_at(self.pos, "block scalar line is less indented than the block"));
+                }
+                Some(block_indent) => body.push(raw.get(block_indent..).unwrap_or("")),
+                None => body.push(""),
+            }
+            self.pos += 1;
+        }
+    
+        let trailing = body.iter().rev().take_while(|line| line.is_empty()).count();
+        let content = &body[..body.len() - trailing];
+    
+        let mut text = if folded { fold(content) } else { content.join("\n") };
+        match chomp {
+            '-' => {}
+            '+' => text.push_str(&"\n".repeat(trailing + usize::from(!content.is_empty()))),
+            _ if !content.is_empty() => text.push('\n'),
+            _ => {}
+        }
+        Ok(ConfigValue::String(text))
+    }
+}
+
+/// Join folded lines: single breaks become spaces, blank lines become breaks
+///
+/// More-indented lines keep their breaks, as in YAML.
+fn fold(lines: &[&str]) -> String {
+    let mut text = String::new();
+    let mut previous: Option<&str> = None;
+    for line in lines {
+        if let Some(previous) = previous {
+            let literal = previous.is_empty()
+                || line.is_empty()
+                || previous.starts_with([' ', '\t'])
+                || line.starts_with([' ', '\t']);
+            text.push(if literal { '\n' } else { ' ' });
+        }
+        text.push_str(line);
+        previous = Some(line);
+    }
+    text
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Drop a `#` comment that is outside quotes and follows whitespace
+fn strip_comment(text: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    let mut chars = text.char_indices().peekable();
+    
+    while let Some((at, c)) = chars.next() {
+        match quote {
+            Some('"') if c == '\\' => {
+                chars.next();
+            }
+            Some('\'') if c == '\'' && chars.peek().is_some_and(|(_, n)| *n == '\'') => {
+                chars.next();
+            }
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && (previous.is_whitespace() || "[{,:-".contains(previous)) => {
+                quote = Some(c);
+            }
+            None if c == '#' && previous.is_whitespace() => return &text[..at],
+            None => {}
+        }
+        previous = c;
+    }
+    text
+}
+
+/// Split `key: value` into the key and the value text
+///
+/// Returns None when the line has no mapping key (a plain scalar).
+fn split_key(text: &str) -> Result<Option<(String, &str)>, String> {
+    if text.starts_with(['[', '{']) {
+        return Ok(None);
+    }
+    if text.starts_with('?') && (text.len() == 1 || text[1..].starts_with(' ')) {
+        return Err("complex mapping keys are not supported".to_string());
+    }
+    
+    let is_separator = |rest: &str| rest.is_empty() || rest.starts_with([' ', '\t']);
+    
+    if text.starts_with(['"', '\'']) {
+        let mut flow = Flow { chars: text.chars().collect(), pos: 0, depth: 0 };
+        let key = flow.quoted()?;
+        let consumed: usize = flow.chars[..flow.pos].iter().map(|c| c.len_utf8()).sum();
+        let after = text[consumed..].trim_start();
+        return Ok(after.strip_prefix(':')
+            .filter(|rest| is_separator(rest))
+            .map(|rest| (key, rest.trim())));
+    }
+    
+    let split = text.match_indices(':').find(|(at, _)| is_separator(&text[at + 1..]));
+    let Some((at, _)) = split else {
+        return Ok(None);
+    };
+    let key = text[..at].trim_end();
+    if key == "<<" {
+        return Err("merge keys are not supported".to_string());
+    }
+    if key.starts_with(['&', '*', '!']) {
+        return Err("anchors, aliases and tags are not supported".to_string());
+    }
+    Ok(Some((key.to_string(), text[at + 1..].trim())))
+}
+
+/// Resolve a plain scalar with the YAML 1.2 core schema
+fn resolve_plain(text: &str) -> Result<ConfigValue, String> {
+    let value = match text {
+        "" | "~" | "null" | "Null" | "NULL" => ConfigValue::Null,
+        "true" | "True" | "TRUE" => ConfigValue::Bool(true),
+        "false" | "False" | "FALSE" => ConfigValue::Bool(false),
+        ".nan" | ".NaN" | ".NAN" => ConfigValue::Float(f64::NAN),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => ConfigValue::Float(f64::INFINITY),
+        "-.inf" | "-.Inf" | "-.INF" => ConfigValue::Float(f64::NEG_INFINITY),
+        _ => {
+            let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+            let digits = |s: &str, radix: u32| !s.is_empty() && s.chars().all(|c| c.is_digit(radix));
+    
+            if digits(unsigned, 10) {
+                let value = text.parse::<i64>().map_err(|_| format!("integer {} is out of range", text))?;
+                ConfigValue::Integer(value)
+            } else if let Some(hex) = text.strip_prefix("0x").filter(|s| digits(s, 16)) {
+                let value = i64::from_str_radix(hex, 16).map_err(|_| format!("integer {} is out of range", text))?;
+                ConfigValue::Integer(value)
+            } else if let Some(octal) = text.strip_prefix("0o").filter(|s| digits(s, 8)) {
+                let value = i64::from_str_radix(octal, 8).map_err(|_| format!("integer {} is out of range", text))?;
+                ConfigValue::Integer(value)
+            } else if is_float(unsigned) {
+                ConfigValue::Float(text.parse().map_err(|_| format!("invalid number {}", text))?)
+            } else {
+                ConfigValue::String(text.to_string())
+            }
+        }
+    };
+    Ok(value)
+}
+
+/// `(\.[0-9]+|[0-9]+(\.[0-9]*)?)([eE][-+]?[0-9]+)?`
+fn is_float(text: &str) -> bool {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(at) => (&text[..at], Some(&text[at + 1..])),
+        None => (text, None),
+    };
+    let digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    
+    let mantissa_ok = match mantissa.split_once('.') {
+        Some((whole, fraction)) => digits(whole) && digits(fraction) && !(whole.is_empty() && fraction.is_empty()),
+        None => !mantissa.is_empty() && digits(mantissa),
+    };
+    let exponent_ok = exponent.is_none_or(|e| {
+        let e = e.strip_prefix(['-', '+']).unwrap_or(e);
+        !e.is_empty() && digits(e)
+    });
+    
+    mantissa_ok && exponent_ok && (mantissa.contains('.') || exponent.is_some())
+}
+
+/// Single-line flow values: scalars, `[...]` and `{...}`
+struct Flow {
+    chars: Vec<char>,
+    pos: usize,
+    /// Nesting inside `[...]`/`{...}`, where `,` `]` `}` end a plain scalar
+    depth: usize,
+}
+
+impl Flow {
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+    
+    fn value(&mut self) -> Result<ConfigValue, String> {
+        self.skip_spaces();
+        match self.chars.get(self.pos) {
+            Some('[') => {
+                self.depth += 1;
+                self.sequence()
+            }
+            Some('{') => {
+                self.depth += 1;
+                self.mapping()
+            }
+            Some('"' | '\'') => self.quoted().map(ConfigValue::String),
+            Some('&' | '*' | '!') => Err("anchors, aliases and tags are not supported".to_string()),
+            _ => resolve_plain(&self.plain(false)),
+        }
+    }
+    
+    fn sequence(&mut self) -> Result<ConfigValue, String> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.chars.get(self.pos) {
+                Some(']') => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Ok(ConfigValue::Array(items));
+                }
+                None => return Err("unterminated `[`; flow sequences must fit on one line".to_string()),
+                _ => {}
+            }
+            items.push(self.value()?);
+            self.separator(']')?;
+        }
+    }
+    
+    fn mapping(&mut self) -> Result<ConfigValue, String> {
+        self.pos += 1;
+        let mut entries: Vec<(String, ConfigValue)> = Vec::new();
+        loop {
+            self.skip_spaces();
+            match self.chars.get(self.pos) {
+                Some('}') => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Ok(ConfigValue::Table(entries));
+                }
+                None => return Err("unterminated `{`; flow mappings must fit on one line".to_string()),
+                _ => {}
+            }
+    
+            let key = match self.chars.get(self.pos) {
+                Some('"' | '\'') => self.quoted()?,
+                _ => self.plain(true),
+            };
+            self.skip_spaces();
+            if self.chars.get(self.pos) != Some(&':') {
+                return Err(format!("expected `:` after key `{}`", key));
+            }
+            self.pos += 1;
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(format!("duplicate key `{}`", key));
+            }
+            let value = self.value()?;
+            entries.push((key, value));
+            self.separator('}')?;
+        }
+    }
+    
+    /// Consume a `,` or stop before the closing bracket
+    fn separator(&mut self, close: char) -> Result<(), String> {
+        self.skip_spaces();
+        match self.chars.get(self.pos) {
+            Some(',') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) if *c == close => Ok(()),
+            Some(c) => Err(format!("expected `,` or `{}`, found `{}`", close, c)),
+            None => Err(format!("expected `{}`", close)),
+        }
+    }
+    
+    /// A plain scalar, ending at flow indicators (and `: ` for keys)
+    fn plain(&mut self, key: bool) -> String {
+        let start = self.pos;
+        while let Some(&c) = self.chars.get(self.pos) {
+            let ends_key = key
+                && c == ':'
+                && self.chars.get(self.pos + 1).is_none_or(|n| n.is_whitespace() || ",]}".contains(*n));
+            if ends_key || (self.depth > 0 && ",]}".contains(c)) {
+                break;
+            }
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect::<String>().trim().to_string()
+    }
+    
+    fn quoted(&mut self) -> Result<String, String> {
+        let quote = self.chars[self.pos];
+        self.pos += 1;
+        let mut text = String::new();
+    
+        while let Some(&c) = self.chars.get(self.pos) {
+            self.pos += 1;
+            match c {
+                '\'' if quote == '\'' => {
+                    if self.chars.get(self.pos) == Some(&'\'') {
+                        self.pos += 1;
+                        text.push('\'');
+                    } else {
+                        return Ok(text);
+                    }
+                }
+                '"' if quote == '"' => return Ok(text),
+                '\\' if quote == '"' => text.push(self.escape()?),
+                c => text.push(c),
+            }
+        }
+        Err("unterminated quoted string; quoted strings must fit on one line".to_string())
+    }
+    
+    fn escape(&mut self) -> Result<char, String> {
+        let Some(&c) = self.chars.get(self.pos) else {
+            return Err("unterminated escape".to_string());
+        };
+        self.pos += 1;
+    
+        let hex_len = match c {
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            _ => {
+                return match c {
+                    '0' => Ok('\0'),
+                    'a' => Ok('\u{07}'),
+                    'b' => Ok('\u{08}'),
+                    't' | '\t' => Ok('\t'),
+                    'n' => Ok('\n'),
+                    'v' => Ok('\u{0b}'),
+                    'f' => Ok('\u{0c}'),
+                    'r' => Ok('\r'),
+                    'e' => Ok('\u{1b}'),
+                    ' ' | '"' | '/' | '\\' => Ok(c),
+                    'N' => Ok('\u{85}'),
+                    '_' => Ok('\u{a0}'),
+                    other => Err(format!("unknown escape `\\{}`", other)),
+                };
+            }
+        };
+    
+        let digits: String = self.chars.iter().skip(self.pos).take(hex_len).collect();
+        self.pos += digits.len();
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() == hex_len)
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("invalid escape `\\{}{}`", c, digits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn table(entries: &[(&str, ConfigValue)]) -> ConfigValue {
+        ConfigValue::Table(entries.iter().map(|(k, v)| (k.to_string(), v.clone())).collect())
+    }
+    
+    fn string(s: &str) -> ConfigValue {
+        ConfigValue::String(s.to_string())
+    }
+    
+    #[test]
+    fn test_parse_hand_written_yaml() {
+        let value = parse(r#"---
+# Edge pipeline
+sources:
+  app:
+    type: file
+    include: ["/var/log/*.log", '/tmp/it''s.log']
+    ignore_older_secs: 600
+transforms:
+  parse:
+    type: remap
+    inputs:
+    - app
+    source: |
+      . = parse_json!(.message)
+    
+      .tag = "a # not a comment"
+    drop_on_error: true
+sinks:
+  out:
+    type: http
+    uri: http://collector:8080/ingest # trailing comment
+    inputs: [parse]
+    request: { retry_attempts: 3, timeout_secs: 1.5 }
+    auth:
+      strategy: "bearer"
+      token: ~
+    headers:
+      - name: x-env
+        value: "prod\tlive"
+      -
+        name: x-empty
+        value: ''
+"#).unwrap();
+
+        let app = value.get("sources").unwrap().get("app").unwrap();
+        assert_eq!(app.get("include"), Some(&ConfigValue::Array(vec![string("/var/log/*.log"), string("/tmp/it's.log")])));
+        assert_eq!(app.get("ignore_older_secs"), Some(&ConfigValue::Integer(600)));
+    
+        let parse_transform = value.get("transforms").unwrap().get("parse").unwrap();
+        assert_eq!(parse_transform.get("inputs"), Some(&ConfigValue::Array(vec![string("app")])));
+        assert_eq!(
+            parse_transform.get("source"),
+            Some(&string(". = parse_json!(.message)\n\n.tag = \"a # not a comment\"\n")),
+        );
+        assert_eq!(parse_transform.get("drop_on_error"), Some(&ConfigValue::Bool(true)));
+    
+        let out = value.get("sinks").unwrap().get("out").unwrap();
+        assert_eq!(out.get("uri"), Some(&string("http://collector:8080/ingest")));
+        assert_eq!(out.get("request"), Some(&table(&[
+            ("retry_attempts", ConfigValue::Integer(3)),
+            ("timeout_secs", ConfigValue::Float(1.5)),
+        ])));
+        assert_eq!(out.get("auth").unwrap().get("token"), Some(&ConfigValue::Null));
+        assert_eq!(out.get("headers"), Some(&ConfigValue::Array(vec![
+            table(&[("name", string("x-env")), ("value", string("prod\tlive"))]),
+            table(&[("name", string("x-empty")), ("value", string(""))]),
+        ])));
+    }
+    
+    #[test]
+    fn test_written_scalars_read_back_unchanged() {
+        let strings = [
+            "plain", "", "true", "yes", "Off", "null", "~", "42", "1.5", "1e3", ".inf", "- dash",
+            "key: value", "trailing:", "a #b", " padded ", "quote\"d", "back\\slash", "tab\there",
+            "one\ntwo\n", "no newline\nat end", "two\n\ntrailing\n\n", " leading\nspace", "*star",
+            "http://example.com:80/a#b", "ünïcødé", "\u{7}bell",
+        ];
+        let value = ConfigValue::Table(vec![
+            ("strings".to_string(), ConfigValue::Array(strings.iter().map(|s| string(s)).collect())),
+            ("numbers".to_string(), ConfigValue::Array(vec![
+                ConfigValue::Integer(-7),
+                ConfigValue::Float(2.0),
+                ConfigValue::Float(-0.25),
+                ConfigValue::Float(1e100),
+                ConfigValue::Float(f64::NEG_INFINITY),
+            ])),
+            ("nested".to_string(), ConfigValue::Array(vec![
+                ConfigValue::Array(vec![ConfigValue::Integer(1), ConfigValue::Array(vec![])]),
+                table(&[("empty", table(&[])), ("text", string("a\nb\n"))]),
+            ])),
+        ]);
+    
+        let yaml = write(&value);
+        assert_eq!(parse(&yaml).unwrap(), value, "{}", yaml);
+    }
+    
+    #[test]
+    fn test_unsupported_yaml_is_rejected() {
+        let cases = [
+            ("a: &anchor 1\nb: *anchor\n", 1),
+            ("a: 1\n---\nb: 2\n", 2),
+            ("a:\n\tb: 1\n", 2),
+            ("a: [1,\n  2]\n", 1),
+            ("a: 1\na: 2\n", 2),
+            ("a: 1\n  b: 2\n", 2),
+            ("<<: {a: 1}\n", 1),
+        ];
+        for (yaml, line) in cases {
+            match parse(yaml) {
+                Err(ConversionError::Parse { line: Some(l), .. }) => assert_eq!(l, line, "{:?}", yaml),
+                other => panic!("expected a parse error for {:?}, got {:?}", yaml, other),
+            }
+        }
+    }
+}
@@ -2,14 +2,24 @@
 //!
 //! Manages the Vector process lifecycle - starting, stopping, and monitoring.
 //! Vector is built from the same workspace and runs as a subprocess.
+//!
+//! Several local instances (e.g. an aggregator and an agent) can run side by
+//! side, each with its own name, config and API port; the one started from
+//! the command line is named `default`.
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::process::Stdio;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::Serialize;
 use tokio::process::Command;
-use tokio::sync::RwLock;
+use tokio::sync::{oneshot, RwLock};
 use tracing::{info, warn, error};
 
+/// Name of the instance started from the command line
+pub const DEFAULT_INSTANCE: &str = "default";
+
 /// Shared state for the Vector process
 #[derive(Clone)]
 pub struct VectorProcess {
@@ -56,6 +66,16 @@ impl VectorProcess {
         inner.config_path = config_path;
     }
     
+    /// Forget the process once it has exited
+    async fn clear_process(&self) {
+        self.inner.write().await.pid = None;
+    }
+    
+    /// PID of the managed Vector process, if one was started
+    pub async fn pid(&self) -> Option<u32> {
+        self.inner.read().await.pid
+    }
+    
     /// Get the config path
     pub async fn config_path(&self) -> Option<PathBuf> {
         self.inner.read().await.config_path.clone()
@@ -113,9 +133,18 @@ pub async fn start_vector(
     process_state: VectorProcess,
 ) -> anyhow::Result<(tokio::task::JoinHandle<anyhow::Result<()>>, PathBuf)> {
     let vector_path = find_vector_binary(vector_bin);
-    let config_path = config.cloned();
-    let vector_path_clone = vector_path.clone();
+    let handle = spawn_vector(vector_path.clone(), config.cloned(), api_port, process_state, None);
+    Ok((handle, vector_path))
+}
 
+/// Spawn Vector and wait for it to exit, or kill it once `stop` fires
+fn spawn_vector(
+    vector_path: PathBuf,
+    config_path: Option<PathBuf>,
+    api_port: u16,
+    process_state: VectorProcess,
+    stop: Option<oneshot::Receiver<()>>,
+) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     info!("Starting Vector from: {:?}", vector_path);
 
     let mut cmd = Command::new(&vector_path);
@@ -135,7 +164,7 @@ pub async fn start_vector(
     cmd.stdout(Stdio::inherit());
     cmd.stderr(Stdio::inherit());
 
-    let handle = tokio::spawn(async move {
+    tokio::spawn(async move {
         match cmd.spawn() {
             Ok(mut child) => {
                 let pid = child.id();
@@ -146,8 +175,28 @@ pub async fn start_vector(
                     process_state.set_process(pid, config_path).await;
                 }
                 
-                // Wait for Vector to exit
-                match child.wait().await {
+                // Wait for Vector to exit, unless asked to stop it first
+                let stopped = async {
+                    match stop {
+                        Some(stop) => { let _ = stop.await; }
+                        None => std::future::pending::<()>().await,
+                    }
+                };
+                let exited = tokio::select! {
+                    status = child.wait() => Some(status),
+                    _ = stopped => None,
+                };
+                let Some(status) = exited else {
+                    info!("Stopping Vector (PID: {:?})", pid);
+                    if let Err(e) = child.kill().await {
+                        warn!("Failed to kill Vector: {}", e);
+                    }
+                    process_state.clear_process().await;
+                    return Ok(());
+                };
+                process_state.clear_process().await;
+                
+                match status {
                     Ok(status) => {
                         if status.success() {
                             info!("Vector exited successfully");
@@ -173,9 +222,133 @@ pub async fn start_vector(
             }
         }
         Ok(())
+    })
+}
+
+/// A managed Vector instance
+struct Instance {
+    /// Distinguishes a restarted instance from the one it replaced
+    generation: u64,
+    api_port: u16,
+    process: VectorProcess,
+    stop: Option<oneshot::Sender<()>>,
+}
+
+/// A running instance, as reported by `list_instances`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct InstanceInfo {
+    pub name: String,
+    pub api_port: u16,
+    pub pid: Option<u32>,
+    pub config_path: Option<PathBuf>,
+}
+
+/// Local Vector instances by name
+#[derive(Clone)]
+pub struct VectorInstances {
+    vector_bin: Option<PathBuf>,
+    instances: Arc<RwLock<BTreeMap<String, Instance>>>,
+    generation: Arc<AtomicU64>,
+}
+
+impl VectorInstances {
+    /// Registry launching `vector_bin` (found next to vectorize or on PATH if unset)
+    pub fn new(vector_bin: Option<PathBuf>) -> Self {
+        Self {
+            vector_bin,
+            instances: Arc::new(RwLock::new(BTreeMap::new())),
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+    
+    /// Process state of a running instance
+    pub async fn get(&self, name: &str) -> Option<VectorProcess> {
+        self.instances.read().await.get(name).map(|i| i.process.clone())
+    }
+}
+
+impl Default for VectorInstances {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// Start a named Vector instance
+///
+/// Names and API ports must be unique among running instances. The instance
+/// leaves the registry when its process exits or `stop_instance` is called.
+pub async fn start_instance(
+    registry: &VectorInstances,
+    name: &str,
+    config: Option<&PathBuf>,
+    api_port: u16,
+) -> anyhow::Result<(tokio::task::JoinHandle<anyhow::Result<()>>, VectorProcess)> {
+    let vector_path = find_vector_binary(&registry.vector_bin);
+    let process = VectorProcess::with_binary_path(vector_path.clone());
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let generation = registry.generation.fetch_add(1, Ordering::Relaxed);
+    
+    {
+        let mut instances = registry.instances.write().await;
+        if instances.contains_key(name) {
+            anyhow::bail!("Vector instance '{}' is already running", name);
+        }
+        if let Some((other, _)) = instances.iter().find(|(_, i)| i.api_port == api_port) {
+            anyhow::bail!("API port {} is already used by Vector instance '{}'", api_port, other);
+        }
+        instances.insert(name.to_string(), Instance {
+            generation,
+            api_port,
+            process: process.clone(),
+            stop: Some(stop_tx),
+        });
+    }
+    
+    info!("Starting Vector instance '{}' (API port {})", name, api_port);
+    let handle = spawn_vector(vector_path, config.cloned(), api_port, process.clone(), Some(stop_rx));
+    
+    // Forget the instance once its process is gone
+    let instances = registry.instances.clone();
+    let name = name.to_string();
+    let handle = tokio::spawn(async move {
+        let result = handle.await.unwrap_or_else(|e| Err(anyhow::anyhow!("Vector task failed: {}", e)));
+        let mut instances = instances.write().await;
+        if instances.get(&name).is_some_and(|i| i.generation == generation) {
+            instances.remove(&name);
+        }
+        result
     });
+    
+    Ok((handle, process))
+}
+
+/// Stop a named Vector instance
+pub async fn stop_instance(registry: &VectorInstances, name: &str) -> anyhow::Result<()> {
+    let stop = registry.instances.write().await
+        .get_mut(name)
+        .ok_or_else(|| anyhow::anyhow!("Vector instance '{}' is not running", name))?
+        .stop
+        .take();
+    
+    if let Some(stop) = stop {
+        let _ = stop.send(());
+    }
+    Ok(())
+}
 
-    Ok((handle, vector_path_clone))
+/// Running instances, by name
+pub async fn list_instances(registry: &VectorInstances) -> Vec<InstanceInfo> {
+    let instances = registry.instances.read().await;
+    let mut list = Vec::with_capacity(instances.len());
+    for (name, instance) in instances.iter() {
+        list.push(InstanceInfo {
+            name: name.clone(),
+            api_port: instance.api_port,
+            pid: instance.process.pid().await,
+            config_path: instance.process.config_path().await,
+        });
+    }
+    list
 }
 
 /// Run Vector with passthrough arguments (for `vectorize vector ...` command)
@@ -201,3 +374,71 @@ pub async fn run_vector_passthrough(
 
     Ok(())
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    
+    /// A stand-in for the Vector binary that just stays up
+    fn mock_vector(dir: &std::path::Path) -> PathBuf {
+        let path = dir.join("vector");
+        std::fs::write(&path, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+    
+    async fn wait_for_pid(process: &VectorProcess) -> u32 {
+        for _ in 0..100 {
+            if let Some(pid) = process.pid().await {
+                return pid;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("instance did not start");
+    }
+    
+    #[tokio::test]
+    async fn test_start_list_and_stop_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = VectorInstances::new(Some(mock_vector(dir.path())));
+        let config = dir.path().join("aggregator.toml");
+    
+        let (_, default) = start_instance(&registry, DEFAULT_INSTANCE, None, 8686).await.unwrap();
+        let (aggregator_handle, aggregator) = start_instance(&registry, "aggregator", Some(&config), 8687)
+            .await
+            .unwrap();
+        let default_pid = wait_for_pid(&default).await;
+        let aggregator_pid = wait_for_pid(&aggregator).await;
+        assert_ne!(default_pid, aggregator_pid);
+    
+        let instances = list_instances(&registry).await;
+        assert_eq!(instances, vec![
+            InstanceInfo {
+                name: "aggregator".to_string(),
+                api_port: 8687,
+                pid: Some(aggregator_pid),
+                config_path: Some(config.clone()),
+            },
+            InstanceInfo {
+                name: DEFAULT_INSTANCE.to_string(),
+                api_port: 8686,
+                pid: Some(default_pid),
+                config_path: None,
+            },
+        ]);
+    
+        // Names and API ports are unique
+        assert!(start_instance(&registry, "aggregator", None, 9000).await.is_err());
+        assert!(start_instance(&registry, "other", None, 8686).await.is_err());
+    
+        stop_instance(&registry, "aggregator").await.unwrap();
+        aggregator_handle.await.unwrap().unwrap();
+        assert!(aggregator.pid().await.is_none());
+        let names: Vec<String> = list_instances(&registry).await.into_iter().map(|i| i.name).collect();
+        assert_eq!(names, vec![DEFAULT_INSTANCE.to_string()]);
+    
+        assert!(stop_instance(&registry, "aggregator").await.is_err());
+        stop_instance(&registry, DEFAULT_INSTANCE).await.unwrap();
+    }
+}
@@ -12,7 +12,7 @@ use tempfile::tempdir;
 use std::sync::Arc;
 
 // Test utilities
-async fn setup_test_app() -> (Router, tempfile::TempDir) {
+async fn setup_test_state() -> (Arc<vectorize::AppState>, tempfile::TempDir) {
     let dir = tempdir().unwrap();
     let db_path = dir.path().join("test.db");
     
@@ -29,20 +29,51 @@ async fn setup_test_app() -> (Router, tempfile::TempDir) {
     // Create services
     let tap_service = Arc::new(vectorize::tap::TapService::new(vectorize::tap::RateLimitConfig::default()));
     let functional_test_service = Arc::new(vectorize::validation::FunctionalTestService::new(None));
+    let health_monitor = Arc::new(vectorize::health::HealthMonitor::new(
+        db.clone(),
+        vectorize::health::HealthMonitorConfig::default(),
+    ));
     
     // Create app state
     let state = Arc::new(vectorize::AppState {
         vector_api_url: "http://localhost:8686".to_string(),
         http_client: reqwest::Client::new(),
+        agent_clients: Arc::new(vectorize::agent_clients::AgentClients::default()),
         vector_process,
+        vector_instances: vectorize::vector_manager::VectorInstances::default(),
         db,
         git_store: Arc::new(git_store),
         tap_service,
         functional_test_service,
+        health_monitor,
+        metrics_handle: vectorize::telemetry::install_recorder(),
+        deployment_events: Arc::new(vectorize::deployment::DeploymentEventBus::new()),
+        live_events: Arc::new(vectorize::live::LiveEventBus::new()),
+        deployment_push_limit: Arc::new(tokio::sync::Semaphore::new(
+            vectorize::deployment::DEFAULT_MAX_CONCURRENT_PUSHES,
+        )),
+        shutdown: Arc::new(vectorize::deployment::ShutdownCoordinator::new()),
+        bootstrap_token: Arc::new(vectorize::api::auth::BootstrapToken::default()),
+        maintenance: Arc::new(vectorize::api::maintenance::MaintenanceMode::default()),
+        // Most tests call the API without credentials
+        allow_anonymous: true,
+        cipher: None,
     });
     
+    (state, dir)
+}
+
+/// The API router behind the server's optional auth layer
+fn api_router(state: &Arc<vectorize::AppState>) -> Router<Arc<vectorize::AppState>> {
+    vectorize::api::create_api_router()
+        .layer(axum::middleware::from_fn_with_state(state.clone(), vectorize::rbac::optional_auth_middleware))
+}
+
+async fn setup_test_app() -> (Router, tempfile::TempDir) {
+    let (state, dir) = setup_test_state().await;
+    
     // Build the API router with state
-    let api_router = vectorize::api::create_api_router();
+    let api_router = api_router(&state);
     let app = Router::new()
         .nest("/api/v1", api_router)
         .with_state(state);
@@ -141,6 +172,129 @@ async fn test_setup_init_validation() {
     assert!(response.status() == StatusCode::BAD_REQUEST || response.status() == StatusCode::UNPROCESSABLE_ENTITY);
 }
 
+#[tokio::test]
+async fn test_bootstrap_admin_from_env_on_fresh_db() {
+    use vectorize::api::auth::{bootstrap_admin, BootstrapAdmin, BOOTSTRAP_ADMIN_ENV};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    std::env::set_var(BOOTSTRAP_ADMIN_ENV, "ops:ops@example.com:pass:with:colons");
+    let admin = BootstrapAdmin::from_env();
+    std::env::remove_var(BOOTSTRAP_ADMIN_ENV);
+    let admin = admin.unwrap().expect("bootstrap admin should be read from the environment");
+    assert_eq!(admin.password, "pass:with:colons");
+    
+    let user = bootstrap_admin(&state.db, &admin).await.unwrap().expect("admin should be created");
+    assert_eq!(user.role_id, "admin");
+    assert!(!state.db.is_fresh().await.unwrap());
+    
+    // The wizard is closed and the bootstrapped credentials work
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/api/v1/setup/status").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(json_response(response).await["is_setup"], true);
+    
+    let body = json!({ "identifier": "ops", "password": "pass:with:colons" });
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/auth/login", &body, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    let body = json!({ "username": "other", "email": "other@test.com", "password": "securePassword123!" });
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/setup/init", &body, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_bootstrap_admin_is_noop_on_existing_install() {
+    use vectorize::api::auth::{bootstrap_admin, BootstrapAdmin};
+    use vectorize::db::repository::UserRepository;
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let body = json!({ "username": "admin", "email": "admin@test.com", "password": "securePassword123!" });
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/setup/init", &body, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    let admin: BootstrapAdmin = "ops:ops@example.com:anotherPassword1".parse().unwrap();
+    assert!(bootstrap_admin(&state.db, &admin).await.unwrap().is_none());
+    assert!(UserRepository::get_by_username(state.db.pool(), "ops").await.unwrap().is_none());
+    
+    // Malformed values are rejected before touching the database
+    assert!("ops:ops@example.com".parse::<BootstrapAdmin>().is_err());
+    assert!("ops:ops@example.com:short".parse::<BootstrapAdmin>().is_err());
+}
+
+#[tokio::test]
+async fn test_setup_init_requires_bootstrap_token() {
+    use vectorize::api::auth::BootstrapToken;
+    
+    let (state, _dir) = setup_test_state().await;
+    let mut state = (*state).clone();
+    state.bootstrap_token = Arc::new(BootstrapToken::new(Some("s3cret-token".to_string())));
+    let app = Router::new()
+        .nest("/api/v1", vectorize::api::create_api_router())
+        .with_state(Arc::new(state));
+    
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/api/v1/setup/status").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let json = json_response(response).await;
+    assert_eq!(json["is_setup"], false);
+    assert_eq!(json["requires_bootstrap_token"], true);
+    
+    let mut body = json!({ "username": "admin", "email": "admin@test.com", "password": "securePassword123!" });
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/setup/init", &body, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    
+    body["bootstrap_token"] = json!("wrong-token");
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/setup/init", &body, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    
+    body["bootstrap_token"] = json!("s3cret-token");
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/setup/init", &body, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/api/v1/setup/status").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let json = json_response(response).await;
+    assert_eq!(json["is_setup"], true);
+    assert_eq!(json["requires_bootstrap_token"], false);
+    
+    // The token is spent with the first setup
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/setup/init", &body, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
 // =============================================================================
 // Agent Tests
 // =============================================================================
@@ -182,6 +336,119 @@ async fn test_agent_register() {
     }
 }
 
+#[tokio::test]
+async fn test_agent_register_with_existing_group_name() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let group = vectorize::db::repository::WorkerGroupRepository::create(state.db.pool(), "edge", None, None)
+        .await
+        .unwrap();
+    
+    // Groups resolve by name as well as ID
+    let body = json!({ "name": "agent-1", "url": "http://localhost:9000", "group_id": "edge" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let json = json_response(response).await;
+    assert_eq!(json["agent"]["group_id"], group.id);
+    
+    let body = json!({ "name": "agent-2", "url": "http://localhost:9001", "group_id": group.id });
+    let response = app.oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let json = json_response(response).await;
+    assert_eq!(json["agent"]["group_id"], group.id);
+}
+
+#[tokio::test]
+async fn test_agent_register_auto_creates_group() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let body = json!({
+        "name": "agent-1",
+        "url": "http://localhost:9000",
+        "group_id": "new-group",
+        "auto_group": true
+    });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let json = json_response(response).await;
+    
+    let group = vectorize::db::repository::WorkerGroupRepository::get_by_name(state.db.pool(), "new-group")
+        .await
+        .unwrap()
+        .expect("group should have been created");
+    assert_eq!(json["agent"]["group_id"], group.id);
+    
+    // A second auto-group registration reuses the group
+    let body = json!({
+        "name": "agent-2",
+        "url": "http://localhost:9001",
+        "group_id": "new-group",
+        "auto_group": true
+    });
+    let response = app.oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    let json = json_response(response).await;
+    assert_eq!(json["agent"]["group_id"], group.id);
+    assert_eq!(
+        vectorize::db::repository::WorkerGroupRepository::list(state.db.pool()).await.unwrap().len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_agent_register_missing_group_without_auto_group() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let body = json!({ "name": "agent-1", "url": "http://localhost:9000", "group_id": "missing" });
+    let response = app.oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    
+    // Nothing was created
+    assert!(vectorize::db::repository::AgentRepository::get_by_name(state.db.pool(), "agent-1").await.unwrap().is_none());
+    assert!(vectorize::db::repository::WorkerGroupRepository::get_by_name(state.db.pool(), "missing").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_agent_register_same_url_is_idempotent() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let body = json!({ "name": "agent-1", "url": "http://localhost:9000" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let id = json_response(response).await["agent"]["id"].as_str().unwrap().to_string();
+    
+    // Re-running provisioning under a new name updates the existing agent
+    let body = json!({ "name": "agent-renamed", "url": "http://localhost:9000", "group_id": "edge", "auto_group": true });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["agent"]["id"], id.as_str());
+    assert_eq!(json["agent"]["name"], "agent-renamed");
+    
+    let agents = vectorize::db::repository::AgentRepository::list(state.db.pool()).await.unwrap();
+    assert_eq!(agents.len(), 1);
+    
+    // Taking another agent's name is rejected
+    let body = json!({ "name": "agent-2", "url": "http://localhost:9001" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    let body = json!({ "name": "agent-2", "url": "http://localhost:9000" });
+    let response = app.oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
 #[tokio::test]
 async fn test_agent_list() {
     let (app, _dir) = setup_test_app().await;
@@ -205,6 +472,112 @@ async fn test_agent_list() {
     // Can be empty if no agents registered
 }
 
+#[tokio::test]
+async fn test_agent_list_ndjson_stream() {
+    let (app, _dir) = setup_test_app().await;
+    
+    for i in 0..3 {
+        let body = json!({ "name": format!("edge-{}", i), "url": format!("http://10.0.0.{}:8686", i) });
+        app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    }
+    
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/api/v1/agents")
+                .header("accept", "application/x-ndjson")
+                .body(Body::empty())
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.ends_with('\n'));
+    let agents: Vec<Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let names: Vec<_> = agents.iter().map(|a| a["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["edge-0", "edge-1", "edge-2"]);
+    assert!(agents.iter().all(|a| a["id"].is_string()));
+}
+
+#[tokio::test]
+async fn test_agent_list_includes_tags() {
+    let (app, _dir) = setup_test_app().await;
+    
+    let body = json!({ "name": "edge-1", "url": "http://10.0.0.1:8686", "tags": { "env": "prod", "region": "eu" } });
+    app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    let body = json!({ "name": "edge-2", "url": "http://10.0.0.2:8686" });
+    app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    
+    let list = |query: &str, accept: &str| {
+        Request::builder()
+            .uri(format!("/api/v1/agents{}", query))
+            .header("accept", accept)
+            .body(Body::empty())
+            .unwrap()
+    };
+    
+    // Plain, paginated and filtered listings all carry tags
+    for query in ["", "?limit=1", "?tag=env:prod&limit=5"] {
+        let response = app.clone().oneshot(list(query, "application/json")).await.unwrap();
+        let agents = json_response(response).await;
+        assert_eq!(agents[0]["name"], "edge-1", "{}", query);
+        assert_eq!(agents[0]["tags"], json!({ "env": "prod", "region": "eu" }), "{}", query);
+    }
+    
+    let response = app.oneshot(list("", "application/x-ndjson")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let agents: Vec<Value> = String::from_utf8(body.to_vec()).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(agents[0]["tags"]["region"], "eu");
+    assert!(agents[1].get("tags").is_none());
+}
+
+#[tokio::test]
+async fn test_agent_list_include_deleted_with_filters() {
+    use vectorize::db::repository::{AgentRepository, WorkerGroupRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let group = WorkerGroupRepository::create(pool, "edge", None, None).await.unwrap();
+    let tags = [("env".to_string(), "prod".to_string())];
+    let mut agents = Vec::new();
+    for name in ["edge-1", "edge-2"] {
+        let agent = AgentRepository::create(pool, name, &format!("http://{}:8686", name), Some(&group.id)).await.unwrap();
+        AgentRepository::set_tags(pool, &agent.id, &tags).await.unwrap();
+        agents.push(agent);
+    }
+    AgentRepository::delete(pool, &agents[1].id).await.unwrap();
+    
+    let names = |query: String| {
+        let app = app.clone();
+        async move {
+            let request = Request::builder().uri(format!("/api/v1/agents?{}", query)).body(Body::empty()).unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            json_response(response).await.as_array().unwrap().iter()
+                .map(|a| a["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+    
+    for filter in [format!("group_id={}", group.id), "tag=env:prod".to_string()] {
+        assert_eq!(names(filter.clone()).await, ["edge-1"]);
+        assert_eq!(names(format!("{}&include_deleted=true", filter)).await, ["edge-1", "edge-2"]);
+        assert_eq!(names(format!("{}&include_deleted=true&limit=1&offset=1", filter)).await, ["edge-2"]);
+    }
+}
+
 // =============================================================================
 // Worker Group Tests
 // =============================================================================
@@ -300,6 +673,98 @@ inputs = ["demo"]
     assert_eq!(json["valid"], true);
 }
 
+#[tokio::test]
+async fn test_explain_config_graph() {
+    let (app, _dir) = setup_test_app().await;
+    
+    let config = r#"
+[sources.app]
+type = "demo_logs"
+
+[transforms.parse]
+type = "remap"
+inputs = ["app"]
+
+[transforms.split]
+type = "route"
+inputs = ["parse"]
+
+[sinks.errors]
+type = "console"
+inputs = ["split.errors"]
+
+[sinks.all]
+type = "console"
+inputs = ["parse"]
+"#;
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/config/explain", &json!({ "config": config }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    let json = json_response(response).await;
+    let edges: Vec<(&str, &str)> = json["edges"].as_array().unwrap().iter()
+        .map(|e| (e["from"].as_str().unwrap(), e["to"].as_str().unwrap()))
+        .collect();
+    assert_eq!(edges, vec![("app", "parse"), ("parse", "split"), ("parse", "all"), ("split", "errors")]);
+    assert_eq!(json["edges"][3]["output"], "errors");
+    assert_eq!(json["nodes"][0]["kind"], "source");
+    assert_eq!(json["nodes"][0]["type"], "demo_logs");
+    assert_eq!(json["has_cycle"], false);
+    
+    // Syntax errors are rejected
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/config/explain", &json!({ "config": "[sources.x" }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json_response(response).await["error"]["details"]["code"], "TOML_SYNTAX");
+}
+
+#[tokio::test]
+async fn test_analyze_config() {
+    let (app, _dir) = setup_test_app().await;
+    
+    let config = r#"
+[sources.app]
+type = "demo_logs"
+
+[transforms.parse]
+type = "remap"
+inputs = ["app"]
+
+[sinks.a]
+type = "console"
+inputs = ["parse"]
+
+[sinks.b]
+type = "blackhole"
+inputs = ["parse"]
+
+[sinks.c]
+type = "blackhole"
+inputs = ["parse"]
+"#;
+    
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/config/analyze", &json!({ "config": config }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    let json = json_response(response).await;
+    assert_eq!(json["sources"], 1);
+    assert_eq!(json["transforms"], 1);
+    assert_eq!(json["sinks"], 3);
+    assert_eq!(json["vrl_transforms"], 1);
+    assert_eq!(json["types"]["blackhole"], 2);
+    assert_eq!(json["max_depth"], 3);
+    assert_eq!(json["fan_out"][0]["component"], "parse");
+    assert_eq!(json["fan_out"][0]["consumers"], 3);
+}
+
 #[tokio::test]
 async fn test_validate_invalid_config() {
     let (app, _dir) = setup_test_app().await;
@@ -368,6 +833,74 @@ inputs = ["demo"]
     assert_eq!(json["valid"], true);
 }
 
+#[tokio::test]
+async fn test_validate_lint() {
+    let (app, _dir) = setup_test_app().await;
+    
+    let config = r#"
+[sources.demo]
+type = "demo_logs"
+
+[sinks.console]
+type = "console"
+inputs = ["demo"]
+"#;
+    
+    let body = json!({
+        "config": config,
+        "production": true
+    });
+    
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/validate/lint")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    let json = json_response(response).await;
+    let warnings = json["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["code"], "CONSOLE_SINK_IN_PROD");
+    assert_eq!(warnings[0]["component"], "console");
+}
+
+#[tokio::test]
+async fn test_validate_oversized_body_rejected() {
+    let (app, _dir) = setup_test_app().await;
+    
+    let limit = vectorize::api::body_limit::DEFAULT_MAX_CONFIG_BODY_BYTES;
+    let body = json!({
+        "config": "#".repeat(limit + 1)
+    });
+    
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/validate")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    
+    let json = json_response(response).await;
+    assert_eq!(json["error"]["code"], "payload_too_large");
+    assert_eq!(json["error"]["details"]["limit_bytes"], limit);
+    assert!(json["error"]["message"].as_str().unwrap().contains("too large"));
+}
+
 // =============================================================================
 // Health Check Tests
 // =============================================================================
@@ -392,3 +925,2145 @@ async fn test_health_check_agents() {
     let json = json_response(response).await;
     assert!(json.is_object());
 }
+
+// =============================================================================
+// Rate Limiting Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_rate_limit_returns_429() {
+    let (app, _dir) = setup_test_app().await;
+    
+    let limiter = Arc::new(vectorize::rate_limit::ApiRateLimiter::new(
+        vectorize::rate_limit::ApiRateLimitConfig {
+            requests_per_minute_per_ip: 2,
+            requests_per_minute_per_token: 2,
+        },
+    ));
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        limiter,
+        vectorize::rate_limit::enforce,
+    ));
+    
+    let request = || {
+        Request::builder()
+            .method(Method::GET)
+            .uri("/api/v1/setup/status")
+            .body(Body::empty())
+            .unwrap()
+    };
+    
+    for _ in 0..2 {
+        let response = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    
+    let response = app.clone().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    
+    let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!(retry_after >= 1);
+    
+    let json = json_response(response).await;
+    assert_eq!(json["error"]["code"], "rate_limited");
+    assert_eq!(json["error"]["message"], "Rate limit exceeded");
+    
+    // Made-up credentials don't get a fresh bucket
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/setup/status")
+                .header("authorization", "Bearer made-up-token")
+                .header("x-api-key", "vz_made_up")
+                .body(Body::empty())
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+// =============================================================================
+// Audit Log Tests
+// =============================================================================
+
+fn json_request(method: Method, uri: &str, body: &Value, token: Option<&str>) -> Request<Body> {
+    let body = body.to_string();
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("content-length", body.len());
+    if let Some(token) = token {
+        builder = builder.header("authorization", format!("Bearer {}", token));
+    }
+    builder.body(Body::from(body)).unwrap()
+}
+
+/// App with the server's auth and audit layers, plus an admin bearer token
+async fn setup_authenticated_app() -> (Router, tempfile::TempDir, String) {
+    let (_state, app, dir, token) = setup_authenticated_state().await;
+    (app, dir, token)
+}
+
+/// Like [`setup_authenticated_app`], also returning the app's state
+async fn setup_authenticated_state() -> (Arc<vectorize::AppState>, Router, tempfile::TempDir, String) {
+    let (state, dir) = setup_test_state().await;
+    
+    let app = Router::new()
+        .nest("/api/v1", vectorize::api::create_api_router())
+        .layer(axum::middleware::from_fn_with_state(state.clone(), vectorize::api::audit::record_mutations))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), vectorize::rbac::optional_auth_middleware))
+        .with_state(state.clone());
+    
+    let admin = json!({
+        "username": "admin",
+        "email": "admin@test.com",
+        "password": "securePassword123!"
+    });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/setup/init", &admin, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    let login = json!({ "identifier": "admin", "password": "securePassword123!" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/auth/login", &login, None)).await.unwrap();
+    let token = json_response(response).await["token"].as_str().unwrap().to_string();
+    
+    (state, app, dir, token)
+}
+
+#[tokio::test]
+async fn test_builtin_roles_grant_access() {
+    use vectorize::db::repository::UserRepository;
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let admin = json!({ "username": "admin", "email": "admin@test.com", "password": "securePassword123!" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/setup/init", &admin, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    // Same password as the admin
+    let password_hash = UserRepository::get_by_username(pool, "admin").await.unwrap().unwrap().password_hash.unwrap();
+    let mut tokens = std::collections::HashMap::new();
+    for role in ["viewer", "operator"] {
+        UserRepository::create(pool, role, &format!("{}@test.com", role), &password_hash, role).await.unwrap();
+        let login = json!({ "identifier": role, "password": "securePassword123!" });
+        let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/auth/login", &login, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        tokens.insert(role, json_response(response).await["token"].as_str().unwrap().to_string());
+    }
+    let get = |uri: &str, token: &str| {
+        Request::builder()
+            .uri(uri)
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    };
+    
+    // Viewers read but don't write
+    let response = app.clone().oneshot(get("/api/v1/groups", &tokens["viewer"])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(get("/api/v1/agents", &tokens["viewer"])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let group = json!({ "name": "edge" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &group, Some(&tokens["viewer"]))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    
+    // Operators manage groups but can't read the audit log
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &group, Some(&tokens["operator"]))).await.unwrap();
+    assert!(response.status().is_success());
+    let response = app.clone().oneshot(get("/api/v1/audit", &tokens["operator"])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_audit_middleware_records_mutations() {
+    let (app, _dir, token) = setup_authenticated_app().await;
+    
+    let group = json!({ "name": "production" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &group, Some(&token))).await.unwrap();
+    assert!(response.status().is_success());
+    
+    // Without a token the CLI user header is only a claim
+    let response = app.clone().oneshot(
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/v1/groups")
+            .header("content-type", "application/json")
+            .header("x-vectorize-user", "admin")
+            .body(Body::from(json!({ "name": "staging" }).to_string()))
+            .unwrap()
+    ).await.unwrap();
+    assert!(response.status().is_success());
+    
+    // Reading the audit log requires a token and is not itself audited
+    let response = app.clone().oneshot(
+        Request::builder().uri("/api/v1/audit").body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    
+    let response = app.clone().oneshot(
+        Request::builder()
+            .uri("/api/v1/audit?limit=10")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    let json = json_response(response).await;
+    assert_eq!(json["total"], 4);
+    
+    let entries = json["entries"].as_array().unwrap();
+    let create = entries.iter().find(|e| e["action"] == "POST /api/v1/groups" && e["actor_type"] == "user").unwrap();
+    assert_eq!(create["actor_type"], "user");
+    assert_eq!(create["resource_type"], "groups");
+    assert_eq!(create["result"], "success");
+    assert_eq!(create["details"]["body"]["fields"], json!(["name"]));
+    
+    // Credentials are summarized by field name only
+    let setup = entries.iter().find(|e| e["action"] == "POST /api/v1/setup/init").unwrap();
+    assert_eq!(setup["actor_type"], "anonymous");
+    assert!(!setup["details"].to_string().contains("securePassword123!"));
+    
+    let claimed = entries.iter().find(|e| e["action"] == "POST /api/v1/groups" && e["actor_type"] == "anonymous").unwrap();
+    assert!(claimed["actor_name"].is_null());
+    assert_eq!(claimed["details"]["claimed_user"], "admin");
+    
+    // Filter by user
+    let user_id = create["actor_id"].as_str().unwrap();
+    let response = app.clone().oneshot(
+        Request::builder()
+            .uri(format!("/api/v1/audit?user={}", user_id))
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    ).await.unwrap();
+    let json = json_response(response).await;
+    assert_eq!(json["total"], 1);
+    
+    // Claimed names don't match the user filter
+    let response = app.clone().oneshot(
+        Request::builder()
+            .uri("/api/v1/audit?user=admin")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    ).await.unwrap();
+    let json = json_response(response).await;
+    assert_eq!(json["total"], 0);
+}
+
+#[tokio::test]
+async fn test_anonymous_requests_need_opt_in() {
+    let (state, _dir) = setup_test_state().await;
+    let mut locked = (*state).clone();
+    locked.allow_anonymous = false;
+    let locked = Arc::new(locked);
+    let app = Router::new()
+        .nest("/api/v1", api_router(&locked))
+        .with_state(locked.clone());
+    let get = |uri: &str, token: Option<&str>| {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(token) = token {
+            builder = builder.header("authorization", format!("Bearer {}", token));
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    // Protected routes refuse missing and invalid credentials alike
+    let response = app.clone().oneshot(get("/api/v1/groups", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(get("/api/v1/groups", Some("not-a-real-token"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/validate", &json!({ "config": "" }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Public routes stay open
+    let response = app.clone().oneshot(get("/api/v1/setup/status", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // With anonymous access enabled, only requests without credentials get in
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let response = app.clone().oneshot(get("/api/v1/groups", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(get("/api/v1/groups", Some("not-a-real-token"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_maintenance_mode_blocks_writes() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", vectorize::api::create_api_router())
+        .layer(axum::middleware::from_fn_with_state(state.clone(), vectorize::api::maintenance::reject_writes))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), vectorize::rbac::optional_auth_middleware))
+        .with_state(state.clone());
+    
+    let admin = json!({
+        "username": "admin",
+        "email": "admin@test.com",
+        "password": "securePassword123!"
+    });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/setup/init", &admin, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    let toggle = json!({ "enabled": true, "message": "Upgrading" });
+    let response = app.clone().oneshot(json_request(Method::PUT, "/api/v1/system/maintenance", &toggle, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    
+    // Login keeps working, so admins can turn maintenance off again
+    let login = json!({ "identifier": "admin", "password": "securePassword123!" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/auth/login", &login, None)).await.unwrap();
+    let token = json_response(response).await["token"].as_str().unwrap().to_string();
+    
+    let response = app.clone().oneshot(json_request(Method::PUT, "/api/v1/system/maintenance", &toggle, Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(state.maintenance.is_enabled());
+    
+    let group = json!({ "name": "production" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &group, Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = json_response(response).await;
+    assert_eq!(body["error"]["details"]["maintenance"], true);
+    assert_eq!(body["error"]["details"]["message"], "Upgrading");
+    
+    let response = app.clone().oneshot(
+        Request::builder().uri("/api/v1/groups").body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(
+        Request::builder().uri("/api/v1/system/maintenance").body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(json_response(response).await["enabled"], true);
+    
+    let toggle = json!({ "enabled": false });
+    let response = app.clone().oneshot(json_request(Method::PUT, "/api/v1/system/maintenance", &toggle, Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &group, Some(&token))).await.unwrap();
+    assert!(response.status().is_success());
+}
+
+// =============================================================================
+// CORS Tests
+// =============================================================================
+
+fn preflight(origin: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/api/v1/groups")
+        .header("origin", origin)
+        .header("access-control-request-method", "POST")
+        .header("access-control-request-headers", "authorization")
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_cors_preflight_allows_configured_origin() {
+    let (app, _dir) = setup_test_app().await;
+    let app = app.layer(vectorize::server::cors_layer(Some("https://ui.example.com, https://other.example.com")));
+    
+    let response = app.clone().oneshot(preflight("https://ui.example.com")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["access-control-allow-origin"], "https://ui.example.com");
+    assert!(response.headers()["access-control-allow-headers"].to_str().unwrap().contains("authorization"));
+    
+    let response = app.clone().oneshot(preflight("https://evil.example.com")).await.unwrap();
+    assert!(response.headers().get("access-control-allow-origin").is_none());
+    
+    // Pagination totals are readable by the browser
+    let response = app.oneshot(
+        Request::builder()
+            .uri("/api/v1/groups?limit=10")
+            .header("origin", "https://ui.example.com")
+            .body(Body::empty())
+            .unwrap()
+    ).await.unwrap();
+    assert_eq!(response.headers()["x-total-count"], "0");
+    let exposed = response.headers()["access-control-expose-headers"].to_str().unwrap();
+    assert!(exposed.contains("x-total-count") && exposed.contains("x-request-id"));
+}
+
+#[tokio::test]
+async fn test_cors_same_origin_only_by_default() {
+    let (app, _dir) = setup_test_app().await;
+    let app = app.layer(vectorize::server::cors_layer(None));
+    
+    let response = app.oneshot(preflight("https://ui.example.com")).await.unwrap();
+    assert!(response.headers().get("access-control-allow-origin").is_none());
+}
+
+// =============================================================================
+// API Key Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_api_key_lifecycle() {
+    let (app, _dir, token) = setup_authenticated_app().await;
+    
+    let body = json!({ "name": "ci", "permissions": ["audit_read"], "expires_in_days": 30 });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/api-keys", &body, Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    let json = json_response(response).await;
+    let key = json["key"].as_str().unwrap().to_string();
+    let key_id = json["api_key"]["id"].as_str().unwrap().to_string();
+    assert!(key.starts_with("vz_"));
+    assert!(key.starts_with(json["api_key"]["key_prefix"].as_str().unwrap()));
+    
+    let audit_with = |header: &'static str, value: String| {
+        Request::builder()
+            .uri("/api/v1/audit")
+            .header(header, value)
+            .body(Body::empty())
+            .unwrap()
+    };
+    
+    // The key authenticates via X-API-Key or as a bearer token
+    let response = app.clone().oneshot(audit_with("x-api-key", key.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(audit_with("authorization", format!("Bearer {}", key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    // It is limited to the permissions it was created with
+    let body = json!({ "name": "nested" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/api-keys", &body, Some(&key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    
+    // A wrong key is rejected
+    let response = app.clone().oneshot(audit_with("x-api-key", format!("{}x", key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    
+    // The raw key is never returned again
+    let response = app.clone().oneshot(
+        Request::builder()
+            .uri("/api/v1/api-keys")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    ).await.unwrap();
+    let listed = json_response(response).await;
+    assert_eq!(listed["api_keys"].as_array().unwrap().len(), 1);
+    assert!(!listed.to_string().contains(&key));
+    
+    let response = app.clone().oneshot(
+        Request::builder()
+            .method(Method::DELETE)
+            .uri(format!("/api/v1/api-keys/{}", key_id))
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    
+    let response = app.clone().oneshot(audit_with("x-api-key", key)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_api_key_limited_by_owner_role() {
+    let (state, app, _dir, token) = setup_authenticated_state().await;
+    
+    let body = json!({ "name": "ci", "permissions": ["groups_read", "groups_write"] });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/api-keys", &body, Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let key = json_response(response).await["key"].as_str().unwrap().to_string();
+    
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge" }), Some(&key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    // Demoting the owner takes away what the new role lacks, even from existing keys
+    sqlx::query("UPDATE users SET role_id = 'viewer' WHERE username = 'admin'")
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "core" }), Some(&key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(json_request(Method::GET, "/api/v1/groups", &json!({}), Some(&key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_routes_enforce_declared_permissions() {
+    let (app, _dir, token) = setup_authenticated_app().await;
+
+    let body = json!({ "name": "viewer", "permissions": ["groups_read"] });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/api-keys", &body, Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let key = json_response(response).await["key"].as_str().unwrap().to_string();
+
+    let group = json!({ "name": "production" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &group, Some(&token))).await.unwrap();
+    assert!(response.status().is_success());
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+
+    let get = |uri: String| {
+        Request::builder()
+            .uri(uri)
+            .header("x-api-key", key.clone())
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(get("/api/v1/groups".to_string())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Under-privileged for writes, deploys and other resources
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "staging" }), Some(&key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(json_request(Method::POST, &format!("/api/v1/groups/{}/deploy", group_id), &json!({}), Some(&key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(get(format!("/api/v1/groups/{}/config", group_id))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(get("/api/v1/agents".to_string())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body = json_response(response).await;
+    assert_eq!(body["error"]["code"], "forbidden");
+
+    // Public routes stay open to any caller
+    let response = app.clone().oneshot(get("/api/v1/setup/status".to_string())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+// =============================================================================
+// Component Catalog Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_component_schema_endpoints() {
+    let (app, _dir) = setup_test_app().await;
+    
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    
+    let response = app.clone().oneshot(get("/api/v1/components/schema")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert!(json["sources"]["file"].is_object());
+    assert!(json["transforms"]["remap"].is_object());
+    
+    let response = app.clone().oneshot(get("/api/v1/components/schema/sink/http")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    let uri = json["fields"].as_array().unwrap().iter().find(|f| f["name"] == "uri").unwrap();
+    assert_eq!(uri["required"], true);
+    assert_eq!(uri["type"], "string");
+    
+    let response = app.clone().oneshot(get("/api/v1/components/schema/sink/nope")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    
+    let response = app.oneshot(get("/api/v1/components/schema/widget/http")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// =============================================================================
+// Group Config Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_copy_group_config() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let mut ids = Vec::new();
+    for name in ["staging", "prod"] {
+        let response = app.clone()
+            .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": name }), None))
+            .await
+            .unwrap();
+        ids.push(json_response(response).await["id"].as_str().unwrap().to_string());
+    }
+    
+    let config = "[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"blackhole\"\ninputs = [\"in\"]\n";
+    state.git_store.write_config("staging", config).unwrap();
+    
+    let response = app.clone()
+        .oneshot(json_request(
+            Method::POST,
+            &format!("/api/v1/groups/{}/config/copy-from", ids[1]),
+            &json!({ "source_group_id": ids[0] }),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["success"], true);
+    assert!(json["version"].is_string());
+    assert_eq!(state.git_store.read_config("prod").unwrap().as_deref(), Some(config));
+    
+    let response = app
+        .oneshot(json_request(
+            Method::POST,
+            &format!("/api/v1/groups/{}/config/copy-from", ids[1]),
+            &json!({ "source_group_id": "missing" }),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_config_history_across_groups() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let edge = state.git_store.write_config("edge", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    let core = state.git_store.write_config("core", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    let edge2 = state.git_store.write_config("edge", "[sources.in]\ntype = \"demo_logs\"\n").unwrap();
+    
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/api/v1/config/history?since=2000-01-01").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    let seen: Vec<_> = json.as_array().unwrap().iter()
+        .map(|c| (c["group"].as_str().unwrap().to_string(), c["hash"].as_str().unwrap().to_string()))
+        .collect();
+    assert_eq!(seen, [
+        ("edge".to_string(), edge.clone()),
+        ("core".to_string(), core),
+        ("edge".to_string(), edge2),
+    ]);
+    assert!(json[0]["author"].is_string());
+    assert!(json[0]["timestamp"].is_string());
+    
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/config/history")
+                .header("accept", "text/csv")
+                .body(Body::empty())
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/csv"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    let rows: Vec<_> = csv.lines().collect();
+    assert_eq!(rows[0], "timestamp,group,commit,author,message");
+    assert_eq!(rows.len(), 4);
+    assert!(rows[1].contains(&format!(",edge,{},", edge)));
+    assert!(rows[3].ends_with(",Update config for group: edge"));
+    
+    // Nothing committed after the end of time
+    let response = app
+        .oneshot(Request::builder().uri("/api/v1/config/history?since=2999-01-01").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(json_response(response).await, json!([]));
+}
+
+#[tokio::test]
+async fn test_validate_stored_group_config() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge" }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    let uri = format!("/api/v1/groups/{}/config/validate", group_id);
+    
+    // Unknown group
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups/no-such-group/config/validate", &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Nothing stored yet: groups created through the API get a starter
+    // config, so create one directly
+    let bare = vectorize::db::repository::WorkerGroupRepository::create(state.db.pool(), "bare", None, None)
+        .await
+        .unwrap();
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, &format!("/api/v1/groups/{}/config/validate", bare.id), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(json_response(response).await["error"]["message"], "No configuration set for this group");
+    
+    let broken = state.git_store
+        .write_config("edge", "[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"blackhole\"\ninputs = [\"missing\"]\n")
+        .unwrap();
+    state.git_store
+        .write_config("edge", "[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"blackhole\"\ninputs = [\"in\"]\n")
+        .unwrap();
+    
+    // The current config is valid
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, &uri, &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["valid"], true);
+    
+    // The historical version is not
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, &format!("{}?version={}", uri, broken), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["valid"], false);
+    assert!(json["errors"].as_array().unwrap().iter().any(|e| e["code"] == "INVALID_INPUT"));
+    
+    let response = app
+        .oneshot(json_request(Method::POST, &format!("{}?version=0000000", uri), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_clone_group() {
+    use vectorize::db::repository::{AgentRepository, WorkerGroupRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({
+            "name": "staging",
+            "description": "Pre-prod",
+            "deployment_strategy": "canary",
+            "requires_approval": true,
+            "approvers": ["lead@example.com"],
+        }), None))
+        .await
+        .unwrap();
+    let source_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    
+    let config = "[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"blackhole\"\ninputs = [\"in\"]\n";
+    let version = state.git_store.write_config("staging", config).unwrap();
+    WorkerGroupRepository::update_config_version(pool, &source_id, &version).await.unwrap();
+    let agent = AgentRepository::create(pool, "edge-1", "http://localhost:9200", Some(&source_id)).await.unwrap();
+    
+    let response = app.clone()
+        .oneshot(json_request(
+            Method::POST,
+            &format!("/api/v1/groups/{}/clone", source_id),
+            &json!({ "name": "prod", "move_agents": true }),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let json = json_response(response).await;
+    let clone_id = json["id"].as_str().unwrap().to_string();
+    assert_ne!(clone_id, source_id);
+    assert_eq!(json["name"], "prod");
+    assert_eq!(json["description"], "Pre-prod");
+    assert_eq!(json["deployment_strategy"], "canary");
+    assert_eq!(json["requires_approval"], true);
+    assert_eq!(json["approvers"], json!(["lead@example.com"]));
+    assert_eq!(json["source_version"], version.as_str());
+    assert_eq!(json["agents_moved"], 1);
+    
+    // Same config, in the clone's own directory and history
+    assert_eq!(state.git_store.read_config("prod").unwrap().as_deref(), Some(config));
+    let clone_version = json["current_config_version"].as_str().unwrap().to_string();
+    assert_ne!(clone_version, version);
+    assert_eq!(
+        state.git_store.get_config_at_version("prod", &clone_version).unwrap().as_deref(),
+        Some(config)
+    );
+    let moved = AgentRepository::get_by_id(pool, &agent.id).await.unwrap().unwrap();
+    assert_eq!(moved.group_id.as_deref(), Some(clone_id.as_str()));
+    
+    state.git_store.write_config("staging", "[sources.in]\ntype = \"demo_logs\"\n").unwrap();
+    assert_eq!(state.git_store.read_config("prod").unwrap().as_deref(), Some(config));
+    
+    let response = app
+        .oneshot(json_request(
+            Method::POST,
+            &format!("/api/v1/groups/{}/clone", source_id),
+            &json!({ "name": "prod" }),
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_search_group_history() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge" }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    
+    state.git_store.write_config("edge", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    let kafka = state.git_store.write_config("edge", "[sinks.out]\ntype = \"kafka\"\ninputs = [\"in\"]\n").unwrap();
+    
+    let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = app.clone()
+        .oneshot(get(format!("/api/v1/groups/{}/history/search?q=KAFKA", group_id)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    let matches = json.as_array().unwrap();
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0]["hash"], kafka);
+    assert_eq!(matches[0]["matched_in"], "diff");
+    
+    // The default commit message doesn't mention kafka
+    let response = app.clone()
+        .oneshot(get(format!("/api/v1/groups/{}/history/search?q=kafka&message_only=true", group_id)))
+        .await
+        .unwrap();
+    assert!(json_response(response).await.as_array().unwrap().is_empty());
+    
+    let response = app
+        .oneshot(get(format!("/api/v1/groups/{}/history/search?q=", group_id)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_preview_rollback_leaves_config_unchanged() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge" }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    
+    let target = state.git_store.write_config("edge", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    state.git_store.write_config("edge", "[sources.in]\ntype = \"demo_logs\"\n").unwrap();
+    let head = state.git_store.head_hash().unwrap();
+    
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = app.clone()
+        .oneshot(get(&format!("/api/v1/groups/{}/rollback/preview?version={}", group_id, target)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["from_version"], head);
+    assert_eq!(json["to_version"], target);
+    assert_eq!(json["has_changes"], true);
+    assert!(json["diff"].as_str().unwrap().contains("+type = \"stdin\""));
+    
+    assert_eq!(state.git_store.head_hash().unwrap(), head);
+    
+    let response = app
+        .oneshot(get(&format!("/api/v1/groups/{}/rollback/preview?version={}", group_id, "0".repeat(40))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_update_group_config_base_version() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state);
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge" }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    let uri = format!("/api/v1/groups/{}/config", group_id);
+    
+    let config = |sink: &str| {
+        format!("[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"{}\"\ninputs = [\"in\"]\n", sink)
+    };
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::PUT, &uri, &json!({ "config": config("blackhole") }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let base = json_response(response).await["version"].as_str().unwrap().to_string();
+    
+    // The loaded version is also the ETag
+    let response = app.clone()
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["etag"], format!("\"{}\"", base).as_str());
+    assert_eq!(json_response(response).await["version"], base.as_str());
+    
+    // Saving on top of the loaded version succeeds
+    let response = app.clone()
+        .oneshot(json_request(Method::PUT, &uri, &json!({ "config": config("console"), "base_version": base }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let current = json_response(response).await["version"].as_str().unwrap().to_string();
+    
+    // A second editor still on the old version gets a conflict with a diff
+    let response = app.clone()
+        .oneshot(json_request(Method::PUT, &uri, &json!({ "config": config("blackhole"), "base_version": base }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let json = json_response(response).await;
+    assert_eq!(json["error"]["details"]["base_version"], base.as_str());
+    assert_eq!(json["error"]["details"]["current_version"], current.as_str());
+    let diff = json["error"]["details"]["diff"].as_str().unwrap();
+    assert!(diff.contains("-type = \"blackhole\""));
+    assert!(diff.contains("+type = \"console\""));
+    
+    // Same check through If-Match
+    let mut request = json_request(Method::PUT, &uri, &json!({ "config": config("blackhole") }), None);
+    request.headers_mut().insert("if-match", format!("\"{}\"", base).parse().unwrap());
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    
+    // An unknown base version is a bad request
+    let response = app
+        .oneshot(json_request(Method::PUT, &uri, &json!({ "config": config("blackhole"), "base_version": "deadbeef" }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_group_config_from_template() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let templates = state.git_store.path().join("templates");
+    std::fs::write(
+        templates.join("edge.toml"),
+        "[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"http\"\ninputs = [\"in\"]\nuri = \"https://${host}:${port}\"\n",
+    ).unwrap();
+    std::fs::write(templates.join("edge.vars.yaml"), "host:\n  description: Log endpoint\nport: 443\n").unwrap();
+    
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/api/v1/templates").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["templates"][0]["name"], "edge");
+    assert_eq!(json["templates"][0]["variables"][0]["name"], "host");
+    assert_eq!(json["templates"][0]["variables"][0]["required"], true);
+    assert_eq!(json["templates"][0]["variables"][1]["default"], "443");
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge-eu" }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    let uri = format!("/api/v1/groups/{}/from-template", group_id);
+    
+    // A required variable is missing
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, &uri, &json!({ "template": "edge" }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json_response(response).await["error"]["details"]["missing"], json!(["host"]));
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, &uri, &json!({ "template": "edge", "variables": { "host": "logs.eu" } }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(json_response(response).await["version"].is_string());
+    let config = state.git_store.read_config("edge-eu").unwrap().unwrap();
+    assert!(config.contains("uri = \"https://logs.eu:443\""));
+    
+    let response = app
+        .oneshot(json_request(Method::POST, &uri, &json!({ "template": "missing" }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_move_agent_between_groups() {
+    use vectorize::db::repository::AgentRepository;
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let mut ids = Vec::new();
+    for name in ["staging", "prod"] {
+        let response = app.clone()
+            .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": name }), None))
+            .await
+            .unwrap();
+        ids.push(json_response(response).await["id"].as_str().unwrap().to_string());
+    }
+    let agent = AgentRepository::create(pool, "agent1", "http://localhost:8081", Some(&ids[0])).await.unwrap();
+    let uri = format!("/api/v1/agents/{}", agent.id);
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::PATCH, &uri, &json!({ "group_id": ids[1] }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["agent"]["id"], agent.id);
+    assert_eq!(json["agent"]["group_id"], ids[1]);
+    assert!(json["deployment_id"].is_null());
+    assert_eq!(AgentRepository::list_by_group(pool, &ids[1]).await.unwrap().len(), 1);
+    
+    // Unknown group is rejected without moving the agent
+    let response = app.clone()
+        .oneshot(json_request(Method::PATCH, &uri, &json!({ "group_id": "missing" }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let current = AgentRepository::get_by_id(pool, &agent.id).await.unwrap().unwrap();
+    assert_eq!(current.group_id.as_deref(), Some(ids[1].as_str()));
+    
+    // Moving to no group unassigns it
+    let response = app.clone()
+        .oneshot(json_request(Method::PATCH, &uri, &json!({ "group_id": null }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(json_response(response).await["agent"]["group_id"].is_null());
+    assert_eq!(AgentRepository::list_unassigned(pool).await.unwrap().len(), 1);
+    
+    let response = app
+        .oneshot(json_request(Method::PATCH, "/api/v1/agents/missing", &json!({ "group_id": null }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_move_agent_with_deploy() {
+    use vectorize::db::repository::{AgentRepository, DeploymentRepository, WorkerGroupRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "prod" }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    
+    // Unreachable agents are recorded as pending sync, which counts as deployed
+    let member = AgentRepository::create(pool, "member", "http://127.0.0.1:1", Some(&group_id)).await.unwrap();
+    let agent = AgentRepository::create(pool, "newcomer", "http://127.0.0.1:2", None).await.unwrap();
+    let uri = format!("/api/v1/agents/{}", agent.id);
+    
+    // Nothing to deploy before the group has a config
+    let response = app.clone()
+        .oneshot(json_request(Method::PATCH, &uri, &json!({ "group_id": group_id, "deploy": true }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert!(json["deployment_id"].is_null());
+    assert!(json["message"].as_str().unwrap().contains("no config"));
+    
+    let version = state.git_store.write_config("prod", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    WorkerGroupRepository::update_config_version(pool, &group_id, &version).await.unwrap();
+    
+    let response = app
+        .oneshot(json_request(Method::PATCH, &uri, &json!({ "group_id": group_id, "deploy": true }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let deployment_id = json_response(response).await["deployment_id"].as_str().unwrap().to_string();
+    
+    let deployment = DeploymentRepository::get_by_id(pool, &deployment_id).await.unwrap().unwrap();
+    assert_eq!(deployment.config_version, version);
+    assert_eq!(deployment.status, "completed");
+    
+    // Only the moved agent is deployed to
+    let agents = DeploymentRepository::get_agents(pool, &deployment_id).await.unwrap();
+    assert_eq!(agents.len(), 1);
+    assert_eq!(agents[0].agent_id, agent.id);
+    assert_ne!(agents[0].agent_id, member.id);
+}
+
+#[tokio::test]
+async fn test_agent_and_group_uptime() {
+    use vectorize::db::repository::{AgentRepository, WorkerGroupRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let group = WorkerGroupRepository::create(pool, "prod", None, None).await.unwrap();
+    let agent = AgentRepository::create(pool, "agent1", "http://localhost:8081", Some(&group.id)).await.unwrap();
+    for healthy in [true, true, true, false] {
+        AgentRepository::record_health_check(pool, &agent.id, healthy, None, None).await.unwrap();
+    }
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::GET, &format!("/api/v1/agents/{}/uptime", agent.id), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["total_checks"], 4);
+    assert_eq!(json["uptime_percentage"], 75.0);
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::GET, &format!("/api/v1/groups/{}/uptime", group.id), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["uptime_percentage"], 75.0);
+    assert_eq!(json["agents"][0]["agent_name"], "agent1");
+    
+    // A window starting in the future has no checks
+    let response = app.clone()
+        .oneshot(json_request(Method::GET, &format!("/api/v1/agents/{}/uptime?since=2999-01-01T00:00:00Z", agent.id), &json!({}), None))
+        .await
+        .unwrap();
+    assert!(json_response(response).await["uptime_percentage"].is_null());
+    
+    let response = app
+        .oneshot(json_request(Method::GET, &format!("/api/v1/agents/{}/uptime?since=yesterday", agent.id), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_group_deploy_preview() {
+    use vectorize::db::repository::{AgentRepository, DeploymentRepository, WorkerGroupRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "prod" }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    let old = state.git_store.write_config("prod", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    let new = state.git_store.write_config("prod", "[sources.in]\ntype = \"demo_logs\"\n").unwrap();
+    WorkerGroupRepository::update_config_version(pool, &group_id, &new).await.unwrap();
+    
+    // (name, cached status, deployed version, deploy error)
+    let agents = [
+        ("current", "healthy", Some(new.as_str()), None),
+        ("stale", "healthy", Some(old.as_str()), None),
+        ("down", "unhealthy", Some(new.as_str()), Some("Pending sync")),
+        ("fresh", "unknown", None, None),
+    ];
+    for (i, (name, status, version, error)) in agents.iter().enumerate() {
+        let agent = AgentRepository::create(pool, name, &format!("http://localhost:{}", 9100 + i), Some(&group_id)).await.unwrap();
+        AgentRepository::update_status(pool, &agent.id, status, None).await.unwrap();
+        if let Some(version) = version {
+            let deployment = DeploymentRepository::create(pool, &group_id, version, "basic", None, None).await.unwrap();
+            DeploymentRepository::add_agent(pool, &deployment.id, &agent.id).await.unwrap();
+            DeploymentRepository::update_agent_status(pool, &deployment.id, &agent.id, "completed", *error).await.unwrap();
+        }
+    }
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::GET, &format!("/api/v1/groups/{}/deploy-preview", group_id), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["target_version"], new.as_str());
+    assert_eq!(json["total"], 4);
+    assert_eq!(json["would_change"], 3);
+    assert_eq!(json["unreachable"], 2);
+    
+    let preview: Vec<(&str, &str, bool, bool)> = json["agents"].as_array().unwrap().iter()
+        .map(|a| (
+            a["agent_name"].as_str().unwrap(),
+            a["current_version"].as_str().unwrap_or("-"),
+            a["reachable"].as_bool().unwrap(),
+            a["would_change"].as_bool().unwrap(),
+        ))
+        .collect();
+    assert_eq!(preview, vec![
+        ("current", new.as_str(), true, false),
+        ("down", "-", false, true),
+        ("fresh", "-", false, true),
+        ("stale", old.as_str(), true, true),
+    ]);
+    
+    // Previewing the old version flips which agents would change
+    let response = app.clone()
+        .oneshot(json_request(Method::GET, &format!("/api/v1/groups/{}/deploy-preview?version={}", group_id, old), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(json_response(response).await["would_change"], 3);
+    
+    let response = app
+        .oneshot(json_request(Method::GET, &format!("/api/v1/groups/{}/deploy-preview?version=deadbeef", group_id), &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_deploy_preview_flags_components_too_new_for_agents() {
+    use vectorize::db::repository::{AgentRepository, WorkerGroupRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let group = WorkerGroupRepository::create(pool, "metrics", None, None).await.unwrap();
+    let config = "[sources.in]\ntype = \"stdin\"\n\n[sinks.db]\ntype = \"greptimedb\"\ninputs = [\"in\"]\nendpoint = \"greptimedb:4001\"\n";
+    let version = state.git_store.write_config("metrics", config).unwrap();
+    WorkerGroupRepository::update_config_version(pool, &group.id, &version).await.unwrap();
+    
+    for (i, vector_version) in [Some("0.34.0"), Some("0.28.1"), None].into_iter().enumerate() {
+        let agent = AgentRepository::create(pool, &format!("agent-{}", i), &format!("http://localhost:{}", 9300 + i), Some(&group.id)).await.unwrap();
+        AgentRepository::update_status(pool, &agent.id, "healthy", vector_version).await.unwrap();
+    }
+    
+    let uri = format!("/api/v1/groups/{}/deploy-preview", group.id);
+    let response = app
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["oldest_vector_version"], "0.28.1");
+    let warnings = json["compatibility_warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["code"], "VECTOR_VERSION_TOO_OLD");
+    assert_eq!(warnings[0]["component"], "db");
+}
+
+#[tokio::test]
+async fn test_trigger_health_check() {
+    use vectorize::db::repository::AgentRepository;
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let agent = AgentRepository::create(state.db.pool(), "down", "http://127.0.0.1:1", None).await.unwrap();
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/agents/health-check", &json!({ "agent_ids": [agent.id] }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    let results = json.as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["agent_id"], agent.id);
+    assert_eq!(results[0]["healthy"], false);
+    
+    // No body checks every agent
+    let response = app.clone()
+        .oneshot(Request::builder().method(Method::POST).uri("/api/v1/agents/health-check").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_response(response).await.as_array().unwrap().len(), 1);
+    
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/agents/health-check", &json!({ "agent_ids": ["missing"] }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json_response(response).await["error"]["details"]["unknown_agents"], json!(["missing"]));
+}
+
+#[tokio::test]
+async fn test_delete_group_with_active_deployment() {
+    use vectorize::db::repository::{AgentRepository, DeploymentRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let delete = |uri: String| Request::builder().method(Method::DELETE).uri(uri).body(Body::empty()).unwrap();
+    
+    let mut ids = Vec::new();
+    for name in ["blocked", "forced"] {
+        let response = app.clone()
+            .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": name }), None))
+            .await
+            .unwrap();
+        ids.push(json_response(response).await["id"].as_str().unwrap().to_string());
+    }
+    
+    // Blocked while a deployment is in progress
+    let deployment = DeploymentRepository::create(pool, &ids[0], "abc123", "basic", None, None).await.unwrap();
+    DeploymentRepository::update_status(pool, &deployment.id, "in_progress", None).await.unwrap();
+    
+    let response = app.clone().oneshot(delete(format!("/api/v1/groups/{}", ids[0]))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    assert_eq!(json_response(response).await["error"]["details"]["deployment_id"], deployment.id);
+    
+    // Allowed once it completes
+    DeploymentRepository::update_status(pool, &deployment.id, "completed", None).await.unwrap();
+    let response = app.clone().oneshot(delete(format!("/api/v1/groups/{}", ids[0]))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    
+    // Forced delete cancels the deployment and unassigns agents
+    let agent = AgentRepository::create(pool, "agent1", "http://localhost:8081", Some(&ids[1])).await.unwrap();
+    DeploymentRepository::create(pool, &ids[1], "abc123", "basic", None, None).await.unwrap();
+    
+    let response = app.clone().oneshot(delete(format!("/api/v1/groups/{}", ids[1]))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    
+    let response = app.oneshot(delete(format!("/api/v1/groups/{}?force=true", ids[1]))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    
+    let agent = AgentRepository::get_by_id(pool, &agent.id).await.unwrap().unwrap();
+    assert!(agent.group_id.is_none());
+}
+
+#[tokio::test]
+async fn test_bulk_deploy_all_groups() {
+    use vectorize::db::repository::{AgentRepository, WorkerGroupRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", vectorize::api::create_api_router())
+        .layer(axum::middleware::from_fn_with_state(state.clone(), vectorize::rbac::optional_auth_middleware))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let admin = json!({
+        "username": "admin",
+        "email": "admin@test.com",
+        "password": "securePassword123!"
+    });
+    app.clone().oneshot(json_request(Method::POST, "/api/v1/setup/init", &admin, None)).await.unwrap();
+    let login = json!({ "identifier": "admin", "password": "securePassword123!" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/auth/login", &login, None)).await.unwrap();
+    let token = json_response(response).await["token"].as_str().unwrap().to_string();
+    
+    let config = "[sources.in]\ntype = \"stdin\"\n\n[sinks.out]\ntype = \"blackhole\"\ninputs = [\"in\"]\n";
+    let mut ids = std::collections::HashMap::new();
+    // (name, has config, agent status)
+    for (i, (name, has_config, agent)) in [
+        ("ready", true, Some("healthy")),
+        ("empty", true, None),
+        ("unconfigured", false, Some("healthy")),
+        ("down", true, Some("unhealthy")),
+    ].into_iter().enumerate() {
+        // Requiring approval keeps started deployments from executing during the test
+        let group = WorkerGroupRepository::create(pool, name, None, None).await.unwrap();
+        WorkerGroupRepository::update(pool, &group.id, None, None, None, Some(true), None).await.unwrap();
+        if has_config {
+            let version = state.git_store.write_config(name, config).unwrap();
+            WorkerGroupRepository::update_config_version(pool, &group.id, &version).await.unwrap();
+        }
+        if let Some(status) = agent {
+            let agent = AgentRepository::create(pool, name, &format!("http://127.0.0.1:{}", 9400 + i), Some(&group.id)).await.unwrap();
+            AgentRepository::update_status(pool, &agent.id, status, Some("0.40.0")).await.unwrap();
+        }
+        ids.insert(name, group.id);
+    }
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/deployments/bulk", &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/deployments/bulk", &json!({}), Some(&token)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["started"], 1);
+    assert_eq!(json["skipped"], 3);
+    
+    let ready = &json["groups"][&ids["ready"]];
+    assert_eq!(ready["group_name"], "ready");
+    assert_eq!(ready["status"], "pending_approval");
+    assert!(ready["skipped"].is_null());
+    let deployment_id = ready["deployment_id"].as_str().unwrap();
+    let deployment = vectorize::db::repository::DeploymentRepository::get_by_id(pool, deployment_id).await.unwrap().unwrap();
+    assert_eq!(deployment.group_id, ids["ready"]);
+    
+    assert_eq!(json["groups"][&ids["empty"]]["skipped"], "No healthy agents");
+    assert_eq!(json["groups"][&ids["down"]]["skipped"], "No healthy agents");
+    assert_eq!(json["groups"][&ids["unconfigured"]]["skipped"], "No configuration set for this group");
+    assert!(json["groups"][&ids["unconfigured"]]["deployment_id"].is_null());
+}
+
+#[tokio::test]
+async fn test_frozen_group_blocks_deployments() {
+    use vectorize::db::repository::WorkerGroupRepository;
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", vectorize::api::create_api_router())
+        .layer(axum::middleware::from_fn_with_state(state.clone(), vectorize::rbac::optional_auth_middleware))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let admin = json!({
+        "username": "admin",
+        "email": "admin@test.com",
+        "password": "securePassword123!"
+    });
+    app.clone().oneshot(json_request(Method::POST, "/api/v1/setup/init", &admin, None)).await.unwrap();
+    let login = json!({ "identifier": "admin", "password": "securePassword123!" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/auth/login", &login, None)).await.unwrap();
+    let token = json_response(response).await["token"].as_str().unwrap().to_string();
+    
+    // Requiring approval keeps started deployments from executing during the test
+    let group = WorkerGroupRepository::create(pool, "prod", None, None).await.unwrap();
+    WorkerGroupRepository::update(pool, &group.id, None, None, None, Some(true), None).await.unwrap();
+    let version = state.git_store.write_config("prod", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    WorkerGroupRepository::update_config_version(pool, &group.id, &version).await.unwrap();
+    
+    let freeze = format!("/api/v1/groups/{}/freeze", group.id);
+    let response = app.clone().oneshot(json_request(Method::POST, &freeze, &json!({}), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(json_request(Method::POST, &freeze, &json!({}), Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_response(response).await["frozen"], true);
+    
+    let deploy = format!("/api/v1/groups/{}/deployments", group.id);
+    let response = app.clone().oneshot(json_request(Method::POST, &deploy, &json!({}), Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let json = json_response(response).await;
+    assert!(json["error"]["message"].as_str().unwrap().contains("frozen"));
+    assert_eq!(json["error"]["details"]["frozen"], true);
+    
+    // Only an admin can force past the freeze
+    let forced = json!({ "force": true });
+    let response = app.clone().oneshot(json_request(Method::POST, &deploy, &forced, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app.clone().oneshot(json_request(Method::POST, &deploy, &forced, Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    
+    let unfreeze = format!("/api/v1/groups/{}/unfreeze", group.id);
+    let response = app.clone().oneshot(json_request(Method::POST, &unfreeze, &json!({}), Some(&token))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_response(response).await["frozen"], false);
+    
+    let response = app.oneshot(json_request(Method::POST, &deploy, &json!({}), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_create_deployment_idempotency_key() {
+    use vectorize::db::repository::DeploymentRepository;
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    // Requiring approval keeps the deployment from executing during the test
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge", "requires_approval": true }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    
+    let deploy_version = |key: &str, version: &str| {
+        let mut request = json_request(
+            Method::POST,
+            &format!("/api/v1/groups/{}/deployments", group_id),
+            &json!({ "config_version": version, "force": true }),
+            None,
+        );
+        request.headers_mut().insert("idempotency-key", key.parse().unwrap());
+        request
+    };
+    let deploy = |key: &str| deploy_version(key, "abc123");
+    
+    let first = app.clone().oneshot(deploy("deploy-1")).await.unwrap();
+    assert_eq!(first.status(), StatusCode::CREATED);
+    assert!(first.headers().get("idempotent-replayed").is_none());
+    let first = json_response(first).await;
+    
+    let second = app.clone().oneshot(deploy("deploy-1")).await.unwrap();
+    assert_eq!(second.status(), StatusCode::CREATED);
+    assert_eq!(second.headers()["idempotent-replayed"], "true");
+    assert_eq!(json_response(second).await, first);
+    
+    let deployments = DeploymentRepository::list_by_group(state.db.pool(), &group_id, 10).await.unwrap();
+    assert_eq!(deployments.len(), 1);
+    assert_eq!(deployments[0].id, first["deployment_id"]);
+    
+    // Reusing the key for a different request is an error, not a replay
+    let response = app.clone().oneshot(deploy_version("deploy-1", "def456")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json_response(response).await["error"]["code"], "unprocessable");
+    
+    // A different key is a different intent
+    let third = app.clone().oneshot(deploy("deploy-2")).await.unwrap();
+    assert_eq!(third.status(), StatusCode::CREATED);
+    assert_ne!(json_response(third).await["deployment_id"], first["deployment_id"]);
+    
+    // A claim left unfinished by a dropped request blocks retries only until its lease runs out
+    sqlx::query("UPDATE idempotency_keys SET response = NULL, deployment_id = NULL WHERE idempotency_key = 'deploy-2'")
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+    let response = app.clone().oneshot(deploy("deploy-2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    
+    let claimed_at = (chrono::Utc::now() - chrono::Duration::minutes(5)).format("%Y-%m-%d %H:%M:%S").to_string();
+    sqlx::query("UPDATE idempotency_keys SET created_at = $1 WHERE idempotency_key = 'deploy-2'")
+        .bind(&claimed_at)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+    let response = app.clone().oneshot(deploy("deploy-2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(response.headers().get("idempotent-replayed").is_none());
+}
+
+#[tokio::test]
+async fn test_list_deployments_filters_and_pagination() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge", "requires_approval": true }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    
+    for version in ["v1", "v2", "v3"] {
+        let response = app.clone()
+            .oneshot(json_request(
+                Method::POST,
+                &format!("/api/v1/groups/{}/deployments", group_id),
+                &json!({ "config_version": version, "force": true }),
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    
+    let list = |query: &str| {
+        json_request(Method::GET, &format!("/api/v1/groups/{}/deployments?{}", group_id, query), &json!({}), None)
+    };
+    
+    let response = app.clone().oneshot(list("limit=2&offset=1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let page = json_response(response).await;
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["limit"], 2);
+    assert_eq!(page["offset"], 1);
+    assert_eq!(page["deployments"].as_array().unwrap().len(), 2);
+    
+    let response = app.clone().oneshot(list("status=failed")).await.unwrap();
+    let page = json_response(response).await;
+    assert_eq!(page["total"], 0);
+    assert!(page["deployments"].as_array().unwrap().is_empty());
+    
+    // Everything was created today, so a past date range is empty and a range through today is not
+    let response = app.clone().oneshot(list("until=2000-01-01")).await.unwrap();
+    assert_eq!(json_response(response).await["total"], 0);
+    let response = app.clone().oneshot(list("since=2000-01-01")).await.unwrap();
+    assert_eq!(json_response(response).await["total"], 3);
+    
+    let response = app.clone().oneshot(list("since=yesterday")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_search_across_categories() {
+    use vectorize::db::repository::{AgentRepository, DeploymentRepository, WorkerGroupRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let group = WorkerGroupRepository::create(pool, "failover", None, None).await.unwrap();
+    WorkerGroupRepository::create(pool, "core", None, None).await.unwrap();
+    let proxy = AgentRepository::create(pool, "failover-proxy", "http://10.0.0.1:8686", Some(&group.id)).await.unwrap();
+    let web = AgentRepository::create(pool, "web-1", "http://10.0.0.2:8686", None).await.unwrap();
+    AgentRepository::set_tags(pool, &web.id, &[("tier".to_string(), "failsafe".to_string())]).await.unwrap();
+    AgentRepository::create(pool, "db-1", "http://10.0.0.3:8686", None).await.unwrap();
+    let failed = DeploymentRepository::create(pool, &group.id, "abc123def456", "basic", None, None).await.unwrap();
+    DeploymentRepository::update_status(pool, &failed.id, "failed", Some("agent unreachable")).await.unwrap();
+    DeploymentRepository::create(pool, &group.id, "abc123def456", "basic", None, None).await.unwrap();
+    
+    let search = |query: &str| {
+        Request::builder().uri(format!("/api/v1/search?{}", query)).body(Body::empty()).unwrap()
+    };
+    
+    let response = app.clone().oneshot(search("q=FAIL")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_response(response).await;
+    let hits: Vec<(&str, &str, &str)> = body["results"].as_array().unwrap().iter()
+        .map(|r| (r["kind"].as_str().unwrap(), r["id"].as_str().unwrap(), r["match_field"].as_str().unwrap()))
+        .collect();
+    assert_eq!(hits, vec![
+        ("agent", proxy.id.as_str(), "name"),
+        ("agent", web.id.as_str(), "tag"),
+        ("group", group.id.as_str(), "name"),
+        ("deployment", failed.id.as_str(), "status"),
+    ]);
+    assert_eq!(body["results"][3]["label"], "failover deployment of abc123de (failed)");
+    
+    // Exact matches outrank prefix matches
+    let response = app.clone().oneshot(search("q=failover")).await.unwrap();
+    let body = json_response(response).await;
+    assert_eq!(body["results"][0]["kind"], "group");
+    assert_eq!(body["results"][1]["kind"], "agent");
+    
+    // Per-category cap
+    let response = app.clone().oneshot(search("q=fail&limit=1")).await.unwrap();
+    let body = json_response(response).await;
+    let agents = body["results"].as_array().unwrap().iter().filter(|r| r["kind"] == "agent").count();
+    assert_eq!(agents, 1);
+    
+    // Wildcards are matched literally
+    let response = app.clone().oneshot(search("q=%25")).await.unwrap();
+    assert!(json_response(response).await["results"].as_array().unwrap().is_empty());
+    
+    // Hits are ranked before the cap, not picked by name
+    AgentRepository::create(pool, "alpha-db", "http://10.0.0.4:8686", None).await.unwrap();
+    WorkerGroupRepository::create(pool, "a-core", None, None).await.unwrap();
+    let response = app.clone().oneshot(search("q=db&limit=1")).await.unwrap();
+    assert_eq!(json_response(response).await["results"][0]["label"], "db-1");
+    let response = app.clone().oneshot(search("q=core&limit=1")).await.unwrap();
+    assert_eq!(json_response(response).await["results"][0]["label"], "core");
+    
+    let response = app.oneshot(search("q=%20")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_deployment_report() {
+    use vectorize::db::repository::{AgentRepository, DeploymentRepository};
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge", "requires_approval": true }), None))
+        .await
+        .unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    let v1 = state.git_store.write_config("edge", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    let v2 = state.git_store.write_config("edge", "[sources.in]\ntype = \"demo_logs\"\n").unwrap();
+    let agents = [
+        AgentRepository::create(pool, "edge-1", "http://10.0.0.1:8686", Some(&group_id)).await.unwrap(),
+        AgentRepository::create(pool, "edge-2", "http://10.0.0.2:8686", Some(&group_id)).await.unwrap(),
+    ];
+    
+    let first = DeploymentRepository::create(pool, &group_id, &v1, "basic", None, None).await.unwrap();
+    DeploymentRepository::update_status(pool, &first.id, "completed", None).await.unwrap();
+    sqlx::query("UPDATE deployments SET created_at = '2024-01-01 12:00:00' WHERE id = $1")
+        .bind(&first.id)
+        .execute(pool)
+        .await
+        .unwrap();
+    
+    let deployment = DeploymentRepository::create(pool, &group_id, &v2, "rolling", None, None).await.unwrap();
+    DeploymentRepository::update_status(pool, &deployment.id, "pending_approval", None).await.unwrap();
+    DeploymentRepository::approve(pool, &deployment.id, "bob").await.unwrap();
+    DeploymentRepository::update_status(pool, &deployment.id, "in_progress", None).await.unwrap();
+    for (agent, error) in agents.iter().zip([None, Some("connection refused")]) {
+        DeploymentRepository::add_agent(pool, &deployment.id, &agent.id).await.unwrap();
+        DeploymentRepository::start_agent(pool, &deployment.id, &agent.id).await.unwrap();
+        let status = if error.is_some() { "failed" } else { "completed" };
+        DeploymentRepository::update_agent_status(pool, &deployment.id, &agent.id, status, error).await.unwrap();
+    }
+    DeploymentRepository::update_status(pool, &deployment.id, "completed", None).await.unwrap();
+    
+    let report = |accept: &str| {
+        Request::builder()
+            .uri(format!("/api/v1/deployments/{}/report", deployment.id))
+            .header("accept", accept)
+            .body(Body::empty())
+            .unwrap()
+    };
+    
+    let response = app.clone().oneshot(report("application/json")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["group_name"], "edge");
+    assert_eq!(json["config_version"], v2);
+    assert_eq!(json["previous_version"], v1);
+    assert_eq!(json["approval"]["approved_by"], "bob");
+    assert_eq!(json["outcomes"], json!({ "completed": 1, "failed": 1 }));
+    let names: Vec<&str> = json["agents"].as_array().unwrap().iter()
+        .map(|a| a["agent_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["edge-1", "edge-2"]);
+    assert_eq!(json["agents"][1]["error"], "connection refused");
+    let diff = json["config_diff"].as_str().unwrap();
+    assert!(diff.contains("-type = \"stdin\""));
+    assert!(diff.contains("+type = \"demo_logs\""));
+    
+    let response = app.clone().oneshot(report("text/markdown")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/markdown"));
+    assert!(response.headers()["content-disposition"].to_str().unwrap().contains(".md"));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let markdown = String::from_utf8(body.to_vec()).unwrap();
+    assert!(markdown.contains("| edge-2 | failed |"));
+    assert!(markdown.contains("+type = \"demo_logs\""));
+    
+    // The first deployment diffs against an empty config
+    let response = app.clone()
+        .oneshot(Request::builder().uri(format!("/api/v1/deployments/{}/report", first.id)).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let json = json_response(response).await;
+    assert!(json["previous_version"].is_null());
+    assert!(json["config_diff"].as_str().unwrap().contains("+type = \"stdin\""));
+    
+    let response = app
+        .oneshot(Request::builder().uri("/api/v1/deployments/missing/report").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_deployment_report_diff_needs_configs_read() {
+    use vectorize::db::repository::DeploymentRepository;
+    
+    let (state, app, _dir, token) = setup_authenticated_state().await;
+    let pool = state.db.pool();
+    
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/groups", &json!({ "name": "edge" }), Some(&token))).await.unwrap();
+    let group_id = json_response(response).await["id"].as_str().unwrap().to_string();
+    let version = state.git_store.write_config("edge", "[sources.in]\ntype = \"stdin\"\n").unwrap();
+    let deployment = DeploymentRepository::create(pool, &group_id, &version, "basic", None, None).await.unwrap();
+    DeploymentRepository::update_status(pool, &deployment.id, "completed", None).await.unwrap();
+    
+    let body = json!({ "name": "dashboard", "permissions": ["groups_read"] });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/api-keys", &body, Some(&token))).await.unwrap();
+    let key = json_response(response).await["key"].as_str().unwrap().to_string();
+    
+    let report = |header: (&'static str, String)| {
+        Request::builder()
+            .uri(format!("/api/v1/deployments/{}/report", deployment.id))
+            .header(header.0, header.1)
+            .body(Body::empty())
+            .unwrap()
+    };
+    
+    let response = app.clone().oneshot(report(("x-api-key", key))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["config_version"], version);
+    assert!(json["config_diff"].is_null());
+    
+    let response = app.oneshot(report(("authorization", format!("Bearer {}", token)))).await.unwrap();
+    let json = json_response(response).await;
+    assert!(json["config_diff"].as_str().unwrap().contains("+type = \"stdin\""));
+}
+
+#[tokio::test]
+async fn test_agent_tls_settings() {
+    use vectorize::agent_clients::{AgentClients, AgentTls};
+    use vectorize::crypto::SecretCipher;
+    use vectorize::db::repository::AgentRepository;
+    
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let client_key = include_str!("fixtures/agent_tls_client_key.pem");
+    let tls = json!({
+        "ca_cert": include_str!("fixtures/agent_tls_ca.pem"),
+        "client_cert": include_str!("fixtures/agent_tls_client.pem"),
+        "client_key": client_key,
+    });
+    let body = json!({ "name": "secure", "url": "https://localhost:9101", "tls": tls });
+    
+    // Client keys are never stored without an encryption key
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(json_response(response).await["error"]["message"].as_str().unwrap().contains("VECTORIZE_ENCRYPTION_KEY"));
+    assert!(AgentRepository::get_by_name(state.db.pool(), "secure").await.unwrap().is_none());
+    
+    let cipher = SecretCipher::from_base64(&SecretCipher::generate_key()).unwrap();
+    let state = Arc::new(vectorize::AppState {
+        agent_clients: Arc::new(AgentClients::default().with_cipher(Some(cipher.clone()))),
+        cipher: Some(cipher.clone()),
+        ..(*state).clone()
+    });
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let agent = json_response(response).await["agent"].clone();
+    assert_eq!(agent["tls_configured"], true);
+    assert!(!agent.to_string().contains("PRIVATE KEY"));
+    
+    // The key is sealed at rest and opened for the agent's client
+    let agent_id = agent["id"].as_str().unwrap().to_string();
+    let stored = AgentRepository::get_by_id(state.db.pool(), &agent_id).await.unwrap().unwrap();
+    assert!(!stored.tls_client_key.as_deref().unwrap().contains("PRIVATE KEY"));
+    assert!(!stored.tls_insecure_skip_verify);
+    let opened = AgentTls::from_agent(&stored, Some(&cipher)).unwrap().unwrap();
+    assert_eq!(opened.client_key.as_deref(), Some(client_key));
+    state.agent_clients.for_agent(&stored);
+    assert_eq!(state.agent_clients.cached(), 1);
+    
+    // Unparseable PEM material is rejected
+    let uri = format!("/api/v1/agents/{}", agent_id);
+    let body = json!({ "tls": { "ca_cert": "not a certificate" } });
+    let response = app.clone().oneshot(json_request(Method::PUT, &uri, &body, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    
+    // An empty object clears the settings
+    let response = app.clone().oneshot(json_request(Method::PUT, &uri, &json!({ "tls": {} }), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_response(response).await["tls_configured"], false);
+    let stored = AgentRepository::get_by_id(state.db.pool(), &agent_id).await.unwrap().unwrap();
+    assert!(stored.tls_ca_cert.is_none() && stored.tls_client_key.is_none());
+}
+
+#[tokio::test]
+async fn test_deploy_to_agent_errors() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/agents/missing/deploy", &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    
+    let body = json!({ "name": "loner", "url": "http://localhost:9100" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    let agent_id = json_response(response).await["agent"]["id"].as_str().unwrap().to_string();
+    
+    let uri = format!("/api/v1/agents/{}/deploy", agent_id);
+    let response = app.clone().oneshot(json_request(Method::POST, &uri, &json!({}), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json_response(response).await["error"]["message"], "Agent is not in a group");
+    
+    // In a group that has no config yet
+    let body = json!({ "name": "grouped", "url": "http://localhost:9101", "group_id": "edge", "auto_group": true });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    let agent_id = json_response(response).await["agent"]["id"].as_str().unwrap().to_string();
+    
+    let uri = format!("/api/v1/agents/{}/deploy", agent_id);
+    let response = app.clone().oneshot(json_request(Method::POST, &uri, &json!({}), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    
+    let response = app.oneshot(json_request(Method::POST, &uri, &json!({ "config_version": "abc123" }), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_agent_config_ack_settles_pending_sync() {
+    use vectorize::db::repository::{AgentRepository, DeploymentRepository, WorkerGroupRepository};
+    use vectorize::deployment::PENDING_SYNC;
+    
+    let (state, _dir) = setup_test_state().await;
+    let pool = state.db.pool();
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    
+    // Two pull-based agents the deployment couldn't push to
+    let group = WorkerGroupRepository::create(pool, "edge", None, None).await.unwrap();
+    let deployment = DeploymentRepository::create(pool, &group.id, "v2", "basic", None, None).await.unwrap();
+    let mut agent_ids = Vec::new();
+    for (name, port) in [("puller-1", 9201), ("puller-2", 9202)] {
+        let agent = AgentRepository::create(pool, name, &format!("http://localhost:{}", port), Some(&group.id)).await.unwrap();
+        DeploymentRepository::add_agent(pool, &deployment.id, &agent.id).await.unwrap();
+        DeploymentRepository::update_agent_status(pool, &deployment.id, &agent.id, "completed", Some(PENDING_SYNC)).await.unwrap();
+        agent_ids.push(agent.id);
+    }
+    
+    let uri = format!("/api/v1/agents/{}/config/ack", agent_ids[0]);
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, &uri, &json!({ "version": "v2", "success": true }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["acknowledged"], true);
+    assert_eq!(json["deployment_id"], deployment.id.as_str());
+    
+    let uri = format!("/api/v1/agents/{}/config/ack", agent_ids[1]);
+    let body = json!({ "version": "v2", "success": false, "error": "vector validate failed" });
+    let response = app.clone().oneshot(json_request(Method::POST, &uri, &body, None)).await.unwrap();
+    assert_eq!(json_response(response).await["acknowledged"], true);
+    
+    let uri = format!("/api/v1/deployments/{}", deployment.id);
+    let response = app.clone()
+        .oneshot(Request::builder().uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let json = json_response(response).await;
+    assert_eq!(json["stats"]["completed"], 1);
+    assert_eq!(json["stats"]["failed"], 1);
+    let agents = json["agents"].as_array().unwrap();
+    let entry = |id: &str| agents.iter().find(|a| a["agent_id"] == id).unwrap().clone();
+    assert_eq!(entry(&agent_ids[0])["error"], Value::Null);
+    assert_eq!(entry(&agent_ids[1])["status"], "failed");
+    assert_eq!(entry(&agent_ids[1])["error"], "vector validate failed");
+    
+    // Repeating the ack, or acking a version nobody deployed, changes nothing
+    let uri = format!("/api/v1/agents/{}/config/ack", agent_ids[0]);
+    for version in ["v2", "v3"] {
+        let body = json!({ "version": version, "success": true });
+        let response = app.clone().oneshot(json_request(Method::POST, &uri, &body, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_response(response).await["acknowledged"], false);
+    }
+    
+    let body = json!({ "version": "v2", "success": true });
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/agents/missing/config/ack", &body, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_agent_heartbeat() {
+    let (app, _dir) = setup_test_app().await;
+
+    let body = json!({ "name": "behind-firewall", "url": "http://10.0.0.5:8686" });
+    let response = app.clone().oneshot(json_request(Method::POST, "/api/v1/agents", &body, None)).await.unwrap();
+    let agent_id = json_response(response).await["agent"]["id"].as_str().unwrap().to_string();
+    let uri = format!("/api/v1/agents/{}/heartbeat", agent_id);
+
+    // No body: only last_seen moves
+    let response = app.clone()
+        .oneshot(Request::builder().method(Method::POST).uri(&uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert!(json["last_seen"].is_string());
+    assert_eq!(json["status"], "unknown");
+
+    // The sidecar's view of Vector becomes the agent's status
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, &uri, &json!({ "vector_health": "healthy" }), None))
+        .await
+        .unwrap();
+    assert_eq!(json_response(response).await["status"], "healthy");
+
+    let response = app.clone().oneshot(Request::builder().uri("/api/v1/health/fleet").body(Body::empty()).unwrap()).await.unwrap();
+    let json = json_response(response).await;
+    assert_eq!(json["healthy"], 1);
+    assert_eq!(json["stale"], 0);
+
+    let response = app
+        .oneshot(json_request(Method::POST, "/api/v1/agents/missing/heartbeat", &json!({}), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_protocol_handshake() {
+    let (app, _dir) = setup_test_app().await;
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/handshake", &json!({ "protocol_version": vectorize_shared::PROTOCOL_VERSION }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_response(response).await["protocol_version"], vectorize_shared::PROTOCOL_VERSION);
+    
+    let response = app.clone()
+        .oneshot(json_request(Method::POST, "/api/v1/handshake", &json!({ "protocol_version": vectorize_shared::PROTOCOL_VERSION + 1 }), None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UPGRADE_REQUIRED);
+    let json = json_response(response).await;
+    assert_eq!(json["error"]["code"], "protocol_mismatch");
+    assert_eq!(json["error"]["details"]["reason"], "client_too_new");
+    
+    // The header is checked on every control plane route
+    let mut request = Request::builder().uri("/api/v1/groups").body(Body::empty()).unwrap();
+    request.headers_mut().insert(vectorize_shared::PROTOCOL_VERSION_HEADER, "999".parse().unwrap());
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UPGRADE_REQUIRED);
+    
+    let mut request = Request::builder().uri("/api/v1/groups").body(Body::empty()).unwrap();
+    request.headers_mut().insert(
+        vectorize_shared::PROTOCOL_VERSION_HEADER,
+        vectorize_shared::PROTOCOL_VERSION.to_string().parse().unwrap(),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+// =============================================================================
+// Orchestrator Probe Tests
+// =============================================================================
+
+#[tokio::test]
+async fn test_readyz_reports_dependencies() {
+    let (state, _dir) = setup_test_state().await;
+    let app = vectorize::server::probe_router().with_state(state.clone());
+    
+    let response = app.clone().oneshot(
+        Request::builder().uri("/healthz").body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    let response = app.clone().oneshot(
+        Request::builder().uri("/readyz").body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_response(response).await;
+    assert_eq!(json["status"], "ready");
+    // No managed Vector process in tests
+    let vector = json["checks"].as_array().unwrap().iter().find(|c| c["name"] == "vector").unwrap();
+    assert_eq!(vector["status"], "skipped");
+    
+    // A closed pool makes the server unready but still alive
+    state.db.close().await;
+    
+    let response = app.clone().oneshot(
+        Request::builder().uri("/readyz").body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let json = json_response(response).await;
+    assert_eq!(json["status"], "not_ready");
+    let failed: Vec<&str> = json["failed"].as_array().unwrap().iter().map(|f| f.as_str().unwrap()).collect();
+    assert!(failed.contains(&"database"));
+    assert!(!failed.contains(&"git_store"));
+    
+    let response = app.oneshot(
+        Request::builder().uri("/healthz").body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+/// Serve `GET /api/config` with a fixed config, as a managed agent does
+async fn spawn_config_agent(config: Value) -> String {
+    let app = Router::new().route("/api/config", axum::routing::get(move || async move { axum::Json(config) }));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    url
+}
+
+#[tokio::test]
+async fn test_agent_config_diff_reports_drift() {
+    let (state, _dir) = setup_test_state().await;
+    let app = Router::new()
+        .nest("/api/v1", api_router(&state))
+        .with_state(state.clone());
+    let pool = state.db.pool();
+    
+    let group = vectorize::db::repository::WorkerGroupRepository::create(pool, "prod", None, None).await.unwrap();
+    state.git_store.write_config("prod", r#"
+# Ingest
+[sources.in]
+type = "stdin"
+
+[sinks.out]
+type = "console"
+inputs = ["in"]
+encoding.codec = "json"
+"#).unwrap();
+    
+    // Same config, different key order and formatting
+    let same = spawn_config_agent(json!({
+        "sinks": { "out": { "inputs": ["in"], "encoding": { "codec": "json" }, "type": "console" } },
+        "sources": { "in": { "type": "stdin" } }
+    })).await;
+    let agent = vectorize::db::repository::AgentRepository::create(pool, "in-sync", &same, Some(&group.id)).await.unwrap();
+    let response = app.clone().oneshot(
+        Request::builder().uri(format!("/api/v1/agents/{}/config-diff", agent.id)).body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_response(response).await;
+    assert_eq!(body["in_sync"], true);
+    assert_eq!(body["diff"], "");
+    
+    // Someone changed the codec on the box
+    let drifted = spawn_config_agent(json!({
+        "sources": { "in": { "type": "stdin" } },
+        "sinks": { "out": { "type": "console", "inputs": ["in"], "encoding": { "codec": "text" } } }
+    })).await;
+    let agent = vectorize::db::repository::AgentRepository::create(pool, "drifted", &drifted, Some(&group.id)).await.unwrap();
+    let response = app.clone().oneshot(
+        Request::builder().uri(format!("/api/v1/agents/{}/config-diff", agent.id)).body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_response(response).await;
+    assert_eq!(body["in_sync"], false);
+    let diff = body["diff"].as_str().unwrap();
+    assert!(diff.contains("-codec = \"json\""), "{}", diff);
+    assert!(diff.contains("+codec = \"text\""), "{}", diff);
+    
+    // Unreachable agents are a gateway error, not drift
+    let agent = vectorize::db::repository::AgentRepository::create(pool, "down", "http://127.0.0.1:1", Some(&group.id)).await.unwrap();
+    let response = app.oneshot(
+        Request::builder().uri(format!("/api/v1/agents/{}/config-diff", agent.id)).body(Body::empty()).unwrap()
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+}